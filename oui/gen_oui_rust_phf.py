@@ -1,17 +1,20 @@
 #!/usr/bin/env python3
 """
 Generate Rust phf::Map tables for MA-L (/24), MA-M (/28), MA-S (/36).
-- Fetches IEEE CSVs over HTTPS only.
+- Fetches IEEE CSVs over HTTPS, or reads them from a directory (--csv-dir)
+  on build hosts without network access.
 - Cleans invisible/non-printable Unicode from organization names.
 - Emits only static PHF maps (no helpers).
 
 Usage:
   python3 gen_oui_rust_phf.py > ../src/oui_map.rs
+  python3 gen_oui_rust_phf.py --csv-dir ./ieee > ../src/oui_map.rs
 """
 
 import argparse
 import csv
 import io
+import os
 import re
 import sys
 import unicodedata
@@ -187,11 +190,20 @@ pub fn lookup_vendor_bytes(mac: [u8; 6]) -> Option<&'static str> {
 def main():
     ap = argparse.ArgumentParser(description="Generate Rust phf maps for MA-L(/24), MA-M(/28), MA-S(/36)")
     ap.add_argument("--out", default="-", help="Output .rs file (default: stdout)")
+    ap.add_argument(
+        "--csv-dir",
+        help="Read oui.csv, mam.csv and oui36.csv from this directory instead of fetching them",
+    )
     args = ap.parse_args()
 
     rows: List[Tuple[int, int, str]] = []
     for url, reg in ((SRC_MAL, "MA-L"), (SRC_MAM, "MA-M"), (SRC_MAS, "MA-S")):
-        rows.extend(parse_registry(fetch_bytes_http(url), reg))
+        if args.csv_dir:
+            with open(os.path.join(args.csv_dir, url.rsplit("/", 1)[1]), "rb") as f:
+                data = f.read()
+        else:
+            data = fetch_bytes_http(url)
+        rows.extend(parse_registry(data, reg))
 
     print(
         f"Successfully retrieved and processed OUI data from IEEE registries.",
@@ -0,0 +1,109 @@
+//! Update interval that follows the packet rate
+//!
+//! With --min-update-interval or --max-update-interval the interval between
+//! updates is no longer fixed: each update should find about
+//! TARGET_PACKETS_PER_UPDATE packets waiting. A busy network is drained often
+//! enough not to fall behind, a quiet one is only looked at every few seconds
+//! and leaves the CPU alone. Packets still waiting after an update halve the
+//! interval right away instead of waiting for the smoothed rate to catch up.
+
+use std::time::Duration;
+
+/// Bounds used when only one of them is given
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_millis(100);
+pub const DEFAULT_MAX_INTERVAL: Duration = Duration::from_secs(5);
+/// Packets an update should find waiting
+const TARGET_PACKETS_PER_UPDATE: f64 = 50.0;
+/// Weight of the latest rate in the moving average
+const RATE_SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Clone)]
+pub struct AdaptiveInterval {
+    min: Duration,
+    max: Duration,
+    current: Duration,
+    /// Smoothed packet rate per second, None before the first update
+    rate: Option<f64>,
+    last_count: u64,
+}
+
+impl AdaptiveInterval {
+    pub fn new(min: Duration, max: Duration, initial: Duration) -> Self {
+        let max = max.max(min);
+        Self {
+            min,
+            max,
+            current: initial.clamp(min, max),
+            rate: None,
+            last_count: 0,
+        }
+    }
+
+    /// Next interval, given the total packet count, the time since the last
+    /// update and whether packets were left waiting
+    pub fn update(&mut self, packet_count: u64, elapsed: Duration, backlogged: bool) -> Duration {
+        let packets = packet_count.saturating_sub(self.last_count);
+        self.last_count = packet_count;
+        if elapsed.is_zero() {
+            return self.current;
+        }
+
+        let latest = packets as f64 / elapsed.as_secs_f64();
+        let rate = match self.rate {
+            Some(rate) => rate + RATE_SMOOTHING * (latest - rate),
+            None => latest,
+        };
+        self.rate = Some(rate);
+
+        let target = if backlogged {
+            self.current / 2
+        } else if rate > 0.0 {
+            Duration::from_secs_f64((TARGET_PACKETS_PER_UPDATE / rate).min(self.max.as_secs_f64()))
+        } else {
+            self.max
+        };
+        self.current = target.clamp(self.min, self.max);
+        self.current
+    }
+
+    /// Smoothed packet rate per second
+    pub fn rate(&self) -> Option<f64> {
+        self.rate
+    }
+
+    pub fn bounds(&self) -> (Duration, Duration) {
+        (self.min, self.max)
+    }
+}
+
+#[test]
+fn test_adaptive_interval() {
+    let second = Duration::from_secs(1);
+    let mut interval =
+        AdaptiveInterval::new(Duration::from_millis(100), Duration::from_secs(5), second);
+
+    // 10 packets per second, 50 packets take 5 s
+    assert_eq!(interval.update(10, second, false), Duration::from_secs(5));
+    assert_eq!(interval.rate(), Some(10.0));
+
+    // A burst of 1000 packets per second is smoothed, not followed at once
+    let next = interval.update(1010, second, false);
+    assert!(next > Duration::from_millis(100) && next < Duration::from_secs(5));
+
+    // Packets left waiting halve the interval, down to the minimum
+    assert_eq!(
+        interval.update(1010, second, true),
+        (next / 2).max(Duration::from_millis(100))
+    );
+    assert_eq!(
+        interval.update(1010, second, true),
+        Duration::from_millis(100)
+    );
+
+    // Silence goes back to the maximum
+    let mut last = Duration::ZERO;
+    for _ in 0..20 {
+        last = interval.update(1010, second, false);
+    }
+    assert_eq!(last, Duration::from_secs(5));
+}
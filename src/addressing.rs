@@ -0,0 +1,180 @@
+//! PTP messages sent to destinations they should not be sent to
+//!
+//! IEEE 1588 Annexes D and F fix the multicast groups PTP uses: 224.0.1.129
+//! (01:1b:19:00:00:00 over Ethernet) for everything but the peer delay
+//! messages, which go to the link-local 224.0.0.107 (01:80:c2:00:00:0e). A
+//! message sent to a unicast address must say so with the unicastFlag.
+//! Misaddressed timing traffic often still works on a small flat network,
+//! e.g. a peer delay request to 224.0.1.129 or a broadcast Sync, and then
+//! fails once switches, routers or transparent clocks treat it by the book.
+
+use std::{
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr},
+};
+
+use crate::types::{PtpHeader, PtpMessageType};
+
+/// Group of all PTP messages except peer delay
+const PTP_PRIMARY_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 1, 129);
+/// Group of the peer delay messages, never forwarded by routers
+const PTP_PDELAY_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 107);
+/// IEEE 1588 over Ethernet, all but the peer delay messages
+const PTP_PRIMARY_MAC: [u8; 6] = [0x01, 0x1b, 0x19, 0x00, 0x00, 0x00];
+/// Peer delay over Ethernet and everything gPTP, not forwarded by bridges
+const PTP_PDELAY_MAC: [u8; 6] = [0x01, 0x80, 0xc2, 0x00, 0x00, 0x0e];
+const BROADCAST_MAC: [u8; 6] = [0xff; 6];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Destination {
+    Ip(Ipv4Addr),
+    Mac([u8; 6]),
+}
+
+impl Display for Destination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Destination::Ip(addr) => write!(f, "{}", addr),
+            Destination::Mac(mac) => write!(
+                f,
+                "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+                mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AddressIssue {
+    /// Sent to a unicast address with the unicastFlag cleared
+    UnicastWithoutFlag,
+    /// Sent to a multicast group other than the one for the message type
+    WrongMulticastGroup {
+        expected: Destination,
+        actual: Destination,
+    },
+    /// Sent to the broadcast MAC or IP address
+    Broadcast,
+}
+
+impl Display for AddressIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressIssue::UnicastWithoutFlag => write!(f, "unicast without unicastFlag"),
+            AddressIssue::WrongMulticastGroup { expected, actual } => {
+                write!(f, "sent to {} instead of {}", actual, expected)
+            }
+            AddressIssue::Broadcast => write!(f, "sent to broadcast"),
+        }
+    }
+}
+
+fn is_peer_delay(message_type: PtpMessageType) -> bool {
+    matches!(
+        message_type,
+        PtpMessageType::PDelayReq | PtpMessageType::PDelayResp | PtpMessageType::PDelayRespFollowUp
+    )
+}
+
+/// Check the destination of a message, None if it is addressed correctly
+pub fn check_destination(
+    header: &PtpHeader,
+    dest_mac: [u8; 6],
+    dest_ip: Option<IpAddr>,
+) -> Option<AddressIssue> {
+    let peer_delay = is_peer_delay(header.message_type);
+    match dest_ip {
+        Some(IpAddr::V4(addr)) => {
+            if addr.is_broadcast() || dest_mac == BROADCAST_MAC {
+                Some(AddressIssue::Broadcast)
+            } else if addr.is_multicast() {
+                let expected = if peer_delay {
+                    PTP_PDELAY_GROUP
+                } else {
+                    PTP_PRIMARY_GROUP
+                };
+                (addr != expected).then_some(AddressIssue::WrongMulticastGroup {
+                    expected: Destination::Ip(expected),
+                    actual: Destination::Ip(addr),
+                })
+            } else {
+                (!header.flags.unicast()).then_some(AddressIssue::UnicastWithoutFlag)
+            }
+        }
+        // Only IPv4 is decoded
+        Some(IpAddr::V6(_)) => None,
+        None => {
+            if dest_mac == BROADCAST_MAC {
+                Some(AddressIssue::Broadcast)
+            } else if dest_mac[0] & 0x01 == 0x01 {
+                // 1588 may use the non-forwardable address for all messages,
+                // peer delay and gPTP must use it
+                let non_forwardable = peer_delay || header.is_gptp() || header.is_cmlds();
+                let allowed =
+                    dest_mac == PTP_PDELAY_MAC || (dest_mac == PTP_PRIMARY_MAC && !non_forwardable);
+                let expected = if non_forwardable {
+                    PTP_PDELAY_MAC
+                } else {
+                    PTP_PRIMARY_MAC
+                };
+                (!allowed).then_some(AddressIssue::WrongMulticastGroup {
+                    expected: Destination::Mac(expected),
+                    actual: Destination::Mac(dest_mac),
+                })
+            } else {
+                (!header.flags.unicast()).then_some(AddressIssue::UnicastWithoutFlag)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_check_destination() {
+    let header = |message_type: u8, unicast: bool| {
+        let mut data = [0u8; 44];
+        data[0] = message_type;
+        data[1] = 0x02;
+        data[2..4].copy_from_slice(&44u16.to_be_bytes());
+        data[6] = if unicast { 0x04 } else { 0 };
+        PtpHeader::try_from(&data[..]).unwrap()
+    };
+    let sync = header(0x0, false);
+    let pdelay_req = header(0x2, false);
+    let ip = |addr: [u8; 4]| Some(IpAddr::V4(Ipv4Addr::from(addr)));
+    let multicast_mac = [0x01, 0x00, 0x5e, 0x00, 0x01, 0x81];
+    let unicast_mac = [0x00, 0x1b, 0x19, 0x12, 0x34, 0x56];
+
+    assert_eq!(
+        check_destination(&sync, multicast_mac, ip([224, 0, 1, 129])),
+        None
+    );
+    assert_eq!(
+        check_destination(&sync, unicast_mac, ip([10, 0, 0, 5])),
+        Some(AddressIssue::UnicastWithoutFlag)
+    );
+    assert_eq!(
+        check_destination(&header(0x0, true), unicast_mac, ip([10, 0, 0, 5])),
+        None
+    );
+    assert_eq!(
+        check_destination(&sync, BROADCAST_MAC, ip([10, 0, 0, 255])),
+        Some(AddressIssue::Broadcast)
+    );
+
+    let issue = check_destination(&pdelay_req, multicast_mac, ip([224, 0, 1, 129])).unwrap();
+    assert_eq!(
+        issue.to_string(),
+        "sent to 224.0.1.129 instead of 224.0.0.107"
+    );
+
+    // Over Ethernet, 1588 may use either address except for peer delay
+    assert_eq!(check_destination(&sync, PTP_PDELAY_MAC, None), None);
+    assert_eq!(check_destination(&sync, PTP_PRIMARY_MAC, None), None);
+    assert_eq!(
+        check_destination(&pdelay_req, PTP_PRIMARY_MAC, None),
+        Some(AddressIssue::WrongMulticastGroup {
+            expected: Destination::Mac(PTP_PDELAY_MAC),
+            actual: Destination::Mac(PTP_PRIMARY_MAC),
+        })
+    );
+}
@@ -0,0 +1,305 @@
+//! Commands and webhooks run when alerts are raised (`[[alert_hooks]]`)
+//!
+//! A hook names an alert kind by its slug ("ptt-changed", or "*" for all)
+//! and either a shell command or a webhook URL. Commands run with `sh -c`
+//! (`cmd /C` on Windows) and get the alert in PTP_ALERT_* environment
+//! variables and as JSON on stdin, e.g. to start a capture script when a
+//! rogue grandmaster shows up. Webhooks get the JSON posted with curl, its
+//! `text` key makes it a Slack message. Hooks run in the background, their
+//! output and failures are logged.
+
+use std::{
+    process::Stdio,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::alerts::{Alert, AlertKind, AlertSeverity};
+
+/// A hook running longer is killed
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+/// Hooks running at once, an alert storm doesn't fork hundreds of processes
+const MAX_RUNNING_HOOKS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlertHook {
+    /// Alert kind slug, "*" for any
+    pub alert: String,
+    /// Alerts below this severity don't run the hook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_severity: Option<AlertSeverity>,
+    /// Shell command to run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// URL to post the alert to as JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+}
+
+impl AlertHook {
+    /// Check that the hook names a known alert and one action
+    pub fn validate(&self) -> Result<(), String> {
+        if self.alert != "*" && AlertKind::from_slug(&self.alert).is_none() {
+            return Err(format!(
+                "Unknown alert '{}' in alert hook, expected '*' or one of: {}",
+                self.alert,
+                AlertKind::ALL
+                    .iter()
+                    .map(|kind| kind.slug())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        match (&self.command, &self.webhook) {
+            (Some(_), None) | (None, Some(_)) => Ok(()),
+            _ => Err(format!(
+                "Alert hook for '{}' needs either a command or a webhook",
+                self.alert
+            )),
+        }
+    }
+
+    pub fn matches(&self, alert: &Alert) -> bool {
+        (self.alert == "*" || self.alert == alert.kind.slug())
+            && self
+                .min_severity
+                .is_none_or(|severity| alert.severity >= severity)
+    }
+
+    /// What the hook does, for the log
+    fn describe(&self) -> String {
+        match (&self.command, &self.webhook) {
+            (Some(command), _) => command.clone(),
+            (_, Some(url)) => format!("webhook {}", url),
+            _ => String::new(),
+        }
+    }
+
+    fn command(&self) -> Command {
+        let mut command = match (&self.command, &self.webhook) {
+            (Some(command), _) => shell_command(command),
+            (_, url) => {
+                let mut curl = Command::new("curl");
+                curl.args(["-fsS", "-X", "POST", "-H", "Content-Type: application/json"])
+                    .args(["--data-binary", "@-"])
+                    .arg(url.as_deref().unwrap_or_default());
+                curl
+            }
+        };
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        command
+    }
+}
+
+#[cfg(unix)]
+fn shell_command(command: &str) -> Command {
+    let mut sh = Command::new("sh");
+    sh.arg("-c").arg(command);
+    sh
+}
+
+#[cfg(not(unix))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+/// Environment variables a command gets
+pub fn alert_env(alert: &Alert) -> Vec<(&'static str, String)> {
+    let mut env = vec![
+        ("PTP_ALERT_KIND", alert.kind.slug().to_string()),
+        ("PTP_ALERT_SEVERITY", alert.severity.slug().to_string()),
+        ("PTP_ALERT_TIME", alert_time(alert)),
+        ("PTP_ALERT_MESSAGE", alert.message.clone()),
+    ];
+    if let Some(domain) = alert.domain_number {
+        env.push(("PTP_ALERT_DOMAIN", domain.to_string()));
+    }
+    if let Some(clock_identity) = alert.clock_identity {
+        env.push(("PTP_ALERT_CLOCK", clock_identity.to_string()));
+    }
+    if let Some(path) = &alert.capture_path {
+        env.push(("PTP_ALERT_CAPTURE", path.display().to_string()));
+    }
+    env
+}
+
+/// The alert as JSON, `text` being a one line summary for chat webhooks
+pub fn alert_json(alert: &Alert) -> serde_json::Value {
+    serde_json::json!({
+        "text": format!("ptp-trace: {}", alert),
+        "kind": alert.kind.slug(),
+        "severity": alert.severity.slug(),
+        "time": alert_time(alert),
+        "domain": alert.domain_number,
+        "clock_identity": alert.clock_identity.map(|id| id.to_string()),
+        "message": alert.message,
+        "capture": alert.capture_path.as_ref().map(|path| path.display().to_string()),
+    })
+}
+
+fn alert_time(alert: &Alert) -> String {
+    chrono::DateTime::<chrono::Utc>::from(alert.timestamp).to_rfc3339()
+}
+
+/// Hooks from the config file and the number running
+#[derive(Debug, Clone, Default)]
+pub struct AlertHooks {
+    hooks: Vec<AlertHook>,
+    running: Arc<AtomicUsize>,
+}
+
+impl AlertHooks {
+    pub fn new(hooks: Vec<AlertHook>) -> Self {
+        Self {
+            hooks,
+            running: Arc::default(),
+        }
+    }
+
+    /// Start the hooks matching the alert in the background
+    pub fn run(&self, alert: &Alert) {
+        for hook in self.hooks.iter().filter(|hook| hook.matches(alert)) {
+            let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+                tracing::warn!(hook = %hook.describe(), "Alert hook not run, no runtime");
+                continue;
+            };
+            if self.running.fetch_add(1, Ordering::Relaxed) >= MAX_RUNNING_HOOKS {
+                self.running.fetch_sub(1, Ordering::Relaxed);
+                tracing::warn!(
+                    hook = %hook.describe(),
+                    "Alert hook skipped, {} hooks still running: {}",
+                    MAX_RUNNING_HOOKS,
+                    alert
+                );
+                continue;
+            }
+
+            let mut command = hook.command();
+            command.envs(alert_env(alert));
+            let input = alert_json(alert).to_string();
+            let name = hook.describe();
+            let running = self.running.clone();
+            runtime.spawn(async move {
+                run_hook(command, input, &name).await;
+                running.fetch_sub(1, Ordering::Relaxed);
+            });
+        }
+    }
+}
+
+async fn run_hook(mut command: Command, input: String, name: &str) {
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            tracing::warn!(hook = %name, "Alert hook failed to start: {}", e);
+            return;
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook not reading its input is fine
+        let _ = stdin.write_all(input.as_bytes()).await;
+    }
+    let output = match tokio::time::timeout(HOOK_TIMEOUT, child.wait_with_output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            tracing::warn!(hook = %name, "Alert hook failed: {}", e);
+            return;
+        }
+        Err(_) => {
+            tracing::warn!(
+                hook = %name,
+                "Alert hook killed after {} s",
+                HOOK_TIMEOUT.as_secs()
+            );
+            return;
+        }
+    };
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        tracing::info!(hook = %name, "{}", line);
+    }
+    for line in String::from_utf8_lossy(&output.stderr).lines() {
+        tracing::warn!(hook = %name, "{}", line);
+    }
+    if output.status.success() {
+        tracing::info!(hook = %name, "Alert hook done");
+    } else {
+        tracing::warn!(hook = %name, "Alert hook failed with {}", output.status);
+    }
+}
+
+#[test]
+fn test_alert_hooks() {
+    use crate::types::ClockIdentity;
+
+    let alert = Alert::new(
+        AlertKind::PttChanged,
+        AlertSeverity::Warning,
+        std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        "PTT changed".to_string(),
+    )
+    .with_domain(0)
+    .with_clock_identity(ClockIdentity {
+        clock_id: [0x00, 0x1b, 0x19, 0xff, 0xfe, 0x12, 0x34, 0x56],
+    });
+    let hook =
+        |alert: &str, min_severity, command: Option<&str>, webhook: Option<&str>| AlertHook {
+            alert: alert.to_string(),
+            min_severity,
+            command: command.map(String::from),
+            webhook: webhook.map(String::from),
+        };
+
+    assert!(hook("ptt-changed", None, Some("true"), None).matches(&alert));
+    assert!(hook("*", Some(AlertSeverity::Warning), Some("true"), None).matches(&alert));
+    assert!(!hook("*", Some(AlertSeverity::Critical), Some("true"), None).matches(&alert));
+    assert!(!hook("bond-failover", None, Some("true"), None).matches(&alert));
+
+    assert!(
+        hook("*", None, None, Some("https://example.com"))
+            .validate()
+            .is_ok()
+    );
+    assert!(
+        hook("ptt-change", None, Some("true"), None)
+            .validate()
+            .is_err()
+    );
+    assert!(hook("*", None, None, None).validate().is_err());
+    assert!(
+        hook("*", None, Some("true"), Some("https://example.com"))
+            .validate()
+            .is_err()
+    );
+
+    let env = alert_env(&alert);
+    assert!(env.contains(&("PTP_ALERT_KIND", "ptt-changed".to_string())));
+    assert!(env.contains(&("PTP_ALERT_CLOCK", "00:1b:19:ff:fe:12:34:56".to_string())));
+    assert!(env.contains(&("PTP_ALERT_TIME", "2023-11-14T22:13:20+00:00".to_string())));
+    let json = alert_json(&alert);
+    assert_eq!(json["domain"], 0);
+    assert_eq!(json["text"], "ptp-trace: [WARN] PTT changed: PTT changed");
+
+    let parsed: AlertHook =
+        toml::from_str("alert = \"*\"\nmin_severity = \"critical\"\ncommand = \"true\"").unwrap();
+    assert_eq!(parsed.min_severity, Some(AlertSeverity::Critical));
+
+    let command = hook("*", None, Some("echo $PTP_ALERT_KIND"), None).command();
+    let shell = if cfg!(unix) { "sh" } else { "cmd" };
+    assert_eq!(command.as_std().get_program(), shell);
+    assert_eq!(
+        command.as_std().get_args().last().unwrap(),
+        "echo $PTP_ALERT_KIND"
+    );
+}
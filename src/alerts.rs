@@ -0,0 +1,276 @@
+//! Alerts raised by the tracker when something noteworthy happens on the network
+//!
+//! Alerts are kept in a bounded log on the tracker. Optionally, the raw traffic
+//! leading up to an alert is dumped to a pcap file so that transient events can
+//! be analyzed after the fact.
+
+use std::{
+    fmt::Display,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::ClockIdentity;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertKind {
+    /// A different transmitter won the BMCA election in a domain
+    PttChanged,
+    /// A host sent a message whose length fields don't match its content
+    MalformedLength,
+    /// The clock quality announced by a time transmitter keeps getting worse
+    QualityDegrading,
+    /// Multicast PTP from a host crossed more routers than allowed
+    MulticastBoundary,
+    /// The Sync arrival skew between two redundant paths moved
+    PathDivergence,
+    /// A unicast session was cancelled or a unicast request denied
+    UnicastTeardown,
+    /// A time transmitter announced inconsistent timescale properties
+    TimescaleMismatch,
+    /// A receiver follows another grandmaster than the BMCA predicts
+    WrongTransmitter,
+    /// The currentUtcOffset announced in a domain changed
+    UtcOffsetChanged,
+    /// The TTL of a host's packets implies a different number of routed hops
+    HopDistanceChanged,
+    /// A grandmaster announces conflicting datasets into several domains
+    SharedGmConflict,
+    /// A host sent PTP to a destination address not meant for the message
+    MisaddressedMessage,
+    /// A domain elected another grandmaster than the config expects
+    GmDeviation,
+    /// A host sent event messages tagged with another 802.1p priority than expected
+    PriorityMismatch,
+    /// The phase between a primary grandmaster and its backup moved
+    GmPairDrift,
+    /// A host forwarded or relabeled Announces of another grandmaster
+    RelabeledAnnounce,
+    /// An active-backup bond captured on with --members switched members
+    BondFailover,
+    /// Two time transmitters of a domain are an epoch or era apart
+    EraMismatch,
+    /// Hosts of a domain send different PTP minor versions (2.0 and 2.1)
+    MixedMinorVersions,
+    /// A receiver's Delay_Req stopped or started hunting while the syncs continue
+    ReceiverLossOfLock,
+}
+
+impl AlertKind {
+    pub const ALL: [AlertKind; 20] = [
+        AlertKind::PttChanged,
+        AlertKind::MalformedLength,
+        AlertKind::QualityDegrading,
+        AlertKind::MulticastBoundary,
+        AlertKind::PathDivergence,
+        AlertKind::UnicastTeardown,
+        AlertKind::TimescaleMismatch,
+        AlertKind::WrongTransmitter,
+        AlertKind::UtcOffsetChanged,
+        AlertKind::HopDistanceChanged,
+        AlertKind::SharedGmConflict,
+        AlertKind::MisaddressedMessage,
+        AlertKind::GmDeviation,
+        AlertKind::PriorityMismatch,
+        AlertKind::GmPairDrift,
+        AlertKind::RelabeledAnnounce,
+        AlertKind::BondFailover,
+        AlertKind::EraMismatch,
+        AlertKind::MixedMinorVersions,
+        AlertKind::ReceiverLossOfLock,
+    ];
+
+    /// Short machine-friendly name, used for file names
+    pub fn slug(&self) -> &'static str {
+        match self {
+            AlertKind::PttChanged => "ptt-changed",
+            AlertKind::MalformedLength => "malformed-length",
+            AlertKind::QualityDegrading => "quality-degrading",
+            AlertKind::MulticastBoundary => "multicast-boundary",
+            AlertKind::PathDivergence => "path-divergence",
+            AlertKind::UnicastTeardown => "unicast-teardown",
+            AlertKind::TimescaleMismatch => "timescale-mismatch",
+            AlertKind::WrongTransmitter => "wrong-transmitter",
+            AlertKind::UtcOffsetChanged => "utc-offset-changed",
+            AlertKind::HopDistanceChanged => "hop-distance-changed",
+            AlertKind::SharedGmConflict => "shared-gm-conflict",
+            AlertKind::MisaddressedMessage => "misaddressed-message",
+            AlertKind::GmDeviation => "gm-deviation",
+            AlertKind::PriorityMismatch => "priority-mismatch",
+            AlertKind::GmPairDrift => "gm-pair-drift",
+            AlertKind::RelabeledAnnounce => "relabeled-announce",
+            AlertKind::BondFailover => "bond-failover",
+            AlertKind::EraMismatch => "era-mismatch",
+            AlertKind::MixedMinorVersions => "mixed-minor-versions",
+            AlertKind::ReceiverLossOfLock => "receiver-loss-of-lock",
+        }
+    }
+
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.slug() == slug)
+    }
+}
+
+impl Display for AlertKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlertKind::PttChanged => write!(f, "PTT changed"),
+            AlertKind::MalformedLength => write!(f, "Malformed length"),
+            AlertKind::QualityDegrading => write!(f, "Clock quality degrading"),
+            AlertKind::MulticastBoundary => write!(f, "Multicast boundary crossed"),
+            AlertKind::PathDivergence => write!(f, "Redundant paths diverging"),
+            AlertKind::UnicastTeardown => write!(f, "Unicast session ended"),
+            AlertKind::TimescaleMismatch => write!(f, "Timescale inconsistent"),
+            AlertKind::WrongTransmitter => write!(f, "Receiver tracking wrong GM"),
+            AlertKind::UtcOffsetChanged => write!(f, "UTC offset changed"),
+            AlertKind::HopDistanceChanged => write!(f, "Hop distance changed"),
+            AlertKind::SharedGmConflict => write!(f, "Shared GM inconsistent"),
+            AlertKind::MisaddressedMessage => write!(f, "Misaddressed message"),
+            AlertKind::GmDeviation => write!(f, "GM deviates from design"),
+            AlertKind::PriorityMismatch => write!(f, "Wrong VLAN priority"),
+            AlertKind::GmPairDrift => write!(f, "GM pair drifting"),
+            AlertKind::RelabeledAnnounce => write!(f, "Relabeled Announce"),
+            AlertKind::BondFailover => write!(f, "Bond failover"),
+            AlertKind::EraMismatch => write!(f, "Transmitters an era apart"),
+            AlertKind::MixedMinorVersions => write!(f, "PTP minor versions mixed"),
+            AlertKind::ReceiverLossOfLock => write!(f, "Receiver lost lock"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSeverity {
+    /// Worth logging, not a problem by itself
+    Info,
+    Warning,
+    /// The network no longer runs as designed
+    Critical,
+}
+
+impl AlertSeverity {
+    /// Lowercase name, as in the config file
+    pub fn slug(&self) -> &'static str {
+        match self {
+            AlertSeverity::Info => "info",
+            AlertSeverity::Warning => "warning",
+            AlertSeverity::Critical => "critical",
+        }
+    }
+}
+
+impl Display for AlertSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlertSeverity::Info => write!(f, "INFO"),
+            AlertSeverity::Warning => write!(f, "WARN"),
+            AlertSeverity::Critical => write!(f, "CRIT"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub severity: AlertSeverity,
+    pub timestamp: SystemTime,
+    pub domain_number: Option<u8>,
+    pub clock_identity: Option<ClockIdentity>,
+    pub message: String,
+    /// pcap file the surrounding traffic was saved to, if alert capture is enabled
+    pub capture_path: Option<PathBuf>,
+}
+
+impl Alert {
+    pub fn new(
+        kind: AlertKind,
+        severity: AlertSeverity,
+        timestamp: SystemTime,
+        message: String,
+    ) -> Self {
+        Self {
+            kind,
+            severity,
+            timestamp,
+            domain_number: None,
+            clock_identity: None,
+            message,
+            capture_path: None,
+        }
+    }
+
+    pub fn with_domain(mut self, domain_number: u8) -> Self {
+        self.domain_number = Some(domain_number);
+        self
+    }
+
+    pub fn with_clock_identity(mut self, clock_identity: ClockIdentity) -> Self {
+        self.clock_identity = Some(clock_identity);
+        self
+    }
+
+    /// File name (without extension) identifying this alert, e.g.
+    /// `20250101T120000Z_ptt-changed_dom0`
+    pub fn file_stem(&self) -> String {
+        let time = chrono::DateTime::<chrono::Utc>::from(self.timestamp);
+        let mut stem = format!("{}_{}", time.format("%Y%m%dT%H%M%SZ"), self.kind.slug());
+
+        if let Some(domain) = self.domain_number {
+            stem.push_str(&format!("_dom{}", domain));
+        }
+
+        if let Some(clock_identity) = self.clock_identity {
+            stem.push('_');
+            stem.push_str(&clock_identity.to_string().replace(':', ""));
+        }
+
+        stem
+    }
+}
+
+impl Display for Alert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}: {}", self.severity, self.kind, self.message)
+    }
+}
+
+/// Configuration for saving recent traffic to pcap when an alert fires
+#[derive(Debug, Clone)]
+pub struct AlertCapture {
+    pub directory: PathBuf,
+    pub window: Duration,
+}
+
+impl AlertCapture {
+    pub fn new(directory: PathBuf, window: Duration) -> Self {
+        Self { directory, window }
+    }
+
+    pub fn path_for(&self, alert: &Alert) -> PathBuf {
+        self.directory.join(format!("{}.pcap", alert.file_stem()))
+    }
+}
+
+#[test]
+fn test_alert_file_stem() {
+    let alert = Alert::new(
+        AlertKind::PttChanged,
+        AlertSeverity::Warning,
+        std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        "test".to_string(),
+    )
+    .with_domain(127)
+    .with_clock_identity(ClockIdentity {
+        clock_id: [0x00, 0x1b, 0x19, 0xff, 0xfe, 0x12, 0x34, 0x56],
+    });
+
+    assert_eq!(
+        alert.file_stem(),
+        "20231114T221320Z_ptt-changed_dom127_001b19fffe123456"
+    );
+    for kind in AlertKind::ALL {
+        assert_eq!(AlertKind::from_slug(kind.slug()), Some(kind));
+    }
+}
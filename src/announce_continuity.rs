@@ -0,0 +1,145 @@
+//! Announce continuity of a time transmitter over the last minute
+//!
+//! A grandmaster that drops an Announce now and then keeps its place in the
+//! host table, but its receivers may time out and re-run the BMCA every time.
+//! The arrival times of its Announces over the last CONTINUITY_WINDOW are
+//! compared with its logMessageInterval: a gap is a stretch longer than
+//! MISSED_INTERVALS intervals without one. The tree view shows the result as
+//! a small bar next to each transmitter, so intermittent ones stand out.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime},
+};
+
+/// Time span covered by the continuity bar
+pub const CONTINUITY_WINDOW: Duration = Duration::from_secs(60);
+/// Cells of the continuity bar
+pub const CONTINUITY_SLOTS: u32 = 12;
+/// An Announce is missed once this many intervals pass without one
+const MISSED_INTERVALS: f64 = 1.5;
+/// logMessageInterval value meaning "not specified", e.g. in unicast mode
+const UNSPECIFIED_LOG_INTERVAL: i8 = 0x7f;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Continuity {
+    /// Before the first Announce in the window
+    NotSeen,
+    /// Announces arrived on schedule
+    OnSchedule,
+    /// At least one Announce was missed
+    Gap,
+}
+
+impl Continuity {
+    pub fn symbol(&self) -> char {
+        match self {
+            Continuity::NotSeen => ' ',
+            Continuity::OnSchedule => '█',
+            Continuity::Gap => '░',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AnnounceContinuity {
+    arrivals: VecDeque<SystemTime>,
+    log_interval: i8,
+}
+
+impl AnnounceContinuity {
+    pub fn record(&mut self, at: SystemTime, log_interval: i8) {
+        self.log_interval = log_interval;
+        self.arrivals.push_back(at);
+        // Keep the last arrival before the window, a gap may start there
+        while self.arrivals.len() > 2
+            && at.duration_since(self.arrivals[1]).unwrap_or_default() > CONTINUITY_WINDOW
+        {
+            self.arrivals.pop_front();
+        }
+    }
+
+    fn max_gap(&self) -> Duration {
+        // Unicast negotiates the rate instead, assume one per second
+        let log_interval = if self.log_interval == UNSPECIFIED_LOG_INTERVAL {
+            0
+        } else {
+            self.log_interval.clamp(-7, 7)
+        };
+        Duration::from_secs_f64(2f64.powi(log_interval as i32) * MISSED_INTERVALS)
+    }
+
+    /// Stretches without Announces longer than the schedule allows, up to now
+    fn gaps(&self, now: SystemTime) -> Vec<(SystemTime, SystemTime)> {
+        let max_gap = self.max_gap();
+        let mut gaps: Vec<_> = self
+            .arrivals
+            .iter()
+            .zip(self.arrivals.iter().skip(1))
+            .filter(|(a, b)| b.duration_since(**a).unwrap_or_default() > max_gap)
+            .map(|(a, b)| (*a, *b))
+            .collect();
+        if let Some(last) = self.arrivals.back()
+            && now.duration_since(*last).unwrap_or_default() > max_gap
+        {
+            gaps.push((*last, now));
+        }
+        gaps
+    }
+
+    /// Continuity of each slot of the window ending at now, oldest first
+    pub fn slots(&self, now: SystemTime) -> Vec<Continuity> {
+        let Some(first) = self.arrivals.front() else {
+            return vec![Continuity::NotSeen; CONTINUITY_SLOTS as usize];
+        };
+        let gaps = self.gaps(now);
+        let slot = CONTINUITY_WINDOW / CONTINUITY_SLOTS;
+        let start = now - CONTINUITY_WINDOW;
+        (0..CONTINUITY_SLOTS)
+            .map(|i| {
+                let slot_start = start + slot * i;
+                let slot_end = slot_start + slot;
+                if slot_end <= *first {
+                    Continuity::NotSeen
+                } else if gaps
+                    .iter()
+                    .any(|(gap_start, gap_end)| *gap_start < slot_end && *gap_end > slot_start)
+                {
+                    Continuity::Gap
+                } else {
+                    Continuity::OnSchedule
+                }
+            })
+            .collect()
+    }
+
+    /// The slots as a bar of block characters
+    pub fn bar(&self, now: SystemTime) -> String {
+        self.slots(now).iter().map(Continuity::symbol).collect()
+    }
+}
+
+#[test]
+fn test_announce_continuity() {
+    let at = |millis: u64| SystemTime::UNIX_EPOCH + Duration::from_millis(millis);
+    let mut continuity = AnnounceContinuity::default();
+    assert_eq!(continuity.bar(at(60_000)), " ".repeat(12));
+
+    // One per second for the last 30 s, except 41 to 44 s
+    for second in 30..60 {
+        if !(41..44).contains(&second) {
+            continuity.record(at(second * 1000), 0);
+        }
+    }
+    assert_eq!(continuity.bar(at(60_000)), "      ██░███");
+    // Nothing since the last one at 59 s
+    assert_eq!(continuity.bar(at(66_000)), "    ██░░██░░");
+
+    // At 8 s intervals a 5 s slot without an Announce is on schedule
+    let mut continuity = AnnounceContinuity::default();
+    for second in (0..=60).step_by(8) {
+        continuity.record(at(second * 1000), 3);
+    }
+    assert_eq!(continuity.bar(at(60_000)), "█".repeat(12));
+    assert_eq!(continuity.arrivals.len(), 8);
+}
@@ -1,30 +1,74 @@
 use anyhow::Result;
 use crossterm::{
+    clipboard::CopyToClipboard,
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, MouseButton,
-        MouseEvent, MouseEventKind,
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
     },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{
     Terminal,
-    backend::{Backend, CrosstermBackend},
+    backend::{Backend, CrosstermBackend, TestBackend},
+    buffer::Buffer,
     layout::Rect,
 };
 use std::{
+    collections::{HashMap, VecDeque},
     io,
-    time::{Duration, Instant},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant, SystemTime},
 };
 use tokio::time;
 
-use crate::types::{ClockIdentity, ParsedPacket};
+#[cfg(unix)]
+use crate::local_clock::{InterfaceClocks, LocalClockMonitor};
+use crate::types::{ClockIdentity, ParsedPacket, format_ptp_instance};
 
 use crate::{
-    ptp::{PtpHost, PtpHostState, PtpTracker},
+    adaptive::{AdaptiveInterval, DEFAULT_MAX_INTERVAL, DEFAULT_MIN_INTERVAL},
+    alert_hooks::AlertHooks,
+    alerts::AlertKind,
+    baseline::{Baseline, BaselineCounts, SessionSnapshot},
+    bookmarks::{Bookmarks, MAX_NOTE_LENGTH},
+    clock::Clock,
+    config::{Config, ConfigOverrides, SettingsField, parse_tags},
+    csv_tail::CsvTail,
+    details_field::details_field,
+    filter::HostFilter,
+    golden::Golden,
+    heartbeat::{HEARTBEAT_FRAME, Heartbeat, HeartbeatState},
+    html_report::{self, Report},
+    keymap::HelpSection,
+    logging::LogTail,
+    macros::{MacroKey, format_keys, parse_keys},
+    minimap::Minimap,
+    mute::{MuteList, MuteRule},
+    pcap_export::{ExportFormat, export_stem, write_packets},
+    profiling::{Profiler, Stage},
+    ptp::{ExchangeStep, PtpHost, PtpHostState, PtpTracker},
+    screenshot,
+    session_summary::SessionSummary,
+    swimlane::Swimlane,
+    switch_ports::{SwitchPort, SwitchPorts},
+    time_range::TimeRange,
+    top_talkers::{TalkerSort, TopTalkers},
     ui::ui,
+    vendors::{VendorCount, VendorSort, vendor_breakdown, vendor_label},
+    what_if::WhatIf,
 };
 
+/// With instant redraw, the shortest time between two updates triggered by
+/// arriving packets, so a burst of them is drawn once
+const REDRAW_DEBOUNCE: Duration = Duration::from_millis(50);
+/// Jumps to a transmitter that can be returned from with Backspace
+const MAX_BREADCRUMBS: usize = 32;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppState {
     Running,
@@ -38,110 +82,297 @@ pub enum ActiveView {
     PacketHistory,
 }
 
+/// How raw numeric header fields are shown in the packet modal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberFormat {
+    Decimal,
+    Hex,
+    Both,
+}
+
+impl NumberFormat {
+    pub fn next(self) -> Self {
+        match self {
+            NumberFormat::Decimal => NumberFormat::Hex,
+            NumberFormat::Hex => NumberFormat::Both,
+            NumberFormat::Both => NumberFormat::Decimal,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NumberFormat::Decimal => "dec",
+            NumberFormat::Hex => "hex",
+            NumberFormat::Both => "dec+hex",
+        }
+    }
+}
+
+/// One side of the UI frozen with 'z' while the other stays live, unlike
+/// 'p' which pauses everything
+#[derive(Debug, Clone, Default)]
+pub enum SplitPause {
+    #[default]
+    Off,
+    /// Host table and details as they were when frozen at `at`, by clock
+    /// identity
+    Table {
+        hosts: HashMap<ClockIdentity, PtpHost>,
+        at: SystemTime,
+    },
+    /// Packet history of `host` as it was when frozen at `at`. Other hosts
+    /// only show their packets up to `at`.
+    Packets {
+        host: Option<ClockIdentity>,
+        at: SystemTime,
+        packets: Vec<Arc<ParsedPacket>>,
+    },
+}
+
+impl SplitPause {
+    /// Header indicator, None while nothing is frozen
+    pub fn label(&self) -> Option<&'static str> {
+        match self {
+            SplitPause::Off => None,
+            SplitPause::Table { .. } => Some("TABLE FROZEN"),
+            SplitPause::Packets { .. } => Some("PACKETS FROZEN"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum SortColumn {
     ClockIdentity,
     IpAddress,
     Interface,
+    Hops,
     State,
+    Role,
     Domain,
+    DelayMechanism,
     Priority,
     ClockClass,
     SelectedTransmitter,
     MessageCount,
     LastSeen,
+    Conformance,
     Vendor,
 }
 
 impl SortColumn {
     pub fn next(&self) -> Self {
         match self {
-            SortColumn::State => SortColumn::ClockIdentity,
+            SortColumn::State => SortColumn::Role,
+            SortColumn::Role => SortColumn::ClockIdentity,
             SortColumn::ClockIdentity => SortColumn::IpAddress,
             SortColumn::IpAddress => SortColumn::Interface,
-            SortColumn::Interface => SortColumn::Vendor,
+            SortColumn::Interface => SortColumn::Hops,
+            SortColumn::Hops => SortColumn::Vendor,
             SortColumn::Vendor => SortColumn::Domain,
-            SortColumn::Domain => SortColumn::Priority,
+            SortColumn::Domain => SortColumn::DelayMechanism,
+            SortColumn::DelayMechanism => SortColumn::Priority,
             SortColumn::Priority => SortColumn::ClockClass,
             SortColumn::ClockClass => SortColumn::SelectedTransmitter,
             SortColumn::SelectedTransmitter => SortColumn::MessageCount,
             SortColumn::MessageCount => SortColumn::LastSeen,
-            SortColumn::LastSeen => SortColumn::State,
+            SortColumn::LastSeen => SortColumn::Conformance,
+            SortColumn::Conformance => SortColumn::State,
         }
     }
 
     pub fn previous(&self) -> Self {
         match self {
+            SortColumn::Conformance => SortColumn::LastSeen,
             SortColumn::LastSeen => SortColumn::MessageCount,
             SortColumn::MessageCount => SortColumn::SelectedTransmitter,
             SortColumn::SelectedTransmitter => SortColumn::ClockClass,
             SortColumn::ClockClass => SortColumn::Priority,
-            SortColumn::Priority => SortColumn::Domain,
+            SortColumn::Priority => SortColumn::DelayMechanism,
+            SortColumn::DelayMechanism => SortColumn::Domain,
             SortColumn::Domain => SortColumn::Vendor,
-            SortColumn::Vendor => SortColumn::Interface,
+            SortColumn::Vendor => SortColumn::Hops,
+            SortColumn::Hops => SortColumn::Interface,
             SortColumn::Interface => SortColumn::IpAddress,
             SortColumn::IpAddress => SortColumn::ClockIdentity,
-            SortColumn::ClockIdentity => SortColumn::State,
-            SortColumn::State => SortColumn::LastSeen,
+            SortColumn::ClockIdentity => SortColumn::Role,
+            SortColumn::Role => SortColumn::State,
+            SortColumn::State => SortColumn::Conformance,
         }
     }
 
     pub fn display_name(&self) -> &'static str {
         match self {
             SortColumn::State => "State",
+            SortColumn::Role => "Role",
             SortColumn::ClockIdentity => "Clock Identity",
             SortColumn::IpAddress => "IP Address",
             SortColumn::Interface => "Interface",
+            SortColumn::Hops => "Hops",
             SortColumn::Vendor => "Vendor",
             SortColumn::Domain => "Domain",
+            SortColumn::DelayMechanism => "Delay Mechanism",
             SortColumn::Priority => "Priority",
             SortColumn::ClockClass => "Clock Class",
             SortColumn::SelectedTransmitter => "Selected Transmitter",
             SortColumn::MessageCount => "Msg Count",
             SortColumn::LastSeen => "Last Seen",
+            SortColumn::Conformance => "Conformance",
         }
     }
 }
 
 #[derive(Clone)]
 pub struct TreeNode {
-    pub host: PtpHost,
+    pub clock_identity: ClockIdentity,
     pub children: Vec<TreeNode>,
     pub depth: usize,
     pub is_primary_transmitter: bool,
 }
 
+/// A single line of the tree view, in display order
+#[derive(Debug, Clone)]
+pub struct TreeRow {
+    pub clock_identity: ClockIdentity,
+    pub depth: usize,
+    pub is_last_child: bool,
+    pub is_primary_transmitter: bool,
+}
+
+/// Packets of a host about to be exported, as the packet history showed them
+pub struct PendingExport {
+    pub clock_identity: ClockIdentity,
+    pub packets: Vec<Arc<ParsedPacket>>,
+    /// Interface filter of the packet history at the time
+    pub interface: Option<String>,
+    /// Bookmarked packets added that had already left the history
+    pub bookmarked: usize,
+}
+
+/// Everything the host ordering depends on, used to tell when it has to be rebuilt
+type HostOrderKey = (u64, u64, SortColumn, bool, bool, bool, Option<String>);
+
+// Helper function to flatten tree nodes for display
+fn flatten_tree_nodes(nodes: &[TreeNode]) -> Vec<TreeRow> {
+    let mut flattened = Vec::new();
+    let mut stack = Vec::new();
+
+    // Push initial nodes onto stack with their sibling information
+    for (i, node) in nodes.iter().enumerate().rev() {
+        let is_last_child = i == nodes.len() - 1;
+        stack.push((node, is_last_child));
+    }
+
+    // Process nodes iteratively
+    while let Some((node, is_last_child)) = stack.pop() {
+        flattened.push(TreeRow {
+            clock_identity: node.clock_identity,
+            depth: node.depth,
+            is_last_child,
+            is_primary_transmitter: node.is_primary_transmitter,
+        });
+
+        // Push children in reverse order so they're processed in correct order
+        for (i, child) in node.children.iter().enumerate().rev() {
+            let child_is_last = i == node.children.len() - 1;
+            stack.push((child, child_is_last));
+        }
+    }
+
+    flattened
+}
+
 pub struct App {
     pub state: AppState,
     pub update_interval: Duration,
-    pub debug: bool,
+    /// Show the tail of the debug log below the main content
+    pub show_log: bool,
+    pub log_tail: LogTail,
+    /// Show the time spent per pipeline stage below the main content
+    pub show_profiling: bool,
+    pub profiler: Profiler,
     pub ptp_tracker: PtpTracker,
     pub last_update: Instant,
+    // Follows the packet rate within bounds, None for a fixed interval
+    pub adaptive_interval: Option<AdaptiveInterval>,
     pub selected_index: usize,
     pub host_scroll_offset: usize,
     pub visible_height: usize,
     pub show_help: bool,
+    // Help limited to one feature (F1), searched with '/' and scrolled
+    pub help_scope: Option<HelpSection>,
+    pub help_query: String,
+    pub help_search_active: bool,
+    pub help_scroll: usize,
     pub theme: crate::themes::Theme,
 
     pub packet_scroll_offset: usize,
     pub max_packet_history: usize,
     pub packet_history_expanded: bool,
+    pub show_packet_deltas: bool,
+    // Message type colors and state abbreviations above the packet history
+    pub show_legend: bool,
+    // Explanations of suspicious host detail values, toggled with '?'
+    pub show_field_warnings: bool,
+    // Packets bookmarked with 'b', listed with 'B'
+    pub bookmarks: Bookmarks,
+    pub show_bookmarks: bool,
+    pub selected_bookmark: usize,
+    // Index of the bookmark whose note is being typed, and the text so far
+    pub bookmark_note: Option<(usize, String)>,
+    // Host whose tags are being edited with 'E', and the text so far
+    pub tag_input: Option<(ClockIdentity, String)>,
+    // Packets about to be exported with 'P', waiting for a format to be picked
+    pub pending_export: Option<PendingExport>,
+    // Only show packets that arrived on this interface, cycled with 'n'
+    pub packet_interface_filter: Option<String>,
+    // Statistics and packet history cover only this recent time range, 'u'
+    pub time_range: TimeRange,
     pub sort_column: SortColumn,
     pub sort_ascending: bool,
     pub selected_host_id: Option<ClockIdentity>,
-    pub paused: bool,
+    // When everything was paused with 'p'
+    pub paused: Option<SystemTime>,
+    // Host table or packet history frozen while the other stays live, 'z'
+    pub split_pause: SplitPause,
     pub tree_view_mode: bool,
+    pub show_timeline: bool,
+    pub show_gm_matrix: bool,
+    // Design the network is compared with (--golden), shown with 'C', and
+    // the file it is reloaded from
+    pub golden: Option<Golden>,
+    golden_path: Option<PathBuf>,
+    // Switch ports of MAC addresses imported with --switch-ports
+    pub switch_ports: SwitchPorts,
+    pub show_compliance: bool,
+    // Previous session the hosts are compared with (--baseline), listed with 'v'
+    pub baseline: Option<Baseline>,
+    pub show_baseline: bool,
+    // BMCA simulation on a snapshot of the transmitters, opened with 'W'
+    pub what_if: Option<WhatIf>,
+    // Message swimlanes of one PTP instance, toggled with 'L'
+    pub swimlane: Option<Swimlane>,
+    // Pulses with the Syncs of the main PTT in the header
+    pub heartbeat: Heartbeat,
+    pub show_interfaces: bool,
+    // Event and general ports of each host, toggled with 'O'
+    pub show_port_matrix: bool,
     pub active_view: ActiveView,
     pub selected_packet_index: usize,
     pub auto_scroll_packets: bool,
+    /// Update as soon as packets arrive instead of waiting for the interval
+    pub instant_redraw: bool,
     pub visible_packet_height: usize,
     pub show_packet_modal: bool,
-    pub modal_packet: Option<ParsedPacket>,
+    pub modal_packet: Option<Arc<ParsedPacket>>,
     pub modal_scroll_offset: usize,
     pub modal_visible_height: usize,
+    // Format of raw header fields in the packet modal, cycled with 'x'
+    pub modal_number_format: NumberFormat,
     pub force_redraw: bool,
     pub host_details_scroll_offset: usize,
     pub host_details_visible_height: usize,
+    // Plain text of the last rendered host details, for copying
+    pub host_details_text: String,
     pub host_selection_changed: bool,
     pub packet_selection_changed: bool,
 
@@ -149,66 +380,229 @@ pub struct App {
     pub host_table_area: Option<Rect>,
     pub host_details_area: Option<Rect>,
     pub packet_history_area: Option<Rect>,
+    // Mini-map line under the packet history, while it is drawn
+    pub packet_minimap_area: Option<Rect>,
     pub terminal_area: Option<Rect>,
 
     // Mouse support
     pub mouse_enabled: bool,
+    // Nothing may be sent over the network (--offline)
+    pub offline: bool,
+    // Alert hooks also run while a pcap file is read (--pcap-hooks)
+    pub pcap_hooks: bool,
 
     // Double-click support
     pub last_click_time: std::time::Instant,
     pub last_click_position: (u16, u16),
+
+    // Sorted host order and flattened tree, only rebuilt when hosts or sorting change
+    host_order: Vec<ClockIdentity>,
+    tree_rows: Vec<TreeRow>,
+    host_order_key: Option<HostOrderKey>,
+
+    // Settings view and the config file it saves to
+    pub show_settings: bool,
+    pub settings_selected: usize,
+    pub settings_status: Option<String>,
+    // Fields changed in the settings view, the only ones it saves
+    pub settings_changed: Vec<SettingsField>,
+    pub config: Config,
+    pub config_path: Option<PathBuf>,
+    // Command line settings, kept when the config file is reloaded
+    pub config_overrides: ConfigOverrides,
+    // Set on SIGHUP, the config file is reloaded with the next frame
+    reload_requested: Arc<AtomicBool>,
+
+    // Directory for pcap exports and the result of the last export
+    pub export_dir: PathBuf,
+    pub status_message: Option<(String, Instant)>,
+    // Exports written this session, for the summary on exit
+    pub written_files: Vec<PathBuf>,
+    pub session_started: Instant,
+    // Set by 'G', the next frame drawn is saved
+    screenshot_requested: bool,
+    // pcap file the packets are read from, named in the HTML report
+    pub pcap_file: Option<PathBuf>,
+
+    // ptp4l/PHC state of this machine, only with --ptp4l-socket or --phc
+    #[cfg(unix)]
+    pub local_clock: Option<LocalClockMonitor>,
+    // Hardware clocks of the capture interfaces, read while the view is shown
+    #[cfg(unix)]
+    pub interface_clocks: InterfaceClocks,
+    // Host rows appended every update interval, only with --tail-csv
+    pub csv_tail: Option<CsvTail>,
+
+    // Hosts listed in the table, set with --filter and toggled with 'F'
+    pub host_filter: HostFilter,
+    pub host_filter_enabled: bool,
+    // Mute rules given with --mute, on top of those in the config file
+    cli_mute_rules: Vec<String>,
+    // List the muted hosts too ('U'), to unmute them with 'M'
+    pub show_muted: bool,
+    // Only list hosts of this vendor, picked in the vendor breakdown ('V')
+    pub vendor_filter: Option<String>,
+    pub show_vendors: bool,
+    pub vendor_sort: VendorSort,
+    pub selected_vendor: usize,
+    // Hosts ranked by message rate over the last minute ('A'), of one
+    // domain or all
+    pub show_top_talkers: bool,
+    pub talker_sort: TalkerSort,
+    pub talker_domain: Option<u8>,
+    pub selected_talker: usize,
+    // Host given with --select-host, selected as soon as it shows up
+    pending_host_selection: Option<ClockIdentity>,
+    // Hosts left by jumping to their transmitter with Enter, most recent last
+    pub host_breadcrumbs: Vec<ClockIdentity>,
+
+    // Keyboard macros: 'm' or '@' waiting for a register, the macro being
+    // recorded, keys of a replayed macro still to handle and the register
+    // replayed last, for '@@'
+    macro_prefix: Option<char>,
+    macro_recording: Option<(char, Vec<MacroKey>)>,
+    macro_queue: VecDeque<MacroKey>,
+    last_macro: Option<char>,
 }
 
 impl App {
     pub fn new(
         update_interval: Duration,
-        debug: bool,
+        show_log: bool,
         theme_name: crate::themes::ThemeName,
-        raw_socket_receiver: crate::source::RawSocketReceiver,
+        packet_source: Box<dyn crate::source::PacketSource>,
         mouse_enabled: bool,
     ) -> Result<Self> {
-        let ptp_tracker = PtpTracker::new(raw_socket_receiver)?;
-        let theme = crate::themes::Theme::new(theme_name);
+        let ptp_tracker = PtpTracker::new(packet_source)?;
+        #[cfg(unix)]
+        let interface_clocks = InterfaceClocks::new(
+            &ptp_tracker
+                .packet_source
+                .interfaces()
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>(),
+        );
+        let theme = crate::themes::Theme::new(theme_name, crate::themes::ColorDepth::Auto);
 
         let mut app = Self {
             state: AppState::Running,
             update_interval,
-            debug,
+            show_log,
+            log_tail: LogTail::default(),
+            show_profiling: false,
+            profiler: Profiler::default(),
             ptp_tracker,
             last_update: Instant::now(),
+            adaptive_interval: None,
             selected_index: 0,
             host_scroll_offset: 0,
             visible_height: 20,
             show_help: false,
+            help_scope: None,
+            help_query: String::new(),
+            help_search_active: false,
+            help_scroll: 0,
             theme,
             packet_scroll_offset: 0,
             max_packet_history: 1000,
             packet_history_expanded: false,
+            show_packet_deltas: false,
+            show_legend: false,
+            packet_interface_filter: None,
+            time_range: TimeRange::default(),
+            show_field_warnings: false,
+            bookmarks: Bookmarks::default(),
+            show_bookmarks: false,
+            selected_bookmark: 0,
+            bookmark_note: None,
+            tag_input: None,
+            pending_export: None,
             sort_column: SortColumn::ClockIdentity,
             sort_ascending: true,
             selected_host_id: None,
-            paused: false,
+            paused: None,
+            split_pause: SplitPause::Off,
             tree_view_mode: false,
+            show_timeline: false,
+            show_gm_matrix: false,
+            golden: None,
+            golden_path: None,
+            switch_ports: SwitchPorts::default(),
+            show_compliance: false,
+            baseline: None,
+            show_baseline: false,
+            what_if: None,
+            swimlane: None,
+            heartbeat: Heartbeat::default(),
+            show_interfaces: false,
+            show_port_matrix: false,
             active_view: ActiveView::HostTable,
             selected_packet_index: 0,
             auto_scroll_packets: true,
+            instant_redraw: false,
             visible_packet_height: 8,
             show_packet_modal: false,
             modal_packet: None,
             modal_scroll_offset: 0,
             modal_visible_height: 10,
+            modal_number_format: NumberFormat::Decimal,
             force_redraw: false,
             host_details_scroll_offset: 0,
             host_details_visible_height: 10,
+            host_details_text: String::new(),
             host_selection_changed: true,
             packet_selection_changed: true,
             host_table_area: None,
             host_details_area: None,
             packet_history_area: None,
+            packet_minimap_area: None,
             terminal_area: None,
             mouse_enabled,
+            offline: false,
+            pcap_hooks: false,
             last_click_time: Instant::now(),
             last_click_position: (0, 0),
+            host_order: Vec::new(),
+            tree_rows: Vec::new(),
+            host_order_key: None,
+            show_settings: false,
+            settings_selected: 0,
+            settings_status: None,
+            settings_changed: Vec::new(),
+            config: Config::default(),
+            config_path: None,
+            config_overrides: ConfigOverrides::default(),
+            reload_requested: Arc::new(AtomicBool::new(false)),
+            export_dir: PathBuf::from("."),
+            status_message: None,
+            written_files: Vec::new(),
+            session_started: Instant::now(),
+            screenshot_requested: false,
+            pcap_file: None,
+            #[cfg(unix)]
+            local_clock: None,
+            #[cfg(unix)]
+            interface_clocks,
+            csv_tail: None,
+            host_filter: HostFilter::default(),
+            host_filter_enabled: false,
+            vendor_filter: None,
+            show_vendors: false,
+            cli_mute_rules: Vec::new(),
+            show_muted: false,
+            show_top_talkers: false,
+            talker_sort: TalkerSort::default(),
+            talker_domain: None,
+            selected_talker: 0,
+            vendor_sort: VendorSort::default(),
+            selected_vendor: 0,
+            pending_host_selection: None,
+            host_breadcrumbs: Vec::new(),
+            macro_prefix: None,
+            macro_recording: None,
+            macro_queue: VecDeque::new(),
+            last_macro: None,
         };
 
         // Set the max packet history on the tracker
@@ -218,6 +612,665 @@ impl App {
         Ok(app)
     }
 
+    /// Only list hosts matching the filter
+    pub fn set_host_filter(&mut self, filter: HostFilter) {
+        self.host_filter_enabled = !filter.is_empty();
+        self.host_filter = filter;
+    }
+
+    /// Select a host once it has been seen
+    pub fn preselect_host(&mut self, clock_identity: ClockIdentity) {
+        self.pending_host_selection = Some(clock_identity);
+    }
+
+    fn is_host_listed(&self, host: &PtpHost) -> bool {
+        (self.show_muted || !self.ptp_tracker.is_muted(host))
+            && (!self.host_filter_enabled || self.host_filter.matches(host))
+            && self
+                .vendor_filter
+                .as_deref()
+                .is_none_or(|vendor| vendor_label(host) == vendor)
+    }
+
+    /// Host counts per vendor over all hosts but the muted ones, in the
+    /// order of the breakdown
+    pub fn get_vendor_breakdown(&self) -> Vec<VendorCount> {
+        vendor_breakdown(
+            self.ptp_tracker
+                .get_hosts()
+                .into_iter()
+                .filter(|host| !self.ptp_tracker.is_muted(host)),
+            self.vendor_sort,
+        )
+    }
+
+    pub fn set_cli_mute_rules(&mut self, rules: Vec<String>) {
+        self.cli_mute_rules = rules;
+        self.update_mute_list();
+    }
+
+    /// Mute by the --mute rules and those of the config file, both checked
+    /// when given
+    fn update_mute_list(&mut self) {
+        let rules: Vec<&String> = self
+            .cli_mute_rules
+            .iter()
+            .chain(self.config.mute.iter())
+            .collect();
+        self.ptp_tracker
+            .set_mute_list(MuteList::parse(&rules).unwrap_or_default());
+        // Also when the table is frozen
+        self.host_order_key = None;
+        self.restore_host_selection();
+    }
+
+    /// Mute the selected host by its clock identity, or unmute it. The
+    /// rule is kept in the config file, like tags.
+    fn toggle_mute(&mut self) {
+        let Some(host) = self
+            .selected_host_id
+            .and_then(|id| self.ptp_tracker.get_host(&id))
+        else {
+            self.set_status("Select a host to mute".to_string());
+            return;
+        };
+        let rule = MuteRule::Clock(host.clock_identity).to_string();
+        let message = if let Some(index) = self.config.mute.iter().position(|r| *r == rule) {
+            self.config.mute.remove(index);
+            format!("Unmuted {}", host.clock_identity)
+        } else if let Some(other) = self.ptp_tracker.get_mute_list().rule_for(host) {
+            self.set_status(format!(
+                "{} is muted by '{}' (--mute or config file)",
+                host.clock_identity, other
+            ));
+            return;
+        } else {
+            self.config.mute.push(rule);
+            format!(
+                "Muted {}, left out of the table, statistics and alerts ('U' lists muted hosts)",
+                host.clock_identity
+            )
+        };
+        self.update_mute_list();
+        let message = match &self.config_path {
+            Some(path) => match self.config.save(path) {
+                Ok(()) => format!("{}, saved to {}", message, path.display()),
+                Err(e) => format!("{}, save failed: {:#}", message, e),
+            },
+            None => message,
+        };
+        self.set_status(message);
+    }
+
+    /// List only the hosts of a vendor, or all hosts again if the table is
+    /// already filtered by it
+    fn filter_by_vendor(&mut self, index: usize) {
+        let Some(count) = self.get_vendor_breakdown().into_iter().nth(index) else {
+            return;
+        };
+        self.show_vendors = false;
+        if self.vendor_filter.as_deref() == Some(count.vendor.as_str()) {
+            self.vendor_filter = None;
+            self.set_status("Vendor filter off".to_string());
+        } else {
+            self.set_status(format!(
+                "Listing the {} hosts of {}",
+                count.hosts(),
+                count.vendor
+            ));
+            self.vendor_filter = Some(count.vendor);
+        }
+        self.restore_host_selection();
+    }
+
+    fn handle_vendors_key(&mut self, key_code: KeyCode) {
+        let count = self.get_vendor_breakdown().len();
+        match key_code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('V') => self.show_vendors = false,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected_vendor = self.selected_vendor.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected_vendor = (self.selected_vendor + 1).min(count.saturating_sub(1));
+            }
+            KeyCode::Char('s') => {
+                self.vendor_sort = self.vendor_sort.next();
+                self.selected_vendor = 0;
+            }
+            KeyCode::Enter => self.filter_by_vendor(self.selected_vendor),
+            KeyCode::Char('c') => {
+                self.show_vendors = false;
+                if self.vendor_filter.take().is_some() {
+                    self.set_status("Vendor filter off".to_string());
+                    self.restore_host_selection();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Ranking of the hosts by message rate over the last minute
+    pub fn get_top_talkers(&self) -> TopTalkers {
+        TopTalkers::rank(
+            self.ptp_tracker
+                .get_hosts()
+                .into_iter()
+                .filter(|host| !self.ptp_tracker.is_muted(host)),
+            self.talker_domain,
+            self.talker_sort,
+            self.clock().now(),
+        )
+    }
+
+    /// Open the ranking on the domain of the selected host
+    fn open_top_talkers(&mut self) {
+        self.show_top_talkers = true;
+        self.selected_talker = 0;
+        self.talker_domain = self
+            .selected_host_id
+            .and_then(|id| self.ptp_tracker.get_host(&id))
+            .and_then(|host| host.domain_number);
+    }
+
+    fn handle_top_talkers_key(&mut self, key_code: KeyCode) {
+        let count = self.get_top_talkers().talkers.len();
+        match key_code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('A') => {
+                self.show_top_talkers = false;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected_talker = self.selected_talker.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected_talker = (self.selected_talker + 1).min(count.saturating_sub(1));
+            }
+            KeyCode::Char('s') => {
+                self.talker_sort = self.talker_sort.next();
+                self.selected_talker = 0;
+            }
+            KeyCode::Char('d') => {
+                // Next domain seen, then all domains
+                let domains: Vec<u8> = self
+                    .ptp_tracker
+                    .get_domain_host_counts()
+                    .into_keys()
+                    .collect();
+                self.talker_domain = match self.talker_domain {
+                    None => domains.first().copied(),
+                    Some(domain) => domains.into_iter().find(|other| *other > domain),
+                };
+                self.selected_talker = 0;
+            }
+            KeyCode::Enter => {
+                let Some(talker) = self
+                    .get_top_talkers()
+                    .talkers
+                    .into_iter()
+                    .nth(self.selected_talker)
+                else {
+                    return;
+                };
+                self.show_top_talkers = false;
+                if !self.select_host(talker.clock_identity) {
+                    self.set_status(format!("{} is not listed", talker.clock_identity));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Register a macro is being recorded into
+    pub fn recording_macro(&self) -> Option<char> {
+        self.macro_recording.as_ref().map(|(register, _)| *register)
+    }
+
+    /// m<register> starts recording and m stops it, @<register> replays a
+    /// macro and @@ the last one again. Returns true if the key was taken.
+    fn handle_macro_key(&mut self, key_code: KeyCode, modifiers: KeyModifiers) -> bool {
+        if let Some(prefix) = self.macro_prefix.take() {
+            match key_code {
+                KeyCode::Char(register) if prefix == 'm' && register.is_ascii_alphanumeric() => {
+                    self.macro_recording = Some((register, Vec::new()));
+                    self.set_status(format!("Recording macro @{}, m to stop", register));
+                }
+                KeyCode::Char(register)
+                    if prefix == '@' && (register.is_ascii_alphanumeric() || register == '@') =>
+                {
+                    self.play_macro(register);
+                }
+                _ => self.set_status("Macro cancelled".to_string()),
+            }
+            return true;
+        }
+
+        if !modifiers.is_empty() {
+            return false;
+        }
+        match key_code {
+            KeyCode::Char('m') => {
+                match self.macro_recording.take() {
+                    Some((register, keys)) => self.save_macro(register, keys),
+                    None => self.macro_prefix = Some('m'),
+                }
+                true
+            }
+            KeyCode::Char('@') => {
+                self.macro_prefix = Some('@');
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn save_macro(&mut self, register: char, keys: Vec<MacroKey>) {
+        let message = if keys.is_empty() {
+            self.config.macros.remove(&register.to_string());
+            format!("Macro @{} cleared", register)
+        } else {
+            self.config
+                .macros
+                .insert(register.to_string(), format_keys(&keys));
+            format!("Recorded macro @{} ({} keys)", register, keys.len())
+        };
+
+        // Macros are kept for the next session, like settings saved with 'w'
+        let message = match &self.config_path {
+            Some(path) => match self.config.save(path) {
+                Ok(()) => format!("{}, saved to {}", message, path.display()),
+                Err(e) => format!("{}, save failed: {:#}", message, e),
+            },
+            None => message,
+        };
+        self.set_status(message);
+    }
+
+    fn play_macro(&mut self, register: char) {
+        let register = match (register, self.last_macro) {
+            ('@', Some(last)) => last,
+            ('@', None) => {
+                self.set_status("No macro replayed yet".to_string());
+                return;
+            }
+            (register, _) => register,
+        };
+        let Some(text) = self.config.macros.get(&register.to_string()) else {
+            self.set_status(format!("Macro @{} is empty", register));
+            return;
+        };
+
+        match parse_keys(text) {
+            Ok(keys) => {
+                self.macro_queue.extend(keys);
+                self.last_macro = Some(register);
+            }
+            Err(e) => self.set_status(format!("Macro @{}: {}", register, e)),
+        }
+    }
+
+    fn toggle_host_filter(&mut self) {
+        if self.host_filter.is_empty() {
+            self.set_status("No host filter set, start with --filter".to_string());
+            return;
+        }
+        self.host_filter_enabled = !self.host_filter_enabled;
+        self.restore_host_selection();
+        let message = if self.host_filter_enabled {
+            format!("Host filter on: {}", self.host_filter)
+        } else {
+            "Host filter off".to_string()
+        };
+        self.set_status(message);
+    }
+
+    /// Use the given settings and apply them to the running application
+    pub fn set_config(&mut self, config: Config, config_path: Option<PathBuf>) {
+        self.config = config;
+        self.config_path = config_path;
+        self.apply_config();
+    }
+
+    fn apply_config(&mut self) {
+        self.update_interval = Duration::from_millis(self.config.update_interval_ms);
+        self.adaptive_interval = match (
+            self.config.min_update_interval_ms,
+            self.config.max_update_interval_ms,
+        ) {
+            (None, None) => None,
+            (min, max) => Some(AdaptiveInterval::new(
+                min.map_or(DEFAULT_MIN_INTERVAL, Duration::from_millis),
+                max.map_or(DEFAULT_MAX_INTERVAL, Duration::from_millis),
+                self.update_interval,
+            )),
+        };
+        self.max_packet_history = self.config.max_packet_history;
+        self.ptp_tracker
+            .set_max_packet_history(self.max_packet_history);
+        self.auto_scroll_packets = self.config.auto_scroll;
+        self.instant_redraw = self.config.instant_redraw;
+        self.theme = crate::themes::Theme::new(self.config.theme_name(), self.config.colors);
+        self.ptp_tracker
+            .set_host_expiry(match self.config.host_expiry_seconds {
+                0 => None,
+                seconds => Some(Duration::from_secs(seconds)),
+            });
+        self.ptp_tracker
+            .set_max_multicast_hops(self.config.max_multicast_hops);
+        self.ptp_tracker
+            .set_expected_event_pcp(self.config.event_pcp);
+        self.ptp_tracker.set_bmca_profile(
+            self.config.bmca_profile(),
+            self.config.local_priorities.clone().into_iter().collect(),
+        );
+        self.ptp_tracker
+            .set_host_tags(self.config.host_tags.clone().into_iter().collect());
+        // Checked when the config was loaded
+        self.ptp_tracker
+            .set_expected_grandmasters(self.config.expected_grandmasters().unwrap_or_default());
+        self.ptp_tracker
+            .set_backup_grandmasters(self.config.backup_grandmasters().unwrap_or_default());
+        // Commands can send as well as webhooks, e.g. with curl
+        let hooks = if self.offline {
+            if !self.config.alert_hooks.is_empty() {
+                tracing::warn!(
+                    "{} alert hooks disabled, offline",
+                    self.config.alert_hooks.len()
+                );
+            }
+            Vec::new()
+        } else if self.pcap_file.is_some() && !self.pcap_hooks {
+            // Alerts of a recording replayed are history, not news
+            if !self.config.alert_hooks.is_empty() {
+                tracing::info!(
+                    "{} alert hooks not run when reading a pcap file, see --pcap-hooks",
+                    self.config.alert_hooks.len()
+                );
+            }
+            Vec::new()
+        } else {
+            self.config.alert_hooks.clone()
+        };
+        self.ptp_tracker.set_alert_hooks(AlertHooks::new(hooks));
+        self.update_mute_list();
+    }
+
+    pub fn load_golden(&mut self, path: PathBuf) -> Result<()> {
+        self.golden = Some(Golden::load(&path)?);
+        self.golden_path = Some(path);
+        Ok(())
+    }
+
+    pub fn load_baseline(&mut self, path: PathBuf) -> Result<()> {
+        self.baseline = Some(Baseline::load(path)?);
+        Ok(())
+    }
+
+    /// New, changed and missing hosts against the baseline, muted hosts left
+    /// out of the new and changed ones
+    pub fn baseline_counts(&self) -> Option<BaselineCounts> {
+        let baseline = self.baseline.as_ref()?;
+        let hosts = self.ptp_tracker.get_hosts();
+        let unmuted: Vec<&PtpHost> = hosts
+            .iter()
+            .filter(|host| !self.ptp_tracker.is_muted(host))
+            .copied()
+            .collect();
+        Some(BaselineCounts {
+            missing: baseline.missing(&hosts).len(),
+            ..baseline.counts(&unmuted)
+        })
+    }
+
+    /// Write the hosts of this session for a later --baseline
+    pub fn save_session(&mut self, path: &Path) -> Result<()> {
+        let now = self.clock().now();
+        SessionSnapshot::from_hosts(self.ptp_tracker.get_hosts(), now).save(path)?;
+        self.written_files.push(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Reload the config file on SIGHUP, without restarting the capture
+    #[cfg(unix)]
+    pub fn reload_on_sighup(&mut self) -> Result<()> {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut hangup = signal(SignalKind::hangup())?;
+        let requested = self.reload_requested.clone();
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                requested.store(true, Ordering::Relaxed);
+            }
+        });
+        Ok(())
+    }
+
+    /// Read the config file and the golden file again (SIGHUP or Ctrl+R):
+    /// themes, tags, mute rules, thresholds and the expected design. The
+    /// command line settings still take precedence, and a file that doesn't
+    /// load leaves the running settings as they are.
+    fn reload_config(&mut self) {
+        let Some(path) = self.config_path.clone() else {
+            self.set_status("No config file to reload".to_string());
+            return;
+        };
+        let mut message = match Config::load(&path) {
+            Ok(mut config) => {
+                self.config_overrides.apply(&mut config);
+                self.config = config;
+                self.apply_config();
+                self.host_order_key = None;
+                self.restore_host_selection();
+                format!("Reloaded {}", path.display())
+            }
+            Err(e) => format!("Reload failed, settings unchanged: {:#}", e),
+        };
+        if let Some(golden_path) = &self.golden_path {
+            match Golden::load(golden_path) {
+                Ok(golden) => {
+                    self.golden = Some(golden);
+                    message.push_str(&format!(", {}", golden_path.display()));
+                }
+                Err(e) => message.push_str(&format!(", golden file unchanged: {:#}", e)),
+            }
+        }
+        tracing::info!("{}", message);
+        self.set_status(message);
+    }
+
+    fn edit_host_tags(&mut self) {
+        let Some(clock_identity) = self.selected_host_id else {
+            self.set_status("Select a host to tag".to_string());
+            return;
+        };
+        let tags = self
+            .config
+            .host_tags
+            .get(&clock_identity.to_string())
+            .map(|tags| tags.join(", "))
+            .unwrap_or_default();
+        self.tag_input = Some((clock_identity, tags));
+    }
+
+    fn handle_tag_input_key(&mut self, key_code: KeyCode) {
+        let Some((clock_identity, text)) = &mut self.tag_input else {
+            return;
+        };
+        match key_code {
+            KeyCode::Enter => {
+                let clock_identity = *clock_identity;
+                let tags = parse_tags(text);
+                self.tag_input = None;
+                let message = if tags.is_empty() {
+                    format!("Tags of {} removed", clock_identity)
+                } else {
+                    format!("Tagged {}: {}", clock_identity, tags.join(", "))
+                };
+                self.config.set_host_tags(&clock_identity.to_string(), tags);
+                self.ptp_tracker
+                    .set_host_tags(self.config.host_tags.clone().into_iter().collect());
+                self.restore_host_selection();
+
+                // Tags are kept for the next session, like macros
+                let message = match &self.config_path {
+                    Some(path) => match self.config.save(path) {
+                        Ok(()) => format!("{}, saved to {}", message, path.display()),
+                        Err(e) => format!("{}, save failed: {:#}", message, e),
+                    },
+                    None => message,
+                };
+                self.set_status(message);
+            }
+            KeyCode::Esc => self.tag_input = None,
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) if text.chars().count() < MAX_NOTE_LENGTH => text.push(c),
+            _ => {}
+        }
+    }
+
+    fn handle_settings_key(&mut self, key_code: KeyCode) {
+        let field_count = SettingsField::ALL.len();
+        match key_code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('o') => {
+                self.show_settings = false;
+                self.settings_status = None;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.settings_selected = (self.settings_selected + field_count - 1) % field_count;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.settings_selected = (self.settings_selected + 1) % field_count;
+            }
+            KeyCode::Left | KeyCode::Right | KeyCode::Enter | KeyCode::Char(' ') => {
+                let field = SettingsField::ALL[self.settings_selected];
+                field.adjust(&mut self.config, key_code != KeyCode::Left);
+                self.config_overrides.clear(field);
+                if !self.settings_changed.contains(&field) {
+                    self.settings_changed.push(field);
+                }
+                self.apply_config();
+                self.settings_status = None;
+            }
+            KeyCode::Char('w') => {
+                self.settings_status = Some(match &self.config_path {
+                    Some(path) => match self.save_settings(path) {
+                        Ok(()) => format!("Saved to {}", path.display()),
+                        Err(e) => format!("Save failed: {:#}", e),
+                    },
+                    None => "No config file location available, use --config".to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Write the fields changed in the settings view into the config file as
+    /// it is on disk, command line settings are not saved
+    fn save_settings(&self, path: &Path) -> Result<()> {
+        let mut config = Config::load(path)?;
+        for field in &self.settings_changed {
+            field.copy(&self.config, &mut config);
+        }
+        config.save(path)
+    }
+
+    fn handle_what_if_key(&mut self, key_code: KeyCode) {
+        let Some(what_if) = &mut self.what_if else {
+            return;
+        };
+        match key_code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('W') => {
+                self.what_if = None;
+            }
+            KeyCode::Up | KeyCode::Char('k') => what_if.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => what_if.select_next(),
+            KeyCode::Left | KeyCode::BackTab => what_if.select_field(false),
+            KeyCode::Right | KeyCode::Tab => what_if.select_field(true),
+            KeyCode::Char('+') | KeyCode::Char('=') => what_if.adjust(1),
+            KeyCode::Char('-') => what_if.adjust(-1),
+            KeyCode::PageUp => what_if.adjust(10),
+            KeyCode::PageDown => what_if.adjust(-10),
+            KeyCode::Char('r') => what_if.reset(false),
+            KeyCode::Char('R') => what_if.reset(true),
+            _ => {}
+        }
+    }
+
+    /// Keys of the swimlane view, false for keys it leaves to the main view
+    fn handle_swimlane_key(&mut self, key_code: KeyCode) -> bool {
+        let instances = self.get_swimlane_instances();
+        let Some(swimlane) = &mut self.swimlane else {
+            return false;
+        };
+        match key_code {
+            KeyCode::Esc | KeyCode::Char('L') => self.swimlane = None,
+            KeyCode::Left => swimlane.pan(true),
+            KeyCode::Right => swimlane.pan(false),
+            KeyCode::End => swimlane.follow(),
+            KeyCode::Char('+') | KeyCode::Char('=') => swimlane.zoom(true),
+            KeyCode::Char('-') => swimlane.zoom(false),
+            KeyCode::Up | KeyCode::Char('k') => swimlane.scroll = swimlane.scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => swimlane.scroll += 1,
+            KeyCode::Char('n') => {
+                let next = instances
+                    .iter()
+                    .position(|instance| Some(*instance) == swimlane.instance)
+                    .map_or(0, |index| (index + 1) % instances.len().max(1));
+                swimlane.instance = instances.get(next).copied();
+                swimlane.scroll = 0;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn toggle_swimlane(&mut self) {
+        if self.swimlane.is_some() {
+            self.swimlane = None;
+            return;
+        }
+        let instance = self
+            .selected_host_id
+            .and_then(|id| self.ptp_tracker.get_host(&id))
+            .and_then(|host| host.instance())
+            .or_else(|| self.get_swimlane_instances().first().copied());
+        self.swimlane = Some(Swimlane::new(instance));
+    }
+
+    /// PTP instances (sdoId, domain) that have hosts
+    pub fn get_swimlane_instances(&self) -> Vec<(u16, u8)> {
+        let instances: std::collections::BTreeSet<_> = self
+            .ptp_tracker
+            .get_hosts()
+            .iter()
+            .filter_map(|host| host.instance())
+            .collect();
+        instances.into_iter().collect()
+    }
+
+    /// Hosts of the swimlane instance, time transmitters first
+    pub fn get_swimlane_hosts(&self) -> Vec<&PtpHost> {
+        let Some(instance) = self.swimlane.and_then(|swimlane| swimlane.instance) else {
+            return Vec::new();
+        };
+        let mut hosts: Vec<&PtpHost> = self
+            .ptp_tracker
+            .get_hosts()
+            .into_iter()
+            .filter(|host| host.instance() == Some(instance))
+            .collect();
+        hosts.sort_by_key(|host| (!host.is_transmitter(), host.clock_identity));
+        hosts
+    }
+
+    /// Snapshot the announcing transmitters into the what-if panel
+    fn open_what_if(&mut self) {
+        self.what_if = Some(WhatIf::new(
+            &self.ptp_tracker.get_bmca_candidates(),
+            self.ptp_tracker.get_bmca_profile(),
+        ));
+    }
+
     pub async fn run(&mut self) -> Result<()> {
         enable_raw_mode()?;
         let mut stdout = io::stdout();
@@ -250,6 +1303,7 @@ impl App {
 
     async fn run_app<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
         let mut last_tick = Instant::now();
+        let mut redraw = true;
 
         loop {
             // Handle forced redraw (like Ctrl+L)
@@ -261,13 +1315,42 @@ impl App {
             }
 
             // Draw the UI
-            terminal.draw(|f| ui(f, self))?;
-
-            // Handle timeout for updates
-            let timeout = self.update_interval.saturating_sub(last_tick.elapsed());
+            if redraw {
+                let render_started = Instant::now();
+                let frame = terminal.draw(|f| ui(f, self))?;
+                if std::mem::take(&mut self.screenshot_requested) {
+                    let buffer = frame.buffer.clone();
+                    self.save_screenshot(&buffer);
+                }
+                if self.paused.is_none() {
+                    self.profiler
+                        .record(Stage::Render, render_started.elapsed());
+                }
+            }
+            redraw = true;
+
+            // Handle timeout for updates, redraw more often while the heartbeat pulses
+            let mut timeout = self.update_interval.saturating_sub(last_tick.elapsed());
+            let heartbeat_active = self.paused.is_none()
+                && self.heartbeat.state(Instant::now()) != HeartbeatState::Idle;
+            if heartbeat_active {
+                timeout = timeout.min(HEARTBEAT_FRAME);
+            }
+            // Look for packets every debounce period, nothing else wakes us up
+            let instant_redraw = self.instant_redraw && self.paused.is_none();
+            if instant_redraw {
+                timeout = timeout.min(REDRAW_DEBOUNCE);
+            }
 
-            // Check for events
-            if event::poll(timeout)? {
+            // Replay macro keys one per frame, each sees the layout the
+            // previous one left behind
+            let input = !self.macro_queue.is_empty() || event::poll(timeout)?;
+            if let Some((key_code, modifiers)) = self.macro_queue.pop_front() {
+                if let Err(_e) = self.handle_key_event(key_code, modifiers).await {
+                    self.state = AppState::Quitting;
+                    break;
+                }
+            } else if input {
                 match event::read()? {
                     Event::Key(key) if key.kind == KeyEventKind::Press => {
                         if let Err(_e) = self.handle_key_event_with_modifiers(key).await {
@@ -282,14 +1365,20 @@ impl App {
                         }
                     }
                     Event::Resize(_, _) => {
-                        // Terminal resize automatically triggers full redraw
-                        let size = terminal.size()?;
-                        terminal.resize(Rect::new(0, 0, size.width, size.height))?;
+                        // Start from a clean screen and bring the selection
+                        // back into view, the layout may have changed entirely
+                        self.force_redraw = true;
+                        self.host_selection_changed = true;
+                        self.packet_selection_changed = true;
                     }
                     _ => {}
                 }
             }
 
+            if self.reload_requested.swap(false, Ordering::Relaxed) {
+                self.reload_config();
+            }
+
             // Update data if enough time has passed
             if last_tick.elapsed() >= self.update_interval {
                 if let Err(_e) = self.update_data().await {
@@ -297,7 +1386,23 @@ impl App {
                     break;
                 }
                 last_tick = Instant::now();
+            } else if instant_redraw && last_tick.elapsed() >= REDRAW_DEBOUNCE {
+                // A full update as soon as packets arrived, only redraw if they did
+                let received = self.ptp_tracker.get_received_packet_count();
+                self.ptp_tracker.receive_packets().await;
+                if self.ptp_tracker.get_received_packet_count() != received {
+                    if let Err(_e) = self.update_data().await {
+                        self.state = AppState::Quitting;
+                        break;
+                    }
+                    last_tick = Instant::now();
+                } else if !heartbeat_active && !input {
+                    redraw = false;
+                }
+            } else if heartbeat_active {
+                self.ptp_tracker.receive_packets().await;
             }
+            self.update_heartbeat();
 
             // Check if we should quit
             if self.state == AppState::Quitting {
@@ -315,6 +1420,28 @@ impl App {
         &mut self,
         key: crossterm::event::KeyEvent,
     ) -> Result<()> {
+        // Notes are typed as they are, not taken as commands or macro keys
+        if self.bookmark_note.is_some() {
+            self.handle_bookmark_note_key(key.code);
+            return Ok(());
+        }
+        if self.tag_input.is_some() {
+            self.handle_tag_input_key(key.code);
+            return Ok(());
+        }
+        if self.show_help && self.help_search_active {
+            self.handle_help_search_key(key.code);
+            return Ok(());
+        }
+
+        // Shift is already part of the character
+        let modifiers = key.modifiers & KeyModifiers::CONTROL;
+        if self.handle_macro_key(key.code, modifiers) {
+            return Ok(());
+        }
+        if let Some((_, keys)) = &mut self.macro_recording {
+            keys.push((key.code, modifiers));
+        }
         self.handle_key_event(key.code, key.modifiers).await
     }
 
@@ -397,17 +1524,11 @@ impl App {
                 if self.show_packet_modal
                     && let Some(terminal_area) = self.terminal_area
                 {
-                    // Calculate modal area (exactly matching render_packet_modal)
-                    let preferred_width = (terminal_area.width as f32 * 0.4) as u16;
-                    let modal_width = preferred_width.max(80);
-                    let modal_height = (terminal_area.height as f32 * 0.6) as u16;
-                    let modal_x = (terminal_area.width - modal_width) / 2;
-                    let modal_y = (terminal_area.height - modal_height) / 2;
-
-                    if x < modal_x
-                        || x >= modal_x + modal_width
-                        || y < modal_y
-                        || y >= modal_y + modal_height
+                    let modal = crate::ui::packet_modal_area(terminal_area);
+                    if x < modal.x
+                        || x >= modal.x + modal.width
+                        || y < modal.y
+                        || y >= modal.y + modal.height
                     {
                         // Clicked outside modal, close it
                         self.show_packet_modal = false;
@@ -421,6 +1542,33 @@ impl App {
                     }
                 }
 
+                // A click on a vendor row filters the host table by it
+                if self.show_vendors
+                    && let Some(terminal_area) = self.terminal_area
+                {
+                    let popup = crate::ui::vendor_breakdown_area(
+                        terminal_area,
+                        self.get_vendor_breakdown().len(),
+                    );
+                    // Borders and header
+                    if x >= popup.x
+                        && x < popup.x + popup.width
+                        && y >= popup.y + 2
+                        && y < popup.y + popup.height - 1
+                    {
+                        let index = (y - popup.y - 2) as usize;
+                        self.selected_vendor = index;
+                        self.filter_by_vendor(index);
+                    } else if x < popup.x
+                        || x >= popup.x + popup.width
+                        || y < popup.y
+                        || y >= popup.y + popup.height
+                    {
+                        self.show_vendors = false;
+                    }
+                    return Ok(());
+                }
+
                 // Check which area was clicked
                 if let Some(area) = self.host_table_area
                     && x >= area.x
@@ -431,8 +1579,14 @@ impl App {
                     // Clicked in host table area
                     self.active_view = ActiveView::HostTable;
 
-                    // Calculate which row was clicked (accounting for borders and header)
-                    if y >= area.y + 2 && y < area.y + area.height - 1 {
+                    // Calculate which row was clicked (accounting for borders and header),
+                    // compact rows have no bottom border
+                    let bottom_border = if self.config.row_density.is_compact() {
+                        0
+                    } else {
+                        1
+                    };
+                    if y >= area.y + 2 && y < area.y + area.height - bottom_border {
                         let clicked_row = (y - area.y - 2) as usize;
                         let new_index = clicked_row + self.host_scroll_offset;
 
@@ -462,6 +1616,23 @@ impl App {
                     return Ok(());
                 }
 
+                if let Some(area) = self.packet_minimap_area
+                    && x >= area.x
+                    && x < area.x + area.width
+                    && y == area.y
+                {
+                    // Clicked in the packet history mini-map, jump there
+                    self.active_view = ActiveView::PacketHistory;
+                    let packets = self.get_packet_history();
+                    let minimap = Minimap::new(&packets, area.width as usize, |_| false, |_| false);
+                    if let Some(index) = minimap.packet_at((x - area.x) as usize) {
+                        self.selected_packet_index = index;
+                        self.packet_selection_changed = true;
+                        self.auto_scroll_packets = false;
+                    }
+                    return Ok(());
+                }
+
                 if let Some(area) = self.packet_history_area
                     && x >= area.x
                     && x < area.x + area.width
@@ -506,6 +1677,38 @@ impl App {
         key_code: KeyCode,
         modifiers: crossterm::event::KeyModifiers,
     ) -> Result<()> {
+        if self.show_settings {
+            self.handle_settings_key(key_code);
+            return Ok(());
+        }
+        if self.what_if.is_some() {
+            self.handle_what_if_key(key_code);
+            return Ok(());
+        }
+        if self.show_help {
+            self.handle_help_key(key_code);
+            return Ok(());
+        }
+        if self.swimlane.is_some() && self.handle_swimlane_key(key_code) {
+            return Ok(());
+        }
+        if self.pending_export.is_some() {
+            self.handle_export_key(key_code);
+            return Ok(());
+        }
+        if self.show_bookmarks {
+            self.handle_bookmarks_key(key_code);
+            return Ok(());
+        }
+        if self.show_vendors {
+            self.handle_vendors_key(key_code);
+            return Ok(());
+        }
+        if self.show_top_talkers {
+            self.handle_top_talkers_key(key_code);
+            return Ok(());
+        }
+
         match key_code {
             KeyCode::Tab => {
                 self.active_view = match self.active_view {
@@ -540,20 +1743,23 @@ impl App {
                     self.modal_packet = None;
                     self.modal_scroll_offset = 0;
                     self.modal_visible_height = 10;
-                } else if self.show_help {
-                    self.show_help = false;
+                } else if self.show_field_warnings {
+                    self.show_field_warnings = false;
                 } else {
                     self.state = AppState::Quitting;
                 }
             }
             KeyCode::Esc => {
-                if self.show_help {
-                    self.show_help = false;
-                }
+                self.show_field_warnings = false;
                 // ESC only closes help now - use 'q' for modal/quit
             }
-            KeyCode::Char('h') | KeyCode::F(1) => {
-                self.show_help = !self.show_help;
+            KeyCode::Char('h') => self.open_help(None),
+            KeyCode::F(1) => self.open_help(self.help_context()),
+            KeyCode::Char('o') => {
+                self.show_settings = true;
+            }
+            KeyCode::Char('r') if modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
+                self.reload_config();
             }
             KeyCode::Char('r') => {
                 self.update_data().await?;
@@ -569,6 +1775,7 @@ impl App {
             KeyCode::Char('c') => {
                 self.ptp_tracker.clear_hosts();
                 self.ptp_tracker.clear_all_packet_histories();
+                self.refresh_host_order();
                 self.selected_index = 0;
                 self.selected_host_id = None;
                 self.host_selection_changed = true;
@@ -647,73 +1854,732 @@ impl App {
                     }
                 }
             }
-            KeyCode::Char('d') => {
-                self.debug = !self.debug;
+            KeyCode::Left if self.show_packet_modal => {
+                self.step_modal_exchange(ExchangeStep::Previous);
+            }
+            KeyCode::Right if self.show_packet_modal => {
+                self.step_modal_exchange(ExchangeStep::Next);
+            }
+            KeyCode::Char('d') => {
+                self.show_log = !self.show_log;
+            }
+            KeyCode::Char('Z') => {
+                self.show_profiling = !self.show_profiling;
+            }
+            KeyCode::Char('D') => {
+                let enabled = self.ptp_tracker.is_dedup_enabled();
+                self.ptp_tracker.set_dedup_enabled(!enabled);
+            }
+            KeyCode::Char('p') => {
+                self.paused = match self.paused {
+                    Some(_) => None,
+                    None => Some(self.clock().now()),
+                };
+            }
+            KeyCode::Char('z') => {
+                self.cycle_split_pause();
+            }
+            KeyCode::Char('P') => {
+                self.start_packet_export();
+            }
+            KeyCode::Char('w') => {
+                self.toggle_auto_scroll();
+            }
+            KeyCode::Char('y') => {
+                self.copy_host_details();
+            }
+            KeyCode::Char('Y') => {
+                if let Some(clock_identity) = self.selected_host_id {
+                    self.copy_to_clipboard(clock_identity.to_string(), "clock identity");
+                } else {
+                    self.set_status("No host selected".to_string());
+                }
+            }
+            KeyCode::Char('e') => {
+                self.packet_history_expanded = !self.packet_history_expanded;
+            }
+            KeyCode::Char('i') => {
+                self.show_packet_deltas = !self.show_packet_deltas;
+            }
+            KeyCode::Char('K') => {
+                self.show_legend = !self.show_legend;
+            }
+            KeyCode::Char('n') => {
+                self.cycle_packet_interface_filter();
+            }
+            KeyCode::Char('u') => {
+                self.cycle_time_range();
+            }
+            KeyCode::Char('?') => {
+                self.show_field_warnings = !self.show_field_warnings;
+            }
+            KeyCode::Char('s') => {
+                self.cycle_sort_column();
+            }
+            KeyCode::Char('S') => {
+                self.toggle_sort_direction();
+            }
+            KeyCode::Char('a') => {
+                self.cycle_sort_column_previous();
+            }
+            KeyCode::Char('t') => {
+                // Toggle tree view mode
+                self.tree_view_mode = !self.tree_view_mode;
+                // Restore selection in the new view mode
+                self.restore_host_selection();
+            }
+            KeyCode::Char('T') => {
+                self.show_timeline = !self.show_timeline;
+                self.host_selection_changed = true;
+            }
+            KeyCode::Char('g') => {
+                self.show_gm_matrix = !self.show_gm_matrix;
+            }
+            KeyCode::Char('C') => {
+                if self.golden.is_some() {
+                    self.show_compliance = !self.show_compliance;
+                } else {
+                    self.set_status("No golden file loaded, start with --golden FILE".to_string());
+                }
+            }
+            KeyCode::Char('v') => {
+                if self.baseline.is_some() {
+                    self.show_baseline = !self.show_baseline;
+                } else {
+                    self.set_status("No baseline loaded, start with --baseline FILE".to_string());
+                }
+            }
+            KeyCode::Char('W') => {
+                self.open_what_if();
+            }
+            KeyCode::Char('L') => {
+                self.toggle_swimlane();
+            }
+            KeyCode::Char('I') => {
+                self.show_interfaces = !self.show_interfaces;
+                #[cfg(unix)]
+                if self.show_interfaces {
+                    self.interface_clocks.poll();
+                }
+            }
+            KeyCode::Char('O') => {
+                self.show_port_matrix = !self.show_port_matrix;
+            }
+            KeyCode::Char('f') => {
+                self.toggle_failover_drill();
+            }
+            KeyCode::Char('N') => {
+                self.request_clock_description();
+            }
+            KeyCode::Char('F') => {
+                self.toggle_host_filter();
+            }
+            KeyCode::Char('V') => {
+                self.show_vendors = true;
+                self.selected_vendor = 0;
+            }
+            KeyCode::Char('A') => {
+                self.open_top_talkers();
+            }
+            KeyCode::Char('b') => {
+                self.toggle_bookmark();
+            }
+            KeyCode::Char('M') => {
+                self.toggle_mute();
+            }
+            KeyCode::Char('U') => {
+                self.show_muted = !self.show_muted;
+                self.restore_host_selection();
+            }
+            KeyCode::Char('E') => {
+                self.edit_host_tags();
+            }
+            KeyCode::Char('B') => {
+                self.show_bookmarks = true;
+                self.selected_bookmark = self
+                    .selected_bookmark
+                    .min(self.bookmarks.len().saturating_sub(1));
+            }
+            KeyCode::Char('G') => {
+                self.screenshot_requested = true;
+            }
+            KeyCode::Char('H') => {
+                self.save_html_report();
+            }
+            KeyCode::Char('X') => {
+                self.show_delay_xref_example();
+            }
+            KeyCode::Char('x') if self.show_packet_modal => {
+                self.modal_number_format = self.modal_number_format.next();
+            }
+            KeyCode::Char('x') => {
+                self.clear_packet_history();
+            }
+            KeyCode::Char('R') => {
+                if let Some(selected_host_id) = self.selected_host_id {
+                    self.ptp_tracker.toggle_host_history(selected_host_id);
+                    self.selected_packet_index = 0;
+                    self.packet_scroll_offset = 0;
+                }
+            }
+            KeyCode::Enter => {
+                if self.show_packet_modal {
+                    // When modal is open, ENTER acts like cursor down
+                    self.scroll_modal_down();
+                } else if matches!(self.active_view, ActiveView::PacketHistory) {
+                    let packet_count = self.get_packet_history().len();
+                    if packet_count > 0
+                        && self.selected_packet_index < packet_count
+                        && let Some(packet) = self.get_selected_packet()
+                    {
+                        self.modal_packet = Some(packet);
+                        self.show_packet_modal = true;
+                        self.modal_scroll_offset = 0;
+                    }
+                } else {
+                    self.jump_to_selected_transmitter();
+                }
+            }
+            KeyCode::Backspace if !matches!(self.active_view, ActiveView::PacketHistory) => {
+                self.jump_back();
+            }
+            _ => {
+                // Other keys - no action needed
+            }
+        }
+        Ok(())
+    }
+
+    /// Feature whose keys F1 lists: the packet modal, the host filter or the
+    /// tree view, whichever is in use
+    fn help_context(&self) -> Option<HelpSection> {
+        if self.show_packet_modal {
+            Some(HelpSection::PacketModal)
+        } else if self.host_filter_enabled || self.vendor_filter.is_some() {
+            Some(HelpSection::Filter)
+        } else if self.tree_view_mode {
+            Some(HelpSection::TreeView)
+        } else {
+            None
+        }
+    }
+
+    fn open_help(&mut self, scope: Option<HelpSection>) {
+        self.show_help = true;
+        self.help_scope = scope;
+        self.help_query.clear();
+        self.help_search_active = false;
+        self.help_scroll = 0;
+    }
+
+    fn handle_help_key(&mut self, key_code: KeyCode) {
+        match key_code {
+            KeyCode::Esc if !self.help_query.is_empty() => {
+                self.help_query.clear();
+                self.help_scroll = 0;
+            }
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('h') | KeyCode::F(1) => {
+                self.show_help = false;
+            }
+            KeyCode::Char('/') => {
+                self.help_search_active = true;
+                self.help_query.clear();
+                self.help_scroll = 0;
+            }
+            // Between the scoped keys and all of them
+            KeyCode::Tab => {
+                self.help_scope = match self.help_scope {
+                    Some(_) => None,
+                    None => self.help_context(),
+                };
+                self.help_scroll = 0;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.help_scroll = self.help_scroll.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.help_scroll += 1,
+            KeyCode::PageUp => self.help_scroll = self.help_scroll.saturating_sub(10),
+            KeyCode::PageDown => self.help_scroll += 10,
+            KeyCode::Home => self.help_scroll = 0,
+            KeyCode::Char('o') => {
+                self.show_help = false;
+                self.show_settings = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_help_search_key(&mut self, key_code: KeyCode) {
+        match key_code {
+            KeyCode::Enter => self.help_search_active = false,
+            KeyCode::Esc => {
+                self.help_search_active = false;
+                self.help_query.clear();
+            }
+            KeyCode::Backspace => {
+                self.help_query.pop();
+            }
+            KeyCode::Char(c) => self.help_query.push(c),
+            _ => {}
+        }
+        self.help_scroll = 0;
+    }
+
+    /// Open the selected host's last Delay_Req without a response, or
+    /// Delay_Resp without a request, in the packet modal
+    fn show_delay_xref_example(&mut self) {
+        let example = self
+            .selected_host_id
+            .and_then(|id| self.ptp_tracker.get_host_by_clock_identity(&id))
+            .and_then(|host| host.delay_xref_example().cloned());
+        match example {
+            Some(packet) => {
+                self.modal_packet = Some(packet);
+                self.show_packet_modal = true;
+                self.modal_scroll_offset = 0;
+            }
+            None => self.set_status("No unmatched Delay_Req/Delay_Resp for this host".to_string()),
+        }
+    }
+
+    /// Show a short message in the header, e.g. the result of an export
+    pub fn set_status(&mut self, message: String) {
+        self.status_message = Some((message, Instant::now()));
+    }
+
+    /// Copy text to the system clipboard with OSC 52, which works through SSH
+    /// as long as the terminal emulator allows it
+    fn copy_to_clipboard(&mut self, text: String, what: &str) {
+        let message = match execute!(io::stdout(), CopyToClipboard::to_clipboard_from(text)) {
+            Ok(()) => format!("Copied {} to clipboard", what),
+            Err(e) => format!("Copy to clipboard failed: {}", e),
+        };
+        self.set_status(message);
+    }
+
+    /// Copy the highlighted field with the host details focused, else all
+    /// of them
+    fn copy_host_details(&mut self) {
+        if self.selected_host_id.is_none() {
+            self.set_status("No host selected".to_string());
+            return;
+        }
+        if !matches!(self.active_view, ActiveView::HostDetails) {
+            self.copy_to_clipboard(self.host_details_text.clone(), "host details");
+            return;
+        }
+        match details_field(&self.host_details_text, self.host_details_scroll_offset) {
+            Some(field) if field.label.is_empty() => {
+                self.copy_to_clipboard(field.value, "highlighted line")
+            }
+            Some(field) => {
+                let what = field.label.clone();
+                self.copy_to_clipboard(field.value, &what)
+            }
+            None => self.set_status("Nothing highlighted to copy".to_string()),
+        }
+    }
+
+    /// Collect the packets the packet history shows for the selected host and
+    /// ask for confirmation and a format. The interface filter applies,
+    /// bookmarked packets that already left the history are added.
+    fn start_packet_export(&mut self) {
+        let Some(clock_identity) = self.selected_host_id else {
+            self.set_status("No host selected".to_string());
+            return;
+        };
+
+        let mut packets = self.get_packet_history();
+        let mut bookmarked = 0;
+        for packet in self.bookmarks.host_packets(clock_identity) {
+            let shown = self
+                .packet_interface_filter
+                .as_ref()
+                .is_none_or(|interface| *interface == packet.raw.interface_name);
+            if shown
+                && !packets
+                    .iter()
+                    .any(|p| std::sync::Arc::ptr_eq(&p.raw, &packet.raw))
+            {
+                packets.push(packet);
+                bookmarked += 1;
+            }
+        }
+        if packets.is_empty() {
+            self.set_status("No packets to export".to_string());
+            return;
+        }
+        packets.sort_by_key(|packet| packet.raw.timestamp);
+
+        self.pending_export = Some(PendingExport {
+            clock_identity,
+            packets,
+            interface: self.packet_interface_filter.clone(),
+            bookmarked,
+        });
+    }
+
+    /// Write the pending export in the chosen format to the export
+    /// directory, named after the host and the current time
+    fn handle_export_key(&mut self, key_code: KeyCode) {
+        let format = match key_code {
+            KeyCode::Enter | KeyCode::Char('p') => ExportFormat::Pcap,
+            KeyCode::Char('c') => ExportFormat::Csv,
+            KeyCode::Char('j') => ExportFormat::Json,
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.pending_export = None;
+                self.set_status("Export cancelled".to_string());
+                return;
+            }
+            _ => return,
+        };
+        let Some(export) = self.pending_export.take() else {
+            return;
+        };
+
+        let stem = export_stem(
+            &self.export_dir,
+            &export.clock_identity.to_string().replace(':', ""),
+            &[format.extension()],
+        );
+        let path = self
+            .export_dir
+            .join(format!("{}.{}", stem, format.extension()));
+
+        let message = match write_packets(&path, format, &export.packets) {
+            Ok(count) => {
+                let message = format!("Exported {} packets to {}", count, path.display());
+                self.written_files.push(path);
+                message
+            }
+            Err(e) => format!("Export to {} failed: {}", path.display(), e),
+        };
+        self.set_status(message);
+    }
+
+    /// Bookmark the packet in the modal or the selected one in the packet
+    /// history and ask for a note, or remove its bookmark
+    fn toggle_bookmark(&mut self) {
+        let packet = if self.show_packet_modal {
+            self.modal_packet.clone()
+        } else if matches!(self.active_view, ActiveView::PacketHistory) {
+            self.get_selected_packet()
+        } else {
+            None
+        };
+        let (Some(packet), Some(clock_identity)) = (packet, self.selected_host_id) else {
+            self.set_status("Select a packet to bookmark".to_string());
+            return;
+        };
+
+        match self.bookmarks.toggle(clock_identity, &packet) {
+            Some(index) => self.bookmark_note = Some((index, String::new())),
+            None => self.set_status("Bookmark removed".to_string()),
+        }
+    }
+
+    fn handle_bookmark_note_key(&mut self, key_code: KeyCode) {
+        let Some((index, note)) = &mut self.bookmark_note else {
+            return;
+        };
+        match key_code {
+            KeyCode::Enter => {
+                self.bookmarks.set_note(*index, note);
+                self.bookmark_note = None;
+                self.set_status("Packet bookmarked, 'B' lists bookmarks".to_string());
+            }
+            // The bookmark stays, only the note is skipped
+            KeyCode::Esc => self.bookmark_note = None,
+            KeyCode::Backspace => {
+                note.pop();
             }
-            KeyCode::Char('p') => {
-                self.paused = !self.paused;
+            KeyCode::Char(c) if note.chars().count() < MAX_NOTE_LENGTH => note.push(c),
+            _ => {}
+        }
+    }
+
+    fn handle_bookmarks_key(&mut self, key_code: KeyCode) {
+        let count = self.bookmarks.len();
+        match key_code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('B') => self.show_bookmarks = false,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected_bookmark = self.selected_bookmark.saturating_sub(1);
             }
-            KeyCode::Char('w') => {
-                self.toggle_auto_scroll();
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected_bookmark = (self.selected_bookmark + 1).min(count.saturating_sub(1));
             }
-            KeyCode::Char('e') => {
-                self.packet_history_expanded = !self.packet_history_expanded;
+            KeyCode::Enter => self.jump_to_bookmark(self.selected_bookmark),
+            KeyCode::Char('e') if self.selected_bookmark < count => {
+                let note = self
+                    .bookmarks
+                    .get(self.selected_bookmark)
+                    .map(|bookmark| bookmark.note.clone())
+                    .unwrap_or_default();
+                self.bookmark_note = Some((self.selected_bookmark, note));
             }
-            KeyCode::Char('s') => {
-                self.cycle_sort_column();
+            KeyCode::Char('d') | KeyCode::Delete => {
+                self.bookmarks.remove(self.selected_bookmark);
+                self.selected_bookmark = self
+                    .selected_bookmark
+                    .min(self.bookmarks.len().saturating_sub(1));
             }
-            KeyCode::Char('S') => {
-                self.toggle_sort_direction();
+            KeyCode::Char('P') => self.export_bookmarks(),
+            _ => {}
+        }
+    }
+
+    /// Select the bookmarked packet in its host's packet history, or open it
+    /// in the modal if it is no longer there
+    fn jump_to_bookmark(&mut self, index: usize) {
+        let Some(bookmark) = self.bookmarks.get(index).cloned() else {
+            return;
+        };
+        self.show_bookmarks = false;
+
+        if let Some(host_index) = self.find_host_index(bookmark.clock_identity) {
+            self.selected_index = host_index;
+            self.update_selected_host(host_index);
+            self.host_selection_changed = true;
+        }
+
+        let position = (self.selected_host_id == Some(bookmark.clock_identity))
+            .then(|| {
+                self.get_packet_history()
+                    .iter()
+                    .position(|packet| std::sync::Arc::ptr_eq(&packet.raw, &bookmark.packet.raw))
+            })
+            .flatten();
+        match position {
+            Some(position) => {
+                self.active_view = ActiveView::PacketHistory;
+                self.selected_packet_index = position;
+                self.auto_scroll_packets = false;
+                self.packet_selection_changed = true;
             }
-            KeyCode::Char('a') => {
-                self.cycle_sort_column_previous();
+            None => {
+                self.modal_packet = Some(bookmark.packet);
+                self.show_packet_modal = true;
+                self.modal_scroll_offset = 0;
             }
-            KeyCode::Char('t') => {
-                // Toggle tree view mode
-                self.tree_view_mode = !self.tree_view_mode;
-                // Restore selection in the new view mode
-                self.restore_host_selection();
+        }
+    }
+
+    /// Save a frame to the export directory as plain text and with ANSI colors
+    fn save_screenshot(&mut self, buffer: &Buffer) {
+        let stem = export_stem(&self.export_dir, "screenshot", &["txt", "ansi"]);
+        let mut saved = Vec::new();
+        for extension in ["txt", "ansi"] {
+            let path = self.export_dir.join(format!("{}.{}", stem, extension));
+            if let Err(e) = screenshot::write(buffer, &path) {
+                self.set_status(format!("{:#}", e));
+                return;
             }
-            KeyCode::Char('x') => {
-                self.clear_packet_history();
+            saved.push(path.display().to_string());
+            self.written_files.push(path);
+        }
+        self.set_status(format!("Screenshot saved to {}", saved.join(" and ")));
+    }
+
+    /// A pcap file is read to the end, live sources give what arrived so far
+    async fn process_available_packets(&mut self) -> Result<()> {
+        loop {
+            let received = self.ptp_tracker.get_received_packet_count();
+            self.update_data().await?;
+            if self.ptp_tracker.get_received_packet_count() == received {
+                return Ok(());
             }
-            KeyCode::Enter => {
-                if self.show_packet_modal {
-                    // When modal is open, ENTER acts like cursor down
-                    self.scroll_modal_down();
-                } else if matches!(self.active_view, ActiveView::PacketHistory) {
-                    let packet_count = self.get_packet_history().len();
-                    if packet_count > 0
-                        && self.selected_packet_index < packet_count
-                        && let Some(packet) = self.get_selected_packet()
-                    {
-                        self.modal_packet = Some(packet);
-                        self.show_packet_modal = true;
-                        self.modal_scroll_offset = 0;
-                    }
+        }
+    }
+
+    /// Process what the packet source has to offer, render one frame of the
+    /// given size without a terminal and write it to `path` (--screenshot)
+    pub async fn screenshot(&mut self, path: &Path, (width, height): (u16, u16)) -> Result<()> {
+        self.process_available_packets().await?;
+
+        let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+        // The first frame lays out the views, scroll positions follow it
+        terminal.draw(|f| ui(f, self))?;
+        let frame = terminal.draw(|f| ui(f, self))?;
+        screenshot::write(frame.buffer, path)
+    }
+
+    /// The hosts as listed in the tree view, with the alerts, for the HTML
+    /// report
+    pub fn html_report(&self) -> Report<'_> {
+        let source = match &self.pcap_file {
+            Some(path) => path.display().to_string(),
+            None => {
+                let interfaces: Vec<String> = self
+                    .ptp_tracker
+                    .packet_source
+                    .interfaces()
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect();
+                if interfaces.is_empty() {
+                    "live capture".to_string()
+                } else {
+                    format!("live capture on {}", interfaces.join(", "))
                 }
             }
-            _ => {
-                // Other keys - no action needed
+        };
+        Report {
+            source,
+            reference_time: self.clock().now(),
+            hosts: self
+                .tree_rows
+                .iter()
+                .filter_map(|row| {
+                    let host = self.ptp_tracker.get_host(&row.clock_identity)?;
+                    Some((host, row.depth))
+                })
+                .collect(),
+            alerts: self.ptp_tracker.get_alerts().iter().collect(),
+        }
+    }
+
+    /// Write the HTML report to the export directory
+    fn save_html_report(&mut self) {
+        let path = self.export_dir.join(format!(
+            "{}.html",
+            export_stem(&self.export_dir, "report", &["html"])
+        ));
+        let message = match html_report::write(&self.html_report(), &path) {
+            Ok(()) => {
+                let message = format!("HTML report saved to {}", path.display());
+                self.written_files.push(path);
+                message
             }
+            Err(e) => format!("{:#}", e),
+        };
+        self.set_status(message);
+    }
+
+    /// Process what the packet source has to offer and write the HTML report
+    /// to `path` (--html-report)
+    pub async fn write_html_report(&mut self, path: &Path) -> Result<()> {
+        self.process_available_packets().await?;
+        self.refresh_host_order();
+        html_report::write(&self.html_report(), path)
+    }
+
+    /// Write all bookmarked packets to a pcapng file in the export directory
+    fn export_bookmarks(&mut self) {
+        if self.bookmarks.is_empty() {
+            self.set_status("No bookmarks to export".to_string());
+            return;
         }
-        Ok(())
+        let path = self.export_dir.join(format!(
+            "{}.pcapng",
+            export_stem(&self.export_dir, "bookmarks", &["pcapng"])
+        ));
+        let message = match self.bookmarks.write_pcapng(&path) {
+            Ok(count) => {
+                let message = format!("Exported {} bookmarks to {}", count, path.display());
+                self.written_files.push(path);
+                message
+            }
+            Err(e) => format!("Export to {} failed: {}", path.display(), e),
+        };
+        self.set_status(message);
+    }
+
+    /// Arm a failover drill on the PTT of the selected host's domain, or end
+    /// the running one
+    fn toggle_failover_drill(&mut self) {
+        if self.ptp_tracker.get_failover_drill().is_some() {
+            self.ptp_tracker.stop_failover_drill();
+            self.set_status("Failover drill ended".to_string());
+            return;
+        }
+
+        let Some(instance) = self
+            .selected_host_id
+            .and_then(|id| self.ptp_tracker.get_host(&id))
+            .and_then(|host| host.instance())
+        else {
+            self.set_status("No host selected".to_string());
+            return;
+        };
+
+        let message = match self.ptp_tracker.start_failover_drill(instance) {
+            Some(ptt) => format!("Failover drill armed on PTT {}, take it down now", ptt),
+            None => format!(
+                "No PTT in domain {}",
+                format_ptp_instance(instance.0, instance.1)
+            ),
+        };
+        self.set_status(message);
+    }
+
+    /// Ask the selected host for its CLOCK_DESCRIPTION (product and name)
+    fn request_clock_description(&mut self) {
+        if self.offline {
+            self.set_status("Offline, hosts are not queried".to_string());
+            return;
+        }
+        let Some(clock_identity) = self.selected_host_id else {
+            self.set_status("No host selected".to_string());
+            return;
+        };
+        let message = match self.ptp_tracker.request_clock_description(&clock_identity) {
+            Ok(()) => format!("Sent GET CLOCK_DESCRIPTION to {}", clock_identity),
+            Err(e) => e.to_string(),
+        };
+        self.set_status(message);
     }
 
     pub async fn update_data(&mut self) -> Result<()> {
         // Skip network scanning if paused
-        if self.paused {
+        if self.paused.is_some() {
             return Ok(());
         }
 
         self.ptp_tracker.scan_network().await;
+        #[cfg(unix)]
+        if self.show_interfaces {
+            self.interface_clocks.poll();
+        }
+        if let Some(summary) = self.ptp_tracker.take_failover_drill_completion() {
+            self.set_status(summary);
+        }
+        if let Some(csv_tail) = &mut self.csv_tail
+            && let Err(e) =
+                csv_tail.append(self.ptp_tracker.get_hosts(), std::time::SystemTime::now())
+        {
+            self.set_status(format!("CSV tail stopped: {:#}", e));
+            self.csv_tail = None;
+        }
         // Restore host selection to maintain stability when list changes
         self.restore_host_selection();
+        let backlogged = self.ptp_tracker.take_backlogged();
+        self.profiler.finish_cycle(
+            self.ptp_tracker.take_stage_times(),
+            self.ptp_tracker.get_received_packet_count(),
+            self.last_update.elapsed(),
+            backlogged,
+        );
+        if let Some(adaptive) = &mut self.adaptive_interval {
+            self.update_interval = adaptive.update(
+                self.ptp_tracker.get_received_packet_count(),
+                self.last_update.elapsed(),
+                backlogged,
+            );
+        }
         self.last_update = Instant::now();
 
         Ok(())
     }
 
+    /// Follow the Sync count of the main PTT
+    fn update_heartbeat(&mut self) {
+        let ptt = self
+            .ptp_tracker
+            .get_main_ptt()
+            .and_then(|(ptt, _)| Some((ptt, self.ptp_tracker.get_host(&ptt)?.sync_count)));
+        self.heartbeat.update(ptt, Instant::now());
+    }
+
     /// Helper method to update the selected host ID and reset packet scroll offset
     fn update_selected_host(&mut self, index: usize) {
         // Get the host clock identity based on current view mode
@@ -733,6 +2599,7 @@ impl App {
         if host_changed {
             self.packet_scroll_offset = 0;
             self.host_details_scroll_offset = 0;
+            self.packet_interface_filter = None;
             self.host_selection_changed = true;
             self.packet_selection_changed = true;
         }
@@ -753,51 +2620,19 @@ impl App {
 
     /// Get the clock identity of the host at the given index in tree view
     fn get_tree_host_clock_identity_at_index(&self, index: usize) -> Option<ClockIdentity> {
-        let tree_nodes = self.get_hosts_tree();
-        let mut current_index = 0;
-
-        fn find_at_index(
-            nodes: &[TreeNode],
-            target_index: usize,
-            current_index: &mut usize,
-        ) -> Option<ClockIdentity> {
-            for node in nodes {
-                if *current_index == target_index {
-                    return Some(node.host.clock_identity);
-                }
-                *current_index += 1;
-
-                if let Some(result) = find_at_index(&node.children, target_index, current_index) {
-                    return Some(result);
-                }
-            }
-            None
-        }
-
-        find_at_index(&tree_nodes, index, &mut current_index)
+        self.tree_rows.get(index).map(|row| row.clock_identity)
     }
 
     /// Get the count of items in the current view (tree or flat)
     fn get_tree_item_count(&self) -> usize {
-        let tree_nodes = self.get_hosts_tree();
-
-        fn count_nodes(nodes: &[TreeNode]) -> usize {
-            let mut count = 0;
-            for node in nodes {
-                count += 1;
-                count += count_nodes(&node.children);
-            }
-            count
-        }
-
-        count_nodes(&tree_nodes)
+        self.tree_rows.len()
     }
 
     fn move_selection_up(&mut self) {
         let total_hosts = if self.tree_view_mode {
             self.get_tree_item_count()
         } else {
-            self.ptp_tracker.get_hosts().len()
+            self.host_order.len()
         };
 
         if total_hosts > 0 && self.selected_index > 0 {
@@ -814,7 +2649,7 @@ impl App {
         let total_hosts = if self.tree_view_mode {
             self.get_tree_item_count()
         } else {
-            self.ptp_tracker.get_hosts().len()
+            self.host_order.len()
         };
 
         if total_hosts > 0 && self.selected_index < total_hosts - 1 {
@@ -834,7 +2669,7 @@ impl App {
         let total_hosts = if self.tree_view_mode {
             self.get_tree_item_count()
         } else {
-            self.ptp_tracker.get_hosts().len()
+            self.host_order.len()
         };
 
         if total_hosts == 0 || visible_height == 0 {
@@ -880,7 +2715,7 @@ impl App {
     }
 
     pub fn move_selection_page_up(&mut self) {
-        if self.ptp_tracker.get_hosts().is_empty() {
+        if self.host_order.is_empty() {
             return;
         }
 
@@ -898,7 +2733,7 @@ impl App {
         let total_hosts = if self.tree_view_mode {
             self.get_tree_item_count()
         } else {
-            self.ptp_tracker.get_hosts().len()
+            self.host_order.len()
         };
 
         if total_hosts == 0 || visible_height == 0 {
@@ -934,7 +2769,7 @@ impl App {
         let total_hosts = if self.tree_view_mode {
             self.get_tree_item_count()
         } else {
-            self.ptp_tracker.get_hosts().len()
+            self.host_order.len()
         };
 
         if total_hosts > 0 {
@@ -947,103 +2782,128 @@ impl App {
         }
     }
 
+    /// Rebuild the sorted host list and tree if hosts or sort settings changed
+    /// since the last call. Sorting thousands of hosts on every frame makes the
+    /// UI sluggish, so rendering only ever reads the cached order.
+    pub fn refresh_host_order(&mut self) {
+        // Frozen hosts don't change, the order is rebuilt when freezing.
+        // Message counts and last seen times change with every packet.
+        let (generation, packets) = match self.split_pause {
+            SplitPause::Table { .. } => (0, 0),
+            _ => (
+                self.ptp_tracker.get_hosts_generation(),
+                match self.sort_column {
+                    SortColumn::MessageCount | SortColumn::LastSeen => {
+                        self.ptp_tracker.get_received_packet_count()
+                    }
+                    _ => 0,
+                },
+            ),
+        };
+        let key = (
+            generation,
+            packets,
+            self.sort_column.clone(),
+            self.sort_ascending,
+            self.host_filter_enabled,
+            self.show_muted,
+            self.vendor_filter.clone(),
+        );
+        if self.host_order_key.as_ref() == Some(&key) {
+            return;
+        }
+
+        let mut hosts = self.table_hosts();
+        hosts.retain(|host| self.is_host_listed(host));
+        hosts.sort_by(|a, b| self.compare_hosts_by_sort_column(a, b));
+        self.host_order = hosts.iter().map(|host| host.clock_identity).collect();
+        self.tree_rows = flatten_tree_nodes(&self.build_hosts_tree());
+        self.host_order_key = Some(key);
+    }
+
     pub fn get_hosts(&self) -> Vec<&PtpHost> {
-        let mut hosts = self.ptp_tracker.get_hosts();
-
-        // Sort hosts based on current sort column
-        hosts.sort_by(|a, b| {
-            let comparison = match self.sort_column {
-                SortColumn::ClockIdentity => a.clock_identity.cmp(&b.clock_identity),
-                SortColumn::IpAddress => {
-                    let a_ip = a.get_primary_ip();
-                    let b_ip = b.get_primary_ip();
-                    a_ip.cmp(&b_ip)
-                }
-                SortColumn::Interface => {
-                    let a_interfaces = a.get_interface_names().join(", ");
-                    let b_interfaces = b.get_interface_names().join(", ");
-                    a_interfaces.cmp(&b_interfaces)
-                }
-                SortColumn::State => {
-                    let a_state_order = match &a.state {
-                        PtpHostState::TimeTransmitter(_) => 0,
-                        PtpHostState::TimeReceiver(_) => 1,
-                        PtpHostState::Listening => 2,
-                    };
-                    let b_state_order = match &b.state {
-                        PtpHostState::TimeTransmitter(_) => 0,
-                        PtpHostState::TimeReceiver(_) => 1,
-                        PtpHostState::Listening => 2,
-                    };
-                    a_state_order.cmp(&b_state_order)
-                }
-                SortColumn::Domain => a.domain_number.cmp(&b.domain_number),
-                SortColumn::Priority => {
-                    let a_priority = match &a.state {
-                        PtpHostState::TimeTransmitter(s) => s.priority1.unwrap_or(255),
-                        _ => 255,
-                    };
-                    let b_priority = match &b.state {
-                        PtpHostState::TimeTransmitter(s) => s.priority1.unwrap_or(255),
-                        _ => 255,
-                    };
-                    a_priority.cmp(&b_priority)
-                }
-                SortColumn::ClockClass => {
-                    let a_clock_class = match &a.state {
-                        PtpHostState::TimeTransmitter(s) => {
-                            s.clock_class.map_or(255, |c| c.class())
-                        }
-                        _ => 255,
-                    };
-                    let b_clock_class = match &b.state {
-                        PtpHostState::TimeTransmitter(s) => {
-                            s.clock_class.map_or(255, |c| c.class())
-                        }
-                        _ => 255,
-                    };
-                    a_clock_class.cmp(&b_clock_class)
-                }
-                SortColumn::SelectedTransmitter => {
-                    let a = match &a.state {
-                        PtpHostState::TimeReceiver(s) => {
-                            s.selected_transmitter_identity.unwrap_or_default()
-                        }
-                        _ => ClockIdentity::default(),
-                    };
+        self.host_order
+            .iter()
+            .filter_map(|clock_identity| self.host(clock_identity))
+            .collect()
+    }
 
-                    let b = match &b.state {
-                        PtpHostState::TimeReceiver(s) => {
-                            s.selected_transmitter_identity.unwrap_or_default()
-                        }
-                        _ => ClockIdentity::default(),
-                    };
+    /// A host as the table and details show it: as it was when the table
+    /// was frozen with 'z', else live
+    pub fn host(&self, clock_identity: &ClockIdentity) -> Option<&PtpHost> {
+        match &self.split_pause {
+            SplitPause::Table { hosts, .. } => hosts.get(clock_identity),
+            _ => self.ptp_tracker.get_host(clock_identity),
+        }
+    }
 
-                    a.cmp(&b)
-                }
-                SortColumn::MessageCount => a
-                    .total_messages_sent_count
-                    .cmp(&b.total_messages_sent_count),
-                SortColumn::LastSeen => a.last_seen.cmp(&b.last_seen),
-                SortColumn::Vendor => {
-                    let a_vendor = a.get_vendor_name().unwrap_or("");
-                    let b_vendor = b.get_vendor_name().unwrap_or("");
-                    a_vendor.cmp(b_vendor)
-                }
-            };
+    /// All hosts the table is built from, transmitters first
+    fn table_hosts(&self) -> Vec<&PtpHost> {
+        match &self.split_pause {
+            SplitPause::Table { hosts, .. } => {
+                let mut hosts: Vec<&PtpHost> = hosts.values().collect();
+                hosts.sort_by_key(|host| !host.is_transmitter());
+                hosts
+            }
+            _ => self.ptp_tracker.get_hosts(),
+        }
+    }
 
-            if self.sort_ascending {
-                comparison
-            } else {
-                comparison.reverse()
+    /// Freeze the host table, then the packet history instead, then neither
+    fn cycle_split_pause(&mut self) {
+        self.split_pause = match self.split_pause {
+            SplitPause::Off => SplitPause::Table {
+                hosts: self
+                    .ptp_tracker
+                    .get_hosts()
+                    .into_iter()
+                    .map(|host| (host.clock_identity, host.clone()))
+                    .collect(),
+                at: self.clock().now(),
+            },
+            SplitPause::Table { .. } => SplitPause::Packets {
+                host: self.selected_host_id,
+                at: self.clock().now(),
+                packets: self
+                    .selected_host_id
+                    .and_then(|id| self.ptp_tracker.get_host_packet_history(id))
+                    .unwrap_or_default(),
+            },
+            SplitPause::Packets { .. } => SplitPause::Off,
+        };
+        self.host_order_key = None;
+        self.refresh_host_order();
+        self.restore_host_selection();
+        let status = match self.split_pause {
+            SplitPause::Off => "Host table and packet history live",
+            SplitPause::Table { .. } => "Host table frozen, packet history live ('z' again swaps)",
+            SplitPause::Packets { .. } => {
+                "Packet history frozen, host table live ('z' again resumes)"
             }
-        });
+        };
+        self.set_status(status.to_string());
+    }
 
-        hosts
+    /// Clock identities of the hosts in the order they are listed (tree or flat)
+    pub fn get_displayed_host_ids(&self) -> Vec<ClockIdentity> {
+        if self.tree_view_mode {
+            self.tree_rows
+                .iter()
+                .map(|row| row.clock_identity)
+                .collect()
+        } else {
+            self.host_order.clone()
+        }
     }
 
-    pub fn get_hosts_tree(&self) -> Vec<TreeNode> {
-        let hosts = self.ptp_tracker.get_hosts();
+    /// Tree view rows in display order
+    pub fn get_tree_rows(&self) -> &[TreeRow] {
+        &self.tree_rows
+    }
+
+    fn build_hosts_tree(&self) -> Vec<TreeNode> {
+        let mut hosts = self.table_hosts();
+        hosts.retain(|host| self.is_host_listed(host));
         let mut tree_nodes = Vec::new();
         let mut processed = std::collections::HashSet::new();
 
@@ -1112,7 +2972,7 @@ impl App {
         for &host_idx in &orphaned_indices {
             let host = hosts[host_idx];
             tree_nodes.push(TreeNode {
-                host: (*host).clone(),
+                clock_identity: host.clock_identity,
                 children: Vec::new(),
                 depth: 0,
                 is_primary_transmitter: matches!(host.state, PtpHostState::TimeTransmitter(_)),
@@ -1149,7 +3009,20 @@ impl App {
                 };
                 a_state_order.cmp(&b_state_order)
             }
-            SortColumn::Domain => a.domain_number.cmp(&b.domain_number),
+            // Unclassified hosts sort last
+            SortColumn::Role => {
+                let a_role = a.role_evidence.role().map_or(u8::MAX, |role| role as u8);
+                let b_role = b.role_evidence.role().map_or(u8::MAX, |role| role as u8);
+                a_role.cmp(&b_role)
+            }
+            // Hosts without a TTL (layer 2, pcap without IP) sort last
+            SortColumn::Hops => {
+                let a_hops = a.hop_distance.current().map_or(u16::MAX, u16::from);
+                let b_hops = b.hop_distance.current().map_or(u16::MAX, u16::from);
+                a_hops.cmp(&b_hops)
+            }
+            SortColumn::Domain => (a.domain_number, a.sdo_id).cmp(&(b.domain_number, b.sdo_id)),
+            SortColumn::DelayMechanism => a.delay_mechanism().cmp(&b.delay_mechanism()),
             SortColumn::Priority => {
                 let a_priority = match &a.state {
                     PtpHostState::TimeTransmitter(s) => s.priority1.unwrap_or(255),
@@ -1193,6 +3066,12 @@ impl App {
                 .total_messages_sent_count
                 .cmp(&b.total_messages_sent_count),
             SortColumn::LastSeen => a.last_seen.cmp(&b.last_seen),
+            // Hosts without a score yet sort after the worst offenders
+            SortColumn::Conformance => {
+                let a_score = a.conformance.score().map_or(u16::MAX, u16::from);
+                let b_score = b.conformance.score().map_or(u16::MAX, u16::from);
+                a_score.cmp(&b_score)
+            }
             SortColumn::Vendor => {
                 let a_vendor = a.get_vendor_name().unwrap_or("");
                 let b_vendor = b.get_vendor_name().unwrap_or("");
@@ -1250,7 +3129,7 @@ impl App {
         }
 
         TreeNode {
-            host: (*host).clone(),
+            clock_identity: host.clock_identity,
             children,
             depth,
             is_primary_transmitter,
@@ -1284,47 +3163,155 @@ impl App {
         self.sort_ascending
     }
 
-    pub fn get_packet_history(&self) -> Vec<ParsedPacket> {
+    pub fn get_packet_history(&self) -> Vec<Arc<ParsedPacket>> {
         // Return packets from the currently selected host
-        if let Some(ref selected_host_id) = self.selected_host_id
-            && let Some(history) = self.ptp_tracker.get_host_packet_history(*selected_host_id)
-        {
+        let history = match &self.split_pause {
+            SplitPause::Packets { host, packets, .. } if *host == self.selected_host_id => {
+                Some(packets.clone())
+            }
+            SplitPause::Packets { at, .. } => self.selected_host_id.and_then(|id| {
+                let mut history = self.ptp_tracker.get_host_packet_history(id)?;
+                history.retain(|packet| packet.raw.timestamp <= *at);
+                Some(history)
+            }),
+            _ => self
+                .selected_host_id
+                .and_then(|id| self.ptp_tracker.get_host_packet_history(id)),
+        };
+        if let Some(mut history) = history {
+            if let Some(interface) = &self.packet_interface_filter {
+                history.retain(|packet| &packet.raw.interface_name == interface);
+            }
+            if let Some(since) = self.time_range_start() {
+                history.retain(|packet| packet.raw.timestamp >= since);
+            }
             return history;
         }
 
         Vec::new()
     }
 
-    fn find_host_index(&self, clock_identity: ClockIdentity) -> Option<usize> {
-        if self.tree_view_mode {
-            // In tree mode, search through tree structure
-            let tree_nodes = self.get_hosts_tree();
-            let mut current_index = 0;
-
-            fn find_in_tree(
-                nodes: &[TreeNode],
-                target_id: ClockIdentity,
-                current_index: &mut usize,
-            ) -> Option<usize> {
-                for node in nodes {
-                    if node.host.clock_identity == target_id {
-                        return Some(*current_index);
-                    }
-                    *current_index += 1;
+    /// Interfaces the selected host's packets arrived on
+    pub fn get_packet_interfaces(&self) -> Vec<String> {
+        self.selected_host_id
+            .and_then(|id| self.ptp_tracker.get_host(&id))
+            .map(|host| host.get_packet_interfaces())
+            .unwrap_or_default()
+    }
 
-                    if let Some(result) = find_in_tree(&node.children, target_id, current_index) {
-                        return Some(result);
-                    }
+    /// Step the packet history through the interfaces the selected host's
+    /// packets arrived on, then back to all of them
+    fn cycle_packet_interface_filter(&mut self) {
+        let interfaces = self.get_packet_interfaces();
+        if self.packet_interface_filter.is_none() && interfaces.len() < 2 {
+            self.set_status("Packets of this host arrive on a single interface".to_string());
+            return;
+        }
+
+        let next = match &self.packet_interface_filter {
+            None => 0,
+            Some(current) => interfaces
+                .iter()
+                .position(|interface| interface == current)
+                .map_or(interfaces.len(), |index| index + 1),
+        };
+        self.packet_interface_filter = interfaces.get(next).cloned();
+
+        let packet_count = self.get_packet_history().len();
+        self.selected_packet_index = packet_count.saturating_sub(1);
+        self.packet_selection_changed = true;
+        let message = match &self.packet_interface_filter {
+            Some(interface) => format!("Packet history: only {}", interface),
+            None => "Packet history: all interfaces".to_string(),
+        };
+        self.set_status(message);
+    }
+
+    /// Start of the selected time range, None for the entire session
+    pub fn time_range_start(&self) -> Option<SystemTime> {
+        self.time_range.start(self.clock().now())
+    }
+
+    /// Start of the time range message counts cover, None for lifetime
+    /// totals. In lite mode there are only lifetime totals.
+    pub fn message_counts_start(&self) -> Option<SystemTime> {
+        self.time_range_start()
+            .filter(|_| !self.ptp_tracker.is_lite())
+    }
+
+    fn cycle_time_range(&mut self) {
+        self.time_range = self.time_range.next();
+        let packet_count = self.get_packet_history().len();
+        self.selected_packet_index = packet_count.saturating_sub(1);
+        self.packet_selection_changed = true;
+        let message = if self.ptp_tracker.is_lite() && self.time_range != TimeRange::Session {
+            format!(
+                "Statistics: {} (host counts only, --lite keeps no message counts)",
+                self.time_range.label()
+            )
+        } else {
+            format!("Statistics and packet history: {}", self.time_range.label())
+        };
+        self.set_status(message);
+    }
+
+    fn select_host(&mut self, clock_identity: ClockIdentity) -> bool {
+        let Some(index) = self.find_host_index(clock_identity) else {
+            return false;
+        };
+        self.selected_index = index;
+        self.update_selected_host(index);
+        self.host_selection_changed = true;
+        true
+    }
+
+    /// Select the transmitter the selected receiver follows, remembering the
+    /// receiver for Backspace. On a host without one, go back instead.
+    fn jump_to_selected_transmitter(&mut self) {
+        let Some(host) = self
+            .selected_host_id
+            .and_then(|id| self.ptp_tracker.get_host(&id))
+        else {
+            return;
+        };
+        let transmitter = match &host.state {
+            PtpHostState::TimeReceiver(state) => state.selected_transmitter_identity,
+            _ => None,
+        };
+        let from = host.clock_identity;
+
+        match transmitter {
+            Some(transmitter) if self.select_host(transmitter) => {
+                if self.host_breadcrumbs.len() == MAX_BREADCRUMBS {
+                    self.host_breadcrumbs.remove(0);
                 }
-                None
+                self.host_breadcrumbs.push(from);
+            }
+            Some(transmitter) => {
+                self.set_status(format!("Transmitter {} is not listed", transmitter));
             }
+            None if !self.host_breadcrumbs.is_empty() => self.jump_back(),
+            None => self.set_status(format!("{} has no selected transmitter", from)),
+        }
+    }
 
-            find_in_tree(&tree_nodes, clock_identity, &mut current_index)
-        } else {
-            // In flat mode, search through flat host list
-            self.get_hosts()
+    /// Return to the host left by the last jump to a transmitter
+    fn jump_back(&mut self) {
+        let Some(previous) = self.host_breadcrumbs.pop() else {
+            return;
+        };
+        if !self.select_host(previous) {
+            self.set_status(format!("{} is no longer listed", previous));
+        }
+    }
+
+    fn find_host_index(&self, clock_identity: ClockIdentity) -> Option<usize> {
+        if self.tree_view_mode {
+            self.tree_rows
                 .iter()
-                .position(|host| host.clock_identity == clock_identity)
+                .position(|row| row.clock_identity == clock_identity)
+        } else {
+            self.host_order.iter().position(|id| *id == clock_identity)
         }
     }
 
@@ -1332,7 +3319,7 @@ impl App {
         if self.tree_view_mode {
             self.get_tree_item_count()
         } else {
-            self.ptp_tracker.get_hosts().len()
+            self.host_order.len()
         }
     }
 
@@ -1352,6 +3339,17 @@ impl App {
     }
 
     fn restore_host_selection(&mut self) {
+        self.refresh_host_order();
+
+        if let Some(pending) = self.pending_host_selection
+            && let Some(found_index) = self.find_host_index(pending)
+        {
+            self.pending_host_selection = None;
+            self.selected_index = found_index;
+            self.update_selected_host(found_index);
+            return;
+        }
+
         // If we have a stored host ID, try to find it in the current list
         if let Some(ref stored_host_id) = self.selected_host_id
             && let Some(found_index) = self.find_host_index(*stored_host_id)
@@ -1482,7 +3480,7 @@ impl App {
         self.visible_packet_height = height;
     }
 
-    pub fn get_selected_packet(&self) -> Option<ParsedPacket> {
+    pub fn get_selected_packet(&self) -> Option<Arc<ParsedPacket>> {
         let packets = self.get_packet_history();
         if self.selected_packet_index < packets.len() {
             Some(packets[self.selected_packet_index].clone())
@@ -1491,12 +3489,111 @@ impl App {
         }
     }
 
-    pub fn get_modal_packet(&self) -> Option<&ParsedPacket> {
+    /// Show the previous/next packet of the modal packet's message exchange in the modal
+    fn step_modal_exchange(&mut self, step: ExchangeStep) {
+        let Some(packet) = &self.modal_packet else {
+            return;
+        };
+        let Some(related) = self.ptp_tracker.find_exchange_packet(packet, step) else {
+            return;
+        };
+
+        // Keep the packet history selection in sync if the packet is listed there
+        if let Some(index) = self
+            .get_packet_history()
+            .iter()
+            .position(|p| std::sync::Arc::ptr_eq(&p.raw, &related.raw))
+        {
+            self.selected_packet_index = index;
+            self.packet_selection_changed = true;
+            self.auto_scroll_packets = false;
+        }
+
+        self.modal_packet = Some(related);
+        self.modal_scroll_offset = 0;
+    }
+
+    pub fn get_modal_packet(&self) -> Option<&Arc<ParsedPacket>> {
         self.modal_packet.as_ref()
     }
 
     pub fn get_reference_timestamp(&self) -> Option<std::time::SystemTime> {
-        self.ptp_tracker.raw_socket_receiver.get_last_timestamp()
+        self.ptp_tracker.packet_source.reference_time()
+    }
+
+    /// Clock of the view: standing at the last packet of a pcap file, or
+    /// where 'p' paused it, else the system clock
+    pub fn clock(&self) -> Clock {
+        match (self.get_reference_timestamp(), self.paused) {
+            (Some(end), _) => Clock::Frozen(end),
+            (None, Some(at)) => Clock::Frozen(at),
+            (None, None) => Clock::Live,
+        }
+    }
+
+    /// Clock of the host table and details, stopped with them by 'z'
+    pub fn host_clock(&self) -> Clock {
+        match &self.split_pause {
+            SplitPause::Table { at, .. } => Clock::Frozen(*at),
+            _ => self.clock(),
+        }
+    }
+
+    /// Clock of the packet history and details, stopped with them by 'z'
+    pub fn packet_clock(&self) -> Clock {
+        match &self.split_pause {
+            SplitPause::Packets { at, .. } => Clock::Frozen(*at),
+            _ => self.clock(),
+        }
+    }
+
+    /// Switch port the host is plugged into, by its MAC addresses
+    pub fn switch_port(&self, host: &PtpHost) -> Option<&SwitchPort> {
+        host.mac_addresses
+            .iter()
+            .find_map(|mac| self.switch_ports.get(mac))
+    }
+
+    /// Connection state of the agents when viewing remote sites
+    pub fn remote_agents(&self) -> Option<&crate::remote::AgentStatuses> {
+        self.ptp_tracker.packet_source.remote_agents()
+    }
+
+    /// What this session saw and wrote, printed on exit
+    pub fn session_summary(&self) -> SessionSummary {
+        let tracker = &self.ptp_tracker;
+        let alerts = tracker
+            .get_alert_counts()
+            .iter()
+            .map(|(kind, count)| (kind.to_string(), *count))
+            .collect();
+        let mut files = self.written_files.clone();
+        files.extend(tracker.get_written_files());
+        if let Some(csv_tail) = &self.csv_tail {
+            files.extend(csv_tail.files());
+        }
+        SessionSummary {
+            duration: self.session_started.elapsed(),
+            packets: tracker.get_received_packet_count(),
+            dropped: vec![
+                ("rejected as malformed", tracker.get_rejected_packet_count()),
+                ("duplicates suppressed", tracker.get_duplicate_count()),
+            ],
+            hosts: tracker.get_discovered_host_count(),
+            gm_changes: Some(
+                tracker
+                    .get_alert_counts()
+                    .get(&AlertKind::PttChanged)
+                    .copied()
+                    .unwrap_or(0),
+            ),
+            alerts: Some(alerts),
+            files,
+        }
+    }
+
+    pub fn synthetic_stats(&self) -> Option<&crate::synth::SynthStats> {
+        self.ptp_tracker.packet_source.synthetic_stats()
     }
 
     fn scroll_modal_up(&mut self) {
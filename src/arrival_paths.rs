@@ -0,0 +1,161 @@
+//! Message counts per arrival path of a host
+//!
+//! A grandmaster or boundary clock is often dual-homed, sending the same
+//! messages from two IP addresses or into two VLANs, and seen on two capture
+//! interfaces. When one leg dies its totals still grow at half the rate and
+//! look healthy. Counting by the (source IP, interface, VLAN) a message
+//! arrived on shows the silent leg.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    net::IpAddr,
+    time::{Duration, SystemTime},
+};
+
+use crate::{source::RawPacket, types::PtpMessageType};
+
+/// A path silent for this long while another one of the host is not is
+/// flagged
+pub const SILENT_AFTER: Duration = Duration::from_secs(5);
+
+/// Where a message arrived from: its source IP (none for Layer 2 transport),
+/// the capture interface and the VLAN
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArrivalPath {
+    pub ip: Option<IpAddr>,
+    pub interface: String,
+    pub vlan_id: Option<u16>,
+}
+
+impl Display for ArrivalPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.ip {
+            Some(ip) => write!(f, "{} on {}", ip, self.interface)?,
+            None => write!(f, "L2 on {}", self.interface)?,
+        }
+        if let Some(vlan_id) = self.vlan_id {
+            write!(f, " vlan {}", vlan_id)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathCounts {
+    pub announce: u32,
+    pub sync: u32,
+    pub follow_up: u32,
+    /// Delay_Req and Delay_Resp
+    pub delay: u32,
+    /// Pdelay_Req, Pdelay_Resp and Pdelay_Resp_Follow_Up
+    pub pdelay: u32,
+    /// Signaling and management
+    pub other: u32,
+    pub last_seen: SystemTime,
+}
+
+impl PathCounts {
+    fn new(last_seen: SystemTime) -> Self {
+        Self {
+            announce: 0,
+            sync: 0,
+            follow_up: 0,
+            delay: 0,
+            pdelay: 0,
+            other: 0,
+            last_seen,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ArrivalPaths {
+    paths: BTreeMap<ArrivalPath, PathCounts>,
+}
+
+impl ArrivalPaths {
+    pub fn record(&mut self, packet: &RawPacket, message_type: PtpMessageType) {
+        let path = ArrivalPath {
+            ip: packet.source_addr.map(|addr| addr.ip()),
+            interface: packet.interface_name.clone(),
+            vlan_id: packet.vlan_id,
+        };
+        let counts = self
+            .paths
+            .entry(path)
+            .or_insert_with(|| PathCounts::new(packet.timestamp));
+        counts.last_seen = counts.last_seen.max(packet.timestamp);
+        let counter = match message_type {
+            PtpMessageType::Announce => &mut counts.announce,
+            PtpMessageType::Sync => &mut counts.sync,
+            PtpMessageType::FollowUp => &mut counts.follow_up,
+            PtpMessageType::DelayReq | PtpMessageType::DelayResp => &mut counts.delay,
+            PtpMessageType::PDelayReq
+            | PtpMessageType::PDelayResp
+            | PtpMessageType::PDelayRespFollowUp => &mut counts.pdelay,
+            _ => &mut counts.other,
+        };
+        *counter += 1;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ArrivalPath, &PathCounts)> {
+        self.paths.iter()
+    }
+
+    /// Whether a path went silent while the host is still heard on another
+    pub fn is_silent(&self, path: &ArrivalPath) -> bool {
+        let Some(counts) = self.paths.get(path) else {
+            return false;
+        };
+        self.paths.values().any(|other| {
+            other
+                .last_seen
+                .duration_since(counts.last_seen)
+                .is_ok_and(|gap| gap > SILENT_AFTER)
+        })
+    }
+}
+
+#[test]
+fn test_arrival_paths() {
+    use std::net::SocketAddr;
+
+    let packet = |ip: [u8; 4], interface: &str, vlan_id: Option<u16>, seconds: u64| RawPacket {
+        source_addr: Some(SocketAddr::from((ip, 319))),
+        vlan_id,
+        ..RawPacket::from_ptp_payload(
+            &[0u8; 44],
+            interface,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(seconds),
+        )
+    };
+
+    let mut paths = ArrivalPaths::default();
+    for second in 0..10 {
+        paths.record(
+            &packet([10, 0, 0, 1], "eth0", Some(10), second),
+            PtpMessageType::Sync,
+        );
+        // The secondary leg dies after 2 seconds
+        if second < 2 {
+            paths.record(
+                &packet([10, 1, 0, 1], "eth1", Some(20), second),
+                PtpMessageType::Sync,
+            );
+        }
+    }
+    paths.record(
+        &packet([10, 0, 0, 1], "eth0", Some(10), 9),
+        PtpMessageType::Announce,
+    );
+
+    assert_eq!(paths.iter().count(), 2);
+    let (primary, counts) = paths.iter().next().unwrap();
+    assert_eq!(primary.to_string(), "10.0.0.1 on eth0 vlan 10");
+    assert_eq!((counts.sync, counts.announce), (10, 1));
+    assert!(!paths.is_silent(primary));
+    let (secondary, counts) = paths.iter().nth(1).unwrap();
+    assert_eq!(counts.sync, 2);
+    assert!(paths.is_silent(secondary));
+}
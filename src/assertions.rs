@@ -0,0 +1,397 @@
+//! Scripted expectations on a capture ("ptp-trace assert")
+//!
+//! Changes to a timing network are easier to trust when a capture taken
+//! afterwards can be checked automatically, e.g. in CI or after every
+//! maintenance window. A rules file lists what must hold for the whole
+//! capture, each rule is evaluated on the packets with their capture times
+//! and the report uses the doctor's checklist format:
+//!
+//! ```yaml
+//! rules:
+//!   - rule: grandmasters
+//!     domain: 0
+//!     count: 1
+//!   - rule: sequence_gaps
+//!     max: 1
+//!   - rule: receivers_lock
+//!     within: 10
+//! ```
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    doctor::{Check, CheckStatus},
+    source,
+    types::{ClockIdentity, PtpMessage, PtpMessageType, Truncation},
+};
+
+/// Offenders listed in the report of a failed rule
+const MAX_LISTED: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Rule {
+    /// Exactly count distinct grandmasters are announced, in the domain or
+    /// in each domain seen
+    Grandmasters { domain: Option<u8>, count: usize },
+    /// Sync and Announce sequence ids of each sender never advance by more
+    /// than max, 1 allows no lost messages
+    SequenceGaps { domain: Option<u8>, max: u16 },
+    /// Every host sending Delay_Req gets its first Delay_Resp within this
+    /// many seconds of the start of the capture
+    ReceiversLock { domain: Option<u8>, within: f64 },
+}
+
+impl Rule {
+    fn name(&self) -> String {
+        let domain = |domain: &Option<u8>| match domain {
+            Some(domain) => format!(" in domain {}", domain),
+            None => String::new(),
+        };
+        match self {
+            Rule::Grandmasters { domain: d, count } => {
+                format!("{} grandmaster(s){}", count, domain(d))
+            }
+            Rule::SequenceGaps { domain: d, max } => {
+                format!("Sequence gaps <= {}{}", max, domain(d))
+            }
+            Rule::ReceiversLock { domain: d, within } => {
+                format!("Receivers lock in {}s{}", within, domain(d))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    pub rules: Vec<Rule>,
+}
+
+impl Rules {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read rules file {}", path.display()))?;
+        serde_yaml::from_str(&text)
+            .with_context(|| format!("Invalid rules file {}", path.display()))
+    }
+}
+
+/// What the rules are evaluated on, collected in one pass over the capture
+#[derive(Debug, Default)]
+pub struct Observations {
+    start: Option<SystemTime>,
+    /// Grandmaster identities announced per domain
+    grandmasters: BTreeMap<u8, BTreeSet<ClockIdentity>>,
+    /// Last sequence id and the largest advance per sender, message type and domain
+    sequences: BTreeMap<(ClockIdentity, u16, PtpMessageType, u8), (u16, u16)>,
+    /// First Delay_Req of each receiver and its first Delay_Resp, by domain
+    receivers: BTreeMap<(ClockIdentity, u8), Option<SystemTime>>,
+}
+
+impl Observations {
+    pub fn record(&mut self, message: &PtpMessage, at: SystemTime) {
+        let start = *self.start.get_or_insert(at);
+        self.start = Some(start.min(at));
+
+        let header = message.header();
+        let domain = header.domain_number;
+        match message {
+            PtpMessage::Announce(announce) => {
+                self.grandmasters
+                    .entry(domain)
+                    .or_default()
+                    .insert(announce.ptt_identity);
+            }
+            PtpMessage::DelayReq(_) => {
+                self.receivers
+                    .entry((header.source_port_identity.clock_identity, domain))
+                    .or_default();
+            }
+            PtpMessage::DelayResp(response) => {
+                if let Some(locked) = self
+                    .receivers
+                    .get_mut(&(response.requesting_port_identity.clock_identity, domain))
+                {
+                    locked.get_or_insert(at);
+                }
+            }
+            _ => {}
+        }
+
+        if matches!(
+            header.message_type,
+            PtpMessageType::Sync | PtpMessageType::Announce
+        ) {
+            let key = (
+                header.source_port_identity.clock_identity,
+                header.source_port_identity.port_number,
+                header.message_type,
+                domain,
+            );
+            let sequence_id = header.sequence_id;
+            self.sequences
+                .entry(key)
+                .and_modify(|(last, max_advance)| {
+                    let advance = sequence_id.wrapping_sub(*last);
+                    // Repeated or reordered messages are not gaps
+                    if advance < u16::MAX / 2 {
+                        *max_advance = (*max_advance).max(advance);
+                    }
+                    *last = sequence_id;
+                })
+                .or_insert((sequence_id, 0));
+        }
+    }
+
+    fn in_domain(rule_domain: Option<u8>, domain: u8) -> bool {
+        rule_domain.is_none_or(|rule_domain| rule_domain == domain)
+    }
+
+    fn check_grandmasters(&self, rule_domain: Option<u8>, count: usize) -> Result<String> {
+        let mut domains: Vec<(u8, usize)> = self
+            .grandmasters
+            .iter()
+            .filter(|(domain, _)| Self::in_domain(rule_domain, **domain))
+            .map(|(domain, gms)| (*domain, gms.len()))
+            .collect();
+        if let Some(domain) = rule_domain
+            && domains.is_empty()
+        {
+            domains.push((domain, 0));
+        }
+        let wrong: Vec<String> = domains
+            .iter()
+            .filter(|(_, found)| *found != count)
+            .map(|(domain, _)| {
+                let gms = self
+                    .grandmasters
+                    .get(domain)
+                    .map(|gms| {
+                        gms.iter()
+                            .map(|gm| gm.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    })
+                    .unwrap_or_else(|| "none".to_string());
+                format!("domain {}: {}", domain, gms)
+            })
+            .collect();
+        if !wrong.is_empty() {
+            bail!("found {}", wrong.join("; "));
+        }
+        if domains.is_empty() {
+            bail!("no Announce in the capture");
+        }
+        Ok(format!("{} domain(s) checked", domains.len()))
+    }
+
+    fn check_sequence_gaps(&self, rule_domain: Option<u8>, max: u16) -> Result<String> {
+        let checked: Vec<_> = self
+            .sequences
+            .iter()
+            .filter(|((_, _, _, domain), _)| Self::in_domain(rule_domain, *domain))
+            .collect();
+        let mut gaps: Vec<_> = checked
+            .iter()
+            .filter(|(_, (_, max_advance))| *max_advance > max)
+            .collect();
+        if !gaps.is_empty() {
+            gaps.sort_by_key(|(_, (_, max_advance))| std::cmp::Reverse(*max_advance));
+            let listed: Vec<String> = gaps
+                .iter()
+                .take(MAX_LISTED)
+                .map(|((clock, port, message_type, domain), (_, max_advance))| {
+                    format!(
+                        "{} port {} {} domain {} jumped by {}",
+                        clock, port, message_type, domain, max_advance
+                    )
+                })
+                .collect();
+            bail!("{} sender(s): {}", gaps.len(), listed.join("; "));
+        }
+        Ok(format!(
+            "{} Sync/Announce sequence(s) checked",
+            checked.len()
+        ))
+    }
+
+    fn check_receivers_lock(&self, rule_domain: Option<u8>, within: f64) -> Result<String> {
+        let start = self.start.unwrap_or(SystemTime::UNIX_EPOCH);
+        let limit = Duration::from_secs_f64(within.max(0.0));
+        let receivers: Vec<_> = self
+            .receivers
+            .iter()
+            .filter(|((_, domain), _)| Self::in_domain(rule_domain, *domain))
+            .collect();
+        let late: Vec<String> = receivers
+            .iter()
+            .filter_map(|((clock, domain), locked)| match locked {
+                None => Some(format!("{} (domain {}) never answered", clock, domain)),
+                Some(locked) => {
+                    let after = locked.duration_since(start).unwrap_or_default();
+                    (after > limit).then(|| {
+                        format!(
+                            "{} (domain {}) after {:.1}s",
+                            clock,
+                            domain,
+                            after.as_secs_f64()
+                        )
+                    })
+                }
+            })
+            .collect();
+        if !late.is_empty() {
+            let listed: Vec<_> = late.iter().take(MAX_LISTED).cloned().collect();
+            bail!("{} receiver(s): {}", late.len(), listed.join("; "));
+        }
+        if receivers.is_empty() {
+            bail!("no Delay_Req in the capture");
+        }
+        Ok(format!("{} receiver(s) locked", receivers.len()))
+    }
+
+    pub fn check(&self, rule: &Rule) -> Check {
+        let result = match rule {
+            Rule::Grandmasters { domain, count } => self.check_grandmasters(*domain, *count),
+            Rule::SequenceGaps { domain, max } => self.check_sequence_gaps(*domain, *max),
+            Rule::ReceiversLock { domain, within } => self.check_receivers_lock(*domain, *within),
+        };
+        match result {
+            Ok(detail) => Check::new(rule.name(), CheckStatus::Pass, detail),
+            Err(e) => Check::new(rule.name(), CheckStatus::Fail, e.to_string()),
+        }
+    }
+}
+
+/// Evaluate the rules on a capture and print the report, failing if any rule
+/// does not hold
+pub fn run(pcap_path: &str, rules_path: &Path) -> Result<()> {
+    let rules = Rules::load(rules_path)?;
+    let mut observations = Observations::default();
+    let mut packets = 0;
+    source::for_each_pcap_frame(pcap_path, |capture_time, data, _| {
+        let Some(raw_packet) = source::process_ethernet_packet(data, "pcap") else {
+            return;
+        };
+        // A body cut short is zero filled, nothing to go by
+        if let Ok(message) = PtpMessage::try_from(raw_packet.ptp_payload())
+            && Truncation::of(
+                message.header().message_type,
+                raw_packet.ptp_payload().len(),
+            )
+            .is_none()
+        {
+            packets += 1;
+            observations.record(&message, capture_time);
+        }
+    })
+    .with_context(|| format!("Failed to read {}", pcap_path))?;
+
+    println!("ptp-trace assert: {} PTP packets in {}", packets, pcap_path);
+    let checks: Vec<Check> = rules
+        .rules
+        .iter()
+        .map(|rule| observations.check(rule))
+        .collect();
+    for check in &checks {
+        println!("{}", check);
+    }
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .count();
+    if failed > 0 {
+        bail!("{} of {} rules failed", failed, checks.len());
+    }
+    println!("{} rules passed", checks.len());
+    Ok(())
+}
+
+#[test]
+fn test_assertions() {
+    let rules: Rules = serde_yaml::from_str(
+        "rules:\n\
+         - rule: grandmasters\n  domain: 0\n  count: 1\n\
+         - rule: sequence_gaps\n  max: 1\n\
+         - rule: receivers_lock\n  within: 10",
+    )
+    .unwrap();
+    assert_eq!(
+        rules.rules[0],
+        Rule::Grandmasters {
+            domain: Some(0),
+            count: 1
+        }
+    );
+    assert!(serde_yaml::from_str::<Rules>("rules:\n- rule: unknown").is_err());
+
+    let message = |message_type: u8, domain: u8, sequence_id: u16, clock: u8| {
+        let mut data = [0u8; 64];
+        data[0] = message_type;
+        data[1] = 0x02;
+        data[2..4].copy_from_slice(&64u16.to_be_bytes());
+        data[4] = domain;
+        data[27] = clock;
+        data[30..32].copy_from_slice(&sequence_id.to_be_bytes());
+        // grandmasterIdentity of an Announce, requestingPortIdentity of a Delay_Resp
+        data[53..61].copy_from_slice(&[0, 0, 0, 0, 0, 0, 0, clock]);
+        data[44..52].copy_from_slice(&[0, 0, 0, 0, 0, 0, 0, 2]);
+        PtpMessage::try_from(&data[..]).unwrap()
+    };
+    let at = |seconds: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+
+    let mut observations = Observations::default();
+    // GM 1 with a lost Sync (4), receiver 2 answered after 12 s
+    for (i, sequence_id) in [1, 2, 3, 5].into_iter().enumerate() {
+        observations.record(&message(0x0, 0, sequence_id, 1), at(i as u64));
+    }
+    observations.record(&message(0xb, 0, 1, 1), at(0));
+    observations.record(&message(0x1, 0, 1, 2), at(1));
+    observations.record(&message(0x9, 0, 1, 1), at(12));
+
+    let status = |rule: Rule| observations.check(&rule).status;
+    assert_eq!(
+        status(Rule::Grandmasters {
+            domain: Some(0),
+            count: 1
+        }),
+        CheckStatus::Pass
+    );
+    assert_eq!(
+        status(Rule::Grandmasters {
+            domain: Some(1),
+            count: 1
+        }),
+        CheckStatus::Fail
+    );
+    assert_eq!(
+        status(Rule::SequenceGaps {
+            domain: None,
+            max: 1
+        }),
+        CheckStatus::Fail
+    );
+    assert_eq!(
+        status(Rule::SequenceGaps {
+            domain: None,
+            max: 2
+        }),
+        CheckStatus::Pass
+    );
+    let check = observations.check(&Rule::ReceiversLock {
+        domain: None,
+        within: 10.0,
+    });
+    assert_eq!(check.status, CheckStatus::Fail);
+    assert_eq!(
+        check.detail,
+        "1 receiver(s): 00:00:00:00:00:00:00:02 (domain 0) after 12.0s"
+    );
+}
@@ -0,0 +1,312 @@
+//! Host list compared with a previous session ("--baseline")
+//!
+//! An audit repeated every week, or after a maintenance window, is about what
+//! changed since the last one. With --save-session the hosts are written to a
+//! JSON file on exit, and a later run with --baseline FILE compares the hosts
+//! it discovers with that file: new and changed hosts are marked in the host
+//! table, and the baseline pane ('v') lists them together with the hosts that
+//! have not been seen again.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::ptp::{PtpHost, PtpHostState};
+
+/// What a session knew about a host
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostSnapshot {
+    pub state: String,
+    pub domain: Option<u8>,
+    pub ip_addresses: Vec<String>,
+    /// From the clock identity, only kept to tell missing hosts apart
+    pub vendor: Option<String>,
+    pub priority1: Option<u8>,
+    pub priority2: Option<u8>,
+    pub clock_class: Option<u8>,
+    pub selected_transmitter: Option<String>,
+    pub version: Option<String>,
+}
+
+fn show<T: Display>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map_or("-".to_string(), |value| value.to_string())
+}
+
+impl HostSnapshot {
+    pub fn from_host(host: &PtpHost) -> Self {
+        let mut ip_addresses: Vec<String> =
+            host.ip_addresses.keys().map(|ip| ip.to_string()).collect();
+        ip_addresses.sort();
+        let (priority1, priority2, clock_class) = match &host.state {
+            PtpHostState::TimeTransmitter(state) => (
+                state.priority1,
+                state.priority2,
+                state.clock_class.map(|class| class.class()),
+            ),
+            _ => (None, None, None),
+        };
+        let selected_transmitter = match &host.state {
+            PtpHostState::TimeReceiver(state) => state
+                .selected_transmitter_identity
+                .map(|identity| identity.to_string()),
+            _ => None,
+        };
+        Self {
+            state: host.role_short_string().to_string(),
+            domain: host.domain_number,
+            ip_addresses,
+            vendor: host.get_vendor_name().map(String::from),
+            priority1,
+            priority2,
+            clock_class,
+            selected_transmitter,
+            version: host.last_version.map(|version| version.to_string()),
+        }
+    }
+
+    /// What is different now, e.g. "state TT → TR"
+    pub fn changes(&self, now: &HostSnapshot) -> Vec<String> {
+        let mut changes = Vec::new();
+        let mut compare = |name: &str, before: String, after: String| {
+            if before != after {
+                changes.push(format!("{} {} → {}", name, before, after));
+            }
+        };
+        compare("state", self.state.clone(), now.state.clone());
+        compare("domain", show(&self.domain), show(&now.domain));
+        compare("IP", self.ip_list(), now.ip_list());
+        compare("priority1", show(&self.priority1), show(&now.priority1));
+        compare("priority2", show(&self.priority2), show(&now.priority2));
+        compare(
+            "clock class",
+            show(&self.clock_class),
+            show(&now.clock_class),
+        );
+        compare(
+            "transmitter",
+            show(&self.selected_transmitter),
+            show(&now.selected_transmitter),
+        );
+        compare("version", show(&self.version), show(&now.version));
+        changes
+    }
+
+    fn ip_list(&self) -> String {
+        if self.ip_addresses.is_empty() {
+            "-".to_string()
+        } else {
+            self.ip_addresses.join(",")
+        }
+    }
+}
+
+impl Display for HostSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}, domain {}, IP {}",
+            self.state,
+            show(&self.domain),
+            self.ip_list()
+        )?;
+        if let Some(vendor) = &self.vendor {
+            write!(f, ", {}", vendor)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hosts of a session by clock identity, as written by --save-session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionSnapshot {
+    pub saved: Option<SystemTime>,
+    pub hosts: BTreeMap<String, HostSnapshot>,
+}
+
+impl SessionSnapshot {
+    pub fn from_hosts<'a, I>(hosts: I, saved: SystemTime) -> Self
+    where
+        I: IntoIterator<Item = &'a PtpHost>,
+    {
+        Self {
+            saved: Some(saved),
+            hosts: hosts
+                .into_iter()
+                .map(|host| {
+                    (
+                        host.clock_identity.to_string(),
+                        HostSnapshot::from_host(host),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read session file {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid session file {}", path.display()))
+    }
+
+    /// Write the session file, via a temporary file like the state file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temp_path = path.with_extension("tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write session file {}", temp_path.display()))?;
+        std::fs::rename(&temp_path, path)
+            .with_context(|| format!("Failed to write session file {}", path.display()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BaselineStatus {
+    /// Not in the baseline
+    New,
+    /// In the baseline with other values, what changed
+    Changed(Vec<String>),
+    Unchanged,
+}
+
+impl BaselineStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            BaselineStatus::New => "new",
+            BaselineStatus::Changed(_) => "changed",
+            BaselineStatus::Unchanged => "",
+        }
+    }
+}
+
+/// Hosts new, changed and missing against the baseline
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BaselineCounts {
+    pub new: usize,
+    pub changed: usize,
+    pub missing: usize,
+}
+
+/// The previous session the hosts are compared with
+#[derive(Debug, Clone)]
+pub struct Baseline {
+    pub path: PathBuf,
+    pub snapshot: SessionSnapshot,
+}
+
+impl Baseline {
+    pub fn load(path: PathBuf) -> Result<Self> {
+        Ok(Self {
+            snapshot: SessionSnapshot::load(&path)?,
+            path,
+        })
+    }
+
+    pub fn status(&self, host: &PtpHost) -> BaselineStatus {
+        match self.snapshot.hosts.get(&host.clock_identity.to_string()) {
+            None => BaselineStatus::New,
+            Some(before) => match before.changes(&HostSnapshot::from_host(host)) {
+                changes if changes.is_empty() => BaselineStatus::Unchanged,
+                changes => BaselineStatus::Changed(changes),
+            },
+        }
+    }
+
+    /// Hosts of the baseline not seen in this session, by clock identity
+    pub fn missing(&self, hosts: &[&PtpHost]) -> Vec<(&str, &HostSnapshot)> {
+        let seen: HashSet<String> = hosts
+            .iter()
+            .map(|host| host.clock_identity.to_string())
+            .collect();
+        self.snapshot
+            .hosts
+            .iter()
+            .filter(|(clock_identity, _)| !seen.contains(*clock_identity))
+            .map(|(clock_identity, snapshot)| (clock_identity.as_str(), snapshot))
+            .collect()
+    }
+
+    pub fn counts(&self, hosts: &[&PtpHost]) -> BaselineCounts {
+        let mut counts = BaselineCounts {
+            missing: self.missing(hosts).len(),
+            ..Default::default()
+        };
+        for host in hosts {
+            match self.status(host) {
+                BaselineStatus::New => counts.new += 1,
+                BaselineStatus::Changed(_) => counts.changed += 1,
+                BaselineStatus::Unchanged => {}
+            }
+        }
+        counts
+    }
+}
+
+#[test]
+fn test_baseline() {
+    use crate::{ptp::PtpHostStateTimeTransmitter, types::ClockIdentity};
+
+    let host = |last: u8, priority1: u8| {
+        let mut host = PtpHost::new(ClockIdentity {
+            clock_id: [0x00, 0x1b, 0x19, 0xff, 0xfe, 0, 0, last],
+        });
+        host.domain_number = Some(0);
+        host.add_ip_address(
+            format!("10.0.0.{}", last).parse().unwrap(),
+            None,
+            "eth0".to_string(),
+        );
+        host.state = PtpHostState::TimeTransmitter(PtpHostStateTimeTransmitter {
+            priority1: Some(priority1),
+            ..Default::default()
+        });
+        host
+    };
+    let before = [host(1, 128), host(2, 128), host(3, 128)];
+    let snapshot = SessionSnapshot::from_hosts(&before, SystemTime::UNIX_EPOCH);
+    let parsed: SessionSnapshot =
+        serde_json::from_str(&serde_json::to_string_pretty(&snapshot).unwrap()).unwrap();
+    assert_eq!(parsed, snapshot);
+    let baseline = Baseline {
+        path: PathBuf::from("previous-session.json"),
+        snapshot: parsed,
+    };
+
+    // Host 2 lowered its priority1, host 3 is gone and host 4 is new
+    let now = [host(1, 128), host(2, 127), host(4, 128)];
+    let hosts: Vec<&PtpHost> = now.iter().collect();
+    assert_eq!(baseline.status(&now[0]), BaselineStatus::Unchanged);
+    assert_eq!(
+        baseline.status(&now[1]),
+        BaselineStatus::Changed(vec!["priority1 128 → 127".to_string()])
+    );
+    assert_eq!(baseline.status(&now[2]), BaselineStatus::New);
+    let missing = baseline.missing(&hosts);
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].0, "00:1b:19:ff:fe:00:00:03");
+    assert!(
+        missing[0]
+            .1
+            .to_string()
+            .starts_with("TT, domain 0, IP 10.0.0.3")
+    );
+    assert_eq!(
+        baseline.counts(&hosts),
+        BaselineCounts {
+            new: 1,
+            changed: 1,
+            missing: 1,
+        }
+    );
+}
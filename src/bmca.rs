@@ -0,0 +1,392 @@
+//! Best Master Clock Algorithm variants
+//!
+//! PTP profiles disagree on which announced attributes the BMCA compares and
+//! in which order, so the PTT predicted with the default IEEE 1588 rules can be
+//! wrong on other profiles:
+//! - IEEE 1588 default: priority1, clockClass, clockAccuracy,
+//!   offsetScaledLogVariance, priority2, clockIdentity
+//! - gPTP (802.1AS): the same attributes and the grandmasterIdentity
+//!   concatenated into one systemIdentity that is compared as a single
+//!   number, then stepsRemoved and last the sender's own identity. Relays
+//!   forward the grandmaster's attributes, so they tie on systemIdentity and
+//!   the grandmaster itself wins on stepsRemoved 0, where the default rules
+//!   would compare the relays' own identities
+//! - G.8275.1 (telecom, full timing support): priority1 is not used, clockClass
+//!   comes first and a per-port localPriority is compared before clockIdentity
+//!
+//! The profile is selected with --bmca-profile. In auto mode, gPTP instances
+//! (by sdoId) use the gPTP rules and everything else the default rules.
+
+use std::{cmp::Ordering, fmt};
+
+use crate::{
+    ptp::PtpHostStateTimeTransmitter,
+    types::{ClockIdentity, SDO_ID_GPTP},
+};
+
+/// localPriority of ports without a configured value (G.8275.1 default)
+pub const DEFAULT_LOCAL_PRIORITY: u8 = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BmcaAttribute {
+    Priority1,
+    ClockClass,
+    ClockAccuracy,
+    Variance,
+    Priority2,
+    LocalPriority,
+    ClockIdentity,
+    /// grandmasterIdentity announced by the candidate
+    GrandmasterIdentity,
+    StepsRemoved,
+}
+
+impl BmcaAttribute {
+    pub fn label(&self) -> &'static str {
+        match self {
+            BmcaAttribute::Priority1 => "Priority1",
+            BmcaAttribute::ClockClass => "Class",
+            BmcaAttribute::ClockAccuracy => "Accuracy",
+            BmcaAttribute::Variance => "Variance",
+            BmcaAttribute::Priority2 => "Priority2",
+            BmcaAttribute::LocalPriority => "LocalPrio",
+            BmcaAttribute::ClockIdentity => "Identity",
+            BmcaAttribute::GrandmasterIdentity => "GM",
+            BmcaAttribute::StepsRemoved => "Steps",
+        }
+    }
+}
+
+/// A transmitter taking part in the election
+#[derive(Debug, Clone, Copy)]
+pub struct BmcaCandidate<'a> {
+    pub clock_identity: ClockIdentity,
+    pub state: &'a PtpHostStateTimeTransmitter,
+    pub local_priority: u8,
+}
+
+impl BmcaCandidate<'_> {
+    /// Value of an attribute, lower is better. None if it was not announced yet.
+    fn value(&self, attribute: BmcaAttribute) -> Option<u64> {
+        match attribute {
+            BmcaAttribute::Priority1 => self.state.priority1.map(u64::from),
+            BmcaAttribute::ClockClass => self.state.clock_class.map(|c| c.class().into()),
+            BmcaAttribute::ClockAccuracy => self.state.clock_accuracy.map(|a| a.accuracy.into()),
+            BmcaAttribute::Variance => self.state.offset_scaled_log_variance.map(u64::from),
+            BmcaAttribute::Priority2 => self.state.priority2.map(u64::from),
+            BmcaAttribute::LocalPriority => Some(self.local_priority.into()),
+            BmcaAttribute::ClockIdentity => Some(u64::from_be_bytes(self.clock_identity.clock_id)),
+            BmcaAttribute::GrandmasterIdentity => self
+                .state
+                .ptt_identifier
+                .map(|identity| u64::from_be_bytes(identity.clock_id)),
+            BmcaAttribute::StepsRemoved => self.state.steps_removed.map(u64::from),
+        }
+    }
+}
+
+pub trait Bmca {
+    fn name(&self) -> &'static str;
+
+    /// Attributes in the order they are compared
+    fn attributes(&self) -> &'static [BmcaAttribute];
+
+    /// Less if a is the better transmitter. Announced attributes beat missing ones.
+    fn compare(&self, a: &BmcaCandidate, b: &BmcaCandidate) -> Ordering {
+        for &attribute in self.attributes() {
+            let ordering = match (a.value(attribute), b.value(attribute)) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// First attribute that differs between two candidates, i.e. the one that
+    /// decides the comparison
+    fn deciding_attribute(&self, a: &BmcaCandidate, b: &BmcaCandidate) -> Option<BmcaAttribute> {
+        self.attributes()
+            .iter()
+            .copied()
+            .find(|&attribute| a.value(attribute) != b.value(attribute))
+    }
+}
+
+pub struct Ieee1588Bmca;
+
+impl Bmca for Ieee1588Bmca {
+    fn name(&self) -> &'static str {
+        "IEEE 1588"
+    }
+
+    fn attributes(&self) -> &'static [BmcaAttribute] {
+        &[
+            BmcaAttribute::Priority1,
+            BmcaAttribute::ClockClass,
+            BmcaAttribute::ClockAccuracy,
+            BmcaAttribute::Variance,
+            BmcaAttribute::Priority2,
+            BmcaAttribute::ClockIdentity,
+        ]
+    }
+
+    fn compare(&self, a: &BmcaCandidate, b: &BmcaCandidate) -> Ordering {
+        a.state
+            .compare_for_bmca(b.state, a.clock_identity, b.clock_identity)
+    }
+}
+
+pub struct GptpBmca;
+
+impl Bmca for GptpBmca {
+    fn name(&self) -> &'static str {
+        "gPTP"
+    }
+
+    fn attributes(&self) -> &'static [BmcaAttribute] {
+        &[
+            BmcaAttribute::Priority1,
+            BmcaAttribute::ClockClass,
+            BmcaAttribute::ClockAccuracy,
+            BmcaAttribute::Variance,
+            BmcaAttribute::Priority2,
+            BmcaAttribute::GrandmasterIdentity,
+            BmcaAttribute::StepsRemoved,
+            BmcaAttribute::ClockIdentity,
+        ]
+    }
+
+    /// systemIdentity is one number, so a missing field simply has the worst value
+    fn compare(&self, a: &BmcaCandidate, b: &BmcaCandidate) -> Ordering {
+        self.attributes()
+            .iter()
+            .map(|&attribute| {
+                let a = a.value(attribute).unwrap_or(u64::MAX);
+                let b = b.value(attribute).unwrap_or(u64::MAX);
+                a.cmp(&b)
+            })
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+pub struct G8275_1Bmca;
+
+impl Bmca for G8275_1Bmca {
+    fn name(&self) -> &'static str {
+        "G.8275.1"
+    }
+
+    fn attributes(&self) -> &'static [BmcaAttribute] {
+        &[
+            BmcaAttribute::ClockClass,
+            BmcaAttribute::ClockAccuracy,
+            BmcaAttribute::Variance,
+            BmcaAttribute::Priority2,
+            BmcaAttribute::LocalPriority,
+            BmcaAttribute::ClockIdentity,
+            BmcaAttribute::StepsRemoved,
+        ]
+    }
+}
+
+/// 802.1AS systemIdentity of an announcing transmitter, compared as a
+/// whole with lower being better. The field order is the comparison order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SystemIdentity {
+    pub priority1: u8,
+    pub clock_class: u8,
+    pub clock_accuracy: u8,
+    pub offset_scaled_log_variance: u16,
+    pub priority2: u8,
+    pub clock_identity: ClockIdentity,
+}
+
+impl SystemIdentity {
+    /// None until the transmitter sent an Announce
+    pub fn from_state(state: &PtpHostStateTimeTransmitter) -> Option<Self> {
+        Some(Self {
+            priority1: state.priority1?,
+            clock_class: state.clock_class?.class(),
+            clock_accuracy: state.clock_accuracy?.accuracy,
+            offset_scaled_log_variance: state.offset_scaled_log_variance?,
+            priority2: state.priority2?,
+            clock_identity: state.ptt_identifier?,
+        })
+    }
+}
+
+impl fmt::Display for SystemIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}/0x{:02x}/0x{:04x}/{}/{}",
+            self.priority1,
+            self.clock_class,
+            self.clock_accuracy,
+            self.offset_scaled_log_variance,
+            self.priority2,
+            self.clock_identity
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BmcaProfile {
+    /// gPTP rules for gPTP instances, IEEE 1588 rules otherwise
+    #[default]
+    Auto,
+    Ieee1588,
+    Gptp,
+    G8275_1,
+}
+
+impl BmcaProfile {
+    pub fn all() -> [BmcaProfile; 4] {
+        [
+            BmcaProfile::Auto,
+            BmcaProfile::Ieee1588,
+            BmcaProfile::Gptp,
+            BmcaProfile::G8275_1,
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BmcaProfile::Auto => "auto",
+            BmcaProfile::Ieee1588 => "1588",
+            BmcaProfile::Gptp => "gptp",
+            BmcaProfile::G8275_1 => "g8275.1",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::all()
+            .into_iter()
+            .find(|profile| profile.as_str() == s)
+    }
+
+    /// BMCA rules for a PTP instance
+    pub fn bmca_for(&self, sdo_id: u16) -> &'static dyn Bmca {
+        match self {
+            BmcaProfile::Auto if sdo_id >> 8 == SDO_ID_GPTP >> 8 => &GptpBmca,
+            BmcaProfile::Auto | BmcaProfile::Ieee1588 => &Ieee1588Bmca,
+            BmcaProfile::Gptp => &GptpBmca,
+            BmcaProfile::G8275_1 => &G8275_1Bmca,
+        }
+    }
+}
+
+#[test]
+fn test_bmca_profiles() {
+    use crate::types::{PtpClockAccuracy, PtpClockClass};
+
+    let better_priority1 = PtpHostStateTimeTransmitter {
+        priority1: Some(100),
+        clock_class: Some(PtpClockClass::new(248)),
+        ..Default::default()
+    };
+    let better_class = PtpHostStateTimeTransmitter {
+        priority1: Some(128),
+        clock_class: Some(PtpClockClass::new(6)),
+        ..Default::default()
+    };
+    let a = BmcaCandidate {
+        clock_identity: ClockIdentity {
+            clock_id: [0, 0, 0, 0, 0, 0, 0, 1],
+        },
+        state: &better_priority1,
+        local_priority: DEFAULT_LOCAL_PRIORITY,
+    };
+    let b = BmcaCandidate {
+        clock_identity: ClockIdentity {
+            clock_id: [0, 0, 0, 0, 0, 0, 0, 2],
+        },
+        state: &better_class,
+        local_priority: DEFAULT_LOCAL_PRIORITY,
+    };
+
+    // Default rules look at priority1 first, G.8275.1 ignores it
+    assert_eq!(Ieee1588Bmca.compare(&a, &b), Ordering::Less);
+    assert_eq!(G8275_1Bmca.compare(&a, &b), Ordering::Greater);
+    assert_eq!(
+        Ieee1588Bmca.deciding_attribute(&a, &b),
+        Some(BmcaAttribute::Priority1)
+    );
+    assert_eq!(
+        G8275_1Bmca.deciding_attribute(&a, &b),
+        Some(BmcaAttribute::ClockClass)
+    );
+
+    // localPriority decides between otherwise equal G.8275.1 candidates
+    let a = BmcaCandidate {
+        state: &better_class,
+        local_priority: 10,
+        ..a
+    };
+    assert_eq!(G8275_1Bmca.compare(&a, &b), Ordering::Less);
+    assert_eq!(
+        G8275_1Bmca.deciding_attribute(&a, &b),
+        Some(BmcaAttribute::LocalPriority)
+    );
+
+    // A relay forwards the grandmaster's systemIdentity one step further
+    // down. The default rules pick it for its lower own identity, gPTP picks
+    // the grandmaster.
+    let grandmaster_state = PtpHostStateTimeTransmitter {
+        ptt_identifier: Some(ClockIdentity {
+            clock_id: [0, 0, 0, 0, 0, 0, 0, 9],
+        }),
+        clock_accuracy: Some(PtpClockAccuracy::new(0x21)),
+        offset_scaled_log_variance: Some(0x4e5d),
+        priority2: Some(128),
+        steps_removed: Some(0),
+        ..better_class.clone()
+    };
+    let relay_state = PtpHostStateTimeTransmitter {
+        steps_removed: Some(1),
+        ..grandmaster_state.clone()
+    };
+    let grandmaster = BmcaCandidate {
+        clock_identity: ClockIdentity {
+            clock_id: [0, 0, 0, 0, 0, 0, 0, 9],
+        },
+        state: &grandmaster_state,
+        local_priority: DEFAULT_LOCAL_PRIORITY,
+    };
+    let relay = BmcaCandidate {
+        clock_identity: ClockIdentity {
+            clock_id: [0, 0, 0, 0, 0, 0, 0, 3],
+        },
+        state: &relay_state,
+        local_priority: DEFAULT_LOCAL_PRIORITY,
+    };
+    assert_eq!(
+        Ieee1588Bmca.compare(&grandmaster, &relay),
+        Ordering::Greater
+    );
+    assert_eq!(GptpBmca.compare(&grandmaster, &relay), Ordering::Less);
+    assert_eq!(
+        GptpBmca.deciding_attribute(&grandmaster, &relay),
+        Some(BmcaAttribute::StepsRemoved)
+    );
+    let system_identity = SystemIdentity::from_state(&grandmaster_state).unwrap();
+    assert_eq!(
+        SystemIdentity::from_state(&relay_state),
+        Some(system_identity)
+    );
+    assert_eq!(
+        system_identity.to_string(),
+        format!("128/6/0x21/0x4e5d/128/{}", grandmaster.clock_identity)
+    );
+    assert_eq!(SystemIdentity::from_state(&better_priority1), None);
+
+    assert_eq!(BmcaProfile::Auto.bmca_for(0x100).name(), "gPTP");
+    assert_eq!(BmcaProfile::Auto.bmca_for(0).name(), "IEEE 1588");
+    assert_eq!(BmcaProfile::from_str("g8275.1"), Some(BmcaProfile::G8275_1));
+}
@@ -0,0 +1,202 @@
+//! Capture on the members of bond and bridge interfaces (--members)
+//!
+//! Capturing on a bond or bridge master shows every frame as arriving on the
+//! master, which physical link delivered it is lost. An active-backup bond
+//! failover then looks like an unexplained path change: the same hosts, all
+//! at once with other delays and arrival times. pnet doesn't pass on the
+//! device a frame originated on, so with --members each member is captured on
+//! instead of the master, and its packets are tagged like "bond0/eth1". The
+//! active member of a bond is watched, a failover raises an alert.
+//!
+//! A bridge forwards a frame out of its other ports, where it is captured
+//! again. The copies are dropped by the duplicate suppression, keeping the
+//! one from the member the frame arrived on.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where Linux describes its network interfaces
+pub const SYSFS_NET: &str = "/sys/class/net";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateKind {
+    Bond,
+    Bridge,
+}
+
+/// A bond or bridge and its member interfaces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Aggregate {
+    pub kind: AggregateKind,
+    pub members: Vec<String>,
+}
+
+/// Members of `interface` if it is a bond or bridge with any
+pub fn read_aggregate(sysfs: &Path, interface: &str) -> Option<Aggregate> {
+    let dir = sysfs.join(interface);
+    let (kind, mut members) = match fs::read_to_string(dir.join("bonding/slaves")) {
+        Ok(slaves) => (
+            AggregateKind::Bond,
+            slaves.split_whitespace().map(String::from).collect(),
+        ),
+        Err(_) => (
+            AggregateKind::Bridge,
+            fs::read_dir(dir.join("brif"))
+                .ok()?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect::<Vec<_>>(),
+        ),
+    };
+    members.sort();
+    (!members.is_empty()).then_some(Aggregate { kind, members })
+}
+
+/// Active member of a bond, None unless it is an active-backup bond
+pub fn active_member(sysfs: &Path, bond: &str) -> Option<String> {
+    let active = fs::read_to_string(sysfs.join(bond).join("bonding/active_slave")).ok()?;
+    let active = active.trim();
+    (!active.is_empty()).then(|| active.to_string())
+}
+
+/// Capture interface name of a member, tagged with its master
+pub fn member_label(master: &str, member: &str) -> String {
+    format!("{}/{}", master, member)
+}
+
+/// Network device a capture interface name refers to, the member itself for
+/// a member of a bond or bridge
+pub fn device_name(label: &str) -> &str {
+    label.rsplit('/').next().unwrap_or(label)
+}
+
+/// Bond or bridge a capture interface name is a member of
+pub fn master_name(label: &str) -> Option<&str> {
+    label.split_once('/').map(|(master, _)| master)
+}
+
+/// Interfaces to capture on for `interface`: with `members` set and
+/// `interface` a bond or bridge its members, labeled with it, else itself
+pub fn capture_names(sysfs: &Path, interface: &str, members: bool) -> Vec<String> {
+    match read_aggregate(sysfs, interface).filter(|_| members) {
+        Some(aggregate) => aggregate
+            .members
+            .iter()
+            .map(|member| member_label(interface, member))
+            .collect(),
+        None => vec![interface.to_string()],
+    }
+}
+
+/// An active-backup bond switched to another member
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BondFailover {
+    pub bond: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl Display for BondFailover {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Bond {} failed over from {} to {}, paths through it changed",
+            self.bond,
+            self.from.as_deref().unwrap_or("no member"),
+            self.to.as_deref().unwrap_or("no member")
+        )
+    }
+}
+
+/// Active members of the bonds captured on
+#[derive(Debug, Clone, Default)]
+pub struct BondWatch {
+    sysfs: PathBuf,
+    active: BTreeMap<String, Option<String>>,
+}
+
+impl BondWatch {
+    /// Watch the bonds among the masters of the capture interfaces
+    pub fn new(sysfs: &Path, capture_interfaces: &[String]) -> Self {
+        let active = capture_interfaces
+            .iter()
+            .filter_map(|label| master_name(label))
+            .filter(|master| {
+                read_aggregate(sysfs, master)
+                    .is_some_and(|aggregate| aggregate.kind == AggregateKind::Bond)
+            })
+            .map(|bond| (bond.to_string(), active_member(sysfs, bond)))
+            .collect();
+        Self {
+            sysfs: sysfs.to_path_buf(),
+            active,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+
+    /// Bonds whose active member changed since the last poll
+    pub fn poll(&mut self) -> Vec<BondFailover> {
+        let mut failovers = Vec::new();
+        for (bond, active) in &mut self.active {
+            let now = active_member(&self.sysfs, bond);
+            if now != *active {
+                failovers.push(BondFailover {
+                    bond: bond.clone(),
+                    from: active.take(),
+                    to: now.clone(),
+                });
+                *active = now;
+            }
+        }
+        failovers
+    }
+}
+
+#[test]
+fn test_bond_members() {
+    let sysfs = std::env::temp_dir().join(format!("ptp-trace-sysfs-{}", std::process::id()));
+    fs::create_dir_all(sysfs.join("bond0/bonding")).unwrap();
+    fs::write(sysfs.join("bond0/bonding/slaves"), "eth1 eth0\n").unwrap();
+    fs::write(sysfs.join("bond0/bonding/active_slave"), "eth0\n").unwrap();
+    fs::create_dir_all(sysfs.join("br0/brif/eth2")).unwrap();
+    fs::create_dir_all(sysfs.join("eth3")).unwrap();
+
+    assert_eq!(
+        read_aggregate(&sysfs, "br0"),
+        Some(Aggregate {
+            kind: AggregateKind::Bridge,
+            members: vec!["eth2".to_string()],
+        })
+    );
+    assert_eq!(read_aggregate(&sysfs, "eth3"), None);
+    let labels = capture_names(&sysfs, "bond0", true);
+    assert_eq!(labels, vec!["bond0/eth0", "bond0/eth1"]);
+    assert_eq!(capture_names(&sysfs, "bond0", false), vec!["bond0"]);
+    assert_eq!(capture_names(&sysfs, "eth3", true), vec!["eth3"]);
+    assert_eq!(device_name(&labels[1]), "eth1");
+    assert_eq!(device_name("eth3"), "eth3");
+    assert_eq!(master_name(&labels[1]), Some("bond0"));
+
+    // Only bonds are watched, bridges have no active member
+    let mut watch = BondWatch::new(&sysfs, &["bond0/eth0".to_string(), "br0/eth2".to_string()]);
+    assert!(watch.poll().is_empty());
+    fs::write(sysfs.join("bond0/bonding/active_slave"), "eth1\n").unwrap();
+    assert_eq!(
+        watch.poll(),
+        vec![BondFailover {
+            bond: "bond0".to_string(),
+            from: Some("eth0".to_string()),
+            to: Some("eth1".to_string()),
+        }]
+    );
+    assert!(watch.poll().is_empty());
+
+    fs::remove_dir_all(&sysfs).unwrap();
+}
@@ -0,0 +1,208 @@
+//! Bookmarked packets
+//!
+//! Packets marked with 'b' in the packet history, each with an optional note.
+//! A bookmark holds on to its packet, so it can still be opened after the
+//! packet dropped out of the host's history, and the bookmark list jumps back
+//! to it. Bookmarked packets are part of the host's pcap export, and the whole
+//! list can be exported as pcapng with the notes as packet comments.
+
+use std::{borrow::Cow, fs::File, io::BufWriter, path::Path, sync::Arc, time::UNIX_EPOCH};
+
+use anyhow::Result;
+use pcap_file::{
+    DataLink,
+    pcapng::{
+        PcapNgWriter,
+        blocks::{
+            enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption},
+            interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption},
+        },
+    },
+};
+
+use crate::types::{ClockIdentity, ParsedPacket};
+
+/// Longest note accepted, a note is a reminder, not a report
+pub const MAX_NOTE_LENGTH: usize = 80;
+
+#[derive(Debug, Clone)]
+pub struct Bookmark {
+    /// Shared with the host's history while the packet is still in it
+    pub packet: Arc<ParsedPacket>,
+    /// Host in whose history the packet was bookmarked
+    pub clock_identity: ClockIdentity,
+    pub note: String,
+}
+
+/// Bookmarks in capture order
+#[derive(Debug, Default)]
+pub struct Bookmarks {
+    items: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    fn position(&self, packet: &ParsedPacket) -> Option<usize> {
+        self.items
+            .iter()
+            .position(|bookmark| Arc::ptr_eq(&bookmark.packet.raw, &packet.raw))
+    }
+
+    /// Bookmark a packet, or remove its bookmark. Returns the index of the new
+    /// bookmark, None if one was removed.
+    pub fn toggle(
+        &mut self,
+        clock_identity: ClockIdentity,
+        packet: &Arc<ParsedPacket>,
+    ) -> Option<usize> {
+        if let Some(index) = self.position(packet) {
+            self.items.remove(index);
+            return None;
+        }
+        let index = self
+            .items
+            .partition_point(|bookmark| bookmark.packet.raw.timestamp <= packet.raw.timestamp);
+        self.items.insert(
+            index,
+            Bookmark {
+                packet: packet.clone(),
+                clock_identity,
+                note: String::new(),
+            },
+        );
+        Some(index)
+    }
+
+    pub fn is_bookmarked(&self, packet: &ParsedPacket) -> bool {
+        self.position(packet).is_some()
+    }
+
+    pub fn set_note(&mut self, index: usize, note: &str) {
+        if let Some(bookmark) = self.items.get_mut(index) {
+            bookmark.note = note.trim().chars().take(MAX_NOTE_LENGTH).collect();
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.items.remove(index);
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Bookmark> {
+        self.items.get(index)
+    }
+
+    pub fn items(&self) -> &[Bookmark] {
+        &self.items
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Bookmarked packets of a host, for its packet export
+    pub fn host_packets(&self, clock_identity: ClockIdentity) -> Vec<Arc<ParsedPacket>> {
+        self.items
+            .iter()
+            .filter(|bookmark| bookmark.clock_identity == clock_identity)
+            .map(|bookmark| bookmark.packet.clone())
+            .collect()
+    }
+
+    /// Write all bookmarked packets to a pcapng file with the notes as packet
+    /// comments, returning the number of packets written
+    pub fn write_pcapng(&self, path: &Path) -> Result<usize> {
+        let mut writer = PcapNgWriter::new(BufWriter::new(File::create(path)?))?;
+        writer.write_pcapng_block(InterfaceDescriptionBlock {
+            linktype: DataLink::ETHERNET,
+            snaplen: 0,
+            // Enhanced packet blocks are written with nanosecond timestamps
+            options: vec![InterfaceDescriptionOption::IfTsResol(9)],
+        })?;
+
+        for bookmark in &self.items {
+            let packet = &bookmark.packet.raw;
+            let mut comment = format!(
+                "{} seq {} from {}",
+                bookmark.packet.ptp.header().message_type,
+                bookmark.packet.ptp.header().sequence_id,
+                bookmark.clock_identity
+            );
+            if !bookmark.note.is_empty() {
+                comment = format!("{}: {}", bookmark.note, comment);
+            }
+            writer.write_pcapng_block(EnhancedPacketBlock {
+                interface_id: 0,
+                timestamp: packet
+                    .timestamp
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default(),
+                original_len: packet.original_length as u32,
+                data: Cow::Borrowed(&packet.data),
+                options: vec![EnhancedPacketOption::Comment(Cow::Owned(comment))],
+            })?;
+        }
+
+        Ok(self.items.len())
+    }
+}
+
+#[test]
+fn test_bookmarks() {
+    use std::time::{Duration, SystemTime};
+
+    use crate::{source::RawPacket, types::PtpMessage};
+
+    let packet = |sequence_id: u16, seconds: u64| {
+        let mut payload = [0u8; 44];
+        payload[1] = 0x02;
+        payload[2..4].copy_from_slice(&44u16.to_be_bytes());
+        payload[30..32].copy_from_slice(&sequence_id.to_be_bytes());
+        Arc::new(ParsedPacket {
+            ptp: PtpMessage::try_from(&payload[..]).unwrap(),
+            raw: Arc::new(RawPacket::from_ptp_payload(
+                &payload,
+                "eth0",
+                SystemTime::UNIX_EPOCH + Duration::from_secs(seconds),
+            )),
+        })
+    };
+    let host = ClockIdentity::default();
+    let (late, early) = (packet(2, 20), packet(1, 10));
+
+    let mut bookmarks = Bookmarks::default();
+    assert_eq!(bookmarks.toggle(host, &late), Some(0));
+    // Kept in capture order, not in the order they were bookmarked
+    assert_eq!(bookmarks.toggle(host, &early), Some(0));
+    bookmarks.set_note(1, "  odd announce  ");
+    assert_eq!(bookmarks.get(1).unwrap().note, "odd announce");
+    assert!(bookmarks.is_bookmarked(&late));
+
+    // A packet with the same contents is still another packet
+    assert!(!bookmarks.is_bookmarked(&packet(2, 20)));
+
+    assert_eq!(bookmarks.toggle(host, &late), None);
+    assert_eq!(bookmarks.len(), 1);
+    assert_eq!(bookmarks.host_packets(host).len(), 1);
+
+    let path =
+        std::env::temp_dir().join(format!("ptp-trace-bookmarks-{}.pcapng", std::process::id()));
+    assert_eq!(bookmarks.write_pcapng(&path).unwrap(), 1);
+    let mut reader = pcap_file::pcapng::PcapNgReader::new(File::open(&path).unwrap()).unwrap();
+    let mut found = false;
+    while let Some(block) = reader.next_block() {
+        if let pcap_file::pcapng::Block::EnhancedPacket(epb) = block.unwrap() {
+            assert_eq!(epb.timestamp, Duration::from_secs(10));
+            assert!(
+                matches!(&epb.options[0], EnhancedPacketOption::Comment(c) if c.starts_with("SYNC seq 1"))
+            );
+            found = true;
+        }
+    }
+    assert!(found);
+    let _ = std::fs::remove_file(path);
+}
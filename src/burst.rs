@@ -0,0 +1,88 @@
+//! Microburst detection
+//!
+//! Deep-buffered switches can hold PTP packets back and release them all at
+//! once. Average packet rates hide this, but receivers see the event packets
+//! of a burst with queueing delay and their servo becomes unstable. A burst is
+//! counted when at least BURST_MIN_PACKETS arrive within BURST_WINDOW, and
+//! lasts until the packets thin out again.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime},
+};
+
+/// Time window packets of a burst arrive within
+pub const BURST_WINDOW: Duration = Duration::from_millis(2);
+/// Packets within the window that make a burst
+pub const BURST_MIN_PACKETS: usize = 16;
+
+#[derive(Debug, Clone, Default)]
+pub struct BurstDetector {
+    /// Arrival times within the last window
+    recent: VecDeque<SystemTime>,
+    /// Packets of the ongoing burst, 0 outside of a burst
+    current_size: usize,
+    pub burst_count: u32,
+    pub max_burst_size: usize,
+}
+
+impl BurstDetector {
+    /// Record a packet arrival. Returns true when it starts a new burst.
+    pub fn record(&mut self, timestamp: SystemTime) -> bool {
+        while let Some(&oldest) = self.recent.front() {
+            if timestamp.duration_since(oldest).unwrap_or_default() <= BURST_WINDOW {
+                break;
+            }
+            self.recent.pop_front();
+        }
+        self.recent.push_back(timestamp);
+
+        if self.recent.len() < BURST_MIN_PACKETS {
+            self.current_size = 0;
+            return false;
+        }
+
+        let started = self.current_size == 0;
+        if started {
+            self.burst_count += 1;
+            self.current_size = self.recent.len();
+        } else {
+            self.current_size += 1;
+        }
+        self.max_burst_size = self.max_burst_size.max(self.current_size);
+        started
+    }
+
+    /// Bursts seen and the largest one, e.g. "3 (max 24 packets)"
+    pub fn summary(&self) -> String {
+        if self.burst_count == 0 {
+            "none".to_string()
+        } else {
+            format!("{} (max {} packets)", self.burst_count, self.max_burst_size)
+        }
+    }
+}
+
+#[test]
+fn test_burst_detection() {
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut detector = BurstDetector::default();
+
+    // Steady 1 ms spacing never gets close
+    for i in 0..100 {
+        assert!(!detector.record(start + Duration::from_millis(i)));
+    }
+
+    // 20 packets 50 µs apart are one burst of 20
+    let burst_start = start + Duration::from_secs(1);
+    let started = (0..20)
+        .filter(|&i| detector.record(burst_start + Duration::from_micros(50 * i)))
+        .count();
+    assert_eq!(started, 1);
+    assert_eq!(detector.burst_count, 1);
+    assert_eq!(detector.max_burst_size, 20);
+
+    // The next packet after a pause ends it
+    assert!(!detector.record(burst_start + Duration::from_millis(100)));
+    assert_eq!(detector.summary(), "1 (max 20 packets)");
+}
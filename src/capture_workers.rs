@@ -0,0 +1,250 @@
+//! Capture on an interface with several worker threads
+//!
+//! One capture task per interface keeps up with PTP itself, but on a busy 10G
+//! link it has to read every other frame the socket gets too, and the kernel
+//! drops what no longer fits the socket's receive buffer. With worker threads
+//! the interface is read by several AF_PACKET sockets joined in one
+//! PACKET_FANOUT group (Linux only), the kernel spreading the frames over
+//! them by flow hash, by the CPU that received them or by the receive queue
+//! of a multi-queue NIC. Each worker can be pinned to a CPU, ideally one of
+//! those the NIC queues interrupt. The kernel counts per socket the frames it
+//! dropped, those counts are kept per worker for the interfaces view.
+
+use std::{
+    io,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU16, Ordering},
+    },
+};
+
+use pnet::datalink::{FanoutOption, FanoutType};
+
+/// How the kernel spreads the frames of an interface over its workers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum FanoutMode {
+    /// By flow hash, the packets of a flow stay with one worker and in order
+    #[default]
+    Hash,
+    /// By the CPU that received the frame
+    Cpu,
+    /// By the NIC receive queue, one worker per queue of a multi-queue NIC
+    Qm,
+}
+
+/// How interfaces are captured: worker threads per interface and where they
+/// run, and whether the PTP multicast groups are joined
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CaptureOptions {
+    /// Workers per interface, 0 or 1 for a single one
+    pub workers: usize,
+    pub fanout: FanoutMode,
+    /// CPUs the workers are pinned to, in turn
+    pub cpus: Vec<usize>,
+    /// Don't join the multicast groups, no IGMP report is sent (--offline)
+    pub offline: bool,
+}
+
+impl CaptureOptions {
+    /// Whether interfaces are captured by worker threads instead of the
+    /// single capture task
+    pub fn use_workers(&self) -> bool {
+        self.workers > 1 || !self.cpus.is_empty()
+    }
+
+    pub fn worker_count(&self) -> usize {
+        self.workers.max(1)
+    }
+
+    /// CPU worker number `worker` is pinned to
+    pub fn cpu_of(&self, worker: usize) -> Option<usize> {
+        (!self.cpus.is_empty()).then(|| self.cpus[worker % self.cpus.len()])
+    }
+
+    /// Fanout settings of the sockets of one interface
+    pub fn fanout_option(&self, group_id: u16) -> FanoutOption {
+        FanoutOption {
+            group_id,
+            fanout_type: match self.fanout {
+                FanoutMode::Hash => FanoutType::HASH,
+                FanoutMode::Cpu => FanoutType::CPU,
+                FanoutMode::Qm => FanoutType::QM,
+            },
+            // Fragments of a datagram are hashed alike only once reassembled
+            defrag: true,
+            rollover: false,
+        }
+    }
+}
+
+/// Parse a CPU list as in /sys/devices/system/cpu, e.g. "2,3,8-11"
+pub fn parse_cpu_list(list: &str) -> Result<Vec<usize>, String> {
+    let mut cpus = Vec::new();
+    for part in list.split(',').map(str::trim) {
+        let parse = |cpu: &str| {
+            cpu.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid CPU '{}' in '{}'", cpu.trim(), list))
+        };
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse(first)?, parse(last)?);
+                if first > last {
+                    return Err(format!("Invalid CPU range '{}' in '{}'", part, list));
+                }
+                cpus.extend(first..=last);
+            }
+            None => cpus.push(parse(part)?),
+        }
+    }
+    Ok(cpus)
+}
+
+/// Frames a capture worker read and the kernel dropped on its socket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerStats {
+    pub interface: String,
+    pub worker: usize,
+    pub cpu: Option<usize>,
+    /// Frames of any protocol the socket received, PTP or not
+    pub packets: u64,
+    /// Frames dropped for a full receive buffer
+    pub drops: u64,
+}
+
+impl WorkerStats {
+    pub fn new(interface: &str, worker: usize, cpu: Option<usize>) -> Self {
+        Self {
+            interface: interface.to_string(),
+            worker,
+            cpu,
+            packets: 0,
+            drops: 0,
+        }
+    }
+}
+
+/// Workers of all interfaces, updated by the workers as they read
+pub type CaptureWorkers = Arc<Mutex<Vec<WorkerStats>>>;
+
+/// Fanout group id for the next interface. The ids are shared by all
+/// processes of the network namespace, the process id keeps them apart from
+/// those of another ptp-trace.
+pub fn next_fanout_group() -> u16 {
+    static NEXT_GROUP: AtomicU16 = AtomicU16::new(0);
+    (std::process::id() as u16).wrapping_add(NEXT_GROUP.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Open an AF_PACKET socket receiving all protocols, for pnet to bind
+#[cfg(target_os = "linux")]
+pub fn open_packet_socket() -> io::Result<i32> {
+    // SAFETY: plain socket(2) call, the descriptor is owned by the caller
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_PACKET,
+            libc::SOCK_RAW,
+            (libc::ETH_P_ALL as u16).to_be() as i32,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(fd)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn open_packet_socket() -> io::Result<i32> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Capture workers are only supported on Linux",
+    ))
+}
+
+/// Frames received and dropped on a packet socket since the last call, the
+/// kernel resets its counters when they are read
+#[cfg(target_os = "linux")]
+pub fn packet_statistics(fd: i32) -> io::Result<(u64, u64)> {
+    let mut stats: libc::tpacket_stats = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::tpacket_stats>() as libc::socklen_t;
+    // SAFETY: the kernel writes at most len bytes into stats
+    if unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_PACKET,
+            libc::PACKET_STATISTICS,
+            (&mut stats as *mut libc::tpacket_stats).cast(),
+            &mut len,
+        )
+    } < 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+    Ok((stats.tp_packets as u64, stats.tp_drops as u64))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn packet_statistics(_fd: i32) -> io::Result<(u64, u64)> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+/// Pin the calling thread to a CPU
+#[cfg(target_os = "linux")]
+pub fn pin_to_cpu(cpu: usize) -> io::Result<()> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    if cpu >= libc::CPU_SETSIZE as usize {
+        return Err(io::Error::from(io::ErrorKind::InvalidInput));
+    }
+    // SAFETY: cpu is within the set, 0 is the calling thread
+    unsafe {
+        libc::CPU_SET(cpu, &mut set);
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_to_cpu(_cpu: usize) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "CPU pinning is only supported on Linux",
+    ))
+}
+
+#[test]
+fn test_capture_options() {
+    assert_eq!(parse_cpu_list("3"), Ok(vec![3]));
+    assert_eq!(parse_cpu_list("2,3, 8-10"), Ok(vec![2, 3, 8, 9, 10]));
+    assert!(parse_cpu_list("4-2").is_err());
+    assert!(parse_cpu_list("2,x").is_err());
+    assert!(parse_cpu_list("").is_err());
+
+    let single = CaptureOptions::default();
+    assert!(!single.use_workers());
+    assert_eq!(single.worker_count(), 1);
+    assert_eq!(single.cpu_of(0), None);
+
+    let options = CaptureOptions {
+        workers: 4,
+        fanout: FanoutMode::Qm,
+        cpus: vec![2, 3],
+        offline: false,
+    };
+    assert!(options.use_workers());
+    assert_eq!(
+        (0..4)
+            .map(|worker| options.cpu_of(worker))
+            .collect::<Vec<_>>(),
+        vec![Some(2), Some(3), Some(2), Some(3)]
+    );
+    assert_eq!(options.fanout_option(7).fanout_type, FanoutType::QM);
+    // A single worker pinned to a CPU still runs as a worker
+    assert!(
+        CaptureOptions {
+            cpus: vec![1],
+            ..CaptureOptions::default()
+        }
+        .use_workers()
+    );
+}
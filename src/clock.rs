@@ -0,0 +1,50 @@
+//! Time the UI measures ages against
+//!
+//! "3.2s ago", last seen, silence and the time windows of the panes are all
+//! measured against the capture timestamps of packets. Live, they are taken
+//! against the system clock. A pcap file is read at once and looked at as of
+//! its last packet, not as of the day it is opened. A view paused with 'p', or
+//! a pane frozen with 'z', is looked at as of the moment it was stopped, so
+//! its ages don't keep growing while its content stands still.
+
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Clock {
+    /// The system clock
+    #[default]
+    Live,
+    /// Standing at this time
+    Frozen(SystemTime),
+}
+
+impl Clock {
+    pub fn now(&self) -> SystemTime {
+        match self {
+            Clock::Live => SystemTime::now(),
+            Clock::Frozen(at) => *at,
+        }
+    }
+
+    /// Time from `time` to now, zero for a time after now
+    pub fn since(&self, time: SystemTime) -> Duration {
+        self.now().duration_since(time).unwrap_or_default()
+    }
+}
+
+#[test]
+fn test_clock() {
+    let end = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let clock = Clock::Frozen(end);
+    assert_eq!(
+        clock.since(end - Duration::from_millis(1500)).as_millis(),
+        1500
+    );
+    // A packet timestamped after the reference is not in the future
+    assert_eq!(clock.since(end + Duration::from_secs(1)), Duration::ZERO);
+
+    let paused = Clock::Frozen(Clock::Live.now());
+    std::thread::sleep(Duration::from_millis(5));
+    assert_eq!(paused.since(paused.now()), Duration::ZERO);
+    assert!(Clock::Live.since(paused.now()) >= Duration::from_millis(5));
+}
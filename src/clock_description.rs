@@ -0,0 +1,359 @@
+//! GET CLOCK_DESCRIPTION management queries
+//!
+//! Hosts are only known by their clock identity and the vendor its OUI
+//! belongs to. Most PTP devices describe themselves when asked with a
+//! CLOCK_DESCRIPTION management GET (IEEE 1588-2019 15.5.3.1.2): the product
+//! as "manufacturer;model;instance", its revisions and a user description set
+//! by the operator, typically the device name and location. With --active,
+//! 'N' sends the request to the selected host; devices that have management
+//! disabled simply never answer, which is reported after RESPONSE_TIMEOUT.
+
+use anyhow::{Result, bail};
+use std::{
+    net::Ipv4Addr,
+    time::{Duration, SystemTime},
+};
+
+use crate::{source::PTP_GENERAL_PORT, types::ClockIdentity};
+
+/// How long to wait for an answer before giving up on the host
+pub const RESPONSE_TIMEOUT: Duration = Duration::from_secs(3);
+
+const MANAGEMENT_MESSAGE_TYPE: u8 = 0x0d;
+const MANAGEMENT_CONTROL_FIELD: u8 = 0x04;
+const ACTION_GET: u8 = 0;
+const ACTION_RESPONSE: u8 = 2;
+const TLV_MANAGEMENT: u16 = 0x0001;
+const TLV_MANAGEMENT_ERROR_STATUS: u16 = 0x0002;
+const CLOCK_DESCRIPTION: u16 = 0x0001;
+/// Request length: header, target port identity, boundary hops, action and
+/// an empty management TLV
+const REQUEST_LENGTH: usize = 54;
+const PTP_PRIMARY_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 1, 129);
+/// IEEE 1588 over Ethernet, forwarded by bridges
+const PTP_PRIMARY_MAC: [u8; 6] = [0x01, 0x1b, 0x19, 0x00, 0x00, 0x00];
+const PTP_ETHERTYPE: u16 = 0x88f7;
+
+/// Answer to a GET CLOCK_DESCRIPTION
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockDescription {
+    pub clock_type: u16,
+    pub physical_layer_protocol: String,
+    pub manufacturer_identity: [u8; 3],
+    /// "manufacturerName;modelNumber;instanceIdentifier"
+    pub product_description: String,
+    /// "hardwareRevision;firmwareRevision;softwareRevision"
+    pub revision_data: String,
+    /// "name;location", set by the operator
+    pub user_description: String,
+    pub profile_identity: [u8; 6],
+}
+
+impl ClockDescription {
+    /// Manufacturer and model, e.g. "Meinberg LANTIME M1000"
+    pub fn product(&self) -> String {
+        let mut fields = self.product_description.split(';');
+        let product = [fields.next(), fields.next()]
+            .into_iter()
+            .flatten()
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if product.is_empty() {
+            "-".to_string()
+        } else {
+            product
+        }
+    }
+
+    /// Name the operator gave the device, None if it was left empty
+    pub fn name(&self) -> Option<&str> {
+        self.user_description
+            .split(';')
+            .next()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Hardware, firmware and software revision, e.g. "1.2/5.10/7.08"
+    pub fn revisions(&self) -> String {
+        self.revision_data.replace(';', "/")
+    }
+}
+
+/// Where a host stands with its clock description
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DescriptionQuery {
+    #[default]
+    NotRequested,
+    Pending {
+        since: SystemTime,
+        sequence_id: u16,
+    },
+    Received(ClockDescription),
+    /// The host did not answer within RESPONSE_TIMEOUT
+    NoResponse,
+    /// The host answered with a management error
+    Refused(u16),
+}
+
+impl DescriptionQuery {
+    /// Give up on a request that was not answered in time
+    pub fn expire(&mut self, now: SystemTime) {
+        if let DescriptionQuery::Pending { since, .. } = self
+            && now.duration_since(*since).unwrap_or_default() > RESPONSE_TIMEOUT
+        {
+            *self = DescriptionQuery::NoResponse;
+        }
+    }
+
+    pub fn description(&self) -> Option<&ClockDescription> {
+        match self {
+            DescriptionQuery::Received(description) => Some(description),
+            _ => None,
+        }
+    }
+
+    /// Status for the host details, None before the host was asked
+    pub fn status(&self) -> Option<String> {
+        match self {
+            DescriptionQuery::NotRequested | DescriptionQuery::Received(_) => None,
+            DescriptionQuery::Pending { .. } => Some("requested...".to_string()),
+            DescriptionQuery::NoResponse => {
+                Some("no response, management may be disabled on the device".to_string())
+            }
+            DescriptionQuery::Refused(error_id) => {
+                Some(format!("refused with management error 0x{:04x}", error_id))
+            }
+        }
+    }
+}
+
+/// EUI-64 clock identity of an interface MAC, used as our source identity
+pub fn clock_identity_from_mac(mac: [u8; 6]) -> ClockIdentity {
+    ClockIdentity {
+        clock_id: [mac[0], mac[1], mac[2], 0xff, 0xfe, mac[3], mac[4], mac[5]],
+    }
+}
+
+/// GET CLOCK_DESCRIPTION for all ports of the target clock
+pub fn request(
+    target: ClockIdentity,
+    source: ClockIdentity,
+    sdo_id: u16,
+    domain: u8,
+    sequence_id: u16,
+) -> Vec<u8> {
+    let mut msg = vec![0u8; REQUEST_LENGTH];
+    msg[0] = ((sdo_id >> 8) as u8) << 4 | MANAGEMENT_MESSAGE_TYPE;
+    msg[1] = 2; // PTP version
+    msg[2..4].copy_from_slice(&(REQUEST_LENGTH as u16).to_be_bytes());
+    msg[4] = domain;
+    msg[5] = sdo_id as u8;
+    msg[20..28].copy_from_slice(&source.clock_id);
+    msg[28..30].copy_from_slice(&1u16.to_be_bytes());
+    msg[30..32].copy_from_slice(&sequence_id.to_be_bytes());
+    msg[32] = MANAGEMENT_CONTROL_FIELD;
+    msg[33] = 0x7f;
+    msg[34..42].copy_from_slice(&target.clock_id);
+    msg[42..44].fill(0xff);
+    // Boundary hops stay 0, the host is asked directly
+    msg[46] = ACTION_GET;
+    msg[48..50].copy_from_slice(&TLV_MANAGEMENT.to_be_bytes());
+    msg[50..52].copy_from_slice(&2u16.to_be_bytes());
+    msg[52..54].copy_from_slice(&CLOCK_DESCRIPTION.to_be_bytes());
+    msg
+}
+
+/// The request in an Ethernet frame, to the PTP multicast group over UDP if
+/// the interface has an IPv4 address or as Layer 2 PTP otherwise
+pub fn request_frame(message: &[u8], source_mac: [u8; 6], source_ip: Option<Ipv4Addr>) -> Vec<u8> {
+    let Some(source_ip) = source_ip else {
+        let mut frame = PTP_PRIMARY_MAC.to_vec();
+        frame.extend_from_slice(&source_mac);
+        frame.extend_from_slice(&PTP_ETHERTYPE.to_be_bytes());
+        frame.extend_from_slice(message);
+        return frame;
+    };
+
+    let group = PTP_PRIMARY_GROUP.octets();
+    let mut frame = vec![0x01, 0x00, 0x5e, group[1] & 0x7f, group[2], group[3]];
+    frame.extend_from_slice(&source_mac);
+    frame.extend_from_slice(&0x0800u16.to_be_bytes());
+
+    let mut ip_header = vec![0x45, 0x00];
+    ip_header.extend_from_slice(&((20 + 8 + message.len()) as u16).to_be_bytes());
+    // No fragmentation, TTL 1 like the multicast PTP it queries
+    ip_header.extend_from_slice(&[0x00, 0x00, 0x40, 0x00, 0x01, 0x11, 0x00, 0x00]);
+    ip_header.extend_from_slice(&source_ip.octets());
+    ip_header.extend_from_slice(&group);
+    let checksum = ipv4_checksum(&ip_header);
+    ip_header[10..12].copy_from_slice(&checksum.to_be_bytes());
+    frame.extend_from_slice(&ip_header);
+
+    frame.extend_from_slice(&PTP_GENERAL_PORT.to_be_bytes());
+    frame.extend_from_slice(&PTP_GENERAL_PORT.to_be_bytes());
+    frame.extend_from_slice(&((8 + message.len()) as u16).to_be_bytes());
+    // The UDP checksum is optional over IPv4
+    frame.extend_from_slice(&[0x00, 0x00]);
+    frame.extend_from_slice(message);
+    frame
+}
+
+fn ipv4_checksum(header: &[u8]) -> u16 {
+    let mut sum: u32 = header
+        .chunks(2)
+        .map(|word| u16::from_be_bytes([word[0], word[1]]) as u32)
+        .sum();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Reads the fields of a TLV data field in order
+struct FieldReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> FieldReader<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8]> {
+        if self.data.len() < length {
+            bail!("CLOCK_DESCRIPTION too short");
+        }
+        let (bytes, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// PTPText, a length octet followed by UTF-8
+    fn text(&mut self) -> Result<String> {
+        let length = self.bytes(1)?[0] as usize;
+        Ok(String::from_utf8_lossy(self.bytes(length)?).into_owned())
+    }
+}
+
+fn parse_clock_description(data: &[u8]) -> Result<ClockDescription> {
+    let mut reader = FieldReader { data };
+    let clock_type = reader.u16()?;
+    let physical_layer_protocol = reader.text()?;
+    let physical_address_length = reader.u16()? as usize;
+    reader.bytes(physical_address_length)?;
+    // protocolAddress: networkProtocol, addressLength, address
+    reader.u16()?;
+    let protocol_address_length = reader.u16()? as usize;
+    reader.bytes(protocol_address_length)?;
+    let manufacturer_identity = reader.bytes(3)?.try_into()?;
+    reader.bytes(1)?;
+    let product_description = reader.text()?;
+    let revision_data = reader.text()?;
+    let user_description = reader.text()?;
+    let profile_identity = reader.bytes(6)?.try_into()?;
+    Ok(ClockDescription {
+        clock_type,
+        physical_layer_protocol,
+        manufacturer_identity,
+        product_description,
+        revision_data,
+        user_description,
+        profile_identity,
+    })
+}
+
+/// The answer in a management message, None if it is not a response to a
+/// CLOCK_DESCRIPTION request
+pub fn parse_response(payload: &[u8]) -> Option<(u16, DescriptionQuery)> {
+    if payload.len() < REQUEST_LENGTH
+        || payload[0] & 0x0f != MANAGEMENT_MESSAGE_TYPE
+        || payload[46] & 0x0f != ACTION_RESPONSE
+    {
+        return None;
+    }
+    let sequence_id = u16::from_be_bytes([payload[30], payload[31]]);
+    let tlv_type = u16::from_be_bytes([payload[48], payload[49]]);
+    let tlv_length = u16::from_be_bytes([payload[50], payload[51]]) as usize;
+    let tlv_data = payload.get(52..52 + tlv_length)?;
+
+    match tlv_type {
+        TLV_MANAGEMENT if tlv_data.get(0..2)? == CLOCK_DESCRIPTION.to_be_bytes() => {
+            // An answer that can't be decoded is as good as none
+            let description = parse_clock_description(&tlv_data[2..]).ok()?;
+            Some((sequence_id, DescriptionQuery::Received(description)))
+        }
+        TLV_MANAGEMENT_ERROR_STATUS if tlv_data.get(2..4)? == CLOCK_DESCRIPTION.to_be_bytes() => {
+            let error_id = u16::from_be_bytes([tlv_data[0], tlv_data[1]]);
+            Some((sequence_id, DescriptionQuery::Refused(error_id)))
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn test_clock_description() {
+    let target = clock_identity_from_mac([0xec, 0x46, 0x70, 0x01, 0x02, 0x03]);
+    let source = clock_identity_from_mac([0x02, 0, 0, 0, 0, 1]);
+    let request = request(target, source, 0, 0, 7);
+    assert_eq!(request[0], MANAGEMENT_MESSAGE_TYPE);
+    let frame = request_frame(
+        &request,
+        [0x02, 0, 0, 0, 0, 1],
+        Some(Ipv4Addr::new(192, 0, 2, 1)),
+    );
+    let packet = crate::source::process_ethernet_packet(&frame, "eth0").unwrap();
+    assert_eq!(packet.ptp_payload(), request);
+    assert_eq!(packet.dest_addr.unwrap().port(), PTP_GENERAL_PORT);
+    assert_eq!(ipv4_checksum(&frame[14..34]), 0);
+    let l2 = crate::source::process_ethernet_packet(&request_frame(&request, [2; 6], None), "eth0")
+        .unwrap();
+    assert_eq!(l2.ptp_payload(), request);
+
+    let text = |s: &str| {
+        let mut field = vec![s.len() as u8];
+        field.extend_from_slice(s.as_bytes());
+        field
+    };
+    let mut data = CLOCK_DESCRIPTION.to_be_bytes().to_vec();
+    data.extend_from_slice(&0x8000u16.to_be_bytes());
+    data.extend(text("IEEE 802.3"));
+    data.extend_from_slice(&6u16.to_be_bytes());
+    data.extend_from_slice(&[0xec, 0x46, 0x70, 0x01, 0x02, 0x03]);
+    data.extend_from_slice(&[0, 1, 0, 4, 192, 0, 2, 10]);
+    data.extend_from_slice(&[0xec, 0x46, 0x70, 0]);
+    data.extend(text("Meinberg;LANTIME M1000;0123"));
+    data.extend(text("1.2;5.10;7.08"));
+    data.extend(text("gm-a;Rack 4"));
+    data.extend_from_slice(&[0x00, 0x1b, 0x19, 0x00, 0x01, 0x00]);
+    let mut response = request.clone();
+    response[46] = ACTION_RESPONSE;
+    response[48..50].copy_from_slice(&TLV_MANAGEMENT.to_be_bytes());
+    response[50..52].copy_from_slice(&(data.len() as u16).to_be_bytes());
+    response.truncate(52);
+    response.extend(data);
+
+    let (sequence_id, answer) = parse_response(&response).unwrap();
+    assert_eq!(sequence_id, 7);
+    let description = answer.description().unwrap();
+    assert_eq!(description.product(), "Meinberg LANTIME M1000");
+    assert_eq!(description.name(), Some("gm-a"));
+    assert_eq!(description.revisions(), "1.2/5.10/7.08");
+    assert_eq!(description.manufacturer_identity, [0xec, 0x46, 0x70]);
+
+    // Requests and truncated answers are not responses
+    assert_eq!(parse_response(&request), None);
+    assert_eq!(parse_response(&response[..70]), None);
+
+    let mut query = DescriptionQuery::Pending {
+        since: SystemTime::UNIX_EPOCH,
+        sequence_id: 7,
+    };
+    query.expire(SystemTime::UNIX_EPOCH + Duration::from_secs(1));
+    assert!(matches!(query, DescriptionQuery::Pending { .. }));
+    query.expire(SystemTime::UNIX_EPOCH + RESPONSE_TIMEOUT * 2);
+    assert_eq!(query, DescriptionQuery::NoResponse);
+}
@@ -0,0 +1,173 @@
+//! Boundary, ordinary and transparent clock classification
+//!
+//! The TT/TR state is what a host did last. A boundary clock is a receiver on
+//! its upstream port and a transmitter on its downstream ports, all with the
+//! same clock identity, so its state flips with every message and says
+//! little about the device. The ports and PTP instances a host sends
+//! transmitter messages (Announce, Sync) and receiver messages (Delay_Req)
+//! from do: an ordinary clock has a single port, a boundary clock sends
+//! transmitter messages from several ports, or receives on one port or
+//! instance while transmitting on another. A host that only ever takes part
+//! in 1588 peer delay exchanges is a peer-to-peer transparent clock.
+
+use std::{collections::BTreeSet, fmt::Display};
+
+use crate::types::{PtpHeader, PtpMessageType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ClockRole {
+    Ordinary,
+    Boundary,
+    Transparent,
+}
+
+impl ClockRole {
+    pub fn short_string(&self) -> &'static str {
+        match self {
+            ClockRole::Ordinary => "OC",
+            ClockRole::Boundary => "BC",
+            ClockRole::Transparent => "TC",
+        }
+    }
+}
+
+impl Display for ClockRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClockRole::Ordinary => write!(f, "Ordinary Clock"),
+            ClockRole::Boundary => write!(f, "Boundary Clock"),
+            ClockRole::Transparent => write!(f, "Transparent Clock"),
+        }
+    }
+}
+
+/// A port of the host in a PTP instance: (portNumber, (sdoId, domain))
+type PortInstance = (u16, (u16, u8));
+
+#[derive(Debug, Clone, Default)]
+pub struct RoleEvidence {
+    /// Ports and instances that sent Announce or Sync
+    transmitting: BTreeSet<PortInstance>,
+    /// Ports and instances that sent Delay_Req
+    receiving: BTreeSet<PortInstance>,
+    /// Ports that sent 1588 peer delay messages
+    peer_delay_ports: BTreeSet<u16>,
+}
+
+impl RoleEvidence {
+    pub fn record(&mut self, header: &PtpHeader) {
+        let port = (
+            header.source_port_identity.port_number,
+            (header.sdo_id, header.domain_number),
+        );
+        match header.message_type {
+            PtpMessageType::Announce | PtpMessageType::Sync => {
+                self.transmitting.insert(port);
+            }
+            PtpMessageType::DelayReq => {
+                self.receiving.insert(port);
+            }
+            // gPTP end stations only do peer delay as well, they are not TCs
+            PtpMessageType::PDelayReq
+            | PtpMessageType::PDelayResp
+            | PtpMessageType::PDelayRespFollowUp
+                if !header.is_gptp() && !header.is_cmlds() =>
+            {
+                self.peer_delay_ports.insert(port.0);
+            }
+            _ => {}
+        }
+    }
+
+    fn transmitting_ports(&self) -> BTreeSet<u16> {
+        self.transmitting.iter().map(|(port, _)| *port).collect()
+    }
+
+    /// None while the host was only heard listening
+    pub fn role(&self) -> Option<ClockRole> {
+        let downstream_elsewhere = self
+            .receiving
+            .iter()
+            .any(|upstream| self.transmitting.iter().any(|port| port != upstream));
+        if self.transmitting_ports().len() > 1 || downstream_elsewhere {
+            Some(ClockRole::Boundary)
+        } else if !self.transmitting.is_empty() || !self.receiving.is_empty() {
+            Some(ClockRole::Ordinary)
+        } else if !self.peer_delay_ports.is_empty() {
+            Some(ClockRole::Transparent)
+        } else {
+            None
+        }
+    }
+
+    /// What the role is based on, for the host details
+    pub fn describe(&self) -> String {
+        let ports = |ports: BTreeSet<u16>| {
+            ports
+                .iter()
+                .map(|port| port.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let receiving: BTreeSet<u16> = self.receiving.iter().map(|(port, _)| *port).collect();
+        let mut evidence = Vec::new();
+        if !self.transmitting.is_empty() {
+            evidence.push(format!(
+                "transmits on port {}",
+                ports(self.transmitting_ports())
+            ));
+        }
+        if !receiving.is_empty() {
+            evidence.push(format!("receives on port {}", ports(receiving)));
+        }
+        if evidence.is_empty() && !self.peer_delay_ports.is_empty() {
+            evidence.push(format!(
+                "only peer delay on port {}",
+                ports(self.peer_delay_ports.clone())
+            ));
+        }
+        evidence.join(", ")
+    }
+}
+
+#[test]
+fn test_clock_role() {
+    use crate::types::PtpMessage;
+
+    let header = |message_type: u8, domain: u8, port: u16| {
+        let mut data = [0u8; 64];
+        data[0] = message_type;
+        data[1] = 0x02;
+        data[2..4].copy_from_slice(&64u16.to_be_bytes());
+        data[4] = domain;
+        data[28..30].copy_from_slice(&port.to_be_bytes());
+        *PtpMessage::try_from(&data[..]).unwrap().header()
+    };
+    const SYNC: u8 = 0x0;
+    const DELAY_REQ: u8 = 0x1;
+    const PDELAY_REQ: u8 = 0x2;
+    const ANNOUNCE: u8 = 0xb;
+
+    let mut evidence = RoleEvidence::default();
+    assert_eq!(evidence.role(), None);
+    evidence.record(&header(PDELAY_REQ, 0, 1));
+    assert_eq!(evidence.role(), Some(ClockRole::Transparent));
+
+    // A GM that turned receiver on the same port is still an OC
+    evidence.record(&header(ANNOUNCE, 0, 1));
+    evidence.record(&header(DELAY_REQ, 0, 1));
+    assert_eq!(evidence.role(), Some(ClockRole::Ordinary));
+
+    // Receiving upstream in domain 0, transmitting downstream in domain 24
+    let mut evidence = RoleEvidence::default();
+    evidence.record(&header(DELAY_REQ, 0, 1));
+    evidence.record(&header(SYNC, 24, 1));
+    assert_eq!(evidence.role(), Some(ClockRole::Boundary));
+
+    // Transmitting from two ports
+    let mut evidence = RoleEvidence::default();
+    evidence.record(&header(ANNOUNCE, 0, 2));
+    evidence.record(&header(ANNOUNCE, 0, 3));
+    assert_eq!(evidence.role(), Some(ClockRole::Boundary));
+    assert_eq!(evidence.describe(), "transmits on port 2, 3");
+}
@@ -0,0 +1,744 @@
+//! Persistent settings, read from and written back to a TOML config file
+//!
+//! The config file provides the defaults for options that can also be given
+//! on the command line; command line flags take precedence. The settings view
+//! ('o') changes these values at runtime and can save them to the file.
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    alert_hooks::AlertHook,
+    bmca::BmcaProfile,
+    mute::MuteList,
+    ptp::PtpHost,
+    themes::{ColorDepth, ThemeName},
+    types::{ClockIdentity, PtpMessageType, find_tlv},
+};
+
+/// How packet and host timestamps are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeDisplayMode {
+    /// Time elapsed since the packet, e.g. "1.5s ago"
+    #[default]
+    Relative,
+    /// Wall clock time of the packet
+    Absolute,
+}
+
+impl Display for TimeDisplayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeDisplayMode::Relative => write!(f, "relative"),
+            TimeDisplayMode::Absolute => write!(f, "absolute"),
+        }
+    }
+}
+
+/// How densely the host table is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RowDensity {
+    /// Bordered table with full clock identities and a vendor column
+    #[default]
+    Normal,
+    /// No borders or padding, abbreviated clock identities and state names,
+    /// to fit more hosts on a laptop screen
+    Compact,
+}
+
+impl RowDensity {
+    pub fn is_compact(&self) -> bool {
+        *self == RowDensity::Compact
+    }
+}
+
+impl Display for RowDensity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RowDensity::Normal => write!(f, "normal"),
+            RowDensity::Compact => write!(f, "compact"),
+        }
+    }
+}
+
+/// Extra host table column showing a field of the last message of a type
+/// the host sent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomColumn {
+    pub title: String,
+    /// Message type, e.g. "announce" or "delay_resp"
+    pub message: String,
+    /// Label of the field as shown in the packet details, e.g. "Time Source"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Show the value of the first TLV of this type in hex instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tlv_type: Option<u16>,
+    /// Column width, defaults to the title width
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u16>,
+}
+
+impl CustomColumn {
+    pub fn message_type(&self) -> Option<PtpMessageType> {
+        PtpMessageType::from_name(&self.message)
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+            .unwrap_or_else(|| self.title.chars().count().max(8) as u16)
+    }
+
+    /// Value of the column for a host, None if the host did not send the
+    /// message or it lacks the field
+    pub fn value(&self, host: &PtpHost) -> Option<String> {
+        let packet = host.latest_packets.get(&self.message_type()?)?;
+        if let Some(tlv_type) = self.tlv_type {
+            let value = find_tlv(packet.ptp.header(), packet.raw.ptp_payload(), tlv_type)?;
+            return Some(value.iter().map(|byte| format!("{:02x}", byte)).collect());
+        }
+
+        let field = self.field.as_deref()?;
+        packet
+            .ptp
+            .details()
+            .into_iter()
+            .find(|(label, _)| label.eq_ignore_ascii_case(field))
+            .map(|(_, value)| value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub update_interval_ms: u64,
+    /// Bounds of the update interval when it follows the packet rate, the
+    /// interval is fixed while both are unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_update_interval_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_update_interval_ms: Option<u64>,
+    pub max_packet_history: usize,
+    pub auto_scroll: bool,
+    /// Update the display as soon as packets arrive, not only every interval
+    pub instant_redraw: bool,
+    pub time_display: TimeDisplayMode,
+    pub row_density: RowDensity,
+    /// Host table column with how long each host has been observed
+    pub observed_column: bool,
+    pub theme: String,
+    /// Colors the terminal can show: auto, truecolor, 256 or 16
+    pub colors: ColorDepth,
+    /// Hosts not seen for this long are removed, 0 keeps them forever
+    pub host_expiry_seconds: u64,
+    /// Human names for domain numbers, e.g. 0 = "House PTP". Keys are strings
+    /// because TOML table keys always are.
+    pub domain_aliases: BTreeMap<String, String>,
+    /// IANA timezone (e.g. "Europe/Berlin") to also show origin timestamps in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Routed hops multicast PTP may cross before an alert is raised, unset
+    /// disables the check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_multicast_hops: Option<u8>,
+    /// 802.1p priority VLAN tagged event messages must carry, unset disables
+    /// the check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_pcp: Option<u8>,
+    /// BMCA rules used to predict the PTT: auto, 1588, gptp or g8275.1
+    pub bmca_profile: String,
+    /// G.8275.1 localPriority of transmitters by clock identity, 128 if unset
+    pub local_priorities: BTreeMap<String, u8>,
+    /// Extra host table columns, after the built-in ones
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<CustomColumn>,
+    /// Keyboard macros by register, recorded with m<register> and replayed
+    /// with @<register>
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub macros: BTreeMap<String, String>,
+    /// Operator-defined tags of hosts by clock identity, set with 'E'
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub host_tags: BTreeMap<String, Vec<String>>,
+    /// Hosts left out of the table, statistics and alerts: "clock=<id>",
+    /// "vendor=TEXT" or "ip=<subnet>", clock rules are toggled with 'M'
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mute: Vec<String>,
+    /// Grandmaster clock identity each domain number should elect, any other
+    /// winner raises a critical alert
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub expected_grandmasters: BTreeMap<String, String>,
+    /// Backup of the expected grandmaster by domain number, compared with it
+    /// in the GM pair widget
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub backup_grandmasters: BTreeMap<String, String>,
+    /// Commands and webhooks run when alerts are raised
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alert_hooks: Vec<AlertHook>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            update_interval_ms: 1000,
+            min_update_interval_ms: None,
+            max_update_interval_ms: None,
+            max_packet_history: 1000,
+            auto_scroll: true,
+            instant_redraw: false,
+            time_display: TimeDisplayMode::Relative,
+            row_density: RowDensity::Normal,
+            observed_column: false,
+            theme: ThemeName::Auto.as_str().to_string(),
+            colors: ColorDepth::Auto,
+            host_expiry_seconds: 0,
+            domain_aliases: BTreeMap::new(),
+            timezone: None,
+            max_multicast_hops: None,
+            event_pcp: None,
+            bmca_profile: BmcaProfile::Auto.as_str().to_string(),
+            local_priorities: BTreeMap::new(),
+            columns: Vec::new(),
+            macros: BTreeMap::new(),
+            host_tags: BTreeMap::new(),
+            mute: Vec::new(),
+            expected_grandmasters: BTreeMap::new(),
+            backup_grandmasters: BTreeMap::new(),
+            alert_hooks: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Default config file location, e.g. ~/.config/ptp-trace/config.toml
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("ptp-trace").join("config.toml"))
+    }
+
+    /// Load the config file, falling back to defaults if it does not exist
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let config: Self = toml::from_str(&content)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        config
+            .backup_grandmasters()
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        MuteList::parse(&config.mute)
+            .map_err(anyhow::Error::msg)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        for hook in &config.alert_hooks {
+            hook.validate()
+                .map_err(anyhow::Error::msg)
+                .with_context(|| format!("Invalid config file {}", path.display()))?;
+        }
+        if let Some(pcp) = config.event_pcp
+            && pcp > 7
+        {
+            bail!(
+                "Invalid config file {}: event_pcp {} is not a 802.1p priority (0-7)",
+                path.display(),
+                pcp
+            );
+        }
+        Ok(config)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write config file {}", path.display()))
+    }
+
+    pub fn bmca_profile(&self) -> BmcaProfile {
+        BmcaProfile::from_str(&self.bmca_profile).unwrap_or_default()
+    }
+
+    pub fn theme_name(&self) -> ThemeName {
+        ThemeName::from_str(&self.theme).unwrap_or(ThemeName::Default)
+    }
+
+    /// Configured timezone for origin timestamps, None if unset or unknown
+    pub fn timezone(&self) -> Option<chrono_tz::Tz> {
+        self.timezone.as_deref()?.parse().ok()
+    }
+
+    /// Set the tags of a host, removing its entry if there are none
+    pub fn set_host_tags(&mut self, clock_identity: &str, tags: Vec<String>) {
+        if tags.is_empty() {
+            self.host_tags.remove(clock_identity);
+        } else {
+            self.host_tags.insert(clock_identity.to_string(), tags);
+        }
+    }
+
+    /// Expected grandmaster by domain number
+    pub fn expected_grandmasters(&self) -> Result<HashMap<u8, ClockIdentity>> {
+        grandmasters_by_domain(&self.expected_grandmasters, "expected_grandmasters")
+    }
+
+    /// Backup grandmaster by domain number. Each needs the expected
+    /// grandmaster of its domain as the primary.
+    pub fn backup_grandmasters(&self) -> Result<HashMap<u8, ClockIdentity>> {
+        let primaries = self.expected_grandmasters()?;
+        let backups = grandmasters_by_domain(&self.backup_grandmasters, "backup_grandmasters")?;
+        for (domain, backup) in &backups {
+            match primaries.get(domain) {
+                None => bail!(
+                    "backup_grandmasters: domain {} has no primary in expected_grandmasters",
+                    domain
+                ),
+                Some(primary) if primary == backup => bail!(
+                    "backup_grandmasters: {} is the primary of domain {} already",
+                    backup,
+                    domain
+                ),
+                Some(_) => {}
+            }
+        }
+        Ok(backups)
+    }
+
+    /// Domain number followed by its alias, if one is configured
+    pub fn domain_label(&self, domain_number: u8) -> String {
+        match self.domain_aliases.get(&domain_number.to_string()) {
+            Some(alias) => format!("{} ({})", domain_number, alias),
+            None => domain_number.to_string(),
+        }
+    }
+}
+
+/// Settings given on the command line, they take precedence over the config
+/// file, also when it is reloaded
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    pub update_interval_ms: Option<u64>,
+    pub min_update_interval_ms: Option<u64>,
+    pub max_update_interval_ms: Option<u64>,
+    pub theme: Option<String>,
+    pub colors: Option<ColorDepth>,
+    pub timezone: Option<String>,
+    pub bmca_profile: Option<String>,
+    pub max_multicast_hops: Option<u8>,
+    pub event_pcp: Option<u8>,
+    pub compact: bool,
+    pub instant_redraw: bool,
+    pub observed_column: bool,
+}
+
+impl ConfigOverrides {
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(update_interval) = self.update_interval_ms {
+            config.update_interval_ms = update_interval;
+        }
+        if let Some(min) = self.min_update_interval_ms {
+            config.min_update_interval_ms = Some(min);
+        }
+        if let Some(max) = self.max_update_interval_ms {
+            config.max_update_interval_ms = Some(max);
+        }
+        if let Some(theme) = &self.theme {
+            config.theme = theme.clone();
+        }
+        if let Some(colors) = self.colors {
+            config.colors = colors;
+        }
+        if let Some(timezone) = &self.timezone {
+            config.timezone = Some(timezone.clone());
+        }
+        if let Some(profile) = &self.bmca_profile {
+            config.bmca_profile = profile.clone();
+        }
+        if let Some(max_hops) = self.max_multicast_hops {
+            config.max_multicast_hops = Some(max_hops);
+        }
+        if let Some(pcp) = self.event_pcp {
+            config.event_pcp = Some(pcp);
+        }
+        if self.compact {
+            config.row_density = RowDensity::Compact;
+        }
+        if self.instant_redraw {
+            config.instant_redraw = true;
+        }
+        if self.observed_column {
+            config.observed_column = true;
+        }
+    }
+
+    /// Drop the command line setting of a field changed in the settings view,
+    /// the new value then also outlasts a reload of the config file
+    pub fn clear(&mut self, field: SettingsField) {
+        match field {
+            SettingsField::UpdateInterval => self.update_interval_ms = None,
+            SettingsField::Theme => self.theme = None,
+            SettingsField::Colors => self.colors = None,
+            SettingsField::RowDensity => self.compact = false,
+            SettingsField::InstantRedraw => self.instant_redraw = false,
+            SettingsField::ObservedColumn => self.observed_column = false,
+            SettingsField::HistoryDepth
+            | SettingsField::AutoScroll
+            | SettingsField::TimeDisplay
+            | SettingsField::HostExpiry => {}
+        }
+    }
+}
+
+/// Clock identities keyed by domain number, `name` being the config table
+fn grandmasters_by_domain(
+    table: &BTreeMap<String, String>,
+    name: &str,
+) -> Result<HashMap<u8, ClockIdentity>> {
+    table
+        .iter()
+        .map(|(domain, clock_identity)| {
+            let domain: u8 = domain
+                .parse()
+                .map_err(|_| anyhow!("Invalid domain number '{}' in {}", domain, name))?;
+            let clock_identity: ClockIdentity =
+                clock_identity.parse().map_err(|e| anyhow!("{}", e))?;
+            Ok((domain, clock_identity))
+        })
+        .collect()
+}
+
+/// Tags typed as a comma or space separated list, without duplicates
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split([',', ' ']).filter(|tag| !tag.is_empty()) {
+        if !tags.iter().any(|known| known.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Options that can be changed in the settings view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsField {
+    UpdateInterval,
+    HistoryDepth,
+    AutoScroll,
+    InstantRedraw,
+    TimeDisplay,
+    RowDensity,
+    ObservedColumn,
+    Theme,
+    Colors,
+    HostExpiry,
+}
+
+const UPDATE_INTERVAL_STEPS: &[u64] = &[100, 250, 500, 1000, 2000, 5000];
+const HISTORY_DEPTH_STEPS: &[usize] = &[100, 500, 1000, 5000, 10000];
+const HOST_EXPIRY_STEPS: &[u64] = &[0, 30, 60, 300, 600, 3600];
+
+/// Next (or previous) value from a list of steps, wrapping around. Values not
+/// in the list snap to the closest step in the requested direction.
+fn step<T: Copy + PartialOrd>(steps: &[T], current: T, forward: bool) -> T {
+    if forward {
+        steps
+            .iter()
+            .find(|&&s| s > current)
+            .copied()
+            .unwrap_or(steps[0])
+    } else {
+        steps
+            .iter()
+            .rev()
+            .find(|&&s| s < current)
+            .copied()
+            .unwrap_or(steps[steps.len() - 1])
+    }
+}
+
+impl SettingsField {
+    pub const ALL: [SettingsField; 10] = [
+        SettingsField::UpdateInterval,
+        SettingsField::HistoryDepth,
+        SettingsField::AutoScroll,
+        SettingsField::InstantRedraw,
+        SettingsField::TimeDisplay,
+        SettingsField::RowDensity,
+        SettingsField::ObservedColumn,
+        SettingsField::Theme,
+        SettingsField::Colors,
+        SettingsField::HostExpiry,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingsField::UpdateInterval => "Update interval",
+            SettingsField::HistoryDepth => "Packet history depth",
+            SettingsField::AutoScroll => "Auto-scroll packets",
+            SettingsField::InstantRedraw => "Instant redraw",
+            SettingsField::TimeDisplay => "Time display",
+            SettingsField::RowDensity => "Row density",
+            SettingsField::ObservedColumn => "Observed column",
+            SettingsField::Theme => "Theme",
+            SettingsField::Colors => "Colors",
+            SettingsField::HostExpiry => "Host expiry",
+        }
+    }
+
+    pub fn value(&self, config: &Config) -> String {
+        match self {
+            SettingsField::UpdateInterval => format!("{} ms", config.update_interval_ms),
+            SettingsField::HistoryDepth => format!("{} packets", config.max_packet_history),
+            SettingsField::AutoScroll => if config.auto_scroll { "on" } else { "off" }.to_string(),
+            SettingsField::InstantRedraw => {
+                if config.instant_redraw { "on" } else { "off" }.to_string()
+            }
+            SettingsField::TimeDisplay => config.time_display.to_string(),
+            SettingsField::RowDensity => config.row_density.to_string(),
+            SettingsField::ObservedColumn => {
+                if config.observed_column { "on" } else { "off" }.to_string()
+            }
+            SettingsField::Theme => match config.theme_name() {
+                ThemeName::Auto => format!(
+                    "auto ({})",
+                    crate::terminal_background::detected_theme().as_str()
+                ),
+                _ => config.theme.clone(),
+            },
+            SettingsField::Colors => match config.colors {
+                ColorDepth::Auto => format!("auto ({})", config.colors.resolve().as_str()),
+                depth => depth.as_str().to_string(),
+            },
+            SettingsField::HostExpiry => match config.host_expiry_seconds {
+                0 => "never".to_string(),
+                seconds => format!("{} s", seconds),
+            },
+        }
+    }
+
+    /// Copy the value from one config to another
+    pub fn copy(&self, from: &Config, to: &mut Config) {
+        match self {
+            SettingsField::UpdateInterval => to.update_interval_ms = from.update_interval_ms,
+            SettingsField::HistoryDepth => to.max_packet_history = from.max_packet_history,
+            SettingsField::AutoScroll => to.auto_scroll = from.auto_scroll,
+            SettingsField::InstantRedraw => to.instant_redraw = from.instant_redraw,
+            SettingsField::TimeDisplay => to.time_display = from.time_display,
+            SettingsField::RowDensity => to.row_density = from.row_density,
+            SettingsField::ObservedColumn => to.observed_column = from.observed_column,
+            SettingsField::Theme => to.theme = from.theme.clone(),
+            SettingsField::Colors => to.colors = from.colors,
+            SettingsField::HostExpiry => to.host_expiry_seconds = from.host_expiry_seconds,
+        }
+    }
+
+    /// Change the value to the next (or previous) choice
+    pub fn adjust(&self, config: &mut Config, forward: bool) {
+        match self {
+            SettingsField::UpdateInterval => {
+                config.update_interval_ms =
+                    step(UPDATE_INTERVAL_STEPS, config.update_interval_ms, forward)
+            }
+            SettingsField::HistoryDepth => {
+                config.max_packet_history =
+                    step(HISTORY_DEPTH_STEPS, config.max_packet_history, forward)
+            }
+            SettingsField::AutoScroll => config.auto_scroll = !config.auto_scroll,
+            SettingsField::InstantRedraw => config.instant_redraw = !config.instant_redraw,
+            SettingsField::ObservedColumn => config.observed_column = !config.observed_column,
+            SettingsField::TimeDisplay => {
+                config.time_display = match config.time_display {
+                    TimeDisplayMode::Relative => TimeDisplayMode::Absolute,
+                    TimeDisplayMode::Absolute => TimeDisplayMode::Relative,
+                }
+            }
+            SettingsField::RowDensity => {
+                config.row_density = match config.row_density {
+                    RowDensity::Normal => RowDensity::Compact,
+                    RowDensity::Compact => RowDensity::Normal,
+                }
+            }
+            SettingsField::Theme => {
+                let themes = ThemeName::all_themes();
+                let current = themes
+                    .iter()
+                    .position(|theme| *theme == config.theme_name())
+                    .unwrap_or(0);
+                let next = if forward {
+                    (current + 1) % themes.len()
+                } else {
+                    (current + themes.len() - 1) % themes.len()
+                };
+                config.theme = themes[next].as_str().to_string();
+            }
+            SettingsField::Colors => {
+                let depths = ColorDepth::all();
+                let current = depths
+                    .iter()
+                    .position(|depth| *depth == config.colors)
+                    .unwrap_or(0);
+                let next = if forward {
+                    (current + 1) % depths.len()
+                } else {
+                    (current + depths.len() - 1) % depths.len()
+                };
+                config.colors = depths[next];
+            }
+            SettingsField::HostExpiry => {
+                config.host_expiry_seconds =
+                    step(HOST_EXPIRY_STEPS, config.host_expiry_seconds, forward)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_settings_save() {
+    // Started with --compact and --theme, then the theme and the history
+    // depth changed in the settings view
+    let on_disk = Config::default();
+    let mut overrides = ConfigOverrides {
+        theme: Some("matrix".to_string()),
+        compact: true,
+        ..ConfigOverrides::default()
+    };
+    let mut running = on_disk.clone();
+    overrides.apply(&mut running);
+    let changed = [SettingsField::Theme, SettingsField::HistoryDepth];
+    for field in changed {
+        field.adjust(&mut running, true);
+        overrides.clear(field);
+    }
+
+    let mut saved = on_disk.clone();
+    for field in changed {
+        field.copy(&running, &mut saved);
+    }
+    assert_eq!(saved.theme, running.theme);
+    assert_eq!(saved.max_packet_history, running.max_packet_history);
+    // The command line --compact stays out of the file
+    assert_eq!(saved.row_density, RowDensity::Normal);
+    assert_eq!(running.row_density, RowDensity::Compact);
+
+    // A reload keeps the theme picked in the settings view
+    let mut reloaded = saved.clone();
+    overrides.apply(&mut reloaded);
+    assert_eq!(reloaded.theme, running.theme);
+    assert_eq!(reloaded.row_density, RowDensity::Compact);
+}
+
+#[test]
+fn test_config_round_trip() {
+    let mut config = Config::default();
+    SettingsField::UpdateInterval.adjust(&mut config, true);
+    SettingsField::HostExpiry.adjust(&mut config, false);
+    SettingsField::TimeDisplay.adjust(&mut config, true);
+    SettingsField::RowDensity.adjust(&mut config, true);
+    SettingsField::InstantRedraw.adjust(&mut config, true);
+    SettingsField::Colors.adjust(&mut config, false);
+    assert!(config.instant_redraw);
+    assert_eq!(config.colors, ColorDepth::Ansi16);
+    assert_eq!(config.update_interval_ms, 2000);
+    assert_eq!(config.host_expiry_seconds, 3600);
+
+    let parsed: Config = toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+    assert_eq!(parsed, config);
+
+    // Missing keys fall back to defaults
+    let partial: Config = toml::from_str("theme = \"matrix\"\ncolors = \"256\"").unwrap();
+    assert_eq!(partial.colors, ColorDepth::Ansi256);
+    assert_eq!(partial.theme_name(), ThemeName::Matrix);
+    assert_eq!(partial.max_packet_history, 1000);
+    assert_eq!(partial.row_density, RowDensity::Normal);
+
+    let aliased: Config =
+        toml::from_str("[domain_aliases]\n0 = \"House PTP\"\n127 = \"SMPTE\"").unwrap();
+    assert_eq!(aliased.domain_label(0), "0 (House PTP)");
+    assert_eq!(aliased.domain_label(127), "127 (SMPTE)");
+    assert_eq!(aliased.domain_label(1), "1");
+
+    assert_eq!(
+        parse_tags("studio-A, router  suspect,Router"),
+        ["studio-A", "router", "suspect"]
+    );
+    let mut tagged = Config::default();
+    tagged.set_host_tags("00:1b:19:ff:fe:12:34:56", parse_tags("studio-A"));
+    let parsed: Config = toml::from_str(&toml::to_string_pretty(&tagged).unwrap()).unwrap();
+    assert_eq!(parsed.host_tags, tagged.host_tags);
+    tagged.set_host_tags("00:1b:19:ff:fe:12:34:56", parse_tags(" , "));
+    assert!(tagged.host_tags.is_empty());
+
+    let zoned: Config = toml::from_str("timezone = \"America/New_York\"").unwrap();
+    assert_eq!(zoned.timezone(), Some(chrono_tz::America::New_York));
+    assert_eq!(Config::default().timezone(), None);
+
+    let columned: Config = toml::from_str(
+        "[[columns]]\ntitle = \"Source\"\nmessage = \"announce\"\nfield = \"Time Source\"\n\n\
+         [[columns]]\ntitle = \"Path\"\nmessage = \"ANNOUNCE\"\ntlv_type = 8\nwidth = 16",
+    )
+    .unwrap();
+    assert_eq!(columned.columns.len(), 2);
+    assert_eq!(
+        columned.columns[0].message_type(),
+        Some(PtpMessageType::Announce)
+    );
+    assert_eq!(columned.columns[0].width(), 8);
+    assert_eq!(columned.columns[1].width(), 16);
+    let parsed: Config = toml::from_str(&toml::to_string_pretty(&columned).unwrap()).unwrap();
+    assert_eq!(parsed, columned);
+
+    let designed: Config = toml::from_str(
+        "[expected_grandmasters]
+127 = \"00:1b:19:ff:fe:12:34:56\"",
+    )
+    .unwrap();
+    assert_eq!(
+        designed.expected_grandmasters().unwrap()[&127].to_string(),
+        "00:1b:19:ff:fe:12:34:56"
+    );
+    let misdesigned: Config = toml::from_str(
+        "[expected_grandmasters]
+256 = \"00:1b:19:ff:fe:12:34:56\"",
+    )
+    .unwrap();
+    assert!(misdesigned.expected_grandmasters().is_err());
+
+    let paired: Config = toml::from_str(
+        "[expected_grandmasters]
+127 = \"00:1b:19:ff:fe:12:34:56\"
+[backup_grandmasters]
+127 = \"00:1b:19:ff:fe:12:34:57\"",
+    )
+    .unwrap();
+    assert_eq!(paired.backup_grandmasters().unwrap().len(), 1);
+    // A backup needs a primary
+    let unpaired: Config = toml::from_str(
+        "[backup_grandmasters]
+0 = \"00:1b:19:ff:fe:12:34:57\"",
+    )
+    .unwrap();
+    assert!(unpaired.backup_grandmasters().is_err());
+}
+
+#[test]
+fn test_config_overrides() {
+    let mut config: Config =
+        toml::from_str("theme = \"matrix\"\nupdate_interval_ms = 500").unwrap();
+    let overrides = ConfigOverrides {
+        theme: Some("light".to_string()),
+        compact: true,
+        ..Default::default()
+    };
+    overrides.apply(&mut config);
+    assert_eq!(config.theme_name(), ThemeName::Light);
+    assert_eq!(config.row_density, RowDensity::Compact);
+    // Settings not given on the command line come from the file
+    assert_eq!(config.update_interval_ms, 500);
+}
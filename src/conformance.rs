@@ -0,0 +1,364 @@
+//! Protocol conformance score per host
+//!
+//! Every message a host sends runs through a handful of checks: lengths,
+//! flags, announced vs. observed message intervals, sequence id continuity
+//! and the sanity of origin timestamps. The score is the average pass rate of
+//! the checks that applied to the host, so a device that gets one thing
+//! consistently wrong ends up far below one that drops a message now and then,
+//! and the worst offenders on a network sort to the top.
+
+use std::{collections::HashMap, time::SystemTime};
+
+use crate::{
+    timescale,
+    types::{ParsedPacket, PtpMessage, PtpMessageType, PtpTimestamp},
+};
+
+/// Gaps larger than this are a restarted sender, not lost messages
+const MAX_SEQUENCE_GAP: u16 = 1000;
+/// logMessageInterval value meaning "not specified", e.g. in unicast mode
+const UNSPECIFIED_LOG_INTERVAL: i8 = 0x7f;
+/// Allowed deviation of an observed interval from the announced one
+/// (IEEE 1588-2019 7.7.2.1 asks for 90% of intervals within ±30%)
+const INTERVAL_TOLERANCE: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConformanceCheck {
+    MessageLength,
+    FlagConsistency,
+    IntervalConformance,
+    SequenceContinuity,
+    TimestampSanity,
+}
+
+impl ConformanceCheck {
+    pub const ALL: [ConformanceCheck; 5] = [
+        ConformanceCheck::MessageLength,
+        ConformanceCheck::FlagConsistency,
+        ConformanceCheck::IntervalConformance,
+        ConformanceCheck::SequenceContinuity,
+        ConformanceCheck::TimestampSanity,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConformanceCheck::MessageLength => "Lengths",
+            ConformanceCheck::FlagConsistency => "Flags",
+            ConformanceCheck::IntervalConformance => "Intervals",
+            ConformanceCheck::SequenceContinuity => "Sequence",
+            ConformanceCheck::TimestampSanity => "Timestamps",
+        }
+    }
+}
+
+/// Outcome of one check over all messages of a host
+#[derive(Debug, Clone, Default)]
+pub struct CheckTally {
+    pub passed: u32,
+    pub failed: u32,
+    /// What was wrong the last time the check failed
+    pub last_failure: Option<String>,
+}
+
+impl CheckTally {
+    fn pass_ratio(&self) -> Option<f64> {
+        let total = self.passed + self.failed;
+        (total > 0).then(|| self.passed as f64 / total as f64)
+    }
+}
+
+/// (port number, message type, sdoId, domain) of a message stream
+type StreamKey = (u16, PtpMessageType, u16, u8);
+
+#[derive(Debug, Clone, Default)]
+pub struct Conformance {
+    tallies: [CheckTally; 5],
+    /// Arrival time and sequence id of the last message of each stream
+    last_message: HashMap<StreamKey, (SystemTime, u16)>,
+    /// Sequence id and precise origin timestamp of the last one-step Sync or Follow_Up
+    last_origin: HashMap<StreamKey, (u16, PtpTimestamp)>,
+}
+
+fn nanoseconds(timestamp: PtpTimestamp) -> i128 {
+    timestamp.seconds as i128 * 1_000_000_000 + timestamp.nanoseconds as i128
+}
+
+impl Conformance {
+    fn tally(&mut self, check: ConformanceCheck, failure: Option<String>) {
+        let index = ConformanceCheck::ALL
+            .iter()
+            .position(|c| *c == check)
+            .unwrap_or_default();
+        let tally = &mut self.tallies[index];
+        match failure {
+            Some(failure) => {
+                tally.failed += 1;
+                tally.last_failure = Some(failure);
+            }
+            None => tally.passed += 1,
+        }
+    }
+
+    /// Run the checks that apply to a message the host sent
+    pub fn record(&mut self, packet: &ParsedPacket) {
+        let header = packet.ptp.header();
+        let message_type = header.message_type;
+        let key = (
+            header.source_port_identity.port_number,
+            message_type,
+            header.sdo_id,
+            header.domain_number,
+        );
+
+        self.tally(
+            ConformanceCheck::MessageLength,
+            packet
+                .length_issues()
+                .first()
+                .map(|issue| issue.to_string()),
+        );
+
+        // unicastFlag has to match how the message was addressed (UDP only)
+        let mut flag_failure = None;
+        let mut flags_checked = false;
+        if let Some(dest) = packet.raw.dest_addr {
+            flags_checked = true;
+            let multicast = dest.ip().is_multicast();
+            if header.flags.unicast() == multicast {
+                flag_failure = Some(format!(
+                    "{} unicastFlag is {} but it was sent to {} {}",
+                    message_type,
+                    if multicast { "set" } else { "clear" },
+                    if multicast { "multicast" } else { "unicast" },
+                    dest.ip()
+                ));
+            }
+        }
+        if let PtpMessage::Announce(announce) = &packet.ptp {
+            flags_checked = true;
+            if flag_failure.is_none() {
+                flag_failure = timescale::check(announce, packet.raw.timestamp)
+                    .first()
+                    .map(|issue| issue.to_string());
+            }
+        }
+        if flags_checked {
+            self.tally(ConformanceCheck::FlagConsistency, flag_failure);
+        }
+
+        if matches!(
+            message_type,
+            PtpMessageType::Sync
+                | PtpMessageType::Announce
+                | PtpMessageType::DelayReq
+                | PtpMessageType::PDelayReq
+        ) {
+            self.check_sequence(
+                key,
+                header.sequence_id,
+                header.log_message_interval.exponent,
+                packet.raw.timestamp,
+            );
+        }
+
+        let origin = match &packet.ptp {
+            PtpMessage::Sync(sync) if !header.flags.two_step() => Some(sync.origin_timestamp),
+            PtpMessage::FollowUp(follow_up) => Some(follow_up.precise_origin_timestamp),
+            _ => None,
+        };
+        if let Some(origin) = origin {
+            self.check_origin(key, header.sequence_id, origin);
+        }
+    }
+
+    fn check_sequence(
+        &mut self,
+        key: StreamKey,
+        sequence_id: u16,
+        log_interval: i8,
+        arrival: SystemTime,
+    ) {
+        let message_type = key.1;
+        let Some((last_arrival, last_id)) = self.last_message.get(&key).copied() else {
+            self.last_message.insert(key, (arrival, sequence_id));
+            return;
+        };
+
+        // The same message seen again, e.g. on another capture interface
+        let gap = sequence_id.wrapping_sub(last_id);
+        if gap == 0 {
+            return;
+        }
+        self.last_message.insert(key, (arrival, sequence_id));
+        if gap > MAX_SEQUENCE_GAP {
+            return;
+        }
+
+        if gap > 1 {
+            self.tally(
+                ConformanceCheck::SequenceContinuity,
+                Some(format!(
+                    "{} {} sequence id{} missing before {}",
+                    gap - 1,
+                    message_type,
+                    if gap > 2 { "s" } else { "" },
+                    sequence_id
+                )),
+            );
+            return;
+        }
+        self.tally(ConformanceCheck::SequenceContinuity, None);
+
+        // Delay requests are randomized and only bounded from below
+        if log_interval == UNSPECIFIED_LOG_INTERVAL
+            || !matches!(
+                message_type,
+                PtpMessageType::Sync | PtpMessageType::Announce
+            )
+        {
+            return;
+        }
+        let Ok(observed) = arrival.duration_since(last_arrival) else {
+            return;
+        };
+        let announced = 2f64.powi(log_interval as i32);
+        let deviation = (observed.as_secs_f64() - announced).abs() / announced;
+        self.tally(
+            ConformanceCheck::IntervalConformance,
+            (deviation > INTERVAL_TOLERANCE).then(|| {
+                format!(
+                    "{} after {:.3} s, logMessageInterval {} announces {:.3} s",
+                    message_type,
+                    observed.as_secs_f64(),
+                    log_interval,
+                    announced
+                )
+            }),
+        );
+    }
+
+    fn check_origin(&mut self, key: StreamKey, sequence_id: u16, origin: PtpTimestamp) {
+        if origin.nanoseconds >= 1_000_000_000 {
+            self.tally(
+                ConformanceCheck::TimestampSanity,
+                Some(format!(
+                    "{} origin timestamp has {} in its nanoseconds field",
+                    key.1, origin.nanoseconds
+                )),
+            );
+            return;
+        }
+
+        let previous = self.last_origin.insert(key, (sequence_id, origin));
+        let Some((previous_id, previous_origin)) = previous else {
+            return;
+        };
+        if previous_id == sequence_id {
+            return;
+        }
+        let step = nanoseconds(origin) - nanoseconds(previous_origin);
+        self.tally(
+            ConformanceCheck::TimestampSanity,
+            (step < 0).then(|| {
+                format!(
+                    "{} origin timestamp went back by {:.6} s",
+                    key.1,
+                    -step as f64 / 1e9
+                )
+            }),
+        );
+    }
+
+    /// 0 to 100, the average pass rate of the checks that applied. None
+    /// before any did.
+    pub fn score(&self) -> Option<u8> {
+        let ratios: Vec<f64> = self
+            .tallies
+            .iter()
+            .filter_map(CheckTally::pass_ratio)
+            .collect();
+        if ratios.is_empty() {
+            return None;
+        }
+        let mean = ratios.iter().sum::<f64>() / ratios.len() as f64;
+        // Any failure at all keeps the score below 100
+        Some((mean * 100.0).floor() as u8)
+    }
+
+    /// Checks that failed at least once, in the order of ConformanceCheck::ALL
+    pub fn failed_checks(&self) -> Vec<(ConformanceCheck, &CheckTally)> {
+        ConformanceCheck::ALL
+            .into_iter()
+            .zip(&self.tallies)
+            .filter(|(_, tally)| tally.failed > 0)
+            .collect()
+    }
+
+    pub fn checked_count(&self) -> usize {
+        self.tallies
+            .iter()
+            .filter(|tally| tally.pass_ratio().is_some())
+            .count()
+    }
+}
+
+#[test]
+fn test_conformance() {
+    use std::{sync::Arc, time::Duration};
+
+    use crate::source::RawPacket;
+
+    // Sync every 125 ms over UDP to the default multicast group
+    let sync = |sequence_id: u16, millis: u64, origin_seconds: u64| {
+        let mut payload = [0u8; 44];
+        payload[1] = 0x02;
+        payload[2..4].copy_from_slice(&44u16.to_be_bytes());
+        payload[30..32].copy_from_slice(&sequence_id.to_be_bytes());
+        payload[33] = (-3i8) as u8;
+        payload[36..40].copy_from_slice(&(origin_seconds as u32).to_be_bytes());
+        let raw = RawPacket {
+            source_addr: Some("192.0.2.1:319".parse().unwrap()),
+            dest_addr: Some("224.0.1.129:319".parse().unwrap()),
+            ..RawPacket::from_ptp_payload(
+                &payload,
+                "eth0",
+                SystemTime::UNIX_EPOCH + Duration::from_millis(millis),
+            )
+        };
+        ParsedPacket {
+            ptp: PtpMessage::try_from(&payload[..]).unwrap(),
+            raw: Arc::new(raw),
+        }
+    };
+
+    let mut conformance = Conformance::default();
+    assert_eq!(conformance.score(), None);
+    for i in 0..8 {
+        conformance.record(&sync(i, i as u64 * 125, 100 + i as u64));
+    }
+    assert_eq!(conformance.score(), Some(100));
+    assert!(conformance.failed_checks().is_empty());
+
+    // Two Syncs lost, then one late and with a timestamp going backwards
+    conformance.record(&sync(10, 10 * 125, 110));
+    conformance.record(&sync(11, 11 * 125 + 100, 50));
+    let failed: Vec<_> = conformance
+        .failed_checks()
+        .iter()
+        .map(|(check, _)| *check)
+        .collect();
+    assert_eq!(
+        failed,
+        [
+            ConformanceCheck::IntervalConformance,
+            ConformanceCheck::SequenceContinuity,
+            ConformanceCheck::TimestampSanity
+        ]
+    );
+    assert_eq!(
+        conformance.failed_checks()[1].1.last_failure.as_deref(),
+        Some("2 SYNC sequence ids missing before 10")
+    );
+    assert!(conformance.score().unwrap() < 100);
+    assert_eq!(conformance.checked_count(), 5);
+}
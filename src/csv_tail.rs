@@ -0,0 +1,303 @@
+//! Host table as a CSV log
+//!
+//! With --tail-csv, a row per host is appended to a CSV file every update
+//! interval, a machine-readable record of the session that can be plotted or
+//! diffed later. The file can be rotated by size or age: the current file is
+//! renamed with a timestamp, e.g. hosts_20240101T120000Z.csv, and a new one
+//! started with a header.
+
+use anyhow::{Context, Result};
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::ptp::{PtpHost, PtpHostState};
+
+const HEADER: &str = "time,clock_identity,state,domain,sdo_id,ip_addresses,vendor,ptt,\
+priority1,clock_class,clock_accuracy,offset_scaled_log_variance,priority2,steps_removed,\
+utc_offset,announce,sync,follow_up,delay_req,delay_resp,pdelay_req,pdelay_resp,\
+pdelay_resp_follow_up,length_errors,bursts,last_seen";
+
+/// When to start a new file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    Size(u64),
+    Age(Duration),
+}
+
+impl Rotation {
+    /// Parse a size with a unit ("500KB", "100MB", "1GB") or an age ("30m", "1h", "1d")
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let split = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(split);
+        let number: u64 = number
+            .parse()
+            .map_err(|_| format!("Invalid rotation '{}', expected e.g. 100MB or 1h", text))?;
+        if number == 0 {
+            return Err("Rotation must be larger than 0".to_string());
+        }
+
+        let rotation = match unit.to_ascii_uppercase().as_str() {
+            "B" => Rotation::Size(number),
+            "KB" => Rotation::Size(number << 10),
+            "MB" => Rotation::Size(number << 20),
+            "GB" => Rotation::Size(number << 30),
+            _ => match unit {
+                "s" => Rotation::Age(Duration::from_secs(number)),
+                "m" => Rotation::Age(Duration::from_secs(number * 60)),
+                "h" => Rotation::Age(Duration::from_secs(number * 3600)),
+                "d" => Rotation::Age(Duration::from_secs(number * 86400)),
+                _ => {
+                    return Err(format!(
+                        "Invalid rotation unit '{}', expected B, KB, MB, GB, s, m, h or d",
+                        unit
+                    ));
+                }
+            },
+        };
+        Ok(rotation)
+    }
+}
+
+/// Quote a field if it contains a separator, quote or line break
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn format_time(time: SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time)
+        .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+        .to_string()
+}
+
+fn host_row(host: &PtpHost, time: SystemTime) -> String {
+    let optional = |value: Option<String>| value.unwrap_or_default();
+
+    let mut ip_addresses: Vec<String> = host.ip_addresses.keys().map(|ip| ip.to_string()).collect();
+    ip_addresses.sort();
+
+    let (state, ptt, transmitter) = match &host.state {
+        PtpHostState::Listening if host.is_p2p_peer() => ("p2p-peer", None, None),
+        PtpHostState::Listening => ("listening", None, None),
+        PtpHostState::TimeTransmitter(s) => ("transmitter", s.ptt_identifier, Some(s)),
+        PtpHostState::TimeReceiver(s) => ("receiver", s.selected_transmitter_identity, None),
+    };
+
+    let fields = [
+        format_time(time),
+        host.clock_identity.to_string(),
+        state.to_string(),
+        optional(host.domain_number.map(|d| d.to_string())),
+        optional(host.sdo_id.map(|s| s.to_string())),
+        ip_addresses.join(" "),
+        host.get_vendor_name().unwrap_or_default().to_string(),
+        optional(ptt.map(|id| id.to_string())),
+        optional(transmitter.and_then(|t| t.priority1).map(|p| p.to_string())),
+        optional(
+            transmitter
+                .and_then(|t| t.clock_class)
+                .map(|c| c.class().to_string()),
+        ),
+        optional(
+            transmitter
+                .and_then(|t| t.clock_accuracy)
+                .map(|a| format!("0x{:02x}", a.accuracy)),
+        ),
+        optional(
+            transmitter
+                .and_then(|t| t.offset_scaled_log_variance)
+                .map(|v| format!("0x{:04x}", v)),
+        ),
+        optional(transmitter.and_then(|t| t.priority2).map(|p| p.to_string())),
+        optional(
+            transmitter
+                .and_then(|t| t.steps_removed)
+                .map(|s| s.to_string()),
+        ),
+        optional(
+            transmitter
+                .and_then(|t| t.current_utc_offset)
+                .map(|o| o.offset.to_string()),
+        ),
+        host.announce_count.to_string(),
+        host.sync_count.to_string(),
+        host.follow_up_count.to_string(),
+        host.delay_req_count.to_string(),
+        host.delay_resp_count.to_string(),
+        host.pdelay_req_count.to_string(),
+        host.pdelay_resp_count.to_string(),
+        host.pdelay_resp_follow_up_count.to_string(),
+        host.length_issue_count.to_string(),
+        host.bursts.burst_count.to_string(),
+        format_time(host.last_seen),
+    ];
+
+    fields
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub struct CsvTail {
+    path: PathBuf,
+    rotation: Option<Rotation>,
+    file: File,
+    size: u64,
+    opened: SystemTime,
+    /// Files rotated away this session, oldest first
+    rotated_files: Vec<PathBuf>,
+}
+
+impl CsvTail {
+    /// Append to the file, starting it with a header if it is new or empty
+    pub fn open(path: &Path, rotation: Option<Rotation>) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open CSV file {}", path.display()))?;
+        let mut tail = Self {
+            path: path.to_path_buf(),
+            rotation,
+            size: file.metadata()?.len(),
+            file,
+            opened: SystemTime::now(),
+            rotated_files: Vec::new(),
+        };
+        if tail.size == 0 {
+            tail.write_line(HEADER)?;
+        }
+        Ok(tail)
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        writeln!(self.file, "{}", line)
+            .with_context(|| format!("Failed to write CSV file {}", self.path.display()))?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    /// Files written this session, the current one last
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = self.rotated_files.clone();
+        files.push(self.path.clone());
+        files
+    }
+
+    /// Name the current file is moved to when rotating
+    fn rotated_path(&self, time: SystemTime) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let timestamp = chrono::DateTime::<chrono::Utc>::from(time).format("%Y%m%dT%H%M%SZ");
+        let name = match self.path.extension() {
+            Some(extension) => format!("{}_{}.{}", stem, timestamp, extension.to_string_lossy()),
+            None => format!("{}_{}", stem, timestamp),
+        };
+        self.path.with_file_name(name)
+    }
+
+    fn rotate_if_due(&mut self, now: SystemTime) -> Result<()> {
+        let due = match self.rotation {
+            None => false,
+            Some(Rotation::Size(size)) => self.size >= size,
+            Some(Rotation::Age(age)) => now.duration_since(self.opened).unwrap_or_default() >= age,
+        };
+        if !due {
+            return Ok(());
+        }
+
+        let rotated = self.rotated_path(now);
+        std::fs::rename(&self.path, &rotated).with_context(|| {
+            format!(
+                "Failed to rotate {} to {}",
+                self.path.display(),
+                rotated.display()
+            )
+        })?;
+        let mut rotated_files = std::mem::take(&mut self.rotated_files);
+        rotated_files.push(rotated);
+        *self = Self::open(&self.path, self.rotation)?;
+        self.opened = now;
+        self.rotated_files = rotated_files;
+        Ok(())
+    }
+
+    /// Append a row for each host
+    pub fn append<'a>(
+        &mut self,
+        hosts: impl IntoIterator<Item = &'a PtpHost>,
+        now: SystemTime,
+    ) -> Result<()> {
+        self.rotate_if_due(now)?;
+        for host in hosts {
+            self.write_line(&host_row(host, now))?;
+        }
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_csv_tail() {
+    use crate::types::ClockIdentity;
+
+    assert_eq!(Rotation::parse("100MB"), Ok(Rotation::Size(100 << 20)));
+    assert_eq!(
+        Rotation::parse("2h"),
+        Ok(Rotation::Age(Duration::from_secs(7200)))
+    );
+    assert!(Rotation::parse("2M").is_err());
+    assert!(Rotation::parse("0d").is_err());
+    assert!(Rotation::parse("MB").is_err());
+
+    assert_eq!(csv_field("Cisco Systems, Inc"), "\"Cisco Systems, Inc\"");
+    assert_eq!(csv_field("a\"b"), "\"a\"\"b\"");
+
+    let dir = std::env::temp_dir().join(format!("ptp-trace-csv-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("hosts.csv");
+    let host = PtpHost::new(ClockIdentity {
+        clock_id: [0x02, 0x00, 0x00, 0xff, 0xfe, 0x00, 0x00, 0x01],
+    });
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+    // The first append fills the file past 100 bytes, the second starts a new one
+    let mut tail = CsvTail::open(&path, Some(Rotation::Size(100))).unwrap();
+    tail.append([&host], start).unwrap();
+    tail.append([&host], start + Duration::from_secs(1))
+        .unwrap();
+
+    let files = tail.files();
+    assert_eq!(files.last(), Some(&path));
+    assert!(files.contains(&dir.join("hosts_20231114T221321Z.csv")));
+    let current = std::fs::read_to_string(&path).unwrap();
+    let rotated = std::fs::read_to_string(dir.join("hosts_20231114T221321Z.csv")).unwrap();
+    for content in [&current, &rotated] {
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], HEADER);
+        assert_eq!(
+            lines[1].split(',').count(),
+            HEADER.split(',').count(),
+            "{}",
+            lines[1]
+        );
+    }
+    assert!(current.contains("02:00:00:ff:fe:00:00:01,listening"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,146 @@
+//! Duplicate suppression for mirrored traffic
+//!
+//! A SPAN port that mirrors both ingress and egress of a switch port delivers
+//! every PTP message twice. Duplicates are detected by hashing the PTP payload
+//! (which is the same in both copies, even if encapsulation differs, e.g. one
+//! copy VLAN tagged and the other not) with the interface it arrived on, and
+//! remembering the hashes seen within a short time window. The same message
+//! seen on another interface is not a mirrored copy but another arrival path,
+//! and is kept.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::{DefaultHasher, Hash, Hasher},
+    time::{Duration, SystemTime},
+};
+
+use crate::source::RawPacket;
+
+/// Mirrored copies of a packet arrive within this window of each other
+const DEDUP_WINDOW: Duration = Duration::from_millis(50);
+/// Upper bound on remembered hashes, regardless of timestamps
+const MAX_TRACKED_HASHES: usize = 4096;
+
+pub struct PacketDeduplicator {
+    enabled: bool,
+    seen: HashMap<u64, SystemTime>,
+    order: VecDeque<(u64, SystemTime)>,
+    duplicate_count: u64,
+}
+
+impl PacketDeduplicator {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            duplicate_count: 0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.seen.clear();
+        self.order.clear();
+    }
+
+    /// Number of packets dropped as duplicates so far
+    pub fn get_duplicate_count(&self) -> u64 {
+        self.duplicate_count
+    }
+
+    /// Check whether the packet is a copy of one seen within the window.
+    /// Duplicates are counted; first copies are remembered.
+    pub fn is_duplicate(&mut self, raw_packet: &RawPacket) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        let mut hasher = DefaultHasher::new();
+        raw_packet.ptp_payload().hash(&mut hasher);
+        raw_packet.interface_name.hash(&mut hasher);
+        let hash = hasher.finish();
+        let timestamp = raw_packet.timestamp;
+
+        // Forget hashes that fell out of the window
+        while let Some(&(old_hash, old_timestamp)) = self.order.front() {
+            let age = timestamp.duration_since(old_timestamp).unwrap_or_default();
+            if age <= DEDUP_WINDOW && self.order.len() < MAX_TRACKED_HASHES {
+                break;
+            }
+            self.order.pop_front();
+            if self.seen.get(&old_hash) == Some(&old_timestamp) {
+                self.seen.remove(&old_hash);
+            }
+        }
+
+        if self.seen.contains_key(&hash) {
+            self.duplicate_count += 1;
+            return true;
+        }
+
+        self.seen.insert(hash, timestamp);
+        self.order.push_back((hash, timestamp));
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(payload: &[u8], timestamp: SystemTime) -> RawPacket {
+        RawPacket::from_ptp_payload(payload, "eth0", timestamp)
+    }
+
+    #[test]
+    fn test_duplicates_within_window() {
+        let now = SystemTime::now();
+        let mut dedup = PacketDeduplicator::new(true);
+
+        assert!(!dedup.is_duplicate(&packet(&[1, 2, 3], now)));
+        assert!(dedup.is_duplicate(&packet(&[1, 2, 3], now + Duration::from_millis(1))));
+        assert!(!dedup.is_duplicate(&packet(&[4, 5, 6], now + Duration::from_millis(2))));
+        assert_eq!(dedup.get_duplicate_count(), 1);
+
+        // Same payload again after the window is not a duplicate
+        assert!(!dedup.is_duplicate(&packet(&[1, 2, 3], now + Duration::from_secs(1))));
+
+        // The same message on another interface is another arrival path
+        let mut other_interface = packet(&[4, 5, 6], now + Duration::from_millis(3));
+        other_interface.interface_name = "eth1".to_string();
+        assert!(!dedup.is_duplicate(&other_interface));
+        assert!(dedup.is_duplicate(&other_interface));
+        assert_eq!(dedup.get_duplicate_count(), 2);
+
+        dedup.set_enabled(false);
+        assert!(!dedup.is_duplicate(&packet(&[1, 2, 3], now + Duration::from_secs(1))));
+        assert_eq!(dedup.get_duplicate_count(), 2);
+    }
+
+    #[test]
+    fn test_duplicates_across_vlan_tagging() {
+        let now = SystemTime::now();
+        let mut dedup = PacketDeduplicator::new(true);
+
+        // Ingress copy tagged, egress copy untagged or retagged by the switch
+        let tagged = RawPacket {
+            vlan_id: Some(100),
+            vlan_pcp: Some(7),
+            ..packet(&[1, 2, 3], now)
+        };
+        let untagged = packet(&[1, 2, 3], now + Duration::from_millis(1));
+        let retagged = RawPacket {
+            vlan_id: Some(200),
+            ..packet(&[1, 2, 3], now + Duration::from_millis(2))
+        };
+        assert!(!dedup.is_duplicate(&tagged));
+        assert!(dedup.is_duplicate(&untagged));
+        assert!(dedup.is_duplicate(&retagged));
+        assert_eq!(dedup.get_duplicate_count(), 2);
+    }
+}
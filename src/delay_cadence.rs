@@ -0,0 +1,246 @@
+//! Receiver loss of lock, inferred from its Delay_Req cadence
+//!
+//! Receivers rarely announce their distress, their Delay_Req cadence is the
+//! best passive sign of it. A locked receiver sends Delay_Req at a steady
+//! interval (logMinDelayReqInterval). When its servo loses lock it either
+//! stops asking (reset, port back to LISTENING or FAULTY) or starts hunting,
+//! asking much faster while it acquires again. Either, while the syncs of its
+//! domain keep coming, is taken as a probable loss of lock on the receiver's
+//! side rather than a grandmaster or network problem.
+
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
+
+/// Intervals the usual cadence is the median of
+const CADENCE_HISTORY: usize = 16;
+/// Intervals needed before the cadence is judged
+const MIN_CADENCE_HISTORY: usize = 8;
+/// Below this the cadence is not judged, e.g. a pcap file without capture times
+const MIN_USUAL_INTERVAL: Duration = Duration::from_millis(7);
+/// Intervals this many times shorter than usual count as hunting
+const HUNTING_FACTOR: u32 = 4;
+/// Consecutive short intervals that make hunting
+const HUNTING_REQUESTS: usize = 4;
+/// Silence this many times the usual interval counts as stopped
+const STOP_FACTOR: u32 = 8;
+/// Shortest silence counted as stopped
+const MIN_STOP_SILENCE: Duration = Duration::from_secs(2);
+/// Loss of lock events kept per receiver
+const MAX_LOCK_EVENTS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockLoss {
+    /// No Delay_Req for many usual intervals
+    Stopped,
+    /// Delay_Req much faster than usual, at this interval
+    Hunting(Duration),
+}
+
+/// A probable loss of lock of a receiver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockEvent {
+    pub kind: LockLoss,
+    /// Capture time of the last request at the usual cadence
+    pub at: SystemTime,
+    pub usual_interval: Duration,
+    /// Capture time the cadence was back to usual
+    pub ended: Option<SystemTime>,
+}
+
+impl Display for LockEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            LockLoss::Stopped => write!(f, "Delay_Req stopped")?,
+            LockLoss::Hunting(interval) => {
+                write!(f, "Delay_Req hunting every {:.3} s", interval.as_secs_f64())?
+            }
+        }
+        write!(f, " (usual {:.3} s)", self.usual_interval.as_secs_f64())?;
+        if let Some(ended) = self.ended {
+            write!(
+                f,
+                ", back after {:.1} s",
+                ended
+                    .duration_since(self.at)
+                    .unwrap_or_default()
+                    .as_secs_f64()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// What a Delay_Req or the silence in between changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CadenceChange {
+    Lost(LockEvent),
+    Recovered(LockEvent),
+}
+
+/// Delay_Req cadence of a receiver and the losses of lock it showed
+#[derive(Debug, Clone, Default)]
+pub struct DelayReqCadence {
+    /// Intervals at the usual cadence, newest last
+    intervals: VecDeque<Duration>,
+    last_request: Option<SystemTime>,
+    /// Short intervals in a row, and the first request of them
+    short_streak: Option<(usize, SystemTime)>,
+    /// Whether the last event has not ended yet
+    lost: bool,
+    pub events: VecDeque<LockEvent>,
+    pub event_count: u32,
+}
+
+impl DelayReqCadence {
+    /// Median interval between requests, once there are enough of them
+    pub fn usual_interval(&self) -> Option<Duration> {
+        if self.intervals.len() < MIN_CADENCE_HISTORY {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.intervals.iter().copied().collect();
+        sorted.sort();
+        Some(sorted[sorted.len() / 2]).filter(|usual| *usual >= MIN_USUAL_INTERVAL)
+    }
+
+    /// Record a Delay_Req sent at `at`; `syncs_flowing` is whether the
+    /// domain's syncs are still coming in
+    pub fn record_request(&mut self, at: SystemTime, syncs_flowing: bool) -> Option<CadenceChange> {
+        let previous = self.last_request.replace(at)?;
+        let interval = at.duration_since(previous).unwrap_or_default();
+        let usual = self.usual_interval();
+
+        if usual.is_some_and(|usual| interval < usual / HUNTING_FACTOR) {
+            let (streak, since) = self.short_streak.get_or_insert((0, previous));
+            *streak += 1;
+            if *streak >= HUNTING_REQUESTS && !self.lost && syncs_flowing {
+                let since = *since;
+                return Some(CadenceChange::Lost(self.lose(
+                    LockLoss::Hunting(interval),
+                    since,
+                    usual?,
+                )));
+            }
+            return None;
+        }
+
+        self.short_streak = None;
+        // The silence of a stop is no usual interval
+        if !self.lost {
+            if self.intervals.len() == CADENCE_HISTORY {
+                self.intervals.pop_front();
+            }
+            self.intervals.push_back(interval);
+        }
+        self.recover(at).map(CadenceChange::Recovered)
+    }
+
+    /// Check for requests having stopped by `now`
+    pub fn check_silence(&mut self, now: SystemTime, syncs_flowing: bool) -> Option<CadenceChange> {
+        let last = self.last_request?;
+        let usual = self.usual_interval()?;
+        let silence = now.duration_since(last).unwrap_or_default();
+        if self.lost || !syncs_flowing || silence < (usual * STOP_FACTOR).max(MIN_STOP_SILENCE) {
+            return None;
+        }
+        Some(CadenceChange::Lost(self.lose(
+            LockLoss::Stopped,
+            last,
+            usual,
+        )))
+    }
+
+    fn lose(&mut self, kind: LockLoss, at: SystemTime, usual_interval: Duration) -> LockEvent {
+        let event = LockEvent {
+            kind,
+            at,
+            usual_interval,
+            ended: None,
+        };
+        self.lost = true;
+        self.event_count += 1;
+        if self.events.len() == MAX_LOCK_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+        event
+    }
+
+    fn recover(&mut self, at: SystemTime) -> Option<LockEvent> {
+        if !self.lost {
+            return None;
+        }
+        self.lost = false;
+        let event = self.events.back_mut()?;
+        event.ended = Some(at);
+        Some(*event)
+    }
+
+    /// Whether the last loss of lock is still going on
+    pub fn is_lost(&self) -> bool {
+        self.lost
+    }
+}
+
+#[test]
+fn test_delay_req_cadence() {
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let at = |millis: u64| start + Duration::from_millis(millis);
+    let mut cadence = DelayReqCadence::default();
+
+    // Steady at 1 s
+    for second in 0..10 {
+        assert_eq!(cadence.record_request(at(second * 1000), true), None);
+    }
+    assert_eq!(cadence.usual_interval(), Some(Duration::from_secs(1)));
+    assert_eq!(cadence.check_silence(at(12_000), true), None);
+
+    // Silent for 9 s while syncs keep coming
+    let Some(CadenceChange::Lost(event)) = cadence.check_silence(at(18_000), true) else {
+        panic!("stop not detected");
+    };
+    assert_eq!(event.kind, LockLoss::Stopped);
+    assert_eq!(event.at, at(9000));
+    assert_eq!(cadence.check_silence(at(19_000), true), None);
+    let Some(CadenceChange::Recovered(event)) = cadence.record_request(at(20_000), true) else {
+        panic!("recovery not detected");
+    };
+    assert_eq!(
+        event.to_string(),
+        "Delay_Req stopped (usual 1.000 s), back after 11.0 s"
+    );
+    // The silence does not count as a usual interval
+    assert_eq!(cadence.usual_interval(), Some(Duration::from_secs(1)));
+
+    // Hunting at 100 ms
+    for request in 1..=3 {
+        assert_eq!(
+            cadence.record_request(at(20_000 + request * 100), true),
+            None
+        );
+    }
+    let Some(CadenceChange::Lost(event)) = cadence.record_request(at(20_400), true) else {
+        panic!("hunting not detected");
+    };
+    assert_eq!(event.kind, LockLoss::Hunting(Duration::from_millis(100)));
+    assert_eq!(event.at, at(20_000));
+    assert!(cadence.is_lost());
+    assert!(matches!(
+        cadence.record_request(at(21_400), true),
+        Some(CadenceChange::Recovered(_))
+    ));
+    assert_eq!(cadence.event_count, 2);
+
+    // Nothing is blamed on the receiver while the syncs are gone too
+    assert_eq!(cadence.check_silence(at(40_000), false), None);
+
+    // Requests without capture times are not judged
+    let mut pcap = DelayReqCadence::default();
+    for _ in 0..20 {
+        pcap.record_request(start, true);
+    }
+    assert_eq!(pcap.usual_interval(), None);
+    assert_eq!(pcap.check_silence(at(60_000), true), None);
+}
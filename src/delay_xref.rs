@@ -0,0 +1,147 @@
+//! Cross-reference of Delay_Req and Delay_Resp messages
+//!
+//! Every Delay_Resp names the port whose Delay_Req it answers, with the same
+//! sequenceId. A response to a port never seen sending Delay_Reqs points at a
+//! transmitter with a stale unicast configuration, or at traffic reflected
+//! back onto the network. A Delay_Req nobody answers means the receiver cannot
+//! measure its path delay, however good its Syncs are. Both only hold where
+//! the capture sees both directions, which is the case for multicast E2E.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use crate::types::{ParsedPacket, PortIdentity};
+
+/// A Delay_Req not answered within this time counts as unanswered
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Delay_Reqs remembered per port while waiting for their Delay_Resp
+const MAX_PENDING_REQUESTS: usize = 16;
+
+/// Delay_Reqs awaiting their Delay_Resp, and the ports seen requesting
+#[derive(Debug, Default)]
+pub struct DelayCrossReference {
+    requesters: HashSet<PortIdentity>,
+    pending: HashMap<PortIdentity, VecDeque<Arc<ParsedPacket>>>,
+    /// Time of the first message, responses to requests sent before the
+    /// capture started are not held against anyone
+    started: Option<SystemTime>,
+    last_expiry: Option<SystemTime>,
+}
+
+impl DelayCrossReference {
+    pub fn record_delay_req(&mut self, packet: &Arc<ParsedPacket>) {
+        self.started.get_or_insert(packet.raw.timestamp);
+        let port = packet.ptp.header().source_port_identity;
+        self.requesters.insert(port);
+        let pending = self.pending.entry(port).or_default();
+        pending.push_back(packet.clone());
+        if pending.len() > MAX_PENDING_REQUESTS {
+            pending.pop_front();
+        }
+    }
+
+    /// Match a Delay_Resp with its Delay_Req, true if it answers a port that
+    /// was never seen sending one
+    pub fn record_delay_resp(
+        &mut self,
+        requesting_port: PortIdentity,
+        packet: &ParsedPacket,
+    ) -> bool {
+        let arrival = packet.raw.timestamp;
+        let started = *self.started.get_or_insert(arrival);
+        if let Some(pending) = self.pending.get_mut(&requesting_port) {
+            let sequence_id = packet.ptp.header().sequence_id;
+            pending.retain(|request| request.ptp.header().sequence_id != sequence_id);
+        }
+        !self.requesters.contains(&requesting_port)
+            && arrival
+                .duration_since(started)
+                .is_ok_and(|elapsed| elapsed > RESPONSE_TIMEOUT)
+    }
+
+    /// Delay_Reqs that went unanswered for longer than the response timeout,
+    /// checked at most once per timeout
+    pub fn expire(&mut self, now: SystemTime) -> Vec<Arc<ParsedPacket>> {
+        if self
+            .last_expiry
+            .and_then(|last| now.duration_since(last).ok())
+            .is_some_and(|since| since < RESPONSE_TIMEOUT)
+        {
+            return Vec::new();
+        }
+        self.last_expiry = Some(now);
+
+        let mut expired = Vec::new();
+        for pending in self.pending.values_mut() {
+            while let Some(request) = pending.front()
+                && now
+                    .duration_since(request.raw.timestamp)
+                    .is_ok_and(|age| age > RESPONSE_TIMEOUT)
+            {
+                expired.extend(pending.pop_front());
+            }
+        }
+        self.pending.retain(|_, pending| !pending.is_empty());
+        expired
+    }
+}
+
+#[test]
+fn test_delay_cross_reference() {
+    use crate::{
+        source::RawPacket,
+        types::{ClockIdentity, PtpMessage},
+    };
+
+    let port = |id: u8| PortIdentity {
+        clock_identity: ClockIdentity { clock_id: [id; 8] },
+        port_number: 1,
+    };
+    // A Delay_Req sent by `port`, or a Delay_Resp answering it
+    let packet = |response: bool, id: u8, sequence_id: u16, millis: u64| {
+        let mut payload = [0u8; 54];
+        payload[0] = if response { 0x09 } else { 0x01 };
+        payload[1] = 0x02;
+        payload[2..4].copy_from_slice(&54u16.to_be_bytes());
+        payload[30..32].copy_from_slice(&sequence_id.to_be_bytes());
+        if response {
+            payload[20..28].copy_from_slice(&[0xaa; 8]);
+            payload[44..52].copy_from_slice(&[id; 8]);
+            payload[53] = 1;
+        } else {
+            payload[20..28].copy_from_slice(&[id; 8]);
+            payload[29] = 1;
+        }
+        Arc::new(ParsedPacket {
+            ptp: PtpMessage::try_from(&payload[..]).unwrap(),
+            raw: Arc::new(RawPacket::from_ptp_payload(
+                &payload,
+                "eth0",
+                SystemTime::UNIX_EPOCH + Duration::from_millis(millis),
+            )),
+        })
+    };
+    let mut xref = DelayCrossReference::default();
+
+    // Answered right after the capture started, possibly to a request sent before
+    xref.record_delay_req(&packet(false, 1, 1, 0));
+    assert!(!xref.record_delay_resp(port(1), &packet(true, 1, 1, 5)));
+    assert!(!xref.record_delay_resp(port(2), &packet(true, 2, 7, 500)));
+
+    // Port 1 asks again but gets no answer
+    xref.record_delay_req(&packet(false, 1, 2, 1000));
+    assert!(
+        xref.expire(SystemTime::UNIX_EPOCH + Duration::from_millis(2500))
+            .is_empty()
+    );
+    let expired = xref.expire(SystemTime::UNIX_EPOCH + Duration::from_millis(5000));
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].ptp.header().sequence_id, 2);
+
+    // Port 2 never asked
+    assert!(xref.record_delay_resp(port(2), &packet(true, 2, 8, 6000)));
+    assert!(!xref.record_delay_resp(port(1), &packet(true, 1, 3, 6000)));
+}
@@ -0,0 +1,77 @@
+//! Fields of the host details pane, for copying one of them
+//!
+//! The host details are rendered as lines of a label padded to a fixed width
+//! followed by the value, with headings, blank lines and indented table rows
+//! in between. With the pane focused its top line is highlighted, and 'y'
+//! copies the value of that line instead of all details.
+
+/// A line of the host details, split into label and value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetailsField {
+    /// Label without the colon, empty for lines without one
+    pub label: String,
+    pub value: String,
+}
+
+/// The field on line `index` of the rendered host details, None for a blank
+/// line or past the end. Lines without a label, such as table rows and
+/// headings, are taken as a whole.
+pub fn details_field(details: &str, index: usize) -> Option<DetailsField> {
+    let line = details.lines().nth(index)?;
+    if line.trim().is_empty() {
+        return None;
+    }
+
+    // Labels start at the margin, indented lines are continuations or rows
+    if !line.starts_with(char::is_whitespace)
+        && let Some((label, value)) = line.split_once(": ")
+        && !value.trim().is_empty()
+    {
+        return Some(DetailsField {
+            label: label.trim().to_string(),
+            value: value.trim().to_string(),
+        });
+    }
+    Some(DetailsField {
+        label: String::new(),
+        value: line.trim().to_string(),
+    })
+}
+
+#[test]
+fn test_details_field() {
+    let details = [
+        "Clock Identity:   00:1b:19:ff:fe:12:34:56",
+        "Priority1:        128",
+        "",
+        "Org TLV:          SMPTE ST 2059-2 in ANNOUNCE",
+        "  Frame rate:     30000/1001",
+        "Last 3 Announces:",
+        "Time",
+    ]
+    .join("\n");
+    let field = |index| details_field(&details, index);
+
+    // The value without the label, colons in the value stay
+    assert_eq!(
+        field(0),
+        Some(DetailsField {
+            label: "Clock Identity".to_string(),
+            value: "00:1b:19:ff:fe:12:34:56".to_string(),
+        })
+    );
+    assert_eq!(field(1).unwrap().value, "128");
+    assert_eq!(field(2), None);
+    assert_eq!(field(3).unwrap().value, "SMPTE ST 2059-2 in ANNOUNCE");
+    // Indented rows and headings are copied whole
+    assert_eq!(
+        field(4),
+        Some(DetailsField {
+            label: String::new(),
+            value: "Frame rate:     30000/1001".to_string(),
+        })
+    );
+    assert_eq!(field(5).unwrap().value, "Last 3 Announces:");
+    assert_eq!(field(6).unwrap().value, "Time");
+    assert_eq!(field(7), None);
+}
@@ -0,0 +1,391 @@
+//! Self-test of a capture host ("ptp-trace doctor")
+//!
+//! Setting up a capture box is often done by remote hands, and the usual
+//! problems (no raw socket privileges, the wrong interfaces, a switch or
+//! firewall that blocks the multicast join) only show up as an empty host
+//! table. The doctor subcommand runs the same steps as a capture would, one at
+//! a time, and prints a pass/fail checklist with a hint for each failure.
+
+use anyhow::{Result, bail};
+use pnet::datalink::{self, Channel, Config, NetworkInterface};
+use std::{
+    fmt::Display,
+    net::{Ipv4Addr, UdpSocket},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    interface_spec::{AvailableInterface, InterfaceSpec},
+    pcap_export,
+    source::{self, PTP_EVENT_PORT, RawPacket},
+};
+
+/// How long to wait for the loopback test frame to be captured
+const LOOPBACK_TIMEOUT: Duration = Duration::from_secs(2);
+/// Sequence id of the test Sync, to recognize it among other loopback traffic
+const TEST_SEQUENCE_ID: u16 = 0xd0c7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    /// Capturing works, but not everywhere it may be expected to
+    Warn,
+    Fail,
+    /// Not run, it would send on the network (--offline)
+    Skip,
+}
+
+impl CheckStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+            CheckStatus::Skip => "SKIP",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl Check {
+    pub fn new(name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        }
+    }
+
+    fn skipped_offline(name: impl Into<String>) -> Self {
+        Check::new(name, CheckStatus::Skip, "skipped (offline)")
+    }
+
+    fn from_result(name: impl Into<String>, result: Result<String>) -> Self {
+        match result {
+            Ok(detail) => Check::new(name, CheckStatus::Pass, detail),
+            Err(e) => Check::new(name, CheckStatus::Fail, e.to_string()),
+        }
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] {:<28} {}",
+            self.status.label(),
+            self.name,
+            self.detail
+        )
+    }
+}
+
+/// Sync message with the test sequence id, the rest of it is irrelevant
+fn test_sync() -> [u8; 44] {
+    let mut sync = [0u8; 44];
+    sync[1] = 0x02;
+    sync[2..4].copy_from_slice(&44u16.to_be_bytes());
+    sync[30..32].copy_from_slice(&TEST_SEQUENCE_ID.to_be_bytes());
+    sync
+}
+
+/// The test Sync in an Ethernet/IPv4/UDP frame to the PTP multicast address
+fn test_frame() -> Vec<u8> {
+    let sync = test_sync();
+    let mut frame = vec![0x01, 0x00, 0x5e, 0x00, 0x01, 0x81, 0x02, 0, 0, 0, 0, 1];
+    frame.extend_from_slice(&0x0800u16.to_be_bytes());
+    frame.extend_from_slice(&[0x45, 0x00]);
+    frame.extend_from_slice(&((20 + 8 + sync.len()) as u16).to_be_bytes());
+    frame.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x01, 0x11, 0x00, 0x00]);
+    frame.extend_from_slice(&[192, 0, 2, 1, 224, 0, 1, 129]);
+    frame.extend_from_slice(&PTP_EVENT_PORT.to_be_bytes());
+    frame.extend_from_slice(&PTP_EVENT_PORT.to_be_bytes());
+    frame.extend_from_slice(&((8 + sync.len()) as u16).to_be_bytes());
+    frame.extend_from_slice(&[0x00, 0x00]);
+    frame.extend_from_slice(&sync);
+    frame
+}
+
+fn is_test_packet(packet: &RawPacket) -> bool {
+    packet.ptp_payload() == test_sync()
+}
+
+fn loopback_interface() -> Option<NetworkInterface> {
+    datalink::interfaces()
+        .into_iter()
+        .find(|iface| iface.is_loopback())
+}
+
+#[cfg(unix)]
+fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    unsafe { libc::geteuid() == 0 }
+}
+
+/// Elsewhere whether raw sockets can be opened is all that is checked
+#[cfg(not(unix))]
+fn is_root() -> bool {
+    false
+}
+
+fn check_privileges() -> Check {
+    if is_root() {
+        return Check::new("Privileges", CheckStatus::Pass, "running as root");
+    }
+    let Some(loopback) = loopback_interface() else {
+        return Check::new(
+            "Privileges",
+            CheckStatus::Warn,
+            "not root and no loopback interface to test raw sockets on",
+        );
+    };
+    match datalink::channel(&loopback, Config::default()) {
+        Ok(_) => Check::new(
+            "Privileges",
+            CheckStatus::Pass,
+            "not root, but raw sockets are allowed (CAP_NET_RAW)",
+        ),
+        Err(e) => Check::new(
+            "Privileges",
+            CheckStatus::Fail,
+            format!(
+                "cannot open a raw socket ({}), run as root or grant CAP_NET_RAW and CAP_NET_ADMIN, e.g. with setcap cap_net_raw,cap_net_admin=eip",
+                e
+            ),
+        ),
+    }
+}
+
+/// Interfaces that would be captured on with the given -i specs, or all of
+/// them if none are given
+fn check_interfaces(specs: &[InterfaceSpec]) -> (Vec<Check>, Vec<(String, Ipv4Addr)>) {
+    let mut checks = Vec::new();
+    let mut multicast_candidates = Vec::new();
+    for iface in datalink::interfaces() {
+        if iface.is_loopback() {
+            continue;
+        }
+        let available = AvailableInterface {
+            name: iface.name.clone(),
+            ips: iface.ips.iter().map(|ip| ip.ip()).collect(),
+        };
+        let selected = if specs.is_empty() {
+            source::is_suitable_interface_name(&iface.name)
+        } else {
+            specs.iter().any(|spec| spec.matches(&available))
+        };
+        if !selected && !specs.is_empty() {
+            continue;
+        }
+
+        let name = format!("Interface {}", iface.name);
+        let addr = available.ipv4();
+        let check = if !selected {
+            Check::new(
+                name,
+                CheckStatus::Warn,
+                "skipped by default as a virtual interface, select it with --interface",
+            )
+        } else if !iface.is_up() {
+            // Only a problem if it was asked for
+            let status = if specs.is_empty() {
+                CheckStatus::Warn
+            } else {
+                CheckStatus::Fail
+            };
+            Check::new(name, status, "interface is down")
+        } else if !iface.is_running() {
+            Check::new(name, CheckStatus::Warn, "up, but no carrier")
+        } else {
+            match addr {
+                Some(addr) => Check::new(name, CheckStatus::Pass, format!("up, {}", addr)),
+                None => Check::new(
+                    name,
+                    CheckStatus::Warn,
+                    "up, but without an IPv4 address the multicast group is not joined",
+                ),
+            }
+        };
+        if selected && let Some(addr) = addr {
+            multicast_candidates.push((iface.name.clone(), addr));
+        }
+        checks.push(check);
+    }
+
+    if checks.iter().all(|check| check.status != CheckStatus::Pass) {
+        checks.push(Check::new(
+            "Interfaces",
+            CheckStatus::Fail,
+            "no interface ready to capture on",
+        ));
+    }
+    (checks, multicast_candidates)
+}
+
+fn check_multicast_join(interface_name: &str, addr: Ipv4Addr) -> Check {
+    Check::from_result(
+        format!("Multicast join {}", interface_name),
+        source::join_multicast_group(interface_name, addr)
+            .map(|_| "joined 224.0.1.129".to_string()),
+    )
+}
+
+/// Send the test Sync to ourselves over loopback and capture it, the whole
+/// capture and decode path except for the network
+fn check_loopback_frame() -> Result<String> {
+    let Some(loopback) = loopback_interface() else {
+        bail!("no loopback interface");
+    };
+    let config = Config {
+        read_timeout: Some(Duration::from_millis(100)),
+        ..Config::default()
+    };
+    let mut rx = match datalink::channel(&loopback, config) {
+        Ok(Channel::Ethernet(_, rx)) => rx,
+        Ok(_) => bail!("unsupported channel type on {}", loopback.name),
+        Err(e) => bail!("cannot capture on {}: {}", loopback.name, e),
+    };
+
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?;
+    socket.send_to(&test_sync(), (Ipv4Addr::LOCALHOST, PTP_EVENT_PORT))?;
+
+    let deadline = Instant::now() + LOOPBACK_TIMEOUT;
+    while Instant::now() < deadline {
+        // Timeouts come back as errors, keep waiting until the deadline
+        if let Ok(frame) = rx.next()
+            && source::process_ethernet_packet(frame, &loopback.name)
+                .is_some_and(|packet| is_test_packet(&packet))
+        {
+            return Ok(format!("sent and captured a Sync on {}", loopback.name));
+        }
+    }
+    bail!(
+        "a Sync sent to 127.0.0.1:{} was not captured on {} within {:?}",
+        PTP_EVENT_PORT,
+        loopback.name,
+        LOOPBACK_TIMEOUT
+    )
+}
+
+/// Write the test frame to a pcap file and read it back
+fn check_pcap_read() -> Result<String> {
+    use pcap_file::pcap::PcapReader;
+
+    let path = std::env::temp_dir().join(format!("ptp-trace-doctor-{}.pcap", std::process::id()));
+    let packet = source::process_ethernet_packet(&test_frame(), "doctor")
+        .ok_or_else(|| anyhow::anyhow!("the test frame was not decoded as PTP"))?;
+    let packet = RawPacket {
+        timestamp: SystemTime::now(),
+        ..packet
+    };
+    let result = (|| {
+        pcap_export::write_pcap(&path, [&packet])?;
+        let mut reader = PcapReader::new(std::fs::File::open(&path)?)?;
+        let Some(read) = reader.next_packet() else {
+            bail!("the pcap file is empty");
+        };
+        match source::process_ethernet_packet(&read?.data, "pcap") {
+            Some(packet) if is_test_packet(&packet) => {
+                Ok(format!("wrote and read back {}", path.display()))
+            }
+            _ => bail!("the packet read back is not the test Sync"),
+        }
+    })();
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Run all checks, capturing on the interfaces selected by the -i specs.
+/// Offline the multicast joins and the loopback frame are skipped, they send
+/// IGMP reports and a Sync.
+pub fn run_checks(interfaces: &[String], offline: bool) -> Result<Vec<Check>> {
+    let specs = interfaces
+        .iter()
+        .map(|spec| InterfaceSpec::parse(spec).map_err(anyhow::Error::msg))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut checks = vec![check_privileges()];
+    let (interface_checks, multicast_candidates) = check_interfaces(&specs);
+    checks.extend(interface_checks);
+    for (name, addr) in multicast_candidates {
+        checks.push(if offline {
+            Check::skipped_offline(format!("Multicast join {}", name))
+        } else {
+            check_multicast_join(&name, addr)
+        });
+    }
+    checks.push(if offline {
+        Check::skipped_offline("Loopback test frame")
+    } else {
+        Check::from_result("Loopback test frame", check_loopback_frame())
+    });
+    checks.push(Check::from_result("Pcap read", check_pcap_read()));
+    Ok(checks)
+}
+
+/// Print the checklist, failing if any check failed
+pub fn run(interfaces: &[String], offline: bool) -> Result<()> {
+    let checks = run_checks(interfaces, offline)?;
+    println!("ptp-trace doctor");
+    for check in &checks {
+        println!("{}", check);
+    }
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .count();
+    if failed > 0 {
+        bail!("{} of {} checks failed", failed, checks.len());
+    }
+    let count = |status| checks.iter().filter(|check| check.status == status).count();
+    let (warnings, skipped) = (count(CheckStatus::Warn), count(CheckStatus::Skip));
+    println!(
+        "{} checks passed, {} with warnings, {} skipped",
+        checks.len() - warnings - skipped,
+        warnings,
+        skipped
+    );
+    Ok(())
+}
+
+#[test]
+fn test_doctor_test_frame() {
+    let packet = source::process_ethernet_packet(&test_frame(), "eth0").unwrap();
+    assert!(is_test_packet(&packet));
+    assert_eq!(packet.dest_addr.unwrap().port(), PTP_EVENT_PORT);
+
+    let check = Check::from_result("Pcap read", Err(anyhow::anyhow!("the pcap file is empty")));
+    assert_eq!(
+        check.to_string(),
+        "[FAIL] Pcap read                    the pcap file is empty"
+    );
+}
+
+#[test]
+fn test_doctor_offline() {
+    let checks = run_checks(&[], true).unwrap();
+    let loopback = checks
+        .iter()
+        .find(|check| check.name == "Loopback test frame")
+        .unwrap();
+    assert_eq!(loopback.status, CheckStatus::Skip);
+    assert_eq!(
+        loopback.to_string(),
+        "[SKIP] Loopback test frame          skipped (offline)"
+    );
+    // Nothing is joined, however many interfaces there are
+    assert!(
+        checks
+            .iter()
+            .filter(|check| check.name.starts_with("Multicast join"))
+            .all(|check| check.status == CheckStatus::Skip)
+    );
+    // Reading a pcap file back sends nothing
+    assert!(checks.iter().any(|check| check.name == "Pcap read"));
+}
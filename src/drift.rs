@@ -0,0 +1,218 @@
+//! Frequency offset of a time transmitter relative to the capture host
+//!
+//! Each Sync pairs the transmitter's origin timestamp (from the Follow_Up for
+//! two-step clocks) with the time the capture host received it. The offset
+//! between the two moves linearly with the difference of the oscillator
+//! frequencies, so a least-squares fit of the offset against the arrival time
+//! over a window gives the frequency offset in ppm, and the scatter around the
+//! fit its uncertainty. A grandmaster whose oscillator starts to wander shows
+//! up here long before it gives up its clock class. The result is relative to
+//! the capture host's clock, which is itself only as good as its discipline.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::types::PtpTimestamp;
+
+/// Samples older than this, relative to the newest one, are dropped
+const DRIFT_WINDOW: Duration = Duration::from_secs(300);
+/// Syncs arriving closer than this to the previous sample are skipped, which
+/// keeps the window bounded at high Sync rates
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+/// Two-step Syncs remembered while waiting for their Follow_Up
+const MAX_PENDING_SYNCS: usize = 8;
+/// An offset jump larger than this is a step of either clock, not drift, and
+/// restarts the estimate
+const STEP_NS: i128 = 1_000_000;
+/// Samples needed before an estimate is given
+const MIN_SAMPLES: usize = 10;
+/// Time spanned by the samples before an estimate is given
+const MIN_SPAN: Duration = Duration::from_secs(10);
+
+fn system_time_ns(time: SystemTime) -> i128 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_nanos() as i128,
+        Err(e) => -(e.duration().as_nanos() as i128),
+    }
+}
+
+/// Frequency offset estimated from the Syncs in the window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriftEstimate {
+    /// Transmitter frequency relative to the capture host, positive when the
+    /// transmitter runs fast
+    pub ppm: f64,
+    /// Half-width of the ~95% confidence interval, in ppm
+    pub bound_ppm: f64,
+    pub samples: usize,
+    pub span: Duration,
+}
+
+/// Origin and arrival timestamps of a transmitter's Syncs
+#[derive(Debug, Clone, Default)]
+pub struct SyncDrift {
+    /// Arrival time of two-step Syncs, by sequence id
+    pending: VecDeque<(u16, i128)>,
+    /// (arrival ns, origin ns - arrival ns), oldest first
+    samples: VecDeque<(i128, i128)>,
+}
+
+impl SyncDrift {
+    /// Record a Sync. One-step Syncs carry the origin timestamp, two-step ones
+    /// wait for their Follow_Up.
+    pub fn record_sync(
+        &mut self,
+        sequence_id: u16,
+        origin: PtpTimestamp,
+        two_step: bool,
+        arrival: SystemTime,
+    ) {
+        let arrival_ns = system_time_ns(arrival);
+        if two_step {
+            self.pending.push_back((sequence_id, arrival_ns));
+            if self.pending.len() > MAX_PENDING_SYNCS {
+                self.pending.pop_front();
+            }
+        } else {
+            self.push(arrival_ns, origin.total_nanoseconds() as i128);
+        }
+    }
+
+    /// Record a Follow_Up, completing the two-step Sync with the same sequence id
+    pub fn record_follow_up(&mut self, sequence_id: u16, precise_origin: PtpTimestamp) {
+        let Some(index) = self
+            .pending
+            .iter()
+            .position(|(pending_id, _)| *pending_id == sequence_id)
+        else {
+            return;
+        };
+        let (_, arrival_ns) = self.pending.remove(index).unwrap_or_default();
+        self.push(arrival_ns, precise_origin.total_nanoseconds() as i128);
+    }
+
+    fn push(&mut self, arrival_ns: i128, origin_ns: i128) {
+        let offset_ns = origin_ns - arrival_ns;
+        if let Some(&(last_arrival, last_offset)) = self.samples.back() {
+            if arrival_ns < last_arrival + SAMPLE_INTERVAL.as_nanos() as i128 {
+                return;
+            }
+            if (offset_ns - last_offset).abs() > STEP_NS {
+                self.samples.clear();
+            }
+        }
+        self.samples.push_back((arrival_ns, offset_ns));
+        let oldest = arrival_ns - DRIFT_WINDOW.as_nanos() as i128;
+        while self
+            .samples
+            .front()
+            .is_some_and(|&(arrival, _)| arrival < oldest)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// (arrival ns, origin ns - arrival ns) of the most recent sample
+    pub fn latest(&self) -> Option<(i128, i128)> {
+        self.samples.back().copied()
+    }
+
+    /// Least-squares fit of the offset against the arrival time, None until
+    /// enough Syncs over a long enough time were seen
+    pub fn estimate(&self) -> Option<DriftEstimate> {
+        let &(first_arrival, first_offset) = self.samples.front()?;
+        let &(last_arrival, _) = self.samples.back()?;
+        let span = Duration::from_nanos((last_arrival - first_arrival) as u64);
+        if self.samples.len() < MIN_SAMPLES || span < MIN_SPAN {
+            return None;
+        }
+
+        // Seconds and nanoseconds relative to the first sample keep the sums
+        // well within f64 precision
+        let points: Vec<(f64, f64)> = self
+            .samples
+            .iter()
+            .map(|&(arrival, offset)| {
+                (
+                    (arrival - first_arrival) as f64 / 1e9,
+                    (offset - first_offset) as f64,
+                )
+            })
+            .collect();
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        let sxy: f64 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let slope = sxy / sxx;
+        let residuals: f64 = points
+            .iter()
+            .map(|(x, y)| (y - mean_y - slope * (x - mean_x)).powi(2))
+            .sum();
+        let standard_error = (residuals / (n - 2.0) / sxx).sqrt();
+
+        // ns per second is ppb
+        Some(DriftEstimate {
+            ppm: slope / 1000.0,
+            bound_ppm: 2.0 * standard_error / 1000.0,
+            samples: points.len(),
+            span,
+        })
+    }
+}
+
+#[test]
+fn test_sync_drift() {
+    let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let timestamp = |ns: u128| PtpTimestamp {
+        seconds: (ns / 1_000_000_000) as u64,
+        nanoseconds: (ns % 1_000_000_000) as u32,
+    };
+    let mut drift = SyncDrift::default();
+
+    // A two-step transmitter running 5 ppm fast with +-20 us of arrival jitter,
+    // 37 s ahead of the capture host (TAI against UTC)
+    for seq in 0..120u16 {
+        let elapsed_ns = seq as u128 * 500_000_000;
+        let jitter_ns = [0, 20_000, -20_000, 10_000][seq as usize % 4];
+        let arrival = start + Duration::from_nanos((elapsed_ns as i128 + jitter_ns) as u64);
+        let origin = timestamp(1_700_000_037_000_000_000 + elapsed_ns + elapsed_ns * 5 / 1_000_000);
+        drift.record_sync(seq, timestamp(0), true, arrival);
+        drift.record_follow_up(seq, origin);
+        if seq == 15 {
+            // 8 s of Syncs are not enough
+            assert_eq!(drift.estimate(), None);
+        }
+    }
+    let estimate = drift.estimate().unwrap();
+    assert_eq!(estimate.samples, 120);
+    assert!((estimate.ppm - 5.0).abs() < estimate.bound_ppm);
+    assert!(estimate.bound_ppm < 0.5);
+
+    // Syncs closer together than the sample interval are thinned out
+    drift.record_sync(
+        0,
+        timestamp(1_700_000_096_500_000_000),
+        false,
+        start + Duration::from_millis(59_600),
+    );
+    assert_eq!(drift.estimate().unwrap().samples, 120);
+
+    // A Follow_Up without its Sync is ignored
+    drift.record_follow_up(999, timestamp(0));
+    assert_eq!(drift.estimate().unwrap().samples, 120);
+
+    // A step of the transmitter restarts the estimate
+    drift.record_sync(
+        0,
+        timestamp(1_700_000_137_000_000_000),
+        false,
+        start + Duration::from_secs(61),
+    );
+    assert_eq!(drift.estimate(), None);
+}
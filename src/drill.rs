@@ -0,0 +1,216 @@
+//! Failover drills: timing how long a domain takes to recover from losing its PTT
+//!
+//! A drill is armed on the current PTT of a domain. The PTT counts as lost when
+//! it stops sending Sync for longer than the announce receipt timeout, or as
+//! soon as another transmitter wins the election. The loss is dated to its last
+//! Sync, and from there the drill measures:
+//! - how long until another transmitter of the domain sends Sync, i.e. took over
+//! - per receiver, how long until a new transmitter answers its Delay_Req
+//!
+//! Receivers using peer delay measurement don't reveal which transmitter they
+//! follow, so their switch is never observed.
+
+use std::{
+    collections::BTreeMap,
+    time::{Duration, SystemTime},
+};
+
+use crate::types::ClockIdentity;
+
+/// A PTT silent for this long is lost (announceReceiptTimeout of 3 default intervals)
+pub const DRILL_LOSS_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrillLoss {
+    /// No Sync from the PTT within the timeout
+    Silent,
+    /// Another transmitter won the election
+    LostElection,
+}
+
+impl DrillLoss {
+    pub fn description(&self) -> &'static str {
+        match self {
+            DrillLoss::Silent => "went silent",
+            DrillLoss::LostElection => "lost the election",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FailoverDrill {
+    pub instance: (u16, u8),
+    pub old_ptt: ClockIdentity,
+    /// Last Sync of the old PTT, the moment the loss is dated to
+    last_ptt_sync: SystemTime,
+    pub loss: Option<DrillLoss>,
+    /// First other transmitter sending Sync since the last Sync of the old PTT
+    pub new_ptt: Option<(ClockIdentity, SystemTime)>,
+    /// First Delay_Resp from another transmitter per receiver, since the last
+    /// Sync of the old PTT
+    pub receivers: BTreeMap<ClockIdentity, Option<(ClockIdentity, SystemTime)>>,
+    completion_reported: bool,
+}
+
+impl FailoverDrill {
+    pub fn new(
+        instance: (u16, u8),
+        old_ptt: ClockIdentity,
+        receivers: impl IntoIterator<Item = ClockIdentity>,
+        started: SystemTime,
+    ) -> Self {
+        Self {
+            instance,
+            old_ptt,
+            // Until the next Sync, arming counts as seeing the PTT alive
+            last_ptt_sync: started,
+            loss: None,
+            new_ptt: None,
+            receivers: receivers.into_iter().map(|id| (id, None)).collect(),
+            completion_reported: false,
+        }
+    }
+
+    /// When the old PTT was lost, if it was
+    pub fn lost_at(&self) -> Option<SystemTime> {
+        self.loss.map(|_| self.last_ptt_sync)
+    }
+
+    pub fn record_sync(
+        &mut self,
+        instance: (u16, u8),
+        sender: ClockIdentity,
+        timestamp: SystemTime,
+    ) {
+        if instance != self.instance {
+            return;
+        }
+
+        if sender == self.old_ptt {
+            // Still alive, anything seen so far was not a takeover
+            if self.loss.is_none() {
+                self.last_ptt_sync = timestamp;
+                self.new_ptt = None;
+                self.receivers
+                    .values_mut()
+                    .for_each(|switch| *switch = None);
+            }
+            return;
+        }
+
+        if self.new_ptt.is_none() {
+            self.new_ptt = Some((sender, timestamp));
+        }
+        self.check_silence(timestamp);
+    }
+
+    pub fn record_delay_resp(
+        &mut self,
+        instance: (u16, u8),
+        sender: ClockIdentity,
+        receiver: ClockIdentity,
+        timestamp: SystemTime,
+    ) {
+        if instance != self.instance || sender == self.old_ptt {
+            return;
+        }
+
+        let switch = self.receivers.entry(receiver).or_default();
+        if switch.is_none() {
+            *switch = Some((sender, timestamp));
+        }
+    }
+
+    /// Declare the PTT lost if it has been silent for too long at `now`
+    pub fn check_silence(&mut self, now: SystemTime) {
+        if self.loss.is_none()
+            && now.duration_since(self.last_ptt_sync).unwrap_or_default() > DRILL_LOSS_TIMEOUT
+        {
+            self.loss = Some(DrillLoss::Silent);
+        }
+    }
+
+    /// The election in the domain picked `winner`
+    pub fn record_election(&mut self, winner: ClockIdentity) {
+        if self.loss.is_none() && winner != self.old_ptt {
+            self.loss = Some(DrillLoss::LostElection);
+        }
+    }
+
+    /// Time from the loss until another transmitter took over
+    pub fn election_time(&self) -> Option<Duration> {
+        let lost_at = self.lost_at()?;
+        let (_, elected_at) = self.new_ptt?;
+        Some(elected_at.duration_since(lost_at).unwrap_or_default())
+    }
+
+    /// Time from the loss until a receiver was served by another transmitter
+    pub fn switch_time(&self, receiver: &ClockIdentity) -> Option<Duration> {
+        let lost_at = self.lost_at()?;
+        let (_, switched_at) = (*self.receivers.get(receiver)?)?;
+        Some(switched_at.duration_since(lost_at).unwrap_or_default())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.loss.is_some()
+            && self.new_ptt.is_some()
+            && self.receivers.values().all(|switch| switch.is_some())
+    }
+
+    /// Summary of the drill once it completed, reported only once
+    pub fn take_completion(&mut self) -> Option<String> {
+        if self.completion_reported || !self.is_complete() {
+            return None;
+        }
+        self.completion_reported = true;
+
+        let slowest = self
+            .receivers
+            .keys()
+            .filter_map(|receiver| self.switch_time(receiver))
+            .max();
+        Some(format!(
+            "Failover drill: new PTT after {}, {} receiver(s) switched{}",
+            format_drill_duration(self.election_time()?),
+            self.receivers.len(),
+            slowest.map_or(String::new(), |slowest| format!(
+                ", slowest after {}",
+                format_drill_duration(slowest)
+            ))
+        ))
+    }
+}
+
+pub fn format_drill_duration(duration: Duration) -> String {
+    format!("{:.3} s", duration.as_secs_f64())
+}
+
+#[test]
+fn test_failover_drill() {
+    let id = |n| ClockIdentity {
+        clock_id: [0, 0, 0, 0, 0, 0, 0, n],
+    };
+    let at = |ms| SystemTime::UNIX_EPOCH + Duration::from_millis(ms);
+    let instance = (0, 0);
+    let (old_ptt, backup, receiver) = (id(1), id(2), id(3));
+
+    let mut drill = FailoverDrill::new(instance, old_ptt, [receiver], at(0));
+    drill.record_sync(instance, old_ptt, at(1_000));
+    drill.record_delay_resp(instance, old_ptt, receiver, at(1_100));
+    drill.check_silence(at(2_000));
+    assert!(drill.loss.is_none());
+
+    // The backup takes over 2.5 s after the last Sync of the old PTT
+    drill.record_sync(instance, backup, at(3_500));
+    assert!(drill.loss.is_none());
+    drill.record_delay_resp(instance, backup, receiver, at(4_200));
+    drill.check_silence(at(4_500));
+    assert_eq!(drill.loss, Some(DrillLoss::Silent));
+    assert_eq!(drill.election_time(), Some(Duration::from_millis(2_500)));
+    assert_eq!(
+        drill.switch_time(&receiver),
+        Some(Duration::from_millis(3_200))
+    );
+    assert!(drill.take_completion().is_some());
+    assert!(drill.take_completion().is_none());
+}
@@ -0,0 +1,291 @@
+//! Epoch and era of the seconds field of PTP timestamps
+//!
+//! PTP timestamps count 48-bit seconds since 1970 (TAI with the PTP
+//! timescale). Devices converting from other time sources get this wrong in
+//! recognizable ways: seconds counted from the NTP (1900) or GPS (1980) epoch,
+//! NTP's 32-bit seconds wrapping in 2036 without the era being carried, or
+//! upper bits of the 48-bit field left over from somewhere. The origin
+//! timestamps of a time transmitter are compared with the capture clock to
+//! infer which of these it is. Two time transmitters of a domain an era apart
+//! mean receivers jump by decades when the grandmaster changes.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+    time::SystemTime,
+};
+
+use crate::types::{ClockIdentity, PtpTimestamp, format_ptp_instance};
+
+/// Seconds from the NTP epoch (1900) to the PTP epoch (1970)
+const NTP_EPOCH_OFFSET: i64 = 2_208_988_800;
+/// Seconds from the PTP epoch (1970) to the GPS epoch (1980-01-06)
+const GPS_EPOCH_OFFSET: i64 = 315_964_800;
+/// Seconds of a 32-bit seconds era
+const ERA_32: i64 = 1 << 32;
+/// Distance from the capture clock still counted as the right epoch, a
+/// clock set to the wrong time of day is not an era problem
+const ERA_TOLERANCE: i64 = 86_400;
+/// Seconds below which a clock is taken to count from zero
+const FROM_ZERO_LIMIT: u64 = 10 * 365 * 86_400;
+
+/// Offset between two timescales that tells of an epoch or era mixup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EraShift {
+    /// Seconds counted from 1900
+    Ntp1900,
+    /// Seconds counted from 1980-01-06
+    Gps1980,
+    /// Whole 32-bit eras off, positive when ahead
+    Rollover(i64),
+}
+
+impl EraShift {
+    /// The shift `offset` seconds are, if any
+    pub fn of(offset: i64) -> Option<Self> {
+        let near = |shift: i64| (offset - shift).abs() <= ERA_TOLERANCE;
+        if near(NTP_EPOCH_OFFSET) {
+            return Some(EraShift::Ntp1900);
+        }
+        if near(-GPS_EPOCH_OFFSET) {
+            return Some(EraShift::Gps1980);
+        }
+        let eras = (offset as f64 / ERA_32 as f64).round() as i64;
+        (eras != 0 && near(eras * ERA_32)).then_some(EraShift::Rollover(eras))
+    }
+}
+
+impl Display for EraShift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EraShift::Ntp1900 => write!(f, "the NTP epoch ({} s)", NTP_EPOCH_OFFSET),
+            EraShift::Gps1980 => write!(f, "the GPS epoch ({} s)", GPS_EPOCH_OFFSET),
+            EraShift::Rollover(eras) => write!(f, "{} × 2^32 s (32-bit rollover)", eras.abs()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Era {
+    /// Seconds since 1970, as expected
+    Ptp,
+    /// Off from 1970 by an epoch or era
+    Shifted(EraShift),
+    /// Counting from zero: never set, or the time since power-up
+    FromZero,
+    /// Anything else, as an ARB timescale may be
+    Arbitrary,
+}
+
+impl Era {
+    /// Whether the timestamps are off by an epoch or era mixup
+    pub fn is_wrong(&self) -> bool {
+        matches!(self, Era::Shifted(_))
+    }
+}
+
+impl Display for Era {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Era::Ptp => write!(f, "PTP epoch (1970)"),
+            Era::Shifted(EraShift::Ntp1900) => {
+                write!(
+                    f,
+                    "NTP epoch (1900), seconds counted from 1900 instead of 1970"
+                )
+            }
+            Era::Shifted(EraShift::Gps1980) => {
+                write!(
+                    f,
+                    "GPS epoch (1980), seconds counted from 1980-01-06 instead of 1970"
+                )
+            }
+            Era::Shifted(EraShift::Rollover(eras)) if *eras > 0 => write!(
+                f,
+                "{} × 2^32 s ahead, upper bits of the 48-bit seconds set",
+                eras
+            ),
+            Era::Shifted(EraShift::Rollover(eras)) => write!(
+                f,
+                "{} × 2^32 s behind, 32-bit seconds wrapped (2036 NTP era not carried)",
+                -eras
+            ),
+            Era::FromZero => write!(f, "counting from zero (clock never set, or since power-up)"),
+            Era::Arbitrary => write!(f, "arbitrary"),
+        }
+    }
+}
+
+/// Era of a time transmitter's timestamps and their distance from the
+/// capture clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EraEstimate {
+    pub era: Era,
+    /// Timestamp seconds minus the capture time's Unix seconds
+    pub offset: i64,
+}
+
+impl EraEstimate {
+    /// Era of an origin timestamp captured at `at`, None for a zero
+    /// timestamp, which senders use when they don't fill it in
+    pub fn infer(timestamp: &PtpTimestamp, at: SystemTime) -> Option<Self> {
+        if timestamp.seconds == 0 && timestamp.nanoseconds == 0 {
+            return None;
+        }
+        let captured = at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let offset = timestamp.seconds as i64 - captured;
+        let era = if offset.abs() <= ERA_TOLERANCE {
+            Era::Ptp
+        } else if let Some(shift) = EraShift::of(offset) {
+            Era::Shifted(shift)
+        } else if timestamp.seconds < FROM_ZERO_LIMIT {
+            Era::FromZero
+        } else {
+            Era::Arbitrary
+        };
+        Some(Self { era, offset })
+    }
+}
+
+/// Two time transmitters of a PTP instance an era apart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EraMismatch {
+    pub instance: (u16, u8),
+    pub clock_identity: ClockIdentity,
+    pub other: ClockIdentity,
+    pub shift: EraShift,
+    /// Seconds `clock_identity` is ahead of `other`
+    pub difference: i64,
+}
+
+impl Display for EraMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} and {} in domain {} are {} s apart, {}: receivers jump by that when the grandmaster changes",
+            self.clock_identity,
+            self.other,
+            format_ptp_instance(self.instance.0, self.instance.1),
+            self.difference,
+            self.shift
+        )
+    }
+}
+
+/// Offsets from the capture clock of the time transmitters of each PTP
+/// instance (sdoId, domain)
+#[derive(Debug, Clone, Default)]
+pub struct DomainEras {
+    offsets: HashMap<(u16, u8), BTreeMap<ClockIdentity, i64>>,
+    /// Pairs already reported, lower clock identity first
+    reported: HashSet<(ClockIdentity, ClockIdentity)>,
+}
+
+impl DomainEras {
+    /// Record the offset of a time transmitter, returning the others of the
+    /// instance it is newly found an era apart from
+    pub fn record(
+        &mut self,
+        instance: (u16, u8),
+        clock_identity: ClockIdentity,
+        offset: i64,
+    ) -> Vec<EraMismatch> {
+        let offsets = self.offsets.entry(instance).or_default();
+        offsets.insert(clock_identity, offset);
+
+        let mut mismatches = Vec::new();
+        for (&other, &other_offset) in offsets.iter() {
+            let difference = offset - other_offset;
+            let Some(shift) = EraShift::of(difference) else {
+                continue;
+            };
+            let pair = (clock_identity.min(other), clock_identity.max(other));
+            if self.reported.insert(pair) {
+                mismatches.push(EraMismatch {
+                    instance,
+                    clock_identity,
+                    other,
+                    shift,
+                    difference,
+                });
+            }
+        }
+        mismatches
+    }
+}
+
+#[test]
+fn test_era_inference() {
+    use std::time::Duration;
+
+    // 2025, with TAI-UTC of 37 s
+    let unix = 1_736_942_400;
+    let at = SystemTime::UNIX_EPOCH + Duration::from_secs(unix);
+    let era = |seconds: u64| {
+        EraEstimate::infer(
+            &PtpTimestamp {
+                seconds,
+                nanoseconds: 1,
+            },
+            at,
+        )
+        .unwrap()
+        .era
+    };
+
+    assert_eq!(era(unix + 37), Era::Ptp);
+    assert_eq!(era(unix + 37 - 3600), Era::Ptp);
+    assert_eq!(
+        era(unix + NTP_EPOCH_OFFSET as u64),
+        Era::Shifted(EraShift::Ntp1900)
+    );
+    assert_eq!(
+        era(unix - GPS_EPOCH_OFFSET as u64 + 18),
+        Era::Shifted(EraShift::Gps1980)
+    );
+    assert_eq!(
+        era(unix + 37 + (3 << 32)),
+        Era::Shifted(EraShift::Rollover(3))
+    );
+    assert!(era(unix + (1 << 32)).is_wrong());
+    assert_eq!(era(86_400 * 12), Era::FromZero);
+    assert_eq!(era(unix / 2), Era::Arbitrary);
+    let unset = PtpTimestamp {
+        seconds: 0,
+        nanoseconds: 0,
+    };
+    assert_eq!(EraEstimate::infer(&unset, at), None);
+
+    // A capture past 2106 from a clock whose seconds wrapped at 32 bits
+    assert_eq!(EraShift::of(-ERA_32 + 500), Some(EraShift::Rollover(-1)));
+    assert_eq!(EraShift::of(ERA_32 / 2), None);
+}
+
+#[test]
+fn test_domain_eras() {
+    let clock = |last: u8| ClockIdentity {
+        clock_id: [0, 0, 0, 0xff, 0xfe, 0, 0, last],
+    };
+    let mut eras = DomainEras::default();
+    assert!(eras.record((0, 0), clock(1), 37).is_empty());
+    // A backup grandmaster within the tolerance, and one in another domain
+    assert!(eras.record((0, 0), clock(2), 40).is_empty());
+    assert!(
+        eras.record((0, 1), clock(3), 37 + NTP_EPOCH_OFFSET)
+            .is_empty()
+    );
+
+    let mismatches = eras.record((0, 0), clock(4), 37 + NTP_EPOCH_OFFSET);
+    assert_eq!(mismatches.len(), 2);
+    assert_eq!(mismatches[0].shift, EraShift::Ntp1900);
+    assert_eq!(mismatches[0].other, clock(1));
+    assert!(mismatches[0].to_string().contains("NTP epoch"));
+    // Reported once per pair
+    assert!(
+        eras.record((0, 0), clock(4), 38 + NTP_EPOCH_OFFSET)
+            .is_empty()
+    );
+}
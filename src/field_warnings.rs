@@ -0,0 +1,165 @@
+//! Suspicious values of announced fields
+//!
+//! Some values are valid on the wire but almost always point to a
+//! misconfigured or unsynchronized device. They are marked with a glyph in
+//! host details, and '?' explains them.
+
+use crate::ptp::PtpHostStateTimeTransmitter;
+
+/// Steps removed beyond this point to a timing loop or far too many boundary clocks
+const MAX_REASONABLE_STEPS_REMOVED: u16 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldWarning {
+    /// Label of the field in host details, without the colon
+    pub field: &'static str,
+    pub explanation: String,
+}
+
+impl FieldWarning {
+    fn new(field: &'static str, explanation: impl Into<String>) -> Self {
+        Self {
+            field,
+            explanation: explanation.into(),
+        }
+    }
+}
+
+/// Warnings about the values a time transmitter announces
+pub fn transmitter_warnings(transmitter: &PtpHostStateTimeTransmitter) -> Vec<FieldWarning> {
+    let mut warnings = Vec::new();
+
+    if let Some(class) = transmitter.clock_class.map(|class| class.class()) {
+        if class == 248 && transmitter.is_bmca_winner {
+            warnings.push(FieldWarning::new(
+                "Clock Class",
+                "248 is the default of a clock that was never synchronized, yet it won the BMCA; check the grandmaster's time source",
+            ));
+        } else if class == 255 {
+            warnings.push(FieldWarning::new(
+                "Clock Class",
+                "255 is reserved for receiver-only clocks, which should never send Announces",
+            ));
+        }
+    }
+
+    if transmitter.current_utc_offset.map(|o| o.offset) == Some(0) {
+        warnings.push(FieldWarning::new(
+            "UTC Offset",
+            "0 means TAI-UTC is unknown (37 s since 2017); receivers converting to UTC will be off by that much",
+        ));
+    }
+
+    if transmitter.offset_scaled_log_variance == Some(0xffff) {
+        warnings.push(FieldWarning::new(
+            "Log Variance",
+            "0xffff means the variance was never computed or is too large to represent",
+        ));
+    }
+
+    if let Some(steps) = transmitter.steps_removed
+        && steps > MAX_REASONABLE_STEPS_REMOVED
+    {
+        warnings.push(FieldWarning::new(
+            "Steps Removed",
+            format!(
+                "{} boundary clocks to the grandmaster, errors add up with each one; often a timing loop",
+                steps
+            ),
+        ));
+    }
+
+    if let Some(path_trace) = &transmitter.path_trace {
+        let looped = path_trace
+            .iter()
+            .enumerate()
+            .find(|(index, identity)| path_trace[..*index].contains(identity));
+        if let Some((_, identity)) = looped {
+            warnings.push(FieldWarning::new(
+                "Path Trace",
+                format!(
+                    "{} appears twice, the Announce went around a timing loop",
+                    identity
+                ),
+            ));
+        } else if let Some(steps) = transmitter.steps_removed
+            && path_trace.len() != steps as usize + 1
+        {
+            warnings.push(FieldWarning::new(
+                "Path Trace",
+                format!(
+                    "{} entries for {} steps removed; a relay did not append its identity, or the trace was cut off",
+                    path_trace.len(),
+                    steps
+                ),
+            ));
+        }
+    }
+
+    warnings
+}
+
+#[test]
+fn test_transmitter_warnings() {
+    use crate::types::{ClockIdentity, PtpClockClass, PtpUtcOffset};
+
+    let healthy = PtpHostStateTimeTransmitter {
+        clock_class: Some(PtpClockClass::new(6)),
+        current_utc_offset: Some(PtpUtcOffset::new(37)),
+        offset_scaled_log_variance: Some(0x4e5d),
+        steps_removed: Some(0),
+        is_bmca_winner: true,
+        ..Default::default()
+    };
+    assert!(transmitter_warnings(&healthy).is_empty());
+
+    let unsynchronized = PtpHostStateTimeTransmitter {
+        clock_class: Some(PtpClockClass::new(248)),
+        current_utc_offset: Some(PtpUtcOffset::new(0)),
+        offset_scaled_log_variance: Some(0xffff),
+        steps_removed: Some(11),
+        ..healthy.clone()
+    };
+    let fields: Vec<_> = transmitter_warnings(&unsynchronized)
+        .iter()
+        .map(|warning| warning.field)
+        .collect();
+    assert_eq!(
+        fields,
+        ["Clock Class", "UTC Offset", "Log Variance", "Steps Removed"]
+    );
+
+    // 248 is fine on a transmitter that lost the election
+    let backup = PtpHostStateTimeTransmitter {
+        clock_class: Some(PtpClockClass::new(248)),
+        is_bmca_winner: false,
+        ..healthy
+    };
+    assert!(transmitter_warnings(&backup).is_empty());
+
+    // 802.1AS path trace of a relay one step below the grandmaster
+    let ids = [1u8, 2].map(|n| ClockIdentity {
+        clock_id: [0, 0, 0, 0, 0, 0, 0, n],
+    });
+    let relay = PtpHostStateTimeTransmitter {
+        steps_removed: Some(1),
+        path_trace: Some(ids.to_vec()),
+        ..Default::default()
+    };
+    assert!(transmitter_warnings(&relay).is_empty());
+    let short = PtpHostStateTimeTransmitter {
+        steps_removed: Some(2),
+        ..relay.clone()
+    };
+    assert_eq!(transmitter_warnings(&short)[0].field, "Path Trace");
+    let looped = PtpHostStateTimeTransmitter {
+        steps_removed: Some(2),
+        path_trace: Some(vec![ids[0], ids[1], ids[0]]),
+        ..relay
+    };
+    assert!(
+        transmitter_warnings(&looped)[0]
+            .explanation
+            .contains("timing loop")
+    );
+}
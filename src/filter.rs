@@ -0,0 +1,173 @@
+//! Host table filters, e.g. "domain=127,state=transmitter"
+//!
+//! A filter is a comma separated list of key=value terms, all of which have to
+//! match for a host to be listed. Text values match case-insensitively anywhere
+//! in the host's field, tags and numbers match exactly.
+
+use std::fmt::Display;
+
+use crate::ptp::{PtpHost, PtpHostState};
+
+fn parse_number<T: std::str::FromStr>(value: &str, term: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid number '{}' in filter '{}'", value, term))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FilterTerm {
+    Domain(u8),
+    SdoId(u16),
+    Vlan(u16),
+    /// "transmitter", "receiver", "listening" or "p2p"
+    State(String),
+    Vendor(String),
+    Interface(String),
+    Ip(String),
+    ClockIdentity(String),
+    /// Operator-defined tag, matched as a whole
+    Tag(String),
+}
+
+impl FilterTerm {
+    fn parse(term: &str) -> Result<Self, String> {
+        let (key, value) = term
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid filter '{}', expected key=value", term))?;
+        let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+        let text = value.to_ascii_lowercase();
+
+        match key.as_str() {
+            "domain" => Ok(FilterTerm::Domain(parse_number(value, term)?)),
+            "sdo" | "sdoid" => Ok(FilterTerm::SdoId(parse_number(value, term)?)),
+            "vlan" => Ok(FilterTerm::Vlan(parse_number(value, term)?)),
+            "state" => match text.as_str() {
+                "transmitter" | "receiver" | "listening" | "p2p" => Ok(FilterTerm::State(text)),
+                _ => Err(format!(
+                    "Invalid state '{}', expected transmitter, receiver, listening or p2p",
+                    value
+                )),
+            },
+            "vendor" => Ok(FilterTerm::Vendor(text)),
+            "interface" | "iface" => Ok(FilterTerm::Interface(text)),
+            "ip" => Ok(FilterTerm::Ip(text)),
+            "clock" | "id" => Ok(FilterTerm::ClockIdentity(text)),
+            "tag" => Ok(FilterTerm::Tag(text)),
+            _ => Err(format!(
+                "Unknown filter key '{}', expected domain, sdo, vlan, state, vendor, interface, ip, clock or tag",
+                key
+            )),
+        }
+    }
+
+    fn matches(&self, host: &PtpHost) -> bool {
+        let contains =
+            |haystack: &str, needle: &str| haystack.to_ascii_lowercase().contains(needle);
+        match self {
+            FilterTerm::Domain(domain) => host.domain_number == Some(*domain),
+            FilterTerm::SdoId(sdo_id) => host.sdo_id == Some(*sdo_id),
+            FilterTerm::Vlan(vlan) => host.vlan_id == Some(*vlan),
+            FilterTerm::State(state) => match &host.state {
+                PtpHostState::TimeTransmitter(_) => state == "transmitter",
+                PtpHostState::TimeReceiver(_) => state == "receiver",
+                PtpHostState::Listening if host.is_p2p_peer() => state == "p2p",
+                PtpHostState::Listening => state == "listening",
+            },
+            FilterTerm::Vendor(vendor) => host
+                .get_vendor_name()
+                .is_some_and(|name| contains(name, vendor)),
+            FilterTerm::Interface(interface) => host
+                .get_interface_names()
+                .iter()
+                .any(|name| contains(name, interface)),
+            FilterTerm::Ip(ip) => host
+                .ip_addresses
+                .keys()
+                .any(|address| contains(&address.to_string(), ip)),
+            FilterTerm::ClockIdentity(id) => contains(&host.clock_identity.to_string(), id),
+            FilterTerm::Tag(tag) => host.tags.iter().any(|name| name.eq_ignore_ascii_case(tag)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostFilter {
+    terms: Vec<FilterTerm>,
+    text: Vec<String>,
+}
+
+impl HostFilter {
+    /// Parse filter expressions, each a comma separated list of terms
+    pub fn parse<S: AsRef<str>>(expressions: &[S]) -> Result<Self, String> {
+        let mut filter = Self::default();
+        for term in expressions
+            .iter()
+            .flat_map(|expression| expression.as_ref().split(','))
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+        {
+            filter.terms.push(FilterTerm::parse(term)?);
+            filter.text.push(term.to_string());
+        }
+        Ok(filter)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn matches(&self, host: &PtpHost) -> bool {
+        self.terms.iter().all(|term| term.matches(host))
+    }
+}
+
+impl Display for HostFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text.join(","))
+    }
+}
+
+#[test]
+fn test_host_filter() {
+    use crate::types::ClockIdentity;
+
+    let mut host = PtpHost::new(ClockIdentity {
+        clock_id: [0x00, 0x1b, 0x19, 0xff, 0xfe, 0x12, 0x34, 0x56],
+    });
+    host.domain_number = Some(127);
+    host.add_interface("eth1".to_string());
+
+    assert!(HostFilter::default().matches(&host));
+    assert!(HostFilter::parse(&["domain=127"]).unwrap().matches(&host));
+    assert!(
+        HostFilter::parse(&["domain=127, state=listening", "iface=ETH"])
+            .unwrap()
+            .matches(&host)
+    );
+    assert!(!HostFilter::parse(&["domain=0"]).unwrap().matches(&host));
+    host.tags = vec!["studio-A".to_string(), "suspect".to_string()];
+    assert!(HostFilter::parse(&["tag=STUDIO-a"]).unwrap().matches(&host));
+    assert!(!HostFilter::parse(&["tag=studio"]).unwrap().matches(&host));
+    host.pdelay_req_count = 1;
+    assert!(HostFilter::parse(&["state=p2p"]).unwrap().matches(&host));
+    assert!(
+        !HostFilter::parse(&["state=listening"])
+            .unwrap()
+            .matches(&host)
+    );
+    assert!(
+        !HostFilter::parse(&["clock=12:34:57"])
+            .unwrap()
+            .matches(&host)
+    );
+    assert_eq!(
+        HostFilter::parse(&["domain=127,vlan=5"])
+            .unwrap()
+            .to_string(),
+        "domain=127,vlan=5"
+    );
+
+    assert!(HostFilter::parse(&["domain"]).is_err());
+    assert!(HostFilter::parse(&["domain=300"]).is_err());
+    assert!(HostFilter::parse(&["color=red"]).is_err());
+}
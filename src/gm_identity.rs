@@ -0,0 +1,198 @@
+//! Advertised versus observed grandmaster identity
+//!
+//! An Announce names the grandmaster in grandmasterIdentity, while its
+//! sourcePortIdentity is the clock that sent it. The two differ by design at
+//! a boundary clock: it passes on the dataset of its grandmaster from a port
+//! of its own, adds one to stepsRemoved and sends its own Syncs. Announces
+//! that name another clock without that were relabeled or forwarded, e.g. by
+//! a misconfigured gateway or a bridge replaying the grandmaster's Announces.
+//! Receivers then elect a grandmaster whose time they don't actually get.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    clock_role::ClockRole,
+    types::{AnnounceMessage, ClockIdentity},
+};
+
+/// An Announce repeating the grandmaster's sequenceId this soon is a copy
+const COPY_WINDOW: Duration = Duration::from_secs(1);
+/// Announces naming another clock before missing Syncs count as evidence
+const SYNC_GRACE_ANNOUNCES: u64 = 3;
+
+/// Last Announce each clock sent naming itself as grandmaster, by (clock,
+/// sdoId, domain): (arrival, sequenceId)
+pub type OwnAnnounces = HashMap<(ClockIdentity, u16, u8), (SystemTime, u16)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GmIdentityCase {
+    /// A boundary clock passing on its grandmaster, as expected
+    BoundaryClock,
+    /// Announces relabeled or forwarded without being a boundary clock
+    Suspicious,
+}
+
+impl Display for GmIdentityCase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GmIdentityCase::BoundaryClock => write!(f, "boundary clock (expected)"),
+            GmIdentityCase::Suspicious => write!(f, "relabeled or forwarded (suspicious)"),
+        }
+    }
+}
+
+/// A host announcing another clock as grandmaster, and why it is one case or the other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GmIdentityMismatch {
+    pub advertised: ClockIdentity,
+    pub case: GmIdentityCase,
+    pub evidence: Vec<String>,
+}
+
+/// Announces of a host naming another clock as grandmaster
+#[derive(Debug, Clone, Default)]
+pub struct AdvertisedGm {
+    /// Grandmaster and stepsRemoved of the last Announce, None if it named
+    /// the host itself
+    last: Option<(ClockIdentity, u16)>,
+    /// Announces naming another clock
+    mismatched: u64,
+    /// Of those, with stepsRemoved 0
+    zero_steps: u64,
+    /// Of those, repeating the sequenceId the grandmaster itself just sent
+    copies: u64,
+}
+
+impl AdvertisedGm {
+    /// Record an Announce of the host. Returns true when it is the first
+    /// suspicious one.
+    pub fn record(
+        &mut self,
+        announce: &AnnounceMessage,
+        at: SystemTime,
+        own_announces: &mut OwnAnnounces,
+    ) -> bool {
+        let header = &announce.header;
+        let sender = header.source_port_identity.clock_identity;
+        let key = (announce.ptt_identity, header.sdo_id, header.domain_number);
+        if announce.ptt_identity == sender {
+            own_announces.insert(key, (at, header.sequence_id));
+            self.last = None;
+            return false;
+        }
+
+        let was_suspicious = self.zero_steps + self.copies > 0;
+        self.last = Some((announce.ptt_identity, announce.steps_removed));
+        self.mismatched += 1;
+        if announce.steps_removed == 0 {
+            self.zero_steps += 1;
+        }
+        if own_announces.get(&key).is_some_and(|(sent, sequence_id)| {
+            *sequence_id == header.sequence_id
+                && at.duration_since(*sent).unwrap_or_default() <= COPY_WINDOW
+        }) {
+            self.copies += 1;
+        }
+        !was_suspicious && self.zero_steps + self.copies > 0
+    }
+
+    /// Which case the host is, None unless its last Announce named another
+    /// clock. `sends_sync` and `role` are what else is known of the host.
+    pub fn assess(&self, sends_sync: bool, role: Option<ClockRole>) -> Option<GmIdentityMismatch> {
+        let (advertised, steps_removed) = self.last?;
+
+        let mut suspicious = Vec::new();
+        if self.zero_steps > 0 {
+            suspicious.push(format!(
+                "{} Announce(s) with stepsRemoved 0, a boundary clock adds one",
+                self.zero_steps
+            ));
+        }
+        if self.copies > 0 {
+            suspicious.push(format!(
+                "{} Announce(s) repeat the sequenceId the grandmaster sent within {} s",
+                self.copies,
+                COPY_WINDOW.as_secs()
+            ));
+        }
+        if !sends_sync && self.mismatched >= SYNC_GRACE_ANNOUNCES {
+            suspicious.push("no Syncs of its own".to_string());
+        }
+        if !suspicious.is_empty() {
+            return Some(GmIdentityMismatch {
+                advertised,
+                case: GmIdentityCase::Suspicious,
+                evidence: suspicious,
+            });
+        }
+
+        let mut evidence = vec![format!("stepsRemoved {}", steps_removed)];
+        if sends_sync {
+            evidence.push("sends its own Syncs".to_string());
+        }
+        if role == Some(ClockRole::Boundary) {
+            evidence.push("receives and transmits on different ports or domains".to_string());
+        }
+        Some(GmIdentityMismatch {
+            advertised,
+            case: GmIdentityCase::BoundaryClock,
+            evidence,
+        })
+    }
+}
+
+#[test]
+fn test_advertised_gm() {
+    use crate::types::PtpMessage;
+
+    let announce = |sender: u8, gm: u8, sequence_id: u16, steps_removed: u16| {
+        let mut data = [0u8; 64];
+        data[0] = 0xb;
+        data[1] = 0x02;
+        data[2..4].copy_from_slice(&64u16.to_be_bytes());
+        data[27] = sender;
+        data[30..32].copy_from_slice(&sequence_id.to_be_bytes());
+        data[60] = gm;
+        data[61..63].copy_from_slice(&steps_removed.to_be_bytes());
+        match PtpMessage::try_from(&data[..]).unwrap() {
+            PtpMessage::Announce(announce) => announce,
+            _ => unreachable!(),
+        }
+    };
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut own = OwnAnnounces::new();
+
+    // The grandmaster itself
+    let mut gm = AdvertisedGm::default();
+    assert!(!gm.record(&announce(1, 1, 7, 0), start, &mut own));
+    assert_eq!(gm.assess(true, Some(ClockRole::Ordinary)), None);
+
+    // A boundary clock one step down with a sequence of its own
+    let mut boundary = AdvertisedGm::default();
+    for sequence_id in 0..5 {
+        assert!(!boundary.record(&announce(2, 1, 100 + sequence_id, 1), start, &mut own));
+    }
+    let mismatch = boundary.assess(true, Some(ClockRole::Boundary)).unwrap();
+    assert_eq!(mismatch.case, GmIdentityCase::BoundaryClock);
+    assert_eq!(mismatch.evidence.len(), 3);
+
+    // A bridge replaying the grandmaster's Announce under its own identity
+    let mut replay = AdvertisedGm::default();
+    let copy_time = start + Duration::from_millis(5);
+    assert!(replay.record(&announce(3, 1, 7, 0), copy_time, &mut own));
+    assert!(!replay.record(&announce(3, 1, 7, 0), copy_time, &mut own));
+    let mismatch = replay.assess(false, None).unwrap();
+    assert_eq!(mismatch.case, GmIdentityCase::Suspicious);
+    assert_eq!(
+        mismatch.advertised,
+        announce(1, 1, 0, 0)
+            .header
+            .source_port_identity
+            .clock_identity
+    );
+    assert_eq!(mismatch.evidence.len(), 2, "{:?}", mismatch.evidence);
+}
@@ -0,0 +1,304 @@
+//! Redundant grandmaster pairs (backup_grandmasters in the config)
+//!
+//! Plants with a backup grandmaster next to the primary one of a domain audit
+//! the pair with three questions: do both announce the same dataset, do they
+//! agree on the time, and would the backup take over cleanly if the primary
+//! failed. The announced attributes are compared side by side. The phase
+//! between the two comes from the capture timestamps: origin minus arrival
+//! time of a Sync differs between the grandmasters by the phase between them
+//! plus the difference of their path delays. The path delays stay put, so the
+//! difference moving away from where it started is drift between the
+//! grandmasters.
+
+use std::time::Duration;
+
+use crate::{
+    bmca::BmcaCandidate, ptp::PtpHostStateTimeTransmitter, redundancy::PathSkew,
+    types::ClockIdentity,
+};
+
+/// Syncs of the two grandmasters arriving further apart are not compared
+const MATCH_WINDOW_NS: i128 = 1_000_000_000;
+/// A backup that sent nothing for this long can not take over
+pub const BACKUP_SILENCE_LIMIT: Duration = Duration::from_secs(5);
+/// Default change of the phase between primary and backup that counts as drift
+pub const DEFAULT_DRIFT_THRESHOLD: Duration = Duration::from_micros(50);
+
+/// One announced attribute of both grandmasters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeComparison {
+    pub name: &'static str,
+    pub primary: String,
+    pub backup: String,
+    /// False for the priorities, which differ by design to rank the pair
+    pub must_agree: bool,
+}
+
+impl AttributeComparison {
+    pub fn is_mismatch(&self) -> bool {
+        self.must_agree && self.primary != self.backup
+    }
+}
+
+/// Reads one attribute from an announced dataset
+type Attribute = fn(&PtpHostStateTimeTransmitter) -> Option<String>;
+
+/// The announced attributes of primary and backup side by side
+pub fn compare_announces(
+    primary: Option<&PtpHostStateTimeTransmitter>,
+    backup: Option<&PtpHostStateTimeTransmitter>,
+) -> Vec<AttributeComparison> {
+    let attributes: [(&'static str, bool, Attribute); 8] = [
+        ("Priority1", false, |state| {
+            state.priority1.map(|p| p.to_string())
+        }),
+        ("Priority2", false, |state| {
+            state.priority2.map(|p| p.to_string())
+        }),
+        ("Class", true, |state| {
+            state.clock_class.map(|class| class.class().to_string())
+        }),
+        ("Accuracy", true, |state| {
+            state
+                .clock_accuracy
+                .map(|accuracy| format!("0x{:02x}", accuracy.accuracy))
+        }),
+        ("Variance", true, |state| {
+            state
+                .offset_scaled_log_variance
+                .map(|variance| format!("0x{:04x}", variance))
+        }),
+        ("Time source", true, |state| {
+            state.time_source.map(|source| format!("0x{:02x}", source))
+        }),
+        ("UTC offset", true, |state| {
+            state
+                .current_utc_offset
+                .map(|offset| format!("{:+}s", offset.offset))
+        }),
+        ("Steps", true, |state| {
+            state.steps_removed.map(|steps| steps.to_string())
+        }),
+    ];
+
+    let value = |state: Option<&PtpHostStateTimeTransmitter>, get: Attribute| {
+        state.and_then(get).unwrap_or_else(|| "-".to_string())
+    };
+    attributes
+        .into_iter()
+        .map(|(name, must_agree, get)| AttributeComparison {
+            name,
+            primary: value(primary, get),
+            backup: value(backup, get),
+            must_agree,
+        })
+        .collect()
+}
+
+/// Primary and backup grandmaster of a domain, as the widget shows them
+#[derive(Debug, Clone)]
+pub struct GmPairStatus<'a> {
+    pub domain: u8,
+    pub primary: ClockIdentity,
+    pub backup: ClockIdentity,
+    /// Grandmaster the domain elected, None before any Announce
+    pub elected: Option<ClockIdentity>,
+    pub attributes: Vec<AttributeComparison>,
+    /// Why the backup could not take over now, empty if it is ready
+    pub backup_problems: Vec<String>,
+    /// Primary minus backup, once there are enough Syncs of both
+    pub phase: Option<&'a PathSkew>,
+}
+
+/// Grandmaster the BMCA elects once the primary is gone, given the candidates
+/// of its instance in election order
+pub fn successor(candidates: &[BmcaCandidate], primary: ClockIdentity) -> Option<ClockIdentity> {
+    // Boundary clocks pass on the identity of the grandmaster they follow
+    candidates
+        .iter()
+        .map(|candidate| {
+            candidate
+                .state
+                .ptt_identifier
+                .unwrap_or(candidate.clock_identity)
+        })
+        .find(|grandmaster| *grandmaster != primary)
+}
+
+/// Why the backup could not take over from the primary right now, empty if it
+/// is ready
+pub fn backup_problems(
+    primary: Option<&PtpHostStateTimeTransmitter>,
+    backup: Option<&PtpHostStateTimeTransmitter>,
+    backup_silence: Duration,
+    successor: Option<ClockIdentity>,
+    backup_identity: ClockIdentity,
+) -> Vec<String> {
+    let Some(backup) = backup.filter(|backup| backup.priority1.is_some()) else {
+        return vec!["not announcing".to_string()];
+    };
+
+    let mut problems = Vec::new();
+    if backup_silence > BACKUP_SILENCE_LIMIT {
+        problems.push(format!("silent for {}s", backup_silence.as_secs()));
+    }
+    let class = |state: &PtpHostStateTimeTransmitter| state.clock_class.map(|class| class.class());
+    if let (Some(backup_class), Some(primary_class)) = (class(backup), primary.and_then(class))
+        && backup_class > primary_class
+    {
+        problems.push(format!(
+            "clockClass {} while the primary announces {}",
+            backup_class, primary_class
+        ));
+    }
+    let utc_offset = |state: &PtpHostStateTimeTransmitter| state.current_utc_offset;
+    if let (Some(backup_offset), Some(primary_offset)) =
+        (utc_offset(backup), primary.and_then(utc_offset))
+        && backup_offset != primary_offset
+    {
+        problems.push(format!(
+            "currentUtcOffset {:+}s, the primary {:+}s",
+            backup_offset.offset, primary_offset.offset
+        ));
+    }
+    if let Some(successor) = successor
+        && successor != backup_identity
+    {
+        problems.push(format!("{} would take over instead", successor));
+    }
+    problems
+}
+
+/// Phase between primary and backup from the capture timestamps of their Syncs
+#[derive(Debug, Clone, Default)]
+pub struct PairPhase {
+    /// Primary minus backup in ns, path delays included
+    pub phase: PathSkew,
+    /// Arrival times of the samples compared last
+    last_compared: Option<(i128, i128)>,
+}
+
+impl PairPhase {
+    /// Compare the latest (arrival ns, origin - arrival ns) Sync samples of
+    /// both grandmasters. Returns a description when the phase drifted more
+    /// than `threshold` from where it started, once per drift.
+    pub fn record(
+        &mut self,
+        primary: Option<(i128, i128)>,
+        backup: Option<(i128, i128)>,
+        threshold: Duration,
+    ) -> Option<String> {
+        let ((primary_arrival, primary_offset), (backup_arrival, backup_offset)) =
+            (primary?, backup?);
+        if (primary_arrival - backup_arrival).abs() > MATCH_WINDOW_NS
+            || self.last_compared == Some((primary_arrival, backup_arrival))
+        {
+            return None;
+        }
+        self.last_compared = Some((primary_arrival, backup_arrival));
+
+        let phase_ns = (primary_offset - backup_offset).clamp(i64::MIN as i128, i64::MAX as i128);
+        self.phase.record(phase_ns as i64, threshold)?;
+        Some(format!(
+            "phase to the backup moved from {:+.1} µs to {:+.1} µs",
+            self.phase.baseline_ns()? / 1000.0,
+            self.phase.current_ns()? / 1000.0
+        ))
+    }
+}
+
+#[test]
+fn test_gm_pair() {
+    use crate::types::{PtpClockClass, PtpUtcOffset};
+
+    let primary_identity = ClockIdentity {
+        clock_id: [0, 0x1b, 0x19, 0xff, 0xfe, 0, 0, 1],
+    };
+    let backup_identity = ClockIdentity {
+        clock_id: [0, 0x1b, 0x19, 0xff, 0xfe, 0, 0, 2],
+    };
+    let announced =
+        |identity: ClockIdentity, priority1: u8, class: u8| PtpHostStateTimeTransmitter {
+            priority1: Some(priority1),
+            priority2: Some(128),
+            clock_class: Some(PtpClockClass::new(class)),
+            current_utc_offset: Some(PtpUtcOffset::new(37)),
+            ptt_identifier: Some(identity),
+            ..Default::default()
+        };
+    let primary = announced(primary_identity, 100, 6);
+    let backup = announced(backup_identity, 110, 6);
+
+    let attributes = compare_announces(Some(&primary), Some(&backup));
+    // Different priorities rank the pair, they are no mismatch
+    assert!(attributes.iter().all(|attribute| !attribute.is_mismatch()));
+    assert!(
+        attributes
+            .iter()
+            .any(|attribute| attribute.primary != attribute.backup)
+    );
+
+    let candidates = [
+        BmcaCandidate {
+            clock_identity: primary_identity,
+            state: &primary,
+            local_priority: 128,
+        },
+        BmcaCandidate {
+            clock_identity: backup_identity,
+            state: &backup,
+            local_priority: 128,
+        },
+    ];
+    let next = successor(&candidates, primary_identity);
+    assert_eq!(next, Some(backup_identity));
+    assert!(
+        backup_problems(
+            Some(&primary),
+            Some(&backup),
+            Duration::ZERO,
+            next,
+            backup_identity
+        )
+        .is_empty()
+    );
+
+    // A free-running backup that went quiet is not ready
+    let lost = announced(backup_identity, 110, 248);
+    let problems = backup_problems(
+        Some(&primary),
+        Some(&lost),
+        Duration::from_secs(30),
+        next,
+        backup_identity,
+    );
+    assert_eq!(problems.len(), 2, "{:?}", problems);
+    assert_eq!(
+        backup_problems(Some(&primary), None, Duration::ZERO, None, backup_identity),
+        vec!["not announcing".to_string()]
+    );
+
+    // The backup drifts away by 200 µs halfway through
+    let mut phase = PairPhase::default();
+    let mut drifts = Vec::new();
+    for sample in 0..64i128 {
+        let arrival = 1_700_000_000_000_000_000 + sample * 250_000_000;
+        let backup_offset = if sample < 32 { 3_000 } else { -197_000 };
+        drifts.extend(phase.record(
+            Some((arrival, 37_000_000_000)),
+            Some((arrival + 1_000_000, 37_000_000_000 + backup_offset)),
+            DEFAULT_DRIFT_THRESHOLD,
+        ));
+        // The same samples are compared once
+        assert_eq!(
+            phase.record(
+                Some((arrival, 37_000_000_000)),
+                Some((arrival + 1_000_000, 37_000_000_000 + backup_offset)),
+                DEFAULT_DRIFT_THRESHOLD,
+            ),
+            None
+        );
+    }
+    assert_eq!(drifts.len(), 1, "{:?}", drifts);
+    assert_eq!(phase.phase.current_ns(), Some(197_000.0));
+}
@@ -0,0 +1,315 @@
+//! Live comparison of the network against its design ("--golden")
+//!
+//! Commissioning a timing network means checking every day that what is
+//! built still matches what was designed: the right grandmasters in each
+//! domain with the planned priorities, and the expected number of receivers
+//! following them. A golden file describes that design, and the tracker
+//! state is diffed against it continuously in the compliance pane ('C'):
+//!
+//! ```yaml
+//! domains:
+//!   - domain: 0
+//!     grandmasters:
+//!       - identity: 00:1b:19:ff:fe:12:34:56
+//!         priority1: 128
+//!         clock_class: 6
+//!       - identity: 00:1b:19:ff:fe:12:34:57
+//!         priority1: 129
+//!     receivers:
+//!       min: 20
+//!       max: 40
+//!   - domain: 24
+//!     sdo_id: 0x100
+//! allow_other_domains: false
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use crate::{
+    doctor::{Check, CheckStatus},
+    ptp::{PtpHost, PtpHostState},
+    types::{ClockIdentity, format_ptp_instance},
+};
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Golden {
+    pub domains: Vec<ExpectedDomain>,
+    /// Whether hosts in domains not listed are a deviation
+    #[serde(default)]
+    pub allow_other_domains: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExpectedDomain {
+    pub domain: u8,
+    #[serde(default)]
+    pub sdo_id: u16,
+    /// The only grandmasters that may be announced, any if none are listed
+    #[serde(default)]
+    pub grandmasters: Vec<ExpectedGrandmaster>,
+    pub receivers: Option<CountRange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExpectedGrandmaster {
+    #[serde(deserialize_with = "clock_identity")]
+    pub identity: ClockIdentity,
+    pub priority1: Option<u8>,
+    pub priority2: Option<u8>,
+    pub clock_class: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CountRange {
+    #[serde(default)]
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl CountRange {
+    fn contains(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+impl std::fmt::Display for CountRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", max),
+            Some(max) => write!(f, "{}-{}", self.min, max),
+            None => write!(f, ">= {}", self.min),
+        }
+    }
+}
+
+fn clock_identity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ClockIdentity, D::Error> {
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+/// What one PTP instance looks like right now
+#[derive(Default)]
+struct Instance<'a> {
+    hosts: usize,
+    /// Transmitters by clock identity
+    transmitters: BTreeMap<ClockIdentity, &'a PtpHost>,
+    /// grandmasterIdentity of all Announces
+    announced: BTreeSet<ClockIdentity>,
+    receivers: usize,
+}
+
+impl Golden {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read golden file {}", path.display()))?;
+        serde_yaml::from_str(&text)
+            .with_context(|| format!("Invalid golden file {}", path.display()))
+    }
+
+    /// Compare the hosts with the design, one check per expectation
+    pub fn diff(&self, hosts: &[&PtpHost]) -> Vec<Check> {
+        let mut instances: BTreeMap<(u16, u8), Instance> = BTreeMap::new();
+        for host in hosts {
+            let Some(key) = host.instance() else {
+                continue;
+            };
+            let instance = instances.entry(key).or_default();
+            instance.hosts += 1;
+            match &host.state {
+                PtpHostState::TimeTransmitter(state) => {
+                    instance.transmitters.insert(host.clock_identity, host);
+                    if let Some(ptt) = state.ptt_identifier {
+                        instance.announced.insert(ptt);
+                    }
+                }
+                PtpHostState::TimeReceiver(_) => instance.receivers += 1,
+                PtpHostState::Listening => {}
+            }
+        }
+
+        let mut checks = Vec::new();
+        for expected in &self.domains {
+            let key = (expected.sdo_id, expected.domain);
+            let name = format!("Domain {}", format_ptp_instance(key.0, key.1));
+            let Some(instance) = instances.get(&key) else {
+                checks.push(Check::new(name, CheckStatus::Fail, "no hosts seen"));
+                continue;
+            };
+            checks.push(Check::new(
+                &name,
+                CheckStatus::Pass,
+                format!("{} host(s)", instance.hosts),
+            ));
+
+            for gm in &expected.grandmasters {
+                checks.push(Self::check_grandmaster(&name, gm, instance));
+            }
+            if !expected.grandmasters.is_empty() {
+                let unexpected: Vec<String> = instance
+                    .announced
+                    .iter()
+                    .filter(|id| !expected.grandmasters.iter().any(|gm| gm.identity == **id))
+                    .map(|id| id.to_string())
+                    .collect();
+                checks.push(if unexpected.is_empty() {
+                    Check::new(
+                        format!("{} GMs", name),
+                        CheckStatus::Pass,
+                        "only expected grandmasters announced",
+                    )
+                } else {
+                    Check::new(
+                        format!("{} GMs", name),
+                        CheckStatus::Fail,
+                        format!("unexpected grandmaster(s) {}", unexpected.join(", ")),
+                    )
+                });
+            }
+
+            if let Some(range) = expected.receivers {
+                let status = if range.contains(instance.receivers) {
+                    CheckStatus::Pass
+                } else {
+                    CheckStatus::Fail
+                };
+                checks.push(Check::new(
+                    format!("{} receivers", name),
+                    status,
+                    format!("{} receiver(s), expected {}", instance.receivers, range),
+                ));
+            }
+        }
+
+        if !self.allow_other_domains {
+            for (sdo_id, domain) in instances.keys() {
+                if !self
+                    .domains
+                    .iter()
+                    .any(|expected| (expected.sdo_id, expected.domain) == (*sdo_id, *domain))
+                {
+                    checks.push(Check::new(
+                        format!("Domain {}", format_ptp_instance(*sdo_id, *domain)),
+                        CheckStatus::Fail,
+                        "not in the design",
+                    ));
+                }
+            }
+        }
+        checks
+    }
+
+    fn check_grandmaster(domain: &str, gm: &ExpectedGrandmaster, instance: &Instance) -> Check {
+        let name = format!("{} GM {}", domain, gm.identity);
+        let Some(PtpHostState::TimeTransmitter(state)) = instance
+            .transmitters
+            .get(&gm.identity)
+            .map(|host| &host.state)
+        else {
+            return Check::new(name, CheckStatus::Fail, "not announcing");
+        };
+
+        let mut mismatches = Vec::new();
+        let mut compare = |field: &str, expected: Option<u8>, actual: Option<u8>| {
+            if let Some(expected) = expected
+                && actual != Some(expected)
+            {
+                let actual = actual.map_or("-".to_string(), |value| value.to_string());
+                mismatches.push(format!("{} {} (expected {})", field, actual, expected));
+            }
+        };
+        compare("priority1", gm.priority1, state.priority1);
+        compare("priority2", gm.priority2, state.priority2);
+        compare(
+            "clockClass",
+            gm.clock_class,
+            state.clock_class.map(|class| class.class()),
+        );
+
+        if mismatches.is_empty() {
+            Check::new(name, CheckStatus::Pass, "announcing as designed")
+        } else {
+            Check::new(name, CheckStatus::Fail, mismatches.join(", "))
+        }
+    }
+}
+
+#[test]
+fn test_golden_diff() {
+    use crate::{ptp::PtpHostStateTimeTransmitter, types::PtpClockClass};
+
+    let golden: Golden = serde_yaml::from_str(
+        "
+domains:
+  - domain: 0
+    grandmasters:
+      - identity: 00:00:00:00:00:00:00:01
+        priority1: 128
+        clock_class: 6
+      - identity: 00:00:00:00:00:00:00:02
+    receivers:
+      min: 2
+",
+    )
+    .unwrap();
+    assert!(serde_yaml::from_str::<Golden>("domains: []\nextra: 1").is_err());
+    assert!(
+        serde_yaml::from_str::<Golden>(
+            "domains:\n  - domain: 0\n    grandmasters:\n      - identity: bogus"
+        )
+        .is_err()
+    );
+
+    let host = |id: u8, domain: u8, state: PtpHostState| {
+        let mut host = PtpHost::new(ClockIdentity {
+            clock_id: [0, 0, 0, 0, 0, 0, 0, id],
+        });
+        host.domain_number = Some(domain);
+        host.sdo_id = Some(0);
+        host.state = state;
+        host
+    };
+    let transmitter = |ptt: u8, priority1: u8| {
+        PtpHostState::TimeTransmitter(PtpHostStateTimeTransmitter {
+            priority1: Some(priority1),
+            clock_class: Some(PtpClockClass::new(6)),
+            ptt_identifier: Some(ClockIdentity {
+                clock_id: [0, 0, 0, 0, 0, 0, 0, ptt],
+            }),
+            ..Default::default()
+        })
+    };
+    let hosts = [
+        host(1, 0, transmitter(1, 127)),
+        host(3, 0, transmitter(3, 128)),
+        host(4, 0, PtpHostState::Listening),
+        host(5, 7, PtpHostState::Listening),
+    ];
+    let hosts: Vec<&PtpHost> = hosts.iter().collect();
+
+    let checks: Vec<String> = golden
+        .diff(&hosts)
+        .iter()
+        .map(|check| check.to_string())
+        .collect();
+    assert_eq!(
+        checks,
+        [
+            "[PASS] Domain 0                     3 host(s)",
+            "[FAIL] Domain 0 GM 00:00:00:00:00:00:00:01 priority1 127 (expected 128)",
+            "[FAIL] Domain 0 GM 00:00:00:00:00:00:00:02 not announcing",
+            "[FAIL] Domain 0 GMs                 unexpected grandmaster(s) 00:00:00:00:00:00:00:03",
+            "[FAIL] Domain 0 receivers           0 receiver(s), expected >= 2",
+            "[FAIL] Domain 7                     not in the design",
+        ]
+    );
+}
@@ -0,0 +1,83 @@
+//! Overall network health, summarized in the header
+//!
+//! Packets are never counted as dropped by the capture itself, so loss is
+//! estimated from gaps in the sequence ids of the periodic messages (Sync and
+//! Announce) each port sends. A gap may also be a message lost on the network
+//! before it reached the capture interface, which is just as relevant.
+
+use std::{collections::HashMap, time::Duration};
+
+use crate::types::{ClockIdentity, PtpMessageType};
+
+/// Gaps larger than this are a restarted sender, not lost messages
+const MAX_SEQUENCE_GAP: u16 = 1000;
+
+/// (sender, port, message type, sdoId, domain) of a sequence id stream
+type SequenceKey = (ClockIdentity, u16, PtpMessageType, u16, u8);
+
+/// Messages missing from the sequence id streams of all senders
+#[derive(Debug, Default)]
+pub struct SequenceLoss {
+    last: HashMap<SequenceKey, u16>,
+    received: u64,
+    missed: u64,
+}
+
+impl SequenceLoss {
+    pub fn record(&mut self, key: SequenceKey, sequence_id: u16) {
+        self.received += 1;
+        let Some(last) = self.last.insert(key, sequence_id) else {
+            return;
+        };
+
+        // Repeated or reordered messages don't count as missed ones
+        let gap = sequence_id.wrapping_sub(last);
+        if gap > 1 && gap <= MAX_SEQUENCE_GAP {
+            self.missed += (gap - 1) as u64;
+        }
+    }
+
+    /// Share of expected messages that never arrived, None before any did
+    pub fn loss_ratio(&self) -> Option<f64> {
+        let expected = self.received + self.missed;
+        (expected > 0).then(|| self.missed as f64 / expected as f64)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Coarse duration for badges, e.g. "45s", "12m", "2h" or "3d"
+pub fn format_badge_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+#[test]
+fn test_sequence_loss() {
+    let sender = ClockIdentity::default();
+    let key = (sender, 1, PtpMessageType::Sync, 0, 0);
+    let mut loss = SequenceLoss::default();
+    assert_eq!(loss.loss_ratio(), None);
+
+    // 3 and 4 missing, 6 repeated, then a wrap around 0xffff
+    for sequence_id in [1, 2, 5, 6, 6, 7] {
+        loss.record(key, sequence_id);
+    }
+    loss.record(key, 0xffff);
+    loss.record(key, 0);
+    assert_eq!(loss.loss_ratio(), Some(2.0 / 10.0));
+
+    // A restarted sender starting over is not counted
+    loss.record(key, 30_000);
+    assert_eq!(loss.loss_ratio(), Some(2.0 / 11.0));
+
+    assert_eq!(format_badge_duration(Duration::from_secs(7200)), "2h");
+    assert_eq!(format_badge_duration(Duration::from_secs(59)), "59s");
+}
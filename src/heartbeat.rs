@@ -0,0 +1,100 @@
+//! Heartbeat of the current PTT
+//!
+//! A glyph in the header that pulses with every Sync the PTT sends, so a
+//! stalled grandmaster is noticeable at a glance within a second, before any
+//! host times out. The screen is redrawn every HEARTBEAT_FRAME while there is
+//! a PTT to follow.
+
+use std::time::{Duration, Instant};
+
+use crate::types::ClockIdentity;
+
+/// Redraw interval while the heartbeat is shown
+pub const HEARTBEAT_FRAME: Duration = Duration::from_millis(100);
+/// How long the glyph stays lit after a Sync
+const PULSE: Duration = Duration::from_millis(150);
+/// Silence after which the PTT counts as lost, well above the slowest
+/// common Sync interval of 1 s
+const LOSS_TIMEOUT: Duration = Duration::from_millis(1500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeartbeatState {
+    /// No PTT elected
+    Idle,
+    /// A Sync arrived just now
+    Beat,
+    /// Between two Syncs
+    Rest,
+    /// No Sync for longer than LOSS_TIMEOUT
+    Lost(Duration),
+}
+
+#[derive(Debug, Default)]
+pub struct Heartbeat {
+    /// PTT and its Sync count at the last update
+    last: Option<(ClockIdentity, u32)>,
+    last_beat: Option<Instant>,
+}
+
+impl Heartbeat {
+    /// Follow the Sync count of the PTT, None if there is none
+    pub fn update(&mut self, ptt: Option<(ClockIdentity, u32)>, now: Instant) {
+        match (self.last, ptt) {
+            // A new PTT starts with a beat, its first Sync made it win
+            (Some((previous, _)), Some((current, _))) if previous != current => {
+                self.last_beat = Some(now);
+            }
+            (Some((_, previous)), Some((_, current))) if current > previous => {
+                self.last_beat = Some(now);
+            }
+            (None, Some(_)) => self.last_beat = Some(now),
+            _ => {}
+        }
+        self.last = ptt;
+    }
+
+    pub fn state(&self, now: Instant) -> HeartbeatState {
+        let (Some(_), Some(last_beat)) = (self.last, self.last_beat) else {
+            return HeartbeatState::Idle;
+        };
+        let silence = now.duration_since(last_beat);
+        if silence < PULSE {
+            HeartbeatState::Beat
+        } else if silence < LOSS_TIMEOUT {
+            HeartbeatState::Rest
+        } else {
+            HeartbeatState::Lost(silence)
+        }
+    }
+}
+
+#[test]
+fn test_heartbeat() {
+    let ptt = ClockIdentity::default();
+    let start = Instant::now();
+    let mut heartbeat = Heartbeat::default();
+    assert_eq!(heartbeat.state(start), HeartbeatState::Idle);
+
+    heartbeat.update(Some((ptt, 10)), start);
+    assert_eq!(heartbeat.state(start), HeartbeatState::Beat);
+    assert_eq!(
+        heartbeat.state(start + Duration::from_millis(500)),
+        HeartbeatState::Rest
+    );
+
+    // The count did not move, the beat fades and then the PTT is lost
+    let later = start + Duration::from_millis(1000);
+    heartbeat.update(Some((ptt, 10)), later);
+    assert_eq!(heartbeat.state(later), HeartbeatState::Rest);
+    let lost = start + Duration::from_secs(2);
+    assert_eq!(
+        heartbeat.state(lost),
+        HeartbeatState::Lost(Duration::from_secs(2))
+    );
+
+    heartbeat.update(Some((ptt, 11)), lost);
+    assert_eq!(heartbeat.state(lost), HeartbeatState::Beat);
+
+    heartbeat.update(None, lost);
+    assert_eq!(heartbeat.state(lost), HeartbeatState::Idle);
+}
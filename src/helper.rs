@@ -0,0 +1,247 @@
+//! Privileged capture helper ("ptp-trace capture-helper")
+//!
+//! Capturing needs raw sockets, and so root or CAP_NET_RAW, but the TUI with
+//! its parsers, analyses and terminal handling does not. The capture helper
+//! is the only part that runs privileged: it captures on the interfaces and
+//! passes the frames unparsed over a unix socket, in the frame format of the
+//! remote agents. A TUI started as an ordinary user with --helper SOCKET
+//! decodes them itself, so untrusted network data is only ever parsed by the
+//! unprivileged process. Access to the socket is controlled by its owner and
+//! mode (--owner, --mode).
+
+use anyhow::{Context, Result, bail};
+use std::{
+    os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+    sync::{Arc, atomic::AtomicU64},
+};
+use tokio::{
+    io::BufReader,
+    net::{UnixListener, UnixStream},
+    sync::{broadcast, mpsc},
+};
+
+use crate::{
+    remote::{self, Frame, PROTOCOL_VERSION, RECONNECT_INTERVAL, VIEWER_BACKLOG},
+    source::{PacketSource, RawPacket, process_ethernet_packet},
+};
+
+/// Parse a socket owner given as UID or UID:GID
+pub fn parse_owner(text: &str) -> Result<(u32, Option<u32>), String> {
+    let invalid = || format!("Invalid owner '{}', expected UID or UID:GID", text);
+    match text.split_once(':') {
+        Some((uid, gid)) => Ok((
+            uid.parse().map_err(|_| invalid())?,
+            Some(gid.parse().map_err(|_| invalid())?),
+        )),
+        None => Ok((text.parse().map_err(|_| invalid())?, None)),
+    }
+}
+
+/// Parse a socket mode given in octal, e.g. 660
+pub fn parse_mode(text: &str) -> Result<u32, String> {
+    u32::from_str_radix(text, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o777)
+        .ok_or_else(|| {
+            format!(
+                "Invalid mode '{}', expected octal permissions like 660",
+                text
+            )
+        })
+}
+
+/// Create the socket, replacing one left behind by an earlier helper. The
+/// helper runs privileged, so whatever else is at the path is left alone.
+fn bind(socket: &Path, owner: Option<(u32, Option<u32>)>, mode: u32) -> Result<UnixListener> {
+    if let Ok(metadata) = std::fs::symlink_metadata(socket) {
+        if !metadata.file_type().is_socket() {
+            bail!(
+                "{} exists and is not a socket, refusing to replace it",
+                socket.display()
+            );
+        }
+        if std::os::unix::net::UnixStream::connect(socket).is_ok() {
+            bail!(
+                "Another capture helper is listening on {}",
+                socket.display()
+            );
+        }
+        std::fs::remove_file(socket)
+            .with_context(|| format!("Failed to remove stale socket {}", socket.display()))?;
+    }
+
+    let listener = UnixListener::bind(socket)
+        .with_context(|| format!("Failed to create socket {}", socket.display()))?;
+    std::fs::set_permissions(socket, std::fs::Permissions::from_mode(mode))
+        .with_context(|| format!("Failed to set the mode of {}", socket.display()))?;
+    if let Some((uid, gid)) = owner {
+        std::os::unix::fs::chown(socket, Some(uid), gid)
+            .with_context(|| format!("Failed to change the owner of {}", socket.display()))?;
+    }
+    Ok(listener)
+}
+
+/// Device and inode of the socket at `socket`, None if there is none
+fn socket_identity(socket: &Path) -> Option<(u64, u64)> {
+    std::fs::symlink_metadata(socket)
+        .ok()
+        .filter(|metadata| metadata.file_type().is_socket())
+        .map(|metadata| (metadata.dev(), metadata.ino()))
+}
+
+/// Remove the socket created by this helper, unless something else took its
+/// place in the meantime
+fn remove_socket(socket: &Path, created: Option<(u64, u64)>) {
+    if created.is_some() && socket_identity(socket) == created {
+        let _ = std::fs::remove_file(socket);
+    }
+}
+
+/// Capture with `receiver` and pass the packets to every TUI connected to
+/// the socket until interrupted, then print a summary
+pub async fn run(
+    mut receiver: Box<dyn PacketSource>,
+    socket: PathBuf,
+    owner: Option<(u32, Option<u32>)>,
+    mode: u32,
+) -> Result<()> {
+    let listener = bind(&socket, owner, mode)?;
+    let created = socket_identity(&socket);
+    println!(
+        "Capture helper listening on {}, start the TUI with --helper {}",
+        socket.display(),
+        socket.display()
+    );
+
+    let (frames, _) = broadcast::channel::<Arc<Vec<u8>>>(VIEWER_BACKLOG);
+    let hello = Arc::new(
+        Frame::Hello {
+            version: PROTOCOL_VERSION,
+            site: String::new(),
+        }
+        .encode(),
+    );
+    let lagged = Arc::new(AtomicU64::new(0));
+
+    let viewer_frames = frames.clone();
+    let viewer_lagged = lagged.clone();
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tracing::info!("TUI connected to the capture helper");
+                    let hello = hello.clone();
+                    let mut frames = viewer_frames.subscribe();
+                    let lagged = viewer_lagged.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = remote::forward(stream, &hello, &mut frames, &lagged).await
+                        {
+                            tracing::info!(error = %e, "TUI disconnected from the capture helper");
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!(error = %e, "Failed to accept a TUI"),
+            }
+        }
+    });
+
+    let summary = remote::broadcast_packets(receiver.as_mut(), &frames, &lagged).await;
+    remove_socket(&socket, created);
+    println!("\n{}", summary?);
+    Ok(())
+}
+
+/// Receive the packets of the capture helper listening on `socket`,
+/// reconnecting whenever it goes away
+pub fn connect(socket: PathBuf) -> mpsc::UnboundedReceiver<RawPacket> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while !sender.is_closed() {
+            let result = match UnixStream::connect(&socket).await {
+                Ok(stream) => receive(stream, &sender).await,
+                Err(e) => Err(e.into()),
+            };
+            if let Err(e) = result {
+                tracing::warn!(socket = %socket.display(), error = %e, "Capture helper connection lost");
+            }
+            tokio::time::sleep(RECONNECT_INTERVAL).await;
+        }
+    });
+    receiver
+}
+
+async fn receive(stream: UnixStream, sender: &mpsc::UnboundedSender<RawPacket>) -> Result<()> {
+    let mut stream = BufReader::new(stream);
+    let Frame::Hello { version, .. } = remote::read_frame(&mut stream).await? else {
+        bail!("Capture helper did not start with a hello");
+    };
+    if version != PROTOCOL_VERSION {
+        bail!(
+            "Capture helper speaks protocol version {}, this TUI {}",
+            version,
+            PROTOCOL_VERSION
+        );
+    }
+    tracing::info!("Connected to the capture helper");
+
+    loop {
+        let Frame::Packet {
+            timestamp,
+            interface,
+            data,
+        } = remote::read_frame(&mut stream).await?
+        else {
+            bail!("Unexpected hello from the capture helper");
+        };
+        let Some(mut packet) = process_ethernet_packet(&data, &interface) else {
+            continue;
+        };
+        packet.timestamp = timestamp;
+        if sender.send(packet).is_err() {
+            // The TUI is shutting down
+            return Ok(());
+        }
+    }
+}
+
+#[test]
+fn test_socket_options() {
+    assert_eq!(parse_owner("1000"), Ok((1000, None)));
+    assert_eq!(parse_owner("1000:27"), Ok((1000, Some(27))));
+    assert!(parse_owner("alice").is_err());
+    assert!(parse_owner("1000:").is_err());
+
+    assert_eq!(parse_mode("660"), Ok(0o660));
+    assert_eq!(parse_mode("0600"), Ok(0o600));
+    assert!(parse_mode("680").is_err());
+    assert!(parse_mode("1777").is_err());
+}
+
+#[test]
+fn test_socket_replacement() {
+    let dir = std::env::temp_dir().join(format!("ptp-trace-helper-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("file");
+    std::fs::write(&file, "keep").unwrap();
+    let link = dir.join("link");
+    std::os::unix::fs::symlink(&file, &link).unwrap();
+
+    // Neither a file nor a link to one is taken for a stale socket
+    assert!(bind(&file, None, 0o660).is_err());
+    assert!(bind(&link, None, 0o660).is_err());
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep");
+    assert_eq!(socket_identity(&file), None);
+
+    // Only the socket the helper created is removed
+    let socket = dir.join("socket");
+    drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+    let created = socket_identity(&socket);
+    assert!(created.is_some());
+    remove_socket(&socket, None);
+    assert!(std::fs::symlink_metadata(&socket).is_ok());
+    remove_socket(&socket, created);
+    assert!(std::fs::symlink_metadata(&socket).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,465 @@
+//! Self-contained HTML report of a session ('H' and --html-report FILE)
+//!
+//! Findings often have to go to people who will never run a terminal tool:
+//! the vendor's support, a broadcast engineer, management. The report is a
+//! single HTML file without scripts or external resources that opens in any
+//! browser and survives being attached to a ticket: a summary, the
+//! transmitter/receiver topology as SVG, the host table, the alerts and per
+//! host its message counts, Sync intervals and packet history.
+
+use anyhow::{Context, Result};
+use std::{
+    fmt::Write,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    alerts::Alert,
+    clock::Clock,
+    csv_tail::format_time,
+    health::format_badge_duration,
+    ptp::{PtpHost, PtpHostState},
+};
+
+/// Packets per host in the report, the most recent ones
+const MAX_PACKETS_PER_HOST: usize = 500;
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+h1 { margin-bottom: 0.2em; }
+.meta { color: #666; }
+table { border-collapse: collapse; margin: 0.5em 0 1.5em; font-size: 0.9em; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; white-space: nowrap; }
+th { background: #f0f0f0; }
+td.mono, .mono { font-family: ui-monospace, monospace; }
+tr.PTT td:first-child { color: #197a30; font-weight: bold; }
+tr.CRIT td { color: #b00020; font-weight: bold; }
+tr.WARN td { color: #9a6700; }
+details { border: 1px solid #ddd; border-radius: 4px; margin: 0.5em 0; padding: 0.3em 0.8em; }
+summary { cursor: pointer; font-weight: bold; }
+svg text { font-family: ui-monospace, monospace; font-size: 12px; }
+";
+
+/// Everything the report shows
+pub struct Report<'a> {
+    /// Interfaces or pcap file the packets came from
+    pub source: String,
+    /// Time ages are measured to: the last packet of a pcap file, or now
+    pub reference_time: SystemTime,
+    /// Hosts in tree order, with their depth below the transmitter they follow
+    pub hosts: Vec<(&'a PtpHost, usize)>,
+    pub alerts: Vec<&'a Alert>,
+}
+
+/// Escape text for HTML element content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A host as a node of the topology diagram
+pub struct TopologyNode {
+    pub label: String,
+    /// Depth below the transmitter the host follows, 0 for roots
+    pub depth: usize,
+    /// CSS color of the node's border
+    pub color: &'static str,
+}
+
+/// Indented tree of the hosts, each connected to the node it follows: the
+/// closest node above with one level less depth
+pub fn topology_svg(nodes: &[TopologyNode]) -> String {
+    const ROW: usize = 30;
+    const INDENT: usize = 40;
+    const BOX_WIDTH: usize = 420;
+    const BOX_HEIGHT: usize = 22;
+
+    let depth = nodes.iter().map(|node| node.depth).max().unwrap_or(0);
+    let width = depth * INDENT + BOX_WIDTH + 2;
+    let height = nodes.len() * ROW + 2;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    );
+
+    // Rows of the nodes on the path from the root to the current one
+    let mut ancestors: Vec<usize> = Vec::new();
+    for (row, node) in nodes.iter().enumerate() {
+        ancestors.truncate(node.depth);
+        let x = node.depth * INDENT + 1;
+        let y = row * ROW + 1;
+        if let Some(&parent) = ancestors.last() {
+            let parent_x = x - INDENT + INDENT / 2;
+            let parent_bottom = parent * ROW + 1 + BOX_HEIGHT;
+            let middle = y + BOX_HEIGHT / 2;
+            let _ = writeln!(
+                svg,
+                "<path d=\"M{} {} V{} H{}\" fill=\"none\" stroke=\"#999\"/>",
+                parent_x, parent_bottom, middle, x
+            );
+        }
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\" fill=\"#fafafa\" stroke=\"{}\" stroke-width=\"2\"/>",
+            x, y, BOX_WIDTH, BOX_HEIGHT, node.color
+        );
+        let _ = writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\">{}</text>",
+            x + 6,
+            y + 15,
+            escape(&node.label)
+        );
+        if ancestors.len() == node.depth {
+            ancestors.push(row);
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Horizontal bars, one per (label, value), scaled to the largest value
+pub fn bar_chart_svg(bars: &[(&str, u64)]) -> String {
+    const ROW: usize = 18;
+    const LABEL_WIDTH: usize = 170;
+    const BAR_WIDTH: usize = 300;
+
+    let max = bars
+        .iter()
+        .map(|(_, value)| *value)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let width = LABEL_WIDTH + BAR_WIDTH + 80;
+    let height = bars.len() * ROW;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+        width, height
+    );
+    for (row, (label, value)) in bars.iter().enumerate() {
+        let y = row * ROW;
+        let length = (*value as f64 / max as f64 * BAR_WIDTH as f64).round() as usize;
+        let _ = writeln!(
+            svg,
+            "<text x=\"0\" y=\"{}\">{}</text><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#4a7ebb\"/><text x=\"{}\" y=\"{}\">{}</text>",
+            y + 13,
+            escape(label),
+            LABEL_WIDTH,
+            y + 3,
+            length,
+            ROW - 6,
+            LABEL_WIDTH + length + 6,
+            y + 13,
+            value
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Line chart of values in order, e.g. the intervals between Syncs, with the
+/// smallest and largest value labeled
+pub fn line_chart_svg(values: &[f64], unit: &str) -> String {
+    const WIDTH: f64 = 600.0;
+    const HEIGHT: f64 = 120.0;
+    const MARGIN: f64 = 70.0;
+
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let span = if max > min { max - min } else { 1.0 };
+    let step = WIDTH / values.len().saturating_sub(1).max(1) as f64;
+    let points: Vec<String> = values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            format!(
+                "{:.1},{:.1}",
+                MARGIN + i as f64 * step,
+                5.0 + (HEIGHT - 10.0) * (1.0 - (value - min) / span)
+            )
+        })
+        .collect();
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n\
+         <text x=\"0\" y=\"12\">{:.3} {}</text><text x=\"0\" y=\"{}\">{:.3} {}</text>\n\
+         <polyline points=\"{}\" fill=\"none\" stroke=\"#4a7ebb\"/>\n</svg>",
+        WIDTH + MARGIN,
+        HEIGHT,
+        max,
+        escape(unit),
+        HEIGHT - 2.0,
+        min,
+        escape(unit),
+        points.join(" ")
+    )
+}
+
+fn state_color(host: &PtpHost) -> &'static str {
+    match &host.state {
+        PtpHostState::TimeTransmitter(state) if state.is_bmca_winner => "#197a30",
+        PtpHostState::TimeTransmitter(_) => "#4a7ebb",
+        PtpHostState::TimeReceiver(_) => "#888",
+        PtpHostState::Listening => "#ccc",
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or("-".to_string(), |value| value.to_string())
+}
+
+fn host_table(report: &Report, html: &mut String) {
+    html.push_str(
+        "<table>\n<tr><th>State</th><th>Clock Identity</th><th>Vendor</th><th>IP Address</th>\
+         <th>Domain</th><th>Priority1</th><th>Class</th><th>Follows</th><th>Messages</th>\
+         <th>Last Seen</th></tr>\n",
+    );
+    for (host, _) in &report.hosts {
+        let (priority1, class, follows) = match &host.state {
+            PtpHostState::TimeTransmitter(state) => (
+                optional(state.priority1),
+                optional(state.clock_class.map(|class| class.class())),
+                "-".to_string(),
+            ),
+            PtpHostState::TimeReceiver(state) => (
+                "-".to_string(),
+                "-".to_string(),
+                optional(state.selected_transmitter_identity),
+            ),
+            PtpHostState::Listening => ("-".to_string(), "-".to_string(), "-".to_string()),
+        };
+        let state = host.state.short_string();
+        let _ = writeln!(
+            html,
+            "<tr class=\"{}\"><td>{}</td><td class=\"mono\"><a href=\"#{}\">{}</a></td><td>{}</td>\
+             <td class=\"mono\">{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"mono\">{}</td>\
+             <td>{}</td><td>{} ago</td></tr>",
+            state,
+            state,
+            host.clock_identity.to_string().replace(':', ""),
+            host.clock_identity,
+            escape(host.get_vendor_name().unwrap_or("-")),
+            optional(host.get_primary_ip()),
+            optional(host.domain_number),
+            priority1,
+            class,
+            follows,
+            host.total_messages_sent_count,
+            format_badge_duration(host.time_since_last_seen(Clock::Frozen(report.reference_time)))
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+fn alert_table(report: &Report, html: &mut String) {
+    if report.alerts.is_empty() {
+        html.push_str("<p>No alerts.</p>\n");
+        return;
+    }
+    html.push_str(
+        "<table>\n<tr><th>Time</th><th>Severity</th><th>Alert</th><th>Message</th></tr>\n",
+    );
+    for alert in &report.alerts {
+        let _ = writeln!(
+            html,
+            "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            alert.severity,
+            format_time(alert.timestamp),
+            alert.severity,
+            escape(&alert.kind.to_string()),
+            escape(&alert.message)
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+fn host_section(host: &PtpHost, html: &mut String) {
+    let _ = writeln!(
+        html,
+        "<details id=\"{}\"><summary>{} <span class=\"mono\">{}</span> {}</summary>",
+        host.clock_identity.to_string().replace(':', ""),
+        host.state.short_string(),
+        host.clock_identity,
+        escape(host.get_vendor_name().unwrap_or(""))
+    );
+
+    html.push_str("<h4>Messages sent</h4>\n");
+    html.push_str(&bar_chart_svg(&[
+        ("Announce", host.announce_count as u64),
+        ("Sync", host.sync_count as u64),
+        ("Follow_Up", host.follow_up_count as u64),
+        ("Delay_Req", host.delay_req_count as u64),
+        ("Delay_Resp", host.delay_resp_count as u64),
+        ("Pdelay_Req", host.pdelay_req_count as u64),
+        ("Pdelay_Resp", host.pdelay_resp_count as u64),
+        (
+            "Pdelay_Resp_Follow_Up",
+            host.pdelay_resp_follow_up_count as u64,
+        ),
+        ("Signaling", host.signaling_message_count as u64),
+        ("Management", host.management_message_count as u64),
+    ]));
+
+    let packets = host.get_packet_history();
+    let sync_times: Vec<SystemTime> = packets
+        .iter()
+        .filter(|packet| {
+            packet.ptp.header().message_type == crate::types::PtpMessageType::Sync
+                && packet.ptp.header().source_port_identity.clock_identity == host.clock_identity
+        })
+        .map(|packet| packet.raw.timestamp)
+        .collect();
+    let intervals: Vec<f64> = sync_times
+        .windows(2)
+        .map(|pair| {
+            pair[1]
+                .duration_since(pair[0])
+                .unwrap_or(Duration::ZERO)
+                .as_secs_f64()
+                * 1000.0
+        })
+        .collect();
+    if intervals.len() >= 2 {
+        html.push_str("<h4>Sync intervals in the packet history</h4>\n");
+        html.push_str(&line_chart_svg(&intervals, "ms"));
+    }
+
+    if !packets.is_empty() {
+        let shown = &packets[packets.len().saturating_sub(MAX_PACKETS_PER_HOST)..];
+        let _ = writeln!(
+            html,
+            "<h4>Packet history ({} of {} stored packets)</h4>",
+            shown.len(),
+            packets.len()
+        );
+        html.push_str(
+            "<table>\n<tr><th>Time</th><th>Interface</th><th>VLAN</th><th>Source</th>\
+             <th>Type</th><th>Domain</th><th>Sequence</th><th>Correction</th><th>Details</th></tr>\n",
+        );
+        for packet in shown {
+            let header = packet.ptp.header();
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"mono\">{}</td><td>{}</td>\
+                 <td>{}</td><td>{}</td><td>{}</td><td class=\"mono\">{}</td></tr>",
+                format_time(packet.raw.timestamp),
+                escape(&packet.raw.interface_label()),
+                optional(packet.raw.vlan_id),
+                optional(packet.raw.source_addr.map(|addr| addr.ip())),
+                header.message_type,
+                header.domain_number,
+                header.sequence_id,
+                escape(&header.correction_field.to_string()),
+                escape(&packet.ptp.to_string())
+            );
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</details>\n");
+}
+
+/// The report as one HTML document
+pub fn render(report: &Report) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>PTP network report - {}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        escape(&report.source),
+        STYLE
+    );
+
+    let transmitters = report
+        .hosts
+        .iter()
+        .filter(|(host, _)| host.is_transmitter())
+        .count();
+    let _ = writeln!(
+        html,
+        "<h1>PTP network report</h1>\n<p class=\"meta\">{} - {} - ptp-trace {}</p>\n\
+         <p>{} hosts, {} transmitters, {} alerts</p>",
+        escape(&report.source),
+        format_time(report.reference_time),
+        crate::version::get_version(),
+        report.hosts.len(),
+        transmitters,
+        report.alerts.len()
+    );
+
+    html.push_str("<h2>Topology</h2>\n");
+    let nodes: Vec<TopologyNode> = report
+        .hosts
+        .iter()
+        .map(|(host, depth)| TopologyNode {
+            label: format!(
+                "{:<3} {} {}",
+                host.state.short_string(),
+                host.clock_identity,
+                host.get_vendor_name().unwrap_or("")
+            ),
+            depth: *depth,
+            color: state_color(host),
+        })
+        .collect();
+    html.push_str(&topology_svg(&nodes));
+
+    html.push_str("\n<h2>Hosts</h2>\n");
+    host_table(report, &mut html);
+    html.push_str("<h2>Alerts</h2>\n");
+    alert_table(report, &mut html);
+    html.push_str("<h2>Host details</h2>\n");
+    for (host, _) in &report.hosts {
+        host_section(host, &mut html);
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+pub fn write(report: &Report, path: &Path) -> Result<()> {
+    std::fs::write(path, render(report))
+        .with_context(|| format!("Failed to write HTML report {}", path.display()))
+}
+
+#[test]
+fn test_html_report() {
+    assert_eq!(
+        escape("<a href=\"x\">Tom & Jerry's</a>"),
+        "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+    );
+
+    // A grandmaster with a boundary clock and its receiver below it, and a
+    // second root
+    let node = |label: &str, depth| TopologyNode {
+        label: label.to_string(),
+        depth,
+        color: "#000",
+    };
+    let svg = topology_svg(&[
+        node("GM <1>", 0),
+        node("BC", 1),
+        node("OC", 2),
+        node("OC", 1),
+        node("lonely", 0),
+    ]);
+    assert!(svg.contains("GM &lt;1&gt;"));
+    assert_eq!(svg.matches("<rect").count(), 5);
+    // Every node but the roots is connected to its parent
+    assert_eq!(svg.matches("<path").count(), 3);
+    // The second receiver of the GM hangs off the GM, not off the BC's receiver
+    assert!(svg.contains("<path d=\"M21 23 V102 H41\""));
+
+    let chart = bar_chart_svg(&[("Sync", 100), ("Announce", 0)]);
+    assert!(chart.contains("width=\"300\""));
+    assert!(chart.contains("width=\"0\""));
+    assert!(line_chart_svg(&[125.0, 124.5, 130.0], "ms").contains("130.000 ms"));
+}
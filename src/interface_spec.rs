@@ -0,0 +1,169 @@
+//! Capture interfaces given by name, pattern or address
+//!
+//! -i takes a plain interface name, a glob pattern ("en*", "eth[0-3]") or an
+//! address specifier ("addr=10.20.0.0/16", "addr=192.0.2.7") matching the
+//! interfaces that have an address in the network. Patterns and addresses are
+//! resolved against the interfaces present at startup and again whenever
+//! interfaces come and go, so one command line fits capture hosts with
+//! different interface naming.
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use pnet::ipnetwork::IpNetwork;
+
+const ADDRESS_PREFIX: &str = "addr=";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterfaceSpec {
+    Name(String),
+    Pattern(String),
+    Network(IpNetwork),
+}
+
+/// An interface present on the machine
+#[derive(Debug, Clone)]
+pub struct AvailableInterface {
+    pub name: String,
+    pub ips: Vec<IpAddr>,
+}
+
+impl AvailableInterface {
+    /// Address used to join the PTP multicast group on the interface
+    pub fn ipv4(&self) -> Option<Ipv4Addr> {
+        self.ips.iter().find_map(|ip| match ip {
+            IpAddr::V4(ip) => Some(*ip),
+            IpAddr::V6(_) => None,
+        })
+    }
+}
+
+impl InterfaceSpec {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        if let Some(address) = spec.strip_prefix(ADDRESS_PREFIX) {
+            return address
+                .parse::<IpNetwork>()
+                .or_else(|_| address.parse::<IpAddr>().map(IpNetwork::from))
+                .map(InterfaceSpec::Network)
+                .map_err(|_| {
+                    format!(
+                        "Invalid address '{}', expected e.g. addr=10.20.0.0/16",
+                        address
+                    )
+                });
+        }
+        if spec.contains(['*', '?', '[']) {
+            Ok(InterfaceSpec::Pattern(spec.to_string()))
+        } else {
+            Ok(InterfaceSpec::Name(spec.to_string()))
+        }
+    }
+
+    pub fn matches(&self, interface: &AvailableInterface) -> bool {
+        match self {
+            InterfaceSpec::Name(name) => *name == interface.name,
+            InterfaceSpec::Pattern(pattern) => glob_match(pattern, &interface.name),
+            InterfaceSpec::Network(network) => interface.ips.iter().any(|ip| network.contains(*ip)),
+        }
+    }
+}
+
+/// Shell style pattern match with `*`, `?` and `[...]` classes (ranges, `!`
+/// or `^` to negate)
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    glob_match_at(&pattern, &name)
+}
+
+fn glob_match_at(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| glob_match_at(&pattern[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && glob_match_at(&pattern[1..], &name[1..]),
+        Some('[') => {
+            let Some(close) = pattern.iter().skip(2).position(|c| *c == ']') else {
+                // No closing bracket, match it literally
+                return name.first() == Some(&'[') && glob_match_at(&pattern[1..], &name[1..]);
+            };
+            let class = &pattern[1..close + 2];
+            let Some(c) = name.first() else {
+                return false;
+            };
+            class_matches(class, *c) && glob_match_at(&pattern[close + 3..], &name[1..])
+        }
+        Some(c) => name.first() == Some(c) && glob_match_at(&pattern[1..], &name[1..]),
+    }
+}
+
+fn class_matches(class: &[char], c: char) -> bool {
+    let (negated, class) = match class.first() {
+        Some('!') | Some('^') => (true, &class[1..]),
+        _ => (false, class),
+    };
+    let mut matched = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            matched |= (class[i]..=class[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            matched |= class[i] == c;
+            i += 1;
+        }
+    }
+    matched != negated
+}
+
+/// Interfaces the specs select, in the order of the specs
+pub fn resolve<'a>(
+    specs: &[InterfaceSpec],
+    available: &'a [AvailableInterface],
+) -> Vec<&'a AvailableInterface> {
+    let mut selected: Vec<&AvailableInterface> = Vec::new();
+    for spec in specs {
+        for interface in available {
+            if spec.matches(interface) && !selected.iter().any(|s| s.name == interface.name) {
+                selected.push(interface);
+            }
+        }
+    }
+    selected
+}
+
+#[test]
+fn test_interface_specs() {
+    let interface = |name: &str, ips: &[&str]| AvailableInterface {
+        name: name.to_string(),
+        ips: ips.iter().map(|ip| ip.parse().unwrap()).collect(),
+    };
+    let available = [
+        interface("eth0", &["10.20.1.5"]),
+        interface("eth4", &["192.0.2.1"]),
+        interface("enp3s0", &["fe80::1", "10.30.0.2"]),
+        interface("lo", &["127.0.0.1"]),
+    ];
+    let names = |specs: &[&str]| -> Vec<String> {
+        let specs: Vec<InterfaceSpec> = specs
+            .iter()
+            .map(|spec| InterfaceSpec::parse(spec).unwrap())
+            .collect();
+        resolve(&specs, &available)
+            .iter()
+            .map(|interface| interface.name.clone())
+            .collect()
+    };
+
+    assert_eq!(names(&["e*"]), ["eth0", "eth4", "enp3s0"]);
+    assert_eq!(names(&["eth[0-3]"]), ["eth0"]);
+    assert_eq!(names(&["eth[!0-3]", "eth?"]), ["eth4", "eth0"]);
+    assert_eq!(names(&["addr=10.0.0.0/8"]), ["eth0", "enp3s0"]);
+    assert_eq!(names(&["addr=192.0.2.1"]), ["eth4"]);
+    assert_eq!(names(&["lo", "l*"]), ["lo"]);
+    assert!(names(&["wlan0"]).is_empty());
+
+    assert_eq!(available[2].ipv4(), Some(Ipv4Addr::new(10, 30, 0, 2)));
+    assert!(InterfaceSpec::parse("addr=10.20.0.0/33").is_err());
+    assert!(glob_match("br-*", "br-123456"));
+    assert!(!glob_match("br-*", "virbr0"));
+    assert!(glob_match("a[b", "a[b"));
+}
@@ -0,0 +1,193 @@
+//! Key bindings, as listed in the help view
+//!
+//! The help view is generated from this table rather than written out line by
+//! line, so it can be searched ('/' in help) and scoped to the feature in use:
+//! F1 in the packet modal, the tree view or with a host filter active only
+//! lists the keys of that feature.
+
+/// Group of key bindings, each shown under its own heading
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelpSection {
+    Navigation,
+    PacketModal,
+    TreeView,
+    Filter,
+    Mouse,
+    Actions,
+    General,
+}
+
+impl HelpSection {
+    pub const ALL: [HelpSection; 7] = [
+        HelpSection::Navigation,
+        HelpSection::PacketModal,
+        HelpSection::TreeView,
+        HelpSection::Filter,
+        HelpSection::Mouse,
+        HelpSection::Actions,
+        HelpSection::General,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            HelpSection::Navigation => "Navigation",
+            HelpSection::PacketModal => "Packet Details",
+            HelpSection::TreeView => "Tree View",
+            HelpSection::Filter => "Host Filter",
+            HelpSection::Mouse => "Mouse Support",
+            HelpSection::Actions => "Actions",
+            HelpSection::General => "General",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub section: HelpSection,
+    pub keys: &'static str,
+    pub description: &'static str,
+}
+
+const fn bind(section: HelpSection, keys: &'static str, description: &'static str) -> KeyBinding {
+    KeyBinding {
+        section,
+        keys,
+        description,
+    }
+}
+
+use HelpSection::*;
+
+#[rustfmt::skip]
+pub const KEYMAP: &[KeyBinding] = &[
+    bind(Navigation, "Tab", "Cycle: Host Table → Host Details → Packet History"),
+    bind(Navigation, "↑/k", "Move selection up (host table) or scroll (details/packets)"),
+    bind(Navigation, "↓/j", "Move selection down (host table) or scroll (details/packets)"),
+    bind(Navigation, "PgUp/PgDn", "Page up/down (10 items or 1 page scroll)"),
+    bind(Navigation, "Home/End", "Jump to top/bottom"),
+    bind(Navigation, "Enter", "Show packet details (when packet history active)"),
+    bind(Navigation, "Enter", "Jump from a receiver to its selected transmitter (host table)"),
+    bind(Navigation, "Backspace", "Return to the host before the last jump"),
+    bind(PacketModal, "q", "Close packet details modal"),
+    bind(PacketModal, "↑↓/k/j", "Scroll modal content"),
+    bind(PacketModal, "PgUp/PgDn/Space", "Page scroll modal content"),
+    bind(PacketModal, "Home/End", "Jump to top/bottom of modal"),
+    bind(PacketModal, "←/→", "Previous/next packet in exchange"),
+    bind(PacketModal, "x", "Raw header fields as decimal, hex or both"),
+    bind(PacketModal, "b", "Bookmark the packet with a note, again to remove"),
+    bind(TreeView, "t", "Toggle tree view mode"),
+    bind(TreeView, "↑/k ↓/j", "Move through the hosts in tree order"),
+    bind(TreeView, "Enter", "Jump from a receiver to its transmitter in the tree"),
+    bind(TreeView, "█ / ░", "Announce continuity: on schedule / missed, 5 s cells over the last minute"),
+    bind(Filter, "F", "Toggle the host filter given with --filter"),
+    bind(Filter, "V", "Hosts per vendor and state, Enter filters the host table"),
+    bind(Filter, "c", "Clear the vendor filter (vendor breakdown)"),
+    bind(Filter, "domain=N", "Hosts in domain N (--filter terms are comma separated)"),
+    bind(Filter, "sdo=N", "Hosts with sdoId N"),
+    bind(Filter, "vlan=N", "Hosts on VLAN N"),
+    bind(Filter, "state=S", "transmitter, receiver, listening or p2p"),
+    bind(Filter, "vendor=TEXT", "Vendor name containing TEXT"),
+    bind(Filter, "interface=TEXT", "Capture interface containing TEXT"),
+    bind(Filter, "ip=TEXT", "IP address containing TEXT"),
+    bind(Filter, "clock=TEXT", "Clock identity containing TEXT"),
+    bind(Filter, "tag=NAME", "Hosts tagged NAME with 'E'"),
+    bind(Mouse, "Click", "Switch to view and select row (host table/packet history)"),
+    bind(Mouse, "Double-click", "Open packet details modal (packet history rows)"),
+    bind(Mouse, "Click mini-map", "Jump to that part of the packet history"),
+    bind(Mouse, "Click outside modal", "Close packet details modal"),
+    bind(Mouse, "Scroll wheel", "Navigate selections/scroll content"),
+    bind(Mouse, "--no-mouse", "Disable mouse support"),
+    bind(Actions, "r", "Refresh/rescan network"),
+    bind(Actions, "Ctrl+L", "Refresh/redraw screen"),
+    bind(Actions, "Ctrl+R", "Reload the config file and golden file (also on SIGHUP)"),
+    bind(Actions, "c", "Clear all hosts and packet histories"),
+    bind(Actions, "x", "Clear packet history for selected host"),
+    bind(Actions, "R", "Toggle packet history retention for selected host (flag NH)"),
+    bind(Actions, "p", "Toggle pause mode"),
+    bind(Actions, "z", "Freeze the host table, then the packet history instead, then resume"),
+    bind(Actions, "P", "Export the packet history as shown (pcap, CSV or JSON)"),
+    bind(Actions, "b", "Bookmark the selected packet with a note, again to remove"),
+    bind(Actions, "B", "Bookmark list: jump, edit note, export as pcapng"),
+    bind(Actions, "E", "Edit the tags of the selected host (filter with tag=NAME)"),
+    bind(Actions, "M", "Mute the selected host: left out of table, statistics and alerts, again to unmute"),
+    bind(Actions, "U", "List muted hosts too, to unmute them"),
+    bind(Actions, "w", "Toggle packet auto-scroll"),
+    bind(Actions, "s", "Cycle host table sorting"),
+    bind(Actions, "a", "Previous sort column"),
+    bind(Actions, "S", "Reverse sort direction"),
+    bind(Actions, "T", "Toggle host timeline (observed spans and gaps)"),
+    bind(Actions, "g", "Toggle GM candidate matrix (BMCA attributes per domain)"),
+    bind(Actions, "A", "Top talkers: hosts by Announce/Sync/delay rate over the last minute, 's' sort, 'd' domain"),
+    bind(Actions, "C", "Toggle compliance with the --golden design file"),
+    bind(Actions, "v", "Toggle hosts differing from the --baseline session"),
+    bind(Actions, "L", "Toggle message swimlanes of the selected host's domain"),
+    bind(Actions, "W", "BMCA what-if: change P1/class/P2 of a snapshot and re-run the election"),
+    bind(Actions, "I", "Toggle interfaces view (PHC hardware clocks)"),
+    bind(Actions, "O", "Toggle port matrix (event 319 / general 320 ports of each host)"),
+    bind(Actions, "f", "Start/end failover drill on the selected host's PTT"),
+    bind(Actions, "N", "Ask the selected host for its product and name (--active)"),
+    bind(Actions, "y / Y", "Copy host details (the highlighted field with the details focused) / clock identity to clipboard (OSC 52)"),
+    bind(Actions, "m<reg> / m", "Start / stop recording a keyboard macro into a register"),
+    bind(Actions, "@<reg>, @@", "Replay a macro, replay the last macro again"),
+    bind(Actions, "e", "Toggle expanded packet history"),
+    bind(Actions, "i", "Toggle Δt column (interval since previous packet)"),
+    bind(Actions, "K", "Toggle legend bar (message type colors, state abbreviations)"),
+    bind(Actions, "n", "Show packets of one capture interface at a time"),
+    bind(Actions, "u", "Statistics and packet history over the last 1/5/15/60 min or the session"),
+    bind(Actions, "?", "Explain the values marked ⚠ in host details"),
+    bind(Actions, "X", "Open the last Delay_Req/Delay_Resp without a partner (flag DX)"),
+    bind(Actions, "G", "Save the screen as text and ANSI files to the export directory"),
+    bind(Actions, "H", "Save a self-contained HTML report to share with others"),
+    bind(Actions, "d", "Toggle log pane"),
+    bind(Actions, "Z", "Toggle profiling pane (time per pipeline stage, packet rate)"),
+    bind(Actions, "D", "Toggle duplicate packet suppression (SPAN de-dup)"),
+    bind(General, "h", "Show/hide this help"),
+    bind(General, "F1", "Help for the packet modal, tree view or host filter in use"),
+    bind(General, "/", "Search the help (in help), Tab switches between scoped and all keys"),
+    bind(General, "o", "Settings (update interval, history, theme, ...)"),
+    bind(General, "Esc/q", "Close help"),
+    bind(General, "q", "Close modal/help or quit application"),
+];
+
+/// Key bindings of `scope` (all sections if None) whose keys, description or
+/// section title contain `query`, ignoring case
+pub fn search(query: &str, scope: Option<HelpSection>) -> Vec<&'static KeyBinding> {
+    let query = query.to_lowercase();
+    KEYMAP
+        .iter()
+        .filter(|binding| scope.is_none_or(|scope| binding.section == scope))
+        .filter(|binding| {
+            query.is_empty()
+                || binding.keys.to_lowercase().contains(&query)
+                || binding.description.to_lowercase().contains(&query)
+                || binding.section.title().to_lowercase().contains(&query)
+        })
+        .collect()
+}
+
+#[test]
+fn test_keymap_search() {
+    // Scoped help only lists the feature's keys
+    let modal = search("", Some(PacketModal));
+    assert!(!modal.is_empty());
+    assert!(modal.iter().all(|binding| binding.section == PacketModal));
+
+    // Searching matches keys and descriptions, ignoring case
+    let export = search("PCAP", None);
+    assert!(export.iter().any(|binding| binding.keys == "P"));
+    assert!(export.iter().any(|binding| binding.keys == "B"));
+    assert!(search("pcap", Some(TreeView)).is_empty());
+
+    // and section titles
+    let tree_view = search("tree view", None);
+    assert!(
+        search("", Some(TreeView))
+            .iter()
+            .all(|binding| tree_view.contains(binding))
+    );
+
+    // Every section has bindings
+    for section in HelpSection::ALL {
+        assert!(!search("", Some(section)).is_empty(), "{:?}", section);
+    }
+}
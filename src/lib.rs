@@ -0,0 +1,20 @@
+//! Packet capture and PTP decoding of ptp-trace, for embedding
+//!
+//! The `ptp-trace` binary gets its packets from a `source::PacketSource`.
+//! Besides the sources it comes with (live capture, pcap files, remote
+//! agents, the capture helper and the synthetic generator), an application
+//! can implement the trait for its own, e.g. packets read from stdin or the
+//! tail of a ring buffer, and decode what it gets with
+//! `source::process_ethernet_packet` and `types::PtpMessage`.
+
+pub mod bond;
+pub mod capture_workers;
+#[cfg(unix)]
+pub mod helper;
+pub mod interface_spec;
+pub mod oui_map;
+pub mod remote;
+pub mod session_summary;
+pub mod source;
+pub mod synth;
+pub mod types;
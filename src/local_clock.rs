@@ -0,0 +1,505 @@
+//! State of the PTP stack on the local machine
+//!
+//! Shown next to the network view to tie what is on the wire to what the host
+//! experiences:
+//! - ptp4l is asked for TIME_STATUS_NP over its UNIX domain management socket,
+//!   the same way `pmc` does, for the servo offset and the grandmaster it follows
+//! - a PHC device is read directly, for the frequency adjustment the servo
+//!   applied and the offset between the PHC and the system clock
+//!
+//! Both are optional and polled once per second by a thread of their own, a
+//! ptp4l that doesn't answer doesn't stall the UI.
+
+use anyhow::{Context, Result, bail};
+use std::{
+    ffi::{CString, OsString},
+    os::unix::{ffi::OsStringExt, net::UnixDatagram},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::types::ClockIdentity;
+
+/// Socket ptp4l listens on by default
+pub const DEFAULT_PTP4L_SOCKET: &str = "/var/run/ptp4l";
+/// How often the local clock state is refreshed
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How long to wait for ptp4l to answer
+const RESPONSE_TIMEOUT: Duration = Duration::from_millis(200);
+
+const MANAGEMENT_MESSAGE_TYPE: u8 = 0x0d;
+const MANAGEMENT_CONTROL_FIELD: u8 = 0x04;
+const ACTION_GET: u8 = 0;
+const ACTION_RESPONSE: u8 = 2;
+const TLV_MANAGEMENT: u16 = 0x0001;
+const TLV_MANAGEMENT_ERROR_STATUS: u16 = 0x0002;
+/// linuxptp specific management id
+const TIME_STATUS_NP: u16 = 0xc000;
+
+/// TIME_STATUS_NP answer of ptp4l
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ptp4lStatus {
+    /// Offset from the time transmitter as seen by the servo
+    pub master_offset_ns: i64,
+    /// Frequency ratio to the grandmaster, (rateRatio - 1) * 2^41
+    pub cumulative_scaled_rate_offset: i32,
+    pub gm_present: bool,
+    pub gm_identity: ClockIdentity,
+}
+
+impl Ptp4lStatus {
+    /// Frequency offset to the grandmaster in ppb
+    pub fn rate_offset_ppb(&self) -> f64 {
+        self.cumulative_scaled_rate_offset as f64 / (1u64 << 41) as f64 * 1e9
+    }
+}
+
+/// GET TIME_STATUS_NP, addressed to all ports of the local clock
+fn time_status_request(domain: u8, sequence_id: u16) -> Vec<u8> {
+    let mut msg = vec![0u8; 54];
+    msg[0] = MANAGEMENT_MESSAGE_TYPE;
+    msg[1] = 2; // PTP version
+    msg[2..4].copy_from_slice(&54u16.to_be_bytes());
+    msg[4] = domain;
+    // Source port identity like pmc: no clock identity, the PID as port number
+    msg[28..30].copy_from_slice(&(std::process::id() as u16).to_be_bytes());
+    msg[30..32].copy_from_slice(&sequence_id.to_be_bytes());
+    msg[32] = MANAGEMENT_CONTROL_FIELD;
+    msg[33] = 0x7f;
+    msg[34..44].fill(0xff);
+    msg[46] = ACTION_GET;
+    msg[48..50].copy_from_slice(&TLV_MANAGEMENT.to_be_bytes());
+    msg[50..52].copy_from_slice(&2u16.to_be_bytes());
+    msg[52..54].copy_from_slice(&TIME_STATUS_NP.to_be_bytes());
+    msg
+}
+
+fn parse_time_status_response(data: &[u8]) -> Result<Ptp4lStatus> {
+    if data.len() < 54 || data[0] & 0x0f != MANAGEMENT_MESSAGE_TYPE {
+        bail!("Not a management message");
+    }
+    if data[46] & 0x0f != ACTION_RESPONSE {
+        bail!("Unexpected management action {}", data[46] & 0x0f);
+    }
+
+    let tlv_type = u16::from_be_bytes([data[48], data[49]]);
+    let management_id = u16::from_be_bytes([data[52], data[53]]);
+    if tlv_type == TLV_MANAGEMENT_ERROR_STATUS {
+        bail!("ptp4l refused the request");
+    }
+    if tlv_type != TLV_MANAGEMENT || management_id != TIME_STATUS_NP {
+        bail!(
+            "Unexpected management TLV {:04x}/{:04x}",
+            tlv_type,
+            management_id
+        );
+    }
+
+    let status = &data[54..];
+    if status.len() < 50 {
+        bail!("TIME_STATUS_NP too short");
+    }
+    let mut gm_identity = ClockIdentity::default();
+    gm_identity.clock_id.copy_from_slice(&status[42..50]);
+    Ok(Ptp4lStatus {
+        master_offset_ns: i64::from_be_bytes(status[0..8].try_into()?),
+        cumulative_scaled_rate_offset: i32::from_be_bytes(status[16..20].try_into()?),
+        gm_present: i32::from_be_bytes(status[38..42].try_into()?) != 0,
+        gm_identity,
+    })
+}
+
+/// Create a directory only the current user can enter, with an unpredictable
+/// name in the temporary directory
+fn private_temp_dir() -> Result<PathBuf> {
+    let template = std::env::temp_dir().join("ptp-trace.XXXXXX");
+    let template = CString::new(template.into_os_string().into_vec())?;
+    let mut template = template.into_bytes_with_nul();
+    // SAFETY: the template is NUL terminated, mkdtemp replaces the Xs in place
+    if unsafe { libc::mkdtemp(template.as_mut_ptr().cast()) }.is_null() {
+        return Err(std::io::Error::last_os_error())
+            .context("Failed to create a directory for the ptp4l client socket");
+    }
+    template.pop();
+    Ok(PathBuf::from(OsString::from_vec(template)))
+}
+
+/// Management client for ptp4l's UNIX domain socket
+struct Ptp4lClient {
+    socket: UnixDatagram,
+    local_path: PathBuf,
+    domain: u8,
+    sequence_id: u16,
+}
+
+impl Ptp4lClient {
+    fn connect(server_path: &Path, domain: u8) -> Result<Self> {
+        // ptp4l answers to the address of the sender, so the socket needs a
+        // name, in a directory of our own so that no one can put one in its way
+        let dir = private_temp_dir()?;
+        let local_path = dir.join("pmc");
+        let socket = match UnixDatagram::bind(&local_path) {
+            Ok(socket) => socket,
+            Err(e) => {
+                let _ = std::fs::remove_dir(&dir);
+                return Err(e).with_context(|| format!("Failed to bind {}", local_path.display()));
+            }
+        };
+        // Removes the socket and its directory if connecting fails
+        let client = Self {
+            socket,
+            local_path,
+            domain,
+            sequence_id: 0,
+        };
+        client.socket.set_read_timeout(Some(RESPONSE_TIMEOUT))?;
+        client
+            .socket
+            .connect(server_path)
+            .with_context(|| format!("Failed to connect to ptp4l at {}", server_path.display()))?;
+        Ok(client)
+    }
+
+    fn time_status(&mut self) -> Result<Ptp4lStatus> {
+        self.sequence_id = self.sequence_id.wrapping_add(1);
+        self.socket
+            .send(&time_status_request(self.domain, self.sequence_id))?;
+
+        let mut buffer = [0u8; 1500];
+        loop {
+            let len = self
+                .socket
+                .recv(&mut buffer)
+                .context("No answer from ptp4l")?;
+            // Skip late answers to earlier requests
+            if len >= 32 && u16::from_be_bytes([buffer[30], buffer[31]]) == self.sequence_id {
+                return parse_time_status_response(&buffer[..len]);
+            }
+        }
+    }
+}
+
+impl Drop for Ptp4lClient {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.local_path);
+        if let Some(dir) = self.local_path.parent() {
+            let _ = std::fs::remove_dir(dir);
+        }
+    }
+}
+
+/// State of a PTP hardware clock
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhcStatus {
+    /// Frequency adjustment currently applied to the PHC
+    pub freq_ppb: f64,
+    /// PHC minus system clock, usually the TAI-UTC offset when ptp4l and phc2sys run
+    pub sys_offset_ns: i64,
+    /// Current time of the PHC, on whatever timescale it runs
+    pub phc_time: SystemTime,
+}
+
+#[cfg(target_os = "linux")]
+fn read_phc(device: &Path) -> Result<PhcStatus> {
+    use std::os::fd::AsRawFd;
+
+    const PTP_MAX_SAMPLES: usize = 25;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    struct PtpClockTime {
+        sec: i64,
+        nsec: u32,
+        reserved: u32,
+    }
+
+    #[repr(C)]
+    struct PtpSysOffset {
+        n_samples: u32,
+        rsv: [u32; 3],
+        ts: [PtpClockTime; 2 * PTP_MAX_SAMPLES + 1],
+    }
+
+    // _IOW('=', 5, struct ptp_sys_offset)
+    const PTP_SYS_OFFSET: u64 =
+        (1 << 30) | ((std::mem::size_of::<PtpSysOffset>() as u64) << 16) | ((b'=' as u64) << 8) | 5;
+
+    let file = std::fs::File::open(device)
+        .with_context(|| format!("Failed to open {}", device.display()))?;
+    let fd = file.as_raw_fd();
+
+    // Dynamic POSIX clock id of the open PHC device
+    let clock_id = ((!fd) << 3) | 3;
+    let mut timex: libc::timex = unsafe { std::mem::zeroed() };
+    if unsafe { libc::clock_adjtime(clock_id, &mut timex) } < 0 {
+        bail!(
+            "Failed to read frequency of {}: {}",
+            device.display(),
+            std::io::Error::last_os_error()
+        );
+    }
+    // freq is in ppm with a 16 bit fractional part
+    let freq_ppb = timex.freq as f64 * 1000.0 / 65536.0;
+
+    let mut request = PtpSysOffset {
+        n_samples: 5,
+        rsv: [0; 3],
+        ts: [PtpClockTime::default(); 2 * PTP_MAX_SAMPLES + 1],
+    };
+    if unsafe { libc::ioctl(fd, PTP_SYS_OFFSET as _, &mut request) } < 0 {
+        bail!(
+            "PTP_SYS_OFFSET failed on {}: {}",
+            device.display(),
+            std::io::Error::last_os_error()
+        );
+    }
+
+    // Samples alternate system, PHC, system; the tightest bracket wins
+    let ns = |t: &PtpClockTime| t.sec as i128 * 1_000_000_000 + t.nsec as i128;
+    let (_, sys_offset_ns, phc_ns) = (0..request.n_samples as usize)
+        .map(|i| {
+            let (before, phc, after) = (
+                ns(&request.ts[2 * i]),
+                ns(&request.ts[2 * i + 1]),
+                ns(&request.ts[2 * i + 2]),
+            );
+            (after - before, phc - (before + after) / 2, phc)
+        })
+        .min_by_key(|(delay, _, _)| *delay)
+        .context("PTP_SYS_OFFSET returned no samples")?;
+
+    Ok(PhcStatus {
+        freq_ppb,
+        sys_offset_ns: sys_offset_ns as i64,
+        phc_time: UNIX_EPOCH + Duration::from_nanos(phc_ns.max(0) as u64),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_phc(_device: &Path) -> Result<PhcStatus> {
+    bail!("PHC devices are only supported on Linux")
+}
+
+/// PHC device of a network interface, if its driver has one. A NIC without
+/// one can't timestamp PTP packets in hardware.
+pub fn interface_phc(interface: &str) -> Option<PathBuf> {
+    let ptp_dir = Path::new("/sys/class/net")
+        .join(interface)
+        .join("device/ptp");
+    std::fs::read_dir(ptp_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("ptp"))
+        .min()
+        .map(|name| Path::new("/dev").join(name))
+}
+
+/// PHC of a capture interface, with its drift measured over the time it is watched
+#[derive(Debug, Clone)]
+pub struct InterfaceClock {
+    pub interface: String,
+    pub phc: Option<PathBuf>,
+    pub status: Option<Result<PhcStatus, String>>,
+    /// First PHC-to-system offset, the baseline drift is measured against
+    baseline: Option<(Instant, i64)>,
+    /// Drift of the PHC against the system clock in ppm
+    pub drift_ppm: Option<f64>,
+}
+
+impl InterfaceClock {
+    fn poll(&mut self) {
+        let Some(device) = &self.phc else {
+            return;
+        };
+
+        let status = read_phc(device);
+        if let Ok(status) = &status {
+            match self.baseline {
+                Some((since, offset)) => {
+                    let elapsed = since.elapsed().as_nanos() as f64;
+                    if elapsed > 0.0 {
+                        self.drift_ppm =
+                            Some((status.sys_offset_ns - offset) as f64 / elapsed * 1e6);
+                    }
+                }
+                None => self.baseline = Some((Instant::now(), status.sys_offset_ns)),
+            }
+        }
+        self.status = Some(status.map_err(|e| format!("{:#}", e)));
+    }
+}
+
+/// Hardware clocks of all capture interfaces
+#[derive(Debug, Clone, Default)]
+pub struct InterfaceClocks {
+    clocks: Vec<InterfaceClock>,
+    last_poll: Option<Instant>,
+}
+
+impl InterfaceClocks {
+    pub fn new(interfaces: &[String]) -> Self {
+        Self {
+            clocks: interfaces
+                .iter()
+                .map(|interface| InterfaceClock {
+                    interface: interface.clone(),
+                    phc: interface_phc(crate::bond::device_name(interface)),
+                    status: None,
+                    baseline: None,
+                    drift_ppm: None,
+                })
+                .collect(),
+            last_poll: None,
+        }
+    }
+
+    /// Read all PHCs if the last reading is older than the poll interval
+    pub fn poll(&mut self) {
+        if self
+            .last_poll
+            .is_some_and(|last_poll| last_poll.elapsed() < POLL_INTERVAL)
+        {
+            return;
+        }
+        self.last_poll = Some(Instant::now());
+        self.clocks.iter_mut().for_each(InterfaceClock::poll);
+    }
+
+    pub fn clocks(&self) -> &[InterfaceClock] {
+        &self.clocks
+    }
+}
+
+/// Latest state of the local PTP stack
+#[derive(Debug, Clone, Default)]
+pub struct LocalClockState {
+    pub ptp4l: Option<Result<Ptp4lStatus, String>>,
+    pub phc: Option<(PathBuf, Result<PhcStatus, String>)>,
+}
+
+/// Polls ptp4l and the PHC on a thread of its own, stopped when dropped
+pub struct LocalClockMonitor {
+    state: Arc<Mutex<LocalClockState>>,
+    /// Dropping it wakes the thread up to stop
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl LocalClockMonitor {
+    pub fn new(
+        ptp4l_socket: Option<PathBuf>,
+        ptp4l_domain: u8,
+        phc_device: Option<PathBuf>,
+    ) -> Self {
+        let state = Arc::new(Mutex::new(LocalClockState::default()));
+        let (stop, stopped) = mpsc::channel::<()>();
+        let mut poller = LocalClockPoller {
+            ptp4l_socket,
+            ptp4l_domain,
+            ptp4l: None,
+            phc_device,
+        };
+        let shared = state.clone();
+        let thread = std::thread::Builder::new()
+            .name("local-clock".to_string())
+            .spawn(move || {
+                loop {
+                    let polled = poller.poll();
+                    *shared.lock().unwrap() = polled;
+                    if stopped.recv_timeout(POLL_INTERVAL) != Err(RecvTimeoutError::Timeout) {
+                        break;
+                    }
+                }
+            })
+            .inspect_err(|e| tracing::warn!(error = %e, "Could not start the local clock thread"))
+            .ok();
+
+        Self {
+            state,
+            stop: Some(stop),
+            thread,
+        }
+    }
+
+    /// Latest state polled
+    pub fn state(&self) -> LocalClockState {
+        self.state.lock().unwrap().clone()
+    }
+}
+
+impl Drop for LocalClockMonitor {
+    fn drop(&mut self) {
+        // Joined so that the client socket is removed before exiting
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// What the thread of a `LocalClockMonitor` polls
+struct LocalClockPoller {
+    ptp4l_socket: Option<PathBuf>,
+    ptp4l_domain: u8,
+    ptp4l: Option<Ptp4lClient>,
+    phc_device: Option<PathBuf>,
+}
+
+impl LocalClockPoller {
+    fn poll(&mut self) -> LocalClockState {
+        let mut state = LocalClockState::default();
+
+        if let Some(socket) = &self.ptp4l_socket {
+            let result = match &mut self.ptp4l {
+                Some(client) => client.time_status(),
+                None => Ptp4lClient::connect(socket, self.ptp4l_domain).and_then(|mut client| {
+                    let status = client.time_status();
+                    self.ptp4l = Some(client);
+                    status
+                }),
+            };
+            // Reconnect next time, e.g. after ptp4l was restarted
+            if result.is_err() {
+                self.ptp4l = None;
+            }
+            state.ptp4l = Some(result.map_err(|e| format!("{:#}", e)));
+        }
+
+        if let Some(device) = &self.phc_device {
+            state.phc = Some((
+                device.clone(),
+                read_phc(device).map_err(|e| format!("{:#}", e)),
+            ));
+        }
+        state
+    }
+}
+
+#[test]
+fn test_time_status_response() {
+    let mut response = time_status_request(0, 7);
+    response[46] = ACTION_RESPONSE;
+    response[50..52].copy_from_slice(&52u16.to_be_bytes());
+    let mut status = [0u8; 50];
+    status[0..8].copy_from_slice(&(-42i64).to_be_bytes());
+    status[16..20].copy_from_slice(&2199i32.to_be_bytes());
+    status[38..42].copy_from_slice(&1i32.to_be_bytes());
+    status[42..50].copy_from_slice(&[0x00, 0x1b, 0x19, 0xff, 0xfe, 0x12, 0x34, 0x56]);
+    response.extend_from_slice(&status);
+
+    let parsed = parse_time_status_response(&response).unwrap();
+    assert_eq!(parsed.master_offset_ns, -42);
+    assert!(parsed.gm_present);
+    assert_eq!(parsed.gm_identity.to_string(), "00:1b:19:ff:fe:12:34:56");
+    assert!((parsed.rate_offset_ppb() - 1.0).abs() < 0.01);
+
+    // Errors from ptp4l are reported, not parsed as a status
+    response[48..50].copy_from_slice(&TLV_MANAGEMENT_ERROR_STATUS.to_be_bytes());
+    assert!(parse_time_status_response(&response).is_err());
+}
@@ -0,0 +1,193 @@
+//! Structured debug logging
+//!
+//! Parser rejects, capture errors, BMCA decisions and host state transitions
+//! are logged with `tracing`. The level is set per module with --log-level,
+//! using the filter syntax of RUST_LOG (e.g. "info,ptp_trace::ptp=debug").
+//! Events go to a file rotated daily when --log-file is given, and always to
+//! a bounded tail shown in the log pane, since the TUI owns the terminal.
+
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write as _},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use anyhow::{Context as _, Result, anyhow};
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{
+    EnvFilter,
+    layer::{Context, Layer, SubscriberExt},
+    util::SubscriberInitExt,
+};
+
+/// Level filter without --log-level
+pub const DEFAULT_LOG_LEVEL: &str = "warn";
+/// Level filter with --debug and without --log-level
+pub const DEBUG_LOG_LEVEL: &str = "debug";
+
+/// Lines kept for the log pane
+const MAX_TAIL_LINES: usize = 500;
+
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub timestamp: SystemTime,
+    pub level: Level,
+    /// Module that logged the event
+    pub target: String,
+    /// Message followed by the other fields as key=value
+    pub message: String,
+}
+
+/// Most recent log lines, shared between the subscriber and the UI
+#[derive(Debug, Clone, Default)]
+pub struct LogTail {
+    lines: Arc<Mutex<VecDeque<LogLine>>>,
+}
+
+impl LogTail {
+    fn push(&self, line: LogLine) {
+        let Ok(mut lines) = self.lines.lock() else {
+            return;
+        };
+        lines.push_back(line);
+        if lines.len() > MAX_TAIL_LINES {
+            lines.pop_front();
+        }
+    }
+
+    /// The last `count` lines, oldest first
+    pub fn last(&self, count: usize) -> Vec<LogLine> {
+        let Ok(lines) = self.lines.lock() else {
+            return Vec::new();
+        };
+        lines
+            .iter()
+            .skip(lines.len().saturating_sub(count))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Collects the fields of an event into a single line
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for LineVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+struct TailLayer(LogTail);
+
+impl<S: Subscriber> Layer<S> for TailLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = LineVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        self.0.push(LogLine {
+            timestamp: SystemTime::now(),
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: visitor.message + &visitor.fields,
+        });
+    }
+}
+
+/// Install the global subscriber. The guard flushes the log file when
+/// dropped and has to live until the end of main.
+pub fn init(filter: &str, log_file: Option<&Path>) -> Result<(LogTail, Option<WorkerGuard>)> {
+    let filter =
+        EnvFilter::try_new(filter).with_context(|| format!("Invalid log level '{}'", filter))?;
+    let tail = LogTail::default();
+
+    let (file_layer, guard) = match log_file {
+        Some(path) => {
+            let file_name = path
+                .file_name()
+                .ok_or_else(|| anyhow!("Log file '{}' has no file name", path.display()))?;
+            let directory = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            let appender = tracing_appender::rolling::daily(directory, file_name);
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = tracing_subscriber::fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false);
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(TailLayer(tail.clone()))
+        .with(file_layer)
+        .try_init()
+        .context("Failed to initialize logging")?;
+
+    Ok((tail, guard))
+}
+
+/// Lines logged by `f` with the given level filter, for tests of what the
+/// user gets to see
+#[cfg(test)]
+pub fn capture(filter: &str, f: impl FnOnce()) -> Vec<LogLine> {
+    let tail = LogTail::default();
+    let subscriber = tracing_subscriber::registry()
+        .with(EnvFilter::new(filter))
+        .with(TailLayer(tail.clone()));
+    tracing::subscriber::with_default(subscriber, f);
+    tail.last(MAX_TAIL_LINES)
+}
+
+#[test]
+fn test_log_tail() {
+    let tail = LogTail::default();
+    let subscriber = tracing_subscriber::registry()
+        .with(EnvFilter::new("info"))
+        .with(TailLayer(tail.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::debug!("filtered out");
+        tracing::info!(interface = "eth0", "BMCA winner changed");
+        for i in 0..MAX_TAIL_LINES {
+            tracing::warn!(sequence_id = i, "rejected");
+        }
+    });
+
+    let lines = tail.last(MAX_TAIL_LINES + 10);
+    assert_eq!(lines.len(), MAX_TAIL_LINES);
+    // The oldest line was dropped to stay within the bound
+    assert_eq!(lines[0].message, "rejected sequence_id=0");
+    assert_eq!(lines[0].level, Level::WARN);
+
+    let last = tail.last(1);
+    assert_eq!(
+        last[0].message,
+        format!("rejected sequence_id={}", MAX_TAIL_LINES - 1)
+    );
+    assert_eq!(last[0].target, module_path!());
+}
@@ -0,0 +1,102 @@
+//! Keyboard macros
+//!
+//! Key sequences are recorded into a register with m<register> ... m and
+//! replayed with @<register>, like vim's q registers. They are stored in the
+//! config file in a vim-like notation: plain characters stand for themselves,
+//! other keys are written in angle brackets, e.g. "Fsd<Tab><End>" or "<C-l>".
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+pub type MacroKey = (KeyCode, KeyModifiers);
+
+/// Keys with a name, in the notation used in the config file
+const NAMED_KEYS: [(&str, KeyCode); 14] = [
+    ("Tab", KeyCode::Tab),
+    ("Enter", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("Space", KeyCode::Char(' ')),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("Backspace", KeyCode::Backspace),
+    ("lt", KeyCode::Char('<')),
+];
+
+/// Write keys in the config file notation. Keys without a notation are skipped.
+pub fn format_keys(keys: &[MacroKey]) -> String {
+    let mut text = String::new();
+    for &(code, modifiers) in keys {
+        let control = modifiers.contains(KeyModifiers::CONTROL);
+        let name = NAMED_KEYS
+            .iter()
+            .find(|(_, named)| *named == code)
+            .map(|(name, _)| name.to_string());
+        match (code, name) {
+            (KeyCode::Char(c), _) if control => text.push_str(&format!("<C-{}>", c)),
+            (KeyCode::Char(c), None) => text.push(c),
+            (_, Some(name)) => text.push_str(&format!("<{}>", name)),
+            (KeyCode::F(n), None) => text.push_str(&format!("<F{}>", n)),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Read keys in the config file notation
+pub fn parse_keys(text: &str) -> Result<Vec<MacroKey>, String> {
+    let mut keys = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c != '<' {
+            keys.push((KeyCode::Char(c), KeyModifiers::NONE));
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+
+        let end = rest
+            .find('>')
+            .ok_or_else(|| format!("Unterminated key name in '{}'", text))?;
+        let name = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let key = if let Some(c) = name.strip_prefix("C-").and_then(|c| {
+            let mut chars = c.chars();
+            chars.next().filter(|_| chars.next().is_none())
+        }) {
+            (KeyCode::Char(c), KeyModifiers::CONTROL)
+        } else if let Some((_, code)) = NAMED_KEYS.iter().find(|(named, _)| *named == name) {
+            (*code, KeyModifiers::NONE)
+        } else if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse().ok()) {
+            (KeyCode::F(n), KeyModifiers::NONE)
+        } else {
+            return Err(format!("Unknown key <{}> in '{}'", name, text));
+        };
+        keys.push(key);
+    }
+    Ok(keys)
+}
+
+#[test]
+fn test_macro_notation() {
+    let keys = vec![
+        (KeyCode::Char('F'), KeyModifiers::NONE),
+        (KeyCode::Char('s'), KeyModifiers::NONE),
+        (KeyCode::Tab, KeyModifiers::NONE),
+        (KeyCode::Char(' '), KeyModifiers::NONE),
+        (KeyCode::Char('<'), KeyModifiers::NONE),
+        (KeyCode::Char('l'), KeyModifiers::CONTROL),
+        (KeyCode::F(1), KeyModifiers::NONE),
+    ];
+    let text = format_keys(&keys);
+    assert_eq!(text, "Fs<Tab><Space><lt><C-l><F1>");
+    assert_eq!(parse_keys(&text).unwrap(), keys);
+
+    assert!(parse_keys("<Tab").is_err());
+    assert!(parse_keys("<Foo>").is_err());
+    assert_eq!(parse_keys("").unwrap(), []);
+}
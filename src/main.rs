@@ -1,19 +1,88 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use clap::Parser;
 use std::time::Duration;
 
+// Capture and decoding live in the library, the rest of the binary reaches
+// them as crate::source, crate::types...
+#[cfg(unix)]
+use ptp_trace::helper;
+use ptp_trace::{
+    bond, capture_workers, interface_spec, oui_map, remote, session_summary, source, synth, types,
+};
+
+mod adaptive;
+mod addressing;
+mod alert_hooks;
+mod alerts;
+mod announce_continuity;
 mod app;
+mod arrival_paths;
+mod assertions;
+mod baseline;
+mod bmca;
+mod bookmarks;
 mod bounded_vec;
-mod oui_map;
+mod burst;
+mod clock;
+mod clock_description;
+mod clock_role;
+mod config;
+mod conformance;
+mod csv_tail;
+mod dedup;
+mod delay_cadence;
+mod delay_xref;
+mod details_field;
+mod doctor;
+mod drift;
+mod drill;
+mod era;
+mod field_warnings;
+mod filter;
+mod gm_identity;
+mod gm_pair;
+mod golden;
+mod health;
+mod heartbeat;
+mod html_report;
+mod keymap;
+#[cfg(unix)]
+mod local_clock;
+mod logging;
+mod macros;
+mod minimap;
+mod minor_versions;
+mod mute;
+mod org_tlv;
+mod pcap_export;
+mod pcap_ring;
+mod port_matrix;
+mod profiling;
 mod ptp;
-mod source;
+mod redundancy;
+mod residence;
+mod screenshot;
+mod shared_gm;
+mod sim_receiver;
+mod stats_store;
+mod swimlane;
+mod switch_ports;
+mod sync_sampler;
+mod terminal_background;
 mod themes;
-mod types;
+mod time_range;
+mod timescale;
+mod top_talkers;
+mod topology;
+mod transmit;
+mod trend;
 mod ui;
+mod vendors;
 mod version;
+mod what_if;
 
 use app::App;
-use themes::ThemeName;
+use themes::{ColorDepth, ThemeName};
 
 fn theme_help_text() -> String {
     let themes = ThemeName::all_themes()
@@ -24,6 +93,32 @@ fn theme_help_text() -> String {
     format!("Color theme to use (available: {})", themes)
 }
 
+fn parse_timezone(s: &str) -> Result<String, String> {
+    s.parse::<chrono_tz::Tz>()
+        .map(|_| s.to_string())
+        .map_err(|_| {
+            format!(
+                "Unknown timezone '{}', expected an IANA name like Europe/Berlin",
+                s
+            )
+        })
+}
+
+fn parse_bmca_profile(s: &str) -> Result<String, String> {
+    match bmca::BmcaProfile::from_str(s) {
+        Some(_) => Ok(s.to_string()),
+        None => Err(format!(
+            "Invalid BMCA profile '{}'. Available profiles: {}",
+            s,
+            bmca::BmcaProfile::all()
+                .iter()
+                .map(|profile| profile.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
 fn parse_theme(s: &str) -> Result<String, String> {
     if ThemeName::all_themes()
         .iter()
@@ -43,6 +138,43 @@ fn parse_theme(s: &str) -> Result<String, String> {
     }
 }
 
+fn parse_color_depth(s: &str) -> Result<ColorDepth, String> {
+    ColorDepth::from_str(s).ok_or_else(|| {
+        format!(
+            "Invalid color depth '{}'. Available: auto, truecolor, 256, 16",
+            s
+        )
+    })
+}
+
+fn parse_filter(s: &str) -> Result<String, String> {
+    filter::HostFilter::parse(&[s]).map(|_| s.to_string())
+}
+
+fn parse_cpus(s: &str) -> Result<String, String> {
+    capture_workers::parse_cpu_list(s).map(|_| s.to_string())
+}
+
+/// View the UI starts in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum StartView {
+    /// Flat host table
+    Table,
+    /// Hosts grouped under their transmitters
+    Tree,
+    /// Host table with the details view focused
+    Details,
+    /// Host table with the packet history focused
+    Packets,
+}
+
+/// Packets not captured from the network, a pcap file or agents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum SourceKind {
+    /// Generated PTP traffic of a synthetic network, to load test the tracker and UI
+    Synth,
+}
+
 #[derive(Parser)]
 #[command(name = "ptp-trace")]
 #[command(about = "A terminal UI application for tracing PTP hosts in a network")]
@@ -51,75 +183,666 @@ pub struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
 
-    /// Network interface(s) to monitor. Can be specified multiple times. If not specified, monitors all interfaces.
+    /// Network interface(s) to monitor: a name, a glob pattern ("en*", "eth[0-3]") or "addr=NETWORK" for interfaces with an address in it ("addr=10.20.0.0/16"). Can be specified multiple times, matching interfaces are picked up as they appear. If not specified, monitors all interfaces.
     #[arg(short, long, conflicts_with = "pcap_file")]
     interface: Vec<String>,
 
+    /// Capture on the members of bond and bridge interfaces instead of the master, tagging packets with the member they arrived on (e.g. bond0/eth1) and alerting on bond failovers
+    #[arg(long, conflicts_with_all = ["pcap_file", "connect", "source", "active"])]
+    members: bool,
+
     /// Read packets from a pcap file instead of network interfaces. In pcap mode, timestamps are shown relative to the last packet in the file
     #[arg(short = 'f', long, value_name = "FILE", conflicts_with = "interface")]
     pcap_file: Option<String>,
 
-    /// Update interval in milliseconds
-    #[arg(short, long, default_value = "1000")]
-    update_interval: u64,
+    /// View the traffic of a remote agent (see the agent subcommand) instead of capturing locally, as HOST[:PORT]. Can be specified multiple times to merge several sites
+    #[arg(long, value_name = "ADDRESS", conflicts_with_all = ["interface", "pcap_file", "active"])]
+    connect: Vec<String>,
+
+    /// Generate packets instead of capturing them
+    #[arg(long, value_enum, conflicts_with_all = ["interface", "pcap_file", "connect", "active"])]
+    source: Option<SourceKind>,
+
+    /// Take the packets from a capture helper (see the capture-helper subcommand) listening on this unix socket, so that the TUI runs without privileges
+    #[cfg(unix)]
+    #[arg(long, value_name = "SOCKET", conflicts_with_all = ["interface", "pcap_file", "connect", "source", "active", "members"])]
+    helper: Option<std::path::PathBuf>,
+
+    /// Packets per second generated by --source synth
+    #[arg(long, value_name = "PPS", default_value = "50000", requires = "source")]
+    rate: u64,
+
+    /// Number of hosts of the network generated by --source synth, two of them grandmasters
+    #[arg(long, value_name = "COUNT", default_value = "100", value_parser = clap::value_parser!(u16).range(3..65535), requires = "source")]
+    synth_hosts: u16,
 
-    /// Enable debug logging
+    /// Connect to the agents with TLS, trusting the certificates signed by the CA in this PEM file
+    #[arg(long, value_name = "FILE", requires = "connect")]
+    tls_ca: Option<std::path::PathBuf>,
+
+    /// Authenticate to the agents with the token in the first line of this file, as given to them with agent --token-file
+    #[arg(long, value_name = "FILE", requires = "connect")]
+    token_file: Option<std::path::PathBuf>,
+
+    /// Update interval in milliseconds [default: 1000]
+    #[arg(short, long)]
+    update_interval: Option<u64>,
+
+    /// Let the update interval follow the packet rate, no shorter than this many milliseconds [default: 100 with --max-update-interval]
+    #[arg(long, value_name = "MS")]
+    min_update_interval: Option<u64>,
+
+    /// Let the update interval follow the packet rate, no longer than this many milliseconds [default: 5000 with --min-update-interval]
+    #[arg(long, value_name = "MS")]
+    max_update_interval: Option<u64>,
+
+    /// Log at debug level and show the log pane
     #[arg(short, long)]
     debug: bool,
 
-    #[arg(short, long, default_value = "default", value_parser = parse_theme, help = theme_help_text())]
-    theme: String,
+    /// Per-module log levels, e.g. "info,ptp_trace::ptp=debug" [default: warn, debug with --debug]
+    #[arg(long, global = true, value_name = "FILTER")]
+    log_level: Option<String>,
+
+    /// Write the log to this file, rotated daily with the date appended to its name
+    #[arg(long, global = true, value_name = "FILE")]
+    log_file: Option<std::path::PathBuf>,
+
+    #[arg(short, long, value_parser = parse_theme, help = theme_help_text())]
+    theme: Option<String>,
+
+    /// Colors the terminal can show, themes are degraded to fit: auto (from COLORTERM and TERM), truecolor, 256 or 16 (colors in the config file)
+    #[arg(long, value_name = "DEPTH", value_parser = parse_color_depth)]
+    colors: Option<ColorDepth>,
+
+    /// Also show origin timestamps as wall clock time in this IANA timezone, e.g. Europe/Berlin
+    #[arg(long, value_name = "TZ", value_parser = parse_timezone)]
+    timezone: Option<String>,
+
+    /// Alert when multicast PTP crossed more than this many routers, judged by its TTL
+    #[arg(long, value_name = "HOPS")]
+    max_multicast_hops: Option<u8>,
+
+    /// Warn when VLAN tagged event messages (Sync, Delay_Req, Pdelay) carry another 802.1p priority than this
+    #[arg(long, value_name = "PCP", value_parser = clap::value_parser!(u8).range(0..=7))]
+    event_pcp: Option<u8>,
+
+    /// Alert when the Sync arrival skew between two capture interfaces (redundant networks) moves this many microseconds from where it started
+    #[arg(long, value_name = "MICROSECONDS", default_value = "100")]
+    path_skew_threshold: u64,
+
+    /// Alert when the phase between a grandmaster and its backup (backup_grandmasters in the config) moves this many microseconds from where it started
+    #[arg(long, value_name = "MICROSECONDS", default_value = "50")]
+    gm_pair_threshold: u64,
+
+    /// BMCA rules used to predict the PTT [default: auto (gPTP rules for gPTP, IEEE 1588 otherwise)]
+    #[arg(long, value_name = "PROFILE", value_parser = parse_bmca_profile)]
+    bmca_profile: Option<String>,
+
+    /// Run a simulated standards compliant receiver on one PTP instance (DOMAIN or SDOID:DOMAIN) and show what it would select and whether it would be in holdover
+    #[arg(long, value_name = "INSTANCE", value_parser = sim_receiver::parse_instance)]
+    simulate_receiver: Option<(u16, u8)>,
+
+    /// Config file with default settings, written by the settings view [default: ~/.config/ptp-trace/config.toml]
+    #[arg(long, value_name = "FILE")]
+    config: Option<std::path::PathBuf>,
+
+    /// Keep per-host counters and first-seen times in this file, so they survive restarts
+    #[arg(long, value_name = "FILE")]
+    state_file: Option<std::path::PathBuf>,
+
+    /// Append a row per host with its key metrics to this CSV file every update interval
+    #[arg(long, value_name = "FILE")]
+    tail_csv: Option<std::path::PathBuf>,
+
+    /// Start a new --tail-csv file when the current one reaches a size (e.g. 100MB) or age (e.g. 1h, 1d), the old one is renamed with a timestamp
+    #[arg(long, value_name = "SIZE|AGE", value_parser = csv_tail::Rotation::parse, requires = "tail_csv")]
+    tail_csv_rotate: Option<csv_tail::Rotation>,
+
+    /// With --state-file, ignore the stored counters and start from zero
+    #[arg(long, requires = "state_file")]
+    fresh: bool,
+
+    /// Show the servo state of the local ptp4l, asked over its management socket [default: /var/run/ptp4l]
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = local_clock::DEFAULT_PTP4L_SOCKET)]
+    ptp4l_socket: Option<std::path::PathBuf>,
+
+    /// Domain number of the local ptp4l
+    #[cfg(unix)]
+    #[arg(
+        long,
+        value_name = "DOMAIN",
+        default_value = "0",
+        requires = "ptp4l_socket"
+    )]
+    ptp4l_domain: u8,
+
+    /// Show frequency adjustment and system clock offset of this PTP hardware clock, e.g. /dev/ptp0
+    #[cfg(unix)]
+    #[arg(long, value_name = "DEVICE")]
+    phc: Option<std::path::PathBuf>,
 
     /// Disable mouse support (mouse support is enabled by default)
     #[arg(long)]
     no_mouse: bool,
+
+    /// Compact host table: no borders or padding, abbreviated clock identities and state names (row_density in the config file)
+    #[arg(long)]
+    compact: bool,
+
+    /// Update the display as soon as packets arrive instead of every update interval, debounced to 50 ms (instant_redraw in the config file)
+    #[arg(long)]
+    instant_redraw: bool,
+
+    /// Show how long each host has been observed in this session as a host table column (observed_column in the config file)
+    #[arg(long)]
+    observed_column: bool,
+
+    /// View to start in
+    #[arg(long, value_enum, default_value = "table")]
+    view: StartView,
+
+    /// Select the host with this clock identity as soon as it is seen, e.g. 00:1b:19:ff:fe:12:34:56
+    #[arg(long, value_name = "CLOCKID")]
+    select_host: Option<types::ClockIdentity>,
+
+    /// Only list hosts matching all key=value terms, e.g. "domain=127,state=transmitter". Keys: domain, sdo, vlan, state, vendor, interface, ip, clock, tag. Can be specified multiple times, 'F' toggles the filter
+    #[arg(long, value_name = "FILTER", value_parser = parse_filter)]
+    filter: Vec<String>,
+
+    /// Leave hosts out of the table, statistics and alerts, still counting them: "clock=<id>", "vendor=TEXT" or "ip=<subnet>", e.g. "ip=10.1.2.0/24". Can be specified multiple times, adds to `mute` in the config file, 'M' mutes the selected host
+    #[arg(long, value_name = "RULE", value_parser = mute::parse_mute_rule)]
+    mute: Vec<String>,
+
+    /// Start with the packet history expanded
+    #[arg(long)]
+    expand_packets: bool,
+
+    /// Render one frame without a terminal to this file and exit, e.g. of a --pcap-file: plain text, or with ANSI colors if it ends in .ans or .ansi
+    #[arg(long, value_name = "FILE")]
+    screenshot: Option<std::path::PathBuf>,
+
+    /// Terminal size of --screenshot, COLUMNSxROWS [default: 160x48]
+    #[arg(long, value_name = "SIZE", value_parser = screenshot::parse_size, requires = "screenshot")]
+    screenshot_size: Option<(u16, u16)>,
+
+    /// Write a self-contained HTML report (topology, hosts, alerts, per-host charts and packets) to this file and exit, e.g. of a --pcap-file
+    #[arg(long, value_name = "FILE")]
+    html_report: Option<std::path::PathBuf>,
+
+    /// Continuously compare the network with its design in this YAML file (expected grandmasters, priorities and receiver counts per domain), shown with 'C'
+    #[arg(long, value_name = "FILE")]
+    golden: Option<std::path::PathBuf>,
+
+    /// Compare the discovered hosts with a session file written by --save-session: new and changed hosts are marked in the host table, 'v' lists them with the missing ones
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<std::path::PathBuf>,
+
+    /// Write the discovered hosts to this JSON file on exit, for a later --baseline
+    #[arg(long, value_name = "FILE")]
+    save_session: Option<std::path::PathBuf>,
+
+    /// Show the switch port of each host from a MAC address table export (CAM table, or mac,switch,port CSV), repeat for several switches
+    #[arg(long, value_name = "FILE")]
+    switch_ports: Vec<std::path::PathBuf>,
+
+    /// Save the traffic preceding each alert to a pcap file in this directory
+    #[arg(long, value_name = "DIR")]
+    alert_capture_dir: Option<std::path::PathBuf>,
+
+    /// Write all captured PTP traffic to a rotating ring of pcap files in this directory
+    #[arg(long, value_name = "DIR", conflicts_with = "pcap_file")]
+    write_ring: Option<std::path::PathBuf>,
+
+    /// Size at which the ring starts a new pcap file, e.g. 100M
+    #[arg(long, value_name = "SIZE", default_value = "100M", value_parser = pcap_ring::parse_size, requires = "write_ring")]
+    ring_size: u64,
+
+    /// Number of pcap files kept in the ring, the oldest is deleted
+    #[arg(long, value_name = "COUNT", default_value = "10", value_parser = clap::value_parser!(u32).range(1..), requires = "write_ring")]
+    ring_files: u32,
+
+    /// Directory for pcap files exported with 'P' [default: current directory]
+    #[arg(long, value_name = "DIR")]
+    export_dir: Option<std::path::PathBuf>,
+
+    /// Number of seconds of traffic to include in alert captures
+    #[arg(long, value_name = "SECONDS", default_value = "30")]
+    alert_capture_seconds: u64,
+
+    /// Allow active features to transmit frames (ptp-trace is passive by default)
+    #[arg(long, conflicts_with = "pcap_file")]
+    active: bool,
+
+    /// Also inspect these UDP ports for PTP besides 319 and 320, e.g. 1319,1320. Hosts using them are tagged as non-standard transport
+    #[arg(long, global = true, value_name = "PORTS", value_delimiter = ',')]
+    ports: Vec<u16>,
+
+    /// Capture each interface with this many worker threads in a PACKET_FANOUT group (Linux), for busy links where a single capture thread drops packets. Drops per worker are shown in the interfaces view ('I')
+    #[arg(long, global = true, value_name = "COUNT", default_value = "1", value_parser = clap::value_parser!(u16).range(1..=64))]
+    capture_workers: u16,
+
+    /// How the kernel spreads the frames of an interface over its capture workers: hash keeps each flow on one worker, cpu follows the CPU that received the frame, qm the receive queue of a multi-queue NIC
+    #[arg(long, global = true, value_enum, default_value = "hash")]
+    fanout: capture_workers::FanoutMode,
+
+    /// Pin the capture workers to these CPUs in turn, e.g. 2,3 or 4-7; best those the NIC queues interrupt (Linux)
+    #[arg(long, global = true, value_name = "CPUS", value_parser = parse_cpus)]
+    capture_cpus: Option<String>,
+
+    /// Minimum footprint for long runs on small devices: keep only host states and counters, without packet history or per-packet analysis
+    #[arg(long, conflicts_with = "alert_capture_dir")]
+    lite: bool,
+
+    /// Store only every Nth Sync and Follow_Up of a stream in the packet history, plus those after a sequence gap or with a large correction; counters still count all
+    #[arg(long, value_name = "N", default_value = "1", value_parser = clap::value_parser!(u32).range(1..))]
+    sync_sample: u32,
+
+    /// Disable suppression of duplicate packets (e.g. ingress and egress copies from a SPAN port)
+    #[arg(long)]
+    no_dedup: bool,
+
+    /// Maximum number of tracked hosts, the least recently seen hosts are dropped beyond this (0 = unlimited)
+    #[arg(long, value_name = "COUNT", default_value = "10000")]
+    max_hosts: usize,
+
+    /// With --active, record the frames that would be transmitted in the packet history and log them, decoded and in hex, instead of sending them
+    #[arg(long, requires = "active")]
+    dry_run: bool,
+
+    /// Guarantee that nothing is sent over the network: refuses --active, --connect and the agent subcommand, joins no multicast group, drops all alert hooks, skips the sending checks of doctor and shows OFFLINE/PASSIVE in the header
+    #[arg(long, conflicts_with_all = ["active", "connect"])]
+    offline: bool,
+
+    /// Run the alert hooks of the config file while reading a pcap file (-f) too, by default they only run on live traffic
+    #[arg(long, requires = "pcap_file")]
+    pcap_hooks: bool,
 }
 
 #[derive(Parser)]
 pub enum Commands {
     /// Show detailed version information
     VersionInfo,
+    /// Capture on the local interfaces and stream the PTP traffic to viewers started with --connect, without a UI
+    Agent {
+        /// Network interface(s) to capture on, by name, glob pattern or "addr=NETWORK" as for the viewer. Can be specified multiple times. If not specified, captures on all interfaces.
+        #[arg(short, long)]
+        interface: Vec<String>,
+
+        /// Address to accept viewers on, e.g. 0.0.0.0:7319 for all interfaces, which needs --token-file
+        #[arg(long, value_name = "ADDRESS", default_value = "127.0.0.1:7319")]
+        listen: std::net::SocketAddr,
+
+        /// Only stream to viewers holding the token in the first line of this file, required to listen on an address other than loopback. Use TLS to keep it from being sniffed
+        #[arg(long, value_name = "FILE")]
+        token_file: Option<std::path::PathBuf>,
+
+        /// Name of this site, shown in the viewer's Site column
+        #[arg(long, value_name = "NAME")]
+        site: String,
+
+        /// Serve viewers over TLS with this PEM certificate chain
+        #[arg(long, value_name = "FILE", requires = "tls_key")]
+        tls_cert: Option<std::path::PathBuf>,
+
+        /// Private key of --tls-cert in PEM format
+        #[arg(long, value_name = "FILE", requires = "tls_cert")]
+        tls_key: Option<std::path::PathBuf>,
+    },
+    /// Capture on the local interfaces with the privileges this needs and pass the packets to an unprivileged TUI started with --helper, without a UI
+    #[cfg(unix)]
+    CaptureHelper {
+        /// Network interface(s) to capture on, by name, glob pattern or "addr=NETWORK" as for the viewer. Can be specified multiple times. If not specified, captures on all interfaces.
+        #[arg(short, long)]
+        interface: Vec<String>,
+
+        /// Unix socket to accept the TUI on
+        #[arg(long, value_name = "PATH", default_value = "/run/ptp-trace.sock")]
+        socket: std::path::PathBuf,
+
+        /// Hand the socket to this user, and optionally group, so that it can connect
+        #[arg(long, value_name = "UID[:GID]", value_parser = helper::parse_owner)]
+        owner: Option<(u32, Option<u32>)>,
+
+        /// Permissions of the socket, in octal
+        #[arg(long, value_name = "MODE", default_value = "600", value_parser = helper::parse_mode)]
+        mode: u32,
+    },
+    /// Check a pcap file against the rules of a YAML file, e.g. "exactly one grandmaster in domain 0", and exit with an error if any fails
+    Assert {
+        /// Pcap or pcapng file to check
+        #[arg(short = 'f', long, value_name = "FILE")]
+        file: String,
+
+        /// YAML file with the rules, see the README for the available ones
+        #[arg(long, value_name = "FILE")]
+        rules: std::path::PathBuf,
+    },
+    /// Check that this host is able to capture PTP traffic and print a pass/fail checklist
+    Doctor {
+        /// Network interface(s) to check, by name, glob pattern or "addr=NETWORK" as for the viewer. Can be specified multiple times. If not specified, checks all interfaces.
+        #[arg(short, long)]
+        interface: Vec<String>,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    // Handle subcommands
-    if let Some(command) = cli.command {
-        match command {
-            Commands::VersionInfo => {
-                version::print_header_info();
-                return Ok(());
-            }
-        }
+    if let Some(Commands::VersionInfo) = cli.command {
+        version::print_header_info();
+        return Ok(());
+    }
+
+    let log_level = cli.log_level.as_deref().unwrap_or(if cli.debug {
+        logging::DEBUG_LOG_LEVEL
+    } else {
+        logging::DEFAULT_LOG_LEVEL
+    });
+    // The guard flushes the log file on exit
+    let (log_tail, _log_guard) = logging::init(log_level, cli.log_file.as_deref())?;
+    source::set_extra_ptp_ports(&cli.ports);
+    let capture_options = capture_workers::CaptureOptions {
+        workers: cli.capture_workers as usize,
+        fanout: cli.fanout,
+        cpus: match &cli.capture_cpus {
+            Some(cpus) => capture_workers::parse_cpu_list(cpus).map_err(anyhow::Error::msg)?,
+            None => Vec::new(),
+        },
+        offline: cli.offline,
+    };
+
+    if let Some(Commands::Doctor { interface }) = &cli.command {
+        return doctor::run(interface, cli.offline);
+    }
+    if let Some(Commands::Assert { file, rules }) = &cli.command {
+        return assertions::run(file, rules);
+    }
+
+    if cli.offline && matches!(cli.command, Some(Commands::Agent { .. })) {
+        bail!(
+            "The agent streams traffic to viewers over the network, not available with --offline"
+        );
+    }
+    if let Some(Commands::Agent {
+        interface,
+        listen,
+        site,
+        tls_cert,
+        tls_key,
+        token_file,
+    }) = cli.command
+    {
+        let tls = match (tls_cert, tls_key) {
+            (Some(cert), Some(key)) => Some(remote::tls_acceptor(&cert, &key)?),
+            _ => None,
+        };
+        let token = token_file.as_deref().map(remote::read_token).transpose()?;
+        remote::check_listen_access(listen, token.is_some())?;
+        let receiver =
+            source::create_raw_socket_receiver(&interface, false, &capture_options).await?;
+        return remote::run_agent(receiver, listen, site, tls, token).await;
+    }
+
+    #[cfg(unix)]
+    if let Some(Commands::CaptureHelper {
+        interface,
+        socket,
+        owner,
+        mode,
+    }) = cli.command
+    {
+        let receiver =
+            source::create_raw_socket_receiver(&interface, false, &capture_options).await?;
+        return helper::run(receiver, socket, owner, mode).await;
+    }
+
+    // Load settings from the config file, command line flags take precedence
+    let config_path = cli.config.clone().or_else(config::Config::default_path);
+    let mut config = match &config_path {
+        Some(path) => config::Config::load(path)?,
+        None => config::Config::default(),
+    };
+    let config_overrides = config::ConfigOverrides {
+        update_interval_ms: cli.update_interval,
+        min_update_interval_ms: cli.min_update_interval,
+        max_update_interval_ms: cli.max_update_interval,
+        theme: cli.theme.clone(),
+        colors: cli.colors,
+        timezone: cli.timezone.clone(),
+        bmca_profile: cli.bmca_profile.clone(),
+        max_multicast_hops: cli.max_multicast_hops,
+        event_pcp: cli.event_pcp,
+        compact: cli.compact,
+        instant_redraw: cli.instant_redraw,
+        observed_column: cli.observed_column,
+    };
+    config_overrides.apply(&mut config);
+
+    // Ask the terminal for its background before the UI takes over its input,
+    // the auto theme follows it. Screenshots look the same on any terminal.
+    if cli.screenshot.is_none() && cli.html_report.is_none() {
+        terminal_background::detect();
     }
 
     // Parse theme
-    let theme_name = ThemeName::from_str(&cli.theme).unwrap_or_else(|| {
-        eprintln!("Unknown theme '{}', using default", cli.theme);
+    let theme_name = ThemeName::from_str(&config.theme).unwrap_or_else(|| {
+        eprintln!("Unknown theme '{}', using default", config.theme);
         ThemeName::Default
     });
 
-    // Create packet source (either from network interfaces or pcap file)
-    let raw_socket_receiver = if let Some(pcap_path) = &cli.pcap_file {
+    if let Some(timezone) = &config.timezone
+        && config.timezone().is_none()
+    {
+        eprintln!("Unknown timezone '{}', showing UTC only", timezone);
+    }
+    for column in &config.columns {
+        if column.message_type().is_none() {
+            eprintln!(
+                "Unknown message type '{}' in column '{}', it will stay empty",
+                column.message, column.title
+            );
+        }
+    }
+    for (register, keys) in &config.macros {
+        if let Err(e) = macros::parse_keys(keys) {
+            eprintln!("Macro @{} can't be replayed: {}", register, e);
+        }
+    }
+
+    #[cfg(unix)]
+    let helper_receiver = cli.helper.as_deref().map(source::create_helper_receiver);
+    #[cfg(not(unix))]
+    let helper_receiver = None;
+
+    // Create packet source (network interfaces, pcap file, remote agents, capture helper or generated)
+    let packet_source = if let Some(receiver) = helper_receiver {
+        receiver
+    } else if cli.source == Some(SourceKind::Synth) {
+        source::create_synthetic_receiver(cli.rate, cli.synth_hosts)?
+    } else if let Some(pcap_path) = &cli.pcap_file {
         source::create_pcap_receiver(pcap_path).await?
+    } else if !cli.connect.is_empty() {
+        source::create_remote_receiver(
+            &cli.connect,
+            cli.tls_ca.as_deref(),
+            cli.token_file
+                .as_deref()
+                .map(remote::read_token)
+                .transpose()?,
+        )?
     } else {
-        source::create_raw_socket_receiver(&cli.interface).await?
+        source::create_raw_socket_receiver(&cli.interface, cli.members, &capture_options).await?
+    };
+
+    // Check transmit capabilities up front so active mode fails before the UI starts
+    let transmit_mode = transmit::TransmitMode::from_flags(cli.active, cli.dry_run);
+    let transmitter = if transmit_mode != transmit::TransmitMode::Passive {
+        let interface_names = packet_source
+            .interfaces()
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        Some(transmit::FrameTransmitter::new(
+            transmit_mode,
+            &interface_names,
+        )?)
+    } else {
+        None
     };
 
     // Initialize the application
-    let update_interval = Duration::from_millis(cli.update_interval);
+    let update_interval = Duration::from_millis(config.update_interval_ms);
     let mut app = App::new(
         update_interval,
         cli.debug,
         theme_name,
-        raw_socket_receiver,
+        packet_source,
         !cli.no_mouse,
     )?;
 
-    // Run the TUI application
-    app.run().await?;
+    app.offline = cli.offline;
+    app.pcap_hooks = cli.pcap_hooks;
+    app.pcap_file = cli.pcap_file.as_ref().map(std::path::PathBuf::from);
+    app.set_config(config, config_path);
+    app.config_overrides = config_overrides;
+    app.log_tail = log_tail;
+    app.ptp_tracker.set_max_hosts(cli.max_hosts);
+    app.ptp_tracker.set_lite(cli.lite);
+    if cli.members {
+        app.ptp_tracker.watch_bonds();
+    }
+    app.ptp_tracker.set_sync_sample_rate(cli.sync_sample);
+    app.ptp_tracker
+        .set_path_skew_threshold(Duration::from_micros(cli.path_skew_threshold));
+    app.ptp_tracker
+        .set_gm_pair_drift_threshold(Duration::from_micros(cli.gm_pair_threshold));
+    if let Some(instance) = cli.simulate_receiver {
+        app.ptp_tracker.set_simulated_receiver(instance);
+    }
+
+    if cli.no_dedup {
+        app.ptp_tracker.set_dedup_enabled(false);
+    }
+
+    if let Some(transmitter) = transmitter {
+        app.ptp_tracker.set_transmitter(transmitter);
+    }
+
+    if let Some(dir) = cli.alert_capture_dir {
+        std::fs::create_dir_all(&dir)?;
+        app.ptp_tracker.set_alert_capture(alerts::AlertCapture::new(
+            dir,
+            Duration::from_secs(cli.alert_capture_seconds),
+        ));
+    }
+
+    if let Some(path) = cli.golden {
+        app.load_golden(path)?;
+    }
+
+    if let Some(path) = cli.baseline {
+        app.load_baseline(path)?;
+    }
+
+    for path in &cli.switch_ports {
+        app.switch_ports.load(path)?;
+    }
+
+    if let Some(dir) = cli.write_ring {
+        app.ptp_tracker.set_pcap_ring(pcap_ring::PcapRing::new(
+            &dir,
+            cli.ring_size,
+            cli.ring_files as usize,
+        )?);
+    }
+
+    if let Some(dir) = cli.export_dir {
+        std::fs::create_dir_all(&dir)?;
+        app.export_dir = dir;
+    }
+
+    if let Some(path) = cli.state_file {
+        let store = if cli.fresh {
+            stats_store::StatsStore::default()
+        } else {
+            stats_store::StatsStore::load(&path)?
+        };
+        app.ptp_tracker.set_stats_store(path, store);
+    }
+
+    if let Some(path) = cli.tail_csv {
+        app.csv_tail = Some(csv_tail::CsvTail::open(&path, cli.tail_csv_rotate)?);
+    }
+
+    // Land in the context given on the command line
+    match cli.view {
+        StartView::Table => {}
+        StartView::Tree => app.tree_view_mode = true,
+        StartView::Details => app.active_view = app::ActiveView::HostDetails,
+        StartView::Packets => app.active_view = app::ActiveView::PacketHistory,
+    }
+    app.set_host_filter(filter::HostFilter::parse(&cli.filter).map_err(anyhow::Error::msg)?);
+    app.set_cli_mute_rules(cli.mute);
+    if let Some(clock_identity) = cli.select_host {
+        app.preselect_host(clock_identity);
+    }
+    app.packet_history_expanded = cli.expand_packets;
+
+    #[cfg(unix)]
+    if cli.ptp4l_socket.is_some() || cli.phc.is_some() {
+        app.local_clock = Some(local_clock::LocalClockMonitor::new(
+            cli.ptp4l_socket,
+            cli.ptp4l_domain,
+            cli.phc,
+        ));
+    }
+
+    if let Some(path) = cli.html_report {
+        app.write_html_report(&path).await?;
+        println!("HTML report written to {}", path.display());
+        if let Some(path) = cli.save_session {
+            app.save_session(&path)?;
+            println!("Session written to {}", path.display());
+        }
+        return Ok(());
+    }
+
+    if let Some(path) = cli.screenshot {
+        app.screenshot(
+            &path,
+            cli.screenshot_size
+                .unwrap_or(screenshot::DEFAULT_SCREENSHOT_SIZE),
+        )
+        .await?;
+        println!("Screenshot written to {}", path.display());
+        if let Some(path) = cli.save_session {
+            app.save_session(&path)?;
+            println!("Session written to {}", path.display());
+        }
+        return Ok(());
+    }
+
+    #[cfg(unix)]
+    app.reload_on_sighup()?;
+
+    // Run the TUI application, saving the counters even if it failed
+    let result = app.run().await;
+    let saved = app.ptp_tracker.save_stats();
+    result?;
+    saved?;
+    if let Some(path) = cli.save_session {
+        app.save_session(&path)?;
+    }
+
+    let mut summary = app.session_summary();
+    if let Some(path) = cli.log_file {
+        // Rotated daily, with the date appended to the name
+        summary.files.push(format!("{}.*", path.display()).into());
+    }
+    println!("{}", summary);
 
     Ok(())
 }
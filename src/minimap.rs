@@ -0,0 +1,201 @@
+//! One-row density map of the packet history, under its table
+//!
+//! Scrolling blindly through thousands of packets gives no sense of where the
+//! interesting parts are. The map squeezes the whole history into one row:
+//! each column covers an equal slice of the time the history spans, its bar
+//! is as high as the number of packets in it and colored by the message type
+//! most of them are. Columns with a bookmarked packet or an anomaly are
+//! marked, the rows on screen are highlighted and a click jumps to a column.
+
+use std::{ops::Range, sync::Arc, time::SystemTime};
+
+use crate::types::{ParsedPacket, PtpMessageType};
+
+/// Bars from few to many packets
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MinimapColumn {
+    pub count: usize,
+    /// Message type most packets of the column are
+    pub message_type: Option<PtpMessageType>,
+    /// Index of the first packet in the column
+    pub first: Option<usize>,
+    pub bookmarked: bool,
+    pub anomaly: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Minimap {
+    pub columns: Vec<MinimapColumn>,
+    /// Column of each packet
+    packet_columns: Vec<usize>,
+}
+
+impl Minimap {
+    /// Map `packets`, oldest first, onto `width` columns
+    pub fn new<B, A>(
+        packets: &[Arc<ParsedPacket>],
+        width: usize,
+        is_bookmarked: B,
+        is_anomaly: A,
+    ) -> Self
+    where
+        B: Fn(&ParsedPacket) -> bool,
+        A: Fn(&ParsedPacket) -> bool,
+    {
+        let timestamps: Vec<SystemTime> =
+            packets.iter().map(|packet| packet.raw.timestamp).collect();
+        let packet_columns = packet_columns(&timestamps, width);
+
+        let mut columns = vec![MinimapColumn::default(); width];
+        let mut type_counts: Vec<Vec<(PtpMessageType, usize)>> = vec![Vec::new(); width];
+        for (index, (packet, &column)) in packets.iter().zip(&packet_columns).enumerate() {
+            let cell = &mut columns[column];
+            cell.count += 1;
+            cell.first.get_or_insert(index);
+            cell.bookmarked |= is_bookmarked(packet);
+            cell.anomaly |= is_anomaly(packet);
+            let message_type = packet.ptp.header().message_type;
+            let counts = &mut type_counts[column];
+            match counts.iter_mut().find(|(kind, _)| *kind == message_type) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((message_type, 1)),
+            }
+        }
+        for (cell, counts) in columns.iter_mut().zip(type_counts) {
+            // The first type to reach the highest count on a tie
+            cell.message_type = counts
+                .iter()
+                .rev()
+                .max_by_key(|(_, count)| *count)
+                .map(|(kind, _)| *kind);
+        }
+
+        Self {
+            columns,
+            packet_columns,
+        }
+    }
+
+    /// Bar of a column, by its packets relative to the fullest column
+    pub fn bar(&self, column: &MinimapColumn) -> char {
+        let max = self
+            .columns
+            .iter()
+            .map(|cell| cell.count)
+            .max()
+            .unwrap_or(0);
+        if column.count == 0 || max == 0 {
+            return ' ';
+        }
+        BARS[((column.count * BARS.len()).div_ceil(max) - 1).min(BARS.len() - 1)]
+    }
+
+    /// Columns the packets `range` are in
+    pub fn columns_of(&self, range: Range<usize>) -> Range<usize> {
+        match (
+            self.packet_columns.get(range.start),
+            range
+                .end
+                .checked_sub(1)
+                .and_then(|last| self.packet_columns.get(last)),
+        ) {
+            (Some(&first), Some(&last)) => first..last + 1,
+            _ => 0..0,
+        }
+    }
+
+    /// Packet a click on `column` jumps to: the first of the column, or of
+    /// the nearest column with packets
+    pub fn packet_at(&self, column: usize) -> Option<usize> {
+        let column = column.min(self.columns.len().checked_sub(1)?);
+        (0..self.columns.len())
+            .flat_map(|distance| [column.checked_sub(distance), Some(column + distance)])
+            .flatten()
+            .find_map(|column| self.columns.get(column).and_then(|cell| cell.first))
+    }
+}
+
+/// Column of each timestamp: equal slices of the time from the first to the
+/// last, by position when they are all the same
+fn packet_columns(timestamps: &[SystemTime], width: usize) -> Vec<usize> {
+    let last_column = width.saturating_sub(1);
+    let (Some(start), Some(end)) = (timestamps.iter().min(), timestamps.iter().max()) else {
+        return Vec::new();
+    };
+    let span = end.duration_since(*start).unwrap_or_default().as_secs_f64();
+    timestamps
+        .iter()
+        .enumerate()
+        .map(|(index, timestamp)| {
+            let position = if span > 0.0 {
+                timestamp
+                    .duration_since(*start)
+                    .unwrap_or_default()
+                    .as_secs_f64()
+                    / span
+            } else {
+                index as f64 / timestamps.len() as f64
+            };
+            ((position * width as f64) as usize).min(last_column)
+        })
+        .collect()
+}
+
+#[test]
+fn test_minimap() {
+    use crate::{source::RawPacket, types::PtpMessage};
+    use std::time::Duration;
+
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let packet = |millis: u64, message_type: u8, sequence_id: u16| {
+        let mut payload = [0u8; 64];
+        payload[0] = message_type;
+        payload[1] = 0x02;
+        let length: u16 = if message_type == 0x0b { 64 } else { 44 };
+        payload[2..4].copy_from_slice(&length.to_be_bytes());
+        payload[30..32].copy_from_slice(&sequence_id.to_be_bytes());
+        let payload = &payload[..length as usize];
+        Arc::new(ParsedPacket {
+            ptp: PtpMessage::try_from(payload).unwrap(),
+            raw: Arc::new(RawPacket::from_ptp_payload(
+                payload,
+                "eth0",
+                start + Duration::from_millis(millis),
+            )),
+        })
+    };
+    // A burst of Syncs at the start, a quiet middle and an Announce at the end
+    let mut packets: Vec<_> = (0..6).map(|i| packet(i * 10, 0x00, i as u16)).collect();
+    packets.push(packet(50, 0x01, 100));
+    packets.push(packet(1000, 0x0b, 200));
+
+    let map = Minimap::new(
+        &packets,
+        10,
+        |packet| packet.ptp.header().sequence_id == 200,
+        |packet| packet.ptp.header().message_type == PtpMessageType::DelayReq,
+    );
+    assert_eq!(map.columns.len(), 10);
+    let first = &map.columns[0];
+    assert_eq!(first.count, 7);
+    assert_eq!(first.message_type, Some(PtpMessageType::Sync));
+    assert!(first.anomaly && !first.bookmarked);
+    assert_eq!(map.bar(first), '█');
+    assert_eq!(map.bar(&map.columns[5]), ' ');
+    assert_eq!(map.bar(&map.columns[9]), '▂');
+    assert!(map.columns[9].bookmarked);
+
+    // The last two packets on screen, and clicks on empty columns
+    assert_eq!(map.columns_of(6..8), 0..10);
+    assert_eq!(map.columns_of(7..8), 9..10);
+    assert_eq!(map.packet_at(0), Some(0));
+    assert_eq!(map.packet_at(3), Some(0));
+    assert_eq!(map.packet_at(7), Some(7));
+    assert_eq!(map.packet_at(20), Some(7));
+    assert_eq!(
+        Minimap::new(&[], 10, |_| false, |_| false).packet_at(3),
+        None
+    );
+}
@@ -0,0 +1,129 @@
+//! PTP minor versions mixed within a domain
+//!
+//! IEEE 1588-2008 devices send minorVersionPTP 0 (PTP 2.0), IEEE 1588-2019
+//! devices 1 (PTP 2.1). They interoperate on the common subset, but what 2.1
+//! added is lost on the 2.0 hosts: the AUTHENTICATION TLV of the security
+//! annex, the minorSdoId and the 2.1 flags are ignored or dropped by them, so
+//! a domain secured or profiled for 2.1 only works for part of its hosts.
+//! Each host's minor version is kept with the host, the hosts of a domain are
+//! grouped by minor version when more than one shows up.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::types::{ClockIdentity, format_ptp_instance};
+
+/// Hosts named per minor version in the statistics, the rest are counted
+const LISTED_HOSTS: usize = 3;
+
+/// Hosts of a PTP instance (sdoId, domain) by the minor version they send,
+/// with more than one minor version among them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MixedMinorVersions {
+    pub instance: (u16, u8),
+    pub hosts: BTreeMap<u8, Vec<ClockIdentity>>,
+}
+
+impl MixedMinorVersions {
+    /// Group hosts given as (instance, clock identity, minor version) by
+    /// instance, keeping the instances with mixed minor versions
+    pub fn find<I>(hosts: I) -> Vec<Self>
+    where
+        I: IntoIterator<Item = ((u16, u8), ClockIdentity, u8)>,
+    {
+        let mut instances: BTreeMap<(u16, u8), BTreeMap<u8, Vec<ClockIdentity>>> = BTreeMap::new();
+        for (instance, clock_identity, minor_version) in hosts {
+            instances
+                .entry(instance)
+                .or_default()
+                .entry(minor_version)
+                .or_default()
+                .push(clock_identity);
+        }
+        instances
+            .into_iter()
+            .filter(|(_, hosts)| hosts.len() > 1)
+            .map(|(instance, mut hosts)| {
+                hosts.values_mut().for_each(|clocks| clocks.sort());
+                Self { instance, hosts }
+            })
+            .collect()
+    }
+
+    /// Hosts of each minor version for the statistics, e.g.
+    /// (1, "Oregano 12:34:56, Meinberg 44:55:66, +2")
+    pub fn host_names(&self) -> Vec<(u8, String)> {
+        self.hosts
+            .iter()
+            .map(|(minor_version, clocks)| {
+                let mut names: Vec<String> = clocks
+                    .iter()
+                    .take(LISTED_HOSTS)
+                    .map(|clock| clock.abbreviated())
+                    .collect();
+                if clocks.len() > LISTED_HOSTS {
+                    names.push(format!("+{}", clocks.len() - LISTED_HOSTS));
+                }
+                (*minor_version, names.join(", "))
+            })
+            .collect()
+    }
+}
+
+impl Display for MixedMinorVersions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Domain {} mixes PTP {}, 2.1 features such as the AUTHENTICATION TLV are not understood by the 2.0 hosts",
+            format_ptp_instance(self.instance.0, self.instance.1),
+            self.hosts
+                .iter()
+                .map(|(minor_version, clocks)| format!(
+                    "2.{} ({} host{})",
+                    minor_version,
+                    clocks.len(),
+                    if clocks.len() == 1 { "" } else { "s" }
+                ))
+                .collect::<Vec<_>>()
+                .join(" and ")
+        )
+    }
+}
+
+#[test]
+fn test_mixed_minor_versions() {
+    let clock = |last: u8| ClockIdentity {
+        clock_id: [0x00, 0x1b, 0x19, 0xff, 0xfe, 0, 0, last],
+    };
+    let hosts = [
+        ((0, 0), clock(1), 0),
+        ((0, 0), clock(2), 1),
+        ((0, 0), clock(3), 0),
+        ((0, 1), clock(4), 1),
+        ((0, 1), clock(5), 1),
+    ];
+
+    // Domain 1 runs 2.1 only
+    let mixed = MixedMinorVersions::find(hosts);
+    assert_eq!(mixed.len(), 1);
+    assert_eq!(mixed[0].instance, (0, 0));
+    assert_eq!(mixed[0].hosts[&0], vec![clock(1), clock(3)]);
+    assert!(
+        mixed[0]
+            .to_string()
+            .starts_with("Domain 0 mixes PTP 2.0 (2 hosts) and 2.1 (1 host)")
+    );
+    let names = mixed[0].host_names();
+    assert_eq!(names.len(), 2);
+    assert_eq!((names[0].0, names[1].0), (0, 1));
+    assert_eq!(names[0].1.matches(", ").count(), 1);
+
+    let many: Vec<_> = (1..=5)
+        .map(|last| ((0, 0), clock(last), 0))
+        .chain([((0, 0), clock(9), 1)])
+        .collect();
+    assert!(
+        MixedMinorVersions::find(many)[0].host_names()[0]
+            .1
+            .ends_with(", +2")
+    );
+}
@@ -0,0 +1,132 @@
+//! Hosts muted as known-benign, e.g. chatty devices nobody can reconfigure
+//!
+//! A muted host is still tracked, but left out of the host table, the
+//! statistics and the alerts, so it does not drown the signal. The hosts and
+//! alerts left out are counted, nothing disappears silently. Rules are given
+//! with --mute or as `mute` in the config file, 'M' mutes the selected host by
+//! its clock identity.
+
+use std::fmt::Display;
+
+use pnet::ipnetwork::IpNetwork;
+
+use crate::{ptp::PtpHost, types::ClockIdentity};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MuteRule {
+    Clock(ClockIdentity),
+    /// Vendor name containing the text, ignoring case
+    Vendor(String),
+    /// Hosts with an IP address in the subnet, a single address without a
+    /// prefix length
+    Subnet(IpNetwork),
+}
+
+impl MuteRule {
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let (key, value) = rule
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid mute rule '{}', expected key=value", rule))?;
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "clock" | "id" => Ok(MuteRule::Clock(value.parse().map_err(|e| {
+                format!("Invalid clock identity in mute rule '{}': {}", rule, e)
+            })?)),
+            "vendor" => Ok(MuteRule::Vendor(value.to_ascii_lowercase())),
+            "ip" | "subnet" => {
+                Ok(MuteRule::Subnet(value.parse().map_err(|e| {
+                    format!("Invalid subnet in mute rule '{}': {}", rule, e)
+                })?))
+            }
+            key => Err(format!(
+                "Unknown mute key '{}', expected clock, vendor or ip",
+                key
+            )),
+        }
+    }
+
+    fn matches(&self, host: &PtpHost) -> bool {
+        match self {
+            MuteRule::Clock(clock_identity) => host.clock_identity == *clock_identity,
+            MuteRule::Vendor(vendor) => host
+                .get_vendor_name()
+                .is_some_and(|name| name.to_ascii_lowercase().contains(vendor)),
+            MuteRule::Subnet(subnet) => host.ip_addresses.keys().any(|ip| subnet.contains(*ip)),
+        }
+    }
+}
+
+impl Display for MuteRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MuteRule::Clock(clock_identity) => write!(f, "clock={}", clock_identity),
+            MuteRule::Vendor(vendor) => write!(f, "vendor={}", vendor),
+            MuteRule::Subnet(subnet) => write!(f, "ip={}", subnet),
+        }
+    }
+}
+
+/// Rules a host is muted by if any of them matches
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MuteList {
+    rules: Vec<MuteRule>,
+}
+
+impl MuteList {
+    pub fn parse<S: AsRef<str>>(rules: &[S]) -> Result<Self, String> {
+        let rules = rules
+            .iter()
+            .map(|rule| rule.as_ref().trim())
+            .filter(|rule| !rule.is_empty())
+            .map(MuteRule::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn is_muted(&self, host: &PtpHost) -> bool {
+        self.rules.iter().any(|rule| rule.matches(host))
+    }
+
+    /// The rule a host is muted by, None if it is not muted
+    pub fn rule_for(&self, host: &PtpHost) -> Option<&MuteRule> {
+        self.rules.iter().find(|rule| rule.matches(host))
+    }
+}
+
+/// Check a --mute rule on the command line
+pub fn parse_mute_rule(rule: &str) -> Result<String, String> {
+    MuteRule::parse(rule).map(|_| rule.to_string())
+}
+
+#[test]
+fn test_mute_list() {
+    let mut host = PtpHost::new(ClockIdentity {
+        clock_id: [0xec, 0x46, 0x70, 0xff, 0xfe, 0x12, 0x34, 0x56],
+    });
+    host.add_ip_address("10.1.2.3".parse().unwrap(), None, "eth0".to_string());
+
+    assert!(!MuteList::default().is_muted(&host));
+    assert!(
+        MuteList::parse(&["clock=ec:46:70:ff:fe:12:34:56"])
+            .unwrap()
+            .is_muted(&host)
+    );
+    assert!(
+        !MuteList::parse(&["ip=10.1.3.0/24"])
+            .unwrap()
+            .is_muted(&host)
+    );
+    let list = MuteList::parse(&["ip=10.1.3.0/24", "ip=10.1.2.0/24"]).unwrap();
+    assert_eq!(list.rule_for(&host).unwrap().to_string(), "ip=10.1.2.0/24");
+    assert!(MuteList::parse(&["ip=10.1.2.3"]).unwrap().is_muted(&host));
+    assert!(
+        MuteList::parse(&["vendor=MEINBERG"])
+            .unwrap()
+            .is_muted(&host)
+    );
+
+    assert!(MuteList::parse(&["clock=nope"]).is_err());
+    assert!(MuteList::parse(&["ip=10.1.2.0/40"]).is_err());
+    assert!(MuteList::parse(&["color=red"]).is_err());
+    assert!(MuteList::parse(&["vendor"]).is_err());
+}
@@ -0,0 +1,310 @@
+//! Organization extension TLVs
+//!
+//! Profiles and vendors append their own data to PTP messages in organization
+//! extension TLVs, identified by an OUI and a sub type. Decoders for known
+//! organizations are kept in a registry; a new one only has to implement
+//! OrgTlvDecoder and be added to OrgTlvRegistry::builtin(). TLVs without a
+//! decoder, or with a layout that is not publicly documented, are shown as
+//! OUI, sub type and hexdump.
+
+use std::sync::OnceLock;
+
+use crate::{
+    oui_map::lookup_vendor_bytes,
+    types::{PtpHeader, tlvs},
+};
+
+/// ORGANIZATION_EXTENSION and its propagate / do not propagate variants
+const ORGANIZATION_EXTENSION_TYPES: [u16; 3] = [0x0003, 0x4000, 0x8000];
+
+pub trait OrgTlvDecoder: Send + Sync {
+    fn organization_id(&self) -> [u8; 3];
+
+    fn name(&self) -> &'static str;
+
+    /// Fields of the data behind organizationSubType, None if the sub type is
+    /// unknown or the data too short
+    fn decode(&self, _sub_type: u32, _data: &[u8]) -> Option<Vec<(String, String)>> {
+        None
+    }
+}
+
+/// Organization extension TLV with whatever could be decoded from it
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrgTlv {
+    pub organization_id: [u8; 3],
+    /// Decoder name, or the OUI owner if no decoder is registered
+    pub organization: Option<&'static str>,
+    pub sub_type: u32,
+    pub fields: Option<Vec<(String, String)>>,
+    pub data: Vec<u8>,
+}
+
+impl OrgTlv {
+    /// e.g. "SMPTE (68:97:5a) sub type 0x000001"
+    pub fn title(&self) -> String {
+        let oui = format!(
+            "{:02x}:{:02x}:{:02x}",
+            self.organization_id[0], self.organization_id[1], self.organization_id[2]
+        );
+        match self.organization {
+            Some(name) => format!("{} ({}) sub type 0x{:06x}", name, oui, self.sub_type),
+            None => format!("OUI {} sub type 0x{:06x}", oui, self.sub_type),
+        }
+    }
+}
+
+pub struct OrgTlvRegistry {
+    decoders: Vec<Box<dyn OrgTlvDecoder>>,
+}
+
+impl OrgTlvRegistry {
+    /// Registry with the decoders shipped with ptp-trace
+    pub fn builtin() -> Self {
+        let mut registry = Self {
+            decoders: Vec::new(),
+        };
+        registry.register(Box::new(Ieee8021AsDecoder));
+        registry.register(Box::new(SmpteDecoder));
+        registry.register(Box::new(NamedOrganization {
+            organization_id: [0xec, 0x46, 0x70],
+            name: "Meinberg",
+        }));
+        registry.register(Box::new(NamedOrganization {
+            organization_id: [0x00, 0x1d, 0xc1],
+            name: "Audinate (Dante)",
+        }));
+        registry.register(Box::new(NamedOrganization {
+            organization_id: [0x08, 0x00, 0x11],
+            name: "Tektronix",
+        }));
+        registry
+    }
+
+    pub fn register(&mut self, decoder: Box<dyn OrgTlvDecoder>) {
+        self.decoders.push(decoder);
+    }
+
+    /// Organization extension TLVs of a message
+    pub fn decode(&self, header: &PtpHeader, payload: &[u8]) -> Vec<OrgTlv> {
+        tlvs(header, payload)
+            .into_iter()
+            .filter(|(tlv_type, value)| {
+                ORGANIZATION_EXTENSION_TYPES.contains(tlv_type) && value.len() >= 6
+            })
+            .map(|(_, value)| {
+                let organization_id = [value[0], value[1], value[2]];
+                let sub_type = u32::from_be_bytes([0, value[3], value[4], value[5]]);
+                let data = &value[6..];
+                let decoder = self
+                    .decoders
+                    .iter()
+                    .find(|decoder| decoder.organization_id() == organization_id);
+
+                OrgTlv {
+                    organization_id,
+                    organization: decoder.map(|decoder| decoder.name()).or_else(|| {
+                        let [a, b, c] = organization_id;
+                        lookup_vendor_bytes([a, b, c, 0, 0, 0])
+                    }),
+                    sub_type,
+                    fields: decoder.and_then(|decoder| decoder.decode(sub_type, data)),
+                    data: data.to_vec(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Registry used for display
+pub fn registry() -> &'static OrgTlvRegistry {
+    static REGISTRY: OnceLock<OrgTlvRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(OrgTlvRegistry::builtin)
+}
+
+/// Organization known by name only, its TLV layout is not publicly documented
+struct NamedOrganization {
+    organization_id: [u8; 3],
+    name: &'static str,
+}
+
+impl OrgTlvDecoder for NamedOrganization {
+    fn organization_id(&self) -> [u8; 3] {
+        self.organization_id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+fn field(label: &str, value: impl ToString) -> (String, String) {
+    (label.to_string(), value.to_string())
+}
+
+fn be_i32(data: &[u8]) -> i32 {
+    i32::from_be_bytes([data[0], data[1], data[2], data[3]])
+}
+
+fn be_u48(data: &[u8]) -> u64 {
+    data[..6]
+        .iter()
+        .fold(0u64, |value, &byte| (value << 8) | byte as u64)
+}
+
+/// IEEE 802.1AS (gPTP) TLVs
+struct Ieee8021AsDecoder;
+
+impl OrgTlvDecoder for Ieee8021AsDecoder {
+    fn organization_id(&self) -> [u8; 3] {
+        [0x00, 0x80, 0xc2]
+    }
+
+    fn name(&self) -> &'static str {
+        "IEEE 802.1AS"
+    }
+
+    fn decode(&self, sub_type: u32, data: &[u8]) -> Option<Vec<(String, String)>> {
+        match sub_type {
+            // Follow_Up information TLV
+            1 if data.len() >= 22 => {
+                let rate_offset = be_i32(&data[0..4]);
+                Some(vec![
+                    field("TLV", "Follow_Up information"),
+                    field(
+                        "Cumulative Rate Offset",
+                        format!("{:+.6} ppm", rate_offset as f64 / 2f64.powi(41) * 1e6),
+                    ),
+                    field(
+                        "GM Time Base Indicator",
+                        u16::from_be_bytes([data[4], data[5]]),
+                    ),
+                    field(
+                        "Last GM Phase Change",
+                        format!("0x{}", hex_string(&data[6..18])),
+                    ),
+                    field(
+                        "Last GM Freq Change",
+                        format!(
+                            "{:+.6} ppm",
+                            be_i32(&data[18..22]) as f64 / 2f64.powi(41) * 1e6
+                        ),
+                    ),
+                ])
+            }
+            // Message interval request TLV
+            2 if data.len() >= 4 => Some(vec![
+                field("TLV", "Message interval request"),
+                field("Link Delay Interval", data[0] as i8),
+                field("Time Sync Interval", data[1] as i8),
+                field("Announce Interval", data[2] as i8),
+                field("Flags", format!("0x{:02x}", data[3])),
+            ]),
+            // gPTP capable TLV
+            4 if data.len() >= 2 => Some(vec![
+                field("TLV", "gPTP capable"),
+                field("gPTP Capable Interval", data[0] as i8),
+                field("Flags", format!("0x{:02x}", data[1])),
+            ]),
+            _ => None,
+        }
+    }
+}
+
+fn hex_string(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// SMPTE ST 2059-2 synchronization metadata
+struct SmpteDecoder;
+
+impl OrgTlvDecoder for SmpteDecoder {
+    fn organization_id(&self) -> [u8; 3] {
+        [0x68, 0x97, 0x5a]
+    }
+
+    fn name(&self) -> &'static str {
+        "SMPTE"
+    }
+
+    fn decode(&self, sub_type: u32, data: &[u8]) -> Option<Vec<(String, String)>> {
+        if sub_type != 1 || data.len() < 42 {
+            return None;
+        }
+
+        let numerator = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        let denominator = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+        let locking_status = match data[8] {
+            0 => "not in use",
+            1 => "free run",
+            2 => "cold locking",
+            3 => "warm locking",
+            4 => "locked",
+            _ => "reserved",
+        };
+        Some(vec![
+            field("TLV", "Synchronization metadata"),
+            field(
+                "Default Frame Rate",
+                format!("{}/{}", numerator, denominator),
+            ),
+            field("Locking Status", locking_status),
+            field(
+                "Time Address Flags",
+                format!(
+                    "drop frame {}, color frame {}",
+                    data[9] & 0x01 != 0,
+                    data[9] & 0x02 != 0
+                ),
+            ),
+            field(
+                "Current Local Offset",
+                format!("{} s", be_i32(&data[10..14])),
+            ),
+            field("Jump Seconds", format!("{} s", be_i32(&data[14..18]))),
+            field("Time Of Next Jump", be_u48(&data[18..24])),
+            field("Time Of Next Jam", be_u48(&data[24..30])),
+            field("Time Of Previous Jam", be_u48(&data[30..36])),
+            field(
+                "Previous Jam Local Offset",
+                format!("{} s", be_i32(&data[36..40])),
+            ),
+            field("Daylight Saving", format!("0x{:02x}", data[40])),
+            field("Leap Second Jump", format!("0x{:02x}", data[41])),
+        ])
+    }
+}
+
+#[test]
+fn test_org_tlv_decoding() {
+    // Announce with a SMPTE synchronization metadata TLV and an unknown one
+    let mut announce = vec![0u8; 64];
+    announce[0] = 0x0b;
+    announce[1] = 0x02;
+
+    let mut smpte = vec![0x68, 0x97, 0x5a, 0x00, 0x00, 0x01];
+    smpte.extend(25u32.to_be_bytes());
+    smpte.extend(1u32.to_be_bytes());
+    smpte.push(4);
+    smpte.extend([0u8; 33]);
+    let unknown = [0x02, 0x00, 0x00, 0x00, 0x00, 0x07, 0xde, 0xad];
+    for value in [&smpte[..], &unknown[..]] {
+        announce.extend(0x0003u16.to_be_bytes());
+        announce.extend((value.len() as u16).to_be_bytes());
+        announce.extend(value);
+    }
+    let length = announce.len() as u16;
+    announce[2..4].copy_from_slice(&length.to_be_bytes());
+    let header = PtpHeader::try_from(&announce[..]).unwrap();
+
+    let decoded = registry().decode(&header, &announce);
+    assert_eq!(decoded.len(), 2);
+    let fields = decoded[0].fields.as_ref().unwrap();
+    assert!(fields.contains(&field("Default Frame Rate", "25/1")));
+    assert!(fields.contains(&field("Locking Status", "locked")));
+    assert_eq!(decoded[0].title(), "SMPTE (68:97:5a) sub type 0x000001");
+
+    assert_eq!(decoded[1].fields, None);
+    assert_eq!(decoded[1].sub_type, 7);
+    assert_eq!(decoded[1].data, [0xde, 0xad]);
+}
@@ -0,0 +1,268 @@
+//! Writing captured PTP frames back out as pcap files
+//!
+//! Every RawPacket keeps the complete Ethernet frame it was decoded from, so
+//! the original traffic can be reproduced byte for byte for Wireshark & co.
+//! Packet history exports can also be written as CSV or JSON with the decoded
+//! fields, one row or object per packet.
+
+use anyhow::Result;
+use pcap_file::pcap::{PcapPacket, PcapWriter};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use crate::csv_tail::{csv_field, format_time};
+use crate::source::RawPacket;
+use crate::types::{ParsedPacket, format_mac};
+
+const CSV_HEADER: &str = "time,interface,vlan,pcp,source_mac,source,destination,message_type,\
+sdo_id,domain,sequence_id,source_port_identity,correction_ns,log_message_interval,\
+message_length,details";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Pcap,
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Pcap => "pcap",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Write the given packets to a new pcap file, returning the number of packets written
+pub fn write_pcap<'a, I>(path: &Path, packets: I) -> Result<usize>
+where
+    I: IntoIterator<Item = &'a RawPacket>,
+{
+    let file = BufWriter::new(File::create(path)?);
+    let mut writer = PcapWriter::new(file)?;
+    let mut count = 0;
+
+    for packet in packets {
+        let timestamp = packet
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        writer.write_packet(&PcapPacket::new(
+            timestamp,
+            packet.original_length as u32,
+            &packet.data,
+        ))?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// File name stem for an export to `dir`: the name, the time to the
+/// millisecond and, if a file of that stem with one of the extensions is
+/// already there, a counter
+pub fn export_stem(dir: &Path, name: &str, extensions: &[&str]) -> String {
+    let stem = format!(
+        "{}_{}",
+        name,
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+    );
+    let taken = |stem: &str| {
+        extensions
+            .iter()
+            .any(|extension| dir.join(format!("{}.{}", stem, extension)).exists())
+    };
+    if !taken(&stem) {
+        return stem;
+    }
+    (2..)
+        .map(|counter| format!("{}_{}", stem, counter))
+        .find(|stem| !taken(stem))
+        .unwrap()
+}
+
+/// Write packets in the given format, returning the number of packets written
+pub fn write_packets(
+    path: &Path,
+    format: ExportFormat,
+    packets: &[Arc<ParsedPacket>],
+) -> Result<usize> {
+    match format {
+        ExportFormat::Pcap => write_pcap(path, packets.iter().map(|packet| packet.raw.as_ref())),
+        ExportFormat::Csv => write_csv(path, packets),
+        ExportFormat::Json => write_json(path, packets),
+    }
+}
+
+/// The columns of a packet, shared by CSV and JSON
+fn packet_fields(packet: &ParsedPacket) -> Vec<(&'static str, String)> {
+    let header = packet.ptp.header();
+    let raw = &packet.raw;
+    vec![
+        ("time", format_time(raw.timestamp)),
+        ("interface", raw.interface_name.clone()),
+        (
+            "vlan",
+            raw.vlan_id.map(|vlan| vlan.to_string()).unwrap_or_default(),
+        ),
+        (
+            "pcp",
+            raw.vlan_pcp.map(|pcp| pcp.to_string()).unwrap_or_default(),
+        ),
+        ("source_mac", format_mac(raw.source_mac)),
+        (
+            "source",
+            raw.source_addr
+                .map(|addr| addr.to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "destination",
+            raw.dest_addr
+                .map(|addr| addr.to_string())
+                .unwrap_or_else(|| format_mac(raw.dest_mac)),
+        ),
+        ("message_type", header.message_type.to_string()),
+        ("sdo_id", header.sdo_id.to_string()),
+        ("domain", header.domain_number.to_string()),
+        ("sequence_id", header.sequence_id.to_string()),
+        (
+            "source_port_identity",
+            header.source_port_identity.to_string(),
+        ),
+        (
+            "correction_ns",
+            format!("{:.3}", header.correction_field.value as f64 / 65536.0),
+        ),
+        (
+            "log_message_interval",
+            header.log_message_interval.exponent.to_string(),
+        ),
+        ("message_length", header.message_length.to_string()),
+    ]
+}
+
+fn write_csv(path: &Path, packets: &[Arc<ParsedPacket>]) -> Result<usize> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "{}", CSV_HEADER)?;
+    for packet in packets {
+        let mut fields: Vec<String> = packet_fields(packet)
+            .into_iter()
+            .map(|(_, value)| csv_field(&value))
+            .collect();
+        fields.push(csv_field(&match packet.truncation() {
+            Some(truncation) => truncation.to_string(),
+            // Sent short, the zero filled body says nothing
+            None if packet.incomplete_body().is_some() => packet
+                .length_issues()
+                .first()
+                .map_or_else(|| packet.ptp.to_string(), ToString::to_string),
+            None => packet.ptp.to_string(),
+        }));
+        writeln!(file, "{}", fields.join(","))?;
+    }
+    file.flush()?;
+    Ok(packets.len())
+}
+
+fn write_json(path: &Path, packets: &[Arc<ParsedPacket>]) -> Result<usize> {
+    let objects: Vec<serde_json::Value> = packets
+        .iter()
+        .map(|packet| {
+            let mut object: serde_json::Map<String, serde_json::Value> = packet_fields(packet)
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), serde_json::Value::String(value)))
+                .collect();
+            let details = packet
+                .details()
+                .into_iter()
+                .map(|(key, value)| (key, serde_json::Value::String(value)))
+                .collect();
+            object.insert("details".to_string(), serde_json::Value::Object(details));
+            serde_json::Value::Object(object)
+        })
+        .collect();
+
+    let mut file = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut file, &objects)?;
+    writeln!(file)?;
+    file.flush()?;
+    Ok(packets.len())
+}
+
+#[test]
+fn test_write_packets() {
+    use std::time::Duration;
+
+    use crate::types::PtpMessage;
+
+    let mut payload = [0u8; 44];
+    payload[1] = 0x02;
+    payload[2..4].copy_from_slice(&44u16.to_be_bytes());
+    payload[30..32].copy_from_slice(&7u16.to_be_bytes());
+    let packet = Arc::new(ParsedPacket {
+        ptp: PtpMessage::try_from(&payload[..]).unwrap(),
+        raw: Arc::new(RawPacket {
+            source_addr: Some("192.0.2.1:319".parse().unwrap()),
+            source_mac: [0, 1, 2, 3, 4, 5],
+            dest_addr: Some("224.0.1.129:319".parse().unwrap()),
+            vlan_id: Some(100),
+            vlan_pcp: Some(7),
+            ..RawPacket::from_ptp_payload(&payload, "eth0", UNIX_EPOCH + Duration::from_secs(10))
+        }),
+    });
+
+    let dir = std::env::temp_dir();
+    let csv = dir.join(format!("ptp-trace-export-{}.csv", std::process::id()));
+    assert_eq!(
+        write_packets(&csv, ExportFormat::Csv, std::slice::from_ref(&packet)).unwrap(),
+        1
+    );
+    let text = std::fs::read_to_string(&csv).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], CSV_HEADER);
+    assert!(
+        lines[1]
+            .starts_with("1970-01-01T00:00:10.000Z,eth0,100,7,00:01:02:03:04:05,192.0.2.1:319,")
+    );
+    assert!(lines[1].contains(",SYNC,0,0,7,"));
+
+    let json = dir.join(format!("ptp-trace-export-{}.json", std::process::id()));
+    assert_eq!(
+        write_packets(&json, ExportFormat::Json, &[packet]).unwrap(),
+        1
+    );
+    let value: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&json).unwrap()).unwrap();
+    assert_eq!(value[0]["sequence_id"], "7");
+    assert!(value[0]["details"].is_object());
+
+    let _ = std::fs::remove_file(csv);
+    let _ = std::fs::remove_file(json);
+}
+
+#[test]
+fn test_export_stem() {
+    let dir = std::env::temp_dir().join(format!("ptp-trace-stem-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let first = export_stem(&dir, "report", &["html"]);
+    assert!(first.starts_with("report_") && first.ends_with('Z'));
+    std::fs::write(dir.join(format!("{}.html", first)), "").unwrap();
+    // Two exports within the same millisecond don't share a file
+    let second = export_stem(&dir, "report", &["html"]);
+    assert_ne!(second, first);
+    if second.starts_with(&first) {
+        assert_eq!(second, format!("{}_2", first));
+    }
+    // Screenshots are only named alike if neither of their files is there
+    std::fs::write(dir.join(format!("{}.ansi", second)), "").unwrap();
+    assert_ne!(export_stem(&dir, "report", &["txt", "ansi"]), second);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
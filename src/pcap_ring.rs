@@ -0,0 +1,171 @@
+//! Rolling ring of pcap files of all captured PTP traffic
+//!
+//! The packet history is bounded, can be cleared or switched off per host and
+//! is gone when ptp-trace exits. With --write-ring, every captured PTP frame is
+//! also written to a pcap file in a directory, like tcpdump -C/-W: a new file
+//! is started once the current one reaches --ring-size, and the oldest file is
+//! deleted when there are more than --ring-files. The last few hundred
+//! megabytes of raw traffic are then always on disk for the post-mortem.
+
+use anyhow::{Context, Result};
+use pcap_file::pcap::{PcapPacket, PcapWriter};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::source::RawPacket;
+
+/// pcap global header
+const FILE_HEADER_SIZE: u64 = 24;
+/// pcap per-packet record header
+const RECORD_HEADER_SIZE: u64 = 16;
+
+/// Parse a file size like "100M", "500KB" or "1G"
+pub fn parse_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid size '{}', expected e.g. 100M", text))?;
+    let shift = match unit.to_ascii_uppercase().trim_end_matches('B') {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        _ => {
+            return Err(format!("Invalid size unit '{}', expected K, M or G", unit));
+        }
+    };
+    let size = number << shift;
+    if size <= FILE_HEADER_SIZE {
+        return Err(format!("Size '{}' is too small for a pcap file", text));
+    }
+    Ok(size)
+}
+
+pub struct PcapRing {
+    directory: PathBuf,
+    max_file_size: u64,
+    max_files: usize,
+    /// Unbuffered, PTP packet rates are low and the files are then complete
+    /// whenever ptp-trace is killed
+    writer: PcapWriter<File>,
+    current_size: u64,
+    /// Files of the ring, oldest first, the last one is being written
+    files: VecDeque<PathBuf>,
+    next_index: u64,
+}
+
+impl PcapRing {
+    /// Start the ring in a directory, creating it if needed
+    pub fn new(directory: &Path, max_file_size: u64, max_files: usize) -> Result<Self> {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("Failed to create ring directory {}", directory.display()))?;
+        let (path, writer) = Self::create_file(directory, 0)?;
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            max_file_size,
+            max_files: max_files.max(1),
+            writer,
+            current_size: FILE_HEADER_SIZE,
+            files: VecDeque::from([path]),
+            next_index: 1,
+        })
+    }
+
+    /// Files are named by start time and index, so that they sort in order
+    /// and never overwrite those of an earlier run
+    fn create_file(directory: &Path, index: u64) -> Result<(PathBuf, PcapWriter<File>)> {
+        let timestamp =
+            chrono::DateTime::<chrono::Utc>::from(SystemTime::now()).format("%Y%m%dT%H%M%SZ");
+        let path = directory.join(format!("ptp-trace_{}_{:05}.pcap", timestamp, index));
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create ring file {}", path.display()))?;
+        let writer = PcapWriter::new(file)?;
+        Ok((path, writer))
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        let (path, writer) = Self::create_file(&self.directory, self.next_index)?;
+        self.next_index += 1;
+        self.writer = writer;
+        self.current_size = FILE_HEADER_SIZE;
+        self.files.push_back(path);
+        while self.files.len() > self.max_files {
+            if let Some(oldest) = self.files.pop_front() {
+                std::fs::remove_file(&oldest)
+                    .with_context(|| format!("Failed to remove ring file {}", oldest.display()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Files of the ring still on disk, oldest first
+    pub fn files(&self) -> &VecDeque<PathBuf> {
+        &self.files
+    }
+
+    pub fn write(&mut self, packet: &RawPacket) -> Result<()> {
+        let record_size = RECORD_HEADER_SIZE + packet.data.len() as u64;
+        if self.current_size > FILE_HEADER_SIZE
+            && self.current_size + record_size > self.max_file_size
+        {
+            self.rotate()?;
+        }
+        let timestamp = packet
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        self.writer.write_packet(&PcapPacket::new(
+            timestamp,
+            packet.original_length as u32,
+            &packet.data,
+        ))?;
+        self.current_size += record_size;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_pcap_ring() {
+    assert_eq!(parse_size("100M"), Ok(100 << 20));
+    assert_eq!(parse_size("500KB"), Ok(500 << 10));
+    assert_eq!(parse_size("4096"), Ok(4096));
+    assert!(parse_size("10X").is_err());
+    assert!(parse_size("16").is_err());
+
+    let directory =
+        std::env::temp_dir().join(format!("ptp-trace-ring-test-{}", std::process::id()));
+    let packet = RawPacket::from_ptp_payload(&[0u8; 84], "eth0", SystemTime::now());
+
+    // Room for 3 packets per file, 10 packets make 4 files of which 2 are kept
+    let mut ring = PcapRing::new(&directory, FILE_HEADER_SIZE + 3 * 100, 2).unwrap();
+    for _ in 0..10 {
+        ring.write(&packet).unwrap();
+    }
+
+    let mut files: Vec<_> = std::fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    files.sort();
+    assert_eq!(files, Vec::from(ring.files.clone()));
+    let count_packets = |path: &Path| {
+        let mut reader = pcap_file::pcap::PcapReader::new(File::open(path).unwrap()).unwrap();
+        let mut count = 0;
+        while let Some(packet) = reader.next_packet() {
+            packet.unwrap();
+            count += 1;
+        }
+        count
+    };
+    assert_eq!(count_packets(&files[0]), 3);
+    assert_eq!(count_packets(&files[1]), 1);
+    std::fs::remove_dir_all(&directory).unwrap();
+}
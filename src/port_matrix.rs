@@ -0,0 +1,247 @@
+//! Event and general port reachability of each host (319/320 matrix)
+//!
+//! PTP over UDP sends the timestamped event messages to port 319 and all other
+//! messages to port 320. A firewall rule opened for only one of the two lets a
+//! transmitter's Syncs through while its Announces and Follow_Ups are dropped,
+//! or a receiver's Delay_Reqs out while the Delay_Resps never come back. The
+//! host looks alive, but the election or the delay measurement quietly break.
+//! The matrix lists the ports each host sends from and to by message class and
+//! flags hosts seen on one of the two only.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    source::{PTP_EVENT_PORT, PTP_GENERAL_PORT},
+    types::PtpMessageType,
+};
+
+/// Time a host gets to show up on the other port before it is flagged, more
+/// than the longest common Announce interval
+pub const PORT_PAIR_GRACE: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PortClass {
+    Event,
+    General,
+}
+
+impl PortClass {
+    pub fn of(message_type: PtpMessageType) -> Self {
+        if message_type.is_event() {
+            PortClass::Event
+        } else {
+            PortClass::General
+        }
+    }
+
+    /// IEEE 1588 Annex C port of the class
+    pub fn port(&self) -> u16 {
+        match self {
+            PortClass::Event => PTP_EVENT_PORT,
+            PortClass::General => PTP_GENERAL_PORT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PortProblem {
+    /// Syncs on the event port, but no Announce or Follow_Up on the general port
+    EventOnly,
+    /// Announces on the general port, but no Sync on the event port
+    GeneralOnly,
+    /// Delay_Reqs sent, but no Delay_Resp came back
+    NoDelayResp,
+    /// A message sent to the port of the other class
+    WrongPort {
+        message_type: PtpMessageType,
+        port: u16,
+    },
+}
+
+impl Display for PortProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PortProblem::EventOnly => write!(
+                f,
+                "Syncs on port {} but nothing on port {}, Announce and Follow_Up blocked?",
+                PTP_EVENT_PORT, PTP_GENERAL_PORT
+            ),
+            PortProblem::GeneralOnly => write!(
+                f,
+                "Announces on port {} but no Sync on port {}, event port blocked?",
+                PTP_GENERAL_PORT, PTP_EVENT_PORT
+            ),
+            PortProblem::NoDelayResp => write!(
+                f,
+                "Delay_Reqs on port {} but no Delay_Resp on port {} came back",
+                PTP_EVENT_PORT, PTP_GENERAL_PORT
+            ),
+            PortProblem::WrongPort { message_type, port } => write!(
+                f,
+                "{} sent to port {} instead of {}",
+                message_type,
+                port,
+                PortClass::of(*message_type).port()
+            ),
+        }
+    }
+}
+
+/// UDP ports a host sends from and to, by message class
+#[derive(Debug, Clone, Default)]
+pub struct PortUsage {
+    /// Messages sent by (class, source port, destination port)
+    sent: BTreeMap<(PortClass, u16, u16), u64>,
+    /// First message of each type the host sent
+    first_sent: BTreeMap<PtpMessageType, SystemTime>,
+    /// Messages sent to a port of the other class, by type and port
+    wrong_ports: BTreeMap<(PtpMessageType, u16), u64>,
+    /// Delay_Resps addressed to the host, by (source port, destination port)
+    delay_resps: BTreeMap<(u16, u16), u64>,
+    last_sent: Option<SystemTime>,
+}
+
+impl PortUsage {
+    pub fn record_sent(
+        &mut self,
+        message_type: PtpMessageType,
+        source_port: u16,
+        dest_port: u16,
+        timestamp: SystemTime,
+    ) {
+        let class = PortClass::of(message_type);
+        *self
+            .sent
+            .entry((class, source_port, dest_port))
+            .or_default() += 1;
+        self.first_sent.entry(message_type).or_insert(timestamp);
+        let other = match class {
+            PortClass::Event => PortClass::General,
+            PortClass::General => PortClass::Event,
+        };
+        if dest_port == other.port() {
+            *self
+                .wrong_ports
+                .entry((message_type, dest_port))
+                .or_default() += 1;
+        }
+        self.last_sent = Some(self.last_sent.map_or(timestamp, |last| last.max(timestamp)));
+    }
+
+    /// A Delay_Resp answering the host's Delay_Req
+    pub fn record_delay_resp(&mut self, source_port: u16, dest_port: u16) {
+        *self
+            .delay_resps
+            .entry((source_port, dest_port))
+            .or_default() += 1;
+    }
+
+    /// No PTP over UDP seen from the host, e.g. layer 2 transport
+    pub fn is_empty(&self) -> bool {
+        self.sent.is_empty()
+    }
+
+    /// (source port, destination port, messages) the host sent in a class
+    pub fn flows(&self, class: PortClass) -> Vec<(u16, u16, u64)> {
+        self.sent
+            .iter()
+            .filter(|((flow_class, _, _), _)| *flow_class == class)
+            .map(|(&(_, source, dest), &count)| (source, dest, count))
+            .collect()
+    }
+
+    /// (source port, destination port, messages) of the Delay_Resps
+    /// addressed to the host
+    pub fn delay_resp_flows(&self) -> Vec<(u16, u16, u64)> {
+        self.delay_resps
+            .iter()
+            .map(|(&(source, dest), &count)| (source, dest, count))
+            .collect()
+    }
+
+    /// Whether the host sent Delay_Reqs, so it expects Delay_Resps back
+    pub fn sends_delay_reqs(&self) -> bool {
+        self.first_sent.contains_key(&PtpMessageType::DelayReq)
+    }
+
+    /// Whether `message_type` was sent for longer than the grace time
+    /// without any of `expected` being seen
+    fn missing(&self, message_type: PtpMessageType, expected: &[PtpMessageType]) -> bool {
+        let (Some(first), Some(last)) = (self.first_sent.get(&message_type), self.last_sent) else {
+            return false;
+        };
+        last.duration_since(*first).unwrap_or_default() >= PORT_PAIR_GRACE
+            && !expected
+                .iter()
+                .any(|expected| self.first_sent.contains_key(expected))
+    }
+
+    pub fn problems(&self) -> Vec<PortProblem> {
+        let mut problems = Vec::new();
+        if self.missing(
+            PtpMessageType::Sync,
+            &[PtpMessageType::Announce, PtpMessageType::FollowUp],
+        ) {
+            problems.push(PortProblem::EventOnly);
+        }
+        if self.missing(PtpMessageType::Announce, &[PtpMessageType::Sync]) {
+            problems.push(PortProblem::GeneralOnly);
+        }
+        if self.delay_resps.is_empty() && self.missing(PtpMessageType::DelayReq, &[]) {
+            problems.push(PortProblem::NoDelayResp);
+        }
+        problems.extend(
+            self.wrong_ports
+                .keys()
+                .map(|&(message_type, port)| PortProblem::WrongPort { message_type, port }),
+        );
+        problems
+    }
+}
+
+#[test]
+fn test_port_usage() {
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let at = |seconds: u64| start + Duration::from_secs(seconds);
+
+    // A transmitter whose general port is blocked on the way
+    let mut transmitter = PortUsage::default();
+    for second in 0..30 {
+        transmitter.record_sent(PtpMessageType::Sync, 319, 319, at(second));
+        if second == 10 {
+            assert!(transmitter.problems().is_empty());
+        }
+    }
+    assert_eq!(transmitter.problems(), vec![PortProblem::EventOnly]);
+    assert_eq!(transmitter.flows(PortClass::Event), vec![(319, 319, 30)]);
+    assert!(transmitter.flows(PortClass::General).is_empty());
+
+    // Announces arriving clear it
+    transmitter.record_sent(PtpMessageType::Announce, 320, 320, at(30));
+    assert!(transmitter.problems().is_empty());
+
+    // A receiver never answered, sending Delay_Reqs from an ephemeral port
+    let mut receiver = PortUsage::default();
+    for second in 0..30 {
+        receiver.record_sent(PtpMessageType::DelayReq, 49152, 319, at(second));
+    }
+    assert!(receiver.sends_delay_reqs());
+    assert_eq!(receiver.problems(), vec![PortProblem::NoDelayResp]);
+    receiver.record_delay_resp(320, 49152);
+    assert!(receiver.problems().is_empty());
+
+    // Announce sent to the event port
+    receiver.record_sent(PtpMessageType::Announce, 320, 319, at(31));
+    assert_eq!(
+        receiver.problems(),
+        vec![PortProblem::WrongPort {
+            message_type: PtpMessageType::Announce,
+            port: 319
+        }]
+    );
+    assert!(PortUsage::default().is_empty());
+}
@@ -0,0 +1,280 @@
+//! Where the time of an update cycle goes
+//!
+//! Every update cycle drains the captured packets (capture), decodes the PTP
+//! messages (parse), updates hosts, BMCA and alerts (tracking), and the screen
+//! is drawn in between (render). The time of each stage is kept for the last
+//! PROFILE_CYCLES cycles together with the packet counts, so a slow parser, a
+//! tracker that cannot keep up with the packet rate or a terminal that takes
+//! too long to draw show up in the profiling pane ('Z') instead of being
+//! guessed at.
+//!
+//! The pane also counts the packets held in memory: a parsed packet is shared
+//! by Arc between the histories of every host it concerns, the latest packets
+//! and the bookmarks, so the number of references is larger than the number
+//! of packets actually stored.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::{source::RawPacket, types::ParsedPacket};
+
+/// Update cycles averaged in the profiling pane
+pub const PROFILE_CYCLES: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Capture,
+    Parse,
+    Tracking,
+    Render,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [Stage::Capture, Stage::Parse, Stage::Tracking, Stage::Render];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Stage::Capture => "Capture",
+            Stage::Parse => "Parse",
+            Stage::Tracking => "Tracking",
+            Stage::Render => "Render",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Time spent in each stage, and the messages parsed, over some stretch of time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StageTimes {
+    times: [Duration; 4],
+    pub parsed: u64,
+}
+
+impl StageTimes {
+    pub fn add(&mut self, stage: Stage, duration: Duration) {
+        self.times[stage.index()] += duration;
+    }
+
+    pub fn get(&self, stage: Stage) -> Duration {
+        self.times[stage.index()]
+    }
+
+    pub fn total(&self) -> Duration {
+        self.times.iter().sum()
+    }
+
+    fn merge(&mut self, other: &StageTimes) {
+        for stage in Stage::ALL {
+            self.add(stage, other.get(stage));
+        }
+        self.parsed += other.parsed;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Cycle {
+    pub times: StageTimes,
+    /// Packets received during the cycle
+    pub packets: u64,
+    /// Wall clock time since the previous cycle
+    pub elapsed: Duration,
+    /// Packets were left waiting at the end of the cycle
+    pub backlogged: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Profiler {
+    /// Stage times of the cycle in progress
+    current: StageTimes,
+    cycles: VecDeque<Cycle>,
+    last_packet_count: u64,
+}
+
+impl Profiler {
+    pub fn record(&mut self, stage: Stage, duration: Duration) {
+        self.current.add(stage, duration);
+    }
+
+    /// Close the cycle in progress with the stage times of the tracker, the
+    /// total packet count and the time since the last cycle
+    pub fn finish_cycle(
+        &mut self,
+        tracker_times: StageTimes,
+        packet_count: u64,
+        elapsed: Duration,
+        backlogged: bool,
+    ) {
+        let mut times = std::mem::take(&mut self.current);
+        times.merge(&tracker_times);
+        let packets = packet_count.saturating_sub(self.last_packet_count);
+        self.last_packet_count = packet_count;
+
+        if self.cycles.len() == PROFILE_CYCLES {
+            self.cycles.pop_front();
+        }
+        self.cycles.push_back(Cycle {
+            times,
+            packets,
+            elapsed,
+            backlogged,
+        });
+    }
+
+    pub fn last(&self) -> Option<&Cycle> {
+        self.cycles.back()
+    }
+
+    pub fn cycle_count(&self) -> usize {
+        self.cycles.len()
+    }
+
+    /// Average time of a stage per cycle
+    pub fn average(&self, stage: Stage) -> Duration {
+        let total: Duration = self.cycles.iter().map(|c| c.times.get(stage)).sum();
+        total / self.cycles.len().max(1) as u32
+    }
+
+    pub fn max(&self, stage: Stage) -> Duration {
+        self.cycles
+            .iter()
+            .map(|c| c.times.get(stage))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Packets received per second over the kept cycles
+    pub fn packet_rate(&self) -> f64 {
+        let elapsed: Duration = self.cycles.iter().map(|c| c.elapsed).sum();
+        if elapsed.is_zero() {
+            return 0.0;
+        }
+        self.cycles.iter().map(|c| c.packets).sum::<u64>() as f64 / elapsed.as_secs_f64()
+    }
+
+    /// Average parse time of one message, None before any was parsed
+    pub fn parse_latency(&self) -> Option<Duration> {
+        let parsed: u64 = self.cycles.iter().map(|c| c.times.parsed).sum();
+        let total: Duration = self.cycles.iter().map(|c| c.times.get(Stage::Parse)).sum();
+        (parsed > 0).then(|| Duration::from_nanos((total.as_nanos() / parsed as u128) as u64))
+    }
+
+    /// Share of the wall clock time spent working, near 1 means the
+    /// pipeline barely keeps up
+    pub fn load(&self) -> f64 {
+        let elapsed: Duration = self.cycles.iter().map(|c| c.elapsed).sum();
+        if elapsed.is_zero() {
+            return 0.0;
+        }
+        let busy: Duration = self.cycles.iter().map(|c| c.times.total()).sum();
+        busy.as_secs_f64() / elapsed.as_secs_f64()
+    }
+
+    /// Cycles that left packets waiting
+    pub fn backlogged_cycles(&self) -> usize {
+        self.cycles.iter().filter(|c| c.backlogged).count()
+    }
+}
+
+/// Packets held in memory, counted once however often they are referenced
+#[derive(Debug, Clone, Default)]
+pub struct PacketMemory {
+    parsed: HashSet<*const ParsedPacket>,
+    raw: HashSet<*const RawPacket>,
+    /// References to parsed packets
+    pub references: usize,
+    /// Captured bytes of the unique raw packets
+    pub bytes: usize,
+}
+
+impl PacketMemory {
+    pub fn add_parsed(&mut self, packet: &Arc<ParsedPacket>) {
+        self.references += 1;
+        if self.parsed.insert(Arc::as_ptr(packet)) {
+            self.add_raw(&packet.raw);
+        }
+    }
+
+    pub fn add_raw(&mut self, packet: &Arc<RawPacket>) {
+        if self.raw.insert(Arc::as_ptr(packet)) {
+            self.bytes += packet.data.len();
+        }
+    }
+
+    pub fn unique_packets(&self) -> usize {
+        self.parsed.len()
+    }
+
+    pub fn unique_raw_packets(&self) -> usize {
+        self.raw.len()
+    }
+}
+
+#[test]
+fn test_profiler() {
+    let ms = Duration::from_millis;
+    let mut profiler = Profiler::default();
+    assert_eq!(profiler.parse_latency(), None);
+    assert_eq!(profiler.packet_rate(), 0.0);
+
+    let mut tracker = StageTimes::default();
+    tracker.add(Stage::Parse, ms(2));
+    tracker.add(Stage::Tracking, ms(8));
+    tracker.parsed = 100;
+    profiler.record(Stage::Render, ms(5));
+    profiler.record(Stage::Render, ms(5));
+    profiler.finish_cycle(tracker, 100, ms(1000), false);
+
+    let last = profiler.last().unwrap();
+    assert_eq!(last.times.get(Stage::Render), ms(10));
+    assert_eq!(last.times.total(), ms(20));
+    assert_eq!(profiler.parse_latency(), Some(Duration::from_micros(20)));
+
+    // The packet count is a running total
+    profiler.finish_cycle(StageTimes::default(), 400, ms(1000), true);
+    assert_eq!(profiler.last().unwrap().packets, 300);
+    assert_eq!(profiler.last().unwrap().times.total(), Duration::ZERO);
+    assert_eq!(profiler.packet_rate(), 200.0);
+    assert_eq!(profiler.average(Stage::Render), ms(5));
+    assert_eq!(profiler.max(Stage::Render), ms(10));
+    assert_eq!(profiler.backlogged_cycles(), 1);
+    assert!((profiler.load() - 0.01).abs() < 1e-9);
+
+    for _ in 0..PROFILE_CYCLES {
+        profiler.finish_cycle(StageTimes::default(), 400, ms(1000), false);
+    }
+    assert_eq!(profiler.cycle_count(), PROFILE_CYCLES);
+    assert_eq!(profiler.max(Stage::Render), Duration::ZERO);
+}
+
+#[test]
+fn test_packet_memory() {
+    // Sync header, the body is all zero
+    let mut payload = [0u8; 44];
+    payload[1] = 0x02;
+    payload[2..4].copy_from_slice(&44u16.to_be_bytes());
+    let raw = Arc::new(RawPacket::from_ptp_payload(
+        &payload,
+        "eth0",
+        std::time::SystemTime::UNIX_EPOCH,
+    ));
+    let packet = Arc::new(ParsedPacket {
+        ptp: crate::types::PtpMessage::try_from(&payload[..]).unwrap(),
+        raw: raw.clone(),
+    });
+
+    // In the histories of two hosts and in the recent raw packets
+    let mut memory = PacketMemory::default();
+    memory.add_parsed(&packet);
+    memory.add_parsed(&packet.clone());
+    memory.add_raw(&raw);
+    assert_eq!(memory.references, 2);
+    assert_eq!(memory.unique_packets(), 1);
+    assert_eq!(memory.unique_raw_packets(), 1);
+    assert_eq!(memory.bytes, 44);
+}
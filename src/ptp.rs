@@ -1,21 +1,82 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow, bail};
+use pnet::ipnetwork::IpNetwork;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    hash::{DefaultHasher, Hash, Hasher},
     net::IpAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
     time::{Duration, Instant, SystemTime},
 };
 
 use crate::{
+    addressing::{self, AddressIssue},
+    alert_hooks::AlertHooks,
+    alerts::{Alert, AlertCapture, AlertKind, AlertSeverity},
+    announce_continuity::AnnounceContinuity,
+    arrival_paths::ArrivalPaths,
+    bmca::{BmcaCandidate, BmcaProfile, DEFAULT_LOCAL_PRIORITY},
+    bond::{self, BondWatch},
     bounded_vec::BoundedVec,
+    burst::BurstDetector,
+    clock::Clock,
+    clock_description::{self, DescriptionQuery},
+    clock_role::RoleEvidence,
+    conformance::Conformance,
+    dedup::PacketDeduplicator,
+    delay_cadence::{CadenceChange, DelayReqCadence},
+    delay_xref::DelayCrossReference,
+    drift::SyncDrift,
+    drill::FailoverDrill,
+    era::{DomainEras, EraEstimate},
+    gm_identity::{AdvertisedGm, OwnAnnounces},
+    gm_pair::{self, DEFAULT_DRIFT_THRESHOLD, GmPairStatus, PairPhase},
+    health::SequenceLoss,
+    minor_versions::MixedMinorVersions,
+    mute::MuteList,
+    pcap_ring::PcapRing,
+    port_matrix::PortUsage,
+    profiling::{PacketMemory, Stage, StageTimes},
+    redundancy::{DEFAULT_SKEW_THRESHOLD, PathSkew, SyncArrivals},
+    residence::ResidenceTimes,
+    shared_gm::{AnnouncedDataset, SharedGrandmasters},
+    sim_receiver::SimulatedReceiver,
+    source::RawPacket,
+    stats_store::StatsStore,
+    sync_sampler::SyncSampler,
+    time_range::WindowedCounts,
+    timescale::{self, TimescaleIssue, UtcOffsets},
+    topology::{HopDistance, PathEvidence, hops_from_ttl, multicast_boundary_violation},
+    transmit::{FrameTransmitter, TransmitMode},
+    trend::QualityTrend,
     types::{
-        AnnounceMessage, ClockIdentity, DelayRespMessage, FollowUpMessage,
+        AnnounceMessage, ClockIdentity, DelayRespMessage, FollowUpMessage, LengthIssue,
         PDelayRespFollowUpMessage, PDelayRespMessage, ParsedPacket, PtpClockAccuracy,
-        PtpClockClass, PtpCorrectionField, PtpHeader, PtpMessage, PtpTimestamp, PtpUtcOffset,
-        PtpVersion, SyncMessage,
+        PtpClockClass, PtpCorrectionField, PtpHeader, PtpMessage, PtpMessageType, PtpTimestamp,
+        PtpUtcOffset, PtpVersion, SDO_ID_GPTP, SyncMessage, TLV_PATH_TRACE, find_tlv,
+        format_ptp_instance, parse_path_trace, unicast_tlvs,
     },
 };
 
-use std::rc::Rc;
+/// Silence after which a host counts as having disappeared
+const OBSERVATION_GAP: Duration = Duration::from_secs(10);
+/// Number of observed spans kept per host
+const MAX_OBSERVED_SPANS: usize = 100;
+/// Packets taken from the capture per call, so the UI stays responsive
+const MAX_PACKETS_PER_POLL: usize = 100;
+/// Silence of a receiver's delay exchanges before its selected transmitter
+/// becomes less certain, covering Delay_Req intervals of up to 8 s
+const CONFIDENCE_GRACE: Duration = Duration::from_secs(10);
+/// Silence of a receiver's delay exchanges after which its selected
+/// transmitter is no longer known
+const ASSOCIATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Direction to move within a packet's message exchange
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExchangeStep {
+    Previous,
+    Next,
+}
 
 #[derive(Debug, Clone, Default)]
 pub struct PtpHostStateTimeTransmitter {
@@ -32,6 +93,9 @@ pub struct PtpHostStateTimeTransmitter {
     pub last_sync_origin_timestamp: Option<PtpTimestamp>,
     pub last_followup_origin_timestamp: Option<PtpTimestamp>,
     pub current_utc_offset: Option<PtpUtcOffset>,
+    /// clockIdentities from the PATH_TRACE TLV of the last Announce (802.1AS),
+    /// None if it carried none
+    pub path_trace: Option<Vec<ClockIdentity>>,
     /// True if this transmitter has been selected as the Best Master Clock in its domain
     /// BMCA winners are displayed as "PTT" (Primary Time Transmitter) in the UI
     pub is_bmca_winner: bool,
@@ -44,7 +108,7 @@ impl PtpHostStateTimeTransmitter {
         s
     }
 
-    fn update_from_announce(&mut self, msg: &AnnounceMessage) {
+    pub fn update_from_announce(&mut self, msg: &AnnounceMessage) {
         self.priority1 = Some(msg.priority1);
         self.priority2 = Some(msg.priority2);
         self.clock_class = Some(msg.clock_class);
@@ -181,6 +245,8 @@ pub struct PtpHostStateTimeReceiver {
     pub last_pdelay_follow_up_timestamp: Option<PtpTimestamp>,
     pub selected_transmitter_identity: Option<ClockIdentity>,
     pub selected_transmitter_confidence: f32, // 0.0 to 1.0 confidence score
+    // Capture time of the last Delay_Req, Delay_Resp or Pdelay_Resp of the receiver
+    pub last_delay_exchange: Option<SystemTime>,
 }
 
 impl Default for PtpHostStateTimeReceiver {
@@ -191,6 +257,7 @@ impl Default for PtpHostStateTimeReceiver {
             last_pdelay_follow_up_timestamp: None,
             selected_transmitter_identity: None,
             selected_transmitter_confidence: 0.0,
+            last_delay_exchange: None,
         }
     }
 }
@@ -213,6 +280,42 @@ impl PtpHostStateTimeReceiver {
         }
     }
 
+    /// Lower the confidence in the selected transmitter while the receiver's
+    /// delay exchanges are silent, and
+    /// drop the association once they have stopped or the transmitter is
+    /// gone. Returns whether the association was dropped.
+    fn decay_confidence(&mut self, now: SystemTime, transmitter_present: bool) -> bool {
+        if self.selected_transmitter_identity.is_none() {
+            return false;
+        }
+        if !transmitter_present || self.delay_exchanges_stopped(now) {
+            self.selected_transmitter_identity = None;
+            self.selected_transmitter_confidence = 0.0;
+            return true;
+        }
+        if let Some(silence) = self.delay_exchange_silence(now)
+            && silence > CONFIDENCE_GRACE
+        {
+            // Linear from full confidence at the end of the grace period to none at the timeout
+            let remaining = (ASSOCIATION_TIMEOUT - silence).as_secs_f32()
+                / (ASSOCIATION_TIMEOUT - CONFIDENCE_GRACE).as_secs_f32();
+            self.selected_transmitter_confidence =
+                self.selected_transmitter_confidence.min(remaining);
+        }
+        false
+    }
+
+    /// Time since the last delay exchange, None if none was seen
+    pub fn delay_exchange_silence(&self, now: SystemTime) -> Option<Duration> {
+        self.last_delay_exchange
+            .map(|last| now.duration_since(last).unwrap_or_default())
+    }
+
+    fn delay_exchanges_stopped(&self, now: SystemTime) -> bool {
+        self.delay_exchange_silence(now)
+            .is_some_and(|silence| silence >= ASSOCIATION_TIMEOUT)
+    }
+
     fn from_delay_resp(msg: &DelayRespMessage) -> Self {
         let mut s = PtpHostStateTimeReceiver::default();
         s.update_from_delay_resp(msg);
@@ -357,7 +460,7 @@ impl PtpHostState {
         }
     }
 
-    pub fn short_string(&self) -> &str {
+    pub fn short_string(&self) -> &'static str {
         match self {
             PtpHostState::Listening => "L",
             PtpHostState::TimeTransmitter(state) => {
@@ -372,14 +475,46 @@ impl PtpHostState {
     }
 }
 
+/// Path delay mechanism a host uses, as observed from the requests it sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DelayMechanism {
+    /// End-to-end, Delay_Req/Delay_Resp
+    E2E,
+    /// Peer-to-peer, PDelay_Req/PDelay_Resp
+    P2P,
+    /// Both kinds of requests were seen
+    Mixed,
+}
+
+impl std::fmt::Display for DelayMechanism {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DelayMechanism::E2E => write!(f, "E2E"),
+            DelayMechanism::P2P => write!(f, "P2P"),
+            DelayMechanism::Mixed => write!(f, "Mix"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PtpHost {
     pub clock_identity: ClockIdentity,
     pub ip_addresses: HashMap<IpAddr, Vec<String>>,
     pub interfaces: HashSet<String>, // For gPTP hosts without IP addresses
+    // Source MACs of link-local messages (Layer 2 transport or peer delay)
+    pub mac_addresses: BTreeSet<[u8; 6]>,
+    // Hosts exchanging peer delay messages with this one, either direction
+    pub pdelay_peers: BTreeSet<ClockIdentity>,
+    // Link partners in the 802.1AS CMLDS, kept apart from the per-domain peers
+    // because one CMLDS exchange serves every gPTP domain on the link
+    pub cmlds_peers: BTreeSet<ClockIdentity>,
+    // CMLDS messages sent, which update neither domain_number nor instances
+    pub cmlds_message_count: u32,
     pub vlan_id: Option<u16>,
     pub domain_number: Option<u8>,
+    pub sdo_id: Option<u16>,
     pub last_version: Option<PtpVersion>,
+    pub last_minor_version: Option<u8>,
     pub last_seen: SystemTime,
 
     pub announce_count: u32,
@@ -397,7 +532,93 @@ pub struct PtpHost {
 
     pub state: PtpHostState,
     pub last_correction_field: Option<PtpCorrectionField>,
-    pub packet_history: BoundedVec<Rc<ParsedPacket>>,
+    pub packet_history: BoundedVec<Arc<ParsedPacket>>,
+    // Packet history retention can be switched off for noisy hosts
+    pub history_enabled: bool,
+    // Time spans during which the host was seen sending, oldest first
+    pub observed_spans: VecDeque<(SystemTime, SystemTime)>,
+    // First packet ever seen from the host, kept across restarts with --state-file
+    pub first_seen: Option<SystemTime>,
+    // First packet seen from the host in this session
+    pub session_first_seen: Option<SystemTime>,
+    // PTP instances (sdoId, domain number) the host was seen in, e.g. several gPTP domains
+    pub instances: BTreeSet<(u16, u8)>,
+    // Messages sent with messageLength or TLV lengths not matching the payload
+    pub length_issue_count: u32,
+    pub last_length_issue: Option<LengthIssue>,
+    // Messages whose body the capture cut short, e.g. a small pcap snaplen
+    pub truncated_count: u32,
+    // Evidence about transparent clocks or PTP-unaware switches towards the host
+    pub path: PathEvidence,
+    // Residence time transparent clocks added to the host's Syncs
+    pub residence: ResidenceTimes,
+    // Sync origin against arrival times, for the frequency offset estimate
+    pub sync_drift: SyncDrift,
+    // Messages sent by type over the last hour, for statistics of a time range
+    pub sent_counts: WindowedCounts,
+    // Syncs and Follow_Ups that go into the packet history with --sync-sample
+    pub sync_sampler: SyncSampler,
+    // Announced clockAccuracy and offsetScaledLogVariance over time
+    pub quality_trend: QualityTrend,
+    // Announce arrivals over the last minute, for the continuity bar
+    pub announce_continuity: AnnounceContinuity,
+    // IP TTLs of multicast packets sent by the host, as received
+    pub multicast_ttls: BTreeSet<u8>,
+    // Routed hops towards the host by the TTL of its packets, and its changes
+    pub hop_distance: HopDistance,
+    // Operator-defined tags from the config file
+    pub tags: Vec<String>,
+    // Messages sent to destinations not meant for them, by message type and issue
+    pub address_issues: BTreeMap<(PtpMessageType, AddressIssue), u32>,
+    // UDP destination ports of the host's packets
+    pub udp_ports: BTreeSet<u16>,
+    // Source and destination ports by message class, for the 319/320 matrix
+    pub port_usage: PortUsage,
+    // Set once a multicast boundary alert was raised for the host
+    pub boundary_alerted: bool,
+    // 802.1p priority of the last VLAN tagged event message the host sent
+    pub event_pcp: Option<u8>,
+    // Tagged event messages sent at another priority than the site policy expects
+    pub event_pcp_violations: u64,
+    // Arrival skew of the host's Syncs between pairs of capture interfaces
+    pub path_skews: BTreeMap<(String, String), PathSkew>,
+    // Last message of each type the host sent, for the custom table columns
+    pub latest_packets: HashMap<PtpMessageType, Arc<ParsedPacket>>,
+    // Microbursts in the arrival of the host's packets
+    pub bursts: BurstDetector,
+    // Delay_Req cadence, for probable losses of lock of a receiver
+    pub delay_req_cadence: DelayReqCadence,
+    // Message counts by the (source IP, interface, VLAN) they arrived on
+    pub arrival_paths: ArrivalPaths,
+    // Answer to GET CLOCK_DESCRIPTION, requested with 'N' in active mode
+    pub description_query: DescriptionQuery,
+    // Ports and instances the host transmits and receives on, telling
+    // boundary, ordinary and transparent clocks apart
+    pub role_evidence: RoleEvidence,
+    // Announces naming another clock as grandmaster, boundary clock or relabeled
+    pub advertised_gm: AdvertisedGm,
+    // Unicast sessions the host cancelled or refused, and the last such event
+    pub unicast_teardown_count: u32,
+    pub last_unicast_teardown: Option<(SystemTime, String)>,
+    // Timescale inconsistencies in the host's last Announce
+    pub timescale_issues: BTreeSet<TimescaleIssue>,
+    // Era inferred from the last origin timestamp the host sent
+    pub era: Option<EraEstimate>,
+    // Transmitter that last answered the host's Delay_Reqs, and where its last
+    // unicast Delay_Req went
+    pub answered_by: Option<ClockIdentity>,
+    pub delay_req_destination: Option<IpAddr>,
+    // Delay_Reqs of the host nobody answered, and the last one as an example
+    pub unanswered_delay_req_count: u32,
+    pub last_unanswered_delay_req: Option<Arc<ParsedPacket>>,
+    // Delay_Resps the host sent to ports never seen sending Delay_Reqs
+    pub unsolicited_delay_resp_count: u32,
+    pub last_unsolicited_delay_resp: Option<Arc<ParsedPacket>>,
+    // (followed, predicted) when the host follows a transmitter of another
+    // grandmaster than the BMCA predicts for its domain
+    pub wrong_transmitter: Option<(ClockIdentity, ClockIdentity)>,
+    // Protocol checks run on every message the host sent
+    pub conformance: Conformance,
 }
 
 impl PtpHost {
@@ -406,8 +627,13 @@ impl PtpHost {
             clock_identity,
             ip_addresses: HashMap::new(),
             interfaces: HashSet::new(),
+            mac_addresses: BTreeSet::new(),
+            pdelay_peers: BTreeSet::new(),
+            cmlds_peers: BTreeSet::new(),
+            cmlds_message_count: 0,
             vlan_id: None,
             domain_number: None,
+            sdo_id: None,
             last_seen: SystemTime::now(),
 
             announce_count: 0,
@@ -425,22 +651,106 @@ impl PtpHost {
 
             state: PtpHostState::Listening,
             last_version: None,
+            last_minor_version: None,
             last_correction_field: None,
             packet_history: BoundedVec::new(1000), // Default max history
+            history_enabled: true,
+            observed_spans: VecDeque::new(),
+            first_seen: None,
+            session_first_seen: None,
+            instances: BTreeSet::new(),
+            length_issue_count: 0,
+            last_length_issue: None,
+            truncated_count: 0,
+            path: PathEvidence::default(),
+            residence: ResidenceTimes::default(),
+            sync_drift: SyncDrift::default(),
+            sent_counts: WindowedCounts::default(),
+            sync_sampler: SyncSampler::default(),
+            quality_trend: QualityTrend::default(),
+            announce_continuity: AnnounceContinuity::default(),
+            multicast_ttls: BTreeSet::new(),
+            hop_distance: HopDistance::default(),
+            tags: Vec::new(),
+            address_issues: BTreeMap::new(),
+            udp_ports: BTreeSet::new(),
+            port_usage: PortUsage::default(),
+            boundary_alerted: false,
+            event_pcp: None,
+            event_pcp_violations: 0,
+            path_skews: BTreeMap::new(),
+            latest_packets: HashMap::new(),
+            bursts: BurstDetector::default(),
+            delay_req_cadence: DelayReqCadence::default(),
+            arrival_paths: ArrivalPaths::default(),
+            description_query: DescriptionQuery::default(),
+            role_evidence: RoleEvidence::default(),
+            advertised_gm: AdvertisedGm::default(),
+            unicast_teardown_count: 0,
+            last_unicast_teardown: None,
+            timescale_issues: BTreeSet::new(),
+            era: None,
+            answered_by: None,
+            delay_req_destination: None,
+            unanswered_delay_req_count: 0,
+            last_unanswered_delay_req: None,
+            unsolicited_delay_resp_count: 0,
+            last_unsolicited_delay_resp: None,
+            wrong_transmitter: None,
+            conformance: Conformance::default(),
+        }
+    }
+
+    /// PTP instance (sdoId, domain number) the host was last seen in. Hosts are
+    /// only compared with each other within the same instance.
+    pub fn instance(&self) -> Option<(u16, u8)> {
+        Some((self.sdo_id?, self.domain_number?))
+    }
+
+    /// Peers of a peer delay exchange, CMLDS or per domain depending on the message
+    fn peers_mut(&mut self, header: &PtpHeader) -> &mut BTreeSet<ClockIdentity> {
+        if header.is_cmlds() {
+            &mut self.cmlds_peers
+        } else {
+            &mut self.pdelay_peers
         }
     }
 
     fn update_from_ptp_header(&mut self, header: &PtpHeader) {
-        self.domain_number = Some(header.domain_number);
+        // CMLDS link delay messages are shared by all gPTP domains on the link
+        if !header.is_cmlds() {
+            self.domain_number = Some(header.domain_number);
+            self.sdo_id = Some(header.sdo_id);
+            self.instances.insert((header.sdo_id, header.domain_number));
+        } else {
+            self.cmlds_message_count += 1;
+        }
         self.last_version = Some(header.version);
+        self.last_minor_version = Some(header.minor_version);
         self.last_correction_field = Some(header.correction_field);
         self.last_seen = SystemTime::now();
     }
 
+    /// Instance, identity and minor version, to find instances mixing minor versions
+    fn minor_version_entry(&self) -> Option<((u16, u8), ClockIdentity, u8)> {
+        Some((
+            (self.sdo_id?, self.domain_number?),
+            self.clock_identity,
+            self.last_minor_version?,
+        ))
+    }
+
     pub fn get_vendor_name(&self) -> Option<&'static str> {
         self.clock_identity.extract_vendor_name()
     }
 
+    /// Whether the host sends PTP to other UDP ports than 319 and 320
+    pub fn uses_nonstandard_ports(&self) -> bool {
+        self.udp_ports
+            .iter()
+            .any(|&port| !crate::source::is_standard_ptp_port(port))
+    }
+
     pub fn is_transmitter(&self) -> bool {
         matches!(self.state, PtpHostState::TimeTransmitter(_))
     }
@@ -449,9 +759,8 @@ impl PtpHost {
         matches!(self.state, PtpHostState::TimeReceiver(_))
     }
 
-    pub fn time_since_last_seen(&self, reference_time: Option<SystemTime>) -> Duration {
-        let reference = reference_time.unwrap_or_else(SystemTime::now);
-        reference.duration_since(self.last_seen).unwrap_or_default()
+    pub fn time_since_last_seen(&self, clock: Clock) -> Duration {
+        clock.since(self.last_seen)
     }
 
     pub fn add_ip_address(&mut self, ip: IpAddr, vlan_id: Option<u16>, interface: String) {
@@ -494,6 +803,19 @@ impl PtpHost {
         result
     }
 
+    /// Sites of the remote agents that saw the host, from its interface names
+    pub fn get_sites(&self) -> Vec<&str> {
+        let mut sites: Vec<&str> = self
+            .interfaces
+            .iter()
+            .chain(self.ip_addresses.values().flatten())
+            .filter_map(|interface| crate::remote::site_of(interface))
+            .collect();
+        sites.sort_unstable();
+        sites.dedup();
+        sites
+    }
+
     /// Returns the number of interfaces the host was seen on
     pub fn get_interface_count(&self) -> usize {
         let mut interface_names = std::collections::HashSet::new();
@@ -555,8 +877,212 @@ impl PtpHost {
         self.ip_addresses.keys().any(|ip| local_ips.contains(ip))
     }
 
-    pub fn add_packet(&mut self, packet: Rc<ParsedPacket>) {
-        self.packet_history.push(packet);
+    pub fn add_packet(&mut self, packet: Arc<ParsedPacket>) {
+        if self.history_enabled {
+            self.packet_history.push(packet);
+        }
+    }
+
+    /// Count a message sent by the host whose body is not looked at
+    fn count_sent(&mut self, message_type: PtpMessageType) {
+        let count = match message_type {
+            PtpMessageType::Announce => &mut self.announce_count,
+            PtpMessageType::Sync => &mut self.sync_count,
+            PtpMessageType::FollowUp => &mut self.follow_up_count,
+            PtpMessageType::DelayReq => &mut self.delay_req_count,
+            PtpMessageType::DelayResp => &mut self.delay_resp_count,
+            PtpMessageType::PDelayReq => &mut self.pdelay_req_count,
+            PtpMessageType::PDelayResp => &mut self.pdelay_resp_count,
+            PtpMessageType::PDelayRespFollowUp => &mut self.pdelay_resp_follow_up_count,
+            PtpMessageType::Signaling => &mut self.signaling_message_count,
+            PtpMessageType::Management => &mut self.management_message_count,
+        };
+        *count += 1;
+    }
+
+    /// Enable or disable packet history retention, dropping the history when disabled
+    pub fn set_history_enabled(&mut self, enabled: bool) {
+        self.history_enabled = enabled;
+        if !enabled {
+            self.packet_history.clear();
+        }
+    }
+
+    /// Infer the era of an origin timestamp the host sent, returning its
+    /// offset from the capture clock
+    fn record_era(&mut self, timestamp: &PtpTimestamp, at: SystemTime) -> Option<i64> {
+        let estimate = EraEstimate::infer(timestamp, at)?;
+        self.era = Some(estimate);
+        Some(estimate.offset)
+    }
+
+    /// Note a delay exchange of a receiver, which keeps its selected transmitter current
+    fn record_delay_exchange(&mut self, timestamp: SystemTime) {
+        if let PtpHostState::TimeReceiver(state) = &mut self.state {
+            state.last_delay_exchange = Some(timestamp);
+        }
+    }
+
+    /// Apply a message to the host state, logging transitions between states
+    fn update_state(&mut self, update: impl FnOnce(&mut PtpHostState)) {
+        let before = self.state.short_string();
+        update(&mut self.state);
+        let after = self.state.short_string();
+        if before != after {
+            tracing::info!(
+                clock_identity = %self.clock_identity,
+                from = before,
+                to = after,
+                "Host state changed"
+            );
+        }
+    }
+
+    /// Time from the first to the last packet of the host in this session
+    pub fn observed_duration(&self) -> Duration {
+        self.session_first_seen
+            .and_then(|first| self.last_seen.duration_since(first).ok())
+            .unwrap_or_default()
+    }
+
+    /// Extend the current observed span, or start a new one if the host was
+    /// silent for longer than OBSERVATION_GAP
+    pub fn record_observation(&mut self, timestamp: SystemTime) {
+        self.first_seen.get_or_insert(timestamp);
+        self.session_first_seen.get_or_insert(timestamp);
+
+        if let Some(last) = self.observed_spans.back_mut()
+            && timestamp.duration_since(last.1).unwrap_or_default() <= OBSERVATION_GAP
+        {
+            last.1 = last.1.max(timestamp);
+            return;
+        }
+
+        self.observed_spans.push_back((timestamp, timestamp));
+        if self.observed_spans.len() > MAX_OBSERVED_SPANS {
+            self.observed_spans.pop_front();
+        }
+    }
+
+    /// Only takes part in peer delay measurement, e.g. an AVB bridge that
+    /// never announces
+    pub fn is_p2p_peer(&self) -> bool {
+        matches!(self.state, PtpHostState::Listening)
+            && (self.pdelay_req_count > 0
+                || !self.pdelay_peers.is_empty()
+                || !self.cmlds_peers.is_empty())
+    }
+
+    /// Role for display, the state except that P2P peers are told apart
+    /// from hosts that are just listening
+    pub fn role(&self) -> String {
+        if self.is_p2p_peer() {
+            "P2P Peer".to_string()
+        } else {
+            self.state.to_string()
+        }
+    }
+
+    pub fn role_short_string(&self) -> &'static str {
+        if self.is_p2p_peer() {
+            "P2P"
+        } else {
+            self.state.short_string()
+        }
+    }
+
+    /// Hash of what the host table sorts, filters and builds its tree on,
+    /// but the counters and timestamps that change with every packet. The
+    /// table order only has to be rebuilt when it changes.
+    pub fn order_fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        match &self.state {
+            PtpHostState::TimeTransmitter(state) => (
+                0,
+                state.priority1,
+                state.clock_class.map(|class| class.class()),
+                state.is_bmca_winner,
+            )
+                .hash(&mut hasher),
+            PtpHostState::TimeReceiver(state) => {
+                (1, state.selected_transmitter_identity).hash(&mut hasher)
+            }
+            PtpHostState::Listening => (2, self.is_p2p_peer()).hash(&mut hasher),
+        }
+        (self.domain_number, self.sdo_id, self.vlan_id).hash(&mut hasher);
+        self.role_evidence
+            .role()
+            .map(|role| role as u8)
+            .hash(&mut hasher);
+        self.hop_distance.current().hash(&mut hasher);
+        self.delay_mechanism()
+            .map(|mechanism| mechanism as u8)
+            .hash(&mut hasher);
+        self.conformance.score().hash(&mut hasher);
+        self.get_interface_names().hash(&mut hasher);
+        (self.get_primary_ip(), self.ip_addresses.len()).hash(&mut hasher);
+        self.tags.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Delay mechanism in use, None until the host sent a delay request
+    pub fn delay_mechanism(&self) -> Option<DelayMechanism> {
+        match (self.delay_req_count > 0, self.pdelay_req_count > 0) {
+            (true, true) => Some(DelayMechanism::Mixed),
+            (true, false) => Some(DelayMechanism::E2E),
+            (false, true) => Some(DelayMechanism::P2P),
+            (false, false) => None,
+        }
+    }
+
+    /// Problem with the observed delay mechanism, if any
+    pub fn delay_mechanism_warning(&self) -> Option<&'static str> {
+        match self.delay_mechanism()? {
+            DelayMechanism::Mixed => Some("both Delay_Req and PDelay_Req seen"),
+            DelayMechanism::E2E if self.sdo_id.is_some_and(|id| id >> 8 == SDO_ID_GPTP >> 8) => {
+                Some("gPTP requires peer-to-peer delay")
+            }
+            _ => None,
+        }
+    }
+
+    /// Most recent example of a Delay_Req without response or a Delay_Resp
+    /// without request
+    pub fn delay_xref_example(&self) -> Option<&Arc<ParsedPacket>> {
+        [
+            &self.last_unanswered_delay_req,
+            &self.last_unsolicited_delay_resp,
+        ]
+        .into_iter()
+        .flatten()
+        .max_by_key(|packet| packet.raw.timestamp)
+    }
+
+    /// Short flags shown in the host table
+    pub fn flags(&self) -> String {
+        let mut flags = Vec::new();
+        if !self.history_enabled {
+            flags.push("NH");
+        }
+        if self.delay_mechanism_warning().is_some() {
+            flags.push("DM");
+        }
+        if self.length_issue_count > 0 {
+            flags.push("LN");
+        }
+        if self.hop_distance.changed() {
+            flags.push("HC");
+        }
+        if !self.address_issues.is_empty() {
+            flags.push("AD");
+        }
+        if self.unanswered_delay_req_count > 0 || self.unsolicited_delay_resp_count > 0 {
+            flags.push("DX");
+        }
+        if self.event_pcp_violations > 0 {
+            flags.push("PC");
+        }
+        flags.join(" ")
     }
 
     pub fn set_max_packet_history(&mut self, max_history: usize) {
@@ -567,17 +1093,41 @@ impl PtpHost {
         }
     }
 
-    pub fn get_packet_history(&self) -> Vec<ParsedPacket> {
-        self.packet_history
-            .items
-            .iter()
-            .map(|p| (**p).clone())
-            .collect()
+    pub fn get_packet_history(&self) -> Vec<Arc<ParsedPacket>> {
+        self.packet_history.items.iter().cloned().collect()
     }
 
     pub fn clear_packet_history(&mut self) {
         self.packet_history.clear();
     }
+
+    /// Interfaces the packets in the history arrived on, in name order
+    pub fn get_packet_interfaces(&self) -> Vec<String> {
+        let interfaces: BTreeSet<&String> = self
+            .packet_history
+            .items
+            .iter()
+            .map(|packet| &packet.raw.interface_name)
+            .collect();
+        interfaces.into_iter().cloned().collect()
+    }
+
+    /// The last `count` Announce messages still in the packet history, oldest first
+    pub fn recent_announces(&self, count: usize) -> Vec<&AnnounceMessage> {
+        let mut announces: Vec<_> = self
+            .packet_history
+            .items
+            .iter()
+            .rev()
+            .filter_map(|packet| match &packet.ptp {
+                PtpMessage::Announce(announce) => Some(announce),
+                _ => None,
+            })
+            .take(count)
+            .collect();
+        announces.reverse();
+        announces
+    }
 }
 
 #[cfg(test)]
@@ -628,6 +1178,235 @@ mod tests {
         assert!(host.has_multiple_ips());
     }
 
+    #[test]
+    fn test_host_instance() {
+        let mut header_data = [
+            0x12, 0x02, 0x00, 0x36, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1B, 0x19, 0xFF, 0xFE, 0x12, 0x34, 0x56,
+            0x00, 0x01, 0x00, 0x64, 0x00, 0x00,
+        ];
+        let mut host = PtpHost::new(ClockIdentity::default());
+        assert_eq!(host.instance(), None);
+
+        // gPTP domain 1
+        host.update_from_ptp_header(&PtpHeader::try_from(&header_data[..]).unwrap());
+        assert_eq!(host.instance(), Some((0x100, 1)));
+
+        // CMLDS peer delay does not move the host to another instance
+        header_data[0] = 0x22;
+        header_data[4] = 0x00;
+        host.update_from_ptp_header(&PtpHeader::try_from(&header_data[..]).unwrap());
+        assert_eq!(host.instance(), Some((0x100, 1)));
+        assert_eq!(host.instances.len(), 1);
+    }
+
+    #[test]
+    fn test_delay_mechanism() {
+        let mut host = PtpHost::new(ClockIdentity::default());
+        assert_eq!(host.delay_mechanism(), None);
+
+        host.delay_req_count = 3;
+        assert_eq!(host.delay_mechanism(), Some(DelayMechanism::E2E));
+        assert_eq!(host.delay_mechanism_warning(), None);
+
+        // E2E is not allowed in gPTP
+        host.sdo_id = Some(SDO_ID_GPTP);
+        assert!(host.delay_mechanism_warning().is_some());
+        assert_eq!(host.flags(), "DM");
+
+        host.sdo_id = Some(0);
+        host.pdelay_req_count = 1;
+        assert_eq!(host.delay_mechanism(), Some(DelayMechanism::Mixed));
+        assert!(host.delay_mechanism_warning().is_some());
+    }
+
+    #[test]
+    fn test_stats_store_on_removal() {
+        let mut tracker =
+            PtpTracker::new(Box::new(crate::source::PcapSource::new(Vec::new()))).unwrap();
+        tracker.set_stats_store(PathBuf::new(), StatsStore::default());
+        for (id, seconds) in [(1, 10), (2, 20), (3, 30)] {
+            let mut host = PtpHost::new(ClockIdentity {
+                clock_id: [0, 0, 0, 0, 0, 0, 0, id],
+            });
+            host.last_seen = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+            host.sync_count = id as u32 * 100;
+            tracker.hosts.insert(host.clock_identity, host);
+        }
+        let store = |tracker: &PtpTracker| tracker.stats_store.as_ref().unwrap().1.clone();
+
+        // The evicted host's counters are kept for when it comes back
+        tracker.set_max_hosts(2);
+        let evicted = PtpHost::new(ClockIdentity {
+            clock_id: [0, 0, 0, 0, 0, 0, 0, 1],
+        });
+        assert_eq!(store(&tracker).get(&evicted).unwrap().sync, 100);
+
+        // Cleared hosts start from zero
+        let stored = PtpHost::new(ClockIdentity {
+            clock_id: [0, 0, 0, 0, 0, 0, 0, 2],
+        });
+        tracker.stats_store.as_mut().unwrap().1.update(&stored);
+        tracker.clear_hosts();
+        assert!(store(&tracker).get(&stored).is_none());
+        assert!(store(&tracker).get(&evicted).is_some());
+    }
+
+    #[test]
+    fn test_host_eviction() {
+        let mut tracker =
+            PtpTracker::new(Box::new(crate::source::PcapSource::new(Vec::new()))).unwrap();
+        let id = |id: u8| ClockIdentity {
+            clock_id: [0, 0, 0, 0, 0, 0, 0, id],
+        };
+        let add = |tracker: &mut PtpTracker, host_id: u8, seconds: u64| {
+            let mut host = PtpHost::new(id(host_id));
+            host.last_seen = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+            tracker.hosts.insert(host.clock_identity, host);
+        };
+        for host_id in 1..=33 {
+            add(&mut tracker, host_id, host_id as u64);
+        }
+
+        // The least recently seen host goes, the next one waits in the queue
+        tracker.set_max_hosts(32);
+        assert!(tracker.get_host(&id(1)).is_none());
+        assert_eq!(tracker.eviction_queue.len(), 1);
+
+        // Seen again since it was queued, it is no longer the oldest
+        tracker.hosts.get_mut(&id(2)).unwrap().last_seen += Duration::from_secs(100);
+        add(&mut tracker, 34, 34);
+        tracker.enforce_host_limit();
+        assert!(tracker.get_host(&id(2)).is_some());
+        assert!(tracker.get_host(&id(3)).is_none());
+        assert_eq!(tracker.hosts.len(), 32);
+        assert_eq!(tracker.get_evicted_host_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_generation_on_delay_resp() {
+        let mut tracker =
+            PtpTracker::new(Box::new(crate::source::PcapSource::new(Vec::new()))).unwrap();
+        // A Delay_Req from clock 1, or the Delay_Resp of clock 0xaa answering it
+        let packet = |response: bool| {
+            let mut payload = [0u8; 54];
+            payload[0] = if response { 0x09 } else { 0x01 };
+            payload[1] = 0x02;
+            payload[2..4].copy_from_slice(&54u16.to_be_bytes());
+            payload[29] = 1;
+            if response {
+                payload[20..28].copy_from_slice(&[0xaa; 8]);
+                payload[44..52].copy_from_slice(&[1; 8]);
+                payload[53] = 1;
+            } else {
+                payload[20..28].copy_from_slice(&[1; 8]);
+            }
+            Arc::new(RawPacket::from_ptp_payload(
+                &payload,
+                "eth0",
+                SystemTime::UNIX_EPOCH,
+            ))
+        };
+        let receiver = ClockIdentity { clock_id: [1; 8] };
+
+        tracker.handle_raw_packet(packet(false)).await;
+        tracker.handle_raw_packet(packet(true)).await;
+        // Both hosts are known now, a further response only changes the
+        // receiver: it is one of the transmitter's now
+        tracker.hosts.get_mut(&receiver).unwrap().state = PtpHostState::Listening;
+        let generation = tracker.hosts_generation;
+        tracker.handle_raw_packet(packet(true)).await;
+        assert!(matches!(
+            tracker.get_host(&receiver).unwrap().state,
+            PtpHostState::TimeReceiver(_)
+        ));
+        assert!(tracker.hosts_generation > generation);
+
+        // Nothing the table orders by changed
+        let generation = tracker.hosts_generation;
+        tracker.handle_raw_packet(packet(true)).await;
+        assert_eq!(tracker.hosts_generation, generation);
+    }
+
+    #[test]
+    fn test_order_fingerprint() {
+        let mut host = PtpHost::new(ClockIdentity::default());
+        host.add_interface("eth0".to_string());
+        let fingerprint = host.order_fingerprint();
+
+        // Counters and timestamps change with every packet, the order doesn't
+        host.total_messages_sent_count += 10;
+        host.last_seen += Duration::from_secs(1);
+        assert_eq!(host.order_fingerprint(), fingerprint);
+
+        host.delay_req_count = 1;
+        assert_ne!(host.order_fingerprint(), fingerprint);
+        let fingerprint = host.order_fingerprint();
+        host.add_interface("eth1".to_string());
+        assert_ne!(host.order_fingerprint(), fingerprint);
+        let fingerprint = host.order_fingerprint();
+        host.state = PtpHostState::TimeTransmitter(PtpHostStateTimeTransmitter::default());
+        assert_ne!(host.order_fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn test_p2p_peer_role() {
+        let mut host = PtpHost::new(ClockIdentity::default());
+        assert!(!host.is_p2p_peer());
+        assert_eq!(host.role_short_string(), "L");
+
+        // A bridge that only answers peer delay requests
+        host.pdelay_peers.insert(ClockIdentity {
+            clock_id: [0, 1, 2, 0xff, 0xfe, 3, 4, 5],
+        });
+        assert!(host.is_p2p_peer());
+        assert_eq!(host.role(), "P2P Peer");
+        assert_eq!(host.role_short_string(), "P2P");
+
+        // So does a bridge only taking part in the CMLDS
+        let mut bridge = PtpHost::new(ClockIdentity::default());
+        bridge.cmlds_peers.insert(ClockIdentity {
+            clock_id: [0, 1, 2, 0xff, 0xfe, 3, 4, 5],
+        });
+        assert!(bridge.is_p2p_peer());
+
+        // Announcing makes it a time transmitter like any other
+        host.state = PtpHostState::TimeTransmitter(PtpHostStateTimeTransmitter::default());
+        assert!(!host.is_p2p_peer());
+        assert_eq!(host.role_short_string(), "TT");
+    }
+
+    #[test]
+    fn test_observed_spans() {
+        let mut host = PtpHost::new(ClockIdentity::default());
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+
+        host.record_observation(start);
+        host.record_observation(start + Duration::from_secs(1));
+        host.record_observation(start + Duration::from_secs(5));
+        assert_eq!(host.observed_spans.len(), 1);
+
+        // Silent for longer than the gap, so a new span starts
+        host.record_observation(start + Duration::from_secs(60));
+        host.record_observation(start + Duration::from_secs(61));
+        assert_eq!(
+            host.observed_spans,
+            [
+                (start, start + Duration::from_secs(5)),
+                (
+                    start + Duration::from_secs(60),
+                    start + Duration::from_secs(61)
+                ),
+            ]
+        );
+
+        // Counted from the first packet of this session, not a restored one
+        host.first_seen = Some(SystemTime::UNIX_EPOCH);
+        host.last_seen = start + Duration::from_secs(61);
+        assert_eq!(host.session_first_seen, Some(start));
+        assert_eq!(host.observed_duration(), Duration::from_secs(61));
+    }
+
     #[test]
     fn test_interface_collection() {
         use std::net::{IpAddr, Ipv4Addr};
@@ -730,122 +1509,1481 @@ mod tests {
         host2.add_interface("eth0".to_string());
         assert!(host2.has_multiple_interfaces());
     }
+
+    #[test]
+    fn test_transmitter_confidence_decay() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut state = PtpHostStateTimeReceiver {
+            selected_transmitter_identity: Some(ClockIdentity::default()),
+            selected_transmitter_confidence: 1.0,
+            last_delay_exchange: Some(start),
+            ..Default::default()
+        };
+
+        // Within the grace period nothing changes
+        assert!(!state.decay_confidence(start + CONFIDENCE_GRACE, true));
+        assert_eq!(state.selected_transmitter_confidence, 1.0);
+
+        // Halfway to the timeout, half the confidence is left
+        assert!(!state.decay_confidence(start + Duration::from_secs(35), true));
+        assert_eq!(state.selected_transmitter_confidence, 0.5);
+
+        // Decay never raises the confidence, only new evidence does
+        assert!(!state.decay_confidence(start + Duration::from_secs(20), true));
+        assert_eq!(state.selected_transmitter_confidence, 0.5);
+
+        // Delay exchanges stopped
+        assert!(state.decay_confidence(start + ASSOCIATION_TIMEOUT, true));
+        assert_eq!(state.selected_transmitter_identity, None);
+        assert_eq!(state.selected_transmitter_confidence, 0.0);
+
+        // The transmitter went away while the receiver is still exchanging
+        let mut state = PtpHostStateTimeReceiver {
+            selected_transmitter_identity: Some(ClockIdentity::default()),
+            selected_transmitter_confidence: 1.0,
+            last_delay_exchange: Some(start),
+            ..Default::default()
+        };
+        assert!(state.decay_confidence(start, false));
+        assert_eq!(state.selected_transmitter_identity, None);
+    }
+}
+
+/// Elected grandmaster of a domain compared with the one the config expects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GmDesign {
+    AsDesigned,
+    Deviation {
+        expected: ClockIdentity,
+        elected: ClockIdentity,
+    },
 }
 
 pub struct PtpTracker {
     hosts: HashMap<ClockIdentity, PtpHost>,
     last_packet: Instant,
-    pub raw_socket_receiver: crate::source::RawSocketReceiver,
+    // Capture timestamp of the newest packet handled
+    last_packet_time: Option<SystemTime>,
+    // Packets taken from the capture, for the adaptive update interval
+    received_packets: u64,
+    // Set when a poll left packets waiting in the capture
+    backlogged: bool,
+    // Time spent in capture, parse and tracking since the last update cycle
+    stage_times: StageTimes,
+    pub packet_source: Box<dyn crate::source::PacketSource>,
     // Track recent sync/follow-up senders per domain for transmitter-receiver correlation
-    recent_sync_senders: HashMap<u8, Vec<(ClockIdentity, Instant)>>,
-    // Track interfaces for determining inbound interface of packets
-    interfaces: Vec<(String, Option<std::net::Ipv4Addr>)>,
+    recent_sync_senders: HashMap<(u16, u8), Vec<(ClockIdentity, Instant)>>,
+    // Capture time of the last Sync of each instance, for telling receiver
+    // trouble from syncs that stopped
+    last_instance_sync: HashMap<(u16, u8), SystemTime>,
+    // Raw packets from all hosts, kept for a short time window for alert captures
+    recent_packets: VecDeque<Arc<RawPacket>>,
+    alert_capture: Option<AlertCapture>,
+    // Rolling pcap files of all captured traffic, with --write-ring
+    pcap_ring: Option<PcapRing>,
+    alerts: BoundedVec<Alert>,
+    // Last BMCA winner per domain, used to detect PTT changes
+    bmca_winners: HashMap<(u16, u8), ClockIdentity>,
+    // When the current BMCA winner of each domain took over
+    bmca_winners_since: HashMap<(u16, u8), SystemTime>,
+    // Grandmaster each domain number should elect, from the config
+    expected_grandmasters: HashMap<u8, ClockIdentity>,
+    // Instances electing another grandmaster than expected, with that grandmaster
+    gm_deviations: HashMap<(u16, u8), ClockIdentity>,
+    // Backup of the expected grandmaster by domain number, from the config
+    backup_grandmasters: HashMap<u8, ClockIdentity>,
+    // Phase between primary and backup grandmaster by domain number
+    gm_pair_phases: HashMap<u8, PairPhase>,
+    gm_pair_drift_threshold: Duration,
+    // currentUtcOffset announced by the PTT of each domain, for leap second and misconfiguration alerts
+    utc_offsets: UtcOffsets,
+    // Datasets each GM identity announces into each instance, for conflicts between them
+    shared_gms: SharedGrandmasters,
+    // Era offsets of the time transmitters of each instance, for transmitters an era apart
+    domain_eras: DomainEras,
+    // Instances already reported for mixing PTP minor versions, until they no longer do
+    mixed_minor_versions: HashSet<(u16, u8)>,
+    // Active members of the bonds captured on with --members
+    bond_watch: BondWatch,
+    // Last Announce each clock sent as its own grandmaster, to spot forwarded copies
+    own_announces: OwnAnnounces,
+    // Gaps in the sequence ids of Sync and Announce, for the loss estimate
+    sequence_loss: SequenceLoss,
+    // Only present when active features were enabled with --active
+    transmitter: Option<FrameTransmitter>,
+    // Bumped when hosts are added or removed, or change what the host table
+    // is ordered by
+    hosts_generation: u64,
+    deduplicator: PacketDeduplicator,
+    // Least recently seen hosts are evicted beyond this many hosts (0 = unlimited)
+    max_hosts: usize,
+    evicted_host_count: u64,
+    // Next hosts to evict, oldest first, as last seen when the queue was
+    // filled; one seen since then is skipped
+    eviction_queue: VecDeque<(SystemTime, ClockIdentity)>,
+    // Hosts dropped by expiry or cleared, for the session summary
+    removed_host_count: u64,
+    // Packets whose PTP message could not be parsed
+    rejected_packets: u64,
+    // Packets parsed from a body the capture cut short
+    truncated_packets: u64,
+    // Hosts left out of the table, statistics and alerts, and the alerts
+    // left out because of it
+    mute_list: MuteList,
+    muted_alerts: u64,
+    // Commands and webhooks run for alerts
+    alert_hooks: AlertHooks,
+    // Alerts raised so far by kind, not bounded like the alert log
+    alert_counts: HashMap<AlertKind, u64>,
+    // Alert captures written, for the session summary
+    written_files: Vec<PathBuf>,
+    // Hosts not seen for this long are removed
+    host_expiry: Option<Duration>,
+    // Packet history depth for new hosts
+    max_packet_history: usize,
+    // Subnets of the capture interfaces, empty for pcap files
+    local_subnets: Vec<(String, IpNetwork)>,
+    // Site policy for routed hops of multicast PTP, None disables the check
+    max_multicast_hops: Option<u8>,
+    // 802.1p priority event messages should be tagged with, per site policy
+    expected_event_pcp: Option<u8>,
+    // BMCA rules used to predict the PTT
+    bmca_profile: BmcaProfile,
+    local_priorities: HashMap<String, u8>,
+    // Operator-defined host tags by clock identity
+    host_tags: HashMap<String, Vec<String>>,
+    // Counters persisted across restarts, only with --state-file
+    stats_store: Option<(PathBuf, StatsStore)>,
+    last_stats_save: Instant,
+    failover_drill: Option<FailoverDrill>,
+    // Delay_Reqs and Delay_Resps matched with each other
+    delay_xref: DelayCrossReference,
+    // Sequence id of the last management request sent with --active
+    management_sequence_id: u16,
+    // Copies of Syncs arriving on several interfaces, for redundant path skew
+    sync_arrivals: SyncArrivals,
+    path_skew_threshold: Duration,
+    // Microbursts per capture interface, counted before de-duplication
+    interface_bursts: BTreeMap<String, BurstDetector>,
+    // --lite: only host states and counters, no packet history or per-packet analysis
+    lite: bool,
+    // Only every Nth Sync and Follow_Up of a stream, and anomalies, go into the history
+    sync_sample_rate: u32,
+    // Reference receiver state machine, only with --simulate-receiver
+    simulated_receiver: Option<SimulatedReceiver>,
 }
 
+/// Upper bound for the recent packet buffer, regardless of the time window
+const MAX_RECENT_PACKETS: usize = 100_000;
+/// Assumed size of a captured frame for memory estimates
+const ESTIMATED_FRAME_SIZE: usize = 128;
+/// How often counters are written to the state file
+const STATS_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+/// Syncs of an instance count as still coming within this time of the last
+const SYNCS_FLOWING: Duration = Duration::from_secs(4);
+
 impl PtpTracker {
-    pub fn new(raw_socket_receiver: crate::source::RawSocketReceiver) -> Result<Self> {
-        let interfaces = raw_socket_receiver.get_interfaces().to_vec();
+    pub fn new(packet_source: Box<dyn crate::source::PacketSource>) -> Result<Self> {
+        let interfaces = packet_source.interfaces();
+        let local_subnets = pnet::datalink::interfaces()
+            .into_iter()
+            .filter(|iface| interfaces.iter().any(|(name, _)| *name == iface.name))
+            .flat_map(|iface| {
+                iface
+                    .ips
+                    .iter()
+                    .map(|ip| (iface.name.clone(), *ip))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
         Ok(Self {
             hosts: HashMap::new(),
             last_packet: Instant::now(),
-            raw_socket_receiver,
+            last_packet_time: None,
+            received_packets: 0,
+            backlogged: false,
+            stage_times: StageTimes::default(),
+            packet_source,
             recent_sync_senders: HashMap::new(),
-            interfaces,
+            last_instance_sync: HashMap::new(),
+            recent_packets: VecDeque::new(),
+            alert_capture: None,
+            pcap_ring: None,
+            alerts: BoundedVec::new(500),
+            bmca_winners: HashMap::new(),
+            utc_offsets: UtcOffsets::default(),
+            shared_gms: SharedGrandmasters::default(),
+            domain_eras: DomainEras::default(),
+            mixed_minor_versions: HashSet::new(),
+            bond_watch: BondWatch::default(),
+            own_announces: OwnAnnounces::new(),
+            bmca_winners_since: HashMap::new(),
+            expected_grandmasters: HashMap::new(),
+            gm_deviations: HashMap::new(),
+            backup_grandmasters: HashMap::new(),
+            gm_pair_phases: HashMap::new(),
+            gm_pair_drift_threshold: DEFAULT_DRIFT_THRESHOLD,
+            sequence_loss: SequenceLoss::default(),
+            transmitter: None,
+            hosts_generation: 0,
+            deduplicator: PacketDeduplicator::new(true),
+            max_hosts: 0,
+            evicted_host_count: 0,
+            eviction_queue: VecDeque::new(),
+            removed_host_count: 0,
+            rejected_packets: 0,
+            truncated_packets: 0,
+            mute_list: MuteList::default(),
+            muted_alerts: 0,
+            alert_hooks: AlertHooks::default(),
+            alert_counts: HashMap::new(),
+            written_files: Vec::new(),
+            host_expiry: None,
+            max_packet_history: 1000,
+            local_subnets,
+            max_multicast_hops: None,
+            expected_event_pcp: None,
+            bmca_profile: BmcaProfile::Auto,
+            local_priorities: HashMap::new(),
+            host_tags: HashMap::new(),
+            stats_store: None,
+            last_stats_save: Instant::now(),
+            failover_drill: None,
+            delay_xref: DelayCrossReference::default(),
+            management_sequence_id: 0,
+            sync_arrivals: SyncArrivals::default(),
+            path_skew_threshold: DEFAULT_SKEW_THRESHOLD,
+            interface_bursts: BTreeMap::new(),
+            lite: false,
+            sync_sample_rate: 1,
+            simulated_receiver: None,
         })
     }
 
-    pub async fn scan_network(&mut self) {
-        self.process_ptp_messages().await;
-        self.cleanup_old_sync_senders();
-        self.run_bmca_election();
+    /// Store only every `rate`th Sync and Follow_Up of a stream in the packet
+    /// history, plus those after a sequence gap or with a large correction
+    pub fn set_sync_sample_rate(&mut self, rate: u32) {
+        self.sync_sample_rate = rate.max(1);
     }
 
-    async fn process_ptp_messages(&mut self) {
-        // Process packets from raw socket capture
-        for _ in 0..100 {
-            // Limit iterations to prevent blocking too long
-            match self.raw_socket_receiver.try_recv() {
-                Some(raw_packet) => {
-                    let raw_packet_arc = std::sync::Arc::new(raw_packet);
-                    self.handle_raw_packet(raw_packet_arc).await;
-                    self.last_packet = Instant::now();
-                }
-                None => {
-                    // No more packets available
-                    break;
-                }
+    pub fn get_sync_sample_rate(&self) -> u32 {
+        self.sync_sample_rate
+    }
+
+    /// Keep only host states and counters: no packet history, no copies of
+    /// the latest packets and none of the analyses that hold per-packet state
+    /// (path inference, residence time, redundant path skew)
+    pub fn set_lite(&mut self, lite: bool) {
+        self.lite = lite;
+        self.set_max_packet_history(self.max_packet_history);
+        if lite {
+            for host in self.hosts.values_mut() {
+                host.latest_packets.clear();
             }
         }
     }
 
-    async fn handle_raw_packet(&mut self, raw_packet: std::sync::Arc<crate::source::RawPacket>) {
-        let msg = match PtpMessage::try_from(raw_packet.ptp_payload.as_slice()) {
-            Ok(m) => m,
-            Err(_) => return, // Invalid message
-        };
+    pub fn is_lite(&self) -> bool {
+        self.lite
+    }
 
-        // Create packet info for recording
-        let packet = Rc::new(ParsedPacket {
-            ptp: msg,
-            raw: raw_packet.clone(),
-        });
+    /// Alert when multicast PTP crossed more routed hops than this
+    pub fn set_max_multicast_hops(&mut self, max_hops: Option<u8>) {
+        self.max_multicast_hops = max_hops;
+    }
 
-        let sending_host = self
-            .hosts
-            .entry(msg.header().source_port_identity.clock_identity)
-            .or_insert_with(|| PtpHost::new(msg.header().source_port_identity.clock_identity));
+    /// 802.1p priority VLAN tagged event messages are expected at, None
+    /// disables the check
+    pub fn set_expected_event_pcp(&mut self, pcp: Option<u8>) {
+        self.expected_event_pcp = pcp;
+    }
 
-        // Add IP address or interface depending on packet type
-        if let Some(source_addr) = raw_packet.source_addr {
-            // PTP over UDP - add IP address
-            sending_host.add_ip_address(
-                source_addr.ip(),
-                packet.raw.vlan_id,
-                packet.raw.interface_name.clone(),
-            );
-        } else {
-            // gPTP - add interface only
-            sending_host.add_interface(packet.raw.interface_name.clone());
+    pub fn get_expected_event_pcp(&self) -> Option<u8> {
+        self.expected_event_pcp
+    }
+
+    /// Alert when the Sync arrival skew between two interfaces moves this far
+    pub fn set_path_skew_threshold(&mut self, threshold: Duration) {
+        self.path_skew_threshold = threshold;
+    }
+
+    /// Run a simulated receiver on one PTP instance
+    pub fn set_simulated_receiver(&mut self, instance: (u16, u8)) {
+        self.simulated_receiver = Some(SimulatedReceiver::new(instance));
+    }
+
+    /// PTP instances a grandmaster announces into, with its dataset in each
+    pub fn get_gm_instances(
+        &self,
+        gm: &ClockIdentity,
+        now: SystemTime,
+    ) -> Vec<((u16, u8), AnnouncedDataset)> {
+        self.shared_gms.instances_of(gm, now)
+    }
+
+    pub fn get_simulated_receiver(&self) -> Option<&SimulatedReceiver> {
+        self.simulated_receiver.as_ref()
+    }
+
+    /// Keep per-host counters in a state file, continuing from what it contains
+    pub fn set_stats_store(&mut self, path: PathBuf, store: StatsStore) {
+        self.stats_store = Some((path, store));
+    }
+
+    /// Write the counters of all hosts to the state file, if there is one
+    pub fn save_stats(&mut self) -> Result<()> {
+        self.last_stats_save = Instant::now();
+        let Some((path, store)) = &mut self.stats_store else {
+            return Ok(());
+        };
+
+        for host in self.hosts.values() {
+            store.update(host);
         }
+        store.save(path)
+    }
 
-        sending_host.total_messages_sent_count += 1;
-        sending_host.update_from_ptp_header(msg.header());
-        // Update last_seen with packet timestamp
-        sending_host.last_seen = raw_packet.timestamp;
+    /// Remove hosts that have not been seen for the given time (None keeps them)
+    pub fn set_host_expiry(&mut self, host_expiry: Option<Duration>) {
+        self.host_expiry = host_expiry;
+    }
 
-        match msg {
-            PtpMessage::Announce(msg) => {
-                sending_host.announce_count += 1;
-                sending_host.state.update_from_announce(&msg);
-                sending_host.add_packet(packet.clone());
+    fn expire_hosts(&mut self) {
+        let Some(expiry) = self.host_expiry else {
+            return;
+        };
+
+        // In pcap mode, age is relative to the last packet in the file
+        let reference = self
+            .packet_source
+            .reference_time()
+            .unwrap_or_else(SystemTime::now);
+        let count = self.hosts.len();
+        let mut stats_store = self.stats_store.as_mut().map(|(_, store)| store);
+        self.hosts.retain(|_, host| {
+            let keep = reference.duration_since(host.last_seen).unwrap_or_default() <= expiry;
+            // Expired hosts keep their counters for when they come back
+            if !keep && let Some(store) = stats_store.as_mut() {
+                store.update(host);
             }
-            PtpMessage::Sync(msg) => {
-                sending_host.sync_count += 1;
-                sending_host.state.update_from_sync(&msg);
+            keep
+        });
 
-                // Record this as a recent sync sender for this domain
-                let domain_senders = self
-                    .recent_sync_senders
-                    .entry(msg.header.domain_number)
-                    .or_default();
+        if self.hosts.len() != count {
+            self.removed_host_count += (count - self.hosts.len()) as u64;
+            self.hosts_generation += 1;
+        }
+    }
 
-                let now = std::time::Instant::now();
-                if let Some(existing) = domain_senders
-                    .iter_mut()
-                    .find(|(id, _)| id == &msg.header.source_port_identity.clock_identity)
-                {
-                    existing.1 = now;
-                } else {
+    /// Limit the number of tracked hosts, evicting the least recently seen
+    /// ones. Protects against floods of random clock identities.
+    pub fn set_max_hosts(&mut self, max_hosts: usize) {
+        self.max_hosts = max_hosts;
+        self.eviction_queue.clear();
+        self.enforce_host_limit();
+    }
+
+    pub fn get_max_hosts(&self) -> usize {
+        self.max_hosts
+    }
+
+    /// Number of hosts evicted because the host limit was reached
+    pub fn get_evicted_host_count(&self) -> u64 {
+        self.evicted_host_count
+    }
+
+    fn enforce_host_limit(&mut self) {
+        if self.max_hosts == 0 || self.hosts.len() <= self.max_hosts {
+            return;
+        }
+
+        let excess = self.hosts.len() - self.max_hosts;
+        let mut evicted = 0;
+        while evicted < excess {
+            let Some((last_seen, clock_identity)) = self.eviction_queue.pop_front() else {
+                self.fill_eviction_queue(excess - evicted);
+                continue;
+            };
+            if self
+                .hosts
+                .get(&clock_identity)
+                .is_none_or(|host| host.last_seen != last_seen)
+            {
+                continue;
+            }
+            // Evicted hosts keep their counters for when they come back
+            if let Some(host) = self.hosts.remove(&clock_identity)
+                && let Some((_, store)) = self.stats_store.as_mut()
+            {
+                store.update(&host);
+            }
+            evicted += 1;
+        }
+        self.evicted_host_count += excess as u64;
+        self.hosts_generation += 1;
+    }
+
+    /// Queue the least recently seen hosts for eviction, a batch at a time:
+    /// during a flood of new clock identities every packet evicts a host, and
+    /// finding the oldest one takes a pass over all hosts
+    fn fill_eviction_queue(&mut self, needed: usize) {
+        let batch = needed.max(self.max_hosts / 16).min(self.hosts.len());
+        let mut by_last_seen: Vec<(SystemTime, ClockIdentity)> = self
+            .hosts
+            .values()
+            .map(|host| (host.last_seen, host.clock_identity))
+            .collect();
+        by_last_seen.select_nth_unstable(batch - 1);
+        by_last_seen.truncate(batch);
+        by_last_seen.sort_unstable();
+        self.eviction_queue = by_last_seen.into();
+    }
+
+    /// Rough estimate of the memory held by tracked hosts and packet buffers
+    pub fn estimate_memory_usage(&self) -> usize {
+        let packet_size = std::mem::size_of::<ParsedPacket>()
+            + std::mem::size_of::<RawPacket>()
+            + ESTIMATED_FRAME_SIZE;
+        let hosts: usize = self
+            .hosts
+            .values()
+            .map(|host| {
+                std::mem::size_of::<PtpHost>()
+                    + host.packet_history.len() * packet_size
+                    + host.observed_spans.len() * std::mem::size_of::<(SystemTime, SystemTime)>()
+                    + host.sent_counts.memory_usage()
+            })
+            .sum();
+
+        hosts + self.recent_packets.len() * packet_size
+    }
+
+    /// Unique packets held by the hosts and the recent packet buffer vs references to them
+    pub fn packet_memory(&self) -> PacketMemory {
+        let mut memory = PacketMemory::default();
+        for host in self.hosts.values() {
+            host.packet_history
+                .items
+                .iter()
+                .for_each(|p| memory.add_parsed(p));
+            host.latest_packets
+                .values()
+                .for_each(|p| memory.add_parsed(p));
+        }
+        self.recent_packets.iter().for_each(|p| memory.add_raw(p));
+        memory
+    }
+
+    pub fn is_dedup_enabled(&self) -> bool {
+        self.deduplicator.is_enabled()
+    }
+
+    pub fn set_dedup_enabled(&mut self, enabled: bool) {
+        self.deduplicator.set_enabled(enabled);
+    }
+
+    pub fn get_duplicate_count(&self) -> u64 {
+        self.deduplicator.get_duplicate_count()
+    }
+
+    /// Attach the transmitter used by active features
+    pub fn set_transmitter(&mut self, transmitter: FrameTransmitter) {
+        self.transmitter = Some(transmitter);
+    }
+
+    pub fn get_transmit_mode(&self) -> TransmitMode {
+        self.transmitter
+            .as_ref()
+            .map_or(TransmitMode::Passive, |t| t.mode())
+    }
+
+    /// Enable saving the traffic preceding each alert to a pcap file
+    pub fn set_alert_capture(&mut self, alert_capture: AlertCapture) {
+        self.alert_capture = Some(alert_capture);
+    }
+
+    /// Write all captured traffic to a ring of pcap files
+    pub fn set_pcap_ring(&mut self, pcap_ring: PcapRing) {
+        self.pcap_ring = Some(pcap_ring);
+    }
+
+    pub fn get_alerts(&self) -> &VecDeque<Alert> {
+        &self.alerts.items
+    }
+
+    fn record_recent_packet(&mut self, raw_packet: Arc<RawPacket>) {
+        let Some(window) = self.alert_capture.as_ref().map(|c| c.window) else {
+            return;
+        };
+
+        let newest = raw_packet.timestamp;
+        self.recent_packets.push_back(raw_packet);
+
+        while let Some(oldest) = self.recent_packets.front() {
+            let age = newest.duration_since(oldest.timestamp).unwrap_or_default();
+            if age > window || self.recent_packets.len() > MAX_RECENT_PACKETS {
+                self.recent_packets.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn raise_alert(&mut self, mut alert: Alert) {
+        if alert
+            .clock_identity
+            .and_then(|clock_identity| self.hosts.get(&clock_identity))
+            .is_some_and(|host| self.mute_list.is_muted(host))
+        {
+            self.muted_alerts += 1;
+            return;
+        }
+        if let Some(capture) = &self.alert_capture {
+            let path = capture.path_for(&alert);
+            let since = alert.timestamp.checked_sub(capture.window);
+            let packets = self
+                .recent_packets
+                .iter()
+                .filter(|p| since.is_none_or(|since| p.timestamp >= since))
+                .map(|p| p.as_ref());
+
+            match crate::pcap_export::write_pcap(&path, packets) {
+                Ok(_) => {
+                    self.written_files.push(path.clone());
+                    alert.capture_path = Some(path);
+                }
+                Err(e) => {
+                    alert.message = format!(
+                        "{} (capture to {} failed: {})",
+                        alert.message,
+                        path.display(),
+                        e
+                    )
+                }
+            }
+        }
+
+        self.alert_hooks.run(&alert);
+        *self.alert_counts.entry(alert.kind).or_default() += 1;
+        self.alerts.push(alert);
+    }
+
+    /// Alerts raised this session by kind, including those dropped from the log
+    pub fn get_alert_counts(&self) -> &HashMap<AlertKind, u64> {
+        &self.alert_counts
+    }
+
+    /// Files written by the tracker: alert captures, the state file and the
+    /// pcap ring files still on disk
+    pub fn get_written_files(&self) -> Vec<PathBuf> {
+        let mut files = self.written_files.clone();
+        if let Some((path, _)) = &self.stats_store {
+            files.push(path.clone());
+        }
+        if let Some(ring) = &self.pcap_ring {
+            files.extend(ring.files().iter().cloned());
+        }
+        files
+    }
+
+    /// Process captured packets, without the housekeeping of scan_network
+    pub async fn receive_packets(&mut self) {
+        self.process_ptp_messages().await;
+    }
+
+    pub async fn scan_network(&mut self) {
+        self.process_ptp_messages().await;
+        let started = Instant::now();
+        self.cleanup_old_sync_senders();
+
+        // A failed periodic save is retried with the next one and reported on exit
+        if self.last_stats_save.elapsed() >= STATS_SAVE_INTERVAL {
+            let _ = self.save_stats();
+        }
+        self.expire_hosts();
+        let now = SystemTime::now();
+        for host in self.hosts.values_mut() {
+            host.description_query.expire(now);
+        }
+        self.run_bmca_election();
+        self.decay_transmitter_confidence();
+        self.check_receiver_transmitters();
+        self.evaluate_simulated_receiver();
+        self.check_bond_failovers();
+        self.check_delay_req_cadences();
+
+        if let Some(drill) = &mut self.failover_drill {
+            drill.check_silence(
+                self.packet_source
+                    .reference_time()
+                    .unwrap_or_else(SystemTime::now),
+            );
+        }
+        self.stage_times.add(Stage::Tracking, started.elapsed());
+    }
+
+    /// Watch the active member of the bonds among the capture interfaces
+    pub fn watch_bonds(&mut self) {
+        let interfaces: Vec<String> = self
+            .packet_source
+            .interfaces()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        self.bond_watch = BondWatch::new(Path::new(bond::SYSFS_NET), &interfaces);
+    }
+
+    fn check_bond_failovers(&mut self) {
+        if self.bond_watch.is_empty() {
+            return;
+        }
+        for failover in self.bond_watch.poll() {
+            tracing::warn!(bond = %failover.bond, "{}", failover);
+            self.raise_alert(Alert::new(
+                AlertKind::BondFailover,
+                AlertSeverity::Info,
+                SystemTime::now(),
+                failover.to_string(),
+            ));
+        }
+    }
+
+    /// Whether the syncs of an instance were still coming at `at`
+    fn syncs_flowing(&self, instance: (u16, u8), at: SystemTime) -> bool {
+        self.last_instance_sync
+            .get(&instance)
+            .is_some_and(|sync| at.duration_since(*sync).unwrap_or_default() <= SYNCS_FLOWING)
+    }
+
+    /// Look for receivers that stopped sending Delay_Req
+    fn check_delay_req_cadences(&mut self) {
+        let now = self
+            .packet_source
+            .reference_time()
+            .unwrap_or_else(SystemTime::now);
+        let requesters: Vec<(ClockIdentity, (u16, u8))> = self
+            .hosts
+            .values()
+            .filter(|host| host.delay_req_count > 0)
+            .filter_map(|host| Some((host.clock_identity, host.instance()?)))
+            .collect();
+        for (clock_identity, instance) in requesters {
+            let syncs_flowing = self.syncs_flowing(instance, now);
+            if let Some(change) = self
+                .hosts
+                .get_mut(&clock_identity)
+                .and_then(|host| host.delay_req_cadence.check_silence(now, syncs_flowing))
+            {
+                self.report_cadence_change(clock_identity, instance, change);
+            }
+        }
+    }
+
+    /// Log a receiver losing lock or its cadence coming back, alerting on the loss
+    fn report_cadence_change(
+        &mut self,
+        clock_identity: ClockIdentity,
+        instance: (u16, u8),
+        change: CadenceChange,
+    ) {
+        let domain = format_ptp_instance(instance.0, instance.1);
+        match change {
+            CadenceChange::Lost(event) => {
+                let since = chrono::DateTime::<chrono::Utc>::from(event.at);
+                tracing::warn!(
+                    clock = %clock_identity,
+                    domain = %domain,
+                    since = %since.to_rfc3339(),
+                    "Probable loss of lock: {}",
+                    event
+                );
+                self.raise_alert(
+                    Alert::new(
+                        AlertKind::ReceiverLossOfLock,
+                        AlertSeverity::Warning,
+                        event.at,
+                        format!(
+                            "{} in domain {}: {} while the syncs continue, probable loss of lock",
+                            clock_identity, domain, event
+                        ),
+                    )
+                    .with_domain(instance.1)
+                    .with_clock_identity(clock_identity),
+                );
+            }
+            CadenceChange::Recovered(event) => {
+                let until = event.ended.map(chrono::DateTime::<chrono::Utc>::from);
+                tracing::info!(
+                    clock = %clock_identity,
+                    domain = %domain,
+                    until = %until.map(|until| until.to_rfc3339()).unwrap_or_default(),
+                    "Delay_Req cadence back to usual: {}",
+                    event
+                );
+            }
+        }
+    }
+
+    fn evaluate_simulated_receiver(&mut self) {
+        let Some(receiver) = &mut self.simulated_receiver else {
+            return;
+        };
+        let now = self
+            .packet_source
+            .reference_time()
+            .unwrap_or_else(SystemTime::now);
+        let bmca = self.bmca_profile.bmca_for(receiver.instance.0);
+        if let Some(previous) = receiver.evaluate(bmca, now) {
+            tracing::info!(
+                domain = %receiver.instance_name(),
+                previous = %previous,
+                state = %receiver.state(),
+                "Simulated receiver changed state"
+            );
+        }
+    }
+
+    /// Arm a failover drill on the current PTT of a PTP instance. Returns the
+    /// PTT, or None if the instance has none.
+    pub fn start_failover_drill(&mut self, instance: (u16, u8)) -> Option<ClockIdentity> {
+        let ptt = *self.bmca_winners.get(&instance)?;
+        let receivers = self
+            .hosts
+            .values()
+            .filter(|host| host.is_receiver() && host.instance() == Some(instance))
+            .map(|host| host.clock_identity);
+        let started = self
+            .packet_source
+            .reference_time()
+            .unwrap_or_else(SystemTime::now);
+        self.failover_drill = Some(FailoverDrill::new(instance, ptt, receivers, started));
+        Some(ptt)
+    }
+
+    pub fn stop_failover_drill(&mut self) {
+        self.failover_drill = None;
+    }
+
+    /// Send a GET CLOCK_DESCRIPTION to a host on the interface it was seen on,
+    /// the answer fills in its description. Needs --active.
+    pub fn request_clock_description(&mut self, clock_identity: &ClockIdentity) -> Result<()> {
+        let Some(transmitter) = self.transmitter.as_mut() else {
+            bail!("Restart with --active to query hosts");
+        };
+        let Some(host) = self.hosts.get_mut(clock_identity) else {
+            bail!("Host {} is gone", clock_identity);
+        };
+        let Some(interface) = host
+            .ip_addresses
+            .values()
+            .flatten()
+            .chain(host.interfaces.iter())
+            .find(|name| transmitter.interface_addresses(name).is_some())
+            .cloned()
+        else {
+            bail!(
+                "{} was not seen on an interface enabled for transmit",
+                clock_identity
+            );
+        };
+        let (mac, ipv4) = transmitter
+            .interface_addresses(&interface)
+            .unwrap_or_default();
+        // Hosts using UDP are asked over UDP, gPTP hosts over Layer 2
+        let source_ip = if host.has_ip_addresses() {
+            Some(ipv4.ok_or_else(|| anyhow!("{} has no IPv4 address to send from", interface))?)
+        } else {
+            None
+        };
+
+        self.management_sequence_id = self.management_sequence_id.wrapping_add(1);
+        let request = clock_description::request(
+            *clock_identity,
+            clock_description::clock_identity_from_mac(mac),
+            host.sdo_id.unwrap_or_default(),
+            host.domain_number.unwrap_or_default(),
+            self.management_sequence_id,
+        );
+        transmitter.send(
+            &interface,
+            clock_description::request_frame(&request, mac, source_ip),
+        )?;
+        host.description_query = DescriptionQuery::Pending {
+            since: SystemTime::now(),
+            sequence_id: self.management_sequence_id,
+        };
+        Ok(())
+    }
+
+    pub fn get_failover_drill(&self) -> Option<&FailoverDrill> {
+        self.failover_drill.as_ref()
+    }
+
+    /// Summary of the running drill, once when it completes
+    pub fn take_failover_drill_completion(&mut self) -> Option<String> {
+        self.failover_drill.as_mut()?.take_completion()
+    }
+
+    /// Match Syncs arriving on more than one interface and track the skew
+    /// between the paths they took
+    fn record_sync_arrival(&mut self, raw_packet: &RawPacket) {
+        let Some(header) = raw_packet
+            .ptp_payload()
+            .get(..34)
+            .and_then(|data| PtpHeader::try_from(data).ok())
+        else {
+            return;
+        };
+        if header.message_type != PtpMessageType::Sync {
+            return;
+        }
+
+        let clock_identity = header.source_port_identity.clock_identity;
+        let Some((pair, skew_ns)) = self.sync_arrivals.record(
+            (
+                clock_identity,
+                header.sdo_id,
+                header.domain_number,
+                header.sequence_id,
+            ),
+            &raw_packet.interface_name,
+            raw_packet.timestamp,
+        ) else {
+            return;
+        };
+        let Some(host) = self.hosts.get_mut(&clock_identity) else {
+            return;
+        };
+
+        let description = format!("{} / {}", pair.0, pair.1);
+        if let Some(divergence) = host
+            .path_skews
+            .entry(pair)
+            .or_default()
+            .record(skew_ns, self.path_skew_threshold)
+        {
+            self.raise_alert(
+                Alert::new(
+                    AlertKind::PathDivergence,
+                    AlertSeverity::Warning,
+                    raw_packet.timestamp,
+                    format!("Host {}: {} {}", clock_identity, description, divergence),
+                )
+                .with_domain(header.domain_number)
+                .with_clock_identity(clock_identity),
+            );
+        }
+    }
+
+    async fn process_ptp_messages(&mut self) {
+        let started = Instant::now();
+        let parse_before = self.stage_times.get(Stage::Parse);
+        let mut handling = Duration::ZERO;
+
+        // Process packets from raw socket capture
+        let mut received = 0;
+        for _ in 0..MAX_PACKETS_PER_POLL {
+            // Limit iterations to prevent blocking too long
+            match self.packet_source.next_packet() {
+                Some(raw_packet) => {
+                    received += 1;
+
+                    // Raw evidence, before anything is skipped; a ring that can't be
+                    // written is given up on rather than failing every packet
+                    if let Some(ring) = self.pcap_ring.as_mut()
+                        && let Err(e) = ring.write(&raw_packet)
+                    {
+                        tracing::error!(error = %e, "Stopped writing the pcap ring");
+                        self.pcap_ring = None;
+                    }
+
+                    // Our own frames are recorded when sent, skip the captured copy
+                    if let Some(transmitter) = self.transmitter.as_mut()
+                        && transmitter.is_echo(&raw_packet)
+                    {
+                        continue;
+                    }
+
+                    // Before de-duplication, the copy from the other path looks the same
+                    if !self.lite {
+                        self.record_sync_arrival(&raw_packet);
+                    }
+                    self.interface_bursts
+                        .entry(raw_packet.interface_name.clone())
+                        .or_default()
+                        .record(raw_packet.timestamp);
+
+                    // Mirrored copies from SPAN sessions are only counted
+                    if self.deduplicator.is_duplicate(&raw_packet) {
+                        continue;
+                    }
+
+                    let raw_packet_arc = std::sync::Arc::new(raw_packet);
+                    let handle_started = Instant::now();
+                    self.record_recent_packet(raw_packet_arc.clone());
+                    self.handle_raw_packet(raw_packet_arc).await;
+                    self.last_packet = Instant::now();
+                    handling += self.last_packet - handle_started;
+                }
+                None => {
+                    // No more packets available
+                    break;
+                }
+            }
+        }
+        self.received_packets += received as u64;
+        self.backlogged |= received == MAX_PACKETS_PER_POLL;
+
+        // Record frames transmitted by active features in the packet history
+        let sent = match self.transmitter.as_mut() {
+            Some(transmitter) => transmitter.take_sent(),
+            None => Vec::new(),
+        };
+        for raw_packet in sent {
+            let raw_packet_arc = Arc::new(raw_packet);
+            let handle_started = Instant::now();
+            self.record_recent_packet(raw_packet_arc.clone());
+            self.handle_raw_packet(raw_packet_arc).await;
+            handling += handle_started.elapsed();
+        }
+
+        // Parsing is timed inside handle_raw_packet, the rest of handling a
+        // packet is tracking and everything around it is capture
+        let parsing = self.stage_times.get(Stage::Parse) - parse_before;
+        self.stage_times
+            .add(Stage::Tracking, handling.saturating_sub(parsing));
+        self.stage_times
+            .add(Stage::Capture, started.elapsed().saturating_sub(handling));
+    }
+
+    async fn handle_raw_packet(&mut self, raw_packet: std::sync::Arc<crate::source::RawPacket>) {
+        let parse_started = Instant::now();
+        let parsed = PtpMessage::try_from(raw_packet.ptp_payload());
+        self.stage_times.add(Stage::Parse, parse_started.elapsed());
+        self.stage_times.parsed += 1;
+        let msg = match parsed {
+            Ok(m) => m,
+            Err(e) => {
+                tracing::debug!(
+                    interface = %raw_packet.interface_name,
+                    source = ?raw_packet.source_addr,
+                    error = %e,
+                    "Rejected PTP message"
+                );
+                self.rejected_packets += 1;
+                return;
+            }
+        };
+
+        let header = msg.header();
+        let sender = header.source_port_identity.clock_identity;
+        // Responses change the requesting host as well as the sender
+        let receiver = match &msg {
+            PtpMessage::DelayResp(msg) => Some(msg.requesting_port_identity.clock_identity),
+            PtpMessage::PDelayResp(msg) => Some(msg.requesting_port_identity.clock_identity),
+            PtpMessage::PDelayRespFollowup(msg) => {
+                Some(msg.requesting_port_identity.clock_identity)
+            }
+            _ => None,
+        };
+        let host_count = self.hosts.len();
+        let fingerprints = |hosts: &HashMap<ClockIdentity, PtpHost>| {
+            [Some(sender), receiver]
+                .map(|clock_identity| hosts.get(&clock_identity?).map(PtpHost::order_fingerprint))
+        };
+        let before = fingerprints(&self.hosts);
+        if matches!(
+            header.message_type,
+            PtpMessageType::Sync | PtpMessageType::Announce
+        ) {
+            self.sequence_loss.record(
+                (
+                    header.source_port_identity.clock_identity,
+                    header.source_port_identity.port_number,
+                    header.message_type,
+                    header.sdo_id,
+                    header.domain_number,
+                ),
+                header.sequence_id,
+            );
+        }
+
+        let syncs_flowing = header.message_type == PtpMessageType::DelayReq
+            && self.syncs_flowing((header.sdo_id, header.domain_number), raw_packet.timestamp);
+
+        let lite = self.lite;
+        let max_packet_history = if lite { 0 } else { self.max_packet_history };
+        let stats_store = self.stats_store.as_ref().map(|(_, store)| store);
+        let host_tags = &self.host_tags;
+        let new_host = |clock_identity: ClockIdentity| {
+            let mut host = PtpHost::new(clock_identity);
+            host.set_max_packet_history(max_packet_history);
+            if let Some(tags) = host_tags.get(&clock_identity.to_string()) {
+                host.tags = tags.clone();
+            }
+            if let Some(counters) = stats_store.and_then(|store| store.get(&host)) {
+                counters.restore(&mut host);
+            }
+            host
+        };
+
+        // Create packet info for recording
+        let packet = Arc::new(ParsedPacket {
+            ptp: msg,
+            raw: raw_packet.clone(),
+        });
+        let truncated = packet.truncation().is_some();
+        let incomplete = packet.incomplete_body().is_some();
+
+        let sending_host = self
+            .hosts
+            .entry(msg.header().source_port_identity.clock_identity)
+            .or_insert_with(|| new_host(msg.header().source_port_identity.clock_identity));
+
+        // Add IP address or interface depending on packet type
+        if let Some(source_addr) = raw_packet.source_addr {
+            // PTP over UDP - add IP address
+            sending_host.add_ip_address(
+                source_addr.ip(),
+                packet.raw.vlan_id,
+                packet.raw.interface_name.clone(),
+            );
+        } else {
+            // gPTP - add interface only
+            sending_host.add_interface(packet.raw.interface_name.clone());
+        }
+        // Peer delay is never forwarded, so the MAC is the host's own, as it is
+        // for packets that crossed no router
+        if raw_packet.source_addr.is_none()
+            || raw_packet.ttl.is_some_and(|ttl| hops_from_ttl(ttl) == 0)
+            || matches!(
+                msg.header().message_type,
+                PtpMessageType::PDelayReq
+                    | PtpMessageType::PDelayResp
+                    | PtpMessageType::PDelayRespFollowUp
+            )
+        {
+            sending_host.mac_addresses.insert(raw_packet.source_mac);
+        }
+
+        sending_host.total_messages_sent_count += 1;
+        // A host taking up another minor version may mix or unmix its domain
+        let minor_version_changed = !msg.header().is_cmlds()
+            && sending_host.last_minor_version != Some(msg.header().minor_version);
+        sending_host.update_from_ptp_header(msg.header());
+        sending_host.role_evidence.record(msg.header());
+        // Update last_seen with packet timestamp
+        sending_host.last_seen = raw_packet.timestamp;
+        sending_host.record_observation(raw_packet.timestamp);
+        sending_host.bursts.record(raw_packet.timestamp);
+        sending_host
+            .arrival_paths
+            .record(&raw_packet, msg.header().message_type);
+        if !lite {
+            sending_host
+                .sent_counts
+                .record(msg.header().message_type, raw_packet.timestamp);
+            sending_host.conformance.record(&packet);
+            sending_host.path.record_sent(&packet);
+            if !incomplete {
+                sending_host
+                    .latest_packets
+                    .insert(msg.header().message_type, packet.clone());
+            }
+        }
+
+        if let Some(dest) = raw_packet.dest_addr {
+            sending_host.udp_ports.insert(dest.port());
+        }
+        if let (Some(source), Some(dest)) = (raw_packet.source_addr, raw_packet.dest_addr) {
+            sending_host.port_usage.record_sent(
+                msg.header().message_type,
+                source.port(),
+                dest.port(),
+                raw_packet.timestamp,
+            );
+        }
+
+        // A changed hop distance means the timing path was rerouted
+        let mut hop_alert = None;
+        if let (Some(ttl), Some(source)) = (raw_packet.ttl, raw_packet.source_addr)
+            && let Some((before, after)) = sending_host.hop_distance.record(source.ip(), ttl)
+        {
+            hop_alert = Some(
+                Alert::new(
+                    AlertKind::HopDistanceChanged,
+                    AlertSeverity::Warning,
+                    raw_packet.timestamp,
+                    format!(
+                        "{} ({}) on {}: {} routed hop(s) away instead of {} (TTL {})",
+                        sending_host.clock_identity,
+                        source.ip(),
+                        raw_packet.interface_name,
+                        after,
+                        before,
+                        ttl
+                    ),
+                )
+                .with_domain(msg.header().domain_number)
+                .with_clock_identity(sending_host.clock_identity),
+            );
+        }
+
+        // Misaddressed messages, once per host, message type and issue
+        let mut address_alert = None;
+        if let Some(issue) = addressing::check_destination(
+            msg.header(),
+            raw_packet.dest_mac,
+            raw_packet.dest_addr.map(|dest| dest.ip()),
+        ) {
+            let message_type = msg.header().message_type;
+            let count = sending_host
+                .address_issues
+                .entry((message_type, issue))
+                .or_default();
+            *count += 1;
+            if *count == 1 {
+                address_alert = Some(
+                    Alert::new(
+                        AlertKind::MisaddressedMessage,
+                        AlertSeverity::Warning,
+                        raw_packet.timestamp,
+                        format!(
+                            "{} ({}) on {}: {} {}",
+                            sending_host.clock_identity,
+                            sending_host.get_vendor_name().unwrap_or("unknown vendor"),
+                            raw_packet.interface_name,
+                            message_type,
+                            issue
+                        ),
+                    )
+                    .with_domain(msg.header().domain_number)
+                    .with_clock_identity(sending_host.clock_identity),
+                );
+            }
+        }
+
+        // Event messages queued behind bulk traffic pick up delay variation,
+        // once per host
+        let mut priority_alert = None;
+        if msg.header().message_type.is_event()
+            && let Some(pcp) = raw_packet.vlan_pcp
+        {
+            sending_host.event_pcp = Some(pcp);
+            if let Some(expected) = self.expected_event_pcp
+                && pcp != expected
+            {
+                sending_host.event_pcp_violations += 1;
+                if sending_host.event_pcp_violations == 1 {
+                    priority_alert = Some(
+                        Alert::new(
+                            AlertKind::PriorityMismatch,
+                            AlertSeverity::Warning,
+                            raw_packet.timestamp,
+                            format!(
+                                "{} on {}: {} tagged with priority {} instead of {}, check the QoS config of the host and switch",
+                                sending_host.clock_identity,
+                                raw_packet.interface_name,
+                                msg.header().message_type,
+                                pcp,
+                                expected
+                            ),
+                        )
+                        .with_domain(msg.header().domain_number)
+                        .with_clock_identity(sending_host.clock_identity),
+                    );
+                }
+            }
+        }
+
+        // Multicast leaking across routers pollutes other sites' domains
+        let mut boundary_alert = None;
+        if let (Some(ttl), Some(source), Some(dest)) =
+            (raw_packet.ttl, raw_packet.source_addr, raw_packet.dest_addr)
+            && dest.ip().is_multicast()
+        {
+            sending_host.multicast_ttls.insert(ttl);
+
+            let subnets: Vec<&IpNetwork> = self
+                .local_subnets
+                .iter()
+                .filter(|(name, _)| *name == raw_packet.interface_name)
+                .map(|(_, network)| network)
+                .collect();
+            let on_local_subnet = (!subnets.is_empty())
+                .then(|| subnets.iter().any(|network| network.contains(source.ip())));
+
+            if !sending_host.boundary_alerted
+                && let Some(reason) =
+                    multicast_boundary_violation(ttl, on_local_subnet, self.max_multicast_hops)
+            {
+                sending_host.boundary_alerted = true;
+                boundary_alert = Some(
+                    Alert::new(
+                        AlertKind::MulticastBoundary,
+                        AlertSeverity::Warning,
+                        raw_packet.timestamp,
+                        format!(
+                            "{} ({}) on {}: {}",
+                            sending_host.clock_identity,
+                            source.ip(),
+                            raw_packet.interface_name,
+                            reason
+                        ),
+                    )
+                    .with_domain(msg.header().domain_number)
+                    .with_clock_identity(sending_host.clock_identity),
+                );
+            }
+        }
+
+        // Name devices that get their length fields wrong, once per host
+        let mut length_alert = None;
+        let mut unicast_alerts = Vec::new();
+        let mut quality_alert = None;
+        let mut timescale_alerts = Vec::new();
+        // Offset from the capture clock of the origin timestamp, if it carried one
+        let mut era_offset = None;
+        let mut cadence_change = None;
+        let length_issues = packet.length_issues();
+        if let Some(issue) = length_issues.first() {
+            sending_host.length_issue_count += 1;
+            sending_host.last_length_issue = Some(*issue);
+            if sending_host.length_issue_count == 1 {
+                length_alert = Some(
+                    Alert::new(
+                        AlertKind::MalformedLength,
+                        AlertSeverity::Warning,
+                        raw_packet.timestamp,
+                        format!(
+                            "{} ({}) sent {}: {}",
+                            sending_host.clock_identity,
+                            sending_host.get_vendor_name().unwrap_or("unknown vendor"),
+                            msg.header().message_type,
+                            issue
+                        ),
+                    )
+                    .with_domain(msg.header().domain_number)
+                    .with_clock_identity(sending_host.clock_identity),
+                );
+            }
+        }
+
+        if truncated {
+            self.truncated_packets += 1;
+            sending_host.truncated_count += 1;
+        }
+
+        match msg {
+            // An incomplete body, cut by the capture or sent short, is zero
+            // filled past what was received, so only the header is known:
+            // count and keep the message, but take none of its fields for
+            // the host's state
+            _ if incomplete => {
+                sending_host.count_sent(msg.header().message_type);
+                sending_host.add_packet(packet.clone());
+            }
+            PtpMessage::Announce(msg) => {
+                sending_host.announce_count += 1;
+                sending_host.update_state(|state| state.update_from_announce(&msg));
+                if let PtpHostState::TimeTransmitter(state) = &mut sending_host.state {
+                    state.path_trace =
+                        find_tlv(&msg.header, raw_packet.ptp_payload(), TLV_PATH_TRACE)
+                            .map(parse_path_trace);
+                }
+                sending_host.add_packet(packet.clone());
+                if let Some(receiver) = &mut self.simulated_receiver {
+                    receiver.record_announce(&msg, raw_packet.timestamp);
+                }
+
+                sending_host.announce_continuity.record(
+                    raw_packet.timestamp,
+                    msg.header.log_message_interval.exponent,
+                );
+                sending_host.quality_trend.record(
+                    raw_packet.timestamp,
+                    msg.clock_accuracy.accuracy,
+                    msg.offset_scaled_log_variance,
+                );
+                if let Some(description) = sending_host.quality_trend.take_new_degradation() {
+                    quality_alert = Some(
+                        Alert::new(
+                            AlertKind::QualityDegrading,
+                            AlertSeverity::Warning,
+                            raw_packet.timestamp,
+                            format!("{}: {}", sending_host.clock_identity, description),
+                        )
+                        .with_domain(msg.header.domain_number)
+                        .with_clock_identity(sending_host.clock_identity),
+                    );
+                }
+
+                // Report each inconsistency when it appears, not with every Announce
+                let issues: BTreeSet<_> = timescale::check(&msg, raw_packet.timestamp)
+                    .into_iter()
+                    .collect();
+                for issue in issues.difference(&sending_host.timescale_issues) {
+                    timescale_alerts.push(
+                        Alert::new(
+                            AlertKind::TimescaleMismatch,
+                            AlertSeverity::Warning,
+                            raw_packet.timestamp,
+                            format!("{}: {}", sending_host.clock_identity, issue),
+                        )
+                        .with_domain(msg.header.domain_number)
+                        .with_clock_identity(sending_host.clock_identity),
+                    );
+                }
+                sending_host.timescale_issues = issues;
+
+                if sending_host.advertised_gm.record(
+                    &msg,
+                    raw_packet.timestamp,
+                    &mut self.own_announces,
+                ) {
+                    timescale_alerts.push(
+                        Alert::new(
+                            AlertKind::RelabeledAnnounce,
+                            AlertSeverity::Warning,
+                            raw_packet.timestamp,
+                            format!(
+                                "{} announces {} as grandmaster without acting as a boundary clock (stepsRemoved {}, sequenceId {})",
+                                sending_host.clock_identity,
+                                msg.ptt_identity,
+                                msg.steps_removed,
+                                msg.header.sequence_id
+                            ),
+                        )
+                        .with_domain(msg.header.domain_number)
+                        .with_clock_identity(sending_host.clock_identity),
+                    );
+                }
+
+                for collision in self.shared_gms.record(&msg, raw_packet.timestamp) {
+                    tracing::warn!(gm = %collision.gm, "{}", collision);
+                    timescale_alerts.push(
+                        Alert::new(
+                            AlertKind::SharedGmConflict,
+                            AlertSeverity::Warning,
+                            raw_packet.timestamp,
+                            collision.to_string(),
+                        )
+                        .with_domain(msg.header.domain_number)
+                        .with_clock_identity(collision.gm),
+                    );
+                }
+
+                // Follow the PTT only, two GMs disagreeing would flip back and forth
+                let instance = (msg.header.sdo_id, msg.header.domain_number);
+                let from_ptt = self
+                    .bmca_winners
+                    .get(&instance)
+                    .is_none_or(|ptt| *ptt == sending_host.clock_identity);
+                if from_ptt && let Some(change) = self.utc_offsets.record(&msg) {
+                    tracing::warn!(
+                        domain = %format_ptp_instance(instance.0, instance.1),
+                        before = change.before,
+                        after = change.after,
+                        previous_gm = %change.previous_gm,
+                        gm = %change.gm,
+                        leap_second = change.leap_second,
+                        "currentUtcOffset changed"
+                    );
+                    timescale_alerts.push(
+                        Alert::new(
+                            AlertKind::UtcOffsetChanged,
+                            AlertSeverity::Warning,
+                            raw_packet.timestamp,
+                            format!(
+                                "Domain {}: {}",
+                                format_ptp_instance(instance.0, instance.1),
+                                change
+                            ),
+                        )
+                        .with_domain(msg.header.domain_number)
+                        .with_clock_identity(sending_host.clock_identity),
+                    );
+                }
+            }
+            PtpMessage::Sync(msg) => {
+                sending_host.sync_count += 1;
+                self.last_instance_sync.insert(
+                    (msg.header.sdo_id, msg.header.domain_number),
+                    raw_packet.timestamp,
+                );
+                sending_host.update_state(|state| state.update_from_sync(&msg));
+                // Two-step clocks may leave the Sync's timestamp approximate
+                if !msg.header.flags.two_step() {
+                    era_offset =
+                        sending_host.record_era(&msg.origin_timestamp, raw_packet.timestamp);
+                }
+                if let Some(receiver) = &mut self.simulated_receiver {
+                    receiver.record_sync(&msg.header, raw_packet.timestamp);
+                }
+                if !lite {
+                    sending_host.residence.record_sync(
+                        &raw_packet.interface_name,
+                        msg.header.sequence_id,
+                        msg.header.correction_field,
+                        msg.header.flags.two_step(),
+                    );
+                    sending_host.sync_drift.record_sync(
+                        msg.header.sequence_id,
+                        msg.origin_timestamp,
+                        msg.header.flags.two_step(),
+                        raw_packet.timestamp,
+                    );
+                }
+
+                // Record this as a recent sync sender for this PTP instance
+                let domain_senders = self
+                    .recent_sync_senders
+                    .entry((msg.header.sdo_id, msg.header.domain_number))
+                    .or_default();
+
+                let now = std::time::Instant::now();
+                if let Some(existing) = domain_senders
+                    .iter_mut()
+                    .find(|(id, _)| id == &msg.header.source_port_identity.clock_identity)
+                {
+                    existing.1 = now;
+                } else {
                     domain_senders.push((msg.header.source_port_identity.clock_identity, now));
                 }
-                sending_host.add_packet(packet.clone());
+                if sending_host.sync_sampler.keep_sync(
+                    (
+                        msg.header.source_port_identity.port_number,
+                        msg.header.sdo_id,
+                        msg.header.domain_number,
+                    ),
+                    msg.header.sequence_id,
+                    msg.header.correction_field.value,
+                    !length_issues.is_empty(),
+                    self.sync_sample_rate,
+                ) {
+                    sending_host.add_packet(packet.clone());
+                }
+
+                if let Some(drill) = &mut self.failover_drill {
+                    drill.record_sync(
+                        (msg.header.sdo_id, msg.header.domain_number),
+                        msg.header.source_port_identity.clock_identity,
+                        raw_packet.timestamp,
+                    );
+                }
             }
             PtpMessage::DelayReq(msg) => {
                 sending_host.delay_req_count += 1;
+                if let Some(dest) = raw_packet.dest_addr
+                    && !dest.ip().is_multicast()
+                {
+                    sending_host.delay_req_destination = Some(dest.ip());
+                }
 
                 let now = std::time::Instant::now();
-                if let Some(domain_senders) =
-                    self.recent_sync_senders.get(&msg.header.domain_number)
+                if let Some(domain_senders) = self
+                    .recent_sync_senders
+                    .get(&(msg.header.sdo_id, msg.header.domain_number))
                 {
                     // Find the most recent sync sender and determine the age of the last sync
                     if let Some((clock_identity, sync_time)) = domain_senders
@@ -854,31 +2992,61 @@ impl PtpTracker {
                     {
                         let age = now.duration_since(*sync_time);
 
-                        sending_host
-                            .state
-                            .update_from_recent_sync_sender(*clock_identity, age);
+                        sending_host.update_state(|state| {
+                            state.update_from_recent_sync_sender(*clock_identity, age)
+                        });
                     }
                 }
+                sending_host.record_delay_exchange(raw_packet.timestamp);
+                cadence_change = sending_host
+                    .delay_req_cadence
+                    .record_request(raw_packet.timestamp, syncs_flowing);
                 sending_host.add_packet(packet.clone());
+                if !lite {
+                    self.delay_xref.record_delay_req(&packet);
+                }
             }
             PtpMessage::DelayResp(msg) => {
                 sending_host.delay_resp_count += 1;
                 sending_host.add_packet(packet.clone());
+                if !lite
+                    && self
+                        .delay_xref
+                        .record_delay_resp(msg.requesting_port_identity, &packet)
+                {
+                    sending_host.unsolicited_delay_resp_count += 1;
+                    sending_host.last_unsolicited_delay_resp = Some(packet.clone());
+                }
 
                 // Handle receiving host separately to avoid borrow checker issues
                 let receiving_clock_id = msg.requesting_port_identity.clock_identity;
                 let receiving_host = self
                     .hosts
                     .entry(receiving_clock_id)
-                    .or_insert_with(|| PtpHost::new(receiving_clock_id));
+                    .or_insert_with(|| new_host(receiving_clock_id));
 
                 receiving_host.delay_resp_count += 1;
                 receiving_host.total_messages_received_count += 1;
-                receiving_host.state.update_from_delay_resp(&msg);
+                receiving_host.update_state(|state| state.update_from_delay_resp(&msg));
+                receiving_host.record_delay_exchange(raw_packet.timestamp);
+                receiving_host.answered_by = Some(msg.header.source_port_identity.clock_identity);
+                if let (Some(source), Some(dest)) = (raw_packet.source_addr, raw_packet.dest_addr) {
+                    receiving_host
+                        .port_usage
+                        .record_delay_resp(source.port(), dest.port());
+                }
                 receiving_host.add_packet(packet.clone());
+
+                if let Some(drill) = &mut self.failover_drill {
+                    drill.record_delay_resp(
+                        (msg.header.sdo_id, msg.header.domain_number),
+                        msg.header.source_port_identity.clock_identity,
+                        receiving_clock_id,
+                        raw_packet.timestamp,
+                    );
+                }
             }
             PtpMessage::PDelayReq(_) => {
-                sending_host.pdelay_req_count += 1;
                 // PDelay requests are used for peer-to-peer delay measurement
                 // In P2P mode, each node measures delay with its neighbors directly
                 // Could extract timing information if needed for analysis
@@ -890,15 +3058,30 @@ impl PtpTracker {
                 // Like PDelayReq, they don't indicate transmitter-receiver relationship
 
                 sending_host.pdelay_resp_count += 1;
+                // The responder may never send anything else, e.g. an AVB bridge
+                sending_host
+                    .peers_mut(&msg.header)
+                    .insert(msg.requesting_port_identity.clock_identity);
 
                 let receiving_host = self
                     .hosts
                     .entry(msg.requesting_port_identity.clock_identity)
-                    .or_insert_with(|| PtpHost::new(msg.requesting_port_identity.clock_identity));
+                    .or_insert_with(|| new_host(msg.requesting_port_identity.clock_identity));
 
                 receiving_host.pdelay_resp_count += 1;
+                receiving_host
+                    .peers_mut(&msg.header)
+                    .insert(msg.header.source_port_identity.clock_identity);
                 receiving_host.total_messages_received_count += 1;
-                receiving_host.state.update_from_pdelay_resp(&msg);
+                if !lite {
+                    receiving_host.path.record_pdelay_response(
+                        msg.requesting_port_identity,
+                        msg.header.sequence_id,
+                        msg.header.source_port_identity.clock_identity,
+                    );
+                }
+                receiving_host.update_state(|state| state.update_from_pdelay_resp(&msg));
+                receiving_host.record_delay_exchange(raw_packet.timestamp);
 
                 receiving_host.add_packet(packet);
             }
@@ -910,29 +3093,166 @@ impl PtpTracker {
                 let receiving_host = self
                     .hosts
                     .entry(msg.requesting_port_identity.clock_identity)
-                    .or_insert_with(|| PtpHost::new(msg.requesting_port_identity.clock_identity));
+                    .or_insert_with(|| new_host(msg.requesting_port_identity.clock_identity));
 
                 receiving_host.pdelay_resp_follow_up_count += 1;
                 receiving_host.total_messages_received_count += 1;
-                receiving_host.state.update_from_pdelay_resp_follow_up(&msg);
+                receiving_host.update_state(|state| state.update_from_pdelay_resp_follow_up(&msg));
                 receiving_host.add_packet(packet);
             }
             PtpMessage::FollowUp(msg) => {
                 sending_host.follow_up_count += 1;
-                sending_host.add_packet(packet.clone());
-                sending_host.state.update_from_follow_up(&msg);
+                if sending_host.sync_sampler.keep_follow_up(
+                    (
+                        msg.header.source_port_identity.port_number,
+                        msg.header.sdo_id,
+                        msg.header.domain_number,
+                    ),
+                    msg.header.sequence_id,
+                    msg.header.correction_field.value,
+                    !length_issues.is_empty(),
+                    self.sync_sample_rate,
+                ) {
+                    sending_host.add_packet(packet.clone());
+                }
+                sending_host.update_state(|state| state.update_from_follow_up(&msg));
+                era_offset =
+                    sending_host.record_era(&msg.precise_origin_timestamp, raw_packet.timestamp);
+                if !lite {
+                    sending_host.residence.record_follow_up(
+                        &raw_packet.interface_name,
+                        msg.header.sequence_id,
+                        msg.header.correction_field,
+                    );
+                    sending_host
+                        .sync_drift
+                        .record_follow_up(msg.header.sequence_id, msg.precise_origin_timestamp);
+                }
             }
-            PtpMessage::Signaling(_) => {
+            PtpMessage::Signaling(msg) => {
                 sending_host.signaling_message_count += 1;
                 sending_host.add_packet(packet.clone());
+
+                // Churn in unicast grants shows up here before clocks lose sync
+                for tlv in unicast_tlvs(&msg.header, raw_packet.ptp_payload()) {
+                    if !tlv.is_teardown() {
+                        continue;
+                    }
+                    let target = msg.target_port_identity.clock_identity;
+                    let event = format!("{} to {}", tlv, target);
+                    sending_host.unicast_teardown_count += 1;
+                    sending_host.last_unicast_teardown =
+                        Some((raw_packet.timestamp, event.clone()));
+                    unicast_alerts.push(
+                        Alert::new(
+                            AlertKind::UnicastTeardown,
+                            AlertSeverity::Info,
+                            raw_packet.timestamp,
+                            format!("{}: {}", sending_host.clock_identity, event),
+                        )
+                        .with_domain(msg.header.domain_number)
+                        .with_clock_identity(sending_host.clock_identity),
+                    );
+                }
             }
             PtpMessage::Management(_) => {
                 sending_host.management_message_count += 1;
+                if let Some((sequence_id, answer)) =
+                    clock_description::parse_response(raw_packet.ptp_payload())
+                    && matches!(
+                        sending_host.description_query,
+                        DescriptionQuery::Pending { sequence_id: pending, .. } if pending == sequence_id
+                    )
+                {
+                    sending_host.description_query = answer;
+                }
                 sending_host.add_packet(packet.clone());
             }
         }
 
+        if let Some(offset) = era_offset {
+            let header = msg.header();
+            for mismatch in self.domain_eras.record(
+                (header.sdo_id, header.domain_number),
+                header.source_port_identity.clock_identity,
+                offset,
+            ) {
+                tracing::warn!(clock = %mismatch.clock_identity, "{}", mismatch);
+                timescale_alerts.push(
+                    Alert::new(
+                        AlertKind::EraMismatch,
+                        AlertSeverity::Warning,
+                        raw_packet.timestamp,
+                        mismatch.to_string(),
+                    )
+                    .with_domain(header.domain_number)
+                    .with_clock_identity(mismatch.clock_identity),
+                );
+            }
+        }
+
+        if let Some(alert) = length_alert {
+            self.raise_alert(alert);
+        }
+        for alert in unicast_alerts {
+            self.raise_alert(alert);
+        }
+        if let Some(alert) = quality_alert {
+            self.raise_alert(alert);
+        }
+        for alert in timescale_alerts {
+            self.raise_alert(alert);
+        }
+        if let Some(alert) = boundary_alert {
+            self.raise_alert(alert);
+        }
+        if let Some(alert) = hop_alert {
+            self.raise_alert(alert);
+        }
+        if let Some(alert) = address_alert {
+            self.raise_alert(alert);
+        }
+        if let Some(alert) = priority_alert {
+            self.raise_alert(alert);
+        }
+        if minor_version_changed
+            && let Some(alert) = self.check_minor_versions(msg.header(), raw_packet.timestamp)
+        {
+            self.raise_alert(alert);
+        }
+        if let Some(change) = cadence_change {
+            let header = msg.header();
+            self.report_cadence_change(
+                header.source_port_identity.clock_identity,
+                (header.sdo_id, header.domain_number),
+                change,
+            );
+        }
+        if matches!(
+            msg.header().message_type,
+            PtpMessageType::Sync | PtpMessageType::FollowUp
+        ) && self.has_gm_pairs()
+        {
+            self.record_gm_pair_phase(
+                msg.header().source_port_identity.clock_identity,
+                raw_packet.timestamp,
+            );
+        }
+
+        for request in self.delay_xref.expire(raw_packet.timestamp) {
+            let requester = request.ptp.header().source_port_identity.clock_identity;
+            if let Some(host) = self.hosts.get_mut(&requester) {
+                host.unanswered_delay_req_count += 1;
+                host.last_unanswered_delay_req = Some(request);
+            }
+        }
+
+        self.enforce_host_limit();
+        if self.hosts.len() != host_count || fingerprints(&self.hosts) != before {
+            self.hosts_generation += 1;
+        }
         self.last_packet = std::time::Instant::now();
+        self.last_packet_time = self.last_packet_time.max(Some(raw_packet.timestamp));
     }
 
     fn cleanup_old_sync_senders(&mut self) {
@@ -967,23 +3287,472 @@ impl PtpTracker {
             .find(|h| h.clock_identity == *clock_identity)
     }
 
+    /// Forget all hosts, they start from zero when seen again
     pub fn clear_hosts(&mut self) {
+        self.removed_host_count += self.hosts.len() as u64;
+        if let Some((_, store)) = self.stats_store.as_mut() {
+            for host in self.hosts.values() {
+                store.remove(host);
+            }
+        }
         self.hosts.clear();
+        self.eviction_queue.clear();
+        self.bmca_winners.clear();
+        self.bmca_winners_since.clear();
+        self.gm_deviations.clear();
+        self.gm_pair_phases.clear();
+        self.own_announces.clear();
+        self.sequence_loss.clear();
+        self.hosts_generation += 1;
+    }
+
+    pub fn get_host(&self, clock_identity: &ClockIdentity) -> Option<&PtpHost> {
+        self.hosts.get(clock_identity)
+    }
+
+    /// Hosts not muted
+    pub fn get_host_count(&self) -> usize {
+        self.unmuted_hosts().count()
+    }
+
+    fn unmuted_hosts(&self) -> impl Iterator<Item = &PtpHost> {
+        self.hosts
+            .values()
+            .filter(|host| !self.mute_list.is_muted(host))
+    }
+
+    pub fn set_alert_hooks(&mut self, alert_hooks: AlertHooks) {
+        self.alert_hooks = alert_hooks;
+    }
+
+    pub fn set_mute_list(&mut self, mute_list: MuteList) {
+        self.mute_list = mute_list;
+        self.hosts_generation += 1;
+    }
+
+    pub fn get_mute_list(&self) -> &MuteList {
+        &self.mute_list
+    }
+
+    pub fn is_muted(&self, host: &PtpHost) -> bool {
+        self.mute_list.is_muted(host)
+    }
+
+    pub fn get_muted_host_count(&self) -> usize {
+        self.hosts.len() - self.get_host_count()
+    }
+
+    /// Alerts about muted hosts that were not raised
+    pub fn get_muted_alert_count(&self) -> u64 {
+        self.muted_alerts
+    }
+
+    /// Counter that changes whenever host data changes, so views can cache derived data
+    pub fn get_hosts_generation(&self) -> u64 {
+        self.hosts_generation
+    }
+
+    /// Select the BMCA rules, and the localPriority of transmitters by clock
+    /// identity for profiles that use it
+    pub fn set_bmca_profile(
+        &mut self,
+        profile: BmcaProfile,
+        local_priorities: HashMap<String, u8>,
+    ) {
+        self.bmca_profile = profile;
+        self.local_priorities = local_priorities;
+    }
+
+    /// Grandmaster clock identity each domain number is designed to elect
+    pub fn set_expected_grandmasters(&mut self, expected: HashMap<u8, ClockIdentity>) {
+        self.gm_deviations
+            .retain(|(_, domain), _| expected.contains_key(domain));
+        self.expected_grandmasters = expected;
+    }
+
+    pub fn has_expected_grandmasters(&self) -> bool {
+        !self.expected_grandmasters.is_empty()
+    }
+
+    /// Elected grandmaster of an instance against the expected one, None if
+    /// no grandmaster is expected in its domain or none was elected yet
+    pub fn get_gm_design(&self, instance: (u16, u8)) -> Option<GmDesign> {
+        let expected = *self.expected_grandmasters.get(&instance.1)?;
+        let ptt = *self.bmca_winners.get(&instance)?;
+        // A boundary clock passes on the grandmaster's identity
+        let grandmaster = match self.hosts.get(&ptt).map(|host| &host.state) {
+            Some(PtpHostState::TimeTransmitter(state)) => state.ptt_identifier.unwrap_or(ptt),
+            _ => ptt,
+        };
+        Some(if ptt == expected || grandmaster == expected {
+            GmDesign::AsDesigned
+        } else {
+            GmDesign::Deviation {
+                expected,
+                elected: grandmaster,
+            }
+        })
+    }
+
+    /// Instances electing another grandmaster than expected, by instance
+    pub fn get_gm_deviations(&self) -> &HashMap<(u16, u8), ClockIdentity> {
+        &self.gm_deviations
+    }
+
+    /// Backup grandmaster of each domain number whose primary is the expected
+    /// grandmaster
+    pub fn set_backup_grandmasters(&mut self, backups: HashMap<u8, ClockIdentity>) {
+        self.gm_pair_phases
+            .retain(|domain, _| backups.contains_key(domain));
+        self.backup_grandmasters = backups;
+    }
+
+    pub fn set_gm_pair_drift_threshold(&mut self, threshold: Duration) {
+        self.gm_pair_drift_threshold = threshold;
+    }
+
+    pub fn has_gm_pairs(&self) -> bool {
+        !self.backup_grandmasters.is_empty()
+    }
+
+    /// Primary and backup grandmaster of each domain with a backup, by domain
+    /// number. Silence of the backup is measured on `clock`.
+    pub fn get_gm_pairs(&self, clock: Clock) -> Vec<GmPairStatus<'_>> {
+        let candidates = self.get_bmca_candidates();
+        let mut pairs: Vec<GmPairStatus> = self
+            .backup_grandmasters
+            .iter()
+            .filter_map(|(&domain, &backup)| {
+                let primary = *self.expected_grandmasters.get(&domain)?;
+                let announced = |identity: ClockIdentity| match self
+                    .hosts
+                    .get(&identity)
+                    .map(|host| &host.state)
+                {
+                    Some(PtpHostState::TimeTransmitter(state)) => Some(state),
+                    _ => None,
+                };
+                let (primary_state, backup_state) = (announced(primary), announced(backup));
+
+                // The instance of the domain the pair announces into, in
+                // election order
+                let instance_candidates = candidates
+                    .iter()
+                    .filter(|((_, candidate_domain), _)| *candidate_domain == domain)
+                    .map(|(_, transmitters)| transmitters)
+                    .find(|transmitters| {
+                        transmitters
+                            .iter()
+                            .any(|candidate| [primary, backup].contains(&candidate.clock_identity))
+                    });
+                let elected = instance_candidates
+                    .and_then(|transmitters| transmitters.first())
+                    .map(|winner| winner.state.ptt_identifier.unwrap_or(winner.clock_identity));
+                let successor = instance_candidates
+                    .and_then(|transmitters| gm_pair::successor(transmitters, primary));
+                let backup_silence = self
+                    .hosts
+                    .get(&backup)
+                    .map_or(Duration::ZERO, |host| host.time_since_last_seen(clock));
+
+                Some(GmPairStatus {
+                    domain,
+                    primary,
+                    backup,
+                    elected,
+                    attributes: gm_pair::compare_announces(primary_state, backup_state),
+                    backup_problems: gm_pair::backup_problems(
+                        primary_state,
+                        backup_state,
+                        backup_silence,
+                        successor,
+                        backup,
+                    ),
+                    phase: self
+                        .gm_pair_phases
+                        .get(&domain)
+                        .map(|phase| &phase.phase)
+                        .filter(|phase| phase.current_ns().is_some()),
+                })
+            })
+            .collect();
+        pairs.sort_by_key(|pair| pair.domain);
+        pairs
+    }
+
+    /// Compare the latest Sync of a grandmaster of a pair with the other one's
+    /// and alarm when their phase drifts
+    fn record_gm_pair_phase(&mut self, clock_identity: ClockIdentity, timestamp: SystemTime) {
+        let pairs: Vec<(u8, ClockIdentity, ClockIdentity)> = self
+            .backup_grandmasters
+            .iter()
+            .filter_map(|(&domain, &backup)| {
+                let primary = *self.expected_grandmasters.get(&domain)?;
+                [primary, backup]
+                    .contains(&clock_identity)
+                    .then_some((domain, primary, backup))
+            })
+            .collect();
+
+        for (domain, primary, backup) in pairs {
+            let latest = |identity: ClockIdentity| {
+                self.hosts
+                    .get(&identity)
+                    .and_then(|host| host.sync_drift.latest())
+            };
+            let (primary_sample, backup_sample) = (latest(primary), latest(backup));
+            let Some(drift) = self.gm_pair_phases.entry(domain).or_default().record(
+                primary_sample,
+                backup_sample,
+                self.gm_pair_drift_threshold,
+            ) else {
+                continue;
+            };
+            self.raise_alert(
+                Alert::new(
+                    AlertKind::GmPairDrift,
+                    AlertSeverity::Warning,
+                    timestamp,
+                    format!(
+                        "Domain {}: primary {} and backup {} drift apart, {}",
+                        domain, primary, backup, drift
+                    ),
+                )
+                .with_domain(domain)
+                .with_clock_identity(backup),
+            );
+        }
+    }
+
+    /// Raise an alarm as soon as an instance elects another grandmaster than
+    /// expected, and note when it is back to the design
+    fn check_expected_grandmaster(&mut self, instance: (u16, u8), timestamp: SystemTime) {
+        let instance_name = format_ptp_instance(instance.0, instance.1);
+        match self.get_gm_design(instance) {
+            Some(GmDesign::Deviation { expected, elected }) => {
+                if self.gm_deviations.insert(instance, elected) != Some(elected) {
+                    self.raise_alert(
+                        Alert::new(
+                            AlertKind::GmDeviation,
+                            AlertSeverity::Critical,
+                            timestamp,
+                            format!(
+                                "Domain {}: {} won the election, the design expects {}",
+                                instance_name, elected, expected
+                            ),
+                        )
+                        .with_domain(instance.1)
+                        .with_clock_identity(elected),
+                    );
+                }
+            }
+            Some(GmDesign::AsDesigned) => {
+                if let Some(elected) = self.gm_deviations.remove(&instance) {
+                    let expected = self.expected_grandmasters[&instance.1];
+                    self.raise_alert(
+                        Alert::new(
+                            AlertKind::GmDeviation,
+                            AlertSeverity::Info,
+                            timestamp,
+                            format!(
+                                "Domain {}: {} replaced {} and is grandmaster as designed again",
+                                instance_name, expected, elected
+                            ),
+                        )
+                        .with_domain(instance.1)
+                        .with_clock_identity(expected),
+                    );
+                }
+            }
+            None => {
+                self.gm_deviations.remove(&instance);
+            }
+        }
+    }
+
+    /// Tags of hosts by clock identity, applied to known and new hosts
+    pub fn set_host_tags(&mut self, host_tags: HashMap<String, Vec<String>>) {
+        for host in self.hosts.values_mut() {
+            host.tags = host_tags
+                .get(&host.clock_identity.to_string())
+                .cloned()
+                .unwrap_or_default();
+        }
+        self.host_tags = host_tags;
+        self.hosts_generation += 1;
+    }
+
+    pub fn get_bmca_profile(&self) -> BmcaProfile {
+        self.bmca_profile
+    }
+
+    /// A transmitter as taking part in the BMCA, None for other hosts
+    fn bmca_candidate<'a>(&self, host: &'a PtpHost) -> Option<BmcaCandidate<'a>> {
+        match &host.state {
+            PtpHostState::TimeTransmitter(state) => Some(BmcaCandidate {
+                clock_identity: host.clock_identity,
+                state,
+                local_priority: self
+                    .local_priorities
+                    .get(&host.clock_identity.to_string())
+                    .copied()
+                    .unwrap_or(DEFAULT_LOCAL_PRIORITY),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Announcing transmitters of each PTP instance, best BMCA candidate first
+    pub fn get_bmca_candidates(
+        &self,
+    ) -> std::collections::BTreeMap<(u16, u8), Vec<BmcaCandidate<'_>>> {
+        let mut candidates: std::collections::BTreeMap<_, Vec<_>> =
+            std::collections::BTreeMap::new();
+        for host in self.hosts.values() {
+            if let (Some(instance), Some(candidate)) = (host.instance(), self.bmca_candidate(host))
+                && candidate.state.priority1.is_some()
+            {
+                candidates.entry(instance).or_default().push(candidate);
+            }
+        }
+
+        for ((sdo_id, _), transmitters) in candidates.iter_mut() {
+            let bmca = self.bmca_profile.bmca_for(*sdo_id);
+            transmitters.sort_by(|a, b| bmca.compare(a, b));
+        }
+        candidates
+    }
+
+    /// Alert for the instance of `header` starting to mix PTP minor versions
+    fn check_minor_versions(&mut self, header: &PtpHeader, at: SystemTime) -> Option<Alert> {
+        let instance = (header.sdo_id, header.domain_number);
+        let mixed = MixedMinorVersions::find(
+            self.hosts
+                .values()
+                .filter_map(PtpHost::minor_version_entry)
+                .filter(|(host_instance, _, _)| *host_instance == instance),
+        )
+        .pop();
+        let Some(mixed) = mixed else {
+            self.mixed_minor_versions.remove(&instance);
+            return None;
+        };
+        if !self.mixed_minor_versions.insert(instance) {
+            return None;
+        }
+        tracing::warn!(
+            domain = %format_ptp_instance(instance.0, instance.1),
+            "{}",
+            mixed
+        );
+        Some(
+            Alert::new(
+                AlertKind::MixedMinorVersions,
+                AlertSeverity::Warning,
+                at,
+                mixed.to_string(),
+            )
+            .with_domain(header.domain_number)
+            .with_clock_identity(header.source_port_identity.clock_identity),
+        )
+    }
+
+    /// Instances whose hosts send more than one PTP minor version
+    pub fn get_mixed_minor_versions(&self) -> Vec<MixedMinorVersions> {
+        MixedMinorVersions::find(
+            self.unmuted_hosts()
+                .filter_map(PtpHost::minor_version_entry),
+        )
+    }
+
+    /// Number of hosts per domain number
+    pub fn get_domain_host_counts(&self) -> std::collections::BTreeMap<u8, usize> {
+        let mut counts = std::collections::BTreeMap::new();
+        for domain in self.unmuted_hosts().filter_map(|host| host.domain_number) {
+            *counts.entry(domain).or_default() += 1;
+        }
+        counts
+    }
+
+    pub fn get_interface_bursts(&self, interface: &str) -> Option<&BurstDetector> {
+        self.interface_bursts.get(interface)
+    }
+
+    /// Number of PTP instances (sdoId, domain) with hosts
+    pub fn get_instance_count(&self) -> usize {
+        self.hosts
+            .values()
+            .filter_map(|host| host.instance())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// PTT of the instance with the most hosts and when it took over
+    pub fn get_main_ptt(&self) -> Option<(ClockIdentity, SystemTime)> {
+        let mut host_counts: HashMap<(u16, u8), usize> = HashMap::new();
+        for instance in self.hosts.values().filter_map(|host| host.instance()) {
+            *host_counts.entry(instance).or_default() += 1;
+        }
+
+        let (instance, ptt) = self.bmca_winners.iter().max_by_key(|(instance, _)| {
+            (
+                host_counts.get(*instance).copied(),
+                std::cmp::Reverse(**instance),
+            )
+        })?;
+        Some((*ptt, *self.bmca_winners_since.get(instance)?))
+    }
+
+    /// Estimated share of Sync and Announce messages lost before capture
+    pub fn get_sequence_loss_ratio(&self) -> Option<f64> {
+        self.sequence_loss.loss_ratio()
     }
 
     pub fn get_transmitter_count(&self) -> usize {
-        self.hosts.values().filter(|h| h.is_transmitter()).count()
+        self.unmuted_hosts().filter(|h| h.is_transmitter()).count()
     }
 
     pub fn get_receiver_count(&self) -> usize {
-        self.hosts.values().filter(|h| h.is_receiver()).count()
+        self.unmuted_hosts().filter(|h| h.is_receiver()).count()
+    }
+
+    /// Packets taken from the capture so far
+    pub fn get_received_packet_count(&self) -> u64 {
+        self.received_packets
+    }
+
+    /// Packets whose PTP message could not be parsed
+    pub fn get_rejected_packet_count(&self) -> u64 {
+        self.rejected_packets
+    }
+
+    /// Packets parsed from a body the capture cut short
+    pub fn get_truncated_packet_count(&self) -> u64 {
+        self.truncated_packets
+    }
+
+    /// Hosts seen this session, including expired, evicted and cleared ones
+    pub fn get_discovered_host_count(&self) -> u64 {
+        self.hosts.len() as u64 + self.evicted_host_count + self.removed_host_count
+    }
+
+    /// Whether a poll since the last call left packets waiting
+    pub fn take_backlogged(&mut self) -> bool {
+        std::mem::take(&mut self.backlogged)
+    }
+
+    /// Time spent in capture, parse and tracking since the last call
+    pub fn take_stage_times(&mut self) -> StageTimes {
+        std::mem::take(&mut self.stage_times)
     }
 
-    pub fn get_last_packet_age(&self) -> Duration {
-        Instant::now().duration_since(self.last_packet)
+    pub fn get_last_packet_time(&self) -> Option<SystemTime> {
+        self.last_packet_time
     }
 
     pub fn set_max_packet_history(&mut self, max_history: usize) {
+        self.max_packet_history = max_history;
+        let max_history = if self.lite { 0 } else { max_history };
         for host in self.hosts.values_mut() {
             host.set_max_packet_history(max_history);
         }
@@ -992,12 +3761,82 @@ impl PtpTracker {
     pub fn get_host_packet_history(
         &self,
         clock_identity: ClockIdentity,
-    ) -> Option<Vec<ParsedPacket>> {
+    ) -> Option<Vec<Arc<ParsedPacket>>> {
         self.hosts
             .get(&clock_identity)
             .map(|host| host.get_packet_history())
     }
 
+    /// Find the packet before or after the given one in its message exchange,
+    /// e.g. the Follow_Up for a Sync or the Delay_Req a Delay_Resp answers.
+    /// Announces step to the previous/next Announce from the same host.
+    pub fn find_exchange_packet(
+        &self,
+        packet: &ParsedPacket,
+        step: ExchangeStep,
+    ) -> Option<Arc<ParsedPacket>> {
+        let is_candidate = |candidate: &ParsedPacket| match (packet.ptp, candidate.ptp) {
+            (PtpMessage::Announce(announce), PtpMessage::Announce(other)) => {
+                other.header.source_port_identity == announce.header.source_port_identity
+                    && other.header.domain_number == announce.header.domain_number
+                    && other.header.sdo_id == announce.header.sdo_id
+                    && !Arc::ptr_eq(&candidate.raw, &packet.raw)
+            }
+            _ => packet.ptp.is_exchange_partner(&candidate.ptp),
+        };
+
+        // Delay_Resp and peer delay packets are kept in more than one host's history
+        let mut candidates: Vec<&Arc<ParsedPacket>> = Vec::new();
+        for host in self.hosts.values() {
+            for candidate in host.packet_history.items.iter() {
+                if is_candidate(candidate) && !candidates.iter().any(|c| Arc::ptr_eq(c, candidate))
+                {
+                    candidates.push(candidate);
+                }
+            }
+        }
+
+        let timestamp = packet.raw.timestamp;
+        let distance = |candidate: &ParsedPacket| {
+            candidate
+                .raw
+                .timestamp
+                .duration_since(timestamp)
+                .or_else(|_| timestamp.duration_since(candidate.raw.timestamp))
+                .unwrap_or_default()
+        };
+
+        if let PtpMessage::Announce(_) = packet.ptp {
+            return candidates
+                .into_iter()
+                .filter(|c| match step {
+                    ExchangeStep::Next => c.raw.timestamp >= timestamp,
+                    ExchangeStep::Previous => c.raw.timestamp <= timestamp,
+                })
+                .min_by_key(|c| distance(c))
+                .cloned();
+        }
+
+        let stage = packet.ptp.exchange_stage()?;
+        candidates
+            .into_iter()
+            .filter_map(|c| c.ptp.exchange_stage().map(|s| (s, c)))
+            .filter(|(s, _)| match step {
+                ExchangeStep::Next => *s > stage,
+                ExchangeStep::Previous => *s < stage,
+            })
+            // Closest stage first, then the partner closest in time
+            .min_by_key(|(s, c)| (s.abs_diff(stage), distance(c)))
+            .map(|(_, c)| c.clone())
+    }
+
+    /// Toggle packet history retention for a host, returning the new setting
+    pub fn toggle_host_history(&mut self, clock_identity: ClockIdentity) -> Option<bool> {
+        let host = self.hosts.get_mut(&clock_identity)?;
+        host.set_history_enabled(!host.history_enabled);
+        Some(host.history_enabled)
+    }
+
     pub fn clear_host_packet_history(&mut self, clock_identity: ClockIdentity) {
         if let Some(host) = self.hosts.get_mut(&clock_identity) {
             host.clear_packet_history();
@@ -1011,7 +3850,8 @@ impl PtpTracker {
     }
 
     pub fn get_local_ips(&self) -> Vec<IpAddr> {
-        self.interfaces
+        self.packet_source
+            .interfaces()
             .iter()
             .filter_map(|(_, ip)| ip.map(std::net::IpAddr::V4))
             .collect()
@@ -1019,7 +3859,8 @@ impl PtpTracker {
 
     /// Run the Best Master Clock Algorithm (BMCA) election to determine the best transmitter in each domain
     ///
-    /// This implements IEEE 1588 BMCA which compares transmitters using the following criteria (in order):
+    /// Transmitters are compared with the rules of the selected BMCA profile,
+    /// see the bmca module. The IEEE 1588 default compares (in order):
     /// 1. Priority1 (lower is better)
     /// 2. Clock Class (lower is better)
     /// 3. Clock Accuracy (lower is better)
@@ -1028,7 +3869,7 @@ impl PtpTracker {
     /// 6. Clock Identity (lower is better, used as tiebreaker)
     ///
     /// The algorithm:
-    /// - Groups all transmitters by domain number
+    /// - Groups all transmitters by PTP instance (sdoId and domain number)
     /// - For each domain with multiple transmitters, runs pairwise comparisons
     /// - Marks the best transmitter as the BMCA winner (shown as "PT" in UI)
     /// - Updates all receivers in the domain to select the BMCA winner
@@ -1037,22 +3878,22 @@ impl PtpTracker {
     pub fn run_bmca_election(&mut self) {
         use std::collections::HashMap;
 
-        // Group transmitters by domain
-        let mut domain_transmitters: HashMap<u8, Vec<ClockIdentity>> = HashMap::new();
+        // Group transmitters by PTP instance, different sdoIds must never be compared
+        let mut domain_transmitters: HashMap<(u16, u8), Vec<ClockIdentity>> = HashMap::new();
 
         for (clock_id, host) in &self.hosts {
-            if let (Some(domain), PtpHostState::TimeTransmitter(_)) =
-                (host.domain_number, &host.state)
+            if let (Some(instance), PtpHostState::TimeTransmitter(_)) =
+                (host.instance(), &host.state)
             {
                 domain_transmitters
-                    .entry(domain)
+                    .entry(instance)
                     .or_default()
                     .push(*clock_id);
             }
         }
 
-        // For each domain, find the best transmitter using BMCA
-        for (domain, transmitters) in domain_transmitters {
+        // For each instance, find the best transmitter using BMCA
+        for ((sdo_id, domain), transmitters) in domain_transmitters {
             if transmitters.is_empty() {
                 continue;
             }
@@ -1066,27 +3907,22 @@ impl PtpTracker {
                 }
             }
 
-            // Find the best transmitter by comparing all pairs
+            // Find the best transmitter by comparing all pairs, with the
+            // rules of the selected profile
+            let bmca = self.bmca_profile.bmca_for(sdo_id);
             let mut best_clock_id = transmitters[0];
 
             for &candidate_clock_id in &transmitters[1..] {
-                if let (Some(best_host), Some(candidate_host)) = (
-                    self.hosts.get(&best_clock_id),
-                    self.hosts.get(&candidate_clock_id),
-                ) && let (
-                    PtpHostState::TimeTransmitter(best_state),
-                    PtpHostState::TimeTransmitter(candidate_state),
-                ) = (&best_host.state, &candidate_host.state)
+                if let (Some(best), Some(candidate)) = (
+                    self.hosts
+                        .get(&best_clock_id)
+                        .and_then(|host| self.bmca_candidate(host)),
+                    self.hosts
+                        .get(&candidate_clock_id)
+                        .and_then(|host| self.bmca_candidate(host)),
+                ) && bmca.compare(&candidate, &best) == std::cmp::Ordering::Less
                 {
-                    let comparison_result = candidate_state.compare_for_bmca(
-                        best_state,
-                        candidate_clock_id,
-                        best_clock_id,
-                    );
-
-                    if comparison_result == std::cmp::Ordering::Less {
-                        best_clock_id = candidate_clock_id;
-                    }
+                    best_clock_id = candidate_clock_id;
                 }
             }
 
@@ -1097,17 +3933,173 @@ impl PtpTracker {
                 state.is_bmca_winner = true;
             }
 
-            // Update receivers in this domain to select the BMCA winner as their transmitter
-            self.update_receivers_for_domain(domain, best_clock_id);
+            let timestamp = self
+                .hosts
+                .get(&best_clock_id)
+                .map_or_else(SystemTime::now, |h| h.last_seen);
+            let previous = self.bmca_winners.insert((sdo_id, domain), best_clock_id);
+            if previous != Some(best_clock_id) {
+                self.hosts_generation += 1;
+                tracing::info!(
+                    domain = %format_ptp_instance(sdo_id, domain),
+                    winner = %best_clock_id,
+                    previous = ?previous.map(|previous| previous.to_string()),
+                    profile = ?self.bmca_profile,
+                    "BMCA elected a new PTT"
+                );
+                self.bmca_winners_since.insert((sdo_id, domain), timestamp);
+            }
+            if let Some(previous) = previous
+                && previous != best_clock_id
+            {
+                self.raise_alert(
+                    Alert::new(
+                        AlertKind::PttChanged,
+                        AlertSeverity::Warning,
+                        timestamp,
+                        format!(
+                            "Domain {}: {} replaced {} as PTT",
+                            format_ptp_instance(sdo_id, domain),
+                            best_clock_id,
+                            previous
+                        ),
+                    )
+                    .with_domain(domain)
+                    .with_clock_identity(best_clock_id),
+                );
+            }
+
+            self.check_expected_grandmaster((sdo_id, domain), timestamp);
+
+            if let Some(drill) = &mut self.failover_drill
+                && drill.instance == (sdo_id, domain)
+            {
+                drill.record_election(best_clock_id);
+            }
+
+            // Update receivers in this instance to select the BMCA winner as their transmitter
+            self.update_receivers_for_domain((sdo_id, domain), best_clock_id);
+        }
+    }
+
+    /// Flag receivers whose Delay_Reqs are answered by, or unicast to, a
+    /// transmitter of another grandmaster than the BMCA predicts for their
+    /// domain. Boundary clocks pass on the grandmaster's identity, so receivers
+    /// behind one are not flagged.
+    fn check_receiver_transmitters(&mut self) {
+        let grandmaster_of =
+            |hosts: &HashMap<ClockIdentity, PtpHost>, id: ClockIdentity| match hosts
+                .get(&id)
+                .map(|host| &host.state)
+            {
+                Some(PtpHostState::TimeTransmitter(s)) => s.ptt_identifier.unwrap_or(id),
+                _ => id,
+            };
+
+        let receivers: Vec<ClockIdentity> = self
+            .hosts
+            .values()
+            .filter(|host| host.is_receiver())
+            .map(|host| host.clock_identity)
+            .collect();
+        let mut alerts = Vec::new();
+        for clock_identity in receivers {
+            let host = &self.hosts[&clock_identity];
+            let Some(instance) = host.instance() else {
+                continue;
+            };
+            let Some(&predicted) = self.bmca_winners.get(&instance) else {
+                continue;
+            };
+
+            let followed = host.answered_by.or_else(|| {
+                let destination = host.delay_req_destination?;
+                self.hosts
+                    .values()
+                    .find(|other| {
+                        other.is_transmitter() && other.ip_addresses.contains_key(&destination)
+                    })
+                    .map(|other| other.clock_identity)
+            });
+            let mismatch = followed
+                .filter(|&followed| {
+                    followed != predicted
+                        && grandmaster_of(&self.hosts, followed)
+                            != grandmaster_of(&self.hosts, predicted)
+                })
+                .map(|followed| (followed, predicted));
+
+            let host = self.hosts.get_mut(&clock_identity).unwrap();
+            if let Some((followed, predicted)) = mismatch
+                && host.wrong_transmitter != mismatch
+            {
+                alerts.push(
+                    Alert::new(
+                        AlertKind::WrongTransmitter,
+                        AlertSeverity::Warning,
+                        host.last_seen,
+                        format!(
+                            "{} in domain {} tracks {} but the BMCA predicts {}, stale unicast config or ACL?",
+                            clock_identity,
+                            format_ptp_instance(instance.0, instance.1),
+                            followed,
+                            predicted
+                        ),
+                    )
+                    .with_domain(instance.1)
+                    .with_clock_identity(clock_identity),
+                );
+            }
+            host.wrong_transmitter = mismatch;
+        }
+
+        for alert in alerts {
+            self.raise_alert(alert);
+        }
+    }
+
+    /// Age the selected transmitters of all receivers, after the election has
+    /// renewed them, so that stale associations lose confidence and go away
+    fn decay_transmitter_confidence(&mut self) {
+        let now = self
+            .packet_source
+            .reference_time()
+            .unwrap_or_else(SystemTime::now);
+        let present: HashSet<ClockIdentity> = self
+            .hosts
+            .values()
+            .filter(|host| now.duration_since(host.last_seen).unwrap_or_default() < OBSERVATION_GAP)
+            .map(|host| host.clock_identity)
+            .collect();
+        let mut dropped = false;
+        for host in self.hosts.values_mut() {
+            if let PtpHostState::TimeReceiver(state) = &mut host.state {
+                let transmitter_present = state
+                    .selected_transmitter_identity
+                    .is_some_and(|id| present.contains(&id));
+                dropped |= state.decay_confidence(now, transmitter_present);
+            }
+        }
+        if dropped {
+            self.hosts_generation += 1;
         }
     }
 
-    /// Update all receivers in a domain to select the BMCA winner as their transmitter
-    fn update_receivers_for_domain(&mut self, domain: u8, winner_clock_id: ClockIdentity) {
+    /// Update the receivers in a PTP instance whose delay exchanges haven't
+    /// stopped to select the BMCA winner as their transmitter
+    fn update_receivers_for_domain(&mut self, instance: (u16, u8), winner_clock_id: ClockIdentity) {
+        let now = self
+            .packet_source
+            .reference_time()
+            .unwrap_or_else(SystemTime::now);
         for host in self.hosts.values_mut() {
-            if host.domain_number == Some(domain)
+            if host.instance() == Some(instance)
                 && let PtpHostState::TimeReceiver(ref mut receiver_state) = host.state
+                && !receiver_state.delay_exchanges_stopped(now)
             {
+                if receiver_state.selected_transmitter_identity != Some(winner_clock_id) {
+                    self.hosts_generation += 1;
+                }
                 receiver_state.selected_transmitter_identity = Some(winner_clock_id);
                 receiver_state.selected_transmitter_confidence = 1.0; // High confidence from BMCA
             }
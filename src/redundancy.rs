@@ -0,0 +1,194 @@
+//! Delay asymmetry between redundant (red/blue) networks
+//!
+//! Plants with two independent networks see every Sync of a grandmaster twice,
+//! once on each capture interface. The arrival time difference of the two
+//! copies is the skew between the paths. Some skew is normal, the paths rarely
+//! have the same length, but it should stay where it was: a skew moving away
+//! from its starting point means one path changed, e.g. a switch was replaced
+//! or a link rerouted, and receivers on the two networks no longer agree.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, SystemTime},
+};
+
+use crate::types::ClockIdentity;
+
+/// Copies of one Sync arrive well within this window of each other
+const PENDING_WINDOW: Duration = Duration::from_secs(1);
+/// Skew samples kept per path pair
+const MAX_SKEW_SAMPLES: usize = 128;
+/// Samples averaged for the baseline and for the current skew
+const SKEW_AVERAGE_SAMPLES: usize = 16;
+/// Default change of the skew that counts as the paths diverging
+pub const DEFAULT_SKEW_THRESHOLD: Duration = Duration::from_micros(100);
+
+/// Skew between the two paths a host's Syncs take
+#[derive(Debug, Clone, Default)]
+pub struct PathSkew {
+    /// Arrival on the second interface minus arrival on the first, in ns
+    samples: VecDeque<i64>,
+    /// Mean skew of the first samples
+    baseline_ns: Option<f64>,
+    /// Whether the current divergence has already been reported
+    pub diverged: bool,
+}
+
+impl PathSkew {
+    fn mean(samples: impl Iterator<Item = i64>) -> Option<f64> {
+        let (sum, count) = samples.fold((0i128, 0usize), |(sum, count), sample| {
+            (sum + sample as i128, count + 1)
+        });
+        (count > 0).then(|| sum as f64 / count as f64)
+    }
+
+    /// Record a skew sample. Returns a description when the skew moved further
+    /// than `threshold` from its baseline, once per divergence.
+    pub fn record(&mut self, skew_ns: i64, threshold: Duration) -> Option<String> {
+        self.samples.push_back(skew_ns);
+        if self.samples.len() > MAX_SKEW_SAMPLES {
+            self.samples.pop_front();
+        }
+
+        if self.baseline_ns.is_none() && self.samples.len() >= SKEW_AVERAGE_SAMPLES {
+            self.baseline_ns = Self::mean(self.samples.iter().copied());
+        }
+        let (Some(baseline), Some(current)) = (self.baseline_ns, self.current_ns()) else {
+            return None;
+        };
+
+        if (current - baseline).abs() <= threshold.as_nanos() as f64 {
+            self.diverged = false;
+            return None;
+        }
+        if self.diverged {
+            return None;
+        }
+        self.diverged = true;
+        Some(format!(
+            "path skew moved from {:+.1} µs to {:+.1} µs",
+            baseline / 1000.0,
+            current / 1000.0
+        ))
+    }
+
+    /// Mean of the most recent samples, once there are enough of them
+    pub fn current_ns(&self) -> Option<f64> {
+        if self.samples.len() < SKEW_AVERAGE_SAMPLES {
+            return None;
+        }
+        Self::mean(
+            self.samples
+                .iter()
+                .rev()
+                .take(SKEW_AVERAGE_SAMPLES)
+                .copied(),
+        )
+    }
+
+    /// Mean skew of the first samples, the reference for divergence
+    pub fn baseline_ns(&self) -> Option<f64> {
+        self.baseline_ns
+    }
+
+    /// Mean skew over all kept samples
+    pub fn mean_ns(&self) -> Option<f64> {
+        Self::mean(self.samples.iter().copied())
+    }
+
+    /// Standard deviation of the skew over all kept samples
+    pub fn jitter_ns(&self) -> Option<f64> {
+        let mean = self.mean_ns()?;
+        let variance = self
+            .samples
+            .iter()
+            .map(|&sample| (sample as f64 - mean).powi(2))
+            .sum::<f64>()
+            / self.samples.len() as f64;
+        Some(variance.sqrt())
+    }
+}
+
+/// (sender, sdoId, domain, sequence id) of a Sync
+type SyncKey = (ClockIdentity, u16, u8, u16);
+
+/// Matches the copies of a Sync that arrive on different interfaces
+#[derive(Debug, Default)]
+pub struct SyncArrivals {
+    pending: HashMap<SyncKey, (String, SystemTime)>,
+    order: VecDeque<(SyncKey, SystemTime)>,
+}
+
+impl SyncArrivals {
+    /// Record the arrival of a Sync. When it is a copy of one that arrived on
+    /// another interface, returns the interface pair in name order and the
+    /// arrival time on the second minus the first.
+    pub fn record(
+        &mut self,
+        key: SyncKey,
+        interface: &str,
+        timestamp: SystemTime,
+    ) -> Option<((String, String), i64)> {
+        while let Some(&(old_key, old_timestamp)) = self.order.front() {
+            if timestamp.duration_since(old_timestamp).unwrap_or_default() <= PENDING_WINDOW {
+                break;
+            }
+            self.order.pop_front();
+            if self.pending.get(&old_key).map(|(_, t)| *t) == Some(old_timestamp) {
+                self.pending.remove(&old_key);
+            }
+        }
+
+        match self.pending.get(&key) {
+            Some((first_interface, first_timestamp)) if first_interface != interface => {
+                let delta = match timestamp.duration_since(*first_timestamp) {
+                    Ok(delta) => delta.as_nanos() as i64,
+                    Err(e) => -(e.duration().as_nanos() as i64),
+                };
+                let result = if first_interface.as_str() < interface {
+                    ((first_interface.clone(), interface.to_string()), delta)
+                } else {
+                    ((interface.to_string(), first_interface.clone()), -delta)
+                };
+                self.pending.remove(&key);
+                Some(result)
+            }
+            Some(_) => None,
+            None => {
+                self.pending.insert(key, (interface.to_string(), timestamp));
+                self.order.push_back((key, timestamp));
+                None
+            }
+        }
+    }
+}
+
+#[test]
+fn test_path_skew() {
+    let gm = ClockIdentity::default();
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut arrivals = SyncArrivals::default();
+    let mut skew = PathSkew::default();
+
+    let mut alerts = Vec::new();
+    for seq in 0..64u16 {
+        let sent = start + Duration::from_millis(125 * seq as u64);
+        // The blue path gets 200 µs longer halfway through
+        let blue_delay = if seq < 32 { 30 } else { 230 };
+        assert!(arrivals.record((gm, 0, 0, seq), "red", sent).is_none());
+        let (pair, delta) = arrivals
+            .record(
+                (gm, 0, 0, seq),
+                "blue",
+                sent + Duration::from_micros(blue_delay),
+            )
+            .unwrap();
+        assert_eq!(pair, ("blue".to_string(), "red".to_string()));
+        alerts.extend(skew.record(delta, DEFAULT_SKEW_THRESHOLD));
+    }
+
+    // Reported once, with the skew seen from blue to red
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(skew.current_ns(), Some(-230_000.0));
+    assert!(skew.jitter_ns().unwrap() > 0.0);
+}
@@ -0,0 +1,656 @@
+//! Remote agents and a central viewer
+//!
+//! `ptp-trace agent` captures on the local interfaces and streams every PTP
+//! frame it sees to the viewers connected over TCP, optionally with TLS. A
+//! viewer started with one or more --connect addresses merges the streams of
+//! all agents and runs the tracker on them, so hosts, BMCA, alerts and the
+//! packet history work just as for a local capture. The capture interface of
+//! each packet is prefixed with the agent's site name ("paris/eth0"), which
+//! the Site column shows. Agents stay light: they only capture and forward.
+//!
+//! Agents listen on the loopback address unless told otherwise. Anyone who
+//! can reach an agent could watch the capture, so listening on another
+//! address needs a token: a shared secret the viewers are given too, checked
+//! before anything is streamed. Over plain TCP it can be sniffed, TLS keeps
+//! it secret.
+//!
+//! Wire format: frames of a 4 byte big-endian length followed by the body,
+//! whose first byte is the frame type. The viewer starts with an auth frame
+//! (the token, empty without one), the agent answers with a hello frame
+//! (protocol version, site name) and then sends one packet frame per captured
+//! packet (capture time in ns since the Unix epoch, interface name, Ethernet
+//! frame). The viewer decodes the Ethernet frame itself, so it needs the same
+//! --ports as the agents.
+
+use std::{
+    collections::HashSet,
+    net::SocketAddr,
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter},
+    net::{TcpListener, TcpStream},
+    sync::{broadcast, mpsc},
+};
+use tokio_rustls::{
+    TlsAcceptor, TlsConnector,
+    rustls::{
+        self,
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName, pem::PemObject},
+    },
+};
+
+use crate::{
+    session_summary::SessionSummary,
+    source::{PacketSource, RawPacket, process_ethernet_packet},
+};
+
+/// Port agents listen on by default
+const DEFAULT_AGENT_PORT: u16 = 7319;
+pub const PROTOCOL_VERSION: u8 = 2;
+const FRAME_HELLO: u8 = 0;
+const FRAME_PACKET: u8 = 1;
+const FRAME_AUTH: u8 = 2;
+/// How long a viewer has to send its auth frame
+const AUTH_TIMEOUT: Duration = Duration::from_secs(5);
+/// Larger frames are a corrupt stream, not a packet
+const MAX_FRAME_LENGTH: usize = 65_536;
+/// Pause between connection attempts to an unreachable agent
+pub const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the agent forwards the packets captured since the last round
+const AGENT_POLL_INTERVAL: Duration = Duration::from_millis(5);
+/// Frames buffered per viewer before a slow viewer starts missing packets
+pub const VIEWER_BACKLOG: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    Hello {
+        version: u8,
+        site: String,
+    },
+    Packet {
+        timestamp: SystemTime,
+        interface: String,
+        data: Vec<u8>,
+    },
+    Auth {
+        token: String,
+    },
+}
+
+impl Frame {
+    /// Body with the length prefix
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Frame::Hello { version, site } => {
+                let mut frame = vec![0; 4];
+                frame.push(FRAME_HELLO);
+                frame.push(*version);
+                frame.extend_from_slice(site.as_bytes());
+                finish_frame(frame)
+            }
+            Frame::Packet {
+                timestamp,
+                interface,
+                data,
+            } => encode_packet(*timestamp, interface, data),
+            Frame::Auth { token } => {
+                let mut frame = vec![0; 4];
+                frame.push(FRAME_AUTH);
+                frame.extend_from_slice(token.as_bytes());
+                finish_frame(frame)
+            }
+        }
+    }
+
+    /// Encoded packet frame of a captured packet, without copying it into a
+    /// Frame first
+    pub fn encode_raw_packet(packet: &RawPacket) -> Vec<u8> {
+        encode_packet(packet.timestamp, &packet.interface_name, &packet.data)
+    }
+
+    /// Decode a body without the length prefix
+    pub fn decode(body: &[u8]) -> Result<Self> {
+        match body.split_first() {
+            Some((&FRAME_HELLO, rest)) => {
+                let (&version, site) = rest
+                    .split_first()
+                    .ok_or_else(|| anyhow!("Truncated hello frame"))?;
+                Ok(Frame::Hello {
+                    version,
+                    site: String::from_utf8_lossy(site).into_owned(),
+                })
+            }
+            Some((&FRAME_PACKET, rest)) => {
+                if rest.len() < 9 {
+                    bail!("Truncated packet frame");
+                }
+                let nanos = u64::from_be_bytes(rest[..8].try_into()?);
+                let interface_length = rest[8] as usize;
+                let rest = &rest[9..];
+                if rest.len() < interface_length {
+                    bail!("Truncated packet frame");
+                }
+                Ok(Frame::Packet {
+                    timestamp: UNIX_EPOCH + Duration::from_nanos(nanos),
+                    interface: String::from_utf8_lossy(&rest[..interface_length]).into_owned(),
+                    data: rest[interface_length..].to_vec(),
+                })
+            }
+            Some((&FRAME_AUTH, token)) => Ok(Frame::Auth {
+                token: String::from_utf8_lossy(token).into_owned(),
+            }),
+            Some((kind, _)) => bail!("Unknown frame type {}", kind),
+            None => bail!("Empty frame"),
+        }
+    }
+}
+
+fn encode_packet(timestamp: SystemTime, interface: &str, data: &[u8]) -> Vec<u8> {
+    let interface = &interface.as_bytes()[..interface.len().min(u8::MAX as usize)];
+    let mut frame = Vec::with_capacity(4 + 1 + 8 + 1 + interface.len() + data.len());
+    frame.extend_from_slice(&[0; 4]);
+    frame.push(FRAME_PACKET);
+    let nanos = timestamp
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    frame.extend_from_slice(&nanos.to_be_bytes());
+    frame.push(interface.len() as u8);
+    frame.extend_from_slice(interface);
+    frame.extend_from_slice(data);
+    finish_frame(frame)
+}
+
+/// Fill in the length prefix the frame was started with
+fn finish_frame(mut frame: Vec<u8>) -> Vec<u8> {
+    let length = (frame.len() - 4) as u32;
+    frame[..4].copy_from_slice(&length.to_be_bytes());
+    frame
+}
+
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Frame> {
+    let length = reader.read_u32().await? as usize;
+    if length > MAX_FRAME_LENGTH {
+        bail!("Frame of {} bytes exceeds the limit", length);
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Frame::decode(&body)
+}
+
+/// TLS for agents, from a PEM certificate chain and private key
+pub fn tls_acceptor(cert: &Path, key: &Path) -> Result<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Failed to read certificates from {}", cert.display()))?;
+    let key = PrivateKeyDer::from_pem_file(key)
+        .with_context(|| format!("Failed to read private key from {}", key.display()))?;
+    let config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("Invalid TLS certificate or key")?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// TLS for viewers, trusting agents whose certificates a PEM CA file signed
+pub fn tls_connector(ca: &Path) -> Result<TlsConnector> {
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in CertificateDer::pem_file_iter(ca)
+        .with_context(|| format!("Failed to read CA certificates from {}", ca.display()))?
+    {
+        roots
+            .add(cert.with_context(|| format!("Invalid CA certificate in {}", ca.display()))?)
+            .context("Unusable CA certificate")?;
+    }
+    let config = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+/// Shared secret of an agent and its viewers, the first line of a file
+pub fn read_token(path: &Path) -> Result<String> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read token from {}", path.display()))?;
+    let token = content.lines().next().unwrap_or_default().trim();
+    if token.is_empty() {
+        bail!("No token in {}", path.display());
+    }
+    Ok(token.to_string())
+}
+
+/// Whether two tokens are the same, in a time that doesn't tell how much of
+/// them is
+fn same_token(expected: &str, offered: &str) -> bool {
+    expected.len() == offered.len()
+        && expected
+            .bytes()
+            .zip(offered.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Refuse to stream the capture to anyone who can reach a non-loopback address
+pub fn check_listen_access(listen: SocketAddr, token: bool) -> Result<()> {
+    if !listen.ip().is_loopback() && !token {
+        bail!(
+            "Listening on {} lets anyone who can reach it watch the capture, protect it with --token-file",
+            listen
+        );
+    }
+    Ok(())
+}
+
+/// Capture with `receiver` and stream the packets to every connected viewer
+/// holding `token` until interrupted, then print a summary
+pub async fn run_agent(
+    mut receiver: Box<dyn PacketSource>,
+    listen: SocketAddr,
+    site: String,
+    tls: Option<TlsAcceptor>,
+    token: Option<String>,
+) -> Result<()> {
+    check_listen_access(listen, token.is_some())?;
+    let listener = TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", listen))?;
+    println!(
+        "Agent for site '{}' listening on {}{}",
+        site,
+        listen,
+        if tls.is_some() { " (TLS)" } else { "" }
+    );
+
+    let (frames, _) = broadcast::channel::<Arc<Vec<u8>>>(VIEWER_BACKLOG);
+    let hello = Arc::new(
+        Frame::Hello {
+            version: PROTOCOL_VERSION,
+            site,
+        }
+        .encode(),
+    );
+
+    let lagged = Arc::new(AtomicU64::new(0));
+
+    let viewer_frames = frames.clone();
+    let viewer_lagged = lagged.clone();
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    tracing::info!(%peer, "Viewer connected");
+                    tokio::spawn(serve_viewer(
+                        stream,
+                        peer,
+                        tls.clone(),
+                        token.clone(),
+                        hello.clone(),
+                        viewer_frames.subscribe(),
+                        viewer_lagged.clone(),
+                    ));
+                }
+                Err(e) => tracing::warn!(error = %e, "Failed to accept a viewer"),
+            }
+        }
+    });
+
+    let summary = broadcast_packets(receiver.as_mut(), &frames, &lagged).await?;
+    println!("\n{}", summary);
+    Ok(())
+}
+
+/// Send the packets captured with `receiver` to `frames` until interrupted,
+/// `lagged` counts the frames slow readers missed
+pub async fn broadcast_packets(
+    receiver: &mut dyn PacketSource,
+    frames: &broadcast::Sender<Arc<Vec<u8>>>,
+    lagged: &AtomicU64,
+) -> Result<SessionSummary> {
+    let started = Instant::now();
+    let mut forwarded = 0u64;
+    // Source clock identities, nothing tracks hosts otherwise
+    let mut clock_identities = HashSet::new();
+
+    let mut interval = tokio::time::interval(AGENT_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                while let Some(packet) = receiver.next_packet() {
+                    forwarded += 1;
+                    if let Some(clock_identity) = packet.ptp_payload().get(20..28) {
+                        clock_identities.insert(<[u8; 8]>::try_from(clock_identity)?);
+                    }
+                    // Nobody listening is fine, the packet is just not needed
+                    let _ = frames.send(Arc::new(Frame::encode_raw_packet(&packet)));
+                }
+            }
+            _ = tokio::signal::ctrl_c() => {
+                return Ok(SessionSummary {
+                    duration: started.elapsed(),
+                    packets: forwarded,
+                    dropped: vec![(
+                        "not sent to slow viewers",
+                        lagged.load(Ordering::Relaxed),
+                    )],
+                    hosts: clock_identities.len() as u64,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+}
+
+async fn serve_viewer(
+    stream: TcpStream,
+    peer: SocketAddr,
+    tls: Option<TlsAcceptor>,
+    token: Option<String>,
+    hello: Arc<Vec<u8>>,
+    mut frames: broadcast::Receiver<Arc<Vec<u8>>>,
+    lagged: Arc<AtomicU64>,
+) {
+    let token = token.as_deref();
+    let result = match tls {
+        Some(acceptor) => match acceptor.accept(stream).await {
+            Ok(stream) => {
+                authenticate_and_forward(stream, peer, token, &hello, &mut frames, &lagged).await
+            }
+            Err(e) => Err(e.into()),
+        },
+        None => authenticate_and_forward(stream, peer, token, &hello, &mut frames, &lagged).await,
+    };
+    if let Err(e) = result {
+        tracing::info!(%peer, error = %e, "Viewer disconnected");
+    }
+}
+
+/// Check the viewer's auth frame against `token`, then forward
+async fn authenticate_and_forward<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    peer: SocketAddr,
+    token: Option<&str>,
+    hello: &[u8],
+    frames: &mut broadcast::Receiver<Arc<Vec<u8>>>,
+    lagged: &AtomicU64,
+) -> Result<()> {
+    let offered = match tokio::time::timeout(AUTH_TIMEOUT, read_frame(&mut stream)).await {
+        Ok(Ok(Frame::Auth { token })) => token,
+        Ok(Ok(_)) => bail!("Viewer did not authenticate"),
+        Ok(Err(e)) => return Err(e),
+        Err(_) => bail!("No auth frame from viewer within {:?}", AUTH_TIMEOUT),
+    };
+    if let Some(token) = token
+        && !same_token(token, &offered)
+    {
+        tracing::warn!(%peer, "Viewer rejected, wrong token");
+        bail!("Wrong token");
+    }
+    forward(stream, hello, frames, lagged).await
+}
+
+/// Send the hello and then the frames to one reader until it goes away
+pub async fn forward<S: AsyncWrite + Unpin>(
+    stream: S,
+    hello: &[u8],
+    frames: &mut broadcast::Receiver<Arc<Vec<u8>>>,
+    lagged: &AtomicU64,
+) -> Result<()> {
+    let mut stream = BufWriter::new(stream);
+    stream.write_all(hello).await?;
+    stream.flush().await?;
+    loop {
+        let frame = match frames.recv().await {
+            Ok(frame) => frame,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                tracing::warn!(missed, "Viewer too slow, packets dropped");
+                lagged.fetch_add(missed, Ordering::Relaxed);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        };
+        stream.write_all(&frame).await?;
+        // Send what is queued up in one go
+        while let Ok(frame) = frames.try_recv() {
+            stream.write_all(&frame).await?;
+        }
+        stream.flush().await?;
+    }
+}
+
+/// Connection state of an agent, shown in the viewer's header
+#[derive(Debug, Clone)]
+pub struct AgentStatus {
+    pub address: String,
+    /// Site name from the agent's hello, None before the first connection
+    pub site: Option<String>,
+    pub connected: bool,
+    pub last_error: Option<String>,
+}
+
+pub type AgentStatuses = Arc<Mutex<Vec<AgentStatus>>>;
+
+/// host:port of an agent, with the default port if none is given
+fn agent_address(address: &str) -> String {
+    if address.parse::<SocketAddr>().is_ok()
+        || address
+            .rsplit_once(':')
+            .is_some_and(|(host, port)| !host.contains(':') && port.parse::<u16>().is_ok())
+    {
+        address.to_string()
+    } else if address.contains(':') {
+        // A bare IPv6 address
+        format!(
+            "[{}]:{}",
+            address.trim_matches(['[', ']']),
+            DEFAULT_AGENT_PORT
+        )
+    } else {
+        format!("{}:{}", address, DEFAULT_AGENT_PORT)
+    }
+}
+
+/// Connect to the agents, reconnecting whenever a connection drops. The
+/// packets of all agents arrive on the returned channel.
+pub fn connect_agents(
+    addresses: &[String],
+    tls: Option<TlsConnector>,
+    token: Option<String>,
+) -> (mpsc::UnboundedReceiver<RawPacket>, AgentStatuses) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let statuses: AgentStatuses = Arc::new(Mutex::new(
+        addresses
+            .iter()
+            .map(|address| AgentStatus {
+                address: agent_address(address),
+                site: None,
+                connected: false,
+                last_error: None,
+            })
+            .collect(),
+    ));
+
+    for index in 0..addresses.len() {
+        tokio::spawn(follow_agent(
+            index,
+            tls.clone(),
+            token.clone().unwrap_or_default(),
+            sender.clone(),
+            statuses.clone(),
+        ));
+    }
+    (receiver, statuses)
+}
+
+fn update_status(statuses: &AgentStatuses, index: usize, update: impl FnOnce(&mut AgentStatus)) {
+    if let Ok(mut statuses) = statuses.lock()
+        && let Some(status) = statuses.get_mut(index)
+    {
+        update(status);
+    }
+}
+
+async fn follow_agent(
+    index: usize,
+    tls: Option<TlsConnector>,
+    token: String,
+    sender: mpsc::UnboundedSender<RawPacket>,
+    statuses: AgentStatuses,
+) {
+    let Some(address) = statuses
+        .lock()
+        .ok()
+        .and_then(|statuses| statuses.get(index).map(|status| status.address.clone()))
+    else {
+        return;
+    };
+
+    while !sender.is_closed() {
+        let result = match TcpStream::connect(&address).await {
+            Ok(stream) => match &tls {
+                Some(connector) => {
+                    let host = address
+                        .rsplit_once(':')
+                        .map_or(address.as_str(), |(host, _)| host)
+                        .trim_matches(['[', ']']);
+                    match ServerName::try_from(host.to_string()) {
+                        Ok(name) => match connector.connect(name, stream).await {
+                            Ok(stream) => receive(stream, &token, index, &sender, &statuses).await,
+                            Err(e) => Err(e.into()),
+                        },
+                        Err(e) => Err(anyhow!("Invalid agent name '{}': {}", host, e)),
+                    }
+                }
+                None => receive(stream, &token, index, &sender, &statuses).await,
+            },
+            Err(e) => Err(e.into()),
+        };
+
+        if let Err(e) = result {
+            tracing::warn!(agent = %address, error = %e, "Agent connection lost");
+            update_status(&statuses, index, |status| {
+                status.connected = false;
+                status.last_error = Some(e.to_string());
+            });
+        }
+        tokio::time::sleep(RECONNECT_INTERVAL).await;
+    }
+}
+
+async fn receive<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    token: &str,
+    index: usize,
+    sender: &mpsc::UnboundedSender<RawPacket>,
+    statuses: &AgentStatuses,
+) -> Result<()> {
+    stream
+        .write_all(
+            &Frame::Auth {
+                token: token.to_string(),
+            }
+            .encode(),
+        )
+        .await?;
+    stream.flush().await?;
+
+    let mut stream = BufReader::new(stream);
+    let Frame::Hello { version, site } = read_frame(&mut stream)
+        .await
+        .context("Agent closed the connection, wrong or missing --token-file?")?
+    else {
+        bail!("Agent did not start with a hello");
+    };
+    if version != PROTOCOL_VERSION {
+        bail!(
+            "Agent speaks protocol version {}, this viewer {}",
+            version,
+            PROTOCOL_VERSION
+        );
+    }
+    tracing::info!(%site, "Agent connected");
+    update_status(statuses, index, |status| {
+        status.site = Some(site.clone());
+        status.connected = true;
+        status.last_error = None;
+    });
+
+    loop {
+        let Frame::Packet {
+            timestamp,
+            interface,
+            data,
+        } = read_frame(&mut stream).await?
+        else {
+            bail!("Unexpected frame from agent '{}'", site);
+        };
+        let Some(mut packet) = process_ethernet_packet(&data, &format!("{}/{}", site, interface))
+        else {
+            continue;
+        };
+        packet.timestamp = timestamp;
+        if sender.send(packet).is_err() {
+            // The viewer is shutting down
+            return Ok(());
+        }
+    }
+}
+
+/// Site of a packet captured by an agent, from its interface name
+pub fn site_of(interface_name: &str) -> Option<&str> {
+    interface_name.split_once('/').map(|(site, _)| site)
+}
+
+#[test]
+fn test_frames() {
+    let hello = Frame::Hello {
+        version: PROTOCOL_VERSION,
+        site: "paris".to_string(),
+    };
+    let packet = Frame::Packet {
+        timestamp: UNIX_EPOCH + Duration::from_nanos(1_700_000_000_123_456_789),
+        interface: "eth0".to_string(),
+        data: vec![1, 2, 3],
+    };
+    let auth = Frame::Auth {
+        token: "s3cret".to_string(),
+    };
+    for frame in [hello, packet, auth] {
+        let encoded = frame.encode();
+        let length = u32::from_be_bytes(encoded[..4].try_into().unwrap()) as usize;
+        assert_eq!(length, encoded.len() - 4);
+        assert_eq!(Frame::decode(&encoded[4..]).unwrap(), frame);
+    }
+
+    assert!(Frame::decode(&[FRAME_PACKET, 0, 0]).is_err());
+    assert!(Frame::decode(&[7]).is_err());
+
+    assert_eq!(agent_address("10.0.0.1"), "10.0.0.1:7319");
+    assert_eq!(agent_address("gm.example:9000"), "gm.example:9000");
+    assert_eq!(agent_address("fe80::1"), "[fe80::1]:7319");
+    assert_eq!(agent_address("[fe80::1]:9000"), "[fe80::1]:9000");
+    assert_eq!(site_of("paris/eth0"), Some("paris"));
+    assert_eq!(site_of("eth0"), None);
+}
+
+#[test]
+fn test_agent_access() {
+    assert!(same_token("s3cret", "s3cret"));
+    assert!(!same_token("s3cret", "s3cres"));
+    assert!(!same_token("s3cret", "s3cret2"));
+    assert!(!same_token("s3cret", ""));
+
+    let loopback: SocketAddr = "127.0.0.1:7319".parse().unwrap();
+    let any: SocketAddr = "0.0.0.0:7319".parse().unwrap();
+    assert!(check_listen_access(loopback, false).is_ok());
+    assert!(check_listen_access("[::1]:7319".parse().unwrap(), false).is_ok());
+    assert!(check_listen_access(any, false).is_err());
+    assert!(check_listen_access(any, true).is_ok());
+}
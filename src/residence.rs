@@ -0,0 +1,220 @@
+//! Residence time added by transparent clocks
+//!
+//! Transparent clocks add the time a Sync spent inside them to its
+//! correctionField: one-step TCs in the Sync itself, two-step TCs in the
+//! Follow_Up. The sum of both, per Sync, is the residence time accumulated
+//! between the grandmaster and the capture point. A capture interface further
+//! down a chain of TCs sees a larger sum; the difference between two capture
+//! points for the same Sync is the residence time of the hops between them.
+//! A congested switch shows up as a hop whose residence time spikes.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::types::PtpCorrectionField;
+
+/// Samples kept per capture interface
+const MAX_RESIDENCE_SAMPLES: usize = 64;
+/// Two-step Syncs remembered per interface while waiting for their Follow_Up
+const MAX_PENDING_SYNCS: usize = 8;
+/// Recent samples checked for spikes
+const SPIKE_RECENT_SAMPLES: usize = 8;
+/// A spike is this many times the median residence time of the hop...
+const SPIKE_FACTOR: f64 = 4.0;
+/// ...and at least this much above it, so jitter on an idle TC is ignored
+const MIN_SPIKE_NS: f64 = 1000.0;
+
+fn correction_ns(correction: PtpCorrectionField) -> f64 {
+    correction.value as f64 / (1u64 << 16) as f64
+}
+
+/// Accumulated residence time of each Sync as seen on one capture interface
+#[derive(Debug, Clone, Default)]
+struct CapturePoint {
+    /// Sync correction of two-step Syncs, by sequence id
+    pending: VecDeque<(u16, f64)>,
+    /// (sequence id, accumulated residence time in ns), oldest first
+    samples: VecDeque<(u16, f64)>,
+}
+
+impl CapturePoint {
+    fn push(&mut self, sequence_id: u16, residence_ns: f64) {
+        self.samples.push_back((sequence_id, residence_ns));
+        if self.samples.len() > MAX_RESIDENCE_SAMPLES {
+            self.samples.pop_front();
+        }
+    }
+
+    fn mean(&self) -> f64 {
+        self.samples.iter().map(|(_, ns)| ns).sum::<f64>() / self.samples.len().max(1) as f64
+    }
+}
+
+/// Residence time of the path between two capture points, or between the
+/// grandmaster and the first one
+#[derive(Debug, Clone, PartialEq)]
+pub struct HopResidence {
+    /// Capture interface at the start of the hop, None for the grandmaster
+    pub from: Option<String>,
+    pub to: String,
+    /// Residence time per Sync in ns, oldest first
+    pub samples: Vec<f64>,
+}
+
+impl HopResidence {
+    pub fn mean_ns(&self) -> f64 {
+        self.samples.iter().sum::<f64>() / self.samples.len().max(1) as f64
+    }
+
+    pub fn max_ns(&self) -> f64 {
+        self.samples.iter().copied().fold(0.0, f64::max)
+    }
+
+    fn median_ns(&self) -> f64 {
+        let mut sorted = self.samples.clone();
+        sorted.sort_by(f64::total_cmp);
+        sorted.get(sorted.len() / 2).copied().unwrap_or_default()
+    }
+
+    /// Whether a recent Sync spent far longer in the hop than usual
+    pub fn is_spiking(&self) -> bool {
+        let median = self.median_ns();
+        let threshold = (median * SPIKE_FACTOR).max(median + MIN_SPIKE_NS);
+        self.samples
+            .iter()
+            .rev()
+            .take(SPIKE_RECENT_SAMPLES)
+            .any(|&sample| sample > threshold)
+    }
+}
+
+/// Residence times of a time transmitter's Syncs, per capture interface
+#[derive(Debug, Clone, Default)]
+pub struct ResidenceTimes {
+    points: BTreeMap<String, CapturePoint>,
+}
+
+impl ResidenceTimes {
+    /// Record a Sync. One-step Syncs carry the whole residence time, two-step
+    /// ones wait for their Follow_Up.
+    pub fn record_sync(
+        &mut self,
+        interface: &str,
+        sequence_id: u16,
+        correction: PtpCorrectionField,
+        two_step: bool,
+    ) {
+        let point = self.points.entry(interface.to_string()).or_default();
+        if two_step {
+            point
+                .pending
+                .push_back((sequence_id, correction_ns(correction)));
+            if point.pending.len() > MAX_PENDING_SYNCS {
+                point.pending.pop_front();
+            }
+        } else {
+            point.push(sequence_id, correction_ns(correction));
+        }
+    }
+
+    /// Record a Follow_Up, completing the two-step Sync with the same sequence id
+    pub fn record_follow_up(
+        &mut self,
+        interface: &str,
+        sequence_id: u16,
+        correction: PtpCorrectionField,
+    ) {
+        let Some(point) = self.points.get_mut(interface) else {
+            return;
+        };
+        let Some(index) = point
+            .pending
+            .iter()
+            .position(|(pending_id, _)| *pending_id == sequence_id)
+        else {
+            return;
+        };
+        let (_, sync_ns) = point.pending.remove(index).unwrap_or_default();
+        point.push(sequence_id, sync_ns + correction_ns(correction));
+    }
+
+    /// Whether any transparent clock added residence time
+    pub fn has_transparent_clocks(&self) -> bool {
+        self.points
+            .values()
+            .any(|point| point.samples.iter().any(|&(_, ns)| ns != 0.0))
+    }
+
+    /// Hops ordered from the grandmaster down, capture points sorted by
+    /// accumulated residence time. Hops between capture points only count
+    /// Syncs seen on both.
+    pub fn hops(&self) -> Vec<HopResidence> {
+        let mut points: Vec<_> = self
+            .points
+            .iter()
+            .filter(|(_, point)| !point.samples.is_empty())
+            .collect();
+        points.sort_by(|(_, a), (_, b)| a.mean().total_cmp(&b.mean()));
+
+        let mut hops = Vec::new();
+        let mut previous: Option<(&String, &CapturePoint)> = None;
+        for (interface, point) in points {
+            let samples = match previous {
+                None => point.samples.iter().map(|&(_, ns)| ns).collect(),
+                Some((_, upstream)) => point
+                    .samples
+                    .iter()
+                    .filter_map(|&(sequence_id, ns)| {
+                        upstream
+                            .samples
+                            .iter()
+                            .find(|(upstream_id, _)| *upstream_id == sequence_id)
+                            .map(|(_, upstream_ns)| ns - upstream_ns)
+                    })
+                    .collect(),
+            };
+            hops.push(HopResidence {
+                from: previous.map(|(name, _)| name.clone()),
+                to: interface.clone(),
+                samples,
+            });
+            previous = Some((interface, point));
+        }
+        hops
+    }
+}
+
+#[test]
+fn test_residence_times() {
+    let ns = |ns: i64| PtpCorrectionField::new(ns << 16);
+    let mut residence = ResidenceTimes::default();
+
+    // eth0 sits behind one TC, eth1 behind a second one that gets congested
+    for seq in 0..32u16 {
+        let first_tc = 500;
+        let second_tc = if seq == 30 { 40_000 } else { 800 };
+        residence.record_sync("eth0", seq, ns(100), true);
+        residence.record_sync("eth1", seq, ns(100), true);
+        residence.record_follow_up("eth0", seq, ns(first_tc - 100));
+        residence.record_follow_up("eth1", seq, ns(first_tc + second_tc - 100));
+    }
+    // A Follow_Up without its Sync is ignored
+    residence.record_follow_up("eth0", 100, ns(1000));
+
+    assert!(residence.has_transparent_clocks());
+    let hops = residence.hops();
+    assert_eq!(hops.len(), 2);
+    assert_eq!(hops[0].from, None);
+    assert_eq!(hops[0].to, "eth0");
+    assert_eq!(hops[0].samples.len(), 32);
+    assert_eq!(hops[0].mean_ns(), 500.0);
+    assert!(!hops[0].is_spiking());
+    assert_eq!(hops[1].from.as_deref(), Some("eth0"));
+    assert_eq!(hops[1].max_ns(), 40_000.0);
+    assert!(hops[1].is_spiking());
+
+    // One-step Syncs without any TC on the path
+    let mut direct = ResidenceTimes::default();
+    direct.record_sync("eth0", 1, ns(0), false);
+    assert!(!direct.has_transparent_clocks());
+    assert_eq!(direct.hops()[0].samples, [0.0]);
+}
@@ -0,0 +1,183 @@
+//! Text snapshots of the UI (--screenshot FILE and 'G')
+//!
+//! A frame is rendered into a ratatui buffer like for the terminal and written
+//! out as plain text, or with ANSI escape codes for the colors and styles if
+//! the file name ends in .ans or .ansi (view it with `cat` or `less -R`).
+//! Rendering the same pcap file gives the same snapshot every time, which
+//! makes them usable for documentation and as golden files in UI tests.
+
+use anyhow::{Context, Result};
+use ratatui::{
+    buffer::{Buffer, Cell},
+    style::{Color, Modifier},
+};
+use std::path::Path;
+
+/// Terminal size of --screenshot if not given
+pub const DEFAULT_SCREENSHOT_SIZE: (u16, u16) = (160, 48);
+
+/// Parse a terminal size like 160x48
+pub fn parse_size(text: &str) -> Result<(u16, u16), String> {
+    let invalid = || format!("Invalid size '{}', expected COLUMNSxROWS like 160x48", text);
+    let (width, height) = text.split_once(['x', 'X']).ok_or_else(invalid)?;
+    let width: u16 = width.parse().map_err(|_| invalid())?;
+    let height: u16 = height.parse().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+    Ok((width, height))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenshotFormat {
+    Text,
+    Ansi,
+}
+
+impl ScreenshotFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("ans" | "ansi") => ScreenshotFormat::Ansi,
+            _ => ScreenshotFormat::Text,
+        }
+    }
+}
+
+/// The cells a terminal would show, by row. Cells covered by the preceding
+/// wide character are left out, as ratatui does when drawing: diffing against
+/// a buffer that matches nowhere yields exactly the visible cells.
+fn visible_rows(buffer: &Buffer) -> Vec<Vec<&Cell>> {
+    let mut nothing = Buffer::empty(buffer.area);
+    for cell in nothing.content.iter_mut() {
+        cell.set_symbol("\0");
+    }
+    let mut rows = vec![Vec::new(); buffer.area.height as usize];
+    for (_, y, cell) in nothing.diff(buffer) {
+        rows[(y - buffer.area.y) as usize].push(cell);
+    }
+    rows
+}
+
+/// Plain text, without trailing spaces
+pub fn to_text(buffer: &Buffer) -> String {
+    let mut text = String::new();
+    for row in visible_rows(buffer) {
+        let line: String = row.iter().map(|cell| cell.symbol()).collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+fn color_code(color: Color, background: bool) -> Option<String> {
+    let base = if background { 40 } else { 30 };
+    let code = match color {
+        Color::Reset => return None,
+        Color::Black => base,
+        Color::Red => base + 1,
+        Color::Green => base + 2,
+        Color::Yellow => base + 3,
+        Color::Blue => base + 4,
+        Color::Magenta => base + 5,
+        Color::Cyan => base + 6,
+        Color::Gray => base + 7,
+        Color::DarkGray => base + 60,
+        Color::LightRed => base + 61,
+        Color::LightGreen => base + 62,
+        Color::LightYellow => base + 63,
+        Color::LightBlue => base + 64,
+        Color::LightMagenta => base + 65,
+        Color::LightCyan => base + 66,
+        Color::White => base + 67,
+        Color::Indexed(index) => return Some(format!("{};5;{}", base + 8, index)),
+        Color::Rgb(r, g, b) => return Some(format!("{};2;{};{};{}", base + 8, r, g, b)),
+    };
+    Some(code.to_string())
+}
+
+/// Escape sequence selecting the style of `cell`, starting from a reset
+fn sgr(cell: &Cell) -> String {
+    const MODIFIERS: [(Modifier, &str); 7] = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::SLOW_BLINK, "5"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ];
+    let mut codes = vec!["0".to_string()];
+    for (modifier, code) in MODIFIERS {
+        if cell.modifier.contains(modifier) {
+            codes.push(code.to_string());
+        }
+    }
+    codes.extend(color_code(cell.fg, false));
+    codes.extend(color_code(cell.bg, true));
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+/// Text with SGR escape codes wherever the style changes
+pub fn to_ansi(buffer: &Buffer) -> String {
+    let plain = sgr(&Cell::default());
+    let mut text = String::new();
+    for row in visible_rows(buffer) {
+        let mut current = plain.clone();
+        for cell in row {
+            let style = sgr(cell);
+            if style != current {
+                text.push_str(&style);
+                current = style;
+            }
+            text.push_str(cell.symbol());
+        }
+        if current != plain {
+            text.push_str(&plain);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Write `buffer` to `path` in the format its extension asks for
+pub fn write(buffer: &Buffer, path: &Path) -> Result<()> {
+    let contents = match ScreenshotFormat::from_path(path) {
+        ScreenshotFormat::Text => to_text(buffer),
+        ScreenshotFormat::Ansi => to_ansi(buffer),
+    };
+    std::fs::write(path, contents)
+        .with_context(|| format!("Failed to write screenshot {}", path.display()))
+}
+
+#[test]
+fn test_screenshot() {
+    use ratatui::{layout::Rect, style::Style};
+
+    assert_eq!(parse_size("160x48"), Ok((160, 48)));
+    assert!(parse_size("160").is_err());
+    assert!(parse_size("0x10").is_err());
+    assert_eq!(
+        ScreenshotFormat::from_path(Path::new("docs/tree.ansi")),
+        ScreenshotFormat::Ansi
+    );
+    assert_eq!(
+        ScreenshotFormat::from_path(Path::new("tree.txt")),
+        ScreenshotFormat::Text
+    );
+
+    let mut buffer = Buffer::empty(Rect::new(0, 0, 6, 2));
+    buffer.set_string(
+        0,
+        0,
+        "ab",
+        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+    );
+    buffer.set_string(0, 1, "漢x", Style::default().bg(Color::Rgb(1, 2, 3)));
+
+    // The wide character covers two cells
+    assert_eq!(to_text(&buffer), "ab\n漢x\n");
+    assert_eq!(
+        to_ansi(&buffer),
+        "\x1b[0;1;31mab\x1b[0m    \n\x1b[0;48;2;1;2;3m漢x\x1b[0m   \n"
+    );
+}
@@ -0,0 +1,111 @@
+//! Summary printed when a session ends
+//!
+//! A scripted run (a capture box started by cron, an agent under systemd)
+//! leaves nothing behind unless exports were configured. On quit, or on
+//! SIGINT for the agent, a short summary goes to stdout: how long the session
+//! ran, how much traffic was seen and dropped, the hosts, grandmaster changes
+//! and alerts, and every file that was written.
+
+use std::{collections::BTreeMap, fmt::Display, path::PathBuf, time::Duration};
+
+#[derive(Debug, Clone, Default)]
+pub struct SessionSummary {
+    pub duration: Duration,
+    pub packets: u64,
+    /// Packets not processed, by reason
+    pub dropped: Vec<(&'static str, u64)>,
+    pub hosts: u64,
+    /// PTT changes, None where the BMCA is not run (agent)
+    pub gm_changes: Option<u64>,
+    /// Alerts raised by kind, None where alerts are not evaluated (agent)
+    pub alerts: Option<BTreeMap<String, u64>>,
+    pub files: Vec<PathBuf>,
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{}h {:02}m {:02}s",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+impl Display for SessionSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Session summary")?;
+        writeln!(f, "  Duration:    {}", format_duration(self.duration))?;
+        write!(f, "  Packets:     {} processed", self.packets)?;
+        for (reason, count) in &self.dropped {
+            write!(f, ", {} {}", count, reason)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "  Hosts:       {} discovered", self.hosts)?;
+        if let Some(gm_changes) = self.gm_changes {
+            writeln!(f, "  GM changes:  {}", gm_changes)?;
+        }
+        if let Some(alerts) = &self.alerts {
+            let total: u64 = alerts.values().sum();
+            write!(f, "  Alerts:      {}", total)?;
+            if total > 0 {
+                let kinds: Vec<String> = alerts
+                    .iter()
+                    .map(|(kind, count)| format!("{} {}", kind, count))
+                    .collect();
+                write!(f, " ({})", kinds.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        if self.files.is_empty() {
+            write!(f, "  Files:       none written")
+        } else {
+            write!(f, "  Files:")?;
+            for file in &self.files {
+                write!(f, "\n    {}", file.display())?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[test]
+fn test_session_summary() {
+    let summary = SessionSummary {
+        duration: Duration::from_secs(3723),
+        packets: 1000,
+        dropped: vec![("rejected as malformed", 2), ("duplicates suppressed", 10)],
+        hosts: 12,
+        gm_changes: Some(1),
+        alerts: Some(BTreeMap::from([
+            ("Hop distance changed".to_string(), 2),
+            ("PTT changed".to_string(), 1),
+        ])),
+        files: vec![PathBuf::from("alerts/capture.pcap")],
+    };
+    assert_eq!(
+        summary.to_string(),
+        "Session summary
+  Duration:    1h 02m 03s
+  Packets:     1000 processed, 2 rejected as malformed, 10 duplicates suppressed
+  Hosts:       12 discovered
+  GM changes:  1
+  Alerts:      3 (Hop distance changed 2, PTT changed 1)
+  Files:
+    alerts/capture.pcap"
+    );
+
+    // The agent neither elects nor alerts
+    let summary = SessionSummary {
+        packets: 5,
+        ..Default::default()
+    };
+    assert_eq!(
+        summary.to_string(),
+        "Session summary
+  Duration:    0h 00m 00s
+  Packets:     5 processed
+  Hosts:       0 discovered
+  Files:       none written"
+    );
+}
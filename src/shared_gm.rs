@@ -0,0 +1,203 @@
+//! Grandmasters serving several PTP instances
+//!
+//! One grandmaster often serves several domains or profiles at once, e.g.
+//! SMPTE 2059-2 and AES67 from the same box. Receivers of each instance only
+//! see their own Announces, so a GM that announces a different clockClass or
+//! currentUtcOffset into each of them goes unnoticed until the devices
+//! downstream disagree about the time. Comparing what each grandmaster
+//! identity announces across instances makes that explicit.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
+
+use crate::types::{AnnounceMessage, ClockIdentity, format_ptp_instance};
+
+/// Instances the GM was not announced into for this long are not compared
+const DATASET_EXPIRY: Duration = Duration::from_secs(60);
+
+/// The parts of a grandmaster's dataset that must agree across instances
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnnouncedDataset {
+    pub clock_class: u8,
+    /// currentUtcOffset, None unless marked valid on the PTP timescale
+    pub utc_offset: Option<i16>,
+}
+
+impl AnnouncedDataset {
+    fn from_announce(announce: &AnnounceMessage) -> Self {
+        let flags = &announce.header.flags;
+        Self {
+            clock_class: announce.clock_class.class(),
+            utc_offset: (flags.ptp_timescale() && flags.current_utc_offset_valid())
+                .then_some(announce.current_utc_offset.offset),
+        }
+    }
+
+    /// What differs from another dataset, empty if they agree
+    fn differences(&self, other: &AnnouncedDataset) -> Vec<String> {
+        let mut differences = Vec::new();
+        if self.clock_class != other.clock_class {
+            differences.push(format!(
+                "clockClass {} vs {}",
+                self.clock_class, other.clock_class
+            ));
+        }
+        // An instance on the ARB timescale has no UTC offset to disagree on
+        if let (Some(a), Some(b)) = (self.utc_offset, other.utc_offset)
+            && a != b
+        {
+            differences.push(format!("currentUtcOffset {:+}s vs {:+}s", a, b));
+        }
+        differences
+    }
+}
+
+/// A grandmaster announcing inconsistent datasets into two instances
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GmCollision {
+    pub gm: ClockIdentity,
+    pub instances: [(u16, u8); 2],
+    pub differences: Vec<String>,
+}
+
+impl Display for GmCollision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "GM {} is active in domains {} and {} with {}",
+            self.gm,
+            format_ptp_instance(self.instances[0].0, self.instances[0].1),
+            format_ptp_instance(self.instances[1].0, self.instances[1].1),
+            self.differences.join(", ")
+        )
+    }
+}
+
+/// Dataset per PTP instance (sdoId, domain), with when it was last announced
+type InstanceDatasets = BTreeMap<(u16, u8), (AnnouncedDataset, SystemTime)>;
+/// A grandmaster and two instances, the lower one first
+type InstancePair = (ClockIdentity, (u16, u8), (u16, u8));
+
+/// Latest dataset each grandmaster identity announced into each instance
+#[derive(Debug, Clone, Default)]
+pub struct SharedGrandmasters {
+    datasets: HashMap<ClockIdentity, InstanceDatasets>,
+    // Conflicting instance pairs already reported, until they agree again
+    reported: HashSet<InstancePair>,
+}
+
+impl SharedGrandmasters {
+    /// Record an Announce, returning the conflicts it newly reveals
+    pub fn record(&mut self, announce: &AnnounceMessage, at: SystemTime) -> Vec<GmCollision> {
+        let gm = announce.ptt_identity;
+        let instance = (announce.header.sdo_id, announce.header.domain_number);
+        let dataset = AnnouncedDataset::from_announce(announce);
+        let instances = self.datasets.entry(gm).or_default();
+        instances.insert(instance, (dataset, at));
+
+        let mut collisions = Vec::new();
+        for (other, (other_dataset, seen)) in instances.iter() {
+            if *other == instance {
+                continue;
+            }
+            let pair = if instance < *other {
+                (gm, instance, *other)
+            } else {
+                (gm, *other, instance)
+            };
+            let stale = at.duration_since(*seen).unwrap_or_default() > DATASET_EXPIRY;
+            let differences = other_dataset.differences(&dataset);
+            if stale || differences.is_empty() {
+                self.reported.remove(&pair);
+            } else if self.reported.insert(pair) {
+                collisions.push(GmCollision {
+                    gm,
+                    instances: [*other, instance],
+                    differences,
+                });
+            }
+        }
+        collisions
+    }
+
+    /// Instances a grandmaster currently announces into, with its dataset in each
+    pub fn instances_of(
+        &self,
+        gm: &ClockIdentity,
+        now: SystemTime,
+    ) -> Vec<((u16, u8), AnnouncedDataset)> {
+        self.datasets
+            .get(gm)
+            .into_iter()
+            .flatten()
+            .filter(|(_, (_, seen))| {
+                now.duration_since(*seen).unwrap_or_default() <= DATASET_EXPIRY
+            })
+            .map(|(instance, (dataset, _))| (*instance, *dataset))
+            .collect()
+    }
+}
+
+#[test]
+fn test_shared_grandmasters() {
+    const UTC_VALID: u8 = 0x04;
+    const PTP_TIMESCALE: u8 = 0x08;
+
+    let announce = |domain: u8, class: u8, utc_offset: i16, flags: u8| {
+        let mut data = [0u8; 64];
+        data[0] = 0x0b;
+        data[1] = 0x02;
+        data[2..4].copy_from_slice(&64u16.to_be_bytes());
+        data[4] = domain;
+        data[7] = flags;
+        data[44..46].copy_from_slice(&utc_offset.to_be_bytes());
+        data[48] = class;
+        data[53..61].copy_from_slice(&[0, 1, 2, 0xff, 0xfe, 3, 4, 5]);
+        AnnounceMessage::try_from(&data[..]).unwrap()
+    };
+    let at = |seconds: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+    let valid = UTC_VALID | PTP_TIMESCALE;
+
+    let mut shared = SharedGrandmasters::default();
+    assert!(shared.record(&announce(0, 6, 37, valid), at(0)).is_empty());
+    assert!(
+        shared
+            .record(&announce(127, 6, 37, valid), at(0))
+            .is_empty()
+    );
+    // An ARB timescale instance has no offset to compare
+    assert!(shared.record(&announce(24, 6, 0, 0), at(0)).is_empty());
+
+    let collisions = shared.record(&announce(127, 6, 36, valid), at(1));
+    assert_eq!(collisions.len(), 1);
+    assert_eq!(collisions[0].instances, [(0, 0), (0, 127)]);
+    assert_eq!(
+        collisions[0].to_string(),
+        "GM 00:01:02:ff:fe:03:04:05 is active in domains 0 and 127 with currentUtcOffset +37s vs +36s"
+    );
+    // Reported once, again only after it was resolved
+    assert!(
+        shared
+            .record(&announce(127, 6, 36, valid), at(2))
+            .is_empty()
+    );
+    assert!(
+        shared
+            .record(&announce(127, 6, 37, valid), at(3))
+            .is_empty()
+    );
+    let collisions = shared.record(&announce(24, 7, 0, 0), at(4));
+    assert_eq!(collisions.len(), 2);
+    assert_eq!(collisions[0].differences, ["clockClass 6 vs 7"]);
+
+    // Instances no longer announced into are not compared
+    assert!(
+        shared
+            .record(&announce(5, 248, 37, valid), at(100))
+            .is_empty()
+    );
+    assert_eq!(shared.instances_of(&collisions[0].gm, at(100)).len(), 1);
+}
@@ -0,0 +1,361 @@
+//! A reference receiver for one PTP instance
+//!
+//! With --simulate-receiver, the state machine of a passive, standards
+//! compliant receiver runs on the captured traffic of one PTP instance, as if
+//! an endpoint was plugged in next to the capture interface:
+//! - A transmitter only takes part in the BMCA once it is qualified, i.e. sent
+//!   FOREIGN_MASTER_THRESHOLD Announces within FOREIGN_MASTER_TIME_WINDOW
+//!   announce intervals (IEEE 1588-2019 9.3.2.4.4, 9.3.2.5)
+//! - It drops out when no Announce arrived for ANNOUNCE_RECEIPT_TIMEOUT
+//!   announce intervals (announceReceiptTimeout, 7.7.3.1)
+//! - The best qualified transmitter by the BMCA of the selected profile is the
+//!   parent; without its Syncs for SYNC_RECEIPT_TIMEOUT sync intervals the
+//!   receiver cannot follow it (syncReceiptTimeout, IEEE 802.1AS 10.7.3.1)
+//!
+//! A receiver that was locked and then loses its parent or its Syncs goes
+//! into holdover. This gives installers a reference endpoint: what a
+//! compliant device on this segment would select right now, and whether it
+//! would be in holdover, without bringing one.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    bmca::{Bmca, BmcaCandidate, DEFAULT_LOCAL_PRIORITY},
+    ptp::PtpHostStateTimeTransmitter,
+    types::{AnnounceMessage, ClockIdentity, PtpHeader, format_ptp_instance},
+};
+
+const FOREIGN_MASTER_THRESHOLD: usize = 2;
+/// In announce intervals
+const FOREIGN_MASTER_TIME_WINDOW: u32 = 4;
+/// Default announceReceiptTimeout, in announce intervals
+const ANNOUNCE_RECEIPT_TIMEOUT: u32 = 3;
+/// Default syncReceiptTimeout, in sync intervals
+const SYNC_RECEIPT_TIMEOUT: u32 = 3;
+/// logMessageInterval value meaning "not specified", e.g. in unicast mode
+const UNSPECIFIED_LOG_INTERVAL: i8 = 0x7f;
+
+/// Parse "DOMAIN" (sdoId 0) or "SDOID:DOMAIN", e.g. "24" or "0x100:0"
+pub fn parse_instance(text: &str) -> Result<(u16, u8), String> {
+    let error = || {
+        format!(
+            "Invalid PTP instance '{}', expected DOMAIN or SDOID:DOMAIN",
+            text
+        )
+    };
+    let (sdo_id, domain) = match text.split_once(':') {
+        Some((sdo_id, domain)) => {
+            let sdo_id = match sdo_id.strip_prefix("0x") {
+                Some(hex) => u16::from_str_radix(hex, 16),
+                None => sdo_id.parse(),
+            }
+            .map_err(|_| error())?;
+            (sdo_id, domain)
+        }
+        None => (0, text),
+    };
+    if sdo_id > 0xfff {
+        return Err(error());
+    }
+    Ok((sdo_id, domain.parse().map_err(|_| error())?))
+}
+
+fn interval(log_interval: i8) -> Duration {
+    // Unicast negotiates the rate instead, assume one per second
+    let log_interval = if log_interval == UNSPECIFIED_LOG_INTERVAL {
+        0
+    } else {
+        log_interval.clamp(-7, 7)
+    };
+    Duration::from_secs_f64(2f64.powi(log_interval as i32))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReceiverState {
+    /// No qualified time transmitter
+    Listening,
+    /// A parent is selected, but its Syncs don't arrive
+    Uncalibrated { parent: ClockIdentity },
+    /// Following the parent's Syncs
+    Locked { parent: ClockIdentity },
+    /// Was locked, then lost its parent or the parent's Syncs
+    Holdover {
+        parent: Option<ClockIdentity>,
+        since: SystemTime,
+        reason: &'static str,
+    },
+}
+
+impl ReceiverState {
+    fn was_locked(&self) -> bool {
+        matches!(
+            self,
+            ReceiverState::Locked { .. } | ReceiverState::Holdover { .. }
+        )
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReceiverState::Listening => "LISTENING",
+            ReceiverState::Uncalibrated { .. } => "UNCALIBRATED",
+            ReceiverState::Locked { .. } => "LOCKED",
+            ReceiverState::Holdover { .. } => "HOLDOVER",
+        }
+    }
+}
+
+impl Display for ReceiverState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReceiverState::Listening => write!(f, "listening, no qualified transmitter"),
+            ReceiverState::Uncalibrated { parent } => {
+                write!(f, "selected {}, no Sync from it", parent)
+            }
+            ReceiverState::Locked { parent } => write!(f, "locked to {}", parent),
+            ReceiverState::Holdover { parent, reason, .. } => match parent {
+                Some(parent) => write!(f, "holdover, {} ({})", reason, parent),
+                None => write!(f, "holdover, {}", reason),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ForeignTransmitter {
+    /// Announced attributes, as for the tracker's BMCA
+    state: PtpHostStateTimeTransmitter,
+    announces: VecDeque<SystemTime>,
+    announce_interval: Duration,
+    last_sync: Option<SystemTime>,
+    sync_interval: Duration,
+}
+
+impl ForeignTransmitter {
+    fn is_qualified(&self, now: SystemTime) -> bool {
+        let Some(last) = self.announces.back() else {
+            return false;
+        };
+        let age = |at: &SystemTime| now.duration_since(*at).unwrap_or_default();
+        let window = self.announce_interval * FOREIGN_MASTER_TIME_WINDOW;
+        age(last) < self.announce_interval * ANNOUNCE_RECEIPT_TIMEOUT
+            && self.announces.iter().filter(|at| age(at) < window).count()
+                >= FOREIGN_MASTER_THRESHOLD
+    }
+
+    fn has_sync(&self, now: SystemTime) -> bool {
+        self.last_sync.is_some_and(|last| {
+            now.duration_since(last).unwrap_or_default() < self.sync_interval * SYNC_RECEIPT_TIMEOUT
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct SimulatedReceiver {
+    pub instance: (u16, u8),
+    transmitters: HashMap<ClockIdentity, ForeignTransmitter>,
+    state: ReceiverState,
+    /// When the current state was entered
+    since: Option<SystemTime>,
+}
+
+impl SimulatedReceiver {
+    pub fn new(instance: (u16, u8)) -> Self {
+        Self {
+            instance,
+            transmitters: HashMap::new(),
+            state: ReceiverState::Listening,
+            since: None,
+        }
+    }
+
+    fn is_instance(&self, header: &PtpHeader) -> bool {
+        (header.sdo_id, header.domain_number) == self.instance
+    }
+
+    pub fn record_announce(&mut self, announce: &AnnounceMessage, at: SystemTime) {
+        if !self.is_instance(&announce.header) {
+            return;
+        }
+        let transmitter = self
+            .transmitters
+            .entry(announce.header.source_port_identity.clock_identity)
+            .or_default();
+        transmitter.state.update_from_announce(announce);
+        transmitter.announce_interval = interval(announce.header.log_message_interval.exponent);
+        transmitter.announces.push_back(at);
+        while transmitter.announces.len() > FOREIGN_MASTER_THRESHOLD {
+            transmitter.announces.pop_front();
+        }
+    }
+
+    pub fn record_sync(&mut self, header: &PtpHeader, at: SystemTime) {
+        if !self.is_instance(header) {
+            return;
+        }
+        let transmitter = self
+            .transmitters
+            .entry(header.source_port_identity.clock_identity)
+            .or_default();
+        transmitter.last_sync = Some(at);
+        transmitter.sync_interval = interval(header.log_message_interval.exponent);
+    }
+
+    /// Run the BMCA on the qualified transmitters and update the state.
+    /// Returns the previous state if it changed.
+    pub fn evaluate(&mut self, bmca: &dyn Bmca, now: SystemTime) -> Option<ReceiverState> {
+        let mut qualified: Vec<BmcaCandidate> = self
+            .transmitters
+            .iter()
+            .filter(|(_, transmitter)| transmitter.is_qualified(now))
+            .map(|(clock_identity, transmitter)| BmcaCandidate {
+                clock_identity: *clock_identity,
+                state: &transmitter.state,
+                local_priority: DEFAULT_LOCAL_PRIORITY,
+            })
+            .collect();
+        qualified.sort_by(|a, b| bmca.compare(a, b));
+        let best = qualified.first().map(|candidate| candidate.clock_identity);
+
+        let holdover = |parent, reason| match &self.state {
+            // Keep when the holdover started
+            ReceiverState::Holdover { since, .. } => ReceiverState::Holdover {
+                parent,
+                since: *since,
+                reason,
+            },
+            _ => ReceiverState::Holdover {
+                parent,
+                since: now,
+                reason,
+            },
+        };
+        let state = match best {
+            Some(parent) if self.transmitters[&parent].has_sync(now) => {
+                ReceiverState::Locked { parent }
+            }
+            Some(parent) if self.state.was_locked() => {
+                holdover(Some(parent), "no Sync from the parent")
+            }
+            Some(parent) => ReceiverState::Uncalibrated { parent },
+            None if self.state.was_locked() => holdover(None, "announce receipt timeout"),
+            None => ReceiverState::Listening,
+        };
+
+        if state == self.state {
+            return None;
+        }
+        self.since = Some(now);
+        Some(std::mem::replace(&mut self.state, state))
+    }
+
+    pub fn state(&self) -> &ReceiverState {
+        &self.state
+    }
+
+    /// How long the receiver has been in its state
+    pub fn state_duration(&self, now: SystemTime) -> Option<Duration> {
+        let since = match &self.state {
+            ReceiverState::Holdover { since, .. } => Some(*since),
+            _ => self.since,
+        }?;
+        now.duration_since(since).ok()
+    }
+
+    pub fn instance_name(&self) -> String {
+        format_ptp_instance(self.instance.0, self.instance.1)
+    }
+}
+
+#[test]
+fn test_simulated_receiver() {
+    use crate::{bmca::Ieee1588Bmca, types::PtpMessage};
+
+    assert_eq!(parse_instance("24"), Ok((0, 24)));
+    assert_eq!(parse_instance("0x100:0"), Ok((0x100, 0)));
+    assert!(parse_instance("0x1000:0").is_err());
+    assert!(parse_instance("256").is_err());
+
+    // Announce and Sync once per second in domain 24
+    let message = |message_type: u8, length: usize, sender: u8, priority1: u8| {
+        let mut data = vec![0u8; length];
+        data[0] = message_type;
+        data[1] = 0x02;
+        data[2..4].copy_from_slice(&(length as u16).to_be_bytes());
+        data[4] = 24;
+        data[27] = sender;
+        if length == 64 {
+            data[47] = priority1;
+        }
+        PtpMessage::try_from(&data[..]).unwrap()
+    };
+    let announce = |sender: u8, priority1: u8| match message(0x0b, 64, sender, priority1) {
+        PtpMessage::Announce(announce) => announce,
+        _ => unreachable!(),
+    };
+    let sync = |sender: u8| *message(0x00, 44, sender, 0).header();
+    let at = |seconds: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+    let id = |sender: u8| {
+        announce(sender, 0)
+            .header
+            .source_port_identity
+            .clock_identity
+    };
+
+    let bmca = Ieee1588Bmca;
+    let mut receiver = SimulatedReceiver::new((0, 24));
+    receiver.record_announce(&announce(1, 128), at(0));
+    assert_eq!(receiver.evaluate(&bmca, at(0)), None);
+    assert_eq!(receiver.state(), &ReceiverState::Listening);
+
+    // Qualified with the second Announce, locked with a Sync
+    receiver.record_announce(&announce(1, 128), at(1));
+    receiver.evaluate(&bmca, at(1));
+    assert_eq!(
+        receiver.state(),
+        &ReceiverState::Uncalibrated { parent: id(1) }
+    );
+    receiver.record_sync(&sync(1), at(1));
+    receiver.evaluate(&bmca, at(1));
+    assert_eq!(receiver.state(), &ReceiverState::Locked { parent: id(1) });
+
+    // A better transmitter takes over once qualified
+    receiver.record_announce(&announce(2, 100), at(2));
+    receiver.record_announce(&announce(1, 128), at(2));
+    receiver.record_sync(&sync(1), at(2));
+    receiver.evaluate(&bmca, at(2));
+    assert_eq!(receiver.state(), &ReceiverState::Locked { parent: id(1) });
+    receiver.record_announce(&announce(2, 100), at(3));
+    receiver.record_sync(&sync(2), at(3));
+    receiver.evaluate(&bmca, at(3));
+    assert_eq!(receiver.state(), &ReceiverState::Locked { parent: id(2) });
+
+    // Its Syncs stop: holdover, then all Announces stop too
+    receiver.record_announce(&announce(2, 100), at(5));
+    receiver.record_announce(&announce(2, 100), at(6));
+    let previous = receiver.evaluate(&bmca, at(7));
+    assert_eq!(previous, Some(ReceiverState::Locked { parent: id(2) }));
+    assert!(matches!(
+        receiver.state(),
+        ReceiverState::Holdover { parent: Some(_), since, .. } if *since == at(7)
+    ));
+    receiver.evaluate(&bmca, at(20));
+    assert!(matches!(
+        receiver.state(),
+        ReceiverState::Holdover { parent: None, since, .. } if *since == at(7)
+    ));
+    assert_eq!(
+        receiver.state_duration(at(20)),
+        Some(Duration::from_secs(13))
+    );
+
+    // Other instances are ignored
+    let mut receiver = SimulatedReceiver::new((0, 0));
+    receiver.record_announce(&announce(1, 128), at(0));
+    receiver.record_announce(&announce(1, 128), at(1));
+    receiver.evaluate(&bmca, at(1));
+    assert_eq!(receiver.state(), &ReceiverState::Listening);
+}
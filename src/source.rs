@@ -7,103 +7,336 @@
 use anyhow::Result;
 use pnet::datalink::{self, Channel, Config};
 use pnet::packet::Packet;
-use pnet::packet::ethernet::{EtherTypes, EthernetPacket};
+use pnet::packet::ethernet::EthernetPacket;
 use pnet::packet::ip::IpNextHeaderProtocols;
 use pnet::packet::ipv4::Ipv4Packet;
 use pnet::packet::udp::UdpPacket;
 use socket2::{Domain, Protocol, Socket, Type};
 use std::io;
 use std::net::{IpAddr, Ipv4Addr};
-use std::time::SystemTime;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Instant, SystemTime};
 use tokio::sync::mpsc;
 use tokio::time::Duration;
 
-const PTP_EVENT_PORT: u16 = 319;
-const PTP_GENERAL_PORT: u16 = 320;
+use crate::bond;
+use crate::capture_workers::{self, CaptureOptions, CaptureWorkers, WorkerStats};
+use crate::interface_spec::{self, AvailableInterface, InterfaceSpec};
+use crate::remote::{self, AgentStatuses};
+use crate::synth::{self, SynthStats};
+use crate::types::PtpHeader;
+
+pub const PTP_EVENT_PORT: u16 = 319;
+pub const PTP_GENERAL_PORT: u16 = 320;
 const PTP_MULTICAST_ADDR: &str = "224.0.1.129";
 /// gPTP (generalized Precision Time Protocol) EtherType for Layer 2 transport
 const GPTP_ETHERTYPE: u16 = 0x88f7;
 /// gPTP multicast MAC address (IEEE 802.1AS)
 const GPTP_MULTICAST_MAC: [u8; 6] = [0x01, 0x80, 0xc2, 0x00, 0x00, 0x0e];
+/// IEEE 1588 multicast MAC address for PTP over Ethernet (all but peer delay messages)
+const PTP_L2_MULTICAST_MAC: [u8; 6] = [0x01, 0x1b, 0x19, 0x00, 0x00, 0x00];
+const IPV4_ETHERTYPE: u16 = 0x0800;
+const VLAN_ETHERTYPE: u16 = 0x8100;
+const QINQ_ETHERTYPE: u16 = 0x88a8;
+const MPLS_UNICAST_ETHERTYPE: u16 = 0x8847;
+const MPLS_MULTICAST_ETHERTYPE: u16 = 0x8848;
+const VXLAN_PORT: u16 = 4789;
+const GRE_PROTOCOL_TRANSPARENT_ETHERNET: u16 = 0x6558;
+const GRE_PROTOCOL_ERSPAN_II: u16 = 0x88be;
+const GRE_PROTOCOL_ERSPAN_III: u16 = 0x22eb;
+/// Maximum number of encapsulation layers peeled off a single frame
+const MAX_ENCAPSULATION_DEPTH: usize = 8;
+/// How often interfaces selected with -i are looked for again
+const HOTPLUG_RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+/// How often capture workers read the drop counters of their socket
+const WORKER_STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// UDP ports inspected besides 319 and 320, set with --ports
+static EXTRA_PTP_PORTS: OnceLock<Vec<u16>> = OnceLock::new();
+
+/// Also decode PTP sent to these UDP ports, e.g. 1319/1320 used by some
+/// vendors to get through firewalls. Only the first call has an effect.
+pub fn set_extra_ptp_ports(ports: &[u16]) {
+    let _ = EXTRA_PTP_PORTS.set(ports.to_vec());
+}
+
+/// Whether a UDP port is one of the two IEEE 1588 Annex C ports
+pub fn is_standard_ptp_port(port: u16) -> bool {
+    port == PTP_EVENT_PORT || port == PTP_GENERAL_PORT
+}
+
+fn is_ptp_port(port: u16) -> bool {
+    is_standard_ptp_port(port)
+        || EXTRA_PTP_PORTS
+            .get()
+            .is_some_and(|ports| ports.contains(&port))
+}
 
 #[derive(Debug, Clone)]
 pub struct RawPacket {
     pub timestamp: std::time::SystemTime,
     pub data: Vec<u8>,
+    /// Length of the frame on the wire, more than data holds when the
+    /// capture cut it short (the snaplen of a pcap)
+    pub original_length: usize,
     pub source_addr: Option<std::net::SocketAddr>,
     pub source_mac: [u8; 6],
     pub dest_addr: Option<std::net::SocketAddr>,
     pub dest_mac: [u8; 6],
     pub vlan_id: Option<u16>,
+    /// 802.1p priority of the outer VLAN tag
+    pub vlan_pcp: Option<u8>,
     pub ttl: Option<u8>,
     pub interface_name: String,
-    pub ptp_payload: Vec<u8>,
-}
-
-pub enum PacketSource {
-    Socket {
-        receiver: mpsc::UnboundedReceiver<RawPacket>,
-        interfaces: Vec<(String, Option<Ipv4Addr>)>,
-        _multicast_sockets: Vec<Socket>,
-    },
-    Pcap {
-        packets: Vec<RawPacket>,
-        current_index: usize,
-        last_timestamp: Option<SystemTime>,
-    },
-}
-
-pub struct RawSocketReceiver {
-    source: PacketSource,
-}
-
-impl RawSocketReceiver {
-    pub fn try_recv(&mut self) -> Option<RawPacket> {
-        match &mut self.source {
-            PacketSource::Socket { receiver, .. } => receiver.try_recv().ok(),
-            PacketSource::Pcap {
-                packets,
-                current_index,
-                ..
-            } => {
-                if *current_index < packets.len() {
-                    let packet = packets[*current_index].clone();
-                    *current_index += 1;
-                    Some(packet)
-                } else {
-                    None
-                }
-            }
+    /// Position of the PTP message in data
+    pub ptp_range: std::ops::Range<usize>,
+    pub origin: PacketOrigin,
+    /// Encapsulation layers the PTP frame was found in, outermost first
+    pub encapsulation: Vec<Encapsulation>,
+}
+
+impl RawPacket {
+    /// A packet of just a PTP message, without Ethernet, IP or UDP headers
+    /// and with nothing known about its addresses, e.g. the fixtures of the
+    /// tests. Set other fields with struct update syntax.
+    pub fn from_ptp_payload(
+        payload: &[u8],
+        interface_name: &str,
+        timestamp: std::time::SystemTime,
+    ) -> Self {
+        Self {
+            timestamp,
+            data: payload.to_vec(),
+            original_length: payload.len(),
+            source_addr: None,
+            source_mac: [0; 6],
+            dest_addr: None,
+            dest_mac: [0; 6],
+            vlan_id: None,
+            vlan_pcp: None,
+            ttl: None,
+            interface_name: interface_name.to_string(),
+            ptp_range: 0..payload.len(),
+            origin: PacketOrigin::Captured,
+            encapsulation: Vec::new(),
         }
     }
 
-    pub fn get_interfaces(&self) -> &[(String, Option<Ipv4Addr>)] {
-        match &self.source {
-            PacketSource::Socket { interfaces, .. } => interfaces,
-            PacketSource::Pcap { .. } => &[],
+    pub fn ptp_payload(&self) -> &[u8] {
+        &self.data[self.ptp_range.clone()]
+    }
+
+    /// Whether the capture kept less of the frame than was on the wire
+    pub fn is_cut_short(&self) -> bool {
+        self.original_length > self.data.len()
+    }
+
+    /// Interface name, tagged if the packet was sent by ptp-trace itself
+    pub fn interface_label(&self) -> String {
+        match self.origin.tag() {
+            Some(tag) => format!("{} ({})", self.interface_name, tag),
+            None => self.interface_name.clone(),
         }
     }
+}
 
-    pub fn get_last_timestamp(&self) -> Option<SystemTime> {
-        match &self.source {
-            PacketSource::Socket { .. } => None,
-            PacketSource::Pcap { last_timestamp, .. } => *last_timestamp,
+/// A tunnel or label layer wrapped around a captured PTP frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encapsulation {
+    Mpls { label: u32 },
+    Vxlan { vni: u32 },
+    Gre,
+    Erspan { session_id: Option<u16> },
+}
+
+impl std::fmt::Display for Encapsulation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encapsulation::Mpls { label } => write!(f, "MPLS {}", label),
+            Encapsulation::Vxlan { vni } => write!(f, "VXLAN {}", vni),
+            Encapsulation::Gre => write!(f, "GRE"),
+            Encapsulation::Erspan {
+                session_id: Some(id),
+            } => write!(f, "ERSPAN {}", id),
+            Encapsulation::Erspan { session_id: None } => write!(f, "ERSPAN"),
         }
     }
 }
 
-fn iface_addrs_by_name(ifname: &str) -> io::Result<Option<Ipv4Addr>> {
-    let mut v4: Option<Ipv4Addr> = None;
+/// Where a packet came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketOrigin {
+    /// Captured from the network or read from a pcap file
+    Captured,
+    /// Transmitted by ptp-trace itself
+    Transmitted,
+    /// Would have been transmitted by ptp-trace, but dry-run mode is active
+    DryRun,
+}
 
-    for iface in if_addrs::get_if_addrs().map_err(io::Error::other)? {
-        if iface.name == ifname {
-            match iface.addr {
-                if_addrs::IfAddr::V4(a) if v4.is_none() => v4 = Some(a.ip),
-                _ => {}
-            }
+impl PacketOrigin {
+    /// Tag shown next to packets that did not come from the network
+    pub fn tag(&self) -> Option<&'static str> {
+        match self {
+            PacketOrigin::Captured => None,
+            PacketOrigin::Transmitted => Some("self"),
+            PacketOrigin::DryRun => Some("self, dry-run"),
         }
     }
-    Ok(v4)
+}
+
+/// Where the tracker gets its packets from: live capture, a pcap file,
+/// remote agents, the capture helper, generated traffic, or anything an
+/// embedding application implements it for (stdin streams, ring buffers...)
+///
+/// The tracker polls `next_packet` from the UI loop, it must not block:
+/// sources doing I/O run it in a task and hand the packets over through a
+/// channel, see `ChannelSource`.
+pub trait PacketSource: Send {
+    /// The next packet if one is waiting, None otherwise
+    fn next_packet(&mut self) -> Option<RawPacket>;
+
+    /// Interfaces captured on right now, with their IPv4 address
+    fn interfaces(&self) -> Vec<(String, Option<Ipv4Addr>)> {
+        Vec::new()
+    }
+
+    /// Time the capture is looked at as of, for a recording its last packet;
+    /// None for live sources, which are looked at as of now
+    fn reference_time(&self) -> Option<SystemTime> {
+        None
+    }
+
+    /// Connection state of the agents when viewing remote sites
+    fn remote_agents(&self) -> Option<&AgentStatuses> {
+        None
+    }
+
+    /// Target and generated rate with the synthetic source
+    fn synthetic_stats(&self) -> Option<&SynthStats> {
+        None
+    }
+
+    /// Capture worker threads and their drops, when interfaces are captured
+    /// by several workers or on pinned CPUs
+    fn capture_workers(&self) -> Vec<WorkerStats> {
+        Vec::new()
+    }
+}
+
+/// Packets handed over through a channel by a task doing the actual I/O
+pub struct ChannelSource {
+    receiver: mpsc::UnboundedReceiver<RawPacket>,
+}
+
+impl ChannelSource {
+    pub fn new(receiver: mpsc::UnboundedReceiver<RawPacket>) -> Self {
+        Self { receiver }
+    }
+}
+
+impl PacketSource for ChannelSource {
+    fn next_packet(&mut self) -> Option<RawPacket> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Live capture on network interfaces
+pub struct SocketSource {
+    receiver: mpsc::UnboundedReceiver<RawPacket>,
+    /// Interfaces captured on right now, changes as interfaces come and go
+    interfaces: CaptureInterfaces,
+    workers: CaptureWorkers,
+}
+
+impl PacketSource for SocketSource {
+    fn next_packet(&mut self) -> Option<RawPacket> {
+        self.receiver.try_recv().ok()
+    }
+
+    fn interfaces(&self) -> Vec<(String, Option<Ipv4Addr>)> {
+        self.interfaces.lock().unwrap().clone()
+    }
+
+    fn capture_workers(&self) -> Vec<WorkerStats> {
+        self.workers.lock().unwrap().clone()
+    }
+}
+
+/// Packets read from a recording, all at once
+pub struct PcapSource {
+    packets: std::vec::IntoIter<RawPacket>,
+    last_timestamp: Option<SystemTime>,
+}
+
+impl PcapSource {
+    pub fn new(packets: Vec<RawPacket>) -> Self {
+        Self {
+            last_timestamp: packets.iter().map(|packet| packet.timestamp).max(),
+            packets: packets.into_iter(),
+        }
+    }
+}
+
+impl PacketSource for PcapSource {
+    fn next_packet(&mut self) -> Option<RawPacket> {
+        self.packets.next()
+    }
+
+    fn reference_time(&self) -> Option<SystemTime> {
+        self.last_timestamp
+    }
+}
+
+/// Packets of remote agents, see `remote`
+pub struct RemoteSource {
+    receiver: mpsc::UnboundedReceiver<RawPacket>,
+    agents: AgentStatuses,
+}
+
+impl PacketSource for RemoteSource {
+    fn next_packet(&mut self) -> Option<RawPacket> {
+        self.receiver.try_recv().ok()
+    }
+
+    fn remote_agents(&self) -> Option<&AgentStatuses> {
+        Some(&self.agents)
+    }
+}
+
+/// Generated traffic, see `synth`
+pub struct SyntheticSource {
+    receiver: mpsc::Receiver<RawPacket>,
+    stats: Arc<SynthStats>,
+}
+
+impl PacketSource for SyntheticSource {
+    fn next_packet(&mut self) -> Option<RawPacket> {
+        self.receiver.try_recv().ok()
+    }
+
+    fn synthetic_stats(&self) -> Option<&SynthStats> {
+        Some(&self.stats)
+    }
+}
+
+type CaptureInterfaces = Arc<Mutex<Vec<(String, Option<Ipv4Addr>)>>>;
+
+pub fn available_interfaces() -> Vec<AvailableInterface> {
+    datalink::interfaces()
+        .into_iter()
+        .map(|iface| AvailableInterface {
+            ips: iface.ips.iter().map(|ip| ip.ip()).collect(),
+            name: iface.name,
+        })
+        .collect()
+}
+
+fn interface_exists(interface_name: &str) -> bool {
+    datalink::interfaces()
+        .iter()
+        .any(|iface| iface.name == interface_name)
 }
 
 fn get_all_interface_addrs() -> io::Result<Vec<(String, Option<Ipv4Addr>)>> {
@@ -143,7 +376,7 @@ fn get_all_interface_addrs() -> io::Result<Vec<(String, Option<Ipv4Addr>)>> {
     Ok(interfaces)
 }
 
-fn is_suitable_interface_name(interface_name: &str) -> bool {
+pub fn is_suitable_interface_name(interface_name: &str) -> bool {
     // Skip common virtual interface patterns
     let virtual_prefixes = [
         "veth", "docker", "br-", "virbr", "vmnet", "tun", "tap", "wg", "dummy", "bond", "team",
@@ -159,7 +392,7 @@ fn is_suitable_interface_name(interface_name: &str) -> bool {
     true
 }
 
-fn join_multicast_group(interface_name: &str, interface_addr: Ipv4Addr) -> Result<Socket> {
+pub fn join_multicast_group(interface_name: &str, interface_addr: Ipv4Addr) -> Result<Socket> {
     // Create socket to join the multicast group - keep it alive to maintain membership
     let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
     socket.set_reuse_address(true)?;
@@ -177,143 +410,307 @@ fn join_multicast_group(interface_name: &str, interface_addr: Ipv4Addr) -> Resul
             )
         })?;
 
-    println!(
+    tracing::info!(
         "Joined PTP multicast group {} on interface {} ({})",
-        PTP_MULTICAST_ADDR, interface_name, interface_addr
+        PTP_MULTICAST_ADDR,
+        interface_name,
+        interface_addr
     );
 
     Ok(socket)
 }
 
-fn process_ethernet_packet(packet_data: &[u8], interface_name: &str) -> Option<RawPacket> {
+/// The part of `outer` that `inner`, a slice of it handed out by a pnet
+/// packet view, covers. pnet ties its payloads to the view rather than to the
+/// captured frame. An empty payload may be a static slice outside the frame,
+/// it is taken as the empty start of `outer`.
+fn subslice<'a>(outer: &'a [u8], inner: &[u8]) -> &'a [u8] {
+    (inner.as_ptr() as usize)
+        .checked_sub(outer.as_ptr() as usize)
+        .filter(|_| !inner.is_empty())
+        .and_then(|start| outer.get(start..start.checked_add(inner.len())?))
+        .unwrap_or(&outer[..0])
+}
+
+/// Fields of the innermost frame that carries the PTP message
+struct DecodedFrame<'a> {
+    source_mac: [u8; 6],
+    dest_mac: [u8; 6],
+    /// Outer VLAN tag control information: priority, DEI and VLAN ID
+    vlan_tci: Option<u16>,
+    source_addr: Option<std::net::SocketAddr>,
+    dest_addr: Option<std::net::SocketAddr>,
+    ttl: Option<u8>,
+    /// Within the captured frame
+    ptp_payload: &'a [u8],
+}
+
+pub fn process_ethernet_packet(packet_data: &[u8], interface_name: &str) -> Option<RawPacket> {
+    let mut encapsulation = Vec::new();
+    let frame = decode_ethernet(packet_data, &mut encapsulation)?;
+    // The payload is a slice of the frame, keep its position instead of a copy
+    let ptp_start = frame.ptp_payload.as_ptr() as usize - packet_data.as_ptr() as usize;
+
+    Some(RawPacket {
+        timestamp: SystemTime::now(),
+        data: packet_data.to_vec(),
+        original_length: packet_data.len(),
+        source_addr: frame.source_addr,
+        source_mac: frame.source_mac,
+        dest_addr: frame.dest_addr,
+        dest_mac: frame.dest_mac,
+        vlan_id: frame.vlan_tci.map(|tci| tci & 0x0FFF),
+        vlan_pcp: frame.vlan_tci.map(|tci| (tci >> 13) as u8),
+        ttl: frame.ttl,
+        interface_name: interface_name.to_string(),
+        ptp_range: ptp_start..ptp_start + frame.ptp_payload.len(),
+        origin: PacketOrigin::Captured,
+        encapsulation,
+    })
+}
+
+fn decode_ethernet<'a>(
+    packet_data: &'a [u8],
+    encapsulation: &mut Vec<Encapsulation>,
+) -> Option<DecodedFrame<'a>> {
     let ethernet = EthernetPacket::new(packet_data)?;
+    let source_mac = ethernet.get_source().octets();
+    let dest_mac = ethernet.get_destination().octets();
 
-    let mut vlan_id: Option<u16> = None;
-    let mut payload_data = ethernet.payload();
-    let mut ethertype = ethernet.get_ethertype();
+    let mut vlan_tci: Option<u16> = None;
+    let mut payload_data = subslice(packet_data, ethernet.payload());
+    let mut ethertype = ethernet.get_ethertype().0;
 
     // Handle VLAN tags (802.1Q and 802.1ad QinQ)
-    if ethertype == EtherTypes::Vlan {
+    while ethertype == VLAN_ETHERTYPE || ethertype == QINQ_ETHERTYPE {
         if payload_data.len() < 4 {
             return None;
         }
-        // Extract VLAN ID from the outer VLAN tag (first 12 bits of the TCI field)
-        vlan_id = Some(u16::from_be_bytes([payload_data[0], payload_data[1]]) & 0x0FFF);
-        // Get the inner EtherType
-        let inner_ethertype_val = u16::from_be_bytes([payload_data[2], payload_data[3]]);
-        ethertype = if inner_ethertype_val == 0x0800 {
-            EtherTypes::Ipv4
-        } else if inner_ethertype_val == 0x8100 {
-            // Double VLAN tag (QinQ) - skip inner VLAN tag
-            EtherTypes::Vlan
-        } else {
-            return None; // Only handle IPv4 for now
-        };
-        // Skip the outer VLAN header (4 bytes)
+        // Keep the outer tag, its priority (PCP) says how switches queue the frame
+        if vlan_tci.is_none() {
+            vlan_tci = Some(u16::from_be_bytes([payload_data[0], payload_data[1]]));
+        }
+        // Get the inner EtherType and skip the VLAN header (4 bytes)
+        ethertype = u16::from_be_bytes([payload_data[2], payload_data[3]]);
         payload_data = &payload_data[4..];
+    }
 
-        // Handle inner VLAN tag if present (QinQ)
-        if ethertype == EtherTypes::Vlan {
-            if payload_data.len() < 4 {
+    if encapsulation.len() > MAX_ENCAPSULATION_DEPTH {
+        return None;
+    }
+
+    // Check if this is gPTP (Layer 2), PTP over UDP (Layer 3) or an encapsulation
+    match ethertype {
+        GPTP_ETHERTYPE => {
+            // Handle gPTP (IEEE 802.1AS - Layer 2 transport)
+            // gPTP uses Ethernet frames directly without IP/UDP encapsulation
+
+            // Classify by sdoId rather than assuming every 0x88f7 frame is gPTP.
+            // 802.1AS (any gPTP domain) and CMLDS only use 01:80:c2:00:00:0e, while
+            // IEEE 1588 over Ethernet also uses 01:1b:19:00:00:00. Unicast is always
+            // accepted.
+            let header = PtpHeader::try_from(payload_data).ok()?;
+            let multicast_allowed = dest_mac == GPTP_MULTICAST_MAC
+                || (dest_mac == PTP_L2_MULTICAST_MAC && !header.is_gptp() && !header.is_cmlds());
+            if dest_mac[0] & 0x01 == 0x01 && !multicast_allowed {
                 return None;
             }
-            // For QinQ, we keep the outer VLAN ID but could extract inner if needed
-            // Inner VLAN ID: u16::from_be_bytes([payload_data[0], payload_data[1]]) & 0x0FFF
-            let inner_inner_ethertype_val = u16::from_be_bytes([payload_data[2], payload_data[3]]);
-            ethertype = if inner_inner_ethertype_val == 0x0800 {
-                EtherTypes::Ipv4
-            } else {
-                return None; // Only handle IPv4 for now
-            };
-            // Skip the inner VLAN header (4 bytes)
-            payload_data = &payload_data[4..];
-        }
-    }
-
-    // Check if this is gPTP (Layer 2) or PTP over UDP (Layer 3)
-    if ethertype.0 == GPTP_ETHERTYPE {
-        // Handle gPTP (IEEE 802.1AS - Layer 2 transport)
-        // gPTP uses Ethernet frames directly without IP/UDP encapsulation
-        let source_mac = ethernet.get_source().octets();
-        let dest_mac = ethernet.get_destination().octets();
-
-        // Optional filtering: accept gPTP multicast or any unicast gPTP traffic
-        // gPTP typically uses multicast address 01:80:c2:00:00:0e but can also be unicast
-        if dest_mac != GPTP_MULTICAST_MAC && dest_mac[0] & 0x01 == 0x01 {
-            // Skip non-gPTP multicast packets (but allow unicast)
-            return None;
+
+            // gPTP payload starts directly after ethernet header (and VLAN if present)
+            Some(DecodedFrame {
+                source_mac,
+                dest_mac,
+                vlan_tci,
+                // For gPTP, we don't have IP addresses
+                source_addr: None,
+                dest_addr: None,
+                ttl: None, // No TTL in Layer 2
+                ptp_payload: payload_data,
+            })
+        }
+        IPV4_ETHERTYPE => decode_ipv4(payload_data, source_mac, dest_mac, vlan_tci, encapsulation),
+        MPLS_UNICAST_ETHERTYPE | MPLS_MULTICAST_ETHERTYPE => {
+            decode_mpls(payload_data, source_mac, dest_mac, vlan_tci, encapsulation)
         }
+        // Not PTP or gPTP
+        _ => None,
+    }
+}
 
-        // For gPTP, we don't have IP addresses, so use None
-        let source_addr = None;
-        let dest_addr = None;
+fn decode_mpls<'a>(
+    mut payload_data: &'a [u8],
+    source_mac: [u8; 6],
+    dest_mac: [u8; 6],
+    vlan_tci: Option<u16>,
+    encapsulation: &mut Vec<Encapsulation>,
+) -> Option<DecodedFrame<'a>> {
+    // Pop label stack entries until the bottom-of-stack bit is set
+    loop {
+        if payload_data.len() < 4 || encapsulation.len() > MAX_ENCAPSULATION_DEPTH {
+            return None;
+        }
+        let entry = u32::from_be_bytes([
+            payload_data[0],
+            payload_data[1],
+            payload_data[2],
+            payload_data[3],
+        ]);
+        encapsulation.push(Encapsulation::Mpls { label: entry >> 12 });
+        payload_data = &payload_data[4..];
 
-        // gPTP payload starts directly after ethernet header (and VLAN if present)
-        let ptp_payload = payload_data.to_vec();
+        if entry & 0x100 != 0 {
+            break;
+        }
+    }
 
-        Some(RawPacket {
-            timestamp: SystemTime::now(),
-            data: packet_data.to_vec(),
-            source_addr,
-            source_mac,
-            dest_addr,
-            dest_mac,
-            vlan_id,
-            ttl: None, // No TTL in Layer 2
-            interface_name: interface_name.to_string(),
-            ptp_payload,
-        })
-    } else if ethertype == EtherTypes::Ipv4 {
-        // Handle PTP over UDP (existing code)
-        let ipv4_packet = Ipv4Packet::new(payload_data)?;
+    // MPLS carries no protocol field, so guess from the first nibble
+    match payload_data.first()? >> 4 {
+        4 => decode_ipv4(payload_data, source_mac, dest_mac, vlan_tci, encapsulation),
+        // Ethernet pseudowire with a control word, or without one to a
+        // destination MAC starting with 0, like all PTP multicast MACs
+        0 if payload_data.len() > 4 => {
+            let depth = encapsulation.len();
+            if let Some(frame) = decode_ethernet(&payload_data[4..], encapsulation) {
+                return Some(frame);
+            }
+            encapsulation.truncate(depth);
+            decode_ethernet(payload_data, encapsulation)
+        }
+        // Ethernet pseudowire without control word
+        _ => decode_ethernet(payload_data, encapsulation),
+    }
+}
 
-        // Check if this is UDP
-        if ipv4_packet.get_next_level_protocol() != IpNextHeaderProtocols::Udp {
-            return None;
+fn decode_ipv4<'a>(
+    payload_data: &'a [u8],
+    source_mac: [u8; 6],
+    dest_mac: [u8; 6],
+    vlan_tci: Option<u16>,
+    encapsulation: &mut Vec<Encapsulation>,
+) -> Option<DecodedFrame<'a>> {
+    let ipv4_packet = Ipv4Packet::new(payload_data)?;
+
+    match ipv4_packet.get_next_level_protocol() {
+        IpNextHeaderProtocols::Udp => {}
+        IpNextHeaderProtocols::Gre => {
+            return decode_gre(
+                subslice(payload_data, ipv4_packet.payload()),
+                source_mac,
+                dest_mac,
+                vlan_tci,
+                encapsulation,
+            );
         }
+        _ => return None,
+    }
 
-        let udp_packet = UdpPacket::new(ipv4_packet.payload())?;
+    let udp_packet = UdpPacket::new(ipv4_packet.payload())?;
+    let dest_port = udp_packet.get_destination();
 
-        // Filter for PTP ports
-        let dest_port = udp_packet.get_destination();
-        if dest_port != PTP_EVENT_PORT && dest_port != PTP_GENERAL_PORT {
+    if dest_port == VXLAN_PORT {
+        // VXLAN header: flags (I bit = VNI present), 3 reserved bytes, 24 bit VNI, 1 reserved byte
+        let vxlan = udp_packet.payload();
+        if vxlan.len() < 8 || vxlan[0] & 0x08 == 0 {
             return None;
         }
+        let vni = u32::from_be_bytes([0, vxlan[4], vxlan[5], vxlan[6]]);
+        encapsulation.push(Encapsulation::Vxlan { vni });
+        return decode_ethernet(subslice(payload_data, &vxlan[8..]), encapsulation);
+    }
 
-        let ttl = Some(ipv4_packet.get_ttl());
+    // Filter for PTP ports
+    if !is_ptp_port(dest_port) {
+        return None;
+    }
 
-        let source_mac = ethernet.get_source().octets();
-        let dest_mac = ethernet.get_destination().octets();
-        let source_ip = ipv4_packet.get_source();
-        let dest_ip = ipv4_packet.get_destination();
-        let source_port = udp_packet.get_source();
+    let source_addr = Some(std::net::SocketAddr::V4(std::net::SocketAddrV4::new(
+        ipv4_packet.get_source(),
+        udp_packet.get_source(),
+    )));
+    let dest_addr = Some(std::net::SocketAddr::V4(std::net::SocketAddrV4::new(
+        ipv4_packet.get_destination(),
+        dest_port,
+    )));
+
+    Some(DecodedFrame {
+        source_mac,
+        dest_mac,
+        vlan_tci,
+        source_addr,
+        dest_addr,
+        ttl: Some(ipv4_packet.get_ttl()),
+        // Extract PTP payload
+        ptp_payload: subslice(payload_data, udp_packet.payload()),
+    })
+}
 
-        let source_addr = Some(std::net::SocketAddr::V4(std::net::SocketAddrV4::new(
-            source_ip,
-            source_port,
-        )));
-        let dest_addr = Some(std::net::SocketAddr::V4(std::net::SocketAddrV4::new(
-            dest_ip, dest_port,
-        )));
+fn decode_gre<'a>(
+    gre: &'a [u8],
+    source_mac: [u8; 6],
+    dest_mac: [u8; 6],
+    vlan_tci: Option<u16>,
+    encapsulation: &mut Vec<Encapsulation>,
+) -> Option<DecodedFrame<'a>> {
+    if gre.len() < 4 || encapsulation.len() > MAX_ENCAPSULATION_DEPTH {
+        return None;
+    }
+    let flags = u16::from_be_bytes([gre[0], gre[1]]);
+    let protocol = u16::from_be_bytes([gre[2], gre[3]]);
 
-        // Extract PTP payload
-        let ptp_payload = udp_packet.payload().to_vec();
-
-        Some(RawPacket {
-            timestamp: SystemTime::now(),
-            data: packet_data.to_vec(),
-            source_addr,
-            source_mac,
-            dest_addr,
-            dest_mac,
-            vlan_id,
-            ttl,
-            interface_name: interface_name.to_string(),
-            ptp_payload,
-        })
-    } else {
-        // Not PTP or gPTP
-        None
+    // Only GRE version 0 carries ERSPAN and bridged Ethernet
+    if flags & 0x0007 != 0 {
+        return None;
+    }
+    let has_sequence = flags & 0x1000 != 0;
+    let mut header_len = 4;
+    if flags & 0xC000 != 0 {
+        header_len += 4; // Checksum and offset
+    }
+    if flags & 0x2000 != 0 {
+        header_len += 4; // Key
+    }
+    if has_sequence {
+        header_len += 4;
+    }
+    let inner = gre.get(header_len..)?;
+
+    match protocol {
+        GRE_PROTOCOL_TRANSPARENT_ETHERNET => {
+            encapsulation.push(Encapsulation::Gre);
+            decode_ethernet(inner, encapsulation)
+        }
+        IPV4_ETHERTYPE => {
+            encapsulation.push(Encapsulation::Gre);
+            decode_ipv4(inner, source_mac, dest_mac, vlan_tci, encapsulation)
+        }
+        GRE_PROTOCOL_ERSPAN_II if !has_sequence => {
+            // ERSPAN type I has no ERSPAN header
+            encapsulation.push(Encapsulation::Erspan { session_id: None });
+            decode_ethernet(inner, encapsulation)
+        }
+        GRE_PROTOCOL_ERSPAN_II => {
+            if inner.len() < 8 {
+                return None;
+            }
+            let session_id = u16::from_be_bytes([inner[2], inner[3]]) & 0x03FF;
+            encapsulation.push(Encapsulation::Erspan {
+                session_id: Some(session_id),
+            });
+            decode_ethernet(&inner[8..], encapsulation)
+        }
+        GRE_PROTOCOL_ERSPAN_III => {
+            if inner.len() < 12 {
+                return None;
+            }
+            let session_id = u16::from_be_bytes([inner[2], inner[3]]) & 0x03FF;
+            // The optional platform specific subheader follows if the O flag is set
+            let header_len = if inner[11] & 0x01 != 0 { 20 } else { 12 };
+            encapsulation.push(Encapsulation::Erspan {
+                session_id: Some(session_id),
+            });
+            decode_ethernet(inner.get(header_len..)?, encapsulation)
+        }
+        _ => None,
     }
 }
 
@@ -322,10 +719,11 @@ async fn capture_on_interface(
     sender: mpsc::UnboundedSender<RawPacket>,
     _multicast_socket: Socket,
 ) -> Result<()> {
-    // Find the interface
+    // Find the interface, the member itself for a member of a bond or bridge
+    let device = bond::device_name(&interface_name).to_string();
     let interface = datalink::interfaces()
         .into_iter()
-        .find(|iface| iface.name == interface_name)
+        .find(|iface| iface.name == device)
         .ok_or_else(|| anyhow::anyhow!("Interface {} not found", interface_name))?;
 
     // Create datalink channel
@@ -339,10 +737,6 @@ async fn capture_on_interface(
             ));
         }
         Err(e) => {
-            eprintln!(
-                "Failed to open datalink channel on interface {}: {}",
-                interface_name, e
-            );
             return Err(anyhow::anyhow!(
                 "Failed to open datalink channel on {}: {}",
                 interface_name,
@@ -362,7 +756,10 @@ async fn capture_on_interface(
                 }
             }
             Err(e) => {
-                eprintln!("Error capturing packet on {}: {}", interface_name, e);
+                if !interface_exists(&device) {
+                    return Err(anyhow::anyhow!("Interface {} was removed", interface_name));
+                }
+                tracing::warn!(interface = %interface_name, error = %e, "Error capturing packet");
                 tokio::time::sleep(Duration::from_millis(100)).await;
             }
         }
@@ -374,135 +771,425 @@ async fn capture_on_interface(
     Ok(())
 }
 
-pub async fn create_raw_socket_receiver(ifnames: &[String]) -> Result<RawSocketReceiver> {
+/// Capture on an interface with worker threads in a fanout group, see
+/// `capture_workers`. Returns once all workers stopped.
+async fn capture_with_workers(
+    interface_name: String,
+    sender: mpsc::UnboundedSender<RawPacket>,
+    options: CaptureOptions,
+    workers: CaptureWorkers,
+) -> Result<()> {
+    let device = bond::device_name(&interface_name).to_string();
+    let interface = datalink::interfaces()
+        .into_iter()
+        .find(|iface| iface.name == device)
+        .ok_or_else(|| anyhow::anyhow!("Interface {} not found", interface_name))?;
+    let fanout = options.fanout_option(capture_workers::next_fanout_group());
+
+    let mut handles = Vec::new();
+    for worker in 0..options.worker_count() {
+        let cpu = options.cpu_of(worker);
+        workers
+            .lock()
+            .unwrap()
+            .push(WorkerStats::new(&interface_name, worker, cpu));
+        let (interface, interface_name, sender, workers) = (
+            interface.clone(),
+            interface_name.clone(),
+            sender.clone(),
+            workers.clone(),
+        );
+        handles.push(
+            std::thread::Builder::new()
+                .name(format!("capture-{}-{}", interface_name, worker))
+                .spawn(move || {
+                    capture_worker(
+                        interface,
+                        interface_name,
+                        worker,
+                        cpu,
+                        fanout,
+                        sender,
+                        workers,
+                    )
+                })?,
+        );
+    }
+
+    let results = tokio::task::spawn_blocking(move || {
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("Capture worker panicked")))
+            })
+            .collect::<Vec<_>>()
+    })
+    .await?;
+    workers
+        .lock()
+        .unwrap()
+        .retain(|stats| stats.interface != interface_name);
+    results.into_iter().collect()
+}
+
+/// One worker of `capture_with_workers`, blocking its thread
+fn capture_worker(
+    interface: datalink::NetworkInterface,
+    interface_name: String,
+    worker: usize,
+    cpu: Option<usize>,
+    fanout: datalink::FanoutOption,
+    sender: mpsc::UnboundedSender<RawPacket>,
+    workers: CaptureWorkers,
+) -> Result<()> {
+    if let Some(cpu) = cpu {
+        capture_workers::pin_to_cpu(cpu)
+            .map_err(|e| anyhow::anyhow!("Failed to pin capture worker to CPU {}: {}", cpu, e))?;
+    }
+
+    // The socket is opened here so its drop counters can be read, pnet
+    // binds it and closes it with the channel
+    let socket = capture_workers::open_packet_socket()?;
+    let config = Config {
+        read_timeout: Some(WORKER_STATS_INTERVAL),
+        linux_fanout: Some(fanout),
+        socket_fd: Some(socket),
+        ..Config::default()
+    };
+    let mut rx = match datalink::channel(&interface, config) {
+        Ok(Channel::Ethernet(_, rx)) => rx,
+        Ok(_) => {
+            return Err(anyhow::anyhow!(
+                "Unsupported channel type for interface {}",
+                interface_name
+            ));
+        }
+        Err(e) => {
+            return Err(anyhow::anyhow!(
+                "Failed to open capture worker socket on {}: {}",
+                interface_name,
+                e
+            ));
+        }
+    };
+
+    let mut last_stats = Instant::now();
+    loop {
+        match rx.next() {
+            Ok(packet_data) => {
+                if let Some(raw_packet) = process_ethernet_packet(packet_data, &interface_name)
+                    && sender.send(raw_packet).is_err()
+                {
+                    break;
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                if sender.is_closed() {
+                    break;
+                }
+            }
+            Err(e) => {
+                if !interface_exists(&interface.name) {
+                    return Err(anyhow::anyhow!("Interface {} was removed", interface_name));
+                }
+                tracing::warn!(interface = %interface_name, worker, error = %e, "Error capturing packet");
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+
+        if last_stats.elapsed() >= WORKER_STATS_INTERVAL {
+            last_stats = Instant::now();
+            match capture_workers::packet_statistics(socket) {
+                Ok((packets, drops)) => {
+                    if let Some(stats) =
+                        workers.lock().unwrap().iter_mut().find(|stats| {
+                            stats.interface == interface_name && stats.worker == worker
+                        })
+                    {
+                        stats.packets += packets;
+                        stats.drops += drops;
+                    }
+                }
+                Err(e) => {
+                    tracing::debug!(interface = %interface_name, worker, error = %e, "Could not read socket statistics");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Start capturing on an interface and keep it in the list of capture
+/// interfaces for as long as it exists
+fn start_capture(
+    interface_name: String,
+    interface_addr: Option<Ipv4Addr>,
+    sender: &mpsc::UnboundedSender<RawPacket>,
+    interfaces: &CaptureInterfaces,
+    options: &CaptureOptions,
+    workers: &CaptureWorkers,
+) {
+    // Members of a bond or bridge share the address of their master, the
+    // group is joined once for all of them
+    let joined = interface_addr.is_some()
+        && interfaces
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(_, addr)| *addr == interface_addr);
+
+    // Try to join multicast group if interface has an IP address, unless
+    // nothing may be sent
+    let multicast_socket = if let Some(interface_addr) =
+        interface_addr.filter(|_| !joined && !options.offline)
+    {
+        match join_multicast_group(&interface_name, interface_addr) {
+            Ok(socket) => socket,
+            Err(e) => {
+                tracing::warn!(interface = %interface_name, error = %e, "Could not join multicast group");
+                // Create a dummy socket for interfaces without multicast capability
+                Socket::new(
+                    socket2::Domain::IPV4,
+                    socket2::Type::DGRAM,
+                    Some(socket2::Protocol::UDP),
+                )
+                .unwrap()
+            }
+        }
+    } else {
+        // Create a dummy socket for interfaces without IP addresses, with
+        // the group already joined or offline
+        Socket::new(
+            socket2::Domain::IPV4,
+            socket2::Type::DGRAM,
+            Some(socket2::Protocol::UDP),
+        )
+        .unwrap()
+    };
+
+    interfaces
+        .lock()
+        .unwrap()
+        .push((interface_name.clone(), interface_addr));
+    let sender = sender.clone();
+    let interfaces = interfaces.clone();
+    let options = options.clone();
+    let workers = workers.clone();
+    tokio::spawn(async move {
+        // Stagger startup to reduce resource contention
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let result = if options.use_workers() {
+            // The group stays joined through the socket while the workers run
+            let _multicast_socket = multicast_socket;
+            capture_with_workers(interface_name.clone(), sender, options, workers).await
+        } else {
+            capture_on_interface(interface_name.clone(), sender, multicast_socket).await
+        };
+        if let Err(e) = result {
+            tracing::error!(interface = %interface_name, error = %e, "Packet capture stopped");
+        }
+        // Forget a removed interface so it is picked up again when it returns
+        if !interface_exists(bond::device_name(&interface_name)) {
+            interfaces
+                .lock()
+                .unwrap()
+                .retain(|(name, _)| *name != interface_name);
+        }
+    });
+}
+
+/// Start capturing on interfaces matching the -i specs as they appear
+async fn watch_interfaces(
+    specs: Vec<InterfaceSpec>,
+    members: bool,
+    sender: mpsc::UnboundedSender<RawPacket>,
+    interfaces: CaptureInterfaces,
+    options: CaptureOptions,
+    workers: CaptureWorkers,
+) {
+    let mut rescan = tokio::time::interval(HOTPLUG_RESCAN_INTERVAL);
+    loop {
+        rescan.tick().await;
+        if sender.is_closed() {
+            break;
+        }
+        let available = available_interfaces();
+        for interface in interface_spec::resolve(&specs, &available) {
+            for name in bond::capture_names(Path::new(bond::SYSFS_NET), &interface.name, members) {
+                let capturing = interfaces
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .any(|(capturing, _)| *capturing == name);
+                if !capturing {
+                    tracing::info!(interface = %name, "Interface appeared, starting capture");
+                    start_capture(
+                        name,
+                        interface.ipv4(),
+                        &sender,
+                        &interfaces,
+                        &options,
+                        &workers,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Capture on the interfaces selected by names, patterns or addresses (see
+/// `interface_spec`), or on all suitable interfaces if none are given. With
+/// `members`, bonds and bridges are captured on their members (see `bond`).
+/// `options` sets the worker threads of each interface (see
+/// `capture_workers`).
+pub async fn create_raw_socket_receiver(
+    ifnames: &[String],
+    members: bool,
+    options: &CaptureOptions,
+) -> Result<Box<dyn PacketSource>> {
+    if options.use_workers() && !cfg!(target_os = "linux") {
+        return Err(anyhow::anyhow!(
+            "Capture workers and CPU pinning are only supported on Linux"
+        ));
+    }
+    let specs = ifnames
+        .iter()
+        .map(|ifname| InterfaceSpec::parse(ifname).map_err(anyhow::Error::msg))
+        .collect::<Result<Vec<_>>>()?;
+
     // Get interfaces to monitor
-    let target_interfaces = if ifnames.is_empty() {
+    let target_interfaces = if specs.is_empty() {
         // Default to all available interfaces
         get_all_interface_addrs()?
     } else {
-        // Use specified interfaces
-        let mut interfaces = Vec::new();
-        for ifname in ifnames {
-            let iface_v4 = iface_addrs_by_name(ifname)?;
-            interfaces.push((ifname.clone(), iface_v4));
+        let available = available_interfaces();
+        for (ifname, spec) in ifnames.iter().zip(&specs) {
+            if !available.iter().any(|interface| spec.matches(interface)) {
+                println!(
+                    "No interface matches {} yet, waiting for one to appear",
+                    ifname
+                );
+            }
         }
-        interfaces
+        interface_spec::resolve(&specs, &available)
+            .into_iter()
+            .map(|interface| (interface.name.clone(), interface.ipv4()))
+            .collect::<Vec<_>>()
     };
+    let target_interfaces: Vec<_> = target_interfaces
+        .into_iter()
+        .flat_map(|(name, ipv4)| {
+            bond::capture_names(Path::new(bond::SYSFS_NET), &name, members)
+                .into_iter()
+                .map(move |name| (name, ipv4))
+        })
+        .collect();
 
-    if target_interfaces.is_empty() {
+    if target_interfaces.is_empty() && specs.is_empty() {
         return Err(anyhow::anyhow!(
             "No suitable interfaces available for PTP monitoring"
         ));
     }
 
-    println!(
-        "Starting live capture on: {}",
-        target_interfaces
-            .iter()
-            .map(|(name, _)| name.as_str())
-            .collect::<Vec<_>>()
-            .join(", ")
-    );
+    if !target_interfaces.is_empty() {
+        println!(
+            "Starting live capture on: {}",
+            target_interfaces
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
 
     let (sender, receiver) = mpsc::unbounded_channel();
 
     // Set up multicast group membership and start packet capture for each interface
-    let mut multicast_sockets = Vec::new();
-    for (interface_name, interface_addr) in &target_interfaces {
-        let sender_clone = sender.clone();
-        let interface_name_clone = interface_name.clone();
-
-        // Try to join multicast group if interface has an IP address
-        let multicast_socket = if let Some(interface_addr) = interface_addr {
-            match join_multicast_group(interface_name, *interface_addr) {
-                Ok(socket) => {
-                    let socket_clone = socket.try_clone().unwrap();
-                    multicast_sockets.push(socket);
-                    socket_clone
-                }
-                Err(e) => {
-                    eprintln!(
-                        "Warning: Could not join multicast group on {}: {}",
-                        interface_name, e
-                    );
-                    // Create a dummy socket for interfaces without multicast capability
-                    Socket::new(
-                        socket2::Domain::IPV4,
-                        socket2::Type::DGRAM,
-                        Some(socket2::Protocol::UDP),
-                    )
-                    .unwrap()
-                }
-            }
-        } else {
-            // Create a dummy socket for interfaces without IP addresses
-            Socket::new(
-                socket2::Domain::IPV4,
-                socket2::Type::DGRAM,
-                Some(socket2::Protocol::UDP),
-            )
-            .unwrap()
-        };
-
-        tokio::spawn(async move {
-            // Stagger startup to reduce resource contention
-            tokio::time::sleep(Duration::from_millis(200)).await;
+    let interfaces = CaptureInterfaces::default();
+    let workers = CaptureWorkers::default();
+    for (interface_name, interface_addr) in target_interfaces {
+        start_capture(
+            interface_name,
+            interface_addr,
+            &sender,
+            &interfaces,
+            options,
+            &workers,
+        );
+    }
 
-            if let Err(e) =
-                capture_on_interface(interface_name_clone.clone(), sender_clone, multicast_socket)
-                    .await
-            {
-                eprintln!("Packet capture error on {}: {}", interface_name_clone, e);
-            }
-        });
+    // Interfaces selected with -i are followed as they come and go
+    if !specs.is_empty() {
+        tokio::spawn(watch_interfaces(
+            specs,
+            members,
+            sender,
+            interfaces.clone(),
+            options.clone(),
+            workers.clone(),
+        ));
     }
 
-    Ok(RawSocketReceiver {
-        source: PacketSource::Socket {
-            receiver,
-            interfaces: target_interfaces,
-            _multicast_sockets: multicast_sockets,
-        },
-    })
+    Ok(Box::new(SocketSource {
+        receiver,
+        interfaces,
+        workers,
+    }))
+}
+
+/// Receive the packets of remote agents, see `remote`
+pub fn create_remote_receiver(
+    addresses: &[String],
+    tls_ca: Option<&Path>,
+    token: Option<String>,
+) -> Result<Box<dyn PacketSource>> {
+    let tls = tls_ca.map(remote::tls_connector).transpose()?;
+    let (receiver, agents) = remote::connect_agents(addresses, tls, token);
+    Ok(Box::new(RemoteSource { receiver, agents }))
+}
+
+/// Receive the packets of a capture helper, see `helper`
+#[cfg(unix)]
+pub fn create_helper_receiver(socket: &Path) -> Box<dyn PacketSource> {
+    Box::new(ChannelSource::new(crate::helper::connect(
+        socket.to_path_buf(),
+    )))
+}
+
+/// Generate synthetic traffic, see `synth`
+pub fn create_synthetic_receiver(rate: u64, hosts: u16) -> Result<Box<dyn PacketSource>> {
+    let (receiver, stats) = synth::spawn(rate, hosts)?;
+    Ok(Box::new(SyntheticSource { receiver, stats }))
 }
 
-pub async fn create_pcap_receiver(pcap_path: &str) -> Result<RawSocketReceiver> {
+/// Call f with the capture time, data and length on the wire of each frame of
+/// a pcap or pcapng file, returning whether it was pcapng
+pub fn for_each_pcap_frame(
+    pcap_path: &str,
+    mut f: impl FnMut(SystemTime, &[u8], usize),
+) -> Result<bool> {
     use pcap_file::pcap::PcapReader;
     use pcap_file::pcapng::PcapNgReader;
     use std::fs::File;
 
-    let mut packets: Vec<RawPacket> = Vec::new();
-    let mut last_timestamp: Option<SystemTime> = None;
-
     let file = File::open(pcap_path)?;
 
     // Try to read as PCAPNG first, then as regular PCAP
     if let Ok(mut pcapng_reader) = PcapNgReader::new(file) {
-        println!("Reading as PCAPNG format");
-
+        // Simple packet blocks have no timestamp, take the one before
+        let mut last_capture_time = SystemTime::UNIX_EPOCH;
         while let Some(block) = pcapng_reader.next_block() {
             match block {
                 Ok(pcap_file::pcapng::Block::EnhancedPacket(epb)) => {
-                    let packet_data = epb.data;
-                    if let Some(raw_packet) = process_ethernet_packet(&packet_data, "pcap") {
-                        if last_timestamp.is_none()
-                            || raw_packet.timestamp > last_timestamp.unwrap()
-                        {
-                            last_timestamp = Some(raw_packet.timestamp);
-                        }
-                        packets.push(raw_packet);
-                    }
+                    last_capture_time = SystemTime::UNIX_EPOCH + epb.timestamp;
+                    f(last_capture_time, &epb.data, epb.original_len as usize);
                 }
                 Ok(pcap_file::pcapng::Block::SimplePacket(spb)) => {
-                    let packet_data = spb.data;
-                    if let Some(raw_packet) = process_ethernet_packet(&packet_data, "pcap") {
-                        if last_timestamp.is_none()
-                            || raw_packet.timestamp > last_timestamp.unwrap()
-                        {
-                            last_timestamp = Some(raw_packet.timestamp);
-                        }
-                        packets.push(raw_packet);
-                    }
+                    f(last_capture_time, &spb.data, spb.original_len as usize);
                 }
                 Ok(_) => {
                     // Other block types (section header, interface description, etc.)
@@ -514,32 +1201,42 @@ pub async fn create_pcap_receiver(pcap_path: &str) -> Result<RawSocketReceiver>
                 }
             }
         }
+        Ok(true)
     } else {
-        println!("Failed to read as PCAPNG, trying regular PCAP format");
-
         // Re-open file for PCAP reading
         let file = File::open(pcap_path)?;
         let mut pcap_reader = PcapReader::new(file)?;
 
         while let Some(pkt) = pcap_reader.next_packet() {
             match pkt {
-                Ok(packet) => {
-                    let packet_data = packet.data;
-                    if let Some(raw_packet) = process_ethernet_packet(&packet_data, "pcap") {
-                        if last_timestamp.is_none()
-                            || raw_packet.timestamp > last_timestamp.unwrap()
-                        {
-                            last_timestamp = Some(raw_packet.timestamp);
-                        }
-                        packets.push(raw_packet);
-                    }
-                }
+                Ok(packet) => f(
+                    SystemTime::UNIX_EPOCH + packet.timestamp,
+                    &packet.data,
+                    packet.orig_len as usize,
+                ),
                 Err(e) => {
                     eprintln!("Error reading PCAP packet: {}", e);
                     break;
                 }
             }
         }
+        Ok(false)
+    }
+}
+
+pub async fn create_pcap_receiver(pcap_path: &str) -> Result<Box<dyn PacketSource>> {
+    let mut packets: Vec<RawPacket> = Vec::new();
+
+    let pcapng = for_each_pcap_frame(pcap_path, |_, packet_data, original_length| {
+        if let Some(mut raw_packet) = process_ethernet_packet(packet_data, "pcap") {
+            raw_packet.original_length = original_length.max(packet_data.len());
+            packets.push(raw_packet);
+        }
+    })?;
+    if pcapng {
+        println!("Read as PCAPNG format");
+    } else {
+        println!("Not a PCAPNG file, read as regular PCAP format");
     }
 
     println!(
@@ -548,11 +1245,235 @@ pub async fn create_pcap_receiver(pcap_path: &str) -> Result<RawSocketReceiver>
         pcap_path
     );
 
-    Ok(RawSocketReceiver {
-        source: PacketSource::Pcap {
-            packets,
-            current_index: 0,
-            last_timestamp,
-        },
-    })
+    Ok(Box::new(PcapSource::new(packets)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sync message from clock 00:1b:19:ff:fe:44:55:66 with sequence id 123
+    const SYNC: [u8; 44] = [
+        0x00, 0x02, 0x00, 0x2C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1B, 0x19, 0xFF, 0xFE, 0x44, 0x55, 0x66, 0x00, 0x01,
+        0x00, 0x7B, 0x00, 0xFF, 0x00, 0x00, 0x3B, 0x9A, 0xCA, 0x00, 0x1D, 0xCD, 0x65, 0x00,
+    ];
+
+    fn ethernet(ethertype: u16, payload: &[u8]) -> Vec<u8> {
+        ethernet_to([0x01, 0x00, 0x5e, 0x00, 0x01, 0x81], ethertype, payload)
+    }
+
+    fn ethernet_to(dest_mac: [u8; 6], ethertype: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = dest_mac.to_vec();
+        frame.extend_from_slice(&[0x00, 0x1b, 0x19, 0x44, 0x55, 0x66]);
+        frame.extend_from_slice(&ethertype.to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    fn ipv4_udp(dest_port: u16, payload: &[u8]) -> Vec<u8> {
+        let total_len = (20 + 8 + payload.len()) as u16;
+        let mut packet = vec![0x45, 0x00];
+        packet.extend_from_slice(&total_len.to_be_bytes());
+        packet.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x01, 0x11, 0x00, 0x00]);
+        packet.extend_from_slice(&[10, 0, 0, 1, 224, 0, 1, 129]);
+        packet.extend_from_slice(&319u16.to_be_bytes());
+        packet.extend_from_slice(&dest_port.to_be_bytes());
+        packet.extend_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+        packet.extend_from_slice(&[0x00, 0x00]);
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    #[test]
+    fn test_plain_udp_decoding() {
+        let frame = ethernet(IPV4_ETHERTYPE, &ipv4_udp(PTP_EVENT_PORT, &SYNC));
+        let packet = process_ethernet_packet(&frame, "eth0").unwrap();
+
+        assert_eq!(packet.ptp_payload(), SYNC);
+        assert_eq!(packet.ttl, Some(1));
+        assert!(packet.encapsulation.is_empty());
+    }
+
+    #[test]
+    fn test_short_frames() {
+        // Zero-length UDP datagram to port 319, padded to the 60 byte minimum
+        let mut frame = ethernet(IPV4_ETHERTYPE, &ipv4_udp(PTP_EVENT_PORT, &[]));
+        frame.resize(60, 0);
+        let packet = process_ethernet_packet(&frame, "eth0").unwrap();
+        assert!(packet.ptp_payload().is_empty());
+
+        // Cut right after the UDP header, e.g. by a small snaplen
+        let frame = ethernet(IPV4_ETHERTYPE, &ipv4_udp(PTP_EVENT_PORT, &SYNC));
+        assert!(
+            process_ethernet_packet(&frame[..42], "eth0")
+                .is_none_or(|packet| packet.ptp_payload().is_empty())
+        );
+
+        // Runt frame with nothing after the Ethernet header
+        let frame = ethernet(IPV4_ETHERTYPE, &[]);
+        assert!(process_ethernet_packet(&frame, "eth0").is_none());
+        assert!(process_ethernet_packet(&frame[..10], "eth0").is_none());
+    }
+
+    #[test]
+    fn test_vlan_priority() {
+        // QinQ: outer tag priority 5 VLAN 200, inner tag priority 7 VLAN 100
+        let mut tagged = vec![0xa0, 0xc8];
+        tagged.extend_from_slice(&VLAN_ETHERTYPE.to_be_bytes());
+        tagged.extend_from_slice(&[0xe0, 0x64]);
+        tagged.extend_from_slice(&IPV4_ETHERTYPE.to_be_bytes());
+        tagged.extend_from_slice(&ipv4_udp(PTP_EVENT_PORT, &SYNC));
+        let packet = process_ethernet_packet(&ethernet(QINQ_ETHERTYPE, &tagged), "eth0").unwrap();
+        assert_eq!(packet.vlan_id, Some(200));
+        assert_eq!(packet.vlan_pcp, Some(5));
+
+        let frame = ethernet(IPV4_ETHERTYPE, &ipv4_udp(PTP_EVENT_PORT, &SYNC));
+        let packet = process_ethernet_packet(&frame, "eth0").unwrap();
+        assert_eq!(packet.vlan_pcp, None);
+    }
+
+    #[test]
+    fn test_extra_ptp_ports() {
+        set_extra_ptp_ports(&[1319, 1320]);
+
+        let frame = ethernet(IPV4_ETHERTYPE, &ipv4_udp(1319, &SYNC));
+        let packet = process_ethernet_packet(&frame, "eth0").unwrap();
+        assert_eq!(packet.dest_addr.unwrap().port(), 1319);
+
+        let frame = ethernet(IPV4_ETHERTYPE, &ipv4_udp(1400, &SYNC));
+        assert!(process_ethernet_packet(&frame, "eth0").is_none());
+    }
+
+    #[test]
+    fn test_layer2_sdo_id_classification() {
+        // gPTP Sync in domain 1
+        let mut gptp = SYNC;
+        gptp[0] = 0x10;
+        gptp[4] = 0x01;
+        let frame = ethernet_to(GPTP_MULTICAST_MAC, GPTP_ETHERTYPE, &gptp);
+        assert!(process_ethernet_packet(&frame, "eth0").is_some());
+
+        // gPTP never uses the IEEE 1588 multicast address
+        let frame = ethernet_to(PTP_L2_MULTICAST_MAC, GPTP_ETHERTYPE, &gptp);
+        assert!(process_ethernet_packet(&frame, "eth0").is_none());
+
+        // IEEE 1588 over Ethernet does
+        let frame = ethernet_to(PTP_L2_MULTICAST_MAC, GPTP_ETHERTYPE, &SYNC);
+        assert!(process_ethernet_packet(&frame, "eth0").is_some());
+    }
+
+    #[test]
+    fn test_mpls_vxlan_decapsulation() {
+        let inner = ethernet(IPV4_ETHERTYPE, &ipv4_udp(PTP_EVENT_PORT, &SYNC));
+
+        let mut vxlan = vec![0x08, 0x00, 0x00, 0x00, 0x00, 0x13, 0x88, 0x00];
+        vxlan.extend_from_slice(&inner);
+
+        // Two labels, 100 and 16001 (bottom of stack)
+        let mut mpls = vec![0x00, 0x06, 0x40, 0x40, 0x03, 0xE8, 0x11, 0x40];
+        mpls.extend_from_slice(&ipv4_udp(VXLAN_PORT, &vxlan));
+
+        let frame = ethernet(MPLS_UNICAST_ETHERTYPE, &mpls);
+        let packet = process_ethernet_packet(&frame, "eth0").unwrap();
+
+        assert_eq!(packet.ptp_payload(), SYNC);
+        assert_eq!(
+            packet.encapsulation,
+            vec![
+                Encapsulation::Mpls { label: 100 },
+                Encapsulation::Mpls { label: 16001 },
+                Encapsulation::Vxlan { vni: 5000 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mpls_pseudowire() {
+        // Label 100, bottom of stack
+        let label = [0x00, 0x06, 0x41, 0x40];
+        let control_word = [0x00, 0x00, 0x00, 0x2a];
+        let inner_frames = [
+            ethernet(IPV4_ETHERTYPE, &ipv4_udp(PTP_EVENT_PORT, &SYNC)),
+            ethernet_to(PTP_L2_MULTICAST_MAC, GPTP_ETHERTYPE, &SYNC),
+        ];
+        for inner in inner_frames {
+            for with_control_word in [true, false] {
+                let mut mpls = label.to_vec();
+                if with_control_word {
+                    mpls.extend_from_slice(&control_word);
+                }
+                mpls.extend_from_slice(&inner);
+                let frame = ethernet(MPLS_UNICAST_ETHERTYPE, &mpls);
+                let packet = process_ethernet_packet(&frame, "eth0").unwrap();
+                assert_eq!(packet.ptp_payload(), SYNC);
+                assert_eq!(packet.dest_mac, inner[..6]);
+                assert_eq!(
+                    packet.encapsulation,
+                    vec![Encapsulation::Mpls { label: 100 }]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_packet_sources() {
+        let frame = ethernet(IPV4_ETHERTYPE, &ipv4_udp(PTP_EVENT_PORT, &SYNC));
+        let packet_at = |secs: u64| {
+            let mut packet = process_ethernet_packet(&frame, "pcap").unwrap();
+            packet.timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+            packet
+        };
+
+        // A recording is looked at as of its last packet, even out of order
+        let mut pcap: Box<dyn PacketSource> =
+            Box::new(PcapSource::new(vec![packet_at(20), packet_at(10)]));
+        assert_eq!(
+            pcap.reference_time(),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(20))
+        );
+        assert_eq!(
+            pcap.next_packet().unwrap().timestamp,
+            packet_at(20).timestamp
+        );
+        assert!(pcap.next_packet().is_some());
+        assert!(pcap.next_packet().is_none());
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut channel: Box<dyn PacketSource> = Box::new(ChannelSource::new(receiver));
+        assert!(channel.next_packet().is_none());
+        sender.send(packet_at(30)).unwrap();
+        assert!(channel.next_packet().is_some());
+        assert_eq!(channel.reference_time(), None);
+        assert!(channel.interfaces().is_empty());
+        assert!(channel.remote_agents().is_none());
+    }
+
+    #[test]
+    fn test_erspan_decapsulation() {
+        let inner = ethernet(IPV4_ETHERTYPE, &ipv4_udp(PTP_GENERAL_PORT, &SYNC));
+
+        // GRE with sequence number, ERSPAN type II header with session 42
+        let mut gre = vec![0x10, 0x00, 0x88, 0xBE, 0x00, 0x00, 0x00, 0x01];
+        gre.extend_from_slice(&[0x10, 0x00, 0x00, 0x2A, 0x00, 0x00, 0x00, 0x00]);
+        gre.extend_from_slice(&inner);
+
+        let mut ip = ipv4_udp(PTP_EVENT_PORT, &[]);
+        ip.truncate(20);
+        ip[9] = 47; // GRE
+        let total_len = (20 + gre.len()) as u16;
+        ip[2..4].copy_from_slice(&total_len.to_be_bytes());
+        ip.extend_from_slice(&gre);
+
+        let frame = ethernet(IPV4_ETHERTYPE, &ip);
+        let packet = process_ethernet_packet(&frame, "eth0").unwrap();
+
+        assert_eq!(packet.ptp_payload(), SYNC);
+        assert_eq!(
+            packet.encapsulation,
+            vec![Encapsulation::Erspan {
+                session_id: Some(42)
+            }]
+        );
+    }
 }
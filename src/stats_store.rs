@@ -0,0 +1,145 @@
+//! Cumulative per-host statistics kept across restarts
+//!
+//! With --state-file, message counters and first-seen times of every host are
+//! written to a small JSON file every minute and on exit. When a host shows up
+//! again after a restart, its counters continue from the stored values instead
+//! of starting at zero. Hosts that are not seen again stay in the file.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path, time::SystemTime};
+
+use crate::ptp::PtpHost;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostCounters {
+    pub first_seen: Option<SystemTime>,
+    pub announce: u32,
+    pub sync: u32,
+    pub follow_up: u32,
+    pub delay_req: u32,
+    pub delay_resp: u32,
+    pub pdelay_req: u32,
+    pub pdelay_resp: u32,
+    pub pdelay_resp_follow_up: u32,
+    pub signaling: u32,
+    pub management: u32,
+    pub total_sent: u32,
+    pub total_received: u32,
+}
+
+impl HostCounters {
+    pub fn from_host(host: &PtpHost) -> Self {
+        Self {
+            first_seen: host.first_seen,
+            announce: host.announce_count,
+            sync: host.sync_count,
+            follow_up: host.follow_up_count,
+            delay_req: host.delay_req_count,
+            delay_resp: host.delay_resp_count,
+            pdelay_req: host.pdelay_req_count,
+            pdelay_resp: host.pdelay_resp_count,
+            pdelay_resp_follow_up: host.pdelay_resp_follow_up_count,
+            signaling: host.signaling_message_count,
+            management: host.management_message_count,
+            total_sent: host.total_messages_sent_count,
+            total_received: host.total_messages_received_count,
+        }
+    }
+
+    /// Continue counting from the stored values on a newly created host
+    pub fn restore(&self, host: &mut PtpHost) {
+        host.first_seen = self.first_seen;
+        host.announce_count = self.announce;
+        host.sync_count = self.sync;
+        host.follow_up_count = self.follow_up;
+        host.delay_req_count = self.delay_req;
+        host.delay_resp_count = self.delay_resp;
+        host.pdelay_req_count = self.pdelay_req;
+        host.pdelay_resp_count = self.pdelay_resp;
+        host.pdelay_resp_follow_up_count = self.pdelay_resp_follow_up;
+        host.signaling_message_count = self.signaling;
+        host.management_message_count = self.management;
+        host.total_messages_sent_count = self.total_sent;
+        host.total_messages_received_count = self.total_received;
+    }
+}
+
+/// Stored counters by clock identity
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatsStore {
+    pub hosts: BTreeMap<String, HostCounters>,
+}
+
+impl StatsStore {
+    /// Load the state file, starting empty if it does not exist yet
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read state file {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid state file {}", path.display()))
+    }
+
+    /// Write the state file, via a temporary file so a crash never leaves a
+    /// half written one behind
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temp_path = path.with_extension("tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write state file {}", temp_path.display()))?;
+        std::fs::rename(&temp_path, path)
+            .with_context(|| format!("Failed to write state file {}", path.display()))
+    }
+
+    pub fn get(&self, host: &PtpHost) -> Option<&HostCounters> {
+        self.hosts.get(&host.clock_identity.to_string())
+    }
+
+    /// Take over the current counters of a host
+    pub fn update(&mut self, host: &PtpHost) {
+        self.hosts.insert(
+            host.clock_identity.to_string(),
+            HostCounters::from_host(host),
+        );
+    }
+
+    /// Forget the counters of a host, it starts from zero when seen again
+    pub fn remove(&mut self, host: &PtpHost) {
+        self.hosts.remove(&host.clock_identity.to_string());
+    }
+}
+
+#[test]
+fn test_stats_store_round_trip() {
+    use crate::types::ClockIdentity;
+
+    let clock_identity = ClockIdentity {
+        clock_id: [0x00, 0x1b, 0x19, 0xff, 0xfe, 0x12, 0x34, 0x56],
+    };
+    let mut host = PtpHost::new(clock_identity);
+    host.record_observation(SystemTime::UNIX_EPOCH);
+    host.announce_count = 10;
+    host.sync_count = 80;
+
+    let mut store = StatsStore::default();
+    store.update(&host);
+    let parsed: StatsStore =
+        serde_json::from_str(&serde_json::to_string_pretty(&store).unwrap()).unwrap();
+    assert_eq!(parsed, store);
+
+    // A new host for the same clock continues from the stored counters
+    let mut restarted = PtpHost::new(clock_identity);
+    let counters = parsed.get(&restarted).cloned().unwrap();
+    counters.restore(&mut restarted);
+    restarted.record_observation(SystemTime::now());
+    assert_eq!(restarted.sync_count, 80);
+    assert_eq!(restarted.first_seen, Some(SystemTime::UNIX_EPOCH));
+}
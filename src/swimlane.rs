@@ -0,0 +1,156 @@
+//! Message sequence swimlanes of one PTP instance
+//!
+//! One lane per host of the instance with a marker for every message it sent,
+//! over a time axis that can be zoomed and scrolled back through the packet
+//! history. Patterns like "Sync stops, Announce continues" or a receiver that
+//! keeps sending Delay_Req without a Delay_Resp coming back are visible at a
+//! glance instead of being inferred from counters.
+
+use std::time::{Duration, SystemTime};
+
+use crate::types::PtpMessageType;
+
+const DEFAULT_WINDOW: Duration = Duration::from_secs(10);
+const MIN_WINDOW: Duration = Duration::from_millis(500);
+const MAX_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Marker of a message type in a lane
+pub fn marker(message_type: PtpMessageType) -> char {
+    match message_type {
+        PtpMessageType::Sync => 'S',
+        PtpMessageType::FollowUp => 'F',
+        PtpMessageType::Announce => 'A',
+        PtpMessageType::DelayReq => 'q',
+        PtpMessageType::DelayResp => 'r',
+        PtpMessageType::PDelayReq
+        | PtpMessageType::PDelayResp
+        | PtpMessageType::PDelayRespFollowUp => 'p',
+        PtpMessageType::Signaling => 'g',
+        PtpMessageType::Management => 'm',
+    }
+}
+
+/// Which marker is shown when several messages fall into one cell: rare
+/// messages first, so an Announce is not hidden behind the Syncs around it
+fn precedence(message_type: PtpMessageType) -> u8 {
+    match message_type {
+        PtpMessageType::Management | PtpMessageType::Signaling => 5,
+        PtpMessageType::Announce => 4,
+        PtpMessageType::DelayReq | PtpMessageType::DelayResp => 3,
+        PtpMessageType::PDelayReq
+        | PtpMessageType::PDelayResp
+        | PtpMessageType::PDelayRespFollowUp => 2,
+        PtpMessageType::Sync => 1,
+        PtpMessageType::FollowUp => 0,
+    }
+}
+
+/// Message type shown in each of `width` cells between start and end
+pub fn lane_cells(
+    messages: impl IntoIterator<Item = (SystemTime, PtpMessageType)>,
+    start: SystemTime,
+    end: SystemTime,
+    width: usize,
+) -> Vec<Option<PtpMessageType>> {
+    let mut cells: Vec<Option<PtpMessageType>> = vec![None; width];
+    let span = end.duration_since(start).unwrap_or_default().as_secs_f64();
+    if width == 0 || span <= 0.0 {
+        return cells;
+    }
+
+    for (timestamp, message_type) in messages {
+        let Ok(offset) = timestamp.duration_since(start) else {
+            continue;
+        };
+        if timestamp > end {
+            continue;
+        }
+        let cell = ((offset.as_secs_f64() / span * width as f64) as usize).min(width - 1);
+        if cells[cell].is_none_or(|shown| precedence(message_type) > precedence(shown)) {
+            cells[cell] = Some(message_type);
+        }
+    }
+    cells
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Swimlane {
+    pub instance: Option<(u16, u8)>,
+    /// Time span shown across the lanes
+    pub window: Duration,
+    /// How far the right edge lies before the newest packet, zero follows live
+    pub offset: Duration,
+    /// First lane shown
+    pub scroll: usize,
+}
+
+impl Swimlane {
+    pub fn new(instance: Option<(u16, u8)>) -> Self {
+        Self {
+            instance,
+            window: DEFAULT_WINDOW,
+            offset: Duration::ZERO,
+            scroll: 0,
+        }
+    }
+
+    pub fn zoom(&mut self, zoom_in: bool) {
+        self.window = if zoom_in {
+            self.window / 2
+        } else {
+            self.window * 2
+        }
+        .clamp(MIN_WINDOW, MAX_WINDOW);
+    }
+
+    /// Scroll by half a window, back in time or towards the present
+    pub fn pan(&mut self, back: bool) {
+        let step = self.window / 2;
+        self.offset = if back {
+            self.offset + step
+        } else {
+            self.offset.saturating_sub(step)
+        };
+    }
+
+    pub fn follow(&mut self) {
+        self.offset = Duration::ZERO;
+    }
+
+    /// Start and end of the shown time span, given the newest packet time
+    pub fn range(&self, newest: SystemTime) -> (SystemTime, SystemTime) {
+        let end = newest.checked_sub(self.offset).unwrap_or(newest);
+        let start = end.checked_sub(self.window).unwrap_or(end);
+        (start, end)
+    }
+}
+
+#[test]
+fn test_swimlane() {
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let at = |ms: u64| start + Duration::from_millis(ms);
+
+    // Syncs every 100 ms for 500 ms, one Announce, then only Announces
+    let mut messages: Vec<_> = (0..5)
+        .map(|i| (at(i * 100), PtpMessageType::Sync))
+        .collect();
+    messages.push((at(210), PtpMessageType::Announce));
+    messages.push((at(900), PtpMessageType::Announce));
+    messages.push((at(2000), PtpMessageType::Sync));
+
+    let cells = lane_cells(messages, start, at(1000), 10);
+    let markers: String = cells.iter().map(|cell| cell.map_or('.', marker)).collect();
+    // The Announce wins the cell it shares with a Sync, the late Sync is outside
+    assert_eq!(markers, "SSASS....A");
+
+    let mut swimlane = Swimlane::new(Some((0, 0)));
+    swimlane.pan(true);
+    assert_eq!(swimlane.range(at(20_000)), (at(5_000), at(15_000)));
+    swimlane.pan(false);
+    swimlane.pan(false);
+    assert_eq!(swimlane.offset, Duration::ZERO);
+    for _ in 0..20 {
+        swimlane.zoom(true);
+    }
+    assert_eq!(swimlane.window, MIN_WINDOW);
+}
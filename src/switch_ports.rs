@@ -0,0 +1,190 @@
+//! Switch ports of hosts by MAC address ("--switch-ports")
+//!
+//! Answers "which physical port is this rogue GM plugged into" from files
+//! exported by the switches themselves. Each file is either CSV with one
+//! `mac,switch,port` line per entry, or the MAC address table (CAM table) of
+//! one switch as printed by its CLI, e.g. `show mac address-table`:
+//!
+//! ```text
+//!   100    001b.19ff.1234    DYNAMIC     Gi1/0/7
+//! ```
+//!
+//! Lines of a CAM table are taken as a MAC address anywhere on the line and
+//! the port as the last word. The switch is named by a `switch: NAME` line,
+//! or else by the file name. LLDP neighbor dumps can be reduced to the CSV
+//! form. A MAC address is learned on the uplinks of every switch its frames
+//! cross, so of several ports the one with the fewest MAC addresses behind it
+//! is taken as the access port the host is plugged into.
+
+use anyhow::{Context, Result, bail};
+use std::{collections::HashMap, fmt::Display, path::Path};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SwitchPort {
+    pub switch: String,
+    pub port: String,
+}
+
+impl Display for SwitchPort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.switch, self.port)
+    }
+}
+
+/// Parse a MAC address written as 00:1b:19:ff:12:34, 00-1b-19-ff-12-34,
+/// 001b.19ff.1234 or 001b19ff1234
+pub fn parse_mac(text: &str) -> Option<[u8; 6]> {
+    // Separators only in one of the usual places
+    let bytes = text.as_bytes();
+    let digits: String = match bytes.len() {
+        12 => text.to_string(),
+        14 if bytes[4] == b'.' && bytes[9] == b'.' => text.replace('.', ""),
+        17 if [2, 5, 8, 11, 14]
+            .iter()
+            .all(|&i| bytes[i] == bytes[2] && matches!(bytes[i], b':' | b'-')) =>
+        {
+            text.replace(bytes[2] as char, "")
+        }
+        _ => return None,
+    };
+    if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let mut mac = [0u8; 6];
+    for (i, byte) in mac.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(mac)
+}
+
+/// Ports each MAC address was learned on, from one or more exports
+#[derive(Debug, Clone, Default)]
+pub struct SwitchPorts {
+    learned: HashMap<[u8; 6], Vec<SwitchPort>>,
+    /// MAC addresses learned on each port
+    port_sizes: HashMap<SwitchPort, usize>,
+}
+
+impl SwitchPorts {
+    pub fn load(&mut self, path: &Path) -> Result<usize> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read switch port file {}", path.display()))?;
+        let switch = path.file_stem().map_or("switch".to_string(), |stem| {
+            stem.to_string_lossy().into_owned()
+        });
+        let count = self.parse(&text, &switch);
+        if count == 0 {
+            bail!(
+                "No MAC addresses found in switch port file {}, expected mac,switch,port lines or a MAC address table",
+                path.display()
+            );
+        }
+        Ok(count)
+    }
+
+    /// Add the entries of an export, `switch` naming the switch of a CAM
+    /// table without a `switch:` line. Returns the number of entries.
+    pub fn parse(&mut self, text: &str, switch: &str) -> usize {
+        let mut switch = switch.to_string();
+        let mut count = 0;
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line
+                .strip_prefix("switch:")
+                .or_else(|| line.strip_prefix("Switch:"))
+            {
+                switch = name.trim().to_string();
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let entry = if let [mac, switch, port] = fields[..] {
+                parse_mac(mac).map(|mac| (mac, switch.to_string(), port.to_string()))
+            } else {
+                let words: Vec<&str> = line.split_whitespace().collect();
+                let mac = words.iter().find_map(|word| parse_mac(word));
+                match (mac, words.last()) {
+                    // A header or a line without a port
+                    (Some(mac), Some(port)) if parse_mac(port).is_none() => {
+                        Some((mac, switch.clone(), port.to_string()))
+                    }
+                    _ => None,
+                }
+            };
+            if let Some((mac, switch, port)) = entry {
+                self.insert(mac, SwitchPort { switch, port });
+                count += 1;
+            }
+        }
+        count
+    }
+
+    fn insert(&mut self, mac: [u8; 6], port: SwitchPort) {
+        let ports = self.learned.entry(mac).or_default();
+        if !ports.contains(&port) {
+            *self.port_sizes.entry(port.clone()).or_default() += 1;
+            ports.push(port);
+        }
+    }
+
+    /// The port a host with this MAC address is most likely plugged into
+    pub fn get(&self, mac: &[u8; 6]) -> Option<&SwitchPort> {
+        self.learned
+            .get(mac)?
+            .iter()
+            .min_by_key(|port| (self.port_sizes.get(*port).copied(), *port))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.learned.is_empty()
+    }
+}
+
+#[test]
+fn test_switch_ports() {
+    assert_eq!(
+        parse_mac("001b.19ff.1234"),
+        Some([0x00, 0x1b, 0x19, 0xff, 0x12, 0x34])
+    );
+    assert_eq!(parse_mac("00-1B-19-FF-12-34"), parse_mac("001b19ff1234"));
+    assert_eq!(parse_mac("Gi1/0/7"), None);
+    assert_eq!(parse_mac("0:01b:19:ff:12:34"), None);
+
+    let mut ports = SwitchPorts::default();
+    let cam = "\
+          Mac Address Table
+-------------------------------------------
+Vlan    Mac Address       Type        Ports
+----    -----------       --------    -----
+ 100    001b.19ff.1234    DYNAMIC     Gi1/0/7
+ 100    001b.19ff.5678    DYNAMIC     Gi1/0/48
+ 100    001b.19ff.9abc    DYNAMIC     Gi1/0/48
+Total Mac Addresses for this criterion: 3
+";
+    assert_eq!(ports.parse(cam, "access-sw1"), 3);
+    let csv =
+        "# mac,switch,port\n00:1b:19:ff:12:34,core-sw,Te1/1\n00:1b:19:ff:56:78,core-sw,Te1/1\n";
+    assert_eq!(ports.parse(csv, "ignored"), 2);
+
+    // Seen on the core uplink too, but plugged into the access switch
+    let gm = ports.get(&[0x00, 0x1b, 0x19, 0xff, 0x12, 0x34]).unwrap();
+    assert_eq!(gm.to_string(), "access-sw1 Gi1/0/7");
+    // Both ports carry two MAC addresses, the name decides
+    let other = ports.get(&[0x00, 0x1b, 0x19, 0xff, 0x56, 0x78]).unwrap();
+    assert_eq!(other.to_string(), "access-sw1 Gi1/0/48");
+    assert_eq!(ports.get(&[0; 6]), None);
+
+    // Named by a switch: line
+    let mut named = SwitchPorts::default();
+    named.parse("switch: edge-3\n0011.2233.4455 DYNAMIC Eth1/2", "file");
+    assert_eq!(
+        named
+            .get(&[0, 0x11, 0x22, 0x33, 0x44, 0x55])
+            .unwrap()
+            .switch,
+        "edge-3"
+    );
+}
@@ -0,0 +1,128 @@
+//! Downsampled packet history of Sync streams (--sync-sample N)
+//!
+//! Telecom profiles send 16 or more Syncs (and as many Follow_Ups) a second
+//! per port, which pushes everything else out of the packet history within a
+//! minute. With a sample rate N only every Nth Sync of a stream and its
+//! Follow_Up are stored. Messages worth looking at are always stored: the
+//! first one after a gap in the sequence ids, one with a large correction and
+//! one whose length fields are wrong. Counters, rates and the analyses that
+//! run on every message are not affected.
+
+use std::collections::HashMap;
+
+/// Corrections above this are queueing or a misbehaving transparent clock
+pub const LARGE_CORRECTION_NS: i64 = 100_000;
+
+/// Sequence id stream of a port: (port number, sdoId, domain)
+type StreamKey = (u16, u16, u8);
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Stream {
+    last_sequence_id: u16,
+    /// Syncs since the last stored one
+    since_kept: u32,
+    /// Sequence id of the last stored Sync, its Follow_Up is stored too
+    kept_sequence_id: Option<u16>,
+}
+
+/// Which Syncs and Follow_Ups of a host go into its packet history
+#[derive(Debug, Clone, Default)]
+pub struct SyncSampler {
+    streams: HashMap<StreamKey, Stream>,
+    /// Syncs and Follow_Ups left out of the history
+    pub skipped: u64,
+}
+
+/// Correction field in ns
+fn correction_ns(correction_field: i64) -> i64 {
+    correction_field >> 16
+}
+
+impl SyncSampler {
+    /// Whether to store a Sync, keeping 1 in `rate` and every anomaly
+    pub fn keep_sync(
+        &mut self,
+        stream: StreamKey,
+        sequence_id: u16,
+        correction_field: i64,
+        malformed: bool,
+        rate: u32,
+    ) -> bool {
+        let state = self.streams.entry(stream).or_insert(Stream {
+            last_sequence_id: sequence_id.wrapping_sub(1),
+            since_kept: rate,
+            kept_sequence_id: None,
+        });
+        let gap = sequence_id != state.last_sequence_id.wrapping_add(1);
+        state.last_sequence_id = sequence_id;
+        state.since_kept += 1;
+
+        let keep = rate <= 1
+            || state.since_kept >= rate
+            || gap
+            || malformed
+            || correction_ns(correction_field).abs() > LARGE_CORRECTION_NS;
+        if keep {
+            state.since_kept = 0;
+            state.kept_sequence_id = Some(sequence_id);
+        } else {
+            self.skipped += 1;
+        }
+        keep
+    }
+
+    /// Whether to store a Follow_Up: with its Sync, or when it is an anomaly
+    /// itself. Without a Sync seen on the stream it is stored as well.
+    pub fn keep_follow_up(
+        &mut self,
+        stream: StreamKey,
+        sequence_id: u16,
+        correction_field: i64,
+        malformed: bool,
+        rate: u32,
+    ) -> bool {
+        let keep = rate <= 1
+            || self
+                .streams
+                .get(&stream)
+                .is_none_or(|state| state.kept_sequence_id == Some(sequence_id))
+            || malformed
+            || correction_ns(correction_field).abs() > LARGE_CORRECTION_NS;
+        if !keep {
+            self.skipped += 1;
+        }
+        keep
+    }
+}
+
+#[test]
+fn test_sync_sampler() {
+    let stream = (1, 0, 24);
+    let mut sampler = SyncSampler::default();
+    let mut kept = Vec::new();
+    for sequence_id in 0..32u16 {
+        // Sequence ids 20 and 21 were lost
+        if sequence_id == 20 || sequence_id == 21 {
+            continue;
+        }
+        // 200 µs of correction on one of them
+        let correction = if sequence_id == 13 {
+            200_000 << 16
+        } else {
+            500 << 16
+        };
+        if sampler.keep_sync(stream, sequence_id, correction, false, 8) {
+            kept.push(sequence_id);
+        }
+        let follow_up = sampler.keep_follow_up(stream, sequence_id, 0, false, 8);
+        assert_eq!(follow_up, kept.last() == Some(&sequence_id));
+    }
+    // 1 in 8, restarting after the anomalies
+    assert_eq!(kept, vec![0, 8, 13, 22, 30]);
+    assert_eq!(sampler.skipped, 2 * (30 - kept.len() as u64));
+
+    // A rate of 1 stores everything
+    let mut all = SyncSampler::default();
+    assert!((0..10).all(|sequence_id| all.keep_sync(stream, sequence_id, 0, false, 1)));
+    assert_eq!(all.skipped, 0);
+}
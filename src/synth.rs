@@ -0,0 +1,289 @@
+//! Synthetic PTP traffic for load testing ("--source synth")
+//!
+//! Finding out how many packets per second the tracker and the UI keep up
+//! with needs a network busier than most labs have. The synthetic source
+//! generates one instead: two grandmasters in domain 0 announcing, sending
+//! two-step Syncs and answering Delay_Reqs of the other hosts, as Ethernet
+//! frames decoded by the same code as captured ones. Frames are generated
+//! at --rate per second into a bounded channel, so a tracker that falls
+//! behind holds the generator back, and the profiling pane ('Z') shows the
+//! generated rate next to the processed one.
+
+use anyhow::Result;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::mpsc;
+
+use crate::source::{self, PTP_EVENT_PORT, PTP_GENERAL_PORT, RawPacket};
+
+/// Generated packets waiting for the tracker before the generator blocks
+const CHANNEL_CAPACITY: usize = 65536;
+/// Interface name of the generated packets
+pub const INTERFACE_NAME: &str = "synth";
+/// The first hosts are grandmasters, the others receivers
+const GRANDMASTERS: u16 = 2;
+
+const SYNC: u8 = 0x0;
+const DELAY_REQ: u8 = 0x1;
+const FOLLOW_UP: u8 = 0x8;
+const DELAY_RESP: u8 = 0x9;
+const ANNOUNCE: u8 = 0xb;
+
+/// Target and achieved rate of the generator, shared with the profiling pane
+#[derive(Debug)]
+pub struct SynthStats {
+    pub rate: u64,
+    pub hosts: u16,
+    generated: AtomicU64,
+    started: Instant,
+}
+
+impl SynthStats {
+    /// Packets generated per second since the start
+    pub fn generated_rate(&self) -> f64 {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed == 0.0 {
+            return 0.0;
+        }
+        self.generated.load(Ordering::Relaxed) as f64 / elapsed
+    }
+}
+
+fn clock_identity(host: u16) -> [u8; 8] {
+    let [high, low] = host.to_be_bytes();
+    [0x02, 0x00, 0x00, 0xff, 0xfe, 0x00, high, low]
+}
+
+fn timestamp() -> [u8; 10] {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let mut timestamp = [0u8; 10];
+    timestamp[..6].copy_from_slice(&now.as_secs().to_be_bytes()[2..]);
+    timestamp[6..].copy_from_slice(&now.subsec_nanos().to_be_bytes());
+    timestamp
+}
+
+/// The messages of the synthetic network, one at a time
+pub struct Synthesizer {
+    hosts: u16,
+    /// Grandmasters that announced in this round
+    announced: u16,
+    /// Receiver whose exchange is next
+    receiver: u16,
+    /// Message of the exchange that is next
+    step: u8,
+    sequence_ids: Vec<u16>,
+}
+
+impl Synthesizer {
+    pub fn new(hosts: u16) -> Self {
+        let hosts = hosts.max(GRANDMASTERS + 1);
+        Self {
+            hosts,
+            announced: 0,
+            receiver: GRANDMASTERS,
+            step: 0,
+            sequence_ids: vec![0; hosts as usize],
+        }
+    }
+
+    /// PTP message of a type from a host, the body is filled in by the caller
+    fn message(&mut self, message_type: u8, length: usize, host: u16) -> Vec<u8> {
+        let sequence_id = &mut self.sequence_ids[host as usize];
+        *sequence_id = sequence_id.wrapping_add(1);
+        let mut message = vec![0u8; length];
+        message[0] = message_type;
+        message[1] = 0x02;
+        message[2..4].copy_from_slice(&(length as u16).to_be_bytes());
+        // twoStepFlag
+        if message_type == SYNC {
+            message[6] = 0x02;
+        }
+        message[20..28].copy_from_slice(&clock_identity(host));
+        message[28..30].copy_from_slice(&1u16.to_be_bytes());
+        message[30..32].copy_from_slice(&sequence_id.to_be_bytes());
+        message[32] = match message_type {
+            SYNC => 0,
+            DELAY_REQ => 1,
+            FOLLOW_UP => 2,
+            DELAY_RESP => 3,
+            _ => 5,
+        };
+        message[33] = if message_type == DELAY_REQ { 0x7f } else { 0 };
+        message
+    }
+
+    fn announce(&mut self, grandmaster: u16) -> Vec<u8> {
+        let mut announce = self.message(ANNOUNCE, 64, grandmaster);
+        announce[34..44].copy_from_slice(&timestamp());
+        announce[44..46].copy_from_slice(&37u16.to_be_bytes());
+        announce[47] = 128 + grandmaster as u8;
+        announce[48] = 6;
+        announce[49] = 0x21;
+        announce[50..52].copy_from_slice(&0x4e5du16.to_be_bytes());
+        announce[52] = 128;
+        announce[53..61].copy_from_slice(&clock_identity(grandmaster));
+        announce[63] = 0x20;
+        announce
+    }
+
+    /// The next message and the host sending it. Each receiver in turn gets
+    /// a Sync, Follow_Up, Delay_Req and Delay_Resp exchange with the primary
+    /// grandmaster, every round over all receivers starts with the Announces.
+    pub fn next_message(&mut self) -> (u16, Vec<u8>) {
+        if self.announced < GRANDMASTERS {
+            let grandmaster = self.announced;
+            self.announced += 1;
+            return (grandmaster, self.announce(grandmaster));
+        }
+
+        let step = self.step;
+        self.step += 1;
+        let receiver = self.receiver;
+        match step {
+            0 => {
+                let mut sync = self.message(SYNC, 44, 0);
+                sync[34..44].copy_from_slice(&timestamp());
+                (0, sync)
+            }
+            1 => {
+                let mut follow_up = self.message(FOLLOW_UP, 44, 0);
+                follow_up[34..44].copy_from_slice(&timestamp());
+                (0, follow_up)
+            }
+            2 => {
+                let mut delay_req = self.message(DELAY_REQ, 44, receiver);
+                delay_req[34..44].copy_from_slice(&timestamp());
+                (receiver, delay_req)
+            }
+            _ => {
+                // Answers the Delay_Req just sent
+                let sequence_id = self.sequence_ids[receiver as usize];
+                let mut delay_resp = self.message(DELAY_RESP, 54, 0);
+                delay_resp[30..32].copy_from_slice(&sequence_id.to_be_bytes());
+                delay_resp[34..44].copy_from_slice(&timestamp());
+                delay_resp[44..52].copy_from_slice(&clock_identity(receiver));
+                delay_resp[52..54].copy_from_slice(&1u16.to_be_bytes());
+                self.step = 0;
+                self.receiver = receiver + 1;
+                if self.receiver == self.hosts {
+                    self.receiver = GRANDMASTERS;
+                    self.announced = 0;
+                }
+                (0, delay_resp)
+            }
+        }
+    }
+
+    /// The next message as a multicast IPv4/UDP frame from its host
+    pub fn next_frame(&mut self) -> Vec<u8> {
+        let (host, message) = self.next_message();
+        let event = matches!(message[0], SYNC | DELAY_REQ);
+        let port = if event {
+            PTP_EVENT_PORT
+        } else {
+            PTP_GENERAL_PORT
+        };
+        let [high, low] = (host + 1).to_be_bytes();
+
+        let mut frame = vec![0x01, 0x00, 0x5e, 0x00, 0x01, 0x81, 0x02, 0, 0, 0, high, low];
+        frame.extend_from_slice(&0x0800u16.to_be_bytes());
+        frame.extend_from_slice(&[0x45, 0x00]);
+        frame.extend_from_slice(&((20 + 8 + message.len()) as u16).to_be_bytes());
+        frame.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x01, 0x11, 0x00, 0x00]);
+        frame.extend_from_slice(&[10, 0, high, low, 224, 0, 1, 129]);
+        frame.extend_from_slice(&port.to_be_bytes());
+        frame.extend_from_slice(&port.to_be_bytes());
+        frame.extend_from_slice(&((8 + message.len()) as u16).to_be_bytes());
+        frame.extend_from_slice(&[0x00, 0x00]);
+        frame.extend_from_slice(&message);
+        frame
+    }
+}
+
+/// Start generating rate frames per second of a network of hosts
+pub fn spawn(rate: u64, hosts: u16) -> Result<(mpsc::Receiver<RawPacket>, Arc<SynthStats>)> {
+    let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+    let stats = Arc::new(SynthStats {
+        rate,
+        hosts,
+        generated: AtomicU64::new(0),
+        started: Instant::now(),
+    });
+
+    let thread_stats = stats.clone();
+    std::thread::Builder::new()
+        .name("synth".to_string())
+        .spawn(move || {
+            let mut synthesizer = Synthesizer::new(hosts);
+            let mut generated = 0u64;
+            loop {
+                let due = (thread_stats.started.elapsed().as_secs_f64() * rate as f64) as u64;
+                while generated < due {
+                    let frame = synthesizer.next_frame();
+                    let Some(packet) = source::process_ethernet_packet(&frame, INTERFACE_NAME)
+                    else {
+                        continue;
+                    };
+                    // Blocks while the tracker is behind, stops with it
+                    if sender.blocking_send(packet).is_err() {
+                        return;
+                    }
+                    generated += 1;
+                    thread_stats.generated.store(generated, Ordering::Relaxed);
+                }
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        })?;
+
+    println!(
+        "Generating {} synthetic PTP packets/s from {} hosts",
+        rate, hosts
+    );
+    Ok((receiver, stats))
+}
+
+#[test]
+fn test_synthesizer() {
+    use crate::types::{PtpMessage, PtpMessageType};
+
+    let mut synthesizer = Synthesizer::new(4);
+    let messages: Vec<(PtpMessageType, u16)> = (0..14)
+        .map(|_| {
+            let frame = synthesizer.next_frame();
+            let packet = source::process_ethernet_packet(&frame, INTERFACE_NAME).unwrap();
+            let message = PtpMessage::try_from(packet.ptp_payload()).unwrap();
+            let header = message.header();
+            (
+                header.message_type,
+                header.source_port_identity.clock_identity.clock_id[7] as u16,
+            )
+        })
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (PtpMessageType::Announce, 0),
+            (PtpMessageType::Announce, 1),
+            (PtpMessageType::Sync, 0),
+            (PtpMessageType::FollowUp, 0),
+            (PtpMessageType::DelayReq, 2),
+            (PtpMessageType::DelayResp, 0),
+            (PtpMessageType::Sync, 0),
+            (PtpMessageType::FollowUp, 0),
+            (PtpMessageType::DelayReq, 3),
+            (PtpMessageType::DelayResp, 0),
+            // Back to the first receiver, with the Announces
+            (PtpMessageType::Announce, 0),
+            (PtpMessageType::Announce, 1),
+            (PtpMessageType::Sync, 0),
+            (PtpMessageType::FollowUp, 0),
+        ]
+    );
+}
@@ -0,0 +1,181 @@
+//! Terminal background detection for the "auto" theme
+//!
+//! The default palette is made for dark terminals and is hard to read on a
+//! light background. Most terminals report their background color when asked
+//! with OSC 11, so it is queried once at startup, before the UI takes over
+//! the terminal, and the auto theme picks the light or the default palette
+//! from its luminance. Terminals that don't answer get the default palette,
+//! unless COLORFGBG says otherwise.
+
+use std::{
+    io::{IsTerminal, Write},
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use crate::themes::ThemeName;
+
+/// How long to wait for the terminal to answer
+const QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+/// OSC 11 background color query followed by a DA1 request. Every terminal
+/// answers DA1, so its answer marks the end of the response even if OSC 11
+/// is not supported.
+const QUERY: &[u8] = b"\x1b]11;?\x1b\\\x1b[c";
+/// Backgrounds brighter than this get the light palette
+const LIGHT_LUMINANCE: f64 = 0.5;
+
+static DETECTED: OnceLock<ThemeName> = OnceLock::new();
+
+/// Parse an OSC 11 answer like "\x1b]11;rgb:ffff/ffff/dddd\x1b\\" into
+/// red, green and blue from 0 to 1
+pub fn parse_osc11_response(response: &[u8]) -> Option<(f64, f64, f64)> {
+    let text = String::from_utf8_lossy(response);
+    let start = text.find("]11;")? + 4;
+    let spec = &text[start..];
+    let spec = spec
+        .strip_prefix("rgba:")
+        .or_else(|| spec.strip_prefix("rgb:"))?;
+    let end = spec
+        .find(|c: char| !c.is_ascii_hexdigit() && c != '/')
+        .unwrap_or(spec.len());
+    let mut components = spec[..end].split('/').map(|component| {
+        // 1 to 4 hex digits each, scaled to their own range
+        if component.is_empty() || component.len() > 4 {
+            return None;
+        }
+        let value = u32::from_str_radix(component, 16).ok()?;
+        let max = (1u32 << (4 * component.len())) - 1;
+        Some(value as f64 / max as f64)
+    });
+    Some((
+        components.next()??,
+        components.next()??,
+        components.next()??,
+    ))
+}
+
+/// Theme for a background color, by its relative luminance
+pub fn theme_for_background((red, green, blue): (f64, f64, f64)) -> ThemeName {
+    let luminance = 0.2126 * red + 0.7152 * green + 0.0722 * blue;
+    if luminance > LIGHT_LUMINANCE {
+        ThemeName::Light
+    } else {
+        ThemeName::Default
+    }
+}
+
+/// Theme from COLORFGBG ("15;0" or "0;default;15"), set by some terminals
+fn theme_from_colorfgbg(value: &str) -> Option<ThemeName> {
+    let background: u8 = value.rsplit(';').next()?.parse().ok()?;
+    // ANSI colors 7 (white) and 9 to 15 (bright colors) are light
+    Some(if background == 7 || background > 8 {
+        ThemeName::Light
+    } else {
+        ThemeName::Default
+    })
+}
+
+/// The DA1 answer "\x1b[?...c" ends the response
+fn is_complete(response: &[u8]) -> bool {
+    response
+        .windows(3)
+        .position(|window| window == b"\x1b[?")
+        .is_some_and(|start| response[start..].contains(&b'c'))
+}
+
+fn read_response() -> Option<Vec<u8>> {
+    let mut stdout = std::io::stdout();
+    stdout.write_all(QUERY).ok()?;
+    stdout.flush().ok()?;
+
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut response = Vec::new();
+    while !is_complete(&response) {
+        let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+            break;
+        };
+        let mut buffer = [0u8; 64];
+        let Some(read) = read_stdin(remaining, &mut buffer) else {
+            break;
+        };
+        response.extend_from_slice(&buffer[..read]);
+    }
+    Some(response)
+}
+
+/// Read what arrives on stdin within `timeout`, None if nothing does
+#[cfg(unix)]
+fn read_stdin(timeout: Duration, buffer: &mut [u8]) -> Option<usize> {
+    let mut poll_fd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: a single valid pollfd is passed
+    let ready = unsafe { libc::poll(&mut poll_fd, 1, timeout.as_millis() as i32) };
+    if ready <= 0 {
+        return None;
+    }
+    // SAFETY: reads at most buffer.len() bytes into the buffer
+    let read = unsafe { libc::read(libc::STDIN_FILENO, buffer.as_mut_ptr().cast(), buffer.len()) };
+    (read > 0).then_some(read as usize)
+}
+
+#[cfg(not(unix))]
+fn read_stdin(_timeout: Duration, _buffer: &mut [u8]) -> Option<usize> {
+    None
+}
+
+/// Ask the terminal for its background color
+fn query_background() -> Option<(f64, f64, f64)> {
+    // Elsewhere the answer could not be read back and would end up as input
+    if !cfg!(unix) || !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return None;
+    }
+    crossterm::terminal::enable_raw_mode().ok()?;
+    let response = read_response();
+    let _ = crossterm::terminal::disable_raw_mode();
+    parse_osc11_response(&response?)
+}
+
+/// Detect the theme for the terminal background, must be called before the
+/// UI starts reading terminal input
+pub fn detect() -> ThemeName {
+    *DETECTED.get_or_init(|| {
+        query_background()
+            .map(theme_for_background)
+            .or_else(|| {
+                std::env::var("COLORFGBG")
+                    .ok()
+                    .and_then(|value| theme_from_colorfgbg(&value))
+            })
+            .unwrap_or(ThemeName::Default)
+    })
+}
+
+/// The theme detected at startup, the default one if detection didn't run
+pub fn detected_theme() -> ThemeName {
+    DETECTED.get().copied().unwrap_or(ThemeName::Default)
+}
+
+#[test]
+fn test_terminal_background() {
+    let white = parse_osc11_response(b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?64;1c").unwrap();
+    assert_eq!(white, (1.0, 1.0, 1.0));
+    assert_eq!(theme_for_background(white), ThemeName::Light);
+
+    // Solarized dark, BEL terminated with 2 digit components
+    let solarized = parse_osc11_response(b"\x1b]11;rgb:00/2b/36\x07").unwrap();
+    assert_eq!(theme_for_background(solarized), ThemeName::Default);
+    let light_gray = parse_osc11_response(b"\x1b]11;rgba:eeee/eeee/eeee/ffff\x1b\\").unwrap();
+    assert_eq!(theme_for_background(light_gray), ThemeName::Light);
+
+    // Only the DA1 answer, OSC 11 is not supported
+    assert_eq!(parse_osc11_response(b"\x1b[?1;2c"), None);
+    assert!(is_complete(b"\x1b]11;rgb:0/0/0\x07\x1b[?1;2c"));
+    assert!(!is_complete(b"\x1b]11;rgb:0/0/0\x07"));
+
+    assert_eq!(theme_from_colorfgbg("15;0"), Some(ThemeName::Default));
+    assert_eq!(theme_from_colorfgbg("0;default;15"), Some(ThemeName::Light));
+    assert_eq!(theme_from_colorfgbg("default"), None);
+}
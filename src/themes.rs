@@ -1,10 +1,14 @@
 use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
 
 use crate::{ptp::PtpHostState, types::PtpMessageType};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ThemeName {
+    /// Default or Light, by the terminal background detected at startup
+    Auto,
     Default,
+    Light,
     Monokai,
     Matrix,
 }
@@ -12,7 +16,9 @@ pub enum ThemeName {
 impl ThemeName {
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
             "default" => Some(Self::Default),
+            "light" => Some(Self::Light),
             "monokai" => Some(Self::Monokai),
             "matrix" => Some(Self::Matrix),
             _ => None,
@@ -21,14 +27,129 @@ impl ThemeName {
 
     pub fn as_str(&self) -> &'static str {
         match self {
+            Self::Auto => "auto",
             Self::Default => "default",
+            Self::Light => "light",
             Self::Monokai => "monokai",
             Self::Matrix => "matrix",
         }
     }
 
     pub fn all_themes() -> &'static [ThemeName] {
-        &[ThemeName::Default, ThemeName::Monokai, ThemeName::Matrix]
+        &[
+            ThemeName::Auto,
+            ThemeName::Default,
+            ThemeName::Light,
+            ThemeName::Monokai,
+            ThemeName::Matrix,
+        ]
+    }
+}
+
+/// How many colors the terminal can show. Themes are defined in truecolor
+/// and degraded to what the terminal supports, as serial consoles and older
+/// terminal emulators turn close RGB shades into the same palette entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ColorDepth {
+    /// From COLORTERM and TERM
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    /// xterm 256-color palette
+    #[serde(rename = "256")]
+    Ansi256,
+    /// The 16 ANSI colors, with hand-picked palettes per theme
+    #[serde(rename = "16")]
+    Ansi16,
+}
+
+impl ColorDepth {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "truecolor" | "24bit" => Some(Self::TrueColor),
+            "256" => Some(Self::Ansi256),
+            "16" => Some(Self::Ansi16),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::TrueColor => "truecolor",
+            Self::Ansi256 => "256",
+            Self::Ansi16 => "16",
+        }
+    }
+
+    pub fn all() -> &'static [ColorDepth] {
+        &[
+            ColorDepth::Auto,
+            ColorDepth::TrueColor,
+            ColorDepth::Ansi256,
+            ColorDepth::Ansi16,
+        ]
+    }
+
+    /// Color depth advertised by the COLORTERM and TERM environment variables
+    pub fn from_env(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if colorterm.is_some_and(|value| value == "truecolor" || value == "24bit") {
+            ColorDepth::TrueColor
+        } else if term.is_some_and(|value| value.contains("256color")) {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    /// The depth to render with, detecting it for auto
+    pub fn resolve(&self) -> ColorDepth {
+        match self {
+            ColorDepth::Auto => ColorDepth::from_env(
+                std::env::var("COLORTERM").ok().as_deref(),
+                std::env::var("TERM").ok().as_deref(),
+            ),
+            depth => *depth,
+        }
+    }
+}
+
+/// Levels of the 6x6x6 color cube of the xterm 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn color_distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// Nearest xterm 256-color palette entry, from the color cube (16 to 231)
+/// or the grayscale ramp (232 to 255). The first 16 entries are left out,
+/// terminals redefine them.
+pub fn to_ansi256(color: Color) -> Color {
+    let Color::Rgb(r, g, b) = color else {
+        return color;
+    };
+    let nearest_level = |value: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - value as i32).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (nearest_level(r), nearest_level(g), nearest_level(b));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_level = 8 + 10 * gray_step;
+    let gray = (gray_level, gray_level, gray_level);
+
+    if color_distance((r, g, b), gray) < color_distance((r, g, b), cube) {
+        Color::Indexed(232 + gray_step)
+    } else {
+        Color::Indexed(cube_index as u8)
     }
 }
 
@@ -72,9 +193,19 @@ pub struct Theme {
 }
 
 impl Theme {
-    pub fn new(theme_name: ThemeName) -> Self {
+    pub fn new(theme_name: ThemeName, color_depth: ColorDepth) -> Self {
+        match color_depth.resolve() {
+            ColorDepth::Ansi16 => Self::ansi16_theme(theme_name),
+            ColorDepth::Ansi256 => Self::truecolor_theme(theme_name).map_colors(to_ansi256),
+            _ => Self::truecolor_theme(theme_name),
+        }
+    }
+
+    fn truecolor_theme(theme_name: ThemeName) -> Self {
         match theme_name {
+            ThemeName::Auto => Self::truecolor_theme(crate::terminal_background::detected_theme()),
             ThemeName::Default => Self::default_theme(),
+            ThemeName::Light => Self::light_theme(),
             ThemeName::Monokai => Self::monokai_theme(),
             ThemeName::Matrix => Self::matrix_theme(),
         }
@@ -120,6 +251,46 @@ impl Theme {
         }
     }
 
+    fn light_theme() -> Self {
+        Self {
+            // PTP State colors - darker shades that stay readable on white
+            state_transmitter: Color::Rgb(30, 132, 73), // Dark emerald green
+            state_receiver: Color::Rgb(31, 97, 141),    // Dark dodger blue
+            state_listening: Color::Rgb(183, 110, 0),   // Dark amber
+
+            // UI element colors
+            header_fg: Color::Rgb(33, 37, 41),        // Charcoal
+            header_bg: Color::Rgb(233, 236, 239),     // Light gray
+            border_normal: Color::Rgb(134, 142, 150), // Gray
+            border_focused: Color::Rgb(30, 132, 73),  // Dark emerald green
+            text_primary: Color::Rgb(33, 37, 41),     // Charcoal
+            text_secondary: Color::Rgb(73, 80, 87),   // Dark gray
+            text_accent: Color::Rgb(30, 132, 73),     // Dark emerald green
+            vendor_text: Color::Rgb(17, 122, 101),    // Dark turquoise
+            background: Color::Rgb(255, 255, 255),    // White
+
+            // Table colors
+            table_header: Color::Rgb(31, 97, 141), // Dark dodger blue
+            sort_column_active: Color::Rgb(30, 132, 73), // Dark emerald green
+            selected_row_background: Color::Rgb(206, 212, 218), // Silver
+
+            // Status indicators
+            confidence_high: Color::Rgb(30, 132, 73), // Dark emerald green
+            confidence_medium: Color::Rgb(183, 110, 0), // Dark amber
+            confidence_low: Color::Rgb(192, 57, 43),  // Pomegranate red
+
+            // Packet type colors
+            message_type_sync: Color::Rgb(30, 132, 73), // Dark emerald green
+            message_type_follow_up: Color::Rgb(31, 97, 141), // Dark dodger blue
+            message_type_delay_req: Color::Rgb(183, 110, 0), // Dark amber
+            message_type_delay_resp: Color::Rgb(125, 60, 152), // Wisteria purple
+            message_type_pdelay_req: Color::Rgb(192, 57, 43), // Pomegranate red
+            message_type_pdelay_resp: Color::Rgb(93, 109, 126), // Slate gray
+            message_type_pdelay_resp_follow_up: Color::Rgb(17, 122, 101), // Dark turquoise
+            message_type_unknown: Color::Rgb(33, 37, 41), // Charcoal
+        }
+    }
+
     fn monokai_theme() -> Self {
         Self {
             // PTP State colors - Monokai inspired
@@ -200,6 +371,156 @@ impl Theme {
         }
     }
 
+    /// Hand-picked 16-color palettes, so that states, confidence levels and
+    /// message types stay distinct where nearest-color mapping would merge them
+    fn ansi16_theme(theme_name: ThemeName) -> Self {
+        match theme_name {
+            ThemeName::Auto => Self::ansi16_theme(crate::terminal_background::detected_theme()),
+            ThemeName::Default | ThemeName::Monokai => Self::ansi16_dark_theme(),
+            ThemeName::Light => Self::ansi16_light_theme(),
+            ThemeName::Matrix => Self::ansi16_matrix_theme(),
+        }
+    }
+
+    fn ansi16_dark_theme() -> Self {
+        Self {
+            state_transmitter: Color::LightGreen,
+            state_receiver: Color::LightCyan,
+            state_listening: Color::Yellow,
+
+            header_fg: Color::White,
+            header_bg: Color::Black,
+            border_normal: Color::Gray,
+            border_focused: Color::LightGreen,
+            text_primary: Color::White,
+            text_secondary: Color::Gray,
+            text_accent: Color::LightGreen,
+            vendor_text: Color::Cyan,
+            background: Color::Black,
+
+            table_header: Color::LightCyan,
+            sort_column_active: Color::LightGreen,
+            selected_row_background: Color::Blue,
+
+            confidence_high: Color::LightGreen,
+            confidence_medium: Color::Yellow,
+            confidence_low: Color::LightRed,
+
+            message_type_sync: Color::LightGreen,
+            message_type_follow_up: Color::LightBlue,
+            message_type_delay_req: Color::Yellow,
+            message_type_delay_resp: Color::LightMagenta,
+            message_type_pdelay_req: Color::LightRed,
+            message_type_pdelay_resp: Color::Gray,
+            message_type_pdelay_resp_follow_up: Color::Cyan,
+            message_type_unknown: Color::White,
+        }
+    }
+
+    fn ansi16_light_theme() -> Self {
+        Self {
+            state_transmitter: Color::Green,
+            state_receiver: Color::Blue,
+            state_listening: Color::Magenta,
+
+            header_fg: Color::Black,
+            header_bg: Color::White,
+            border_normal: Color::DarkGray,
+            border_focused: Color::Green,
+            text_primary: Color::Black,
+            text_secondary: Color::DarkGray,
+            text_accent: Color::Green,
+            vendor_text: Color::Cyan,
+            background: Color::White,
+
+            table_header: Color::Blue,
+            sort_column_active: Color::Green,
+            selected_row_background: Color::Gray,
+
+            // Yellow is unreadable on white
+            confidence_high: Color::Green,
+            confidence_medium: Color::Magenta,
+            confidence_low: Color::Red,
+
+            message_type_sync: Color::Green,
+            message_type_follow_up: Color::Blue,
+            message_type_delay_req: Color::Magenta,
+            message_type_delay_resp: Color::Cyan,
+            message_type_pdelay_req: Color::Red,
+            message_type_pdelay_resp: Color::DarkGray,
+            message_type_pdelay_resp_follow_up: Color::LightBlue,
+            message_type_unknown: Color::Black,
+        }
+    }
+
+    fn ansi16_matrix_theme() -> Self {
+        Self {
+            // Only two greens, listening hosts fall back to gray
+            state_transmitter: Color::LightGreen,
+            state_receiver: Color::Green,
+            state_listening: Color::Gray,
+
+            header_fg: Color::LightGreen,
+            header_bg: Color::Black,
+            border_normal: Color::Green,
+            border_focused: Color::LightGreen,
+            text_primary: Color::Green,
+            text_secondary: Color::DarkGray,
+            text_accent: Color::LightGreen,
+            vendor_text: Color::Green,
+            background: Color::Black,
+
+            table_header: Color::LightGreen,
+            sort_column_active: Color::LightGreen,
+            selected_row_background: Color::DarkGray,
+
+            confidence_high: Color::LightGreen,
+            confidence_medium: Color::Green,
+            confidence_low: Color::LightRed,
+
+            message_type_sync: Color::LightGreen,
+            message_type_follow_up: Color::Green,
+            message_type_delay_req: Color::LightGreen,
+            message_type_delay_resp: Color::Green,
+            message_type_pdelay_req: Color::LightGreen,
+            message_type_pdelay_resp: Color::Green,
+            message_type_pdelay_resp_follow_up: Color::LightGreen,
+            message_type_unknown: Color::DarkGray,
+        }
+    }
+
+    /// The theme with every color passed through a mapping
+    fn map_colors(self, map: impl Fn(Color) -> Color) -> Self {
+        Self {
+            state_transmitter: map(self.state_transmitter),
+            state_receiver: map(self.state_receiver),
+            state_listening: map(self.state_listening),
+            header_fg: map(self.header_fg),
+            header_bg: map(self.header_bg),
+            border_normal: map(self.border_normal),
+            border_focused: map(self.border_focused),
+            text_primary: map(self.text_primary),
+            text_secondary: map(self.text_secondary),
+            text_accent: map(self.text_accent),
+            vendor_text: map(self.vendor_text),
+            background: map(self.background),
+            table_header: map(self.table_header),
+            sort_column_active: map(self.sort_column_active),
+            selected_row_background: map(self.selected_row_background),
+            confidence_high: map(self.confidence_high),
+            confidence_medium: map(self.confidence_medium),
+            confidence_low: map(self.confidence_low),
+            message_type_sync: map(self.message_type_sync),
+            message_type_follow_up: map(self.message_type_follow_up),
+            message_type_delay_req: map(self.message_type_delay_req),
+            message_type_delay_resp: map(self.message_type_delay_resp),
+            message_type_pdelay_req: map(self.message_type_pdelay_req),
+            message_type_pdelay_resp: map(self.message_type_pdelay_resp),
+            message_type_pdelay_resp_follow_up: map(self.message_type_pdelay_resp_follow_up),
+            message_type_unknown: map(self.message_type_unknown),
+        }
+    }
+
     pub fn get_state_color(&self, state: &crate::ptp::PtpHostState) -> Color {
         match state {
             PtpHostState::TimeTransmitter(_) => self.state_transmitter,
@@ -232,6 +553,36 @@ impl Theme {
 
 impl Default for Theme {
     fn default() -> Self {
-        Self::new(ThemeName::Default)
+        Self::new(ThemeName::Default, ColorDepth::TrueColor)
     }
 }
+
+#[test]
+fn test_color_depth() {
+    assert_eq!(
+        ColorDepth::from_env(Some("truecolor"), Some("xterm-256color")),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(
+        ColorDepth::from_env(None, Some("screen-256color")),
+        ColorDepth::Ansi256
+    );
+    assert_eq!(
+        ColorDepth::from_env(None, Some("vt220")),
+        ColorDepth::Ansi16
+    );
+    assert_eq!(ColorDepth::from_env(None, None), ColorDepth::Ansi16);
+
+    assert_eq!(to_ansi256(Color::Rgb(0, 0, 0)), Color::Indexed(16));
+    assert_eq!(to_ansi256(Color::Rgb(255, 255, 255)), Color::Indexed(231));
+    assert_eq!(to_ansi256(Color::Rgb(128, 128, 128)), Color::Indexed(244));
+    // Emerald green
+    assert_eq!(to_ansi256(Color::Rgb(46, 204, 113)), Color::Indexed(41));
+    assert_eq!(to_ansi256(Color::DarkGray), Color::DarkGray);
+
+    // The state colors stay distinct in 256 colors
+    let theme = Theme::new(ThemeName::Default, ColorDepth::Ansi256);
+    assert_ne!(theme.state_transmitter, theme.state_receiver);
+    assert_ne!(theme.state_receiver, theme.state_listening);
+    assert_ne!(theme.confidence_medium, theme.confidence_low);
+}
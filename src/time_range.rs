@@ -0,0 +1,179 @@
+//! Statistics over a recent time range instead of the whole session
+//!
+//! After a change to the network, lifetime totals still carry the traffic
+//! from before it. With a time range selected ('u'), message counts, rates,
+//! host counts and the packet history only cover its last minutes. Counts are
+//! kept in 10 s buckets for the longest range, so the start of a range is
+//! accurate to 10 s.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::types::PtpMessageType;
+
+/// Granularity of the windowed message counts
+const BUCKET: Duration = Duration::from_secs(10);
+const MESSAGE_TYPES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeRange {
+    #[default]
+    Session,
+    LastMinute,
+    Last5Minutes,
+    Last15Minutes,
+    LastHour,
+}
+
+impl TimeRange {
+    pub fn next(&self) -> Self {
+        match self {
+            TimeRange::Session => TimeRange::LastMinute,
+            TimeRange::LastMinute => TimeRange::Last5Minutes,
+            TimeRange::Last5Minutes => TimeRange::Last15Minutes,
+            TimeRange::Last15Minutes => TimeRange::LastHour,
+            TimeRange::LastHour => TimeRange::Session,
+        }
+    }
+
+    /// Length of the range, None for the entire session
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            TimeRange::Session => None,
+            TimeRange::LastMinute => Some(Duration::from_secs(60)),
+            TimeRange::Last5Minutes => Some(Duration::from_secs(5 * 60)),
+            TimeRange::Last15Minutes => Some(Duration::from_secs(15 * 60)),
+            TimeRange::LastHour => Some(Duration::from_secs(60 * 60)),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TimeRange::Session => "entire session",
+            TimeRange::LastMinute => "last 1 min",
+            TimeRange::Last5Minutes => "last 5 min",
+            TimeRange::Last15Minutes => "last 15 min",
+            TimeRange::LastHour => "last 1 h",
+        }
+    }
+
+    /// Start of the range ending at `now`, None for the entire session
+    pub fn start(&self, now: SystemTime) -> Option<SystemTime> {
+        now.checked_sub(self.duration()?)
+    }
+}
+
+/// Longest range counts are kept for
+const RETENTION: Duration = Duration::from_secs(60 * 60);
+
+fn bucket_of(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / BUCKET.as_secs()
+}
+
+/// Start of the bucket `since` falls in, which counts since `since` really
+/// start at
+pub fn bucket_start(since: SystemTime) -> SystemTime {
+    UNIX_EPOCH + BUCKET * bucket_of(since) as u32
+}
+
+fn type_index(message_type: PtpMessageType) -> usize {
+    PtpMessageType::all()
+        .iter()
+        .position(|known| *known == message_type)
+        .unwrap_or_default()
+}
+
+/// Messages by type over the last hour, in 10 s buckets
+#[derive(Debug, Clone, Default)]
+pub struct WindowedCounts {
+    /// (bucket number, messages of each type), oldest first, only buckets
+    /// with messages
+    buckets: VecDeque<(u64, [u32; MESSAGE_TYPES])>,
+}
+
+impl WindowedCounts {
+    pub fn record(&mut self, message_type: PtpMessageType, timestamp: SystemTime) {
+        let bucket = bucket_of(timestamp);
+        match self.buckets.back_mut() {
+            Some((last, counts)) if *last >= bucket => counts[type_index(message_type)] += 1,
+            _ => {
+                let mut counts = [0; MESSAGE_TYPES];
+                counts[type_index(message_type)] = 1;
+                self.buckets.push_back((bucket, counts));
+            }
+        }
+
+        let oldest = bucket.saturating_sub(RETENTION.as_secs() / BUCKET.as_secs());
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(first, _)| *first < oldest)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    /// Messages of `message_type` since `since`
+    pub fn count(&self, message_type: PtpMessageType, since: SystemTime) -> u32 {
+        let first = bucket_of(since);
+        let index = type_index(message_type);
+        self.buckets
+            .iter()
+            .rev()
+            .take_while(|(bucket, _)| *bucket >= first)
+            .map(|(_, counts)| counts[index])
+            .sum()
+    }
+
+    /// Approximate heap size in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.buckets.len() * std::mem::size_of::<(u64, [u32; MESSAGE_TYPES])>()
+    }
+
+    /// Messages of all types since `since`
+    pub fn total(&self, since: SystemTime) -> u32 {
+        let first = bucket_of(since);
+        self.buckets
+            .iter()
+            .rev()
+            .take_while(|(bucket, _)| *bucket >= first)
+            .map(|(_, counts)| counts.iter().sum::<u32>())
+            .sum()
+    }
+}
+
+#[test]
+fn test_windowed_counts() {
+    let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut counts = WindowedCounts::default();
+
+    // One Sync per second for 20 minutes, an Announce every other second
+    for second in 0..1200 {
+        let time = start + Duration::from_secs(second);
+        counts.record(PtpMessageType::Sync, time);
+        if second % 2 == 0 {
+            counts.record(PtpMessageType::Announce, time);
+        }
+    }
+    let now = start + Duration::from_secs(1199);
+    let since = TimeRange::Last5Minutes.start(now).unwrap();
+    // 5 minutes, give or take the bucket the range starts in
+    let syncs = counts.count(PtpMessageType::Sync, since);
+    assert!((300..=310).contains(&syncs), "{}", syncs);
+    assert_eq!(counts.count(PtpMessageType::DelayReq, since), 0);
+    let total = counts.total(since);
+    assert!((450..=465).contains(&total), "{}", total);
+    assert_eq!(counts.total(start), 1800);
+
+    // Nothing older than an hour is kept
+    counts.record(PtpMessageType::Sync, start + Duration::from_secs(3 * 3600));
+    assert_eq!(counts.total(start), 1);
+
+    assert_eq!(TimeRange::Session.start(now), None);
+    assert_eq!(TimeRange::LastHour.next(), TimeRange::Session);
+}
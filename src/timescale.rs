@@ -0,0 +1,271 @@
+//! Sanity checks of the timescale properties announced by time transmitters
+//!
+//! With the PTP timescale, clocks count TAI and receivers get UTC by applying
+//! currentUtcOffset. An offset that is valid by its flag but wrong, or flags
+//! that contradict the clockClass, don't break synchronization itself: they
+//! show up downstream as UTC being off by exactly a leap second or more, which
+//! is hard to trace back to the grandmaster's configuration.
+
+use std::{collections::HashMap, fmt::Display, time::SystemTime};
+
+use crate::types::{AnnounceMessage, ClockIdentity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TimescaleIssue {
+    /// PTP timescale with currentUtcOffset marked valid, but not TAI-UTC
+    WrongUtcOffset { announced: i16, expected: i16 },
+    /// PTP timescale traceable to a primary reference without a valid UTC offset
+    UtcOffsetNotValid,
+    /// leap59 and leap61 both set
+    ConflictingLeapFlags,
+    /// ARB timescale with currentUtcOffsetValid or a leap flag set
+    UtcFieldsOnArbTimescale,
+    /// clockClass 6 or 7 without the PTP timescale
+    PrimaryClassOnArbTimescale(u8),
+}
+
+impl Display for TimescaleIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimescaleIssue::WrongUtcOffset {
+                announced,
+                expected,
+            } => write!(
+                f,
+                "currentUtcOffset {} s is marked valid but TAI-UTC is {} s, UTC will be off by {} s (IEEE 1588-2019 7.2.3, 8.2.4.2)",
+                announced,
+                expected,
+                expected - announced
+            ),
+            TimescaleIssue::UtcOffsetNotValid => write!(
+                f,
+                "PTP timescale traceable to a primary reference, but currentUtcOffsetValid is FALSE (IEEE 1588-2019 8.2.4.3)"
+            ),
+            TimescaleIssue::ConflictingLeapFlags => write!(
+                f,
+                "leap59 and leap61 are both set (IEEE 1588-2019 8.2.4.4, 8.2.4.5)"
+            ),
+            TimescaleIssue::UtcFieldsOnArbTimescale => write!(
+                f,
+                "ARB timescale with currentUtcOffsetValid or leap flags set, receivers may apply a UTC offset to a non-TAI time (IEEE 1588-2019 7.2.1, 8.2.4.8)"
+            ),
+            TimescaleIssue::PrimaryClassOnArbTimescale(class) => write!(
+                f,
+                "clockClass {} requires the PTP timescale, but ptpTimescale is FALSE (IEEE 1588-2019 7.6.2.5)",
+                class
+            ),
+        }
+    }
+}
+
+/// TAI-UTC at a time, from hifitime's leap second table
+fn expected_utc_offset(at: SystemTime) -> i16 {
+    use hifitime::{Epoch, TimeScale};
+
+    let unix = at
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let epoch = Epoch::from_unix_seconds(unix.as_secs_f64());
+    let offset = epoch.to_duration_in_time_scale(TimeScale::TAI)
+        - epoch.to_duration_in_time_scale(TimeScale::UTC);
+    offset.to_seconds().round() as i16
+}
+
+/// Inconsistencies in an Announce received at `at`
+pub fn check(announce: &AnnounceMessage, at: SystemTime) -> Vec<TimescaleIssue> {
+    let flags = &announce.header.flags;
+    let class = announce.clock_class.class();
+    let leap_pending = flags.leap59() || flags.leap61();
+    let mut issues = Vec::new();
+
+    if flags.leap59() && flags.leap61() {
+        issues.push(TimescaleIssue::ConflictingLeapFlags);
+    }
+
+    if flags.ptp_timescale() {
+        let announced = announce.current_utc_offset.offset;
+        let expected = expected_utc_offset(at);
+        // Around a leap second the grandmaster and the capture clock may
+        // switch to the new offset a moment apart
+        let tolerance = if leap_pending { 1 } else { 0 };
+        if flags.current_utc_offset_valid() && (announced - expected).abs() > tolerance {
+            issues.push(TimescaleIssue::WrongUtcOffset {
+                announced,
+                expected,
+            });
+        }
+        if !flags.current_utc_offset_valid() && (flags.time_traceable() || class == 6) {
+            issues.push(TimescaleIssue::UtcOffsetNotValid);
+        }
+    } else {
+        if flags.current_utc_offset_valid() || leap_pending {
+            issues.push(TimescaleIssue::UtcFieldsOnArbTimescale);
+        }
+        if matches!(class, 6 | 7) {
+            issues.push(TimescaleIssue::PrimaryClassOnArbTimescale(class));
+        }
+    }
+
+    issues
+}
+
+/// The currentUtcOffset announced in a PTP instance took a new value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtcOffsetChange {
+    pub before: i16,
+    pub after: i16,
+    /// Grandmaster that announced the old offset
+    pub previous_gm: ClockIdentity,
+    pub gm: ClockIdentity,
+    /// The grandmaster had announced a leap second in this direction
+    pub leap_second: bool,
+}
+
+impl Display for UtcOffsetChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "currentUtcOffset changed from {} s to {} s",
+            self.before, self.after
+        )?;
+        if self.gm == self.previous_gm {
+            write!(f, " on GM {}", self.gm)?;
+        } else {
+            write!(f, ", GM {} replaced GM {}", self.gm, self.previous_gm)?;
+        }
+        if self.leap_second {
+            write!(f, " (announced leap second), check downstream timecode")
+        } else {
+            write!(
+                f,
+                " without a leap second announced, check the GM configuration"
+            )
+        }
+    }
+}
+
+/// Last valid currentUtcOffset per PTP instance (sdoId, domain), with the
+/// grandmaster announcing it and its pending leap second (+1, -1 or 0)
+#[derive(Debug, Clone, Default)]
+pub struct UtcOffsets {
+    last: HashMap<(u16, u8), (i16, ClockIdentity, i16)>,
+}
+
+impl UtcOffsets {
+    /// Record an Announce of the instance's PTT, returning the change if the
+    /// offset differs from the last one
+    pub fn record(&mut self, announce: &AnnounceMessage) -> Option<UtcOffsetChange> {
+        let flags = &announce.header.flags;
+        // Offsets not marked valid are not applied by receivers
+        if !flags.ptp_timescale() || !flags.current_utc_offset_valid() {
+            return None;
+        }
+        let instance = (announce.header.sdo_id, announce.header.domain_number);
+        let offset = announce.current_utc_offset.offset;
+        let leap = match (flags.leap61(), flags.leap59()) {
+            (true, false) => 1,
+            (false, true) => -1,
+            _ => 0,
+        };
+
+        let previous = self
+            .last
+            .insert(instance, (offset, announce.ptt_identity, leap));
+        let (before, previous_gm, pending) = previous?;
+        (before != offset).then_some(UtcOffsetChange {
+            before,
+            after: offset,
+            previous_gm,
+            gm: announce.ptt_identity,
+            leap_second: pending != 0 && offset - before == pending,
+        })
+    }
+}
+
+#[test]
+fn test_timescale_checks() {
+    use std::time::Duration;
+
+    // 2025, TAI-UTC is 37 s
+    let at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_736_942_400);
+    assert_eq!(expected_utc_offset(at), 37);
+
+    let mut data = vec![0u8; 64];
+    data[0] = 0x0b;
+    data[1] = 0x02;
+    data[2..4].copy_from_slice(&64u16.to_be_bytes());
+    let announce = |flags: u8, utc_offset: i16, class: u8| {
+        let mut data = data.clone();
+        data[7] = flags;
+        data[44..46].copy_from_slice(&utc_offset.to_be_bytes());
+        data[48] = class;
+        AnnounceMessage::try_from(&data[..]).unwrap()
+    };
+    const LEAP61: u8 = 0x01;
+    const LEAP59: u8 = 0x02;
+    const UTC_VALID: u8 = 0x04;
+    const PTP_TIMESCALE: u8 = 0x08;
+    const TIME_TRACEABLE: u8 = 0x10;
+
+    // A healthy GNSS grandmaster
+    let flags = PTP_TIMESCALE | UTC_VALID | TIME_TRACEABLE;
+    assert!(check(&announce(flags, 37, 6), at).is_empty());
+    // Off by one leap second, tolerated only with a leap second pending
+    assert_eq!(
+        check(&announce(flags, 36, 6), at),
+        [TimescaleIssue::WrongUtcOffset {
+            announced: 36,
+            expected: 37
+        }]
+    );
+    assert!(check(&announce(flags | LEAP61, 38, 6), at).is_empty());
+
+    assert_eq!(
+        check(&announce(PTP_TIMESCALE | TIME_TRACEABLE, 0, 248), at),
+        [TimescaleIssue::UtcOffsetNotValid]
+    );
+    assert_eq!(
+        check(&announce(UTC_VALID | LEAP59 | LEAP61, 37, 6), at),
+        [
+            TimescaleIssue::ConflictingLeapFlags,
+            TimescaleIssue::UtcFieldsOnArbTimescale,
+            TimescaleIssue::PrimaryClassOnArbTimescale(6)
+        ]
+    );
+    // A free running ARB clock
+    assert!(check(&announce(0, 0, 248), at).is_empty());
+}
+
+#[test]
+fn test_utc_offset_changes() {
+    let mut data = vec![0u8; 64];
+    data[0] = 0x0b;
+    data[1] = 0x02;
+    data[2..4].copy_from_slice(&64u16.to_be_bytes());
+    data[4] = 24;
+    let announce = |flags: u8, utc_offset: i16, gm: u8| {
+        let mut data = data.clone();
+        data[7] = flags;
+        data[44..46].copy_from_slice(&utc_offset.to_be_bytes());
+        data[53] = gm;
+        AnnounceMessage::try_from(&data[..]).unwrap()
+    };
+    const LEAP61: u8 = 0x01;
+    const VALID: u8 = 0x04 | 0x08;
+
+    let mut offsets = UtcOffsets::default();
+    assert_eq!(offsets.record(&announce(VALID, 37, 1)), None);
+    assert_eq!(offsets.record(&announce(VALID | LEAP61, 37, 1)), None);
+    let leap = offsets.record(&announce(VALID, 38, 1)).unwrap();
+    assert!(leap.leap_second);
+    assert_eq!((leap.before, leap.after), (37, 38));
+
+    // Not marked valid, not applied and not recorded
+    assert_eq!(offsets.record(&announce(0x08, 0, 1)), None);
+
+    // Another grandmaster with a stale offset
+    let change = offsets.record(&announce(VALID, 37, 2)).unwrap();
+    assert!(!change.leap_second);
+    assert_ne!(change.gm, change.previous_gm);
+    assert!(change.to_string().contains("check the GM configuration"));
+}
@@ -0,0 +1,232 @@
+//! Hosts ranked by the messages they sent over the last minute ('A')
+//!
+//! A domain drowning in Announces, e.g. from a misconfigured logAnnounceInterval
+//! or a clock stuck announcing on every port, shows up as multicast load on
+//! every switch and receiver. The host table only has lifetime totals, so the
+//! responsible device is hard to spot among hundreds of hosts. The ranking
+//! puts the hosts with the highest Announce, Sync or delay message rate first,
+//! with their share of the domain's messages of that kind.
+
+use std::time::{Duration, SystemTime};
+
+use crate::{
+    ptp::PtpHost,
+    time_range::bucket_start,
+    types::{ClockIdentity, PtpMessageType},
+};
+
+/// Time the rates are taken over
+pub const TOP_TALKERS_WINDOW: Duration = Duration::from_secs(60);
+
+const DELAY_MESSAGES: [PtpMessageType; 5] = [
+    PtpMessageType::DelayReq,
+    PtpMessageType::DelayResp,
+    PtpMessageType::PDelayReq,
+    PtpMessageType::PDelayResp,
+    PtpMessageType::PDelayRespFollowUp,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TalkerSort {
+    #[default]
+    Announce,
+    Sync,
+    Delay,
+    Total,
+}
+
+impl TalkerSort {
+    pub const ALL: [TalkerSort; 4] = [
+        TalkerSort::Announce,
+        TalkerSort::Sync,
+        TalkerSort::Delay,
+        TalkerSort::Total,
+    ];
+
+    pub fn next(&self) -> Self {
+        match self {
+            TalkerSort::Announce => TalkerSort::Sync,
+            TalkerSort::Sync => TalkerSort::Delay,
+            TalkerSort::Delay => TalkerSort::Total,
+            TalkerSort::Total => TalkerSort::Announce,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TalkerSort::Announce => "Announce",
+            TalkerSort::Sync => "Sync",
+            TalkerSort::Delay => "Delay",
+            TalkerSort::Total => "Total",
+        }
+    }
+}
+
+/// Messages sent over the window: Announce, Sync, Delay_Req/Resp and
+/// PDelay together, and all types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TalkerCounts {
+    pub announce: u32,
+    pub sync: u32,
+    pub delay: u32,
+    pub total: u32,
+}
+
+impl TalkerCounts {
+    pub fn get(&self, sort: TalkerSort) -> u32 {
+        match sort {
+            TalkerSort::Announce => self.announce,
+            TalkerSort::Sync => self.sync,
+            TalkerSort::Delay => self.delay,
+            TalkerSort::Total => self.total,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopTalker {
+    pub clock_identity: ClockIdentity,
+    pub domain: Option<u8>,
+    pub counts: TalkerCounts,
+}
+
+/// Ranking of the hosts of a domain, or of all domains
+#[derive(Debug, Clone, Default)]
+pub struct TopTalkers {
+    /// Hosts that sent anything over the window, highest count first
+    pub talkers: Vec<TopTalker>,
+    /// All messages of the ranked hosts
+    pub totals: TalkerCounts,
+    /// Seconds the counts actually cover, the window rounded to the count
+    /// buckets or less when the capture is shorter
+    pub seconds: f64,
+}
+
+impl TopTalkers {
+    /// Hosts of `domain` (all if None) ranked by `sort` over the window
+    /// ending at `now`
+    pub fn rank<'a, I>(hosts: I, domain: Option<u8>, sort: TalkerSort, now: SystemTime) -> Self
+    where
+        I: IntoIterator<Item = &'a PtpHost>,
+    {
+        let since = bucket_start(now.checked_sub(TOP_TALKERS_WINDOW).unwrap_or(now));
+        let mut start = now;
+        let mut talkers = Vec::new();
+        let mut totals = TalkerCounts::default();
+        for host in hosts {
+            if domain.is_some() && host.domain_number != domain {
+                continue;
+            }
+            let sent = &host.sent_counts;
+            let counts = TalkerCounts {
+                announce: sent.count(PtpMessageType::Announce, since),
+                sync: sent.count(PtpMessageType::Sync, since),
+                delay: DELAY_MESSAGES
+                    .iter()
+                    .map(|message_type| sent.count(*message_type, since))
+                    .sum(),
+                total: sent.total(since),
+            };
+            if counts.total == 0 {
+                continue;
+            }
+            totals.announce += counts.announce;
+            totals.sync += counts.sync;
+            totals.delay += counts.delay;
+            totals.total += counts.total;
+            if let Some(first_seen) = host.session_first_seen {
+                start = start.min(first_seen.max(since));
+            }
+            talkers.push(TopTalker {
+                clock_identity: host.clock_identity,
+                domain: host.domain_number,
+                counts,
+            });
+        }
+        // Equal counts keep the order of the clock identities
+        talkers.sort_by_key(|talker| {
+            (
+                std::cmp::Reverse(talker.counts.get(sort)),
+                talker.clock_identity,
+            )
+        });
+
+        Self {
+            talkers,
+            totals,
+            // At least a second, a single packet is no rate
+            seconds: now
+                .duration_since(start)
+                .unwrap_or_default()
+                .as_secs_f64()
+                .max(1.0),
+        }
+    }
+
+    /// Messages per second of a host
+    pub fn rate(&self, talker: &TopTalker, sort: TalkerSort) -> f64 {
+        talker.counts.get(sort) as f64 / self.seconds
+    }
+
+    /// Percentage of the messages of the kind that came from the host
+    pub fn share(&self, talker: &TopTalker, sort: TalkerSort) -> f64 {
+        match self.totals.get(sort) {
+            0 => 0.0,
+            total => talker.counts.get(sort) as f64 * 100.0 / total as f64,
+        }
+    }
+}
+
+#[test]
+fn test_top_talkers() {
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let host = |last: u8, domain: u8, announces_per_second: u64, seconds: u64| {
+        let mut host = PtpHost::new(
+            ClockIdentity::try_from(&[0x02, 0, 0, 0xff, 0xfe, 0, 0, last][..]).unwrap(),
+        );
+        host.domain_number = Some(domain);
+        host.session_first_seen = Some(start);
+        for second in 0..seconds {
+            let time = start + Duration::from_secs(second);
+            host.sent_counts.record(PtpMessageType::Sync, time);
+            for _ in 0..announces_per_second {
+                host.sent_counts.record(PtpMessageType::Announce, time);
+            }
+        }
+        host
+    };
+    // A clock announcing 8 times a second next to two sane ones
+    let hosts = [
+        host(1, 0, 1, 120),
+        host(2, 0, 8, 120),
+        host(3, 0, 1, 120),
+        host(4, 1, 0, 120),
+    ];
+    let now = start + Duration::from_secs(119);
+
+    let ranking = TopTalkers::rank(&hosts, Some(0), TalkerSort::Announce, now);
+    assert_eq!(ranking.talkers.len(), 3);
+    let top = &ranking.talkers[0];
+    assert_eq!(top.clock_identity, hosts[1].clock_identity);
+    assert_eq!(ranking.share(top, TalkerSort::Announce), 80.0);
+    let rate = ranking.rate(top, TalkerSort::Announce);
+    assert!((7.5..=8.5).contains(&rate), "{}", rate);
+    assert!((ranking.rate(top, TalkerSort::Sync) - 1.0).abs() < 0.1);
+
+    // Every host has the same Sync rate, ties are in clock identity order
+    let ranking = TopTalkers::rank(&hosts, None, TalkerSort::Sync, now);
+    assert_eq!(ranking.talkers.len(), 4);
+    assert_eq!(ranking.talkers[0].clock_identity, hosts[0].clock_identity);
+    assert_eq!(ranking.share(&ranking.talkers[3], TalkerSort::Sync), 25.0);
+    assert_eq!(ranking.share(&ranking.talkers[3], TalkerSort::Delay), 0.0);
+
+    // A capture shorter than the window is not diluted
+    let hosts = [host(5, 0, 0, 10)];
+    let short = TopTalkers::rank(
+        &hosts,
+        None,
+        TalkerSort::Sync,
+        start + Duration::from_secs(10),
+    );
+    assert_eq!(short.rate(&short.talkers[0], TalkerSort::Sync), 1.0);
+}
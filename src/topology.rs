@@ -0,0 +1,311 @@
+//! Passive inference of what sits on the path between a host and the capture point
+//!
+//! Nothing here is proof, only evidence collected from traffic as it is seen
+//! at the capture point:
+//! - Sync, Follow_Up and Delay_Req messages that arrive with a non-zero
+//!   correctionField passed through a transparent clock that added its
+//!   residence time
+//! - If the correctionField is always zero, they did not (or the capture point
+//!   is next to the sender)
+//! - A TTL below the usual initial values means the path is routed, and a
+//!   hop distance that changes mid-session means the route did
+//! - The same host showing up with different VLAN IDs means tags are rewritten
+//! - Several devices answering the same PDelay_Req means peer delay messages
+//!   are flooded by a bridge that does not terminate them, i.e. a PTP-unaware switch
+
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::Display,
+    net::IpAddr,
+};
+
+use crate::types::{ClockIdentity, ParsedPacket, PortIdentity, PtpMessage};
+
+/// Common initial TTL values of IP stacks. PTP multicast is often sent with TTL 1.
+const INITIAL_TTLS: [u8; 5] = [1, 32, 64, 128, 255];
+/// Timing messages needed before an all-zero correctionField counts as evidence
+const MIN_TIMING_MESSAGES: u32 = 16;
+/// Number of recent PDelay_Resp (sequence id, responder) pairs remembered
+const MAX_PDELAY_RESPONSES: usize = 64;
+
+/// Number of routed hops implied by a received TTL, assuming the sender used
+/// the closest common initial TTL at or above it
+pub fn hops_from_ttl(ttl: u8) -> u8 {
+    INITIAL_TTLS
+        .iter()
+        .find(|&&initial| initial >= ttl)
+        .map_or(0, |initial| initial - ttl)
+}
+
+/// Multicast PTP is not supposed to cross routers. Returns why a multicast
+/// packet looks like it did, if it does. `on_local_subnet` is None when the
+/// subnets of the capture interface are unknown, e.g. for pcap files.
+pub fn multicast_boundary_violation(
+    ttl: u8,
+    on_local_subnet: Option<bool>,
+    max_hops: Option<u8>,
+) -> Option<String> {
+    let hops = hops_from_ttl(ttl);
+    if let Some(max_hops) = max_hops
+        && hops > max_hops
+    {
+        return Some(format!(
+            "TTL {} implies {} routed hop(s), policy allows {}",
+            ttl, hops, max_hops
+        ));
+    }
+    if ttl == 1 && on_local_subnet == Some(false) {
+        return Some("TTL 1 traffic from outside the local subnet".to_string());
+    }
+    None
+}
+
+/// Routed hops towards a host, judged by the TTL of its packets. Kept per
+/// source address, a host on two networks is legitimately at two distances.
+#[derive(Debug, Clone, Default)]
+pub struct HopDistance {
+    hops: BTreeMap<IpAddr, u8>,
+    last: Option<u8>,
+    /// The latest change as (before, after)
+    pub last_change: Option<(u8, u8)>,
+    pub change_count: u32,
+}
+
+impl HopDistance {
+    /// Record the TTL of a packet from `source`. Returns the hop distance
+    /// before and after if it changed, i.e. the route towards the host did.
+    pub fn record(&mut self, source: IpAddr, ttl: u8) -> Option<(u8, u8)> {
+        let hops = hops_from_ttl(ttl);
+        self.last = Some(hops);
+        let before = self.hops.insert(source, hops)?;
+        if before == hops {
+            return None;
+        }
+        self.last_change = Some((before, hops));
+        self.change_count += 1;
+        Some((before, hops))
+    }
+
+    /// Hop distance of the latest packet
+    pub fn current(&self) -> Option<u8> {
+        self.last
+    }
+
+    pub fn changed(&self) -> bool {
+        self.change_count > 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathKind {
+    /// Not enough evidence yet
+    Unknown,
+    /// A transparent clock updated the correctionField
+    TransparentClock,
+    /// No sign of PTP support on the path
+    Unaware,
+    /// At least one router is on the path
+    Routed,
+}
+
+impl PathKind {
+    /// Short label for the tree view
+    pub fn short(&self) -> &'static str {
+        match self {
+            PathKind::Unknown => "?",
+            PathKind::TransparentClock => "TC",
+            PathKind::Unaware => "no TC",
+            PathKind::Routed => "routed",
+        }
+    }
+}
+
+impl Display for PathKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathKind::Unknown => write!(f, "Unknown"),
+            PathKind::TransparentClock => write!(f, "Transparent clock"),
+            PathKind::Unaware => write!(f, "PTP-unaware switch"),
+            PathKind::Routed => write!(f, "Routed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PathEvidence {
+    timing_message_count: u32,
+    corrected_timing_count: u32,
+    max_hops: u8,
+    vlan_ids: BTreeSet<u16>,
+    untagged_seen: bool,
+    pdelay_responses: VecDeque<(PortIdentity, u16, ClockIdentity)>,
+    shared_pdelay_link: bool,
+}
+
+impl PathEvidence {
+    /// Record a packet sent by the host
+    pub fn record_sent(&mut self, packet: &ParsedPacket) {
+        // A two-step TC puts the residence time into the Follow_Up
+        if let PtpMessage::Sync(_) | PtpMessage::FollowUp(_) | PtpMessage::DelayReq(_) = packet.ptp
+        {
+            self.timing_message_count += 1;
+            if packet.ptp.header().correction_field.value != 0 {
+                self.corrected_timing_count += 1;
+            }
+        }
+
+        if let Some(ttl) = packet.raw.ttl {
+            self.max_hops = self.max_hops.max(hops_from_ttl(ttl));
+        }
+
+        match packet.raw.vlan_id {
+            Some(vlan_id) => {
+                self.vlan_ids.insert(vlan_id);
+            }
+            None => self.untagged_seen = true,
+        }
+    }
+
+    /// Record a PDelay_Resp answering the PDelay_Req `sequence_id` of the
+    /// host's port `requester`. Other ports of the host may use the same
+    /// sequence ids, the responses to them are not answers to the same request.
+    pub fn record_pdelay_response(
+        &mut self,
+        requester: PortIdentity,
+        sequence_id: u16,
+        responder: ClockIdentity,
+    ) {
+        if self.pdelay_responses.iter().any(|&(port, seq, other)| {
+            port == requester && seq == sequence_id && other != responder
+        }) {
+            self.shared_pdelay_link = true;
+        }
+
+        self.pdelay_responses
+            .push_back((requester, sequence_id, responder));
+        if self.pdelay_responses.len() > MAX_PDELAY_RESPONSES {
+            self.pdelay_responses.pop_front();
+        }
+    }
+
+    fn vlan_rewritten(&self) -> bool {
+        self.vlan_ids.len() + usize::from(self.untagged_seen) > 1
+    }
+
+    /// Best guess of the path type, with the reasons for it
+    pub fn infer(&self) -> (PathKind, Vec<String>) {
+        let mut reasons = Vec::new();
+
+        if self.max_hops > 0 {
+            reasons.push(format!("TTL implies {} routed hop(s)", self.max_hops));
+        }
+        if self.vlan_rewritten() {
+            reasons.push(format!(
+                "seen with different VLAN tags ({})",
+                self.vlan_ids
+                    .iter()
+                    .map(|id| id.to_string())
+                    .chain(self.untagged_seen.then(|| "untagged".to_string()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if self.shared_pdelay_link {
+            reasons.push("several devices answered one PDelay_Req".to_string());
+        }
+
+        let kind = if self.corrected_timing_count > 0 {
+            reasons.push(format!(
+                "correctionField updated in {}/{} timing messages",
+                self.corrected_timing_count, self.timing_message_count
+            ));
+            PathKind::TransparentClock
+        } else if self.shared_pdelay_link {
+            PathKind::Unaware
+        } else if self.timing_message_count >= MIN_TIMING_MESSAGES {
+            reasons.push(format!(
+                "correctionField zero in all {} timing messages",
+                self.timing_message_count
+            ));
+            PathKind::Unaware
+        } else if self.max_hops > 0 {
+            PathKind::Routed
+        } else {
+            PathKind::Unknown
+        };
+
+        // Routing trumps the switch question, a router is never a TC
+        let kind = if self.max_hops > 0 && kind == PathKind::Unaware {
+            PathKind::Routed
+        } else {
+            kind
+        };
+
+        (kind, reasons)
+    }
+}
+
+#[test]
+fn test_hops_from_ttl() {
+    assert_eq!(hops_from_ttl(1), 0);
+    assert_eq!(hops_from_ttl(64), 0);
+    assert_eq!(hops_from_ttl(62), 2);
+    assert_eq!(hops_from_ttl(250), 5);
+}
+
+#[test]
+fn test_hop_distance() {
+    let a: IpAddr = "10.0.0.1".parse().unwrap();
+    let b: IpAddr = "10.1.0.1".parse().unwrap();
+    let mut distance = HopDistance::default();
+    assert_eq!(distance.current(), None);
+    assert_eq!(distance.record(a, 63), None);
+    assert_eq!(distance.record(a, 63), None);
+    // Another address of the host is not a route change
+    assert_eq!(distance.record(b, 128), None);
+    assert_eq!(distance.current(), Some(0));
+    assert!(!distance.changed());
+
+    assert_eq!(distance.record(a, 62), Some((1, 2)));
+    assert_eq!(distance.record(a, 62), None);
+    assert_eq!(distance.current(), Some(2));
+    assert_eq!(distance.last_change, Some((1, 2)));
+    assert_eq!(distance.change_count, 1);
+}
+
+#[test]
+fn test_multicast_boundary_violation() {
+    assert_eq!(multicast_boundary_violation(1, Some(true), Some(0)), None);
+    assert_eq!(multicast_boundary_violation(62, None, None), None);
+    assert!(multicast_boundary_violation(62, None, Some(1)).is_some());
+    assert!(multicast_boundary_violation(63, Some(true), Some(1)).is_none());
+    assert!(multicast_boundary_violation(1, Some(false), None).is_some());
+    assert!(multicast_boundary_violation(1, None, None).is_none());
+}
+
+#[test]
+fn test_shared_pdelay_link() {
+    let mut evidence = PathEvidence::default();
+    let a = ClockIdentity {
+        clock_id: [0, 0, 0, 0, 0, 0, 0, 1],
+    };
+    let b = ClockIdentity {
+        clock_id: [0, 0, 0, 0, 0, 0, 0, 2],
+    };
+
+    let port = |port_number| PortIdentity {
+        clock_identity: ClockIdentity {
+            clock_id: [0, 0, 0, 0, 0, 0, 0, 3],
+        },
+        port_number,
+    };
+
+    evidence.record_pdelay_response(port(1), 1, a);
+    evidence.record_pdelay_response(port(1), 2, a);
+    // Another port of the requester with the same sequence id has its own link
+    evidence.record_pdelay_response(port(2), 2, b);
+    assert_eq!(evidence.infer().0, PathKind::Unknown);
+
+    evidence.record_pdelay_response(port(1), 2, b);
+    assert_eq!(evidence.infer().0, PathKind::Unaware);
+}
@@ -0,0 +1,316 @@
+//! Guarded frame transmission for active features
+//!
+//! ptp-trace is passive by default and never puts anything on the wire. Active
+//! features (management queries, Delay_Req probing) must send through
+//! FrameTransmitter, which refuses to transmit unless --active was given,
+//! verifies that the interface is able to transmit PTP traffic, and supports a
+//! dry-run mode where frames are only recorded and logged instead of sent.
+//!
+//! Every frame that is sent (or would have been sent in dry-run mode) is handed
+//! back to the tracker so it shows up in the packet history tagged as "self".
+
+use anyhow::{Result, anyhow, bail};
+use pnet::datalink::{self, Channel, Config, DataLinkSender, NetworkInterface};
+use std::{
+    collections::{HashMap, VecDeque},
+    net::{IpAddr, Ipv4Addr},
+};
+
+use crate::{
+    source::{PacketOrigin, RawPacket, process_ethernet_packet},
+    types::PtpMessage,
+};
+
+/// Number of transmitted frames remembered to suppress their captured echo
+const MAX_PENDING_ECHOES: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransmitMode {
+    /// Never transmit (default)
+    Passive,
+    /// Record what would be transmitted without sending anything
+    DryRun,
+    /// Transmit frames on the wire
+    Active,
+}
+
+impl TransmitMode {
+    pub fn from_flags(active: bool, dry_run: bool) -> Self {
+        match (active, dry_run) {
+            (false, _) => TransmitMode::Passive,
+            (true, true) => TransmitMode::DryRun,
+            (true, false) => TransmitMode::Active,
+        }
+    }
+}
+
+/// Check that an interface is suitable for transmitting PTP frames
+pub fn check_interface_capabilities(interface: &NetworkInterface) -> Result<()> {
+    if interface.is_loopback() {
+        bail!("{} is a loopback interface", interface.name);
+    }
+    if !interface.is_up() {
+        bail!("{} is down", interface.name);
+    }
+    if !interface.is_running() {
+        bail!("{} has no carrier", interface.name);
+    }
+    if !interface.is_multicast() {
+        bail!("{} does not support multicast", interface.name);
+    }
+    match interface.mac {
+        Some(mac) if !mac.is_zero() => Ok(()),
+        _ => bail!("{} has no MAC address", interface.name),
+    }
+}
+
+pub struct FrameTransmitter {
+    mode: TransmitMode,
+    senders: HashMap<String, Box<dyn DataLinkSender>>,
+    /// MAC and IPv4 address of each interface enabled for transmit
+    addresses: HashMap<String, ([u8; 6], Option<Ipv4Addr>)>,
+    /// Frames sent (or dry-run) since the tracker last collected them
+    sent: Vec<RawPacket>,
+    /// Frames that went on the wire and may be seen again by the capture
+    pending_echoes: VecDeque<Vec<u8>>,
+}
+
+impl FrameTransmitter {
+    /// Prepare transmission on the given interfaces. In active mode, every
+    /// interface has to pass the capability checks and get a datalink channel.
+    pub fn new(mode: TransmitMode, interface_names: &[String]) -> Result<Self> {
+        let mut senders = HashMap::new();
+        let mut addresses = HashMap::new();
+
+        if mode != TransmitMode::Passive {
+            let interfaces = datalink::interfaces();
+            for name in interface_names {
+                let interface = interfaces
+                    .iter()
+                    .find(|iface| &iface.name == name)
+                    .ok_or_else(|| anyhow!("Interface {} not found", name))?;
+
+                check_interface_capabilities(interface)
+                    .map_err(|e| anyhow!("Cannot transmit on {}: {}", name, e))?;
+                let ipv4 = interface.ips.iter().find_map(|ip| match ip.ip() {
+                    IpAddr::V4(addr) => Some(addr),
+                    IpAddr::V6(_) => None,
+                });
+                let mac = interface.mac.map(|mac| mac.octets()).unwrap_or_default();
+                addresses.insert(name.clone(), (mac, ipv4));
+
+                if mode == TransmitMode::Active {
+                    let tx = match datalink::channel(interface, Config::default()) {
+                        Ok(Channel::Ethernet(tx, _)) => tx,
+                        Ok(_) => bail!("Unsupported channel type for interface {}", name),
+                        Err(e) => bail!("Failed to open transmit channel on {}: {}", name, e),
+                    };
+                    senders.insert(name.clone(), tx);
+                }
+            }
+        }
+
+        Ok(Self {
+            mode,
+            senders,
+            addresses,
+            sent: Vec::new(),
+            pending_echoes: VecDeque::new(),
+        })
+    }
+
+    pub fn mode(&self) -> TransmitMode {
+        self.mode
+    }
+
+    /// MAC and IPv4 address to send from on an interface, None if it is not
+    /// enabled for transmit
+    pub fn interface_addresses(&self, interface_name: &str) -> Option<([u8; 6], Option<Ipv4Addr>)> {
+        self.addresses.get(interface_name).copied()
+    }
+
+    /// Send a complete Ethernet frame carrying a PTP message on an interface.
+    /// Entry point for all active features.
+    pub fn send(&mut self, interface_name: &str, frame: Vec<u8>) -> Result<()> {
+        let origin = match self.mode {
+            TransmitMode::Passive => bail!(
+                "Refusing to transmit on {}: restart with --active to enable active features",
+                interface_name
+            ),
+            TransmitMode::DryRun => PacketOrigin::DryRun,
+            TransmitMode::Active => PacketOrigin::Transmitted,
+        };
+
+        // Only frames we are able to decode ourselves are allowed out
+        let mut raw_packet = process_ethernet_packet(&frame, interface_name)
+            .ok_or_else(|| anyhow!("Refusing to transmit a frame that is not PTP"))?;
+        raw_packet.origin = origin;
+
+        // Logged as a warning to be seen with the default log level
+        if self.mode == TransmitMode::DryRun {
+            tracing::warn!(
+                interface = interface_name,
+                decoded = %describe_frame(&raw_packet),
+                frame = %hex_string(&frame),
+                "Dry run, frame not sent"
+            );
+        }
+
+        if self.mode == TransmitMode::Active {
+            let sender = self.senders.get_mut(interface_name).ok_or_else(|| {
+                anyhow!("Interface {} is not enabled for transmit", interface_name)
+            })?;
+            match sender.send_to(&frame, None) {
+                Some(Ok(())) => {}
+                Some(Err(e)) => bail!("Failed to transmit on {}: {}", interface_name, e),
+                None => bail!("Failed to transmit on {}: no buffer space", interface_name),
+            }
+
+            self.pending_echoes.push_back(frame);
+            if self.pending_echoes.len() > MAX_PENDING_ECHOES {
+                self.pending_echoes.pop_front();
+            }
+        }
+
+        self.sent.push(raw_packet);
+        Ok(())
+    }
+
+    /// Take the frames sent since the last call, for recording in the packet history
+    pub fn take_sent(&mut self) -> Vec<RawPacket> {
+        std::mem::take(&mut self.sent)
+    }
+
+    /// Whether a captured packet is our own transmitted frame seen again by the
+    /// capture. The matching frame is forgotten so each frame is suppressed once.
+    pub fn is_echo(&mut self, raw_packet: &RawPacket) -> bool {
+        match self
+            .pending_echoes
+            .iter()
+            .position(|frame| *frame == raw_packet.data)
+        {
+            Some(index) => {
+                self.pending_echoes.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Type and fields of the PTP message of a frame
+fn describe_frame(raw_packet: &RawPacket) -> String {
+    PtpMessage::try_from(raw_packet.ptp_payload()).map_or_else(
+        |e| format!("undecodable PTP message: {}", e),
+        |msg| format!("{} {}", msg.header().message_type, msg),
+    )
+}
+
+fn hex_string(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+fn test_frame() -> Vec<u8> {
+    use crate::clock_description::{clock_identity_from_mac, request, request_frame};
+
+    let source = clock_identity_from_mac([0x02, 0, 0, 0, 0, 1]);
+    let target = clock_identity_from_mac([0x02, 0, 0, 0, 0, 2]);
+    request_frame(
+        &request(target, source, 0, 0, 1),
+        [0x02, 0, 0, 0, 0, 1],
+        Some(Ipv4Addr::new(192, 0, 2, 1)),
+    )
+}
+
+#[test]
+fn test_transmit_mode() {
+    assert_eq!(
+        TransmitMode::from_flags(false, false),
+        TransmitMode::Passive
+    );
+    // --dry-run without --active is refused by the CLI, but never transmits
+    assert_eq!(TransmitMode::from_flags(false, true), TransmitMode::Passive);
+    assert_eq!(TransmitMode::from_flags(true, true), TransmitMode::DryRun);
+    assert_eq!(TransmitMode::from_flags(true, false), TransmitMode::Active);
+
+    let mut passive = FrameTransmitter::new(TransmitMode::Passive, &["eth0".to_string()]).unwrap();
+    assert!(passive.send("eth0", test_frame()).is_err());
+    assert!(passive.take_sent().is_empty());
+
+    // Dry run records the frame without sending it, so there is no echo
+    let mut dry_run = FrameTransmitter::new(TransmitMode::DryRun, &[]).unwrap();
+    assert!(dry_run.send("eth0", b"not PTP".to_vec()).is_err());
+    dry_run.send("eth0", test_frame()).unwrap();
+    let sent = dry_run.take_sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].origin, PacketOrigin::DryRun);
+    assert!(describe_frame(&sent[0]).starts_with("MANAGEMENT "));
+    assert!(!dry_run.is_echo(&sent[0]));
+
+    // The frame is shown without raising the log level
+    let lines = crate::logging::capture(crate::logging::DEFAULT_LOG_LEVEL, || {
+        dry_run.send("eth0", test_frame()).unwrap();
+    });
+    assert_eq!(lines.len(), 1);
+    assert!(
+        lines[0]
+            .message
+            .starts_with("Dry run, frame not sent interface=eth0 decoded=MANAGEMENT ")
+    );
+    assert!(
+        lines[0]
+            .message
+            .contains(&format!("decoded={}", describe_frame(&sent[0])))
+    );
+    assert!(lines[0].message.contains(&hex_string(&test_frame())));
+}
+
+#[test]
+fn test_echo_detection() {
+    let mut transmitter = FrameTransmitter::new(TransmitMode::Passive, &[]).unwrap();
+    let frame = test_frame();
+    transmitter.pending_echoes.push_back(frame.clone());
+    let captured = process_ethernet_packet(&frame, "eth0").unwrap();
+
+    // Each transmitted frame is suppressed once
+    assert!(transmitter.is_echo(&captured));
+    assert!(!transmitter.is_echo(&captured));
+
+    let mut other = frame;
+    let last = other.len() - 1;
+    other[last] ^= 0xff;
+    transmitter.pending_echoes.push_back(other);
+    assert!(!transmitter.is_echo(&captured));
+    assert_eq!(transmitter.pending_echoes.len(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_interface_capabilities() {
+    let flags = (libc::IFF_UP | libc::IFF_RUNNING | libc::IFF_MULTICAST) as u32;
+    let interface = |flags: u32, mac: Option<[u8; 6]>| NetworkInterface {
+        name: "eth0".to_string(),
+        description: String::new(),
+        index: 2,
+        mac: mac.map(|mac| mac.into()),
+        ips: Vec::new(),
+        flags,
+    };
+    let mac = Some([0x02, 0, 0, 0, 0, 1]);
+
+    assert!(check_interface_capabilities(&interface(flags, mac)).is_ok());
+    assert!(
+        check_interface_capabilities(&interface(flags | libc::IFF_LOOPBACK as u32, mac)).is_err()
+    );
+    assert!(check_interface_capabilities(&interface(flags & !(libc::IFF_UP as u32), mac)).is_err());
+    assert!(
+        check_interface_capabilities(&interface(flags & !(libc::IFF_RUNNING as u32), mac)).is_err()
+    );
+    assert!(
+        check_interface_capabilities(&interface(flags & !(libc::IFF_MULTICAST as u32), mac))
+            .is_err()
+    );
+    assert!(check_interface_capabilities(&interface(flags, None)).is_err());
+    assert!(check_interface_capabilities(&interface(flags, Some([0; 6]))).is_err());
+}
@@ -0,0 +1,206 @@
+//! Long term trends of the clock quality a time transmitter announces
+//!
+//! A single change of clockAccuracy or offsetScaledLogVariance is normal, e.g.
+//! when a grandmaster loses and regains its GNSS lock. What is worth flagging
+//! is a sustained drift: every recent value worse than every value at the start
+//! of the observation window. Comparing the oldest and newest third of the
+//! window keeps single spikes from counting as a trend.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime},
+};
+
+/// Span of announces a trend is judged over
+const TREND_WINDOW: Duration = Duration::from_secs(600);
+/// A trend needs at least this much history, so a fresh host has none
+const MIN_TREND_SPAN: Duration = Duration::from_secs(120);
+/// Fewer samples than this are too noisy to call a trend
+const MIN_TREND_SAMPLES: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Improving,
+    Steady,
+    Degrading,
+}
+
+impl Trend {
+    /// Arrow shown next to the value, pointing in the direction of quality
+    pub fn arrow(&self) -> &'static str {
+        match self {
+            Trend::Improving => "▲",
+            Trend::Steady => "",
+            Trend::Degrading => "▼",
+        }
+    }
+
+    /// Trend of values where a higher number means worse quality
+    fn of<T: Copy + Ord>(samples: &[T]) -> Self {
+        if samples.len() < MIN_TREND_SAMPLES {
+            return Trend::Steady;
+        }
+
+        let third = samples.len() / 3;
+        let oldest = &samples[..third];
+        let newest = &samples[samples.len() - third..];
+        let (Some(oldest_min), Some(oldest_max), Some(newest_min), Some(newest_max)) = (
+            oldest.iter().min(),
+            oldest.iter().max(),
+            newest.iter().min(),
+            newest.iter().max(),
+        ) else {
+            return Trend::Steady;
+        };
+
+        if newest_min > oldest_max {
+            Trend::Degrading
+        } else if newest_max < oldest_min {
+            Trend::Improving
+        } else {
+            Trend::Steady
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct QualitySample {
+    timestamp: SystemTime,
+    /// clockAccuracy, only the enumerated values where lower is better
+    accuracy: Option<u8>,
+    offset_scaled_log_variance: u16,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct QualityTrend {
+    samples: VecDeque<QualitySample>,
+    /// Whether the current degradation has already been reported
+    degradation_reported: bool,
+}
+
+impl QualityTrend {
+    /// Record the quality from an Announce
+    pub fn record(
+        &mut self,
+        timestamp: SystemTime,
+        clock_accuracy: u8,
+        offset_scaled_log_variance: u16,
+    ) {
+        self.samples.push_back(QualitySample {
+            timestamp,
+            // 0xFE (unknown) and reserved values don't order
+            accuracy: (0x17..=0x31)
+                .contains(&clock_accuracy)
+                .then_some(clock_accuracy),
+            offset_scaled_log_variance,
+        });
+
+        while let Some(oldest) = self.samples.front() {
+            let age = timestamp
+                .duration_since(oldest.timestamp)
+                .unwrap_or_default();
+            if age > TREND_WINDOW {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn has_enough_history(&self) -> bool {
+        match (self.samples.front(), self.samples.back()) {
+            (Some(oldest), Some(newest)) => {
+                newest
+                    .timestamp
+                    .duration_since(oldest.timestamp)
+                    .unwrap_or_default()
+                    >= MIN_TREND_SPAN
+            }
+            _ => false,
+        }
+    }
+
+    pub fn accuracy_trend(&self) -> Trend {
+        if !self.has_enough_history() {
+            return Trend::Steady;
+        }
+        let values: Vec<u8> = self.samples.iter().filter_map(|s| s.accuracy).collect();
+        Trend::of(&values)
+    }
+
+    pub fn variance_trend(&self) -> Trend {
+        if !self.has_enough_history() {
+            return Trend::Steady;
+        }
+        let values: Vec<u16> = self
+            .samples
+            .iter()
+            .map(|s| s.offset_scaled_log_variance)
+            .collect();
+        Trend::of(&values)
+    }
+
+    /// Description of a degradation that started since the last call, so each
+    /// degradation is reported once until the quality recovers
+    pub fn take_new_degradation(&mut self) -> Option<String> {
+        let mut degraded = Vec::new();
+        if self.accuracy_trend() == Trend::Degrading {
+            degraded.push("clockAccuracy");
+        }
+        if self.variance_trend() == Trend::Degrading {
+            degraded.push("offsetScaledLogVariance");
+        }
+
+        if degraded.is_empty() {
+            self.degradation_reported = false;
+            return None;
+        }
+        if self.degradation_reported {
+            return None;
+        }
+        self.degradation_reported = true;
+
+        let (oldest, newest) = (self.samples.front()?, self.samples.back()?);
+        let minutes = newest
+            .timestamp
+            .duration_since(oldest.timestamp)
+            .unwrap_or_default()
+            .as_secs()
+            / 60;
+        Some(format!(
+            "{} worsening over {} min (variance 0x{:04x} -> 0x{:04x})",
+            degraded.join(" and "),
+            minutes,
+            oldest.offset_scaled_log_variance,
+            newest.offset_scaled_log_variance
+        ))
+    }
+}
+
+#[test]
+fn test_variance_trend() {
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut trend = QualityTrend::default();
+
+    // Variance creeping up over 10 minutes, one announce every 30 s
+    for i in 0..20u16 {
+        trend.record(
+            start + Duration::from_secs(30 * i as u64),
+            0x21,
+            0x4000 + i * 16,
+        );
+    }
+    assert_eq!(trend.variance_trend(), Trend::Degrading);
+    assert_eq!(trend.accuracy_trend(), Trend::Steady);
+    assert!(trend.take_new_degradation().is_some());
+    assert!(trend.take_new_degradation().is_none());
+
+    // A single spike in otherwise constant values is not a trend
+    let mut trend = QualityTrend::default();
+    for i in 0..20u16 {
+        let variance = if i == 15 { 0x6000 } else { 0x4000 };
+        trend.record(start + Duration::from_secs(30 * i as u64), 0x21, variance);
+    }
+    assert_eq!(trend.variance_trend(), Trend::Steady);
+    assert!(trend.take_new_degradation().is_none());
+}
@@ -21,6 +21,45 @@ impl PtpTimestamp {
         vec![44100, 48000, 96000]
     }
 
+    /// Leap seconds between TAI and UTC at this time, from hifitime's table
+    fn leap_seconds(&self) -> i64 {
+        use hifitime::{Duration, Epoch, TimeScale};
+
+        let epoch = Epoch::from_unix_duration(Duration::from_total_nanoseconds(
+            self.total_nanoseconds() as i128,
+        ));
+        let dur_utc = epoch.to_duration_in_time_scale(TimeScale::UTC);
+        let dur_tai = epoch.to_duration_in_time_scale(TimeScale::TAI);
+        (dur_tai - dur_utc).to_seconds().round() as i64
+    }
+
+    /// UTC time of the timestamp, which counts TAI seconds since 1970. The
+    /// currentUtcOffset announced by the grandmaster is used if known, the leap
+    /// second table otherwise.
+    pub fn utc_time(
+        &self,
+        utc_offset: Option<PtpUtcOffset>,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        let offset = utc_offset.map_or_else(|| self.leap_seconds(), |o| o.offset as i64);
+        let seconds = i64::try_from(self.seconds).ok()?.checked_sub(offset)?;
+        chrono::DateTime::from_timestamp(seconds, self.nanoseconds)
+    }
+
+    /// Wall clock time of the timestamp in a timezone (TAI -> UTC -> local)
+    pub fn format_in_timezone(
+        &self,
+        utc_offset: Option<PtpUtcOffset>,
+        timezone: chrono_tz::Tz,
+    ) -> String {
+        match self.utc_time(utc_offset) {
+            Some(utc) => utc
+                .with_timezone(&timezone)
+                .format("%Y-%m-%d %H:%M:%S%.9f %Z")
+                .to_string(),
+            None => "N/A".to_string(),
+        }
+    }
+
     pub fn format_common_samplerates(&self, prefix: &str) -> Vec<(String, String)> {
         if self.seconds == 0 && self.nanoseconds == 0 {
             return vec![];
@@ -102,6 +141,30 @@ pub fn format_timestamp(timestamp: Option<PtpTimestamp>) -> String {
     }
 }
 
+pub fn format_mac(mac: [u8; 6]) -> String {
+    mac.iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+#[test]
+fn test_ptp_timestamp_timezone() {
+    // 2025-01-15 12:00:37 TAI is 12:00:00 UTC with 37 leap seconds
+    let timestamp = PtpTimestamp {
+        seconds: 1_736_942_437,
+        nanoseconds: 500,
+    };
+    let utc = timestamp.utc_time(Some(PtpUtcOffset::new(37))).unwrap();
+    assert_eq!(utc.to_rfc3339(), "2025-01-15T12:00:00.000000500+00:00");
+    assert_eq!(timestamp.utc_time(None), Some(utc));
+
+    assert_eq!(
+        timestamp.format_in_timezone(Some(PtpUtcOffset::new(37)), chrono_tz::Europe::Berlin),
+        "2025-01-15 13:00:00.000000500 CET"
+    );
+}
+
 #[test]
 fn test_ptp_timestamp_formatting() {
     // Test TAI offset display functionality
@@ -124,7 +187,7 @@ fn test_ptp_timestamp_formatting() {
     timestamp[9] = (nanos & 0xff) as u8;
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum PtpMessageType {
     Sync = 0x0,
     DelayReq = 0x1,   // End-to-end delay request (transmitter-receiver mode)
@@ -138,6 +201,91 @@ pub enum PtpMessageType {
     Management = 0xd,
 }
 
+impl PtpMessageType {
+    /// Length of the fixed part of the message, header included. Anything
+    /// beyond this up to messageLength is a sequence of TLVs.
+    pub fn body_length(&self) -> usize {
+        match self {
+            PtpMessageType::Sync
+            | PtpMessageType::DelayReq
+            | PtpMessageType::FollowUp
+            | PtpMessageType::Signaling => 44,
+            PtpMessageType::Management => 48,
+            PtpMessageType::DelayResp
+            | PtpMessageType::PDelayReq
+            | PtpMessageType::PDelayResp
+            | PtpMessageType::PDelayRespFollowUp => 54,
+            PtpMessageType::Announce => 64,
+        }
+    }
+
+    /// End of each field of the fixed part, labeled as in the message
+    /// details, to tell which ones a truncated capture is missing
+    fn body_fields(&self) -> &'static [(&'static str, usize)] {
+        match self {
+            PtpMessageType::Sync | PtpMessageType::PDelayReq => &[("OriginTS", 44)],
+            PtpMessageType::FollowUp => &[("PreciseOriginTS", 44)],
+            PtpMessageType::DelayReq => &[("Origin TS", 44)],
+            PtpMessageType::DelayResp => &[("Receive TS", 44), ("Requesting PI", 54)],
+            PtpMessageType::PDelayResp => &[("RequestReceiptTS", 44), ("RequestingPI", 54)],
+            PtpMessageType::PDelayRespFollowUp => &[("ResponseOriginTS", 44), ("RequestingPI", 54)],
+            PtpMessageType::Signaling => &[("Target PI", 44)],
+            PtpMessageType::Management => &[
+                ("Target PI", 44),
+                ("StartingBoundaryHops", 45),
+                ("BoundaryHops", 46),
+                ("ActionField", 47),
+            ],
+            PtpMessageType::Announce => &[
+                ("Origin Timestamp", 44),
+                ("Current UTC Offset", 46),
+                ("Priority1", 48),
+                ("Clock Class", 49),
+                ("Clock Accuracy", 50),
+                ("Offset Scaled Log Variance", 52),
+                ("Priority2", 53),
+                ("PTP Identity", 61),
+                ("Steps Removed", 63),
+                ("Time Source", 64),
+            ],
+        }
+    }
+
+    /// Event messages are timestamped on the wire, any queuing delay they
+    /// pick up ends up in the time transfer
+    pub fn is_event(&self) -> bool {
+        matches!(
+            self,
+            PtpMessageType::Sync
+                | PtpMessageType::DelayReq
+                | PtpMessageType::PDelayReq
+                | PtpMessageType::PDelayResp
+        )
+    }
+
+    pub fn all() -> [PtpMessageType; 10] {
+        [
+            PtpMessageType::Sync,
+            PtpMessageType::DelayReq,
+            PtpMessageType::PDelayReq,
+            PtpMessageType::PDelayResp,
+            PtpMessageType::FollowUp,
+            PtpMessageType::DelayResp,
+            PtpMessageType::PDelayRespFollowUp,
+            PtpMessageType::Announce,
+            PtpMessageType::Signaling,
+            PtpMessageType::Management,
+        ]
+    }
+
+    /// Message type by its display name, e.g. "ANNOUNCE" or "delay_resp"
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all()
+            .into_iter()
+            .find(|message_type| message_type.to_string().eq_ignore_ascii_case(name))
+    }
+}
+
 impl TryFrom<u8> for PtpMessageType {
     type Error = anyhow::Error;
 
@@ -221,6 +369,44 @@ impl ClockIdentity {
 
         lookup_vendor_bytes(mac_bytes)
     }
+
+    /// Last 3 octets after the first word of the vendor name, e.g.
+    /// "Meinberg_12:34:56", for narrow columns
+    pub fn abbreviated(&self) -> String {
+        let octets = format!(
+            "{:02x}:{:02x}:{:02x}",
+            self.clock_id[5], self.clock_id[6], self.clock_id[7]
+        );
+        match self
+            .extract_vendor_name()
+            .and_then(|vendor| vendor.split_whitespace().next())
+        {
+            Some(vendor) => format!("{}_{}", vendor.chars().take(8).collect::<String>(), octets),
+            None => octets,
+        }
+    }
+}
+
+/// Parse a clock identity like "00:1b:19:ff:fe:12:34:56", with or without separators
+impl std::str::FromStr for ClockIdentity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits: String = s
+            .chars()
+            .filter(|c| !matches!(c, ':' | '-' | '.'))
+            .collect();
+        let invalid = || format!("Invalid clock identity '{}', expected 8 hex bytes", s);
+        if digits.len() != 16 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let mut clock_id = [0u8; 8];
+        for (i, byte) in clock_id.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+        }
+        Ok(Self { clock_id })
+    }
 }
 
 impl TryFrom<&[u8]> for ClockIdentity {
@@ -254,6 +440,24 @@ fn test_oui_vendor_lookup() {
     );
 }
 
+#[test]
+fn test_clock_identity_parsing() {
+    let id: ClockIdentity = "00:1b:19:ff:fe:12:34:56".parse().unwrap();
+    assert_eq!(
+        id.clock_id,
+        [0x00, 0x1b, 0x19, 0xff, 0xfe, 0x12, 0x34, 0x56]
+    );
+    assert_eq!(id.to_string().parse::<ClockIdentity>(), Ok(id));
+    assert_eq!("001B19FFFE123456".parse::<ClockIdentity>(), Ok(id));
+    assert!("00:1b:19:ff:fe:12:34".parse::<ClockIdentity>().is_err());
+    assert!("00:1b:19:ff:fe:12:34:zz".parse::<ClockIdentity>().is_err());
+
+    let meinberg: ClockIdentity = "ec:46:70:ff:fe:12:34:56".parse().unwrap();
+    assert_eq!(meinberg.abbreviated(), "Meinberg_12:34:56");
+    let unknown: ClockIdentity = "02:00:00:ff:fe:12:34:56".parse().unwrap();
+    assert_eq!(unknown.abbreviated(), "12:34:56");
+}
+
 impl Display for ClockIdentity {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -499,6 +703,34 @@ impl PtpHeaderFlags {
         format!("{:02x}{:02x}", self.v[0], self.v[1])
     }
 
+    pub fn two_step(&self) -> bool {
+        self.two_step_flag
+    }
+
+    pub fn unicast(&self) -> bool {
+        self.unicast_flag
+    }
+
+    pub fn leap61(&self) -> bool {
+        self.leap61
+    }
+
+    pub fn leap59(&self) -> bool {
+        self.leap59
+    }
+
+    pub fn current_utc_offset_valid(&self) -> bool {
+        self.current_utc_offset_valid
+    }
+
+    pub fn ptp_timescale(&self) -> bool {
+        self.ptp_timescale
+    }
+
+    pub fn time_traceable(&self) -> bool {
+        self.time_traceable
+    }
+
     pub fn details(&self) -> Vec<(&str, bool)> {
         vec![
             ("Alternate TT Flag", self.alternate_tt_flag),
@@ -560,12 +792,41 @@ fn format_kv(kv: Vec<(String, String)>) -> String {
         .join(", ")
 }
 
+/// sdoId of IEEE 802.1AS gPTP domains (majorSdoId 0x1)
+pub const SDO_ID_GPTP: u16 = 0x100;
+/// sdoId of the IEEE 802.1AS-2020 Common Mean Link Delay Service (majorSdoId 0x2)
+pub const SDO_ID_CMLDS: u16 = 0x200;
+
+/// Standard organization an sdoId belongs to
+pub fn sdo_id_name(sdo_id: u16) -> &'static str {
+    match sdo_id {
+        0 => "IEEE 1588 default",
+        SDO_ID_CMLDS => "IEEE 802.1AS CMLDS",
+        id if id >> 8 == SDO_ID_GPTP >> 8 => "IEEE 802.1AS gPTP",
+        _ => "unassigned",
+    }
+}
+
+/// Human readable name of a PTP instance, identified by sdoId and domain number
+pub fn format_ptp_instance(sdo_id: u16, domain_number: u8) -> String {
+    match sdo_id {
+        0 => domain_number.to_string(),
+        SDO_ID_CMLDS => "CMLDS".to_string(),
+        id if id >> 8 == SDO_ID_GPTP >> 8 => format!("gPTP {}", domain_number),
+        id => format!("{} (sdoId 0x{:03x})", domain_number, id),
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct PtpHeader {
     pub message_type: PtpMessageType,
     pub version: PtpVersion,
+    /// minorVersionPTP, 0 for IEEE 1588-2008 and 1 for IEEE 1588-2019
+    pub minor_version: u8,
     pub message_length: u16,
     pub domain_number: u8,
+    /// 12 bit sdoId, majorSdoId (formerly transportSpecific) and minorSdoId combined
+    pub sdo_id: u16,
     pub flags: PtpHeaderFlags,
     pub correction_field: PtpCorrectionField,
     pub source_port_identity: PortIdentity,
@@ -574,6 +835,33 @@ pub struct PtpHeader {
     pub log_message_interval: PtpLogInterval,
 }
 
+impl PtpHeader {
+    /// IEEE 802.1AS gPTP message, for any gPTP domain
+    pub fn is_gptp(&self) -> bool {
+        self.sdo_id >> 8 == SDO_ID_GPTP >> 8
+    }
+
+    /// Peer delay message of the 802.1AS Common Mean Link Delay Service, which is
+    /// shared by all gPTP domains on a link rather than belonging to one
+    pub fn is_cmlds(&self) -> bool {
+        self.sdo_id == SDO_ID_CMLDS
+    }
+
+    /// Version with the minor version, e.g. "v2.1"
+    pub fn version_label(&self) -> String {
+        format!("{}.{}", self.version, self.minor_version)
+    }
+
+    /// Domain as shown in the packet history
+    pub fn domain_label(&self) -> String {
+        if self.is_cmlds() {
+            "CMLDS".to_string()
+        } else {
+            self.domain_number.to_string()
+        }
+    }
+}
+
 impl TryFrom<&[u8]> for PtpHeader {
     type Error = anyhow::Error;
 
@@ -584,8 +872,10 @@ impl TryFrom<&[u8]> for PtpHeader {
             Ok(PtpHeader {
                 message_type: PtpMessageType::try_from(data[0] & 0x0f)?,
                 version: PtpVersion::try_from(data[1] & 0x0f)?,
+                minor_version: data[1] >> 4,
                 message_length: u16::from_be_bytes([data[2], data[3]]),
                 domain_number: data[4],
+                sdo_id: ((data[0] as u16 >> 4) << 8) | data[5] as u16,
                 flags: PtpHeaderFlags::try_from(&data[6..8])?,
                 correction_field: PtpCorrectionField::new(i64::from_be_bytes([
                     data[8], data[9], data[10], data[11], data[12], data[13], data[14], data[15],
@@ -987,7 +1277,8 @@ impl TryFrom<&[u8]> for SignalingMessage {
     type Error = anyhow::Error;
 
     fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
-        if data.len() < 54 {
+        // A CANCEL_UNICAST_TRANSMISSION TLV makes for a 50 byte message
+        if data.len() < 44 {
             Err(anyhow::anyhow!("Invalid SignalingMessage length"))
         } else {
             Ok(SignalingMessage {
@@ -1038,7 +1329,7 @@ impl TryFrom<&[u8]> for ManagementMessage {
     type Error = anyhow::Error;
 
     fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
-        if data.len() < 54 {
+        if data.len() < PtpMessageType::Management.body_length() {
             Err(anyhow::anyhow!("Invalid ManagementMessage length"))
         } else {
             Ok(ManagementMessage {
@@ -1102,18 +1393,80 @@ impl PtpMessage {
             PtpMessage::Management(msg) => msg.details(),
         }
     }
+
+    /// Position of this message within its message exchange
+    /// (Sync -> Follow_Up, Delay_Req -> Delay_Resp, PDelay_Req -> PDelay_Resp -> PDelay_Resp_Follow_Up)
+    pub fn exchange_stage(&self) -> Option<u8> {
+        match self {
+            PtpMessage::Sync(_) | PtpMessage::DelayReq(_) | PtpMessage::PDelayReq(_) => Some(0),
+            PtpMessage::FollowUp(_) | PtpMessage::DelayResp(_) | PtpMessage::PDelayResp(_) => {
+                Some(1)
+            }
+            PtpMessage::PDelayRespFollowup(_) => Some(2),
+            PtpMessage::Announce(_) | PtpMessage::Signaling(_) | PtpMessage::Management(_) => None,
+        }
+    }
+
+    /// Whether both messages belong to the same message exchange, matched by
+    /// domain, sequence id and the port identities involved
+    pub fn is_exchange_partner(&self, other: &PtpMessage) -> bool {
+        let (a, b) = (self.header(), other.header());
+        if a.domain_number != b.domain_number
+            || a.sdo_id != b.sdo_id
+            || a.sequence_id != b.sequence_id
+        {
+            return false;
+        }
+
+        match (self, other) {
+            (PtpMessage::Sync(_), PtpMessage::FollowUp(_))
+            | (PtpMessage::FollowUp(_), PtpMessage::Sync(_)) => {
+                a.source_port_identity == b.source_port_identity
+            }
+            (PtpMessage::DelayReq(req), PtpMessage::DelayResp(resp))
+            | (PtpMessage::DelayResp(resp), PtpMessage::DelayReq(req)) => {
+                resp.requesting_port_identity == req.header.source_port_identity
+            }
+            (PtpMessage::PDelayReq(req), PtpMessage::PDelayResp(resp))
+            | (PtpMessage::PDelayResp(resp), PtpMessage::PDelayReq(req)) => {
+                resp.requesting_port_identity == req.header.source_port_identity
+            }
+            (PtpMessage::PDelayReq(req), PtpMessage::PDelayRespFollowup(fup))
+            | (PtpMessage::PDelayRespFollowup(fup), PtpMessage::PDelayReq(req)) => {
+                fup.requesting_port_identity == req.header.source_port_identity
+            }
+            (PtpMessage::PDelayResp(resp), PtpMessage::PDelayRespFollowup(fup))
+            | (PtpMessage::PDelayRespFollowup(fup), PtpMessage::PDelayResp(resp)) => {
+                resp.header.source_port_identity == fup.header.source_port_identity
+                    && resp.requesting_port_identity == fup.requesting_port_identity
+            }
+            _ => false,
+        }
+    }
 }
 
 impl TryFrom<&[u8]> for PtpMessage {
     type Error = anyhow::Error;
 
     fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
-        let header = PtpHeader::try_from(&data[..34])?;
+        let header = PtpHeader::try_from(data)?;
 
         if header.version != PtpVersion::V2 {
             return Err(anyhow::anyhow!("Unsupported PTP version"));
         }
 
+        // A capture with a small snaplen cuts the body short. Parse what is
+        // there with the rest zero filled, ParsedPacket::truncation tells the
+        // fields apart.
+        let padded;
+        let data = match header.message_type.body_length().checked_sub(data.len()) {
+            Some(missing) if missing > 0 => {
+                padded = [data, &vec![0; missing]].concat();
+                &padded[..]
+            }
+            _ => data,
+        };
+
         match header.message_type {
             PtpMessageType::Announce => Ok(PtpMessage::Announce(AnnounceMessage::try_from(data)?)),
             PtpMessageType::DelayReq => Ok(PtpMessage::DelayReq(DelayReqMessage::try_from(data)?)),
@@ -1158,12 +1511,357 @@ impl Display for PtpMessage {
     }
 }
 
+/// Message body cut short by the capture, e.g. a pcap taken with a small
+/// snaplen. The header is complete, the fields past `captured` are missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncation {
+    pub captured: usize,
+    pub required: usize,
+}
+
+impl Truncation {
+    /// None if the payload holds the whole fixed part of the message
+    pub fn of(message_type: PtpMessageType, payload_length: usize) -> Option<Self> {
+        let required = message_type.body_length();
+        (payload_length < required).then_some(Truncation {
+            captured: payload_length,
+            required,
+        })
+    }
+
+    /// Labels of the body fields not captured in full
+    pub fn missing_fields(&self, message_type: PtpMessageType) -> Vec<&'static str> {
+        message_type
+            .body_fields()
+            .iter()
+            .filter(|(_, end)| *end > self.captured)
+            .map(|(label, _)| *label)
+            .collect()
+    }
+}
+
+impl Display for Truncation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "capture truncated: {} of {} bytes of the message",
+            self.captured, self.required
+        )
+    }
+}
+
+/// Inconsistency between the length fields of a PTP message and the bytes received
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthIssue {
+    /// messageLength claims more bytes than were received
+    Truncated {
+        message_length: u16,
+        payload_length: usize,
+    },
+    /// Bytes were received beyond messageLength
+    Padded {
+        message_length: u16,
+        payload_length: usize,
+    },
+    /// messageLength is too short for the fixed part of the message type
+    TooShort {
+        message_length: u16,
+        required: usize,
+    },
+    /// A TLV length field runs past the end of the message
+    TlvOverrun {
+        offset: usize,
+        tlv_type: u16,
+        tlv_length: u16,
+    },
+}
+
+impl Display for LengthIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LengthIssue::Truncated {
+                message_length,
+                payload_length,
+            } => write!(
+                f,
+                "truncated: messageLength {} but {} bytes received",
+                message_length, payload_length
+            ),
+            LengthIssue::Padded {
+                message_length,
+                payload_length,
+            } => write!(
+                f,
+                "padded: messageLength {} but {} bytes received",
+                message_length, payload_length
+            ),
+            LengthIssue::TooShort {
+                message_length,
+                required,
+            } => write!(
+                f,
+                "messageLength {} shorter than the {} byte message body",
+                message_length, required
+            ),
+            LengthIssue::TlvOverrun {
+                offset,
+                tlv_type,
+                tlv_length,
+            } => write!(
+                f,
+                "TLV 0x{:04x} at offset {} with length {} overruns the message",
+                tlv_type, offset, tlv_length
+            ),
+        }
+    }
+}
+
+/// Check messageLength against the received payload and walk the TLVs behind
+/// the message body to make sure none of them claims more bytes than there are.
+pub fn validate_message_length(header: &PtpHeader, payload: &[u8]) -> Vec<LengthIssue> {
+    let mut issues = Vec::new();
+    let message_length = header.message_length;
+    let payload_length = payload.len();
+
+    if (message_length as usize) > payload_length {
+        issues.push(LengthIssue::Truncated {
+            message_length,
+            payload_length,
+        });
+    } else if (message_length as usize) < payload_length {
+        issues.push(LengthIssue::Padded {
+            message_length,
+            payload_length,
+        });
+    }
+
+    let required = header.message_type.body_length();
+    if (message_length as usize) < required {
+        issues.push(LengthIssue::TooShort {
+            message_length,
+            required,
+        });
+        return issues;
+    }
+
+    // TLVs: 2 byte type, 2 byte length, value
+    let end = payload_length.min(message_length as usize);
+    let mut offset = required;
+    while offset + 4 <= end {
+        let tlv_type = u16::from_be_bytes([payload[offset], payload[offset + 1]]);
+        let tlv_length = u16::from_be_bytes([payload[offset + 2], payload[offset + 3]]);
+        if offset + 4 + tlv_length as usize > end {
+            issues.push(LengthIssue::TlvOverrun {
+                offset,
+                tlv_type,
+                tlv_length,
+            });
+            break;
+        }
+        offset += 4 + tlv_length as usize;
+    }
+
+    issues
+}
+
+/// Type and value of the TLVs behind the message body, up to the first one
+/// that overruns the message
+pub fn tlvs<'a>(header: &PtpHeader, payload: &'a [u8]) -> Vec<(u16, &'a [u8])> {
+    let end = payload.len().min(header.message_length as usize);
+    let mut offset = header.message_type.body_length();
+    let mut tlvs = Vec::new();
+    while offset + 4 <= end {
+        let tlv_type = u16::from_be_bytes([payload[offset], payload[offset + 1]]);
+        let tlv_length = u16::from_be_bytes([payload[offset + 2], payload[offset + 3]]) as usize;
+        let value_end = offset + 4 + tlv_length;
+        if value_end > end {
+            break;
+        }
+        tlvs.push((tlv_type, &payload[offset + 4..value_end]));
+        offset = value_end;
+    }
+    tlvs
+}
+
+/// Value of the first TLV of `tlv_type` behind the message body, if any
+pub fn find_tlv<'a>(header: &PtpHeader, payload: &'a [u8], tlv_type: u16) -> Option<&'a [u8]> {
+    tlvs(header, payload)
+        .into_iter()
+        .find(|(current_type, _)| *current_type == tlv_type)
+        .map(|(_, value)| value)
+}
+
+/// PATH_TRACE TLV of 802.1AS Announces
+pub const TLV_PATH_TRACE: u16 = 0x0008;
+
+/// clockIdentities listed in a PATH_TRACE TLV, from the grandmaster down to
+/// the sender. Each time-aware relay appends its own identity.
+pub fn parse_path_trace(value: &[u8]) -> Vec<ClockIdentity> {
+    value
+        .chunks_exact(8)
+        .map(|chunk| {
+            let mut clock_id = [0u8; 8];
+            clock_id.copy_from_slice(chunk);
+            ClockIdentity { clock_id }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnicastTlvKind {
+    Request,
+    Grant,
+    Cancel,
+    AcknowledgeCancel,
+}
+
+/// Unicast negotiation TLV of a Signaling message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnicastTlv {
+    pub kind: UnicastTlvKind,
+    /// Message type of the unicast transmission, e.g. Announce or Sync
+    pub message_type: Option<PtpMessageType>,
+    /// logInterMessagePeriod, requests and grants only
+    pub log_period: Option<i8>,
+    /// durationField in seconds, requests and grants only
+    pub duration: Option<u32>,
+}
+
+impl UnicastTlv {
+    /// A grant for 0 seconds refuses the request
+    pub fn is_denial(&self) -> bool {
+        self.kind == UnicastTlvKind::Grant && self.duration == Some(0)
+    }
+
+    /// Whether the TLV ends or refuses a unicast session
+    pub fn is_teardown(&self) -> bool {
+        matches!(
+            self.kind,
+            UnicastTlvKind::Cancel | UnicastTlvKind::AcknowledgeCancel
+        ) || self.is_denial()
+    }
+}
+
+impl Display for UnicastTlv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message_type = self
+            .message_type
+            .map_or("UNKNOWN".to_string(), |message_type| {
+                message_type.to_string()
+            });
+        match self.kind {
+            UnicastTlvKind::Request => write!(f, "REQUEST {}", message_type)?,
+            UnicastTlvKind::Grant if self.is_denial() => {
+                return write!(f, "GRANT {} denied", message_type);
+            }
+            UnicastTlvKind::Grant => write!(f, "GRANT {}", message_type)?,
+            UnicastTlvKind::Cancel => return write!(f, "CANCEL {}", message_type),
+            UnicastTlvKind::AcknowledgeCancel => {
+                return write!(f, "ACKNOWLEDGE_CANCEL {}", message_type);
+            }
+        }
+        if let (Some(log_period), Some(duration)) = (self.log_period, self.duration) {
+            write!(f, " log period {} for {} s", log_period, duration)?;
+        }
+        Ok(())
+    }
+}
+
+/// Unicast negotiation TLVs (REQUEST, GRANT, CANCEL and ACKNOWLEDGE_CANCEL
+/// UNICAST_TRANSMISSION) of a Signaling message
+pub fn unicast_tlvs(header: &PtpHeader, payload: &[u8]) -> Vec<UnicastTlv> {
+    tlvs(header, payload)
+        .into_iter()
+        .filter_map(|(tlv_type, value)| {
+            let kind = match tlv_type {
+                0x0004 => UnicastTlvKind::Request,
+                0x0005 => UnicastTlvKind::Grant,
+                0x0006 => UnicastTlvKind::Cancel,
+                0x0007 => UnicastTlvKind::AcknowledgeCancel,
+                _ => return None,
+            };
+            let message_type = PtpMessageType::try_from(*value.first()? >> 4).ok();
+            let (log_period, duration) = match kind {
+                UnicastTlvKind::Request | UnicastTlvKind::Grant if value.len() >= 6 => (
+                    Some(value[1] as i8),
+                    Some(u32::from_be_bytes([value[2], value[3], value[4], value[5]])),
+                ),
+                _ => (None, None),
+            };
+            Some(UnicastTlv {
+                kind,
+                message_type,
+                log_period,
+                duration,
+            })
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct ParsedPacket {
     pub ptp: PtpMessage,
     pub raw: std::sync::Arc<crate::source::RawPacket>,
 }
 
+/// Ethernet frames shorter than this (without FCS, with a VLAN tag) are padded by the sender
+const MIN_ETHERNET_FRAME_SIZE: usize = 64;
+
+impl ParsedPacket {
+    /// Length problems of this packet. Padding of short layer 2 frames up to the
+    /// Ethernet minimum frame size is not the sender's PTP stack at fault.
+    pub fn length_issues(&self) -> Vec<LengthIssue> {
+        let mut issues = validate_message_length(self.ptp.header(), self.raw.ptp_payload());
+        if self.raw.source_addr.is_none() && self.raw.data.len() <= MIN_ETHERNET_FRAME_SIZE {
+            issues.retain(|issue| !matches!(issue, LengthIssue::Padded { .. }));
+        }
+        // A body cut short by the capture's snaplen is not the sender's fault
+        if self.truncation().is_some() {
+            issues.retain(|issue| !matches!(issue, LengthIssue::Truncated { .. }));
+        }
+        issues
+    }
+
+    /// Part of the fixed body missing, cut by the capture or never sent.
+    /// Only the header is known then, the body is zero filled.
+    pub fn incomplete_body(&self) -> Option<Truncation> {
+        Truncation::of(self.ptp.header().message_type, self.raw.ptp_payload().len())
+    }
+
+    /// Body cut short by the capture, None if it is complete or the sender
+    /// sent it short
+    pub fn truncation(&self) -> Option<Truncation> {
+        self.incomplete_body().filter(|_| self.raw.is_cut_short())
+    }
+
+    /// Message details, with the fields an incomplete body is missing
+    /// marked instead of showing their zero fill. Values derived from a
+    /// field, listed after it, go with it.
+    pub fn details(&self) -> Vec<(String, String)> {
+        let details = self.ptp.details();
+        let Some(truncation) = self.incomplete_body() else {
+            return details;
+        };
+        let message_type = self.ptp.header().message_type;
+        let fields = message_type.body_fields();
+        let missing = truncation.missing_fields(message_type);
+        let mut field_missing = false;
+        details
+            .into_iter()
+            .map(|(label, value)| {
+                if fields.iter().any(|(field, _)| *field == label) {
+                    field_missing = missing.contains(&label.as_str());
+                }
+                if field_missing {
+                    (label, "(truncated)".to_string())
+                } else {
+                    (label, value)
+                }
+            })
+            .collect()
+    }
+}
+
 #[test]
 fn test_ptp_header_parsing() {
     let header_data = [
@@ -1238,6 +1936,69 @@ fn test_followup_message_parsing() {
     assert_eq!(followup.precise_origin_timestamp.nanoseconds, 500000001);
 }
 
+#[test]
+fn test_exchange_partner_matching() {
+    let sync_data = [
+        0x00, 0x02, 0x00, 0x2C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1B, 0x19, 0xFF, 0xFE, 0x44, 0x55, 0x66, 0x00, 0x01,
+        0x00, 0x7B, 0x00, 0xFF, 0x00, 0x00, 0x3B, 0x9A, 0xCA, 0x00, 0x1D, 0xCD, 0x65, 0x00,
+    ];
+    let mut followup_data = sync_data;
+    followup_data[0] = 0x08;
+    followup_data[32] = 0x02;
+
+    let sync = PtpMessage::try_from(&sync_data[..]).unwrap();
+    let followup = PtpMessage::try_from(&followup_data[..]).unwrap();
+
+    assert!(sync.is_exchange_partner(&followup));
+    assert!(followup.is_exchange_partner(&sync));
+    assert!(!sync.is_exchange_partner(&sync));
+    assert_eq!(sync.exchange_stage(), Some(0));
+    assert_eq!(followup.exchange_stage(), Some(1));
+
+    // Different sequence id
+    followup_data[31] = 0x7C;
+    let other = PtpMessage::try_from(&followup_data[..]).unwrap();
+    assert!(!sync.is_exchange_partner(&other));
+
+    // Different sender
+    followup_data[31] = 0x7B;
+    followup_data[27] = 0x77;
+    let other = PtpMessage::try_from(&followup_data[..]).unwrap();
+    assert!(!sync.is_exchange_partner(&other));
+}
+
+#[test]
+fn test_sdo_id_parsing() {
+    let mut header_data = [
+        0x12, 0x02, 0x00, 0x36, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1B, 0x19, 0xFF, 0xFE, 0x12, 0x34, 0x56, 0x00, 0x01,
+        0x00, 0x64, 0x00, 0x00,
+    ];
+
+    // gPTP PDelay_Req in domain 1
+    let header = PtpHeader::try_from(&header_data[..]).unwrap();
+    assert_eq!(header.sdo_id, SDO_ID_GPTP);
+    assert!(header.is_gptp());
+    assert!(!header.is_cmlds());
+    assert_eq!(
+        format_ptp_instance(header.sdo_id, header.domain_number),
+        "gPTP 1"
+    );
+    assert_eq!(sdo_id_name(header.sdo_id), "IEEE 802.1AS gPTP");
+
+    // CMLDS PDelay_Req
+    header_data[0] = 0x22;
+    header_data[4] = 0x00;
+    let header = PtpHeader::try_from(&header_data[..]).unwrap();
+    assert_eq!(header.sdo_id, SDO_ID_CMLDS);
+    assert!(header.is_cmlds());
+    assert_eq!(header.domain_label(), "CMLDS");
+    assert_eq!(sdo_id_name(header.sdo_id), "IEEE 802.1AS CMLDS");
+    assert_eq!(sdo_id_name(0), "IEEE 1588 default");
+    assert_eq!(sdo_id_name(0x300), "unassigned");
+}
+
 #[test]
 fn test_ptp_header_parsing_errors() {
     let short_data = [0u8; 33];
@@ -1258,6 +2019,79 @@ fn test_ptp_header_parsing_errors() {
     assert!(PtpHeader::try_from(&invalid_ver_data[..]).is_err());
 }
 
+#[test]
+fn test_message_length_validation() {
+    // Announce with a 12 byte path trace TLV (one clock identity)
+    let mut announce = [0u8; 76];
+    announce[0] = 0x0b;
+    announce[1] = 0x02;
+    announce[2..4].copy_from_slice(&76u16.to_be_bytes());
+    announce[64..66].copy_from_slice(&0x0008u16.to_be_bytes());
+    announce[66..68].copy_from_slice(&8u16.to_be_bytes());
+    let header = PtpHeader::try_from(&announce[..]).unwrap();
+    assert!(validate_message_length(&header, &announce).is_empty());
+    assert_eq!(
+        find_tlv(&header, &announce, TLV_PATH_TRACE).map(parse_path_trace),
+        Some(vec![ClockIdentity::default()])
+    );
+    assert_eq!(find_tlv(&header, &announce, 0x0003), None);
+
+    // Received bytes cut short
+    assert_eq!(
+        validate_message_length(&header, &announce[..70]),
+        vec![
+            LengthIssue::Truncated {
+                message_length: 76,
+                payload_length: 70
+            },
+            LengthIssue::TlvOverrun {
+                offset: 64,
+                tlv_type: 0x0008,
+                tlv_length: 8
+            }
+        ]
+    );
+
+    // TLV length larger than the message
+    announce[66..68].copy_from_slice(&16u16.to_be_bytes());
+    assert!(matches!(
+        validate_message_length(&header, &announce)[..],
+        [LengthIssue::TlvOverrun { offset: 64, .. }]
+    ));
+}
+
+#[test]
+fn test_unicast_tlvs() {
+    // Signaling with a CANCEL_UNICAST_TRANSMISSION TLV for Sync
+    let mut signaling = [0u8; 50];
+    signaling[0] = 0x0c;
+    signaling[1] = 0x02;
+    signaling[2..4].copy_from_slice(&50u16.to_be_bytes());
+    signaling[44..46].copy_from_slice(&0x0006u16.to_be_bytes());
+    signaling[46..48].copy_from_slice(&2u16.to_be_bytes());
+    let message = PtpMessage::try_from(&signaling[..]).unwrap();
+    let tlvs = unicast_tlvs(message.header(), &signaling);
+    assert_eq!(tlvs.len(), 1);
+    assert_eq!(tlvs[0].kind, UnicastTlvKind::Cancel);
+    assert_eq!(tlvs[0].message_type, Some(PtpMessageType::Sync));
+    assert!(tlvs[0].is_teardown());
+    assert_eq!(tlvs[0].to_string(), "CANCEL SYNC");
+
+    // GRANT_UNICAST_TRANSMISSION of Announce for 0 s is a denial
+    let mut signaling = [0u8; 56];
+    signaling[0] = 0x0c;
+    signaling[1] = 0x02;
+    signaling[2..4].copy_from_slice(&56u16.to_be_bytes());
+    signaling[44..46].copy_from_slice(&0x0005u16.to_be_bytes());
+    signaling[46..48].copy_from_slice(&8u16.to_be_bytes());
+    signaling[48] = 0xb0;
+    signaling[49] = 1;
+    let header = PtpHeader::try_from(&signaling[..]).unwrap();
+    let tlvs = unicast_tlvs(&header, &signaling);
+    assert!(tlvs[0].is_denial());
+    assert_eq!(tlvs[0].to_string(), "GRANT ANNOUNCE denied");
+}
+
 #[test]
 fn test_message_parsing_errors() {
     let short_announce = [0u8; 63];
@@ -1265,4 +2099,72 @@ fn test_message_parsing_errors() {
 
     let short_sync = [0u8; 43];
     assert!(SyncMessage::try_from(&short_sync[..]).is_err());
+
+    let short_header = [0u8; 20];
+    assert!(PtpMessage::try_from(&short_header[..]).is_err());
+}
+
+#[test]
+fn test_truncated_capture() {
+    use crate::source::RawPacket;
+
+    // An Announce captured with 54 of its 64 bytes: up to Priority2
+    let mut announce = [0u8; 54];
+    announce[0] = 0x0b;
+    announce[1] = 0x02;
+    announce[2..4].copy_from_slice(&64u16.to_be_bytes());
+    announce[47] = 128;
+    announce[48] = 6;
+    assert!(AnnounceMessage::try_from(&announce[..]).is_err());
+
+    let ptp = PtpMessage::try_from(&announce[..]).unwrap();
+    let PtpMessage::Announce(parsed) = ptp else {
+        panic!("not an Announce");
+    };
+    assert_eq!(parsed.priority1, 128);
+    assert_eq!(parsed.clock_class.class(), 6);
+
+    let mut packet = ParsedPacket {
+        ptp,
+        raw: std::sync::Arc::new(RawPacket {
+            original_length: 64,
+            ..RawPacket::from_ptp_payload(&announce, "pcap", std::time::SystemTime::UNIX_EPOCH)
+        }),
+    };
+    let truncation = packet.truncation().unwrap();
+    assert_eq!(
+        truncation,
+        Truncation {
+            captured: 54,
+            required: 64
+        }
+    );
+    assert_eq!(
+        truncation.missing_fields(PtpMessageType::Announce),
+        vec!["PTP Identity", "Steps Removed", "Time Source"]
+    );
+    let details = packet.details();
+    let value = |label: &str| &details.iter().find(|(key, _)| key == label).unwrap().1;
+    assert_eq!(value("Priority1"), "128");
+    assert_eq!(value("Steps Removed"), "(truncated)");
+    // The capture's fault, not the sender's
+    assert!(packet.length_issues().is_empty());
+
+    // Received in full but shorter than messageLength: the sender's fault
+    std::sync::Arc::make_mut(&mut packet.raw).original_length = announce.len();
+    assert_eq!(packet.truncation(), None);
+    assert_eq!(packet.incomplete_body(), Some(truncation));
+    assert_eq!(
+        packet.length_issues(),
+        vec![LengthIssue::Truncated {
+            message_length: 64,
+            payload_length: 54
+        }]
+    );
+    let details = packet.details();
+    let value = |label: &str| &details.iter().find(|(key, _)| key == label).unwrap().1;
+    assert_eq!(value("Steps Removed"), "(truncated)");
+
+    // Nothing to go by without a complete header
+    assert!(PtpMessage::try_from(&announce[..33]).is_err());
 }
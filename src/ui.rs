@@ -7,39 +7,64 @@ use ratatui::{
 };
 
 use crate::{
-    app::{ActiveView, App, SortColumn, TreeNode},
-    ptp::{PtpHost, PtpHostState},
-    types::{ParsedPacket, PtpClockAccuracy, PtpClockClass, format_timestamp},
+    alerts::AlertSeverity,
+    app::{ActiveView, App, NumberFormat, PendingExport, SortColumn},
+    arrival_paths::ArrivalPaths,
+    baseline::{Baseline, BaselineStatus, HostSnapshot},
+    bmca::SystemIdentity,
+    capture_workers::WorkerStats,
+    clock::Clock,
+    config::{SettingsField, TimeDisplayMode},
+    doctor::CheckStatus,
+    drill::format_drill_duration,
+    era::Era,
+    field_warnings::{FieldWarning, transmitter_warnings},
+    gm_identity::GmIdentityCase,
+    gm_pair::GmPairStatus,
+    golden::Golden,
+    health::format_badge_duration,
+    heartbeat::HeartbeatState,
+    keymap::{self, HelpSection},
+    minimap::Minimap,
+    org_tlv,
+    port_matrix::PortClass,
+    profiling::{PROFILE_CYCLES, Stage},
+    ptp::{GmDesign, PtpHost, PtpHostState},
+    residence::HopResidence,
+    sim_receiver::ReceiverState,
+    swimlane::{self, Swimlane, lane_cells},
+    time_range::TimeRange,
+    top_talkers::TalkerSort,
+    topology::PathKind,
+    transmit::TransmitMode,
+    trend::Trend,
+    types::{
+        AnnounceMessage, ClockIdentity, ParsedPacket, PtpClockAccuracy, PtpClockClass, PtpMessage,
+        PtpMessageType, PtpTimestamp, PtpUtcOffset, SDO_ID_GPTP, format_mac, format_ptp_instance,
+        format_timestamp, sdo_id_name, unicast_tlvs,
+    },
     version,
+    what_if::{EDITABLE_ATTRIBUTES, WhatIf},
 };
 
-use std::time::UNIX_EPOCH;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::Level;
 
-// Helper function to flatten tree nodes for display
-fn flatten_tree_nodes(nodes: &[TreeNode]) -> Vec<(&TreeNode, usize, bool)> {
-    let mut flattened = Vec::new();
-    let mut stack = Vec::new();
-    let mut index = 0;
+/// How long a status message stays in the header
+const STATUS_MESSAGE_DURATION: Duration = Duration::from_secs(5);
 
-    // Push initial nodes onto stack with their sibling information
-    for (i, node) in nodes.iter().enumerate().rev() {
-        let is_last_child = i == nodes.len() - 1;
-        stack.push((node, is_last_child));
-    }
-
-    // Process nodes iteratively
-    while let Some((node, is_last_child)) = stack.pop() {
-        flattened.push((node, index, is_last_child));
-        index += 1;
+/// Height of the log pane, borders included
+const LOG_PANE_HEIGHT: u16 = 8;
+/// Height of the profiling pane: borders, column headers, stages and totals
+const PROFILING_PANE_HEIGHT: u16 = 9;
 
-        // Push children in reverse order so they're processed in correct order
-        for (i, child) in node.children.iter().enumerate().rev() {
-            let child_is_last = i == node.children.len() - 1;
-            stack.push((child, child_is_last));
-        }
+/// Green only for a clean host, a few failures are already worth a look
+fn conformance_color(score: u8, theme: &crate::themes::Theme) -> Color {
+    match score {
+        100 => theme.confidence_high,
+        90..100 => theme.confidence_medium,
+        _ => theme.confidence_low,
     }
-
-    flattened
 }
 
 // Helper function to create a table row for a host
@@ -56,9 +81,12 @@ fn create_host_row<'a>(
 ) -> Row<'a> {
     let state_color = theme.get_state_color(&host.state);
 
-    let reference_timestamp = app.get_reference_timestamp();
-    let time_since_last_seen = host.time_since_last_seen(reference_timestamp);
-    let last_seen_str = if time_since_last_seen.as_secs() < 60 {
+    let time_since_last_seen = host.time_since_last_seen(app.host_clock());
+    let last_seen_str = if app.config.time_display == TimeDisplayMode::Absolute {
+        chrono::DateTime::<chrono::Local>::from(host.last_seen)
+            .format("%H:%M:%S")
+            .to_string()
+    } else if time_since_last_seen.as_secs() < 60 {
         format!("{}s", time_since_last_seen.as_secs())
     } else {
         format!("{}m", time_since_last_seen.as_secs() / 60)
@@ -72,14 +100,37 @@ fn create_host_row<'a>(
         Style::default()
     };
 
-    let mut state_display = host.state.short_string().to_string();
+    // Compact rows use a single letter per state
+    let compact = app.config.row_density.is_compact();
+    let state_label = |short: &'static str| match short {
+        "PTT" if compact => "P",
+        "P2P" if compact => "P2",
+        "TT" if compact => "T",
+        "TR" if compact => "R",
+        _ => short,
+    };
+    // Device kind, as opposed to the state of the port heard last
+    let role_display = match host.role_evidence.role() {
+        Some(role) if compact => &role.short_string()[..1],
+        Some(role) => role.short_string(),
+        None => "-",
+    };
+    let identity = |id: &ClockIdentity| {
+        if compact {
+            id.abbreviated()
+        } else {
+            id.to_string()
+        }
+    };
+
+    let mut state_display = state_label(host.role_short_string()).to_string();
     if host.has_local_ip(local_ips) {
         state_display = format!("{}*", state_display);
     }
 
     // Add PTT indicator for primary transmitters (BMCA winners) in tree mode
     if is_primary_transmitter.unwrap_or(false) {
-        state_display = "PTT".to_string();
+        state_display = state_label("PTT").to_string();
     }
 
     let ip_display = if let Some(primary_ip) = host.get_primary_ip() {
@@ -105,6 +156,20 @@ fn create_host_row<'a>(
     };
 
     let selected_transmitter_cell = match &host.state {
+        PtpHostState::TimeReceiver(_) if let Some((followed, _)) = host.wrong_transmitter => {
+            Cell::from(Line::from(vec![
+                Span::styled(
+                    identity(&followed),
+                    Style::default().fg(theme.confidence_low),
+                ),
+                Span::styled(
+                    " ⚠",
+                    Style::default()
+                        .fg(theme.confidence_low)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            ]))
+        }
         PtpHostState::TimeReceiver(s) => {
             s.selected_transmitter_identity
                 .as_ref()
@@ -128,7 +193,7 @@ fn create_host_row<'a>(
                         };
 
                     Cell::from(Line::from(vec![
-                        Span::styled(id.to_string(), Style::default().fg(theme.text_primary)),
+                        Span::styled(identity(id), Style::default().fg(theme.text_primary)),
                         Span::styled(confidence_symbol, Style::default().fg(confidence_color)),
                     ]))
                 })
@@ -137,6 +202,28 @@ fn create_host_row<'a>(
         _ => Cell::from("-"),
     };
 
+    let delay_mechanism_cell = match host.delay_mechanism() {
+        Some(mechanism) if host.delay_mechanism_warning().is_some() => {
+            Cell::from(mechanism.to_string()).style(Style::default().fg(theme.confidence_low))
+        }
+        Some(mechanism) => Cell::from(mechanism.to_string()),
+        None => Cell::from("-"),
+    };
+
+    let conformance_cell = match host.conformance.score() {
+        Some(score) => Cell::from(score.to_string())
+            .style(Style::default().fg(conformance_color(score, theme))),
+        None => Cell::from("-"),
+    };
+
+    // A changed hop distance is a rerouted timing path
+    let hops_cell = match (host.hop_distance.current(), host.hop_distance.last_change) {
+        (Some(_), Some((before, after))) => Cell::from(format!("{}→{}", before, after))
+            .style(Style::default().fg(theme.confidence_low)),
+        (Some(hops), None) => Cell::from(hops.to_string()),
+        (None, _) => Cell::from("-"),
+    };
+
     let interfaces_display = if let Some(primary_interface) = host.get_primary_interface() {
         if host.has_multiple_interfaces() {
             format!(
@@ -151,25 +238,386 @@ fn create_host_row<'a>(
         "-".to_string()
     };
 
-    Row::new(vec![
-        Cell::from(state_display).style(Style::default().fg(state_color)),
-        Cell::from(clock_identity_display),
-        Cell::from(ip_display),
-        Cell::from(interfaces_display),
-        Cell::from(host.get_vendor_name().unwrap_or("-")),
-        Cell::from(
-            host.domain_number
-                .map_or("-".to_string(), |domain| domain.to_string()),
+    Row::new(
+        vec![
+            Cell::from(state_display).style(Style::default().fg(state_color)),
+            Cell::from(role_display),
+            Cell::from(clock_identity_display),
+            Cell::from(ip_display),
+            Cell::from(interfaces_display),
+            hops_cell,
+        ]
+        .into_iter()
+        // The vendor is part of the abbreviated clock identity in compact rows
+        .chain((!compact).then(|| Cell::from(host.get_vendor_name().unwrap_or("-"))))
+        .chain([
+            Cell::from(match host.domain_number {
+                Some(domain) => app.config.domain_label(domain),
+                // Only takes part in the link delay service shared by all domains
+                None if host.cmlds_message_count > 0 => "CMLDS".to_string(),
+                None => "-".to_string(),
+            }),
+            delay_mechanism_cell,
+            Cell::from(priority1_display),
+            Cell::from(clock_class_display),
+            selected_transmitter_cell,
+            Cell::from(
+                match app.message_counts_start() {
+                    Some(since) => host.sent_counts.total(since),
+                    None => host.total_messages_sent_count,
+                }
+                .to_string(),
+            ),
+            Cell::from(last_seen_str),
+            conformance_cell,
+        ])
+        // Only when viewing remote agents
+        .chain(app.remote_agents().map(|_| {
+            let sites = host.get_sites();
+            Cell::from(if sites.is_empty() {
+                "-".to_string()
+            } else {
+                sites.join(",")
+            })
+        }))
+        .chain(
+            app.config
+                .observed_column
+                .then(|| Cell::from(format_observed_duration(host.observed_duration()))),
+        )
+        .chain((!app.switch_ports.is_empty()).then(|| {
+            Cell::from(
+                app.switch_port(host)
+                    .map_or("-".to_string(), |port| port.to_string()),
+            )
+        }))
+        .chain(app.baseline.as_ref().map(|baseline| {
+            let status = baseline.status(host);
+            let color = match status {
+                BaselineStatus::New => theme.confidence_medium,
+                _ => theme.confidence_low,
+            };
+            Cell::from(status.label()).style(Style::default().fg(color))
+        }))
+        .chain(std::iter::once(Cell::from(host.flags())))
+        .chain(
+            app.config
+                .columns
+                .iter()
+                .map(|column| Cell::from(column.value(host).unwrap_or_else(|| "-".to_string()))),
         ),
-        Cell::from(priority1_display),
-        Cell::from(clock_class_display),
-        selected_transmitter_cell,
-        Cell::from(host.total_messages_sent_count.to_string()),
-        Cell::from(last_seen_str),
-    ])
+    )
     .style(style)
 }
 
+/// Glanceable network status for the header, colored by severity
+fn health_badges(app: &App) -> Line<'static> {
+    let theme = &app.theme;
+    let tracker = &app.ptp_tracker;
+    let now = app.clock().now();
+
+    let gm_badge = match tracker.get_main_ptt() {
+        Some((ptt, since)) => {
+            let stable = now.duration_since(since).unwrap_or_default();
+            // A PTT that only just took over may not be the final one
+            let color = if stable < Duration::from_secs(60) {
+                theme.confidence_medium
+            } else {
+                theme.confidence_high
+            };
+            (
+                format!("GM: {} stable {}", ptt, format_badge_duration(stable)),
+                color,
+            )
+        }
+        None => ("GM: none".to_string(), theme.confidence_low),
+    };
+
+    let domains_badge = (
+        format!("Domains: {}", tracker.get_instance_count()),
+        theme.text_primary,
+    );
+
+    let alert_count = tracker
+        .get_alerts()
+        .iter()
+        .filter(|alert| alert.severity >= AlertSeverity::Warning)
+        .count();
+    let critical = tracker
+        .get_alerts()
+        .iter()
+        .any(|alert| alert.severity == AlertSeverity::Critical);
+    let alerts_badge = (
+        format!("Alerts: {}", alert_count),
+        if alert_count == 0 {
+            theme.confidence_high
+        } else if critical {
+            theme.confidence_low
+        } else {
+            theme.confidence_medium
+        },
+    );
+
+    let drops_badge = match tracker.get_sequence_loss_ratio() {
+        Some(ratio) => (
+            format!("Drops: {:.1}%", ratio * 100.0),
+            match ratio {
+                r if r < 0.001 => theme.confidence_high,
+                r if r < 0.01 => theme.confidence_medium,
+                _ => theme.confidence_low,
+            },
+        ),
+        None => ("Drops: -".to_string(), theme.text_secondary),
+    };
+
+    // Only with expected grandmasters in the config
+    let design_badge = tracker.has_expected_grandmasters().then(|| {
+        let mut deviations: Vec<(u16, u8)> = tracker.get_gm_deviations().keys().copied().collect();
+        deviations.sort();
+        if deviations.is_empty() {
+            ("GM: as designed".to_string(), theme.confidence_high)
+        } else {
+            (
+                format!(
+                    "GM deviation: {}",
+                    deviations
+                        .iter()
+                        .map(|(sdo_id, domain)| format_ptp_instance(*sdo_id, *domain))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                theme.confidence_low,
+            )
+        }
+    });
+
+    let mut spans = Vec::new();
+    for (i, (text, color)) in [gm_badge, domains_badge, alerts_badge, drops_badge]
+        .into_iter()
+        .chain(design_badge)
+        .enumerate()
+    {
+        if i > 0 {
+            spans.push(Span::styled(
+                " | ",
+                Style::default().fg(theme.text_secondary),
+            ));
+        }
+        spans.push(Span::styled(
+            text,
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Decoded fields of an organization extension TLV, or its hexdump
+/// Announces shown side by side in host details
+const RECENT_ANNOUNCES: usize = 10;
+
+/// Syncs plotted per hop in the residence time sparkline
+const RESIDENCE_SPARKLINE_WIDTH: usize = 32;
+
+/// Key fields of Announce messages, one column per message. Values that differ
+/// from the previous message are highlighted, so flapping stands out.
+/// Message counts of a host per (IP, interface, VLAN) it arrived on, one row
+/// per path, paths gone silent while others are not flagged
+fn arrival_path_lines<'a>(
+    paths: &ArrivalPaths,
+    clock: Clock,
+    label_width: usize,
+    theme: &'a crate::themes::Theme,
+) -> Vec<Line<'a>> {
+    let names: Vec<String> = paths.iter().map(|(path, _)| path.to_string()).collect();
+    let name_width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(label_width - 2);
+
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            "  {:name_width$} {:>7} {:>7} {:>7} {:>7} {:>7} {:>7}  Last Seen",
+            "Path", "Ann", "Sync", "FU", "Delay", "PDelay", "Other"
+        ),
+        Style::default().fg(theme.text_secondary),
+    ))];
+    for ((path, counts), name) in paths.iter().zip(names) {
+        let silent = paths.is_silent(path);
+        let mut last_seen = format_system_time_ago(counts.last_seen, clock);
+        if silent {
+            last_seen.push_str(" ⚠ silent");
+        }
+        lines.push(Line::from(Span::styled(
+            format!(
+                "  {:name_width$} {:>7} {:>7} {:>7} {:>7} {:>7} {:>7}  {}",
+                name,
+                counts.announce,
+                counts.sync,
+                counts.follow_up,
+                counts.delay,
+                counts.pdelay,
+                counts.other,
+                last_seen
+            ),
+            Style::default().fg(if silent {
+                theme.confidence_low
+            } else {
+                theme.text_primary
+            }),
+        )));
+    }
+    lines
+}
+
+fn announce_table_lines<'a>(
+    announces: &[&AnnounceMessage],
+    label_width: usize,
+    theme: &'a crate::themes::Theme,
+) -> Vec<Line<'a>> {
+    let rows: Vec<(&str, Vec<String>)> = vec![
+        (
+            "Seq",
+            announces
+                .iter()
+                .map(|a| a.header.sequence_id.to_string())
+                .collect(),
+        ),
+        (
+            "GM",
+            announces
+                .iter()
+                .map(|a| {
+                    // The low bytes are enough to tell grandmasters apart
+                    let id = a.ptt_identity.to_string();
+                    id[id.len() - 8..].to_string()
+                })
+                .collect(),
+        ),
+        (
+            "Priority1",
+            announces.iter().map(|a| a.priority1.to_string()).collect(),
+        ),
+        (
+            "Class",
+            announces
+                .iter()
+                .map(|a| a.clock_class.class().to_string())
+                .collect(),
+        ),
+        (
+            "Accuracy",
+            announces
+                .iter()
+                .map(|a| format!("0x{:02x}", a.clock_accuracy.accuracy))
+                .collect(),
+        ),
+        (
+            "Variance",
+            announces
+                .iter()
+                .map(|a| format!("0x{:04x}", a.offset_scaled_log_variance))
+                .collect(),
+        ),
+        (
+            "Priority2",
+            announces.iter().map(|a| a.priority2.to_string()).collect(),
+        ),
+        (
+            "Steps",
+            announces
+                .iter()
+                .map(|a| a.steps_removed.to_string())
+                .collect(),
+        ),
+        (
+            "Time Source",
+            announces
+                .iter()
+                .map(|a| format!("0x{:02x}", a.time_source))
+                .collect(),
+        ),
+        (
+            "UTC Offset",
+            announces
+                .iter()
+                .map(|a| a.current_utc_offset.offset.to_string())
+                .collect(),
+        ),
+        (
+            "Flags",
+            announces.iter().map(|a| a.header.flags.short()).collect(),
+        ),
+    ];
+
+    let column_widths: Vec<usize> = (0..announces.len())
+        .map(|column| {
+            rows.iter()
+                .map(|(_, values)| values[column].len())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    rows.into_iter()
+        .map(|(label, values)| {
+            let mut spans = vec![Span::styled(
+                format!("  {:width$}", label, width = label_width - 2),
+                Style::default().fg(theme.text_secondary),
+            )];
+            for (column, value) in values.iter().enumerate() {
+                // The sequence id always changes, that is not worth pointing out
+                let changed = label != "Seq" && column > 0 && values[column - 1] != *value;
+                let style = if changed {
+                    Style::default()
+                        .fg(theme.confidence_low)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(theme.text_primary)
+                };
+                spans.push(Span::styled(
+                    format!("{:>width$} ", value, width = column_widths[column]),
+                    style,
+                ));
+            }
+            Line::from(spans)
+        })
+        .collect()
+}
+
+fn org_tlv_lines<'a>(
+    tlv: &org_tlv::OrgTlv,
+    label_width: usize,
+    theme: &'a crate::themes::Theme,
+) -> Vec<Line<'a>> {
+    match &tlv.fields {
+        Some(fields) => fields
+            .iter()
+            .map(|(label, value)| {
+                create_aligned_field(format!("  {}: ", label), value.clone(), label_width, theme)
+            })
+            .collect(),
+        None => tlv
+            .data
+            .chunks(16)
+            .enumerate()
+            .map(|(i, chunk)| {
+                create_aligned_field(
+                    format!("  {:04x}: ", i * 16),
+                    chunk
+                        .iter()
+                        .map(|byte| format!("{:02x}", byte))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    label_width,
+                    theme,
+                )
+            })
+            .collect(),
+    }
+}
+
 // Helper function to create aligned label-value pairs
 fn create_aligned_field(
     label: String,
@@ -186,6 +634,114 @@ fn create_aligned_field(
     ])
 }
 
+// Append a trend arrow to a field, red when the quality is getting worse
+fn with_trend<'a>(mut line: Line<'a>, trend: Trend, theme: &crate::themes::Theme) -> Line<'a> {
+    let color = match trend {
+        Trend::Improving => theme.confidence_high,
+        Trend::Steady => theme.text_primary,
+        Trend::Degrading => theme.confidence_low,
+    };
+    if trend != Trend::Steady {
+        line.spans.push(Span::styled(
+            format!(" {}", trend.arrow()),
+            Style::default().fg(color),
+        ));
+    }
+    line
+}
+
+// Values as a row of block characters scaled to the largest one
+fn sparkline(values: &[f64]) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().fold(0.0, f64::max);
+    values
+        .iter()
+        .map(|&value| {
+            if max <= 0.0 {
+                BLOCKS[0]
+            } else {
+                let level = (value.max(0.0) / max * (BLOCKS.len() - 1) as f64).round() as usize;
+                BLOCKS[level.min(BLOCKS.len() - 1)]
+            }
+        })
+        .collect()
+}
+
+// Residence time of one hop with a sparkline of the recent Syncs, red when it spikes
+fn residence_line<'a>(
+    hop: &HopResidence,
+    label_width: usize,
+    theme: &'a crate::themes::Theme,
+) -> Line<'a> {
+    let recent = &hop.samples[hop.samples.len().saturating_sub(RESIDENCE_SPARKLINE_WIDTH)..];
+    let spiking = hop.is_spiking();
+    let mut line = create_aligned_field(
+        "Residence: ".to_string(),
+        format!(
+            "{}→{} avg {:.2} µs max {:.2} µs ",
+            hop.from.as_deref().unwrap_or("GM"),
+            hop.to,
+            hop.mean_ns() / 1000.0,
+            hop.max_ns() / 1000.0
+        ),
+        label_width,
+        theme,
+    );
+    line.spans.push(Span::styled(
+        sparkline(recent),
+        Style::default().fg(if spiking {
+            theme.confidence_low
+        } else {
+            theme.text_accent
+        }),
+    ));
+    if spiking {
+        line.spans.push(Span::styled(
+            " ⚠ spiking",
+            Style::default()
+                .fg(theme.confidence_low)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    line
+}
+
+// Mark a field with a suspicious value, '?' explains it
+fn with_warning<'a>(
+    mut line: Line<'a>,
+    field: &str,
+    warnings: &[FieldWarning],
+    theme: &crate::themes::Theme,
+) -> Line<'a> {
+    if warnings.iter().any(|warning| warning.field == field) {
+        line.spans.push(Span::styled(
+            " ⚠",
+            Style::default()
+                .fg(theme.confidence_low)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    line
+}
+
+// Origin timestamp as wall clock time in the configured timezone, if any
+fn create_timezone_field(
+    timestamp: Option<PtpTimestamp>,
+    utc_offset: Option<PtpUtcOffset>,
+    timezone: Option<chrono_tz::Tz>,
+    label_width: usize,
+    theme: &crate::themes::Theme,
+) -> Option<Line<'_>> {
+    let timezone = timezone?;
+    let timestamp = timestamp.filter(|ts| ts.seconds != 0 || ts.nanoseconds != 0)?;
+    Some(create_aligned_field(
+        format!("→ {}:", timezone.name()),
+        timestamp.format_in_timezone(utc_offset, timezone),
+        label_width,
+        theme,
+    ))
+}
+
 fn create_aligned_field_with_vendor(
     label: String,
     value: String,
@@ -204,951 +760,4469 @@ fn create_aligned_field_with_vendor(
     ])
 }
 
+/// Below this size the panes don't fit side by side and only the active one is shown
+const FULL_LAYOUT_MIN_WIDTH: u16 = 100;
+const FULL_LAYOUT_MIN_HEIGHT: u16 = 30;
+/// Below this size not even a single pane fits
+const MIN_WIDTH: u16 = 30;
+const MIN_HEIGHT: u16 = 8;
+
 pub fn ui(f: &mut Frame, app: &mut App) {
     // Store terminal area for mouse support
-    app.terminal_area = Some(f.area());
-    let chunks = if app.is_packet_history_expanded() {
-        // Expanded view: split roughly 50/50 between hosts and packets
-        Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(3),      // Header
-                Constraint::Percentage(50), // Main content (hosts + details)
-                Constraint::Percentage(50), // Expanded packet history
-            ])
-            .split(f.area())
-    } else {
-        // Normal view: smaller packet history area
-        Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(3),  // Header
-                Constraint::Min(15),    // Main content (hosts + details)
-                Constraint::Length(10), // Compact packet history (fixed height)
-            ])
-            .split(f.area())
-    };
-
-    // Store packet history area for mouse support
-    app.packet_history_area = Some(chunks[2]);
-
-    // Render header
-    render_header(f, chunks[0], app);
+    let area = f.area();
+    app.terminal_area = Some(area);
+    app.packet_minimap_area = None;
+
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        app.host_table_area = None;
+        app.host_details_area = None;
+        app.packet_history_area = None;
+        render_too_small(f, area, app);
+        return;
+    }
 
-    // Render main content
-    if app.show_help {
-        render_help(f, chunks[1], app);
+    if area.width < FULL_LAYOUT_MIN_WIDTH || area.height < FULL_LAYOUT_MIN_HEIGHT {
+        render_compact_layout(f, area, app);
     } else {
-        render_main_content(f, chunks[1], app);
-        render_packet_history(f, chunks[2], app);
+        render_full_layout(f, area, app);
     }
 
-    // Render packet modal overlay if active
-    if app.show_packet_modal {
-        render_packet_modal(f, f.area(), app);
+    if app.show_field_warnings {
+        render_field_warnings(f, area, app);
     }
-}
 
-fn render_header(f: &mut Frame, area: Rect, app: &App) {
-    let theme = &app.theme;
+    if app.show_bookmarks {
+        render_bookmarks(f, area, app);
+    }
 
-    // Create header content with version and build info
-    let mut header_spans = vec![
-        Span::styled(
-            "PTP Network Tracer",
-            Style::default()
-                .fg(theme.header_fg)
-                .add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(
-            format!(" {}", version::get_version()),
-            Style::default()
-                .fg(theme.text_accent)
-                .add_modifier(Modifier::BOLD),
-        ),
-    ];
+    if app.show_vendors {
+        render_vendor_breakdown(f, area, app);
+    }
 
-    // Add PAUSED indicator if paused
-    if app.paused {
-        header_spans.push(Span::styled(
-            " [PAUSED]",
-            Style::default()
-                .fg(theme.text_accent)
-                .add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK),
-        ));
+    if app.show_top_talkers {
+        render_top_talkers(f, area, app);
     }
 
-    let header_content = vec![
-        Line::from(header_spans),
-        Line::from(vec![Span::styled(
-            format!(
-                "Built: {} | Git: {}",
-                version::get_build_time(),
-                version::get_git_hash()
-            ),
-            Style::default()
-                .fg(theme.text_secondary)
-                .add_modifier(Modifier::ITALIC),
-        )]),
-    ];
+    // Render packet modal overlay if active
+    // Help opened from the packet modal is shown in its place
+    if app.show_packet_modal && !app.show_help {
+        render_packet_modal(f, area, app);
+    }
 
-    let header = Paragraph::new(header_content)
-        .style(Style::default().bg(theme.header_bg))
-        .alignment(Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(theme.border_normal)),
+    if let Some((_, note)) = &app.bookmark_note {
+        render_text_input(
+            f,
+            area,
+            "Bookmark note - Enter save, Esc no note",
+            note,
+            &app.theme,
         );
+    }
 
-    f.render_widget(header, area);
-}
+    if let Some((clock_identity, tags)) = &app.tag_input {
+        let title = format!(
+            "Tags of {} - comma separated, Enter save, Esc cancel",
+            clock_identity
+        );
+        render_text_input(f, area, &title, tags, &app.theme);
+    }
 
-/// Resolve clock class to human-readable description
-pub fn format_clock_class(cc: Option<PtpClockClass>) -> String {
-    match cc {
-        None => "N/A".to_string(),
-        Some(class) => class.to_string(),
+    if let Some(export) = &app.pending_export {
+        render_export_confirmation(f, area, export, &app.theme);
     }
 }
 
-/// Resolve clock accuracy
-pub fn format_clock_accuracy(ca: Option<PtpClockAccuracy>) -> String {
-    match ca {
-        None => "N/A".to_string(),
-        Some(accuracy) => accuracy.to_string(),
+/// Centered popup of the given size, shrunk to what the terminal has
+fn popup_area(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
     }
 }
 
-fn render_main_content(f: &mut Frame, area: Rect, app: &mut App) {
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
-        .split(area);
-
-    // Store areas for mouse support
-    app.host_table_area = Some(chunks[0]);
-
-    // Left panel: PTP hosts list
-    render_hosts_table(f, chunks[0], app);
+/// Jump list of the bookmarked packets
+fn render_bookmarks(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let bookmarks = app.bookmarks.items();
+    let popup_area = popup_area(area, 100, bookmarks.len().max(1) as u16 + 3);
 
-    // Right panel: Statistics and details
-    render_stats_panel(f, chunks[1], app);
-}
-
-fn render_hosts_table(f: &mut Frame, area: Rect, app: &mut App) {
-    // Calculate visible rows (subtract 4 for top border, header row, header bottom margin, and bottom border)
-    let visible_height = area.height.saturating_sub(4) as usize;
+    let rows: Vec<Row> = bookmarks
+        .iter()
+        .enumerate()
+        .map(|(i, bookmark)| {
+            let header = bookmark.packet.ptp.header();
+            let style = if i == app.selected_bookmark {
+                Style::default()
+                    .bg(theme.selected_row_background)
+                    .fg(theme.text_primary)
+            } else {
+                Style::default().fg(theme.text_primary)
+            };
+            Row::new(vec![
+                Cell::from(
+                    chrono::DateTime::<chrono::Local>::from(bookmark.packet.raw.timestamp)
+                        .format("%H:%M:%S%.3f")
+                        .to_string(),
+                ),
+                Cell::from(bookmark.clock_identity.to_string()),
+                Cell::from(Span::styled(
+                    header.message_type.to_string(),
+                    theme.get_message_type_color(&header.message_type),
+                )),
+                Cell::from(header.sequence_id.to_string()),
+                Cell::from(if bookmark.note.is_empty() {
+                    "-".to_string()
+                } else {
+                    bookmark.note.clone()
+                }),
+            ])
+            .style(style)
+        })
+        .collect();
 
-    // Store visible height in app for key handling
-    app.set_visible_height(visible_height);
+    let title = format!(
+        "Bookmarks ({}) - Enter jump, 'e' note, 'd' delete, 'P' export pcapng, 'q' close",
+        bookmarks.len()
+    );
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_focused))
+        .style(Style::default().bg(theme.background));
 
-    // Only ensure the selected item is visible if selection changed
-    if app.host_selection_changed {
-        app.ensure_host_visible(visible_height);
-        app.host_selection_changed = false;
+    f.render_widget(Clear, popup_area);
+    if bookmarks.is_empty() {
+        f.render_widget(
+            Paragraph::new("No bookmarks yet, 'b' bookmarks the selected packet")
+                .style(Style::default().fg(theme.text_primary))
+                .block(block),
+            popup_area,
+        );
+        return;
     }
 
-    let theme = &app.theme;
-    let _is_focused = true; // Hosts are always focused now
-    let selected_index = app.get_selected_index();
-    let updated_scroll_offset = app.get_host_scroll_offset();
-
-    // Get local IPs for comparison
-    let local_ips = app.ptp_tracker.get_local_ips();
-
-    let sort_column = app.get_sort_column();
-    let headers = [
-        (SortColumn::State, "State"),
-        (SortColumn::ClockIdentity, "Clock Identity"),
-        (SortColumn::IpAddress, "IP Address"),
-        (SortColumn::Interface, "Interfaces"),
-        (SortColumn::Vendor, "Vendor"),
-        (SortColumn::Domain, "Dom"),
-        (SortColumn::Priority, "Pri"),
-        (SortColumn::ClockClass, "CC"),
-        (SortColumn::SelectedTransmitter, "Selected Transmitter"),
-        (SortColumn::MessageCount, "Msgs"),
-        (SortColumn::LastSeen, "Last Seen"),
-    ];
-
-    let header_cells = headers.iter().map(|(col_type, display_name)| {
-        let style = if col_type == sort_column {
-            Style::default()
-                .fg(theme.sort_column_active)
-                .add_modifier(Modifier::BOLD)
-        } else {
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(12), // Time
+            Constraint::Length(23), // Host
+            Constraint::Length(13), // Message Type
+            Constraint::Length(5),  // Sequence
+            Constraint::Min(10),    // Note
+        ],
+    )
+    .header(
+        Row::new(vec!["Time", "Host", "Message Type", "Seq", "Note"]).style(
             Style::default()
                 .fg(theme.table_header)
-                .add_modifier(Modifier::BOLD)
-        };
-        Cell::from(*display_name).style(style)
-    });
+                .add_modifier(Modifier::BOLD),
+        ),
+    )
+    .block(block);
+    f.render_widget(table, popup_area);
+}
 
-    let header = Row::new(header_cells).height(1);
+/// Area of the vendor breakdown popup, also used for mouse clicks
+pub fn vendor_breakdown_area(area: Rect, vendor_count: usize) -> Rect {
+    popup_area(area, 80, vendor_count.max(1) as u16 + 3)
+}
 
-    // Get hosts data based on tree view mode
-    let (total_count, rows) = if app.tree_view_mode {
-        // Tree view mode
-        let tree_nodes = app.get_hosts_tree();
-        let flattened_nodes = flatten_tree_nodes(&tree_nodes);
-        let total_count = flattened_nodes.len();
+fn render_vendor_breakdown(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let breakdown = app.get_vendor_breakdown();
+    let popup_area = vendor_breakdown_area(area, breakdown.len());
 
-        // Apply scrolling - only show visible rows
-        let visible_nodes: Vec<_> = flattened_nodes
-            .iter()
-            .skip(updated_scroll_offset)
-            .take(visible_height)
-            .collect();
+    let rows: Vec<Row> = breakdown
+        .iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let filtered = app.vendor_filter.as_deref() == Some(count.vendor.as_str());
+            let mut style = if i == app.selected_vendor {
+                Style::default()
+                    .bg(theme.selected_row_background)
+                    .fg(theme.text_primary)
+            } else {
+                Style::default().fg(theme.text_primary)
+            };
+            if filtered {
+                style = style.add_modifier(Modifier::BOLD);
+            }
+            let number = |n: usize| {
+                if n == 0 {
+                    "-".to_string()
+                } else {
+                    n.to_string()
+                }
+            };
+            Row::new(vec![
+                Cell::from(format!(
+                    "{}{}",
+                    if filtered { "▶ " } else { "" },
+                    count.vendor
+                )),
+                Cell::from(count.hosts().to_string()),
+                Cell::from(number(count.transmitters)),
+                Cell::from(number(count.receivers)),
+                Cell::from(number(count.listening)),
+            ])
+            .style(style)
+        })
+        .collect();
 
-        let rows: Vec<Row> = visible_nodes
-            .iter()
-            .enumerate()
-            .map(|(visible_i, (node, _flat_index, is_last_child))| {
-                let actual_i = visible_i + updated_scroll_offset;
-                let host = &node.host;
+    let title = format!(
+        "Vendors ({}) - sorted by {}, 's' sort, Enter/click filter hosts, 'c' clear filter, 'q' close",
+        breakdown.len(),
+        app.vendor_sort.label()
+    );
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_focused))
+        .style(Style::default().bg(theme.background));
 
-                // Create indentation for tree structure
-                let indent = "  ".repeat(node.depth);
-                let tree_prefix = if node.depth > 0 {
-                    if *is_last_child { "└─ " } else { "├─ " }
-                } else {
-                    ""
-                };
+    f.render_widget(Clear, popup_area);
+    if breakdown.is_empty() {
+        f.render_widget(
+            Paragraph::new("No hosts seen yet")
+                .style(Style::default().fg(theme.text_primary))
+                .block(block),
+            popup_area,
+        );
+        return;
+    }
 
-                let clock_identity_display =
-                    format!("{}{}{}", indent, tree_prefix, host.clock_identity);
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(20),    // Vendor
+            Constraint::Length(6),  // Hosts
+            Constraint::Length(12), // Transmitters
+            Constraint::Length(10), // Receivers
+            Constraint::Length(10), // Listening
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Vendor",
+            "Hosts",
+            "Transmitters",
+            "Receivers",
+            "Listening",
+        ])
+        .style(
+            Style::default()
+                .fg(theme.table_header)
+                .add_modifier(Modifier::BOLD),
+        ),
+    )
+    .block(block);
+    f.render_widget(table, popup_area);
+}
 
-                create_host_row(
-                    host,
-                    clock_identity_display,
-                    actual_i,
-                    selected_index,
-                    theme,
-                    &local_ips,
-                    Some(node.is_primary_transmitter),
-                    app,
-                )
-            })
-            .collect();
+/// Hosts ranked by message rate over the last minute, with their share of
+/// each kind of message
+fn render_top_talkers(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let ranking = app.get_top_talkers();
+    let popup_area = popup_area(area, 110, ranking.talkers.len().max(1) as u16 + 3);
 
-        (total_count, rows)
-    } else {
-        // Table view mode (original)
-        let hosts = app.get_hosts();
-        let total_count = hosts.len();
+    let rows: Vec<Row> = ranking
+        .talkers
+        .iter()
+        .enumerate()
+        .map(|(i, talker)| {
+            let style = if i == app.selected_talker {
+                Style::default()
+                    .bg(theme.selected_row_background)
+                    .fg(theme.text_primary)
+            } else {
+                Style::default().fg(theme.text_primary)
+            };
+            let mut cells = vec![
+                Cell::from(talker.clock_identity.to_string()),
+                Cell::from(
+                    app.ptp_tracker
+                        .get_host(&talker.clock_identity)
+                        .and_then(|host| host.get_vendor_name())
+                        .unwrap_or("-"),
+                ),
+                Cell::from(match talker.domain {
+                    Some(domain) => app.config.domain_label(domain),
+                    None => "-".to_string(),
+                }),
+            ];
+            cells.extend(TalkerSort::ALL.iter().map(|sort| {
+                let cell = match talker.counts.get(*sort) {
+                    0 => "-".to_string(),
+                    _ => format!(
+                        "{:>7.2}/s {:>5.1}%",
+                        ranking.rate(talker, *sort),
+                        ranking.share(talker, *sort)
+                    ),
+                };
+                if *sort == app.talker_sort {
+                    Cell::from(Span::styled(
+                        cell,
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ))
+                } else {
+                    Cell::from(cell)
+                }
+            }));
+            Row::new(cells).style(style)
+        })
+        .collect();
 
-        // Apply scrolling - only show visible rows
-        let visible_hosts: Vec<_> = hosts
-            .iter()
-            .skip(updated_scroll_offset)
-            .take(visible_height)
-            .collect();
+    let scope = match app.talker_domain {
+        Some(domain) => format!("domain {}", app.config.domain_label(domain)),
+        None => "all domains".to_string(),
+    };
+    let title = format!(
+        "Top talkers in {} over the last {:.0} s ({:.1} Announce/s) - sorted by {}, 's' sort, 'd' domain, Enter select host, 'q' close",
+        scope,
+        ranking.seconds,
+        ranking.totals.announce as f64 / ranking.seconds,
+        app.talker_sort.label()
+    );
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_focused))
+        .style(Style::default().bg(theme.background));
 
-        let rows: Vec<Row> = visible_hosts
-            .iter()
-            .enumerate()
-            .map(|(visible_i, host)| {
-                let actual_i = visible_i + updated_scroll_offset;
-                let clock_identity_display = host.clock_identity.to_string();
+    f.render_widget(Clear, popup_area);
+    if ranking.talkers.is_empty() {
+        let message = if app.ptp_tracker.is_lite() {
+            "--lite keeps no message counts to rank hosts by"
+        } else {
+            "No messages over the last minute"
+        };
+        f.render_widget(
+            Paragraph::new(message)
+                .style(Style::default().fg(theme.text_primary))
+                .block(block),
+            popup_area,
+        );
+        return;
+    }
 
-                create_host_row(
-                    host,
-                    clock_identity_display,
-                    actual_i,
-                    selected_index,
-                    theme,
-                    &local_ips,
-                    None,
-                    app,
-                )
-            })
-            .collect();
+    let mut header = vec!["Host", "Vendor", "Domain"];
+    header.extend(TalkerSort::ALL.iter().map(|sort| match sort {
+        TalkerSort::Announce => "Announce",
+        TalkerSort::Sync => "Sync",
+        TalkerSort::Delay => "Delay Req/Resp",
+        TalkerSort::Total => "All Messages",
+    }));
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(23), // Host
+            Constraint::Min(10),    // Vendor
+            Constraint::Length(6),  // Domain
+            Constraint::Length(16), // Announce
+            Constraint::Length(16), // Sync
+            Constraint::Length(16), // Delay
+            Constraint::Length(16), // All messages
+        ],
+    )
+    .header(
+        Row::new(header).style(
+            Style::default()
+                .fg(theme.table_header)
+                .add_modifier(Modifier::BOLD),
+        ),
+    )
+    .block(block);
+    f.render_widget(table, popup_area);
+}
 
-        (total_count, rows)
-    };
+/// Single line text entry, e.g. for a bookmark note
+fn render_text_input(
+    f: &mut Frame,
+    area: Rect,
+    title: &str,
+    text: &str,
+    theme: &crate::themes::Theme,
+) {
+    let popup_area = popup_area(area, 60, 3);
+    let input = Paragraph::new(Line::from(vec![
+        Span::styled(text.to_string(), Style::default().fg(theme.text_primary)),
+        Span::styled("_", Style::default().fg(theme.text_accent)),
+    ]))
+    .block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border_focused))
+            .style(Style::default().bg(theme.background)),
+    );
+    f.render_widget(Clear, popup_area);
+    f.render_widget(input, popup_area);
+}
 
-    let widths = [
-        Constraint::Length(5),  // State
-        Constraint::Min(23),    // Clock Identity
-        Constraint::Length(24), // IP Address
-        Constraint::Length(20), // Interfaces
-        Constraint::Length(20), // Vendor
-        Constraint::Length(3),  // Domain
-        Constraint::Length(3),  // Priority
-        Constraint::Length(3),  // Clock Class
-        Constraint::Length(25), // Selected Transmitter
-        Constraint::Length(5),  // Message Count
-        Constraint::Length(10), // Last Seen
+fn render_export_confirmation(
+    f: &mut Frame,
+    area: Rect,
+    export: &PendingExport,
+    theme: &crate::themes::Theme,
+) {
+    let popup_area = popup_area(area, 64, 7);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Export ", Style::default().fg(theme.text_primary)),
+            Span::styled(
+                format!("{} packets", export.packets.len()),
+                Style::default()
+                    .fg(theme.text_accent)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!(" of {}", export.clock_identity),
+                Style::default().fg(theme.text_primary),
+            ),
+        ]),
+        Line::from(Span::styled(
+            match &export.interface {
+                Some(interface) => format!("Only interface {}, as filtered with 'n'", interface),
+                None => "All interfaces".to_string(),
+            },
+            Style::default().fg(theme.text_secondary),
+        )),
     ];
+    if export.bookmarked > 0 {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "Including {} bookmarked packets no longer in the history",
+                export.bookmarked
+            ),
+            Style::default().fg(theme.text_secondary),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "p/Enter pcap   c CSV   j JSON   Esc cancel",
+        Style::default().fg(theme.text_accent),
+    )));
+
+    let dialog = Paragraph::new(lines).block(
+        Block::default()
+            .title("Export packet history")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border_focused))
+            .style(Style::default().bg(theme.background)),
+    );
+    f.render_widget(Clear, popup_area);
+    f.render_widget(dialog, popup_area);
+}
 
-    let sort_direction = if app.is_sort_ascending() {
-        "↑"
-    } else {
-        "↓"
-    };
+/// Explanations of the suspicious values marked in host details
+fn render_field_warnings(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let warnings = app
+        .selected_host_id
+        .and_then(|id| app.host(&id))
+        .map(|host| match &host.state {
+            PtpHostState::TimeTransmitter(s) => transmitter_warnings(s),
+            _ => Vec::new(),
+        })
+        .unwrap_or_default();
 
-    let view_indicator = match app.active_view {
-        ActiveView::HostTable => " [ACTIVE - TAB to switch]",
-        ActiveView::HostDetails => " [TAB to switch]",
-        ActiveView::PacketHistory => " [TAB to switch]",
+    let lines: Vec<Line> = if warnings.is_empty() {
+        vec![Line::from(Span::styled(
+            "No suspicious values for the selected host",
+            Style::default().fg(theme.text_primary),
+        ))]
+    } else {
+        warnings
+            .iter()
+            .map(|warning| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("⚠ {}: ", warning.field),
+                        Style::default()
+                            .fg(theme.confidence_low)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        warning.explanation.clone(),
+                        Style::default().fg(theme.text_primary),
+                    ),
+                ])
+            })
+            .collect()
     };
 
-    let title = if app.tree_view_mode {
-        format!(
-            "PTP Hosts - Tree View - Sort: {}{} (s to cycle, S to reverse){}",
-            sort_column.display_name(),
-            sort_direction,
-            view_indicator
-        )
-    } else {
-        format!(
-            "PTP Hosts - Sort: {}{} (s to cycle, S to reverse){}",
-            sort_column.display_name(),
-            sort_direction,
-            view_indicator
-        )
+    let width = 90.min(area.width);
+    // Explanations wrap to about two lines each
+    let height = (lines.len() as u16 * 2 + 2).min(area.height);
+    let popup_area = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
     };
 
-    let table = Table::new(rows, widths)
-        .header(header)
+    let popup = Paragraph::new(lines)
         .block(
             Block::default()
+                .title("Suspicious Values - '?' or 'q' to close")
                 .borders(Borders::ALL)
-                .title(title.as_str())
-                .border_type(BorderType::Rounded)
-                .border_style(match app.active_view {
-                    ActiveView::HostTable => Style::default().fg(theme.border_focused),
-                    ActiveView::HostDetails => Style::default().fg(theme.border_normal),
-                    ActiveView::PacketHistory => Style::default().fg(theme.border_normal),
-                }),
+                .border_style(Style::default().fg(theme.border_focused))
+                .style(Style::default().bg(theme.background)),
         )
-        .style(Style::default().bg(theme.background))
-        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
-        .highlight_symbol(">> ");
-
-    f.render_widget(table, area);
+        .wrap(Wrap { trim: true });
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup, popup_area);
+}
 
-    // Render scrollbar if needed
-    if total_count > visible_height {
-        render_scrollbar(
-            f,
-            area,
-            total_count,
-            updated_scroll_offset,
-            visible_height,
-            theme,
-        );
-    }
+fn render_too_small(f: &mut Frame, area: Rect, app: &App) {
+    let message = Paragraph::new(format!(
+        "Terminal too small ({}x{}), need at least {}x{}",
+        area.width, area.height, MIN_WIDTH, MIN_HEIGHT
+    ))
+    .style(
+        Style::default()
+            .fg(app.theme.text_primary)
+            .bg(app.theme.background),
+    )
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true });
+    f.render_widget(message, area);
 }
 
-fn render_stats_panel(f: &mut Frame, area: Rect, app: &mut App) {
+/// One pane at a time, the active view picked with Tab
+fn render_compact_layout(f: &mut Frame, area: Rect, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(8), // Summary stats
-            Constraint::Min(5),    // Details panel (host or packet)
+            Constraint::Length(3), // Header
+            Constraint::Length(1), // Pane tabs
+            Constraint::Min(0),    // Active pane
         ])
         .split(area);
+    let pane = chunks[2];
 
-    // Store area for mouse support
-    app.host_details_area = Some(chunks[1]);
+    render_header(f, chunks[0], app);
 
-    // Summary statistics
-    render_summary_stats(f, chunks[0], app);
+    let theme = &app.theme;
+    let mut tabs = Vec::new();
+    for (view, name) in [
+        (ActiveView::HostTable, " Hosts "),
+        (ActiveView::HostDetails, " Details "),
+        (ActiveView::PacketHistory, " Packets "),
+    ] {
+        let style = if app.active_view == view {
+            Style::default()
+                .fg(theme.sort_column_active)
+                .bg(theme.selected_row_background)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.text_secondary)
+        };
+        tabs.push(Span::styled(name, style));
+    }
+    tabs.push(Span::styled(
+        format!(
+            " Tab to switch, all panes from {}x{}",
+            FULL_LAYOUT_MIN_WIDTH, FULL_LAYOUT_MIN_HEIGHT
+        ),
+        Style::default().fg(theme.text_secondary),
+    ));
+    f.render_widget(
+        Paragraph::new(Line::from(tabs)).style(Style::default().bg(theme.background)),
+        chunks[1],
+    );
 
-    // Show host details (merged with network info)
-    render_host_details(f, chunks[1], app);
+    app.host_table_area = None;
+    app.host_details_area = None;
+    app.packet_history_area = None;
+
+    if app.show_help {
+        render_help(f, pane, app);
+        return;
+    }
+    if app.show_settings {
+        render_settings(f, pane, app);
+        return;
+    }
+    if let Some(what_if) = &app.what_if {
+        render_what_if(f, pane, app, what_if);
+        return;
+    }
+    if let Some(swimlane) = app.swimlane {
+        render_swimlane(f, pane, app, swimlane);
+        return;
+    }
+
+    match app.active_view {
+        ActiveView::HostTable
+            if app.show_compliance
+                && let Some(golden) = &app.golden =>
+        {
+            render_compliance(f, pane, app, golden)
+        }
+        ActiveView::HostTable
+            if app.show_baseline
+                && let Some(baseline) = &app.baseline =>
+        {
+            render_baseline(f, pane, app, baseline)
+        }
+        ActiveView::HostTable if app.show_gm_matrix => render_gm_matrix(f, pane, app),
+        ActiveView::HostTable if app.show_port_matrix => render_port_matrix(f, pane, app),
+        ActiveView::HostTable if app.show_interfaces => render_interfaces(f, pane, app),
+        ActiveView::HostTable if app.show_timeline => render_timeline(f, pane, app),
+        ActiveView::HostTable => {
+            app.host_table_area = Some(pane);
+            render_hosts_table(f, pane, app);
+        }
+        ActiveView::HostDetails => {
+            app.host_details_area = Some(pane);
+            render_host_details(f, pane, app);
+        }
+        ActiveView::PacketHistory => {
+            app.packet_history_area = Some(pane);
+            render_packet_history(f, pane, app);
+        }
+    }
 }
 
-fn render_summary_stats(f: &mut Frame, area: Rect, app: &mut App) {
-    let theme = &app.theme;
-    let hosts = app.get_hosts();
-    let total_hosts = hosts.len();
-    let transmitter_count = app.ptp_tracker.get_transmitter_count();
-    let receiver_count = app.ptp_tracker.get_receiver_count();
+fn render_full_layout(f: &mut Frame, area: Rect, app: &mut App) {
+    let chunks = if app.is_packet_history_expanded() {
+        // Expanded view: split roughly 50/50 between hosts and packets
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),      // Header
+                Constraint::Percentage(50), // Main content (hosts + details)
+                Constraint::Percentage(50), // Expanded packet history
+            ])
+            .split(area)
+    } else {
+        // Normal view: smaller packet history area
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3), // Header
+                Constraint::Min(15),   // Main content (hosts + details)
+                // Compact packet history (fixed height), fewer lines with compact rows
+                Constraint::Length(if app.config.row_density.is_compact() {
+                    6
+                } else {
+                    10
+                }),
+            ])
+            .split(area)
+    };
 
-    // Define the width for label alignment in statistics
-    const STATS_LABEL_WIDTH: usize = 15; // Width for "Total Hosts: "
+    // Store packet history area for mouse support
+    app.packet_history_area = Some(chunks[2]);
 
-    let stats_text = vec![
-        create_aligned_field(
-            "Total Hosts: ".to_string(),
-            total_hosts.to_string(),
-            STATS_LABEL_WIDTH,
-            theme,
-        ),
-        create_aligned_field_with_vendor(
-            "Transmitters: ".to_string(),
-            transmitter_count.to_string(),
-            String::new(),
-            STATS_LABEL_WIDTH,
-            theme,
-            theme.state_transmitter,
-        ),
-        create_aligned_field_with_vendor(
-            "Receivers: ".to_string(),
-            receiver_count.to_string(),
-            String::new(),
-            STATS_LABEL_WIDTH,
-            theme,
-            theme.state_receiver,
+    // Render header
+    render_header(f, chunks[0], app);
+
+    // A running failover drill takes the bottom of the main content area
+    let main_area = match app.ptp_tracker.get_failover_drill() {
+        Some(drill) => {
+            // Borders, three status lines and one line per receiver
+            let drill_height = (drill.receivers.len() as u16 + 5).min(12);
+            let areas = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(8), Constraint::Length(drill_height)])
+                .split(chunks[1]);
+            render_failover_drill(f, areas[1], app);
+            areas[0]
+        }
+        None => chunks[1],
+    };
+
+    // The log pane takes the bottom of what is left
+    let main_area = if app.show_log {
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(8), Constraint::Length(LOG_PANE_HEIGHT)])
+            .split(main_area);
+        render_log_pane(f, areas[1], app);
+        areas[0]
+    } else {
+        main_area
+    };
+
+    let main_area = if app.show_profiling {
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(8),
+                Constraint::Length(PROFILING_PANE_HEIGHT),
+            ])
+            .split(main_area);
+        render_profiling_pane(f, areas[1], app);
+        areas[0]
+    } else {
+        main_area
+    };
+
+    // Render main content
+    if app.show_help {
+        render_help(f, chunks[1], app);
+    } else if app.show_settings {
+        render_settings(f, chunks[1], app);
+    } else if let Some(what_if) = &app.what_if {
+        render_what_if(f, main_area, app, what_if);
+        render_packet_history(f, chunks[2], app);
+    } else if let Some(swimlane) = app.swimlane {
+        render_swimlane(f, main_area, app, swimlane);
+        render_packet_history(f, chunks[2], app);
+    } else if app.show_compliance
+        && let Some(golden) = &app.golden
+    {
+        render_compliance(f, main_area, app, golden);
+        render_packet_history(f, chunks[2], app);
+    } else if app.show_baseline
+        && let Some(baseline) = &app.baseline
+    {
+        render_baseline(f, main_area, app, baseline);
+        render_packet_history(f, chunks[2], app);
+    } else if app.show_gm_matrix {
+        render_gm_matrix(f, main_area, app);
+        render_packet_history(f, chunks[2], app);
+    } else if app.show_port_matrix {
+        render_port_matrix(f, main_area, app);
+        render_packet_history(f, chunks[2], app);
+    } else if app.show_interfaces {
+        render_interfaces(f, main_area, app);
+        render_packet_history(f, chunks[2], app);
+    } else if app.show_timeline {
+        render_timeline(f, main_area, app);
+        render_packet_history(f, chunks[2], app);
+    } else {
+        render_main_content(f, main_area, app);
+        render_packet_history(f, chunks[2], app);
+    }
+}
+
+fn render_header(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+
+    // Create header content with version and build info
+    let mut header_spans = vec![
+        Span::styled(
+            "PTP Network Tracer",
+            Style::default()
+                .fg(theme.header_fg)
+                .add_modifier(Modifier::BOLD),
         ),
-        create_aligned_field(
-            "Last packet: ".to_string(),
-            format!("{}s ago", app.ptp_tracker.get_last_packet_age().as_secs()),
-            STATS_LABEL_WIDTH,
-            theme,
+        Span::styled(
+            format!(" {}", version::get_version()),
+            Style::default()
+                .fg(theme.text_accent)
+                .add_modifier(Modifier::BOLD),
         ),
     ];
 
-    let paragraph = Paragraph::new(stats_text)
-        .style(Style::default().fg(theme.text_primary).bg(theme.background))
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Statistics")
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(theme.border_normal)),
-        )
-        .wrap(Wrap { trim: true });
+    // Pulses with every Sync of the main PTT
+    if app.paused.is_none() {
+        match app.heartbeat.state(std::time::Instant::now()) {
+            HeartbeatState::Idle => {}
+            HeartbeatState::Beat => header_spans.push(Span::styled(
+                " ♥",
+                Style::default()
+                    .fg(theme.confidence_high)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            HeartbeatState::Rest => header_spans.push(Span::styled(
+                " ♡",
+                Style::default().fg(theme.text_secondary),
+            )),
+            HeartbeatState::Lost(silence) => header_spans.push(Span::styled(
+                format!(" ♡ no Sync from PTT for {:.0} s", silence.as_secs_f64()),
+                Style::default()
+                    .fg(theme.confidence_low)
+                    .add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK),
+            )),
+        }
+    }
 
-    f.render_widget(paragraph, area);
-}
+    // Add PAUSED indicator if paused
+    if app.paused.is_some() {
+        header_spans.push(Span::styled(
+            " [PAUSED]",
+            Style::default()
+                .fg(theme.text_accent)
+                .add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK),
+        ));
+    }
+    if let Some(label) = app.split_pause.label() {
+        header_spans.push(Span::styled(
+            format!(" [{}]", label),
+            Style::default()
+                .fg(theme.text_accent)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
 
-fn render_host_details(f: &mut Frame, area: Rect, app: &mut App) {
-    // Calculate visible content area accounting for borders
-    let content_height = area.height.saturating_sub(2) as usize; // Subtract top and bottom borders
+    // Make it obvious when ptp-trace may put frames on the wire
+    match app.ptp_tracker.get_transmit_mode() {
+        TransmitMode::Passive if app.offline => header_spans.push(Span::styled(
+            " [OFFLINE/PASSIVE]",
+            Style::default()
+                .fg(theme.confidence_high)
+                .add_modifier(Modifier::BOLD),
+        )),
+        TransmitMode::Passive => {}
+        TransmitMode::DryRun => header_spans.push(Span::styled(
+            " [ACTIVE: DRY-RUN]",
+            Style::default()
+                .fg(theme.text_accent)
+                .add_modifier(Modifier::BOLD),
+        )),
+        TransmitMode::Active => header_spans.push(Span::styled(
+            " [ACTIVE]",
+            Style::default()
+                .fg(theme.confidence_low)
+                .add_modifier(Modifier::BOLD),
+        )),
+    }
 
-    let theme = &app.theme;
+    if let Some(agents) = app.remote_agents()
+        && let Ok(agents) = agents.lock()
+    {
+        let connected = agents.iter().filter(|agent| agent.connected).count();
+        header_spans.push(Span::styled(
+            format!(" [VIEWER {}/{} agents]", connected, agents.len()),
+            Style::default()
+                .fg(if connected == agents.len() {
+                    theme.text_accent
+                } else {
+                    theme.confidence_low
+                })
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
 
-    let details_text = if let Some(ref selected_host_id) = app.selected_host_id {
-        if let Some(host) = app.ptp_tracker.get_host_by_clock_identity(selected_host_id) {
-            // Get local IPs for comparison
-            let local_ips = app.ptp_tracker.get_local_ips();
-            // Define the width for label alignment
-            const LABEL_WIDTH: usize = 22;
+    if app.ptp_tracker.is_lite() {
+        header_spans.push(Span::styled(
+            " [LITE]",
+            Style::default()
+                .fg(theme.text_accent)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
 
-            let mut details_text = vec![
-                // Host details section
-                create_aligned_field(
-                    "Clock Identity: ".to_string(),
-                    host.clock_identity.to_string(),
-                    LABEL_WIDTH,
-                    theme,
-                ),
-                create_aligned_field(
-                    "Vendor: ".to_string(),
-                    host.get_vendor_name().unwrap_or("-").to_string(),
-                    LABEL_WIDTH,
-                    theme,
+    // What a compliant receiver on this segment would do right now
+    if let Some(receiver) = app.ptp_tracker.get_simulated_receiver() {
+        let now = app.clock().now();
+        let state = receiver.state();
+        let (detail, color) = match state {
+            ReceiverState::Listening => (String::new(), theme.confidence_medium),
+            ReceiverState::Uncalibrated { parent } => {
+                (format!(" {}, no Sync", parent), theme.confidence_medium)
+            }
+            ReceiverState::Locked { parent } => (format!(" to {}", parent), theme.confidence_high),
+            ReceiverState::Holdover { reason, .. } => (
+                format!(
+                    " {} ({})",
+                    format_badge_duration(receiver.state_duration(now).unwrap_or_default()),
+                    reason
                 ),
-            ];
+                theme.confidence_low,
+            ),
+        };
+        header_spans.push(Span::styled(
+            format!(
+                " [SIM RX {}: {}{}]",
+                receiver.instance_name(),
+                state.label(),
+                detail
+            ),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ));
+    }
 
-            // Add IP addresses with interface info - each on its own row with "IP Address:" label
-            if host.has_ip_addresses() {
-                for (ip, interfaces) in host.ip_addresses.iter() {
-                    let s = interfaces.join(", ");
+    if let Some(register) = app.recording_macro() {
+        header_spans.push(Span::styled(
+            format!(" [REC @{}]", register),
+            Style::default()
+                .fg(theme.confidence_low)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
 
-                    let ip_display = if local_ips.contains(ip) {
-                        format!("{} ({}) *", ip, s)
-                    } else {
-                        format!("{} ({})", ip, s)
-                    };
-                    details_text.push(create_aligned_field(
-                        "IP Address: ".to_string(),
-                        ip_display,
-                        LABEL_WIDTH,
-                        theme,
-                    ));
-                }
-                details_text.push(create_aligned_field(
-                    "Vlan Id:".to_string(),
-                    host.vlan_id.map_or("-".to_string(), |id| id.to_string()),
-                    LABEL_WIDTH,
-                    theme,
-                ));
-            } else if !host.get_interfaces().is_empty() {
-                // Show interfaces for gPTP hosts without IP addresses
-                let interfaces_display = host
-                    .get_interfaces()
+    if app.host_filter_enabled {
+        header_spans.push(Span::styled(
+            format!(" [FILTER: {}]", app.host_filter),
+            Style::default()
+                .fg(theme.text_accent)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if let Some(vendor) = &app.vendor_filter {
+        header_spans.push(Span::styled(
+            format!(" [VENDOR: {}]", vendor),
+            Style::default()
+                .fg(theme.text_accent)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    // A host flood is otherwise only visible as hosts silently disappearing
+    let evicted = app.ptp_tracker.get_evicted_host_count();
+    if evicted > 0 {
+        header_spans.push(Span::styled(
+            format!(
+                " [HOST LIMIT {} REACHED: {} evicted]",
+                app.ptp_tracker.get_max_hosts(),
+                evicted
+            ),
+            Style::default()
+                .fg(theme.confidence_low)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    // Result of the last action, e.g. an export, shown for a few seconds
+    if let Some((message, since)) = &app.status_message
+        && since.elapsed() < STATUS_MESSAGE_DURATION
+    {
+        header_spans.push(Span::styled(
+            format!(" | {}", message),
+            Style::default().fg(theme.text_secondary),
+        ));
+    }
+
+    let header_content = vec![Line::from(header_spans), health_badges(app)];
+
+    let header = Paragraph::new(header_content)
+        .style(Style::default().bg(theme.header_bg))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme.border_normal)),
+        );
+
+    f.render_widget(header, area);
+}
+
+/// Resolve clock class to human-readable description
+pub fn format_clock_class(cc: Option<PtpClockClass>) -> String {
+    match cc {
+        None => "N/A".to_string(),
+        Some(class) => class.to_string(),
+    }
+}
+
+/// Resolve clock accuracy
+pub fn format_clock_accuracy(ca: Option<PtpClockAccuracy>) -> String {
+    match ca {
+        None => "N/A".to_string(),
+        Some(accuracy) => accuracy.to_string(),
+    }
+}
+
+fn render_main_content(f: &mut Frame, area: Rect, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+        .split(area);
+
+    // Store areas for mouse support
+    app.host_table_area = Some(chunks[0]);
+
+    // Left panel: PTP hosts list
+    render_hosts_table(f, chunks[0], app);
+
+    // Right panel: Statistics and details
+    render_stats_panel(f, chunks[1], app);
+}
+
+fn render_hosts_table(f: &mut Frame, area: Rect, app: &mut App) {
+    // Calculate visible rows (subtract 4 for top border, header row, header bottom margin, and bottom border)
+    // Compact rows only have the title line above the header
+    let compact = app.config.row_density.is_compact();
+    let visible_height = area.height.saturating_sub(if compact { 2 } else { 4 }) as usize;
+
+    // Store visible height in app for key handling
+    app.set_visible_height(visible_height);
+
+    // Only ensure the selected item is visible if selection changed
+    if app.host_selection_changed {
+        app.ensure_host_visible(visible_height);
+        app.host_selection_changed = false;
+    }
+
+    let theme = &app.theme;
+    let _is_focused = true; // Hosts are always focused now
+    let selected_index = app.get_selected_index();
+    let updated_scroll_offset = app.get_host_scroll_offset();
+
+    // Get local IPs for comparison
+    let local_ips = app.ptp_tracker.get_local_ips();
+
+    let sort_column = app.get_sort_column();
+    let headers = [
+        (SortColumn::State, "State"),
+        (SortColumn::Role, "Role"),
+        (SortColumn::ClockIdentity, "Clock Identity"),
+        (SortColumn::IpAddress, "IP Address"),
+        (SortColumn::Interface, "Interfaces"),
+        (SortColumn::Hops, "Hops"),
+        (SortColumn::Vendor, "Vendor"),
+        (SortColumn::Domain, "Dom"),
+        (SortColumn::DelayMechanism, "Dly"),
+        (SortColumn::Priority, "Pri"),
+        (SortColumn::ClockClass, "CC"),
+        (SortColumn::SelectedTransmitter, "Selected Transmitter"),
+        (SortColumn::MessageCount, "Msgs"),
+        (SortColumn::LastSeen, "Last Seen"),
+        (SortColumn::Conformance, "Conf"),
+    ];
+
+    let header_cells = headers
+        .iter()
+        .filter(|(col_type, _)| !(compact && *col_type == SortColumn::Vendor))
+        .map(|(col_type, display_name)| {
+            let style = if col_type == sort_column {
+                Style::default()
+                    .fg(theme.sort_column_active)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+                    .fg(theme.table_header)
+                    .add_modifier(Modifier::BOLD)
+            };
+            Cell::from(*display_name).style(style)
+        });
+
+    // Site, observed, switch port, baseline, flags and custom columns are not
+    // sortable
+    let header_cells = header_cells.chain(
+        app.remote_agents()
+            .map(|_| "Site")
+            .into_iter()
+            .chain(app.config.observed_column.then_some("Observed"))
+            .chain((!app.switch_ports.is_empty()).then_some("Switch Port"))
+            .chain(app.baseline.is_some().then_some("Baseline"))
+            .chain(std::iter::once("Flags"))
+            .chain(
+                app.config
+                    .columns
                     .iter()
-                    .cloned()
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                details_text.push(create_aligned_field(
-                    "Interfaces: ".to_string(),
-                    interfaces_display,
-                    LABEL_WIDTH,
+                    .map(|column| column.title.as_str()),
+            )
+            .map(|title| {
+                Cell::from(title).style(
+                    Style::default()
+                        .fg(theme.table_header)
+                        .add_modifier(Modifier::BOLD),
+                )
+            }),
+    );
+
+    let header = Row::new(header_cells).height(1);
+
+    // Get hosts data based on tree view mode
+    let (total_count, rows) = if app.tree_view_mode {
+        // Tree view mode
+        let tree_rows = app.get_tree_rows();
+        let now = app.host_clock().now();
+        let total_count = tree_rows.len();
+
+        // Only the visible rows are looked up and rendered
+        let rows: Vec<Row> = tree_rows
+            .iter()
+            .enumerate()
+            .skip(updated_scroll_offset)
+            .take(visible_height)
+            .filter_map(|(actual_i, node)| {
+                let host = app.host(&node.clock_identity)?;
+
+                // Create indentation for tree structure
+                let indent = "  ".repeat(node.depth);
+                let tree_prefix = if node.depth > 0 {
+                    if node.is_last_child {
+                        "└─ "
+                    } else {
+                        "├─ "
+                    }
+                } else {
+                    ""
+                };
+
+                // Annotate what the path towards the host appears to contain
+                let path_annotation = match host.path.infer().0 {
+                    PathKind::Unknown => String::new(),
+                    kind => format!(" [{}]", kind.short()),
+                };
+
+                // Announce continuity over the last minute, gaps mark intermittent transmitters
+                let continuity = if host.is_transmitter() {
+                    format!(" {}", host.announce_continuity.bar(now))
+                } else {
+                    String::new()
+                };
+
+                let clock_identity = if compact {
+                    host.clock_identity.abbreviated()
+                } else {
+                    host.clock_identity.to_string()
+                };
+                let clock_identity_display = format!(
+                    "{}{}{}{}{}",
+                    indent, tree_prefix, clock_identity, path_annotation, continuity
+                );
+
+                Some(create_host_row(
+                    host,
+                    clock_identity_display,
+                    actual_i,
+                    selected_index,
                     theme,
-                ));
+                    &local_ips,
+                    Some(node.is_primary_transmitter),
+                    app,
+                ))
+            })
+            .collect();
+
+        (total_count, rows)
+    } else {
+        // Table view mode (original)
+        let hosts = app.get_hosts();
+        let total_count = hosts.len();
+
+        // Apply scrolling - only show visible rows
+        let visible_hosts: Vec<_> = hosts
+            .iter()
+            .skip(updated_scroll_offset)
+            .take(visible_height)
+            .collect();
+
+        let rows: Vec<Row> = visible_hosts
+            .iter()
+            .enumerate()
+            .map(|(visible_i, host)| {
+                let actual_i = visible_i + updated_scroll_offset;
+                let clock_identity_display = if compact {
+                    host.clock_identity.abbreviated()
+                } else {
+                    host.clock_identity.to_string()
+                };
+
+                create_host_row(
+                    host,
+                    clock_identity_display,
+                    actual_i,
+                    selected_index,
+                    theme,
+                    &local_ips,
+                    None,
+                    app,
+                )
+            })
+            .collect();
+
+        (total_count, rows)
+    };
+
+    let mut widths = vec![
+        Constraint::Length(if compact { 2 } else { 5 }), // State
+        Constraint::Length(if compact { 2 } else { 4 }), // Role
+        Constraint::Min(if compact { 17 } else { 23 }),  // Clock Identity
+        Constraint::Length(24),                          // IP Address
+        Constraint::Length(20),                          // Interfaces
+        Constraint::Length(5),                           // Hops
+    ];
+    if !compact {
+        widths.push(Constraint::Length(20)); // Vendor
+    }
+    widths.extend([
+        // Aliases are shown next to the domain number
+        Constraint::Length(if app.config.domain_aliases.is_empty() {
+            3
+        } else {
+            16
+        }), // Domain
+        Constraint::Length(3),                             // Delay Mechanism
+        Constraint::Length(3),                             // Priority
+        Constraint::Length(3),                             // Clock Class
+        Constraint::Length(if compact { 19 } else { 25 }), // Selected Transmitter
+        Constraint::Length(5),                             // Message Count
+        Constraint::Length(10),                            // Last Seen
+        Constraint::Length(4),                             // Conformance
+    ]);
+    if app.remote_agents().is_some() {
+        widths.push(Constraint::Length(12)); // Site
+    }
+    if app.config.observed_column {
+        widths.push(Constraint::Length(9)); // Observed
+    }
+    if !app.switch_ports.is_empty() {
+        widths.push(Constraint::Length(18)); // Switch Port
+    }
+    if app.baseline.is_some() {
+        widths.push(Constraint::Length(8)); // Baseline
+    }
+    widths.push(Constraint::Length(8)); // Flags
+    widths.extend(
+        app.config
+            .columns
+            .iter()
+            .map(|column| Constraint::Length(column.width())),
+    );
+
+    let sort_direction = if app.is_sort_ascending() {
+        "↑"
+    } else {
+        "↓"
+    };
+
+    let view_indicator = match app.active_view {
+        ActiveView::HostTable => " [ACTIVE - TAB to switch]",
+        ActiveView::HostDetails => " [TAB to switch]",
+        ActiveView::PacketHistory => " [TAB to switch]",
+    };
+
+    // Show which slice of a long host list is on screen
+    let page_indicator = if total_count > visible_height && visible_height > 0 {
+        format!(
+            " - {}-{} of {} (page {}/{})",
+            updated_scroll_offset + 1,
+            (updated_scroll_offset + visible_height).min(total_count),
+            total_count,
+            updated_scroll_offset / visible_height + 1,
+            total_count.div_ceil(visible_height)
+        )
+    } else {
+        String::new()
+    };
+
+    // Receivers left by jumping to their transmitter, the last few of them
+    let breadcrumbs = if app.host_breadcrumbs.is_empty() {
+        String::new()
+    } else {
+        let skipped = app.host_breadcrumbs.len().saturating_sub(3);
+        let trail: Vec<String> = app.host_breadcrumbs[skipped..]
+            .iter()
+            .map(|id| id.abbreviated())
+            .collect();
+        format!(
+            " - From: {}{} (Backspace to return)",
+            if skipped > 0 { "… › " } else { "" },
+            trail.join(" › ")
+        )
+    };
+
+    let title = if app.tree_view_mode {
+        format!(
+            "PTP Hosts - Tree View{} - Sort: {}{} (s to cycle, S to reverse){}{}",
+            page_indicator,
+            sort_column.display_name(),
+            sort_direction,
+            view_indicator,
+            breadcrumbs
+        )
+    } else {
+        format!(
+            "PTP Hosts{} - Sort: {}{} (s to cycle, S to reverse){}{}",
+            page_indicator,
+            sort_column.display_name(),
+            sort_direction,
+            view_indicator,
+            breadcrumbs
+        )
+    };
+
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(if compact { Borders::TOP } else { Borders::ALL })
+                .title(title.as_str())
+                .border_type(BorderType::Rounded)
+                .border_style(match app.active_view {
+                    ActiveView::HostTable => Style::default().fg(theme.border_focused),
+                    ActiveView::HostDetails => Style::default().fg(theme.border_normal),
+                    ActiveView::PacketHistory => Style::default().fg(theme.border_normal),
+                }),
+        )
+        .style(Style::default().bg(theme.background))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol(">> ");
+
+    f.render_widget(table, area);
+
+    // Render scrollbar if needed
+    if total_count > visible_height {
+        render_scrollbar(
+            f,
+            area,
+            total_count,
+            updated_scroll_offset,
+            visible_height,
+            theme,
+        );
+    }
+}
+
+/// Per-host timeline of observed spans, so hosts that come and go stand out
+fn render_timeline(f: &mut Frame, area: Rect, app: &mut App) {
+    // Subtract borders and the time axis line
+    let visible_height = area.height.saturating_sub(3) as usize;
+    app.set_visible_height(visible_height);
+    if app.host_selection_changed {
+        app.ensure_host_visible(visible_height);
+        app.host_selection_changed = false;
+    }
+
+    let theme = &app.theme;
+    let selected_index = app.get_selected_index();
+    let scroll_offset = app.get_host_scroll_offset();
+    let host_ids = app.get_displayed_host_ids();
+    let hosts: Vec<&PtpHost> = host_ids.iter().filter_map(|id| app.host(id)).collect();
+
+    let end = app.clock().now();
+    let start = hosts
+        .iter()
+        .filter_map(|host| host.observed_spans.front().map(|span| span.0))
+        .min()
+        .unwrap_or(end);
+    let total = end.duration_since(start).unwrap_or_default();
+
+    const LABEL_WIDTH: usize = 25;
+    let bar_width = (area.width as usize).saturating_sub(LABEL_WIDTH + 3).max(1);
+    let cell_duration = total / bar_width as u32;
+
+    let format_time = |time: std::time::SystemTime| {
+        chrono::DateTime::<chrono::Local>::from(time)
+            .format("%H:%M:%S")
+            .to_string()
+    };
+
+    // Time axis with start, middle and end labels
+    let mut axis = vec![' '; bar_width];
+    for (position, label) in [
+        (0, format_time(start)),
+        (
+            bar_width.saturating_sub(8) / 2,
+            format_time(start + total / 2),
+        ),
+        (bar_width.saturating_sub(8), format_time(end)),
+    ] {
+        for (i, c) in label.chars().enumerate() {
+            if let Some(slot) = axis.get_mut(position + i) {
+                *slot = c;
+            }
+        }
+    }
+    let mut lines = vec![Line::from(vec![
+        Span::raw(format!("{:width$}", "", width = LABEL_WIDTH)),
+        Span::styled(
+            axis.into_iter().collect::<String>(),
+            Style::default().fg(theme.text_secondary),
+        ),
+    ])];
+
+    for (i, host) in hosts
+        .iter()
+        .enumerate()
+        .skip(scroll_offset)
+        .take(visible_height)
+    {
+        let first_seen = host.observed_spans.front().map(|span| span.0);
+        let last_seen = host.observed_spans.back().map(|span| span.1);
+
+        let mut bar = String::with_capacity(bar_width * 3);
+        let mut gaps = 0;
+        for cell in 0..bar_width {
+            let cell_start = start + cell_duration * cell as u32;
+            let cell_end = cell_start + cell_duration;
+            let observed = host
+                .observed_spans
+                .iter()
+                .any(|(span_start, span_end)| *span_start <= cell_end && *span_end >= cell_start);
+
+            if observed {
+                bar.push('█');
+            } else if first_seen.is_some_and(|t| t < cell_start)
+                && last_seen.is_some_and(|t| t > cell_end)
+            {
+                bar.push('░');
+                gaps += 1;
+            } else {
+                bar.push(' ');
+            }
+        }
+
+        let label_style = if i == selected_index {
+            Style::default()
+                .bg(theme.selected_row_background)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let bar_color = if gaps > 0 {
+            theme.confidence_medium
+        } else {
+            theme.get_state_color(&host.state)
+        };
+
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!(
+                    "{:width$}",
+                    host.clock_identity.to_string(),
+                    width = LABEL_WIDTH
+                ),
+                label_style.fg(theme.text_primary),
+            ),
+            Span::styled(bar, Style::default().fg(bar_color)),
+        ]));
+    }
+
+    let block = Block::default()
+        .title("Host Timeline - █ observed, ░ gap ('T' to close)")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.border_focused))
+        .style(Style::default().bg(theme.background));
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// One lane per host of a PTP instance with a marker for each message it sent
+fn render_swimlane(f: &mut Frame, area: Rect, app: &App, swimlane: Swimlane) {
+    let theme = &app.theme;
+    let hosts = app.get_swimlane_hosts();
+
+    let newest = app.clock().now();
+    let (start, end) = swimlane.range(newest);
+
+    const LABEL_WIDTH: usize = 27;
+    let lane_width = (area.width as usize).saturating_sub(LABEL_WIDTH + 2).max(1);
+    // Borders, time axis and legend
+    let visible_lanes = area.height.saturating_sub(4) as usize;
+
+    let time_format = if swimlane.window < Duration::from_secs(60) {
+        "%H:%M:%S%.3f"
+    } else {
+        "%H:%M:%S"
+    };
+    let format_time = |time: std::time::SystemTime| {
+        chrono::DateTime::<chrono::Local>::from(time)
+            .format(time_format)
+            .to_string()
+    };
+    let start_label = format_time(start);
+    let end_label = format_time(end);
+    let mut axis = vec![' '; lane_width];
+    for (position, label) in [
+        (0, &start_label),
+        (lane_width.saturating_sub(end_label.len()), &end_label),
+    ] {
+        for (i, c) in label.chars().enumerate() {
+            if let Some(slot) = axis.get_mut(position + i) {
+                *slot = c;
+            }
+        }
+    }
+    let mut lines = vec![Line::from(vec![
+        Span::raw(format!("{:width$}", "", width = LABEL_WIDTH)),
+        Span::styled(
+            axis.into_iter().collect::<String>(),
+            Style::default().fg(theme.text_secondary),
+        ),
+    ])];
+
+    let scroll = swimlane
+        .scroll
+        .min(hosts.len().saturating_sub(visible_lanes));
+    for host in hosts.iter().skip(scroll).take(visible_lanes) {
+        let sent = host.packet_history.items.iter().filter_map(|packet| {
+            let header = packet.ptp.header();
+            (header.source_port_identity.clock_identity == host.clock_identity)
+                .then_some((packet.raw.timestamp, header.message_type))
+        });
+        let state = match host.state {
+            PtpHostState::TimeTransmitter(_) => "T",
+            PtpHostState::TimeReceiver(_) => "R",
+            PtpHostState::Listening => "L",
+        };
+
+        let mut spans = vec![Span::styled(
+            format!(
+                "{} {:width$}",
+                state,
+                host.clock_identity.to_string(),
+                width = LABEL_WIDTH - 2
+            ),
+            Style::default().fg(theme.get_state_color(&host.state)),
+        )];
+        for cell in lane_cells(sent, start, end, lane_width) {
+            spans.push(match cell {
+                Some(message_type) => Span::styled(
+                    swimlane::marker(message_type).to_string(),
+                    Style::default().fg(match message_type {
+                        PtpMessageType::Announce => theme.text_accent,
+                        _ => theme.get_message_type_color(&message_type),
+                    }),
+                ),
+                None => Span::styled("·", Style::default().fg(theme.text_secondary)),
+            });
+        }
+        lines.push(Line::from(spans));
+    }
+    if hosts.is_empty() {
+        lines.push(Line::from("  No hosts in this PTP instance."));
+    }
+    while lines.len() < visible_lanes + 1 {
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled(
+        "S Sync  F Follow_Up  A Announce  q Delay_Req  r Delay_Resp  p PDelay  g Signaling  m Management",
+        Style::default().fg(theme.text_secondary),
+    )));
+
+    let instance = swimlane
+        .instance
+        .map_or("-".to_string(), |(sdo_id, domain)| {
+            if sdo_id == 0 {
+                app.config.domain_label(domain)
+            } else {
+                format_ptp_instance(sdo_id, domain)
+            }
+        });
+    let title = format!(
+        "Swimlanes - {} - {:.1} s{} - ←/→ scroll  +/- zoom  End live  n next domain  'L' to close",
+        instance,
+        swimlane.window.as_secs_f64(),
+        if swimlane.offset.is_zero() {
+            String::new()
+        } else {
+            format!(", {:.1} s back", swimlane.offset.as_secs_f64())
+        }
+    );
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.border_focused))
+        .style(Style::default().bg(theme.background));
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn render_stats_panel(f: &mut Frame, area: Rect, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            // Summary stats, with lines for truncated packets, muted hosts,
+            // the baseline and domains mixing minor versions if any
+            Constraint::Length(
+                11 + u16::from(app.ptp_tracker.get_truncated_packet_count() > 0)
+                    + u16::from(has_muted(app))
+                    + u16::from(app.baseline.is_some())
+                    + app
+                        .ptp_tracker
+                        .get_mixed_minor_versions()
+                        .iter()
+                        .map(|mixed| 1 + mixed.hosts.len() as u16)
+                        .sum::<u16>(),
+            ),
+            Constraint::Length(if has_local_clock(app) { 5 } else { 0 }), // Local clock
+            Constraint::Min(5), // Details panel (host or packet)
+        ])
+        .split(area);
+
+    // Store area for mouse support
+    app.host_details_area = Some(chunks[2]);
+
+    // Summary statistics
+    render_summary_stats(f, chunks[0], app);
+
+    #[cfg(unix)]
+    if app.local_clock.is_some() {
+        render_local_clock(f, chunks[1], app);
+    }
+
+    // Show host details (merged with network info)
+    render_host_details(f, chunks[2], app);
+}
+
+/// Whether the ptp4l/PHC state of this machine is shown, Unix only
+fn has_local_clock(app: &App) -> bool {
+    #[cfg(unix)]
+    return app.local_clock.is_some();
+    #[cfg(not(unix))]
+    return {
+        let _ = app;
+        false
+    };
+}
+
+#[cfg(unix)]
+fn render_local_clock(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let Some(local_clock) = &app.local_clock else {
+        return;
+    };
+    let state = local_clock.state();
+
+    const LOCAL_LABEL_WIDTH: usize = 8;
+    let mut lines = Vec::new();
+
+    match &state.ptp4l {
+        Some(Ok(status)) => {
+            lines.push(create_aligned_field(
+                "ptp4l: ".to_string(),
+                format!(
+                    "offset {:+} ns, rate {:+.3} ppb",
+                    status.master_offset_ns,
+                    status.rate_offset_ppb()
+                ),
+                LOCAL_LABEL_WIDTH,
+                theme,
+            ));
+
+            // Whether the GM ptp4l follows is the one elected on the wire
+            let on_wire = match app.ptp_tracker.get_host(&status.gm_identity) {
+                Some(host) => match &host.state {
+                    PtpHostState::TimeTransmitter(s) if s.is_bmca_winner => "PTT on wire",
+                    _ => "seen on wire, not PTT",
+                },
+                None => "not seen on wire",
+            };
+            lines.push(create_aligned_field(
+                "GM: ".to_string(),
+                if status.gm_present {
+                    format!("{} ({})", status.gm_identity, on_wire)
+                } else {
+                    "none, ptp4l is its own GM".to_string()
+                },
+                LOCAL_LABEL_WIDTH,
+                theme,
+            ));
+        }
+        Some(Err(e)) => lines.push(create_aligned_field(
+            "ptp4l: ".to_string(),
+            e.clone(),
+            LOCAL_LABEL_WIDTH,
+            theme,
+        )),
+        None => {}
+    }
+
+    if let Some((device, phc)) = &state.phc {
+        lines.push(create_aligned_field(
+            "PHC: ".to_string(),
+            match phc {
+                Ok(phc) => format!(
+                    "{} freq {:+.3} ppm, PHC-sys {:+.9} s",
+                    device.display(),
+                    phc.freq_ppb / 1000.0,
+                    phc.sys_offset_ns as f64 / 1e9
+                ),
+                Err(e) => e.clone(),
+            },
+            LOCAL_LABEL_WIDTH,
+            theme,
+        ));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .style(Style::default().fg(theme.text_primary).bg(theme.background))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Local Clock")
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme.border_normal)),
+        );
+
+    f.render_widget(paragraph, area);
+}
+
+/// Whether hosts are muted or alerts were left out for them
+fn has_muted(app: &App) -> bool {
+    app.ptp_tracker.get_muted_host_count() > 0 || app.ptp_tracker.get_muted_alert_count() > 0
+}
+
+fn render_summary_stats(f: &mut Frame, area: Rect, app: &mut App) {
+    let theme = &app.theme;
+    let since = app.time_range_start();
+    // Hosts seen in the selected time range, all of them for the session
+    let (total_hosts, transmitter_count, receiver_count) = match since {
+        Some(since) => {
+            let hosts = app.ptp_tracker.get_hosts();
+            let seen: Vec<_> = hosts
+                .iter()
+                .filter(|host| host.last_seen >= since && !app.ptp_tracker.is_muted(host))
+                .collect();
+            (
+                seen.len(),
+                seen.iter().filter(|host| host.is_transmitter()).count(),
+                seen.iter().filter(|host| host.is_receiver()).count(),
+            )
+        }
+        None => (
+            app.ptp_tracker.get_host_count(),
+            app.ptp_tracker.get_transmitter_count(),
+            app.ptp_tracker.get_receiver_count(),
+        ),
+    };
+
+    // Define the width for label alignment in statistics
+    const STATS_LABEL_WIDTH: usize = 15; // Width for "Total Hosts: "
+
+    let mut stats_text = vec![
+        create_aligned_field(
+            "Total Hosts: ".to_string(),
+            match since {
+                Some(_) => format!(
+                    "{} of {} seen ({}, 'u')",
+                    total_hosts,
+                    app.ptp_tracker.get_host_count(),
+                    app.time_range.label()
+                ),
+                None => total_hosts.to_string(),
+            },
+            STATS_LABEL_WIDTH,
+            theme,
+        ),
+        create_aligned_field_with_vendor(
+            "Transmitters: ".to_string(),
+            transmitter_count.to_string(),
+            String::new(),
+            STATS_LABEL_WIDTH,
+            theme,
+            theme.state_transmitter,
+        ),
+        create_aligned_field_with_vendor(
+            "Receivers: ".to_string(),
+            receiver_count.to_string(),
+            String::new(),
+            STATS_LABEL_WIDTH,
+            theme,
+            theme.state_receiver,
+        ),
+        create_aligned_field(
+            "Last packet: ".to_string(),
+            match app.ptp_tracker.get_last_packet_time() {
+                Some(time) => format!("{}s ago", app.clock().since(time).as_secs()),
+                None => "-".to_string(),
+            },
+            STATS_LABEL_WIDTH,
+            theme,
+        ),
+        create_aligned_field(
+            "Duplicates: ".to_string(),
+            if app.ptp_tracker.is_dedup_enabled() {
+                app.ptp_tracker.get_duplicate_count().to_string()
+            } else {
+                format!("{} (de-dup off)", app.ptp_tracker.get_duplicate_count())
+            },
+            STATS_LABEL_WIDTH,
+            theme,
+        ),
+        create_aligned_field(
+            "Alerts: ".to_string(),
+            {
+                let alerts: Vec<_> = app
+                    .ptp_tracker
+                    .get_alerts()
+                    .iter()
+                    .filter(|alert| since.is_none_or(|since| alert.timestamp >= since))
+                    .collect();
+                match alerts.last() {
+                    Some(alert) => format!("{} (last: {})", alerts.len(), alert.kind),
+                    None => "0".to_string(),
+                }
+            },
+            STATS_LABEL_WIDTH,
+            theme,
+        ),
+        create_aligned_field(
+            "Domains: ".to_string(),
+            {
+                let domains = app.ptp_tracker.get_domain_host_counts();
+                if domains.is_empty() {
+                    "-".to_string()
+                } else {
+                    domains
+                        .iter()
+                        .map(|(domain, count)| {
+                            format!("{}: {}", app.config.domain_label(*domain), count)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            },
+            STATS_LABEL_WIDTH,
+            theme,
+        ),
+        create_aligned_field(
+            "Update: ".to_string(),
+            match &app.adaptive_interval {
+                Some(adaptive) => {
+                    let (min, max) = adaptive.bounds();
+                    format!(
+                        "{} ms (adaptive {}-{} ms, {:.0} pkt/s)",
+                        app.update_interval.as_millis(),
+                        min.as_millis(),
+                        max.as_millis(),
+                        adaptive.rate().unwrap_or_default()
+                    )
+                }
+                None => format!("{} ms", app.update_interval.as_millis()),
+            },
+            STATS_LABEL_WIDTH,
+            theme,
+        ),
+        create_aligned_field(
+            "Memory: ".to_string(),
+            format!(
+                "~{:.1} MB",
+                app.ptp_tracker.estimate_memory_usage() as f64 / (1024.0 * 1024.0)
+            ),
+            STATS_LABEL_WIDTH,
+            theme,
+        ),
+    ];
+    let truncated = app.ptp_tracker.get_truncated_packet_count();
+    if truncated > 0 {
+        stats_text.push(create_aligned_field(
+            "Truncated: ".to_string(),
+            format!("{} packets (capture snaplen)", truncated),
+            STATS_LABEL_WIDTH,
+            theme,
+        ));
+    }
+    if has_muted(app) {
+        stats_text.push(create_aligned_field(
+            "Muted: ".to_string(),
+            format!(
+                "{} hosts, {} alerts{}",
+                app.ptp_tracker.get_muted_host_count(),
+                app.ptp_tracker.get_muted_alert_count(),
+                if app.show_muted { " (listed)" } else { "" }
+            ),
+            STATS_LABEL_WIDTH,
+            theme,
+        ));
+    }
+    if let Some(counts) = app.baseline_counts() {
+        stats_text.push(create_aligned_field(
+            "Baseline: ".to_string(),
+            format!(
+                "{} new, {} changed, {} missing",
+                counts.new, counts.changed, counts.missing
+            ),
+            STATS_LABEL_WIDTH,
+            theme,
+        ));
+    }
+    // Domains mixing minor versions, with the hosts on each
+    for mixed in app.ptp_tracker.get_mixed_minor_versions() {
+        let header = (
+            "Versions: ".to_string(),
+            format!(
+                "domain {} mixes minor versions",
+                app.config.domain_label(mixed.instance.1)
+            ),
+        );
+        let hosts = mixed
+            .host_names()
+            .into_iter()
+            .map(|(minor_version, names)| (format!("v2.{}: ", minor_version), names));
+        for (label, value) in std::iter::once(header).chain(hosts) {
+            stats_text.push(Line::from(vec![
+                Span::styled(
+                    format!("{:width$}", label, width = STATS_LABEL_WIDTH),
+                    Style::default().fg(theme.text_secondary),
+                ),
+                Span::styled(value, Style::default().fg(theme.confidence_low)),
+            ]));
+        }
+    }
+
+    let paragraph = Paragraph::new(stats_text)
+        .style(Style::default().fg(theme.text_primary).bg(theme.background))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Statistics")
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme.border_normal)),
+        )
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_host_details(f: &mut Frame, area: Rect, app: &mut App) {
+    // Calculate visible content area accounting for borders
+    let content_height = area.height.saturating_sub(2) as usize; // Subtract top and bottom borders
+
+    let theme = &app.theme;
+
+    let details_text = if let Some(ref selected_host_id) = app.selected_host_id {
+        if let Some(host) = app.host(selected_host_id) {
+            // Get local IPs for comparison
+            let local_ips = app.ptp_tracker.get_local_ips();
+            // Define the width for label alignment
+            const LABEL_WIDTH: usize = 22;
+            let timezone = app.config.timezone();
+
+            let mut details_text = vec![
+                // Host details section
+                create_aligned_field(
+                    "Clock Identity: ".to_string(),
+                    host.clock_identity.to_string(),
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Vendor: ".to_string(),
+                    host.get_vendor_name().unwrap_or("-").to_string(),
+                    LABEL_WIDTH,
+                    theme,
+                ),
+            ];
+            if let Some(description) = host.description_query.description() {
+                details_text.push(create_aligned_field(
+                    "Product: ".to_string(),
+                    description.product(),
+                    LABEL_WIDTH,
+                    theme,
+                ));
+                details_text.push(create_aligned_field(
+                    "Name: ".to_string(),
+                    description.name().unwrap_or("-").to_string(),
+                    LABEL_WIDTH,
+                    theme,
+                ));
+                details_text.push(create_aligned_field(
+                    "Revisions: ".to_string(),
+                    description.revisions(),
+                    LABEL_WIDTH,
+                    theme,
+                ));
+            } else if let Some(status) = host.description_query.status() {
+                details_text.push(create_aligned_field(
+                    "Description: ".to_string(),
+                    status,
+                    LABEL_WIDTH,
+                    theme,
+                ));
+            }
+            if !host.tags.is_empty() {
+                details_text.push(create_aligned_field(
+                    "Tags: ".to_string(),
+                    host.tags.join(", "),
+                    LABEL_WIDTH,
+                    theme,
+                ));
+            }
+
+            // Add IP addresses with interface info - each on its own row with "IP Address:" label
+            if host.has_ip_addresses() {
+                for (ip, interfaces) in host.ip_addresses.iter() {
+                    let s = interfaces.join(", ");
+
+                    let ip_display = if local_ips.contains(ip) {
+                        format!("{} ({}) *", ip, s)
+                    } else {
+                        format!("{} ({})", ip, s)
+                    };
+                    details_text.push(create_aligned_field(
+                        "IP Address: ".to_string(),
+                        ip_display,
+                        LABEL_WIDTH,
+                        theme,
+                    ));
+                }
+                details_text.push(create_aligned_field(
+                    "Vlan Id:".to_string(),
+                    host.vlan_id.map_or("-".to_string(), |id| id.to_string()),
+                    LABEL_WIDTH,
+                    theme,
+                ));
+            } else if !host.get_interfaces().is_empty() {
+                // Show interfaces for gPTP hosts without IP addresses
+                let interfaces_display = host
+                    .get_interfaces()
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                details_text.push(create_aligned_field(
+                    "Interfaces: ".to_string(),
+                    interfaces_display,
+                    LABEL_WIDTH,
+                    theme,
+                ));
+            }
+            if !host.mac_addresses.is_empty() {
+                details_text.push(create_aligned_field(
+                    "MAC Address: ".to_string(),
+                    host.mac_addresses
+                        .iter()
+                        .map(|mac| format_mac(*mac))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    LABEL_WIDTH,
+                    theme,
+                ));
+            }
+            if !app.switch_ports.is_empty() {
+                details_text.push(create_aligned_field(
+                    "Switch Port: ".to_string(),
+                    match app.switch_port(host) {
+                        Some(port) => port.to_string(),
+                        None if host.mac_addresses.is_empty() => {
+                            "- (MAC unknown, routed)".to_string()
+                        }
+                        None => "- (MAC not in the imported tables)".to_string(),
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ));
+            }
+            if let Some(baseline) = &app.baseline {
+                details_text.push(create_aligned_field(
+                    "Baseline: ".to_string(),
+                    match baseline.status(host) {
+                        BaselineStatus::New => "new, not in the previous session".to_string(),
+                        BaselineStatus::Changed(changes) => {
+                            format!("changed: {}", changes.join(", "))
+                        }
+                        BaselineStatus::Unchanged => "unchanged".to_string(),
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ));
+            }
+            if let Some(pcp) = host.event_pcp {
+                details_text.push(create_aligned_field(
+                    "Event Priority: ".to_string(),
+                    match app.ptp_tracker.get_expected_event_pcp() {
+                        Some(expected) if host.event_pcp_violations > 0 => format!(
+                            "⚠ PCP {} ({} event msgs not at the expected {})",
+                            pcp, host.event_pcp_violations, expected
+                        ),
+                        _ => format!("PCP {}", pcp),
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ));
+            }
+            if !host.pdelay_peers.is_empty() {
+                details_text.push(create_aligned_field(
+                    "P2P Peers: ".to_string(),
+                    host.pdelay_peers
+                        .iter()
+                        .map(|peer| peer.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    LABEL_WIDTH,
+                    theme,
+                ));
+            }
+            if !host.cmlds_peers.is_empty() {
+                details_text.push(create_aligned_field(
+                    "CMLDS Peers: ".to_string(),
+                    host.cmlds_peers
+                        .iter()
+                        .map(|peer| peer.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    LABEL_WIDTH,
+                    theme,
+                ));
+            }
+
+            details_text.extend(vec![
+                create_aligned_field_with_vendor(
+                    "State: ".to_string(),
+                    host.role(),
+                    String::new(),
+                    LABEL_WIDTH,
+                    theme,
+                    theme.get_state_color(&host.state),
+                ),
+                create_aligned_field(
+                    "Device Role: ".to_string(),
+                    match host.role_evidence.role() {
+                        Some(role) => format!("{} ({})", role, host.role_evidence.describe()),
+                        None => "N/A".to_string(),
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                match host
+                    .advertised_gm
+                    .assess(host.sync_count > 0, host.role_evidence.role())
+                {
+                    Some(mismatch) => create_aligned_field_with_vendor(
+                        "Advertised GM: ".to_string(),
+                        format!("{}, {}", mismatch.advertised, mismatch.case),
+                        format!(" - {}", mismatch.evidence.join(", ")),
+                        LABEL_WIDTH,
+                        theme,
+                        match mismatch.case {
+                            GmIdentityCase::BoundaryClock => theme.text_primary,
+                            GmIdentityCase::Suspicious => theme.confidence_low,
+                        },
+                    ),
+                    None => create_aligned_field(
+                        "Advertised GM: ".to_string(),
+                        if host.announce_count > 0 {
+                            "own identity".to_string()
+                        } else {
+                            "N/A".to_string()
+                        },
+                        LABEL_WIDTH,
+                        theme,
+                    ),
+                },
+                create_aligned_field(
+                    "PTP Version: ".to_string(),
+                    match (host.last_version, host.last_minor_version) {
+                        (Some(version), Some(minor)) => format!("{}.{}", version, minor),
+                        _ => "N/A".to_string(),
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Domain: ".to_string(),
+                    host.domain_number
+                        .map(|d| app.config.domain_label(d))
+                        .unwrap_or("N/A".to_string()),
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Domains: ".to_string(),
+                    if host.instances.is_empty() && host.cmlds_message_count == 0 {
+                        "N/A".to_string()
+                    } else {
+                        host.instances
+                            .iter()
+                            .map(|(sdo_id, domain)| format_ptp_instance(*sdo_id, *domain))
+                            .chain(
+                                (host.cmlds_message_count > 0)
+                                    .then(|| format!("CMLDS ({} msgs)", host.cmlds_message_count)),
+                            )
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Last Correction: ".to_string(),
+                    host.last_correction_field
+                        .map_or("N/A".to_string(), |v| format!("{} ({})", v, v.value)),
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "First Seen: ".to_string(),
+                    host.first_seen.map_or("N/A".to_string(), format_local_time),
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Observed For: ".to_string(),
+                    host.session_first_seen.map_or("N/A".to_string(), |time| {
+                        format!(
+                            "{} since {}",
+                            format_observed_duration(host.observed_duration()),
+                            format_local_time(time)
+                        )
+                    }),
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Last Seen: ".to_string(),
+                    format!(
+                        "{:.1}s ago",
+                        host.time_since_last_seen(app.host_clock()).as_secs_f64()
+                    ),
+                    LABEL_WIDTH,
+                    theme,
+                ),
+            ]);
+
+            if let Some(score) = host.conformance.score() {
+                let failed = host.conformance.failed_checks();
+                let summary = if failed.is_empty() {
+                    format!(
+                        "{} ({} checks passed)",
+                        score,
+                        host.conformance.checked_count()
+                    )
+                } else {
+                    format!(
+                        "{} ({} of {} checks failing)",
+                        score,
+                        failed.len(),
+                        host.conformance.checked_count()
+                    )
+                };
+                details_text.push(Line::from(vec![
+                    Span::styled(
+                        format!("{:<width$}", "Conformance: ", width = LABEL_WIDTH),
+                        Style::default().fg(theme.text_secondary),
+                    ),
+                    Span::styled(
+                        summary,
+                        Style::default().fg(conformance_color(score, theme)),
+                    ),
+                ]));
+                for (check, tally) in failed {
+                    details_text.push(create_aligned_field(
+                        format!("  {}: ", check.label()),
+                        format!(
+                            "{} of {} failed, last: {}",
+                            tally.failed,
+                            tally.passed + tally.failed,
+                            tally.last_failure.as_deref().unwrap_or("-")
+                        ),
+                        LABEL_WIDTH,
+                        theme,
+                    ));
+                }
+            }
+
+            match &host.state {
+                PtpHostState::Listening => {}
+                PtpHostState::TimeTransmitter(s) => {
+                    let warnings = transmitter_warnings(s);
+                    let mut section_title = vec![Span::styled(
+                        "Time Transmitter:",
+                        Style::default()
+                            .fg(theme.text_accent)
+                            .add_modifier(Modifier::BOLD),
+                    )];
+                    if !warnings.is_empty() {
+                        section_title.push(Span::styled(
+                            " ⚠ suspicious values, '?' explains",
+                            Style::default().fg(theme.text_secondary),
+                        ));
+                    }
+                    details_text.extend(vec![
+                        Line::from(""),
+                        Line::from(section_title),
+                        create_aligned_field(
+                            "Priority 1: ".to_string(),
+                            s.priority1.map_or("N/A".to_string(), |p| p.to_string()),
+                            LABEL_WIDTH,
+                            theme,
+                        ),
+                        create_aligned_field(
+                            "Priority 2: ".to_string(),
+                            s.priority2.map_or("N/A".to_string(), |p| p.to_string()),
+                            LABEL_WIDTH,
+                            theme,
+                        ),
+                        with_warning(
+                            create_aligned_field(
+                                "Clock Class: ".to_string(),
+                                format_clock_class(s.clock_class),
+                                LABEL_WIDTH,
+                                theme,
+                            ),
+                            "Clock Class",
+                            &warnings,
+                            theme,
+                        ),
+                        with_trend(
+                            create_aligned_field(
+                                "Accuracy: ".to_string(),
+                                format_clock_accuracy(s.clock_accuracy),
+                                LABEL_WIDTH,
+                                theme,
+                            ),
+                            host.quality_trend.accuracy_trend(),
+                            theme,
+                        ),
+                        with_warning(
+                            create_aligned_field(
+                                "Steps Removed: ".to_string(),
+                                s.steps_removed
+                                    .map(|d| d.to_string())
+                                    .unwrap_or("N/A".to_string()),
+                                LABEL_WIDTH,
+                                theme,
+                            ),
+                            "Steps Removed",
+                            &warnings,
+                            theme,
+                        ),
+                        with_warning(
+                            with_trend(
+                                create_aligned_field(
+                                    "Log Variance: ".to_string(),
+                                    s.offset_scaled_log_variance
+                                        .map_or("N/A".to_string(), |v| v.to_string()),
+                                    LABEL_WIDTH,
+                                    theme,
+                                ),
+                                host.quality_trend.variance_trend(),
+                                theme,
+                            ),
+                            "Log Variance",
+                            &warnings,
+                            theme,
+                        ),
+                        create_aligned_field(
+                            "Primary Identity: ".to_string(),
+                            s.ptt_identifier
+                                .map_or("N/A".to_string(), |p| p.to_string()),
+                            LABEL_WIDTH,
+                            theme,
+                        ),
+                        with_warning(
+                            create_aligned_field(
+                                "UTC Offset: ".to_string(),
+                                s.current_utc_offset
+                                    .map_or("N/A".to_string(), |o| o.to_string()),
+                                LABEL_WIDTH,
+                                theme,
+                            ),
+                            "UTC Offset",
+                            &warnings,
+                            theme,
+                        ),
+                    ]);
+
+                    // 802.1AS compares the announced attributes as one systemIdentity
+                    if host
+                        .sdo_id
+                        .is_some_and(|sdo_id| sdo_id >> 8 == SDO_ID_GPTP >> 8)
+                    {
+                        details_text.push(create_aligned_field(
+                            "System Identity: ".to_string(),
+                            SystemIdentity::from_state(s)
+                                .map_or("N/A".to_string(), |identity| identity.to_string()),
+                            LABEL_WIDTH,
+                            theme,
+                        ));
+                    }
+                    if let Some(path_trace) = &s.path_trace {
+                        details_text.push(with_warning(
+                            create_aligned_field(
+                                "Path Trace: ".to_string(),
+                                path_trace
+                                    .iter()
+                                    .map(|identity| identity.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(" → "),
+                                LABEL_WIDTH,
+                                theme,
+                            ),
+                            "Path Trace",
+                            &warnings,
+                            theme,
+                        ));
+                    }
+
+                    // The GM this host announces may serve other domains too
+                    let gm_instances = s.ptt_identifier.map_or_else(Vec::new, |gm| {
+                        app.ptp_tracker
+                            .get_gm_instances(&gm, app.host_clock().now())
+                    });
+                    if gm_instances.len() > 1 {
+                        let consistent = gm_instances
+                            .windows(2)
+                            .all(|pair| pair[0].1.clock_class == pair[1].1.clock_class)
+                            && gm_instances
+                                .iter()
+                                .filter_map(|(_, dataset)| dataset.utc_offset)
+                                .collect::<std::collections::BTreeSet<_>>()
+                                .len()
+                                <= 1;
+                        let domains = gm_instances
+                            .iter()
+                            .map(|((sdo_id, domain), dataset)| {
+                                format!(
+                                    "{} (class {}{})",
+                                    format_ptp_instance(*sdo_id, *domain),
+                                    dataset.clock_class,
+                                    dataset
+                                        .utc_offset
+                                        .map_or(String::new(), |offset| format!(", {:+}s", offset))
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        details_text.push(create_aligned_field(
+                            "GM Domains: ".to_string(),
+                            if consistent {
+                                domains
+                            } else {
+                                format!("{} ⚠ inconsistent", domains)
+                            },
+                            LABEL_WIDTH,
+                            theme,
+                        ));
+                    }
+
+                    details_text.push(create_aligned_field(
+                        "Sync TS: ".to_string(),
+                        format_timestamp(s.last_sync_origin_timestamp),
+                        LABEL_WIDTH,
+                        theme,
+                    ));
+                    details_text.extend(create_timezone_field(
+                        s.last_sync_origin_timestamp,
+                        s.current_utc_offset,
+                        timezone,
+                        LABEL_WIDTH,
+                        theme,
+                    ));
+
+                    if let Some(ts) = s.last_sync_origin_timestamp {
+                        for (k, v) in ts.format_common_samplerates("→ samples").iter() {
+                            details_text.push(create_aligned_field(
+                                format!("{}:", k),
+                                v.to_string(),
+                                LABEL_WIDTH,
+                                theme,
+                            ));
+                        }
+                    }
+
+                    details_text.push(create_aligned_field(
+                        "Follow-Up TS: ".to_string(),
+                        format_timestamp(s.last_followup_origin_timestamp),
+                        LABEL_WIDTH,
+                        theme,
+                    ));
+                    details_text.extend(create_timezone_field(
+                        s.last_followup_origin_timestamp,
+                        s.current_utc_offset,
+                        timezone,
+                        LABEL_WIDTH,
+                        theme,
+                    ));
+
+                    if let Some(ts) = s.last_followup_origin_timestamp {
+                        for (k, v) in ts.format_common_samplerates("→ samples").iter() {
+                            details_text.push(create_aligned_field(
+                                format!("{}:", k),
+                                v.to_string(),
+                                LABEL_WIDTH,
+                                theme,
+                            ));
+                        }
+                    }
+
+                    if let Some(estimate) = host.era {
+                        details_text.push(create_aligned_field(
+                            "Era: ".to_string(),
+                            match estimate.era {
+                                Era::Ptp => estimate.era.to_string(),
+                                era if era.is_wrong() => {
+                                    format!("⚠ {} ({:+} s vs capture clock)", era, estimate.offset)
+                                }
+                                era => {
+                                    format!("{} ({:+} s vs capture clock)", era, estimate.offset)
+                                }
+                            },
+                            LABEL_WIDTH,
+                            theme,
+                        ));
+                    }
+                }
+                PtpHostState::TimeReceiver(s) => {
+                    details_text.extend(vec![
+                        Line::from(""),
+                        Line::from(vec![Span::styled(
+                            "Time Receiver:",
+                            Style::default()
+                                .fg(theme.text_accent)
+                                .add_modifier(Modifier::BOLD),
+                        )]),
+                        create_aligned_field_with_vendor(
+                            "Selected Transmitter: ".to_string(),
+                            match s.selected_transmitter_identity {
+                                Some(identity) => identity.to_string(),
+                                None => "None".to_string(),
+                            },
+                            s.selected_transmitter_identity
+                                .and_then(|id| id.extract_vendor_name())
+                                .map(|vendor| format!(" ({})", vendor))
+                                .unwrap_or_default(),
+                            LABEL_WIDTH,
+                            theme,
+                            theme.get_confidence_color(s.selected_transmitter_confidence),
+                        ),
+                        create_aligned_field(
+                            "Confidence: ".to_string(),
+                            match (s.selected_transmitter_identity, s.last_delay_exchange) {
+                                (None, _) => "-".to_string(),
+                                (Some(_), Some(last)) => format!(
+                                    "{:.0}% (last delay exchange {})",
+                                    s.selected_transmitter_confidence * 100.0,
+                                    format_system_time_ago(last, app.host_clock())
+                                ),
+                                (Some(_), None) => {
+                                    format!("{:.0}%", s.selected_transmitter_confidence * 100.0)
+                                }
+                            },
+                            LABEL_WIDTH,
+                            theme,
+                        ),
+                    ]);
+
+                    if let Some((followed, predicted)) = host.wrong_transmitter {
+                        details_text.push(Line::from(vec![
+                            Span::styled(
+                                format!("{:width$}", "Tracking: ", width = LABEL_WIDTH),
+                                Style::default().fg(theme.text_secondary),
+                            ),
+                            Span::styled(
+                                format!(
+                                    "⚠ wrong GM: follows {}, BMCA predicts {}",
+                                    followed, predicted
+                                ),
+                                Style::default()
+                                    .fg(theme.confidence_low)
+                                    .add_modifier(Modifier::BOLD),
+                            ),
+                        ]));
+                    }
+
+                    // The UTC offset comes from the transmitter the receiver follows
+                    let utc_offset = s
+                        .selected_transmitter_identity
+                        .and_then(|id| app.host(&id))
+                        .and_then(|transmitter| match &transmitter.state {
+                            PtpHostState::TimeTransmitter(t) => t.current_utc_offset,
+                            _ => None,
+                        });
+
+                    for (label, timestamp) in [
+                        (
+                            "Last E2E Delay TS: ",
+                            s.last_delay_response_origin_timestamp,
+                        ),
+                        (
+                            "Last P2P Delay TS: ",
+                            s.last_pdelay_response_origin_timestamp,
+                        ),
+                        ("Last P2P Delay FU TS: ", s.last_pdelay_follow_up_timestamp),
+                    ] {
+                        details_text.push(create_aligned_field(
+                            label.to_string(),
+                            format_timestamp(timestamp),
+                            LABEL_WIDTH,
+                            theme,
+                        ));
+                        details_text.extend(create_timezone_field(
+                            timestamp,
+                            utc_offset,
+                            timezone,
+                            LABEL_WIDTH,
+                            theme,
+                        ));
+                    }
+                }
+            }
+
+            // Lifetime totals, or the messages of the selected time range with
+            // their rate
+            let counts_since = app.message_counts_start();
+            let range_seconds = app
+                .time_range
+                .duration()
+                .map_or(1.0, |range| range.as_secs_f64());
+            let counts = |counts: &[(PtpMessageType, u32)]| {
+                let values: Vec<String> = counts
+                    .iter()
+                    .map(|(message_type, total)| match counts_since {
+                        Some(since) => host.sent_counts.count(*message_type, since).to_string(),
+                        None => total.to_string(),
+                    })
+                    .collect();
+                let rate = match (counts_since, counts.first()) {
+                    (Some(since), Some((message_type, _))) => format!(
+                        " ({:.2}/s)",
+                        host.sent_counts.count(*message_type, since) as f64 / range_seconds
+                    ),
+                    _ => String::new(),
+                };
+                format!("{}{}", values.join("/"), rate)
+            };
+            details_text.extend(vec![
+                Line::from(""),
+                Line::from(vec![Span::styled(
+                    match counts_since {
+                        Some(_) => format!("Message Counts ({}):", app.time_range.label()),
+                        None => "Message Counts:".to_string(),
+                    },
+                    Style::default()
+                        .fg(theme.text_accent)
+                        .add_modifier(Modifier::BOLD),
+                )]),
+                create_aligned_field(
+                    "Announce: ".to_string(),
+                    counts(&[(PtpMessageType::Announce, host.announce_count)]),
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Sync/FU: ".to_string(),
+                    counts(&[
+                        (PtpMessageType::Sync, host.sync_count),
+                        (PtpMessageType::FollowUp, host.follow_up_count),
+                    ]),
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Delay Req/Resp: ".to_string(),
+                    counts(&[
+                        (PtpMessageType::DelayReq, host.delay_req_count),
+                        (PtpMessageType::DelayResp, host.delay_resp_count),
+                    ]),
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "PDelay Req/Resp/FU: ".to_string(),
+                    counts(&[
+                        (PtpMessageType::PDelayReq, host.pdelay_req_count),
+                        (PtpMessageType::PDelayResp, host.pdelay_resp_count),
+                        (
+                            PtpMessageType::PDelayRespFollowUp,
+                            host.pdelay_resp_follow_up_count,
+                        ),
+                    ]),
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Delay Mechanism: ".to_string(),
+                    match (host.delay_mechanism(), host.delay_mechanism_warning()) {
+                        (Some(mechanism), Some(warning)) => format!("{} ⚠ {}", mechanism, warning),
+                        (Some(mechanism), None) => mechanism.to_string(),
+                        (None, _) => "N/A".to_string(),
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Path: ".to_string(),
+                    {
+                        let (kind, reasons) = host.path.infer();
+                        if reasons.is_empty() {
+                            kind.to_string()
+                        } else {
+                            format!("{} ({})", kind, reasons.join("; "))
+                        }
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Multicast TTL: ".to_string(),
+                    if host.multicast_ttls.is_empty() {
+                        "N/A".to_string()
+                    } else {
+                        let ttls = host
+                            .multicast_ttls
+                            .iter()
+                            .map(|ttl| ttl.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        if host.boundary_alerted {
+                            format!("{} ⚠ crossed boundary", ttls)
+                        } else {
+                            ttls
+                        }
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Hop Distance: ".to_string(),
+                    match (host.hop_distance.current(), host.hop_distance.last_change) {
+                        (None, _) => "N/A".to_string(),
+                        (Some(hops), None) => format!("{} routed hop(s)", hops),
+                        (Some(hops), Some((before, after))) => format!(
+                            "{} routed hop(s) ⚠ changed {} time(s), last {} → {}",
+                            hops, host.hop_distance.change_count, before, after
+                        ),
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Addressing: ".to_string(),
+                    if host.address_issues.is_empty() {
+                        "OK".to_string()
+                    } else {
+                        host.address_issues
+                            .iter()
+                            .map(|((message_type, issue), count)| {
+                                format!("⚠ {} {} ({})", message_type, issue, count)
+                            })
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Delay Cross-Ref: ".to_string(),
+                    {
+                        let clock = app.host_clock();
+                        let mut anomalies = Vec::new();
+                        if let Some(request) = &host.last_unanswered_delay_req {
+                            anomalies.push(format!(
+                                "⚠ {} Delay_Req unanswered (last seq {}, {})",
+                                host.unanswered_delay_req_count,
+                                request.ptp.header().sequence_id,
+                                format_system_time_ago(request.raw.timestamp, clock)
+                            ));
+                        }
+                        if let Some(response) = &host.last_unsolicited_delay_resp
+                            && let PtpMessage::DelayResp(msg) = &response.ptp
+                        {
+                            anomalies.push(format!(
+                                "⚠ {} Delay_Resp to ports never requesting (last {} seq {}, {})",
+                                host.unsolicited_delay_resp_count,
+                                msg.requesting_port_identity,
+                                msg.header.sequence_id,
+                                format_system_time_ago(response.raw.timestamp, clock)
+                            ));
+                        }
+                        if anomalies.is_empty() {
+                            "OK".to_string()
+                        } else {
+                            format!("{}, 'X' shows the last", anomalies.join(", "))
+                        }
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Delay_Req Cadence: ".to_string(),
+                    {
+                        let cadence = &host.delay_req_cadence;
+                        let usual = match cadence.usual_interval() {
+                            Some(usual) => format!("every {:.3} s", usual.as_secs_f64()),
+                            None => "N/A".to_string(),
+                        };
+                        match cadence.events.back() {
+                            Some(event) => format!(
+                                "{}, ⚠ {} probable loss(es) of lock, last {} {}{}",
+                                usual,
+                                cadence.event_count,
+                                format_system_time_ago(event.at, app.host_clock()),
+                                event,
+                                if cadence.is_lost() { ", ongoing" } else { "" }
+                            ),
+                            None => usual,
+                        }
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "UDP Ports: ".to_string(),
+                    if host.udp_ports.is_empty() {
+                        "N/A".to_string()
+                    } else {
+                        let ports = host
+                            .udp_ports
+                            .iter()
+                            .map(|port| port.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        if host.uses_nonstandard_ports() {
+                            format!("{} (non-standard transport)", ports)
+                        } else {
+                            ports
+                        }
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Port Pair: ".to_string(),
+                    if host.port_usage.is_empty() {
+                        "N/A".to_string()
+                    } else {
+                        let problems = host.port_usage.problems();
+                        if problems.is_empty() {
+                            "ok, 'O' shows the port matrix".to_string()
+                        } else {
+                            problems
+                                .iter()
+                                .map(|problem| problem.to_string())
+                                .collect::<Vec<_>>()
+                                .join("; ")
+                        }
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Path Skew: ".to_string(),
+                    if host.path_skews.is_empty() {
+                        "N/A".to_string()
+                    } else {
+                        host.path_skews
+                            .iter()
+                            .filter_map(|((first, second), skew)| {
+                                Some(format!(
+                                    "{}→{} {:+.1} µs ±{:.1} µs{}",
+                                    first,
+                                    second,
+                                    skew.mean_ns()? / 1000.0,
+                                    skew.jitter_ns()? / 1000.0,
+                                    if skew.diverged { " ⚠ diverged" } else { "" }
+                                ))
+                            })
+                            .collect::<Vec<_>>()
+                            .join("; ")
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Bursts: ".to_string(),
+                    host.bursts.summary(),
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Length Errors: ".to_string(),
+                    {
+                        let errors = match &host.last_length_issue {
+                            Some(issue) => {
+                                format!("{} ⚠ last: {}", host.length_issue_count, issue)
+                            }
+                            None => "0".to_string(),
+                        };
+                        match host.truncated_count {
+                            0 => errors,
+                            truncated => {
+                                format!("{}, {} truncated by the capture", errors, truncated)
+                            }
+                        }
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Management/Signaling: ".to_string(),
+                    format!(
+                        "{}/{}",
+                        host.management_message_count, host.signaling_message_count
+                    ),
+                    LABEL_WIDTH,
+                    theme,
+                ),
+                create_aligned_field(
+                    "Unicast Teardowns: ".to_string(),
+                    match &host.last_unicast_teardown {
+                        Some((timestamp, event)) => format!(
+                            "{} (last: {}, {})",
+                            host.unicast_teardown_count,
+                            event,
+                            format_system_time_ago(*timestamp, app.host_clock())
+                        ),
+                        None => "0".to_string(),
+                    },
+                    LABEL_WIDTH,
+                    theme,
+                ),
+            ]);
+
+            details_text.extend(vec![
+                Line::from(""),
+                Line::from(vec![Span::styled(
+                    "Arrival Paths:",
+                    Style::default()
+                        .fg(theme.text_accent)
+                        .add_modifier(Modifier::BOLD),
+                )]),
+            ]);
+            details_text.extend(arrival_path_lines(
+                &host.arrival_paths,
+                app.host_clock(),
+                LABEL_WIDTH,
+                theme,
+            ));
+
+            for issue in &host.timescale_issues {
+                details_text.push(create_aligned_field(
+                    "Timescale: ".to_string(),
+                    format!("⚠ {}", issue),
+                    LABEL_WIDTH,
+                    theme,
+                ));
+            }
+
+            if let Some(drift) = host.sync_drift.estimate() {
+                details_text.push(create_aligned_field(
+                    "Frequency Offset: ".to_string(),
+                    format!(
+                        "{:+.3} ± {:.3} ppm (vs capture host, {} Syncs over {}s)",
+                        drift.ppm,
+                        drift.bound_ppm,
+                        drift.samples,
+                        drift.span.as_secs()
+                    ),
+                    LABEL_WIDTH,
+                    theme,
+                ));
+            }
+
+            if host.residence.has_transparent_clocks() {
+                for hop in host.residence.hops() {
+                    details_text.push(residence_line(&hop, LABEL_WIDTH, theme));
+                }
+            }
+
+            // Vendor and profile extensions of the last message of each type
+            let mut message_types: Vec<_> = host.latest_packets.keys().copied().collect();
+            message_types.sort_by_key(|message_type| *message_type as u8);
+            for message_type in message_types {
+                let packet = &host.latest_packets[&message_type];
+                for tlv in org_tlv::registry().decode(packet.ptp.header(), packet.raw.ptp_payload())
+                {
+                    details_text.push(create_aligned_field(
+                        "Org TLV: ".to_string(),
+                        format!("{} in {}", tlv.title(), message_type),
+                        LABEL_WIDTH,
+                        theme,
+                    ));
+                    details_text.extend(org_tlv_lines(&tlv, LABEL_WIDTH, theme));
+                }
+            }
+
+            let announces = host.recent_announces(RECENT_ANNOUNCES);
+            if !announces.is_empty() {
+                details_text.extend(vec![
+                    Line::from(""),
+                    Line::from(vec![Span::styled(
+                        format!("Last {} Announces:", announces.len()),
+                        Style::default()
+                            .fg(theme.text_accent)
+                            .add_modifier(Modifier::BOLD),
+                    )]),
+                ]);
+                details_text.extend(announce_table_lines(&announces, LABEL_WIDTH, theme));
+            }
+
+            details_text
+        } else {
+            vec![
+                Line::from("No host found with selected ID"),
+                Line::from(""),
+                Line::from("This may indicate a synchronization issue"),
+            ]
+        }
+    } else {
+        vec![
+            Line::from("No host selected"),
+            Line::from(""),
+            Line::from("Use ↑/↓ to select a host"),
+            Line::from("Press Tab to switch between views"),
+        ]
+    };
+
+    app.host_details_text = details_text
+        .iter()
+        .map(|line| line.to_string().trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // Update scroll position - need to do this after we have the content but before we use it
+    let total_lines = details_text.len();
+    let max_scroll = total_lines.saturating_sub(content_height);
+    app.host_details_visible_height = content_height;
+    app.host_details_scroll_offset = app.host_details_scroll_offset.min(max_scroll);
+
+    // Create scrolled content
+    let mut scrolled_text = if app.host_details_scroll_offset < details_text.len() {
+        details_text
+            .iter()
+            .skip(app.host_details_scroll_offset)
+            .take(content_height)
+            .cloned()
+            .collect::<Vec<_>>()
+    } else {
+        vec![]
+    };
+
+    let theme = &app.theme;
+    // The top line is the field 'y' copies with the pane focused
+    if matches!(app.active_view, crate::app::ActiveView::HostDetails)
+        && let Some(line) = scrolled_text.first_mut()
+    {
+        *line = line
+            .clone()
+            .patch_style(Style::default().bg(theme.selected_row_background));
+    }
+    let border_style = if matches!(app.active_view, crate::app::ActiveView::HostDetails) {
+        Style::default().fg(theme.border_focused)
+    } else {
+        Style::default().fg(theme.border_normal)
+    };
+
+    let details_paragraph = Paragraph::new(scrolled_text)
+        .style(Style::default().fg(theme.text_primary).bg(theme.background))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Host Details")
+                .border_type(BorderType::Rounded)
+                .border_style(border_style),
+        )
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(details_paragraph, area);
+
+    // Render scrollbar if needed
+    render_scrollbar(
+        f,
+        area,
+        details_text.len(),
+        app.host_details_scroll_offset,
+        content_height,
+        theme,
+    );
+}
+
+fn render_help(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let heading = |title: String| {
+        Line::from(vec![Span::styled(
+            title,
+            Style::default()
+                .fg(theme.table_header)
+                .add_modifier(Modifier::BOLD),
+        )])
+    };
+
+    let time_transmitter_state =
+        PtpHostState::TimeTransmitter(crate::ptp::PtpHostStateTimeTransmitter::default());
+    let time_receiver_state =
+        PtpHostState::TimeReceiver(crate::ptp::PtpHostStateTimeReceiver::default());
+    let listening_state = PtpHostState::Listening;
+
+    let mut help_text = vec![
+        Line::from(vec![Span::styled(
+            "PTP Network Tracer Help",
+            Style::default()
+                .fg(theme.text_accent)
+                .add_modifier(Modifier::BOLD),
+        )]),
+        Line::from(Span::styled(
+            if app.help_search_active {
+                format!("Search: {}█", app.help_query)
+            } else if !app.help_query.is_empty() {
+                format!("Search: {}  (/ new search, Esc clear)", app.help_query)
+            } else {
+                "/ search  Tab scoped/all keys  ↑/↓ scroll  Esc close".to_string()
+            },
+            Style::default().fg(theme.text_secondary),
+        )),
+        Line::from(""),
+    ];
+
+    let bindings = keymap::search(&app.help_query, app.help_scope);
+    for section in HelpSection::ALL {
+        // The mouse section only applies if the mouse is enabled
+        if section == HelpSection::Mouse && !app.mouse_enabled {
+            continue;
+        }
+        let section_bindings: Vec<_> = bindings
+            .iter()
+            .filter(|binding| binding.section == section)
+            .collect();
+        if section_bindings.is_empty() {
+            continue;
+        }
+        help_text.push(heading(format!("{}:", section.title())));
+        for binding in section_bindings {
+            help_text.push(Line::from(format!(
+                "  {:<10} - {}",
+                binding.keys, binding.description
+            )));
+        }
+        help_text.push(Line::from(""));
+    }
+    if bindings.is_empty() {
+        help_text.push(Line::from(format!("  No keys match '{}'", app.help_query)));
+    }
+
+    // The full help ends with the notes, legend and terminology
+    if app.help_scope.is_none() && app.help_query.is_empty() {
+        help_text.extend(vec![
+            heading("Notes:".to_string()),
+            Line::from("  • Host details and packet history are scrollable"),
+            Line::from("  • Packet selection preserved when switching views"),
+            Line::from("  • Scroll positions reset when selecting different host"),
+            Line::from("  • Auto-scroll disabled when manually navigating packets"),
+            Line::from(""),
+            heading("Legend:".to_string()),
+            Line::from(vec![
+                Span::styled(
+                    format!("  {}", time_transmitter_state.short_string()),
+                    Style::default().fg(theme.get_state_color(&time_transmitter_state)),
+                ),
+                Span::raw(format!("  - {}", time_receiver_state)),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "  PTT",
+                    Style::default().fg(theme.get_state_color(&time_transmitter_state)),
+                ),
+                Span::raw(format!(" - {} (Primary)", time_transmitter_state)),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    format!("  {}", time_receiver_state.short_string()),
+                    Style::default().fg(theme.get_state_color(&time_receiver_state)),
+                ),
+                Span::raw(format!("  - {}", time_receiver_state)),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    format!("  {}", listening_state.short_string()),
+                    Style::default().fg(theme.get_state_color(&listening_state)),
+                ),
+                Span::raw(format!("  - {}", listening_state)),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "  P2P",
+                    Style::default().fg(theme.get_state_color(&listening_state)),
+                ),
+                Span::raw(" - P2P Peer (only peer delay, e.g. an AVB bridge)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  *", Style::default().fg(theme.text_primary)),
+                Span::raw("  - Local machine (your own host)"),
+            ]),
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "Terminology:",
+                Style::default()
+                    .fg(theme.table_header)
+                    .add_modifier(Modifier::BOLD),
+            )]),
+            Line::from("  This project uses inclusive terminology:"),
+            Line::from("  • Time Transmitter = Master Clock"),
+            Line::from("  • Time Receiver = Slave Clock"),
+            Line::from("  • Primary Time Transmitter (PTT) = Grandmaster Clock"),
+        ]);
+    }
+
+    let title = match app.help_scope {
+        Some(section) => format!("Help - {} (Tab for all keys)", section.title()),
+        None => "Help".to_string(),
+    };
+    let scroll = app.help_scroll.min(help_text.len().saturating_sub(1));
+    let help_paragraph = Paragraph::new(help_text)
+        .style(Style::default().fg(theme.text_primary).bg(theme.background))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme.border_normal)),
+        )
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: true })
+        .scroll((scroll as u16, 0));
+
+    f.render_widget(help_paragraph, area);
+}
+
+fn render_settings(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    const LABEL_WIDTH: usize = 24;
+
+    let mut settings_text = vec![
+        Line::from(vec![Span::styled(
+            "Settings",
+            Style::default()
+                .fg(theme.text_accent)
+                .add_modifier(Modifier::BOLD),
+        )]),
+        Line::from(""),
+    ];
+
+    for (i, field) in SettingsField::ALL.iter().enumerate() {
+        let style = if i == app.settings_selected {
+            Style::default()
+                .bg(theme.selected_row_background)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        settings_text.push(Line::from(vec![
+            Span::styled(
+                format!("  {:width$}", field.label(), width = LABEL_WIDTH),
+                style.fg(theme.text_secondary),
+            ),
+            Span::styled(
+                format!("◀ {} ▶", field.value(&app.config)),
+                style.fg(theme.text_primary),
+            ),
+        ]));
+    }
+
+    settings_text.push(Line::from(""));
+    settings_text.push(Line::from(format!(
+        "  Config file: {}",
+        app.config_path
+            .as_ref()
+            .map_or("none".to_string(), |path| path.display().to_string())
+    )));
+    if let Some(status) = &app.settings_status {
+        settings_text.push(Line::from(Span::styled(
+            format!("  {}", status),
+            Style::default().fg(theme.text_accent),
+        )));
+    }
+    settings_text.push(Line::from(""));
+    settings_text.push(Line::from(
+        "  ↑/↓ select   ←/→ change   w write to config file   Esc/o close",
+    ));
+
+    let settings_paragraph = Paragraph::new(settings_text)
+        .style(Style::default().fg(theme.text_primary).bg(theme.background))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Settings")
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme.border_focused)),
+        )
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(settings_paragraph, area);
+}
+
+/// All announcing transmitters of each domain with their BMCA attributes, in
+/// election order, so it is visible why the winner won
+fn render_gm_matrix(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let pairs = app.ptp_tracker.get_gm_pairs(app.clock());
+    let area = if pairs.is_empty() {
+        area
+    } else {
+        // Four lines per pair, a blank one between them
+        let height = (pairs.len() * 5 + 1) as u16;
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(6), Constraint::Max(height)])
+            .split(area);
+        render_gm_pairs(f, chunks[1], app, &pairs);
+        chunks[0]
+    };
+    let candidates = app.ptp_tracker.get_bmca_candidates();
+    let bmca_profile = app.ptp_tracker.get_bmca_profile();
+
+    let headers = Row::new(vec![
+        Cell::from("#"),
+        Cell::from("Domain"),
+        Cell::from("Clock Identity"),
+        Cell::from("Vendor"),
+        Cell::from("P1"),
+        Cell::from("Class"),
+        Cell::from("Accuracy"),
+        Cell::from("Variance"),
+        Cell::from("P2"),
+        Cell::from("Steps"),
+        Cell::from("Loses on"),
+        Cell::from("Design"),
+    ])
+    .style(
+        Style::default()
+            .fg(theme.table_header)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let mut rows = Vec::new();
+    for ((sdo_id, domain), transmitters) in &candidates {
+        if !rows.is_empty() {
+            rows.push(Row::new(vec![Cell::from("")]));
+        }
+
+        let domain_display = if *sdo_id == 0 {
+            app.config.domain_label(*domain)
+        } else {
+            format_ptp_instance(*sdo_id, *domain)
+        };
+
+        let bmca = bmca_profile.bmca_for(*sdo_id);
+        let gm_design = app.ptp_tracker.get_gm_design((*sdo_id, *domain));
+        for (rank, candidate) in transmitters.iter().enumerate() {
+            let state = candidate.state;
+            // Compared with the candidate ranked right above it
+            let loses_on = rank
+                .checked_sub(1)
+                .and_then(|previous| bmca.deciding_attribute(&transmitters[previous], candidate))
+                .map_or("-", |attribute| attribute.label());
+            let style = if state.is_bmca_winner {
+                Style::default()
+                    .fg(theme.state_transmitter)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.text_primary)
+            };
+            let design = match gm_design {
+                Some(GmDesign::AsDesigned) if state.is_bmca_winner => {
+                    Cell::from("as designed").style(Style::default().fg(theme.confidence_high))
+                }
+                Some(GmDesign::Deviation { .. }) if state.is_bmca_winner => Cell::from("DEVIATION")
+                    .style(
+                        Style::default()
+                            .fg(theme.confidence_low)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                Some(GmDesign::Deviation { expected, .. })
+                    if candidate.clock_identity == expected
+                        || state.ptt_identifier == Some(expected) =>
+                {
+                    Cell::from("expected")
+                }
+                _ => Cell::from(""),
+            };
+
+            rows.push(
+                Row::new(vec![
+                    Cell::from(if state.is_bmca_winner {
+                        "PTT".to_string()
+                    } else {
+                        (rank + 1).to_string()
+                    }),
+                    Cell::from(format!("{} [{}]", domain_display, bmca.name())),
+                    Cell::from(candidate.clock_identity.to_string()),
+                    Cell::from(
+                        candidate
+                            .clock_identity
+                            .extract_vendor_name()
+                            .unwrap_or("-"),
+                    ),
+                    Cell::from(state.priority1.map_or("-".to_string(), |p| p.to_string())),
+                    Cell::from(
+                        state
+                            .clock_class
+                            .map_or("-".to_string(), |c| c.class().to_string()),
+                    ),
+                    Cell::from(format_clock_accuracy(state.clock_accuracy)),
+                    Cell::from(
+                        state
+                            .offset_scaled_log_variance
+                            .map_or("-".to_string(), |v| format!("0x{:04x}", v)),
+                    ),
+                    Cell::from(state.priority2.map_or("-".to_string(), |p| p.to_string())),
+                    Cell::from(
+                        state
+                            .steps_removed
+                            .map_or("-".to_string(), |s| s.to_string()),
+                    ),
+                    Cell::from(loses_on),
+                    design,
+                ])
+                .style(style),
+            );
+        }
+    }
+
+    let block = Block::default()
+        .title("GM Candidates - election order per domain, 'g' to close")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_focused))
+        .style(Style::default().bg(theme.background));
+
+    if rows.is_empty() {
+        let empty = Paragraph::new("No Announce messages seen yet.")
+            .style(Style::default().fg(theme.text_primary).bg(theme.background))
+            .block(block)
+            .alignment(Alignment::Center);
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let widths = [
+        Constraint::Length(4), // Rank
+        Constraint::Length(if app.config.domain_aliases.is_empty() {
+            21
+        } else {
+            27
+        }), // Domain and BMCA rules
+        Constraint::Length(23), // Clock Identity
+        Constraint::Length(16), // Vendor
+        Constraint::Length(4), // Priority1
+        Constraint::Length(5), // Clock Class
+        Constraint::Length(14), // Accuracy
+        Constraint::Length(8), // Variance
+        Constraint::Length(4), // Priority2
+        Constraint::Length(5), // Steps Removed
+        Constraint::Length(10), // Deciding attribute
+        Constraint::Length(11), // Against the expected GM of the config
+    ];
+
+    let table = Table::new(rows, widths)
+        .header(headers)
+        .block(block)
+        .style(Style::default().bg(theme.background));
+
+    f.render_widget(table, area);
+}
+
+/// Primary and backup grandmaster of each domain with a backup: announced
+/// attributes side by side, readiness of the backup and the phase between them
+fn render_gm_pairs(f: &mut Frame, area: Rect, app: &App, pairs: &[GmPairStatus]) {
+    let theme = &app.theme;
+    let label = Style::default().fg(theme.text_secondary);
+    let good = Style::default().fg(theme.confidence_high);
+    let bad = Style::default()
+        .fg(theme.confidence_low)
+        .add_modifier(Modifier::BOLD);
+
+    let mut lines = Vec::new();
+    for pair in pairs {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+
+        let elected = match pair.elected {
+            Some(elected) if elected == pair.primary => Span::styled("primary is PTT", good),
+            Some(elected) if elected == pair.backup => Span::styled("BACKUP IS PTT", bad),
+            Some(elected) => Span::styled(format!("{} is PTT", elected), bad),
+            None => Span::styled("no election yet", label),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("Domain {}", app.config.domain_label(pair.domain)),
+                Style::default()
+                    .fg(theme.text_accent)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("  primary ", label),
+            Span::raw(pair.primary.to_string()),
+            Span::styled("  backup ", label),
+            Span::raw(pair.backup.to_string()),
+            Span::raw("  "),
+            elected,
+        ]));
+
+        // primary / backup of each attribute, mismatches highlighted
+        let mut attributes = vec![Span::raw("  ")];
+        for attribute in &pair.attributes {
+            attributes.push(Span::styled(format!("{} ", attribute.name), label));
+            let values = format!("{}/{}   ", attribute.primary, attribute.backup);
+            attributes.push(if attribute.is_mismatch() {
+                Span::styled(values, bad)
+            } else {
+                Span::raw(values)
+            });
+        }
+        lines.push(Line::from(attributes));
+
+        lines.push(Line::from(vec![
+            Span::styled("  Backup: ", label),
+            if pair.backup_problems.is_empty() {
+                Span::styled("ready to take over", good)
+            } else {
+                Span::styled(
+                    format!("NOT READY - {}", pair.backup_problems.join(", ")),
+                    bad,
+                )
+            },
+        ]));
+
+        let phase = match pair.phase {
+            Some(phase) => {
+                let drift = phase.baseline_ns().zip(phase.current_ns()).map_or(
+                    String::new(),
+                    |(baseline, current)| {
+                        format!(", {:+.1} µs since start", (current - baseline) / 1000.0)
+                    },
+                );
+                let text = format!(
+                    "{:+.1} µs ±{:.1} µs{}",
+                    phase.current_ns().unwrap_or_default() / 1000.0,
+                    phase.jitter_ns().unwrap_or_default() / 1000.0,
+                    drift
+                );
+                if phase.diverged {
+                    Span::styled(format!("{} ⚠ drifting", text), bad)
+                } else {
+                    Span::raw(text)
+                }
+            }
+            None if app.ptp_tracker.is_lite() => Span::styled("not measured in lite mode", label),
+            None => Span::styled("waiting for Syncs of both grandmasters", label),
+        };
+        lines.push(Line::from(vec![
+            Span::styled("  Phase primary - backup: ", label),
+            phase,
+        ]));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .style(Style::default().fg(theme.text_primary).bg(theme.background))
+        .block(
+            Block::default()
+                .title("Redundant GM Pairs - primary/backup")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border_focused))
+                .style(Style::default().bg(theme.background)),
+        );
+    f.render_widget(paragraph, area);
+}
+
+/// Hosts missing, new and changed since the baseline session
+fn render_baseline(f: &mut Frame, area: Rect, app: &App, baseline: &Baseline) {
+    let theme = &app.theme;
+    let hosts = app.ptp_tracker.get_hosts();
+    let missing = baseline.missing(&hosts);
+
+    let headers = Row::new(vec![
+        Cell::from("Status"),
+        Cell::from("Clock Identity"),
+        Cell::from("Details"),
+    ])
+    .style(
+        Style::default()
+            .fg(theme.table_header)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let row = |label: &'static str, color, clock_identity: String, detail: String| {
+        Row::new(vec![
+            Cell::from(label).style(Style::default().fg(color)),
+            Cell::from(clock_identity),
+            Cell::from(detail),
+        ])
+        .style(Style::default().fg(theme.text_primary))
+    };
+    let mut rows: Vec<Row> = missing
+        .iter()
+        .map(|(clock_identity, snapshot)| {
+            row(
+                "missing",
+                theme.confidence_low,
+                clock_identity.to_string(),
+                format!("was {}", snapshot),
+            )
+        })
+        .collect();
+    let mut changed = Vec::new();
+    for host in hosts.iter().filter(|host| !app.ptp_tracker.is_muted(host)) {
+        match baseline.status(host) {
+            BaselineStatus::New => rows.push(row(
+                "new",
+                theme.confidence_medium,
+                host.clock_identity.to_string(),
+                HostSnapshot::from_host(host).to_string(),
+            )),
+            BaselineStatus::Changed(changes) => changed.push(row(
+                "changed",
+                theme.confidence_low,
+                host.clock_identity.to_string(),
+                changes.join(", "),
+            )),
+            BaselineStatus::Unchanged => {}
+        }
+    }
+    rows.extend(changed);
+
+    let saved = baseline
+        .snapshot
+        .saved
+        .map(|saved| {
+            chrono::DateTime::<chrono::Local>::from(saved)
+                .format(" of %Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default();
+    let block = Block::default()
+        .title(format!(
+            "Baseline - {} host(s) differ from {}{}, 'v' to close",
+            rows.len(),
+            baseline.path.display(),
+            saved
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_focused))
+        .style(Style::default().bg(theme.background));
+
+    let widths = [
+        Constraint::Length(8),
+        Constraint::Length(24),
+        Constraint::Min(20),
+    ];
+    let table = Table::new(rows, widths)
+        .header(headers)
+        .block(block)
+        .style(Style::default().bg(theme.background));
+
+    f.render_widget(table, area);
+}
+
+/// Every expectation of the golden file with how the network meets it,
+/// deviations first
+fn render_compliance(f: &mut Frame, area: Rect, app: &App, golden: &Golden) {
+    let theme = &app.theme;
+    let mut checks = golden.diff(&app.ptp_tracker.get_hosts());
+    checks.sort_by_key(|check| check.status != CheckStatus::Fail);
+    let deviations = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .count();
+
+    let headers = Row::new(vec![
+        Cell::from("Status"),
+        Cell::from("Expectation"),
+        Cell::from("Observed"),
+    ])
+    .style(
+        Style::default()
+            .fg(theme.table_header)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let rows: Vec<Row> = checks
+        .into_iter()
+        .map(|check| {
+            let (label, color) = match check.status {
+                CheckStatus::Pass => ("as designed", theme.confidence_high),
+                CheckStatus::Warn => ("warning", theme.confidence_medium),
+                CheckStatus::Fail => ("DEVIATION", theme.confidence_low),
+                CheckStatus::Skip => ("skipped", theme.text_secondary),
+            };
+            Row::new(vec![
+                Cell::from(label).style(Style::default().fg(color)),
+                Cell::from(check.name),
+                Cell::from(check.detail),
+            ])
+            .style(Style::default().fg(theme.text_primary))
+        })
+        .collect();
+
+    let block = Block::default()
+        .title(format!(
+            "Compliance - {} deviation(s) from the golden file, 'C' to close",
+            deviations
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_focused))
+        .style(Style::default().bg(theme.background));
+
+    let widths = [
+        Constraint::Length(12),
+        Constraint::Length(40),
+        Constraint::Min(20),
+    ];
+    let table = Table::new(rows, widths)
+        .header(headers)
+        .block(block)
+        .style(Style::default().bg(theme.background));
+
+    f.render_widget(table, area);
+}
+
+/// The GM candidates with hypothetical priority1, clockClass and priority2,
+/// re-elected on every change
+fn render_what_if(f: &mut Frame, area: Rect, app: &App, what_if: &WhatIf) {
+    let theme = &app.theme;
+
+    let block = Block::default()
+        .title("BMCA What-If - snapshot, not applied to the live view, 'W' to close")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_focused))
+        .style(Style::default().bg(theme.background));
+
+    if what_if.is_empty() {
+        let empty = Paragraph::new("No Announce messages seen yet.")
+            .style(Style::default().fg(theme.text_primary).bg(theme.background))
+            .block(block)
+            .alignment(Alignment::Center);
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let areas = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(4), Constraint::Length(4)])
+        .split(block.inner(area));
+    f.render_widget(block, area);
+
+    let headers = Row::new(vec![
+        Cell::from("#"),
+        Cell::from("Live"),
+        Cell::from("Domain"),
+        Cell::from("Clock Identity"),
+        Cell::from("P1"),
+        Cell::from("Class"),
+        Cell::from("P2"),
+        Cell::from("Accuracy"),
+        Cell::from("Variance"),
+        Cell::from("Loses on"),
+    ])
+    .style(
+        Style::default()
+            .fg(theme.table_header)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let mut rows = Vec::new();
+    for (index, candidate) in what_if.candidates.iter().enumerate() {
+        let (sdo_id, domain) = candidate.instance;
+        if index > 0 && what_if.candidates[index - 1].instance != candidate.instance {
+            rows.push(Row::new(vec![Cell::from("")]));
+        }
+
+        let rank = what_if.rank(index);
+        let domain_display = if sdo_id == 0 {
+            app.config.domain_label(domain)
+        } else {
+            format_ptp_instance(sdo_id, domain)
+        };
+        let row_style = if rank == 0 {
+            Style::default()
+                .fg(theme.state_transmitter)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.text_primary)
+        };
+
+        let mut cells = vec![
+            Cell::from(if rank == 0 {
+                "PTT".to_string()
+            } else {
+                (rank + 1).to_string()
+            }),
+            Cell::from(if candidate.live_rank == 0 {
+                "PTT".to_string()
+            } else {
+                (candidate.live_rank + 1).to_string()
+            }),
+            Cell::from(domain_display),
+            Cell::from(candidate.clock_identity.to_string()),
+        ];
+        for (field, &attribute) in EDITABLE_ATTRIBUTES.iter().enumerate() {
+            let mut style = row_style;
+            if candidate.is_modified(attribute) {
+                style = style
+                    .fg(theme.text_accent)
+                    .add_modifier(Modifier::UNDERLINED);
+            }
+            if index == what_if.selected && field == what_if.field {
+                style = style.add_modifier(Modifier::REVERSED);
             }
+            cells.push(
+                Cell::from(
+                    candidate
+                        .get(attribute)
+                        .map_or("-".to_string(), |value| value.to_string()),
+                )
+                .style(style),
+            );
+        }
+        cells.push(Cell::from(format_clock_accuracy(
+            candidate.state.clock_accuracy,
+        )));
+        cells.push(Cell::from(
+            candidate
+                .state
+                .offset_scaled_log_variance
+                .map_or("-".to_string(), |v| format!("0x{:04x}", v)),
+        ));
+        cells.push(Cell::from(
+            what_if
+                .loses_on(index)
+                .map_or("-", |attribute| attribute.label()),
+        ));
 
-            details_text.extend(vec![
-                create_aligned_field_with_vendor(
-                    "State: ".to_string(),
-                    host.state.to_string(),
-                    String::new(),
-                    LABEL_WIDTH,
-                    theme,
-                    theme.get_state_color(&host.state),
-                ),
-                create_aligned_field(
-                    "PTP Version: ".to_string(),
-                    host.last_version
-                        .map_or("N/A".to_string(), |v| v.to_string()),
-                    LABEL_WIDTH,
-                    theme,
-                ),
-                create_aligned_field(
-                    "Domain: ".to_string(),
-                    host.domain_number
-                        .map(|d| d.to_string())
-                        .unwrap_or("N/A".to_string()),
-                    LABEL_WIDTH,
-                    theme,
-                ),
-                create_aligned_field(
-                    "Last Correction: ".to_string(),
-                    host.last_correction_field
-                        .map_or("N/A".to_string(), |v| format!("{} ({})", v, v.value)),
-                    LABEL_WIDTH,
-                    theme,
-                ),
-                create_aligned_field(
-                    "Last Seen: ".to_string(),
+        let mut row = Row::new(cells).style(row_style);
+        if index == what_if.selected {
+            row = row.style(row_style.bg(theme.selected_row_background));
+        }
+        rows.push(row);
+    }
+
+    let widths = [
+        Constraint::Length(4), // What-if rank
+        Constraint::Length(4), // Live rank
+        Constraint::Length(if app.config.domain_aliases.is_empty() {
+            10
+        } else {
+            16
+        }), // Domain
+        Constraint::Length(23), // Clock Identity
+        Constraint::Length(4), // Priority1
+        Constraint::Length(5), // Clock Class
+        Constraint::Length(4), // Priority2
+        Constraint::Length(14), // Accuracy
+        Constraint::Length(8), // Variance
+        Constraint::Length(10), // Deciding attribute
+    ];
+    f.render_widget(
+        Table::new(rows, widths)
+            .header(headers)
+            .style(Style::default().bg(theme.background)),
+        areas[0],
+    );
+
+    let changes = what_if.changed_winners();
+    let mut summary = if changes.is_empty() {
+        vec![Line::from("  The PTT of every domain stays the same.")]
+    } else {
+        changes
+            .iter()
+            .map(|((sdo_id, domain), live, winner)| {
+                Line::from(Span::styled(
                     format!(
-                        "{:.1}s ago",
-                        host.time_since_last_seen(app.get_reference_timestamp())
-                            .as_secs_f64()
+                        "  {}: {} would take over from {}",
+                        format_ptp_instance(*sdo_id, *domain),
+                        winner,
+                        live
                     ),
-                    LABEL_WIDTH,
-                    theme,
+                    Style::default()
+                        .fg(theme.text_accent)
+                        .add_modifier(Modifier::BOLD),
+                ))
+            })
+            .collect()
+    };
+    summary.push(Line::from(
+        "  ↑/↓ select   ←/→ field   +/- change (PgUp/PgDn by 10)   r reset   R reset all",
+    ));
+    f.render_widget(
+        Paragraph::new(summary)
+            .style(Style::default().fg(theme.text_primary).bg(theme.background))
+            .wrap(Wrap { trim: false }),
+        areas[1],
+    );
+}
+
+/// Source → destination ports and message counts of one class, e.g. "319→319 ×30"
+fn format_port_flows(flows: &[(u16, u16, u64)]) -> String {
+    if flows.is_empty() {
+        return "none".to_string();
+    }
+    flows
+        .iter()
+        .map(|(source, dest, count)| format!("{}→{} ×{}", source, dest, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Event and general ports each host sends on, hosts seen on one only first
+fn render_port_matrix(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let hosts = app.ptp_tracker.get_hosts();
+    let layer2_hosts = hosts
+        .iter()
+        .filter(|host| host.port_usage.is_empty())
+        .count();
+    let mut udp_hosts: Vec<_> = hosts
+        .into_iter()
+        .filter(|host| !host.port_usage.is_empty())
+        .map(|host| (host, host.port_usage.problems()))
+        .collect();
+    udp_hosts.sort_by_key(|(_, problems)| problems.is_empty());
+    let flagged = udp_hosts
+        .iter()
+        .filter(|(_, problems)| !problems.is_empty())
+        .count();
+
+    let headers = Row::new(vec![
+        Cell::from("State"),
+        Cell::from("Clock Identity"),
+        Cell::from("IP Address"),
+        Cell::from("Event sent"),
+        Cell::from("General sent"),
+        Cell::from("Delay_Resp in"),
+        Cell::from("Port pair"),
+    ])
+    .style(
+        Style::default()
+            .fg(theme.table_header)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let rows: Vec<Row> = udp_hosts
+        .iter()
+        .map(|(host, problems)| {
+            let usage = &host.port_usage;
+            let state = match host.state {
+                PtpHostState::TimeTransmitter(_) => "PTT",
+                PtpHostState::TimeReceiver(_) => "PTR",
+                PtpHostState::Listening => "L",
+            };
+            let delay_resps = if usage.sends_delay_reqs() {
+                format_port_flows(&usage.delay_resp_flows())
+            } else {
+                "-".to_string()
+            };
+            let (status, color) = if problems.is_empty() {
+                ("ok".to_string(), theme.confidence_high)
+            } else {
+                (
+                    problems
+                        .iter()
+                        .map(|problem| problem.to_string())
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                    theme.confidence_low,
+                )
+            };
+            Row::new(vec![
+                Cell::from(state).style(Style::default().fg(theme.get_state_color(&host.state))),
+                Cell::from(host.clock_identity.to_string()),
+                Cell::from(
+                    host.get_primary_ip()
+                        .map_or("-".to_string(), |ip| ip.to_string()),
                 ),
-            ]);
+                Cell::from(format_port_flows(&usage.flows(PortClass::Event))),
+                Cell::from(format_port_flows(&usage.flows(PortClass::General))),
+                Cell::from(delay_resps),
+                Cell::from(status).style(Style::default().fg(color)),
+            ])
+            .style(Style::default().fg(theme.text_primary))
+        })
+        .collect();
 
-            match &host.state {
-                PtpHostState::Listening => {}
-                PtpHostState::TimeTransmitter(s) => {
-                    details_text.extend(vec![
-                        Line::from(""),
-                        Line::from(vec![Span::styled(
-                            "Time Transmitter:",
-                            Style::default()
-                                .fg(theme.text_accent)
-                                .add_modifier(Modifier::BOLD),
-                        )]),
-                        create_aligned_field(
-                            "Priority 1: ".to_string(),
-                            s.priority1.map_or("N/A".to_string(), |p| p.to_string()),
-                            LABEL_WIDTH,
-                            theme,
-                        ),
-                        create_aligned_field(
-                            "Priority 2: ".to_string(),
-                            s.priority2.map_or("N/A".to_string(), |p| p.to_string()),
-                            LABEL_WIDTH,
-                            theme,
-                        ),
-                        create_aligned_field(
-                            "Clock Class: ".to_string(),
-                            format_clock_class(s.clock_class),
-                            LABEL_WIDTH,
-                            theme,
-                        ),
-                        create_aligned_field(
-                            "Accuracy: ".to_string(),
-                            format_clock_accuracy(s.clock_accuracy),
-                            LABEL_WIDTH,
-                            theme,
-                        ),
-                        create_aligned_field(
-                            "Steps Removed: ".to_string(),
-                            s.steps_removed
-                                .map(|d| d.to_string())
-                                .unwrap_or("N/A".to_string()),
-                            LABEL_WIDTH,
-                            theme,
-                        ),
-                        create_aligned_field(
-                            "Log Variance: ".to_string(),
-                            s.offset_scaled_log_variance
-                                .map_or("N/A".to_string(), |v| v.to_string()),
-                            LABEL_WIDTH,
-                            theme,
-                        ),
-                        create_aligned_field(
-                            "Primary Identity: ".to_string(),
-                            s.ptt_identifier
-                                .map_or("N/A".to_string(), |p| p.to_string()),
-                            LABEL_WIDTH,
-                            theme,
-                        ),
-                        create_aligned_field(
-                            "UTC Offset: ".to_string(),
-                            s.current_utc_offset
-                                .map_or("N/A".to_string(), |o| o.to_string()),
-                            LABEL_WIDTH,
-                            theme,
-                        ),
-                    ]);
+    let block = Block::default()
+        .title(format!(
+            "Port Matrix - event (319) and general (320) ports, {} host(s) on one only, {} layer 2 host(s) not shown, 'O' to close",
+            flagged, layer2_hosts
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_focused))
+        .style(Style::default().bg(theme.background));
+
+    if rows.is_empty() {
+        let empty = Paragraph::new("No PTP over UDP seen yet.")
+            .style(Style::default().fg(theme.text_primary).bg(theme.background))
+            .block(block)
+            .alignment(Alignment::Center);
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let widths = [
+        Constraint::Length(6),  // State
+        Constraint::Length(24), // Clock identity
+        Constraint::Length(16), // IP address
+        Constraint::Length(22), // Event sent
+        Constraint::Length(22), // General sent
+        Constraint::Length(16), // Delay_Resp in
+        Constraint::Min(30),    // Port pair
+    ];
+    let table = Table::new(rows, widths)
+        .header(headers)
+        .block(block)
+        .style(Style::default().bg(theme.background));
+
+    f.render_widget(table, area);
+}
+
+fn render_interfaces(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let interfaces = app.ptp_tracker.packet_source.interfaces();
+
+    // Capture workers below the interfaces, when there are any
+    let workers = app.ptp_tracker.packet_source.capture_workers();
+    let area = if workers.is_empty() {
+        area
+    } else {
+        let height = (workers.len() as u16 + 3).min(area.height / 2);
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(5), Constraint::Length(height)])
+            .split(area);
+        render_capture_workers(f, areas[1], app, &workers);
+        areas[0]
+    };
+
+    let headers = Row::new(vec![
+        Cell::from("Interface"),
+        Cell::from("IPv4"),
+        Cell::from("Bursts"),
+        Cell::from("PHC"),
+        Cell::from("PHC Time"),
+        Cell::from("PHC - System"),
+        Cell::from("Drift"),
+    ])
+    .style(
+        Style::default()
+            .fg(theme.table_header)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    // The interfaces of the hardware clocks, read on Unix only
+    #[cfg(unix)]
+    let names: Vec<&str> = app
+        .interface_clocks
+        .clocks()
+        .iter()
+        .map(|clock| clock.interface.as_str())
+        .collect();
+    #[cfg(not(unix))]
+    let names: Vec<&str> = interfaces.iter().map(|(name, _)| name.as_str()).collect();
+
+    let rows: Vec<Row> = names
+        .into_iter()
+        .map(|interface| {
+            let ipv4 = interfaces
+                .iter()
+                .find(|(name, _)| name == interface)
+                .and_then(|(_, ip)| *ip)
+                .map_or("-".to_string(), |ip| ip.to_string());
+
+            let bursts = app
+                .ptp_tracker
+                .get_interface_bursts(interface)
+                .map_or("none".to_string(), |bursts| bursts.summary());
+
+            let mut cells = vec![
+                Cell::from(interface.to_string()),
+                Cell::from(ipv4),
+                Cell::from(bursts),
+            ];
+            let style = push_phc_cells(app, interface, &mut cells);
+            Row::new(cells).style(style)
+        })
+        .collect();
+
+    let block = Block::default()
+        .title("Interfaces - PTP hardware clocks and microbursts of the capture interfaces, 'I' to close")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_focused))
+        .style(Style::default().bg(theme.background));
 
-                    details_text.push(create_aligned_field(
-                        "Sync TS: ".to_string(),
-                        format_timestamp(s.last_sync_origin_timestamp),
-                        LABEL_WIDTH,
-                        theme,
-                    ));
+    if rows.is_empty() {
+        let empty = Paragraph::new("No capture interfaces, packets are read from a pcap file.")
+            .style(Style::default().fg(theme.text_primary).bg(theme.background))
+            .block(block)
+            .alignment(Alignment::Center);
+        f.render_widget(empty, area);
+        return;
+    }
 
-                    if let Some(ts) = s.last_sync_origin_timestamp {
-                        for (k, v) in ts.format_common_samplerates("→ samples").iter() {
-                            details_text.push(create_aligned_field(
-                                format!("{}:", k),
-                                v.to_string(),
-                                LABEL_WIDTH,
-                                theme,
-                            ));
-                        }
-                    }
+    let widths = [
+        Constraint::Length(16), // Interface
+        Constraint::Length(16), // IPv4
+        Constraint::Length(20), // Bursts
+        Constraint::Length(12), // PHC device
+        Constraint::Length(24), // PHC time
+        Constraint::Length(16), // PHC - system
+        Constraint::Min(12),    // Drift
+    ];
 
-                    details_text.push(create_aligned_field(
-                        "Follow-Up TS: ".to_string(),
-                        format_timestamp(s.last_followup_origin_timestamp),
-                        LABEL_WIDTH,
-                        theme,
-                    ));
+    let table = Table::new(rows, widths)
+        .header(headers)
+        .block(block)
+        .style(Style::default().bg(theme.background));
 
-                    if let Some(ts) = s.last_followup_origin_timestamp {
-                        for (k, v) in ts.format_common_samplerates("→ samples").iter() {
-                            details_text.push(create_aligned_field(
-                                format!("{}:", k),
-                                v.to_string(),
-                                LABEL_WIDTH,
-                                theme,
-                            ));
-                        }
-                    }
-                }
-                PtpHostState::TimeReceiver(s) => {
-                    details_text.extend(vec![
-                        Line::from(""),
-                        Line::from(vec![Span::styled(
-                            "Time Receiver:",
-                            Style::default()
-                                .fg(theme.text_accent)
-                                .add_modifier(Modifier::BOLD),
-                        )]),
-                        create_aligned_field_with_vendor(
-                            "Selected Transmitter: ".to_string(),
-                            match s.selected_transmitter_identity {
-                                Some(identity) => identity.to_string(),
-                                None => "None".to_string(),
-                            },
-                            s.selected_transmitter_identity
-                                .and_then(|id| id.extract_vendor_name())
-                                .map(|vendor| format!(" ({})", vendor))
-                                .unwrap_or_default(),
-                            LABEL_WIDTH,
-                            theme,
-                            theme.get_confidence_color(s.selected_transmitter_confidence),
-                        ),
-                        create_aligned_field(
-                            "Last E2E Delay TS: ".to_string(),
-                            format_timestamp(s.last_delay_response_origin_timestamp),
-                            LABEL_WIDTH,
-                            theme,
-                        ),
-                        create_aligned_field(
-                            "Last P2P Delay TS: ".to_string(),
-                            format_timestamp(s.last_pdelay_response_origin_timestamp),
-                            LABEL_WIDTH,
-                            theme,
-                        ),
-                        create_aligned_field(
-                            "Last P2P Delay FU TS: ".to_string(),
-                            format_timestamp(s.last_pdelay_follow_up_timestamp),
-                            LABEL_WIDTH,
-                            theme,
-                        ),
-                    ]);
-                }
-            }
+    f.render_widget(table, area);
+}
 
-            details_text.extend(vec![
-                Line::from(""),
-                Line::from(vec![Span::styled(
-                    "Message Counts:",
-                    Style::default()
-                        .fg(theme.text_accent)
-                        .add_modifier(Modifier::BOLD),
-                )]),
-                create_aligned_field(
-                    "Announce: ".to_string(),
-                    host.announce_count.to_string(),
-                    LABEL_WIDTH,
-                    theme,
+/// PHC cells of an interface in the interfaces view, and the style of its row
+#[cfg(unix)]
+fn push_phc_cells(app: &App, interface: &str, cells: &mut Vec<Cell>) -> Style {
+    let theme = &app.theme;
+    let Some(clock) = app
+        .interface_clocks
+        .clocks()
+        .iter()
+        .find(|clock| clock.interface == interface)
+    else {
+        cells.push(Cell::from("none, no HW timestamping"));
+        return Style::default().fg(theme.confidence_low);
+    };
+    match (&clock.phc, &clock.status) {
+        (None, _) => {
+            cells.push(Cell::from("none, no HW timestamping"));
+            Style::default().fg(theme.confidence_low)
+        }
+        (Some(device), Some(Ok(status))) => {
+            cells.extend([
+                Cell::from(device.display().to_string()),
+                Cell::from(
+                    chrono::DateTime::<chrono::Utc>::from(status.phc_time)
+                        .format("%Y-%m-%d %H:%M:%S%.3f")
+                        .to_string(),
                 ),
-                create_aligned_field(
-                    "Sync/FU: ".to_string(),
-                    format!("{}/{}", host.sync_count, host.follow_up_count),
-                    LABEL_WIDTH,
-                    theme,
+                Cell::from(format!("{:+.6} s", status.sys_offset_ns as f64 / 1e9)),
+                Cell::from(
+                    clock
+                        .drift_ppm
+                        .map_or("-".to_string(), |drift| format!("{:+.3} ppm", drift)),
                 ),
-                create_aligned_field(
-                    "Delay Req/Resp: ".to_string(),
-                    format!("{}/{}", host.delay_req_count, host.delay_resp_count),
-                    LABEL_WIDTH,
-                    theme,
+            ]);
+            Style::default().fg(theme.text_primary)
+        }
+        (Some(device), Some(Err(e))) => {
+            cells.push(Cell::from(device.display().to_string()));
+            cells.push(Cell::from(e.clone()));
+            Style::default().fg(theme.confidence_medium)
+        }
+        (Some(device), None) => {
+            cells.push(Cell::from(device.display().to_string()));
+            Style::default().fg(theme.text_primary)
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn push_phc_cells(app: &App, _interface: &str, cells: &mut Vec<Cell>) -> Style {
+    cells.push(Cell::from("not read, PHCs are only supported on Linux"));
+    Style::default().fg(app.theme.confidence_low)
+}
+
+fn render_capture_workers(f: &mut Frame, area: Rect, app: &App, workers: &[WorkerStats]) {
+    let theme = &app.theme;
+
+    let headers = Row::new(vec![
+        Cell::from("Interface"),
+        Cell::from("Worker"),
+        Cell::from("CPU"),
+        Cell::from("Frames"),
+        Cell::from("Drops"),
+        Cell::from("Dropped"),
+    ])
+    .style(
+        Style::default()
+            .fg(theme.table_header)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let rows: Vec<Row> = workers
+        .iter()
+        .map(|stats| {
+            // The kernel counts dropped frames among those it received
+            let dropped = if stats.packets > 0 {
+                format!("{:.3}%", stats.drops as f64 * 100.0 / stats.packets as f64)
+            } else {
+                "-".to_string()
+            };
+            let style = if stats.drops > 0 {
+                Style::default().fg(theme.confidence_low)
+            } else {
+                Style::default().fg(theme.text_primary)
+            };
+            Row::new(vec![
+                Cell::from(stats.interface.clone()),
+                Cell::from(stats.worker.to_string()),
+                Cell::from(stats.cpu.map_or("any".to_string(), |cpu| cpu.to_string())),
+                Cell::from(stats.packets.to_string()),
+                Cell::from(stats.drops.to_string()),
+                Cell::from(dropped),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let block = Block::default()
+        .title("Capture workers - frames of any protocol read and dropped by the kernel per worker socket")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_focused))
+        .style(Style::default().bg(theme.background));
+
+    let widths = [
+        Constraint::Length(16), // Interface
+        Constraint::Length(8),  // Worker
+        Constraint::Length(6),  // CPU
+        Constraint::Length(14), // Frames
+        Constraint::Length(12), // Drops
+        Constraint::Min(10),    // Dropped
+    ];
+
+    let table = Table::new(rows, widths)
+        .header(headers)
+        .block(block)
+        .style(Style::default().bg(theme.background));
+
+    f.render_widget(table, area);
+}
+
+fn render_log_pane(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let visible = area.height.saturating_sub(2) as usize;
+
+    let lines: Vec<Line> = app
+        .log_tail
+        .last(visible)
+        .into_iter()
+        .map(|line| {
+            let level_color = match line.level {
+                Level::ERROR => theme.confidence_low,
+                Level::WARN => theme.confidence_medium,
+                Level::INFO => theme.text_primary,
+                _ => theme.text_secondary,
+            };
+            Line::from(vec![
+                Span::styled(
+                    chrono::DateTime::<chrono::Local>::from(line.timestamp)
+                        .format("%H:%M:%S%.3f ")
+                        .to_string(),
+                    Style::default().fg(theme.text_secondary),
                 ),
-                create_aligned_field(
-                    "PDelay Req/Resp/FU: ".to_string(),
-                    format!(
-                        "{}/{}/{}",
-                        host.pdelay_req_count,
-                        host.pdelay_resp_count,
-                        host.pdelay_resp_follow_up_count
-                    ),
-                    LABEL_WIDTH,
-                    theme,
+                Span::styled(
+                    format!("{:<5} ", line.level),
+                    Style::default().fg(level_color),
                 ),
-                create_aligned_field(
-                    "Management/Signaling: ".to_string(),
+                Span::styled(
                     format!(
-                        "{}/{}",
-                        host.management_message_count, host.signaling_message_count
+                        "{}: ",
+                        line.target
+                            .strip_prefix("ptp_trace::")
+                            .unwrap_or(&line.target)
                     ),
-                    LABEL_WIDTH,
-                    theme,
+                    Style::default().fg(theme.text_accent),
                 ),
-            ]);
+                Span::styled(line.message, Style::default().fg(theme.text_primary)),
+            ])
+        })
+        .collect();
 
-            details_text
-        } else {
-            vec![
-                Line::from("No host found with selected ID"),
-                Line::from(""),
-                Line::from("This may indicate a synchronization issue"),
-            ]
-        }
-    } else {
-        vec![
-            Line::from("No host selected"),
-            Line::from(""),
-            Line::from("Use ↑/↓ to select a host"),
-            Line::from("Press Tab to switch between views"),
-        ]
-    };
+    let block = Block::default()
+        .title("Log - 'd' to hide, --log-level sets the levels")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_normal))
+        .style(Style::default().bg(theme.background));
 
-    // Update scroll position - need to do this after we have the content but before we use it
-    let total_lines = details_text.len();
-    let max_scroll = total_lines.saturating_sub(content_height);
-    app.host_details_visible_height = content_height;
-    app.host_details_scroll_offset = app.host_details_scroll_offset.min(max_scroll);
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .style(Style::default().bg(theme.background));
+    f.render_widget(paragraph, area);
+}
 
-    // Create scrolled content
-    let scrolled_text = if app.host_details_scroll_offset < details_text.len() {
-        details_text
-            .iter()
-            .skip(app.host_details_scroll_offset)
-            .take(content_height)
-            .cloned()
-            .collect::<Vec<_>>()
+/// Stage time with a unit that keeps a few significant digits
+fn format_stage_time(duration: Duration) -> String {
+    let micros = duration.as_secs_f64() * 1e6;
+    if micros < 1000.0 {
+        format!("{:.1} µs", micros)
+    } else if micros < 1e6 {
+        format!("{:.2} ms", micros / 1000.0)
     } else {
-        vec![]
-    };
+        format!("{:.3} s", micros / 1e6)
+    }
+}
 
+fn render_profiling_pane(f: &mut Frame, area: Rect, app: &App) {
     let theme = &app.theme;
-    let border_style = if matches!(app.active_view, crate::app::ActiveView::HostDetails) {
-        Style::default().fg(theme.border_focused)
+    let profiler = &app.profiler;
+    let label = |text: String| Span::styled(text, Style::default().fg(theme.text_secondary));
+    let value = |text: String| Span::styled(text, Style::default().fg(theme.text_primary));
+
+    let average_total: Duration = Stage::ALL.iter().map(|s| profiler.average(*s)).sum();
+    let mut lines = vec![Line::from(label(format!(
+        "{:<10}{:>12}{:>12}{:>12}{:>8}",
+        "Stage", "Last", "Average", "Max", "Share"
+    )))];
+    for stage in Stage::ALL {
+        let last = profiler.last().map_or("-".to_string(), |cycle| {
+            format_stage_time(cycle.times.get(stage))
+        });
+        let share = if average_total.is_zero() {
+            "-".to_string()
+        } else {
+            format!(
+                "{:.0}%",
+                profiler.average(stage).as_secs_f64() / average_total.as_secs_f64() * 100.0
+            )
+        };
+        lines.push(Line::from(vec![
+            label(format!("{:<10}", stage.label())),
+            value(format!(
+                "{:>12}{:>12}{:>12}{:>8}",
+                last,
+                format_stage_time(profiler.average(stage)),
+                format_stage_time(profiler.max(stage)),
+                share
+            )),
+        ]));
+    }
+
+    // Busy most of the time or packets left waiting: the pipeline is falling behind
+    let load = profiler.load();
+    let backlogged = profiler.backlogged_cycles();
+    let load_color = if load > 0.8 || backlogged > 0 {
+        theme.confidence_low
+    } else if load > 0.5 {
+        theme.confidence_medium
     } else {
-        Style::default().fg(theme.border_normal)
+        theme.confidence_high
     };
+    let mut packets = vec![
+        label("Packets ".to_string()),
+        value(format!("{:.0}/s", profiler.packet_rate())),
+        label("   Parse ".to_string()),
+        value(profiler.parse_latency().map_or("-".to_string(), |latency| {
+            format!("{}/msg", format_stage_time(latency))
+        })),
+        label("   Load ".to_string()),
+        Span::styled(
+            format!("{:.1}%", load * 100.0),
+            Style::default().fg(load_color),
+        ),
+        label("   Backlogged ".to_string()),
+        Span::styled(
+            format!("{}/{} cycles", backlogged, profiler.cycle_count()),
+            Style::default().fg(if backlogged > 0 {
+                theme.confidence_low
+            } else {
+                theme.text_primary
+            }),
+        ),
+    ];
+    // A generator held back by the tracker falls short of its target
+    if let Some(stats) = app.synthetic_stats() {
+        packets.extend([
+            label("   Generated ".to_string()),
+            value(format!(
+                "{:.0}/s of {}/s, {} hosts",
+                stats.generated_rate(),
+                stats.rate,
+                stats.hosts
+            )),
+        ]);
+    }
+    lines.push(Line::from(packets));
+
+    // Parsed packets are shared between hosts, count each of them once
+    let memory = app.ptp_tracker.packet_memory();
+    lines.push(Line::from(vec![
+        label("Memory  ".to_string()),
+        value(format!(
+            "{} packets, {} references",
+            memory.unique_packets(),
+            memory.references
+        )),
+        label("   Raw ".to_string()),
+        value(format!(
+            "{} frames, {:.1} KiB",
+            memory.unique_raw_packets(),
+            memory.bytes as f64 / 1024.0
+        )),
+    ]));
 
-    let details_paragraph = Paragraph::new(scrolled_text)
-        .style(Style::default().fg(theme.text_primary).bg(theme.background))
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Host Details")
-                .border_type(BorderType::Rounded)
-                .border_style(border_style),
-        )
-        .wrap(Wrap { trim: true });
-
-    f.render_widget(details_paragraph, area);
+    let block = Block::default()
+        .title(format!(
+            "Profiling - time per update cycle over the last {} cycles, 'Z' to hide",
+            PROFILE_CYCLES
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_normal))
+        .style(Style::default().bg(theme.background));
 
-    // Render scrollbar if needed
-    render_scrollbar(
-        f,
-        area,
-        details_text.len(),
-        app.host_details_scroll_offset,
-        content_height,
-        theme,
-    );
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .style(Style::default().bg(theme.background));
+    f.render_widget(paragraph, area);
 }
 
-fn render_help(f: &mut Frame, area: Rect, app: &App) {
+fn render_failover_drill(f: &mut Frame, area: Rect, app: &App) {
     let theme = &app.theme;
+    let Some(drill) = app.ptp_tracker.get_failover_drill() else {
+        return;
+    };
 
-    let time_transmitter_state =
-        PtpHostState::TimeTransmitter(crate::ptp::PtpHostStateTimeTransmitter::default());
-    let time_receiver_state =
-        PtpHostState::TimeReceiver(crate::ptp::PtpHostStateTimeReceiver::default());
-    let listening_state = PtpHostState::Listening;
-
-    let mut help_text = vec![
-        Line::from(vec![Span::styled(
-            "PTP Network Tracer Help",
+    let label = |text: &str| {
+        Span::styled(
+            format!("{:<10}", text),
+            Style::default().fg(theme.text_secondary),
+        )
+    };
+    let pending = |text: &str| {
+        Span::styled(
+            text.to_string(),
             Style::default()
                 .fg(theme.text_accent)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Navigation:",
-            Style::default()
-                .fg(theme.table_header)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  Tab        - Cycle: Host Table → Host Details → Packet History"),
-        Line::from("  ↑/k        - Move selection up (host table) or scroll (details/packets)"),
-        Line::from("  ↓/j        - Move selection down (host table) or scroll (details/packets)"),
-        Line::from("  PgUp/PgDn  - Page up/down (10 items or 1 page scroll)"),
-        Line::from("  Home/End   - Jump to top/bottom"),
-        Line::from("  Enter      - Show packet details (when packet history active)"),
-        Line::from("  q          - Close packet details modal (when modal open)"),
-        Line::from("  ↑↓/k/j     - Scroll modal content (when modal open)"),
-        Line::from("  PgUp/PgDn/Space - Page scroll modal content (when modal open)"),
-        Line::from("  Home/End   - Jump to top/bottom of modal (when modal open)"),
-        Line::from(""),
-    ];
-
-    // Add mouse support section only if mouse is enabled
-    if app.mouse_enabled {
-        help_text.extend_from_slice(&[
-            Line::from(vec![Span::styled(
-                "Mouse Support:",
-                Style::default()
-                    .fg(theme.table_header)
-                    .add_modifier(Modifier::BOLD),
-            )]),
-            Line::from("  Click      - Switch to view and select row (host table/packet history)"),
-            Line::from("  Double-click - Open packet details modal (packet history rows)"),
-            Line::from("  Click outside modal - Close packet details modal"),
-            Line::from("  Scroll wheel - Navigate selections/scroll content"),
-            Line::from("  Note: Use 'q' key to close modals/help or click outside modals"),
-            Line::from("  Note: Use --no-mouse flag to disable mouse support"),
-            Line::from(""),
-        ]);
+                .add_modifier(Modifier::ITALIC),
+        )
+    };
+    let value = |text: String| Span::styled(text, Style::default().fg(theme.text_primary));
+
+    let mut lines = vec![Line::from(vec![
+        label("PTT:"),
+        value(drill.old_ptt.to_string()),
+        match drill.loss {
+            Some(loss) => value(format!(" {}", loss.description())),
+            None => pending(" waiting for it to go away"),
+        },
+    ])];
+
+    lines.push(Line::from(vec![
+        label("New PTT:"),
+        match (drill.new_ptt, drill.election_time()) {
+            (Some((new_ptt, _)), Some(election_time)) => value(format!(
+                "{} after {}",
+                new_ptt,
+                format_drill_duration(election_time)
+            )),
+            _ => pending("-"),
+        },
+    ]));
+
+    lines.push(Line::from(label("Receivers:")));
+    for (receiver, switch) in &drill.receivers {
+        let status = match (switch, drill.switch_time(receiver)) {
+            (Some((transmitter, _)), Some(switch_time)) => {
+                let mut status = format!(
+                    "→ {} after {}",
+                    transmitter,
+                    format_drill_duration(switch_time)
+                );
+                if drill
+                    .new_ptt
+                    .is_some_and(|(new_ptt, _)| new_ptt != *transmitter)
+                {
+                    status.push_str(" (not the new PTT)");
+                }
+                value(status)
+            }
+            _ => pending("not switched"),
+        };
+        lines.push(Line::from(vec![
+            Span::raw("  "),
+            value(format!("{}  ", receiver)),
+            status,
+        ]));
     }
 
-    help_text.extend_from_slice(&[
-        Line::from(vec![Span::styled(
-            "Actions:",
-            Style::default()
-                .fg(theme.table_header)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  r          - Refresh/rescan network"),
-        Line::from("  Ctrl+L     - Refresh/redraw screen"),
-        Line::from("  c          - Clear all hosts and packet histories"),
-        Line::from("  x          - Clear packet history for selected host"),
-        Line::from("  p          - Toggle pause mode"),
-        Line::from("  w          - Toggle packet auto-scroll"),
-        Line::from("  s          - Cycle host table sorting"),
-        Line::from("  a          - Previous sort column"),
-        Line::from("  S          - Reverse sort direction"),
-        Line::from("  t          - Toggle tree view mode"),
-        Line::from("  e          - Toggle expanded packet history"),
-        Line::from("  d          - Toggle debug mode"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "General:",
-            Style::default()
-                .fg(theme.table_header)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  h/F1       - Show/hide this help"),
-        Line::from("  Esc/q      - Close help"),
-        Line::from("  q          - Close modal/help or quit application"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Notes:",
-            Style::default()
-                .fg(theme.table_header)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  • Host details and packet history are scrollable"),
-        Line::from("  • Packet selection preserved when switching views"),
-        Line::from("  • Scroll positions reset when selecting different host"),
-        Line::from("  • Auto-scroll disabled when manually navigating packets"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Legend:",
-            Style::default()
-                .fg(theme.table_header)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from(vec![
-            Span::styled(
-                format!("  {}", time_transmitter_state.short_string()),
-                Style::default().fg(theme.get_state_color(&time_transmitter_state)),
-            ),
-            Span::raw(format!("  - {}", time_receiver_state)),
-        ]),
-        Line::from(vec![
-            Span::styled(
-                "  PTT",
-                Style::default().fg(theme.get_state_color(&time_transmitter_state)),
-            ),
-            Span::raw(format!(" - {} (Primary)", time_transmitter_state)),
-        ]),
-        Line::from(vec![
-            Span::styled(
-                format!("  {}", time_receiver_state.short_string()),
-                Style::default().fg(theme.get_state_color(&time_receiver_state)),
-            ),
-            Span::raw(format!("  - {}", time_receiver_state)),
-        ]),
-        Line::from(vec![
-            Span::styled(
-                format!("  {}", listening_state.short_string()),
-                Style::default().fg(theme.get_state_color(&listening_state)),
-            ),
-            Span::raw(format!("  - {}", listening_state)),
-        ]),
-        Line::from(vec![
-            Span::styled("  *", Style::default().fg(theme.text_primary)),
-            Span::raw("  - Local machine (your own host)"),
-        ]),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Terminology:",
-            Style::default()
-                .fg(theme.table_header)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  This project uses inclusive terminology:"),
-        Line::from("  • Time Transmitter = Master Clock"),
-        Line::from("  • Time Receiver = Slave Clock"),
-        Line::from("  • Primary Time Transmitter (PTT) = Grandmaster Clock"),
-    ]);
-
-    let help_paragraph = Paragraph::new(help_text)
-        .style(Style::default().fg(theme.text_primary).bg(theme.background))
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Help")
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(theme.border_normal)),
-        )
-        .alignment(Alignment::Left)
-        .wrap(Wrap { trim: true });
+    let block = Block::default()
+        .title(format!(
+            "Failover Drill - domain {}, 'f' to end",
+            format_ptp_instance(drill.instance.0, drill.instance.1)
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_focused))
+        .style(Style::default().bg(theme.background));
 
-    f.render_widget(help_paragraph, area);
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .style(Style::default().bg(theme.background));
+    f.render_widget(paragraph, area);
 }
 
 fn render_scrollbar(
@@ -1211,12 +5285,25 @@ fn render_scrollbar(
     }
 }
 
-fn format_system_time_ago(
-    system_time: std::time::SystemTime,
-    reference_time: Option<std::time::SystemTime>,
-) -> String {
-    let reference = reference_time.unwrap_or_else(std::time::SystemTime::now);
-    let elapsed = reference.duration_since(system_time).unwrap_or_default();
+fn format_local_time(time: SystemTime) -> String {
+    chrono::DateTime::<chrono::Local>::from(time)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+/// Duration a host has been observed, e.g. "45s", "12m05s" or "3h12m"
+fn format_observed_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m{:02}s", secs / 60, secs % 60),
+        3600..86400 => format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60),
+        _ => format!("{}d{:02}h", secs / 86400, (secs % 86400) / 3600),
+    }
+}
+
+fn format_system_time_ago(system_time: std::time::SystemTime, clock: Clock) -> String {
+    let elapsed = clock.since(system_time);
 
     let elapsed_str = if elapsed.as_secs() < 1 {
         format!("{}ms", elapsed.as_millis())
@@ -1235,10 +5322,105 @@ fn format_system_time_ago(
     format!("{} ago", elapsed_str)
 }
 
+/// Interval between two consecutive packets, with microsecond resolution
+/// below one second so jitter is visible
+fn format_packet_delta(previous: std::time::SystemTime, current: std::time::SystemTime) -> String {
+    let (sign, delta) = match current.duration_since(previous) {
+        Ok(delta) => ("+", delta),
+        // Packets from different interfaces can arrive slightly out of order
+        Err(e) => ("-", e.duration()),
+    };
+
+    if delta.as_secs() < 1 {
+        format!("{}{:.3}ms", sign, delta.as_secs_f64() * 1000.0)
+    } else {
+        format!("{}{:.3}s", sign, delta.as_secs_f64())
+    }
+}
+
+/// One line mapping the message type colors of the packet history and the
+/// state abbreviations of the host table
+fn render_legend_bar(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let mut spans = vec![Span::styled(
+        " Legend: ",
+        Style::default()
+            .fg(theme.table_header)
+            .add_modifier(Modifier::BOLD),
+    )];
+    for message_type in [
+        PtpMessageType::Sync,
+        PtpMessageType::FollowUp,
+        PtpMessageType::DelayReq,
+        PtpMessageType::DelayResp,
+        PtpMessageType::PDelayReq,
+        PtpMessageType::PDelayResp,
+        PtpMessageType::PDelayRespFollowUp,
+    ] {
+        spans.push(Span::styled(
+            format!("{} ", message_type),
+            Style::default().fg(theme.get_message_type_color(&message_type)),
+        ));
+    }
+    spans.push(Span::styled(
+        "other",
+        Style::default().fg(theme.message_type_unknown),
+    ));
+    spans.push(Span::styled(
+        " │ ",
+        Style::default().fg(theme.border_normal),
+    ));
+    for (abbreviation, meaning, color) in [
+        ("PTT", "primary transmitter", theme.state_transmitter),
+        ("TT", "transmitter", theme.state_transmitter),
+        ("TR", "receiver", theme.state_receiver),
+        ("L", "listening", theme.state_listening),
+        ("P2P", "peer delay only", theme.state_listening),
+    ] {
+        spans.push(Span::styled(abbreviation, Style::default().fg(color)));
+        spans.push(Span::styled(
+            format!(" {}  ", meaning),
+            Style::default().fg(theme.text_secondary),
+        ));
+    }
+    spans.push(Span::styled(
+        "'K' to hide",
+        Style::default().fg(theme.text_secondary),
+    ));
+
+    let legend = Paragraph::new(Line::from(spans))
+        .style(Style::default().fg(theme.text_primary).bg(theme.background));
+    f.render_widget(legend, area);
+}
+
 fn render_packet_history(f: &mut Frame, area: Rect, app: &mut App) {
+    // The legend takes the top line, as long as a few packets remain visible
+    let area = if app.show_legend && area.height > 4 {
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(3)])
+            .split(area);
+        render_legend_bar(f, areas[0], app);
+        areas[1]
+    } else {
+        area
+    };
+
     let packets = app.get_packet_history();
     let total_packets = packets.len();
 
+    // The mini-map takes the bottom line, as long as a few packets remain visible
+    let (area, minimap_area) = if total_packets > 0 && area.height > 4 {
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(1)])
+            .split(area);
+        (areas[0], Some(areas[1]))
+    } else {
+        (area, None)
+    };
+    app.packet_minimap_area = minimap_area;
+
     // Calculate how many packets we can display
     let content_height = area.height.saturating_sub(3) as usize; // Subtract borders + header
     let visible_packets = if app.is_packet_history_expanded() {
@@ -1290,6 +5472,47 @@ fn render_packet_history(f: &mut Frame, area: Rect, app: &mut App) {
     } else {
         ""
     };
+    let expanded_status = match &app.packet_interface_filter {
+        Some(interface) => format!("{} [IFACE: {} - 'n' for next]", expanded_status, interface),
+        None => expanded_status.to_string(),
+    };
+    let expanded_status = match app.time_range {
+        TimeRange::Session => expanded_status,
+        range => format!(
+            "{} [{} - 'u' for next]",
+            expanded_status,
+            range.label().to_uppercase()
+        ),
+    };
+    let sync_sample_rate = app.ptp_tracker.get_sync_sample_rate();
+    let expanded_status = match app
+        .selected_host_id
+        .and_then(|id| app.ptp_tracker.get_host(&id))
+    {
+        Some(host) if sync_sample_rate > 1 => format!(
+            "{} [SYNC 1:{}, {} skipped]",
+            expanded_status, sync_sample_rate, host.sync_sampler.skipped
+        ),
+        _ => expanded_status,
+    };
+
+    // Packets arriving on several interfaces are path diversity, not jitter.
+    // Each interface gets its own color to tell the paths apart.
+    let interfaces = app.get_packet_interfaces();
+    let interface_palette = [
+        theme.text_accent,
+        theme.vendor_text,
+        theme.state_transmitter,
+        theme.state_receiver,
+        theme.confidence_medium,
+        theme.message_type_delay_req,
+    ];
+    let interface_style = |name: &str| match interfaces.iter().position(|i| i == name) {
+        Some(index) if interfaces.len() > 1 => {
+            Style::default().fg(interface_palette[index % interface_palette.len()])
+        }
+        _ => Style::default(),
+    };
 
     let title = if total_packets > 0 {
         let display_count = visible_packets.min(total_packets);
@@ -1317,8 +5540,16 @@ fn render_packet_history(f: &mut Frame, area: Rect, app: &mut App) {
         .style(Style::default().bg(theme.background));
 
     if total_packets == 0 {
-        let message = if app.selected_host_id.is_none() {
+        let history_disabled = app
+            .selected_host_id
+            .and_then(|id| app.ptp_tracker.get_host(&id))
+            .is_some_and(|host| !host.history_enabled);
+        let message = if app.ptp_tracker.is_lite() {
+            "Packets are not kept in --lite mode, only host states and counters."
+        } else if app.selected_host_id.is_none() {
             "Select a host to view its packet history."
+        } else if history_disabled {
+            "Packet history is disabled for this host. Press 'R' to enable it."
         } else {
             "No packets captured yet for this host. Packets will appear here as they arrive."
         };
@@ -1332,8 +5563,11 @@ fn render_packet_history(f: &mut Frame, area: Rect, app: &mut App) {
     }
 
     // Create table headers
-    let headers = Row::new(vec![
-        Cell::from("Time Ago"),
+    let mut header_cells = vec![
+        Cell::from(match app.config.time_display {
+            TimeDisplayMode::Relative => "Time Ago",
+            TimeDisplayMode::Absolute => "Time",
+        }),
         Cell::from("VLAN"),
         Cell::from("TTL"),
         Cell::from("Source IP"),
@@ -1348,8 +5582,13 @@ fn render_packet_history(f: &mut Frame, area: Rect, app: &mut App) {
         Cell::from("Correction"),
         Cell::from("Interval"),
         Cell::from("Details"),
-    ])
-    .style(
+    ];
+    if app.show_packet_deltas {
+        header_cells.insert(1, Cell::from("Δt"));
+    }
+    // Bookmark marker
+    header_cells.insert(0, Cell::from(""));
+    let headers = Row::new(header_cells).style(
         Style::default()
             .fg(theme.table_header)
             .add_modifier(Modifier::BOLD),
@@ -1368,13 +5607,30 @@ fn render_packet_history(f: &mut Frame, area: Rect, app: &mut App) {
 
     let selected_in_view = app.selected_packet_index.saturating_sub(scroll_offset);
 
+    // Packets in the same message exchange as the selected one are underlined
+    let exchange_packet = if app.show_packet_modal {
+        app.get_modal_packet()
+    } else if matches!(app.active_view, ActiveView::PacketHistory) {
+        packets.get(app.selected_packet_index)
+    } else {
+        None
+    };
+
     // Create table rows from visible packets
     let rows: Vec<Row> = visible_packets_slice
         .iter()
         .enumerate()
         .map(|(i, packet)| {
-            let time_str =
-                format_system_time_ago(packet.raw.timestamp, app.get_reference_timestamp());
+            let time_str = match app.config.time_display {
+                TimeDisplayMode::Relative => {
+                    format_system_time_ago(packet.raw.timestamp, app.packet_clock())
+                }
+                TimeDisplayMode::Absolute => {
+                    chrono::DateTime::<chrono::Local>::from(packet.raw.timestamp)
+                        .format("%H:%M:%S%.3f")
+                        .to_string()
+                }
+            };
             let header = packet.ptp.header();
 
             let row_style =
@@ -1385,13 +5641,28 @@ fn render_packet_history(f: &mut Frame, area: Rect, app: &mut App) {
                 } else {
                     Style::default()
                 };
+            let row_style = match exchange_packet {
+                Some(related) if related.ptp.is_exchange_partner(&packet.ptp) => {
+                    row_style.add_modifier(Modifier::UNDERLINED)
+                }
+                _ => row_style,
+            };
 
-            Row::new(vec![
+            let mut cells = vec![
                 Cell::from(time_str),
-                Cell::from(match packet.raw.vlan_id {
-                    Some(id) => id.to_string(),
-                    None => "-".to_string(),
-                }),
+                {
+                    let wrong_priority =
+                        wrong_event_priority(packet, app.ptp_tracker.get_expected_event_pcp());
+                    let cell = Cell::from(match (packet.raw.vlan_id, packet.raw.vlan_pcp) {
+                        (Some(id), Some(pcp)) => format!("{} p{}", id, pcp),
+                        _ => "-".to_string(),
+                    });
+                    if wrong_priority {
+                        cell.style(Style::default().fg(theme.confidence_low))
+                    } else {
+                        cell
+                    }
+                },
                 Cell::from(
                     packet
                         .raw
@@ -1406,41 +5677,83 @@ fn render_packet_history(f: &mut Frame, area: Rect, app: &mut App) {
                     Some(std::net::SocketAddr::V4(a)) => a.port().to_string(),
                     _ => "-".to_string(),
                 }),
-                Cell::from(packet.raw.interface_name.clone()),
-                Cell::from(header.version.to_string()),
+                Cell::from(Span::styled(
+                    packet.raw.interface_label(),
+                    interface_style(&packet.raw.interface_name),
+                )),
+                Cell::from(header.version_label()),
                 Cell::from(Span::styled(
                     header.message_type.to_string(),
                     theme.get_message_type_color(&header.message_type),
                 )),
                 Cell::from(header.message_length.to_string()),
-                Cell::from(header.domain_number.to_string()),
+                Cell::from(if header.is_cmlds() {
+                    header.domain_label()
+                } else {
+                    app.config.domain_label(header.domain_number)
+                }),
                 Cell::from(header.sequence_id.to_string()),
                 Cell::from(header.flags.short()),
                 Cell::from(header.correction_field.to_string()),
                 Cell::from(header.log_message_interval.to_string()),
                 Cell::from(packet.ptp.to_string()),
-            ])
-            .style(row_style)
+            ];
+            if app.show_packet_deltas {
+                // Delta to the previous packet in the displayed list, which
+                // may be outside the visible slice
+                let index = scroll_offset + i;
+                cells.insert(
+                    1,
+                    Cell::from(match index.checked_sub(1) {
+                        Some(previous) => format_packet_delta(
+                            packets[previous].raw.timestamp,
+                            packet.raw.timestamp,
+                        ),
+                        None => "-".to_string(),
+                    }),
+                );
+            }
+            cells.insert(
+                0,
+                if app.bookmarks.is_bookmarked(packet) {
+                    Cell::from(Span::styled("★", Style::default().fg(theme.text_accent)))
+                } else {
+                    Cell::from("")
+                },
+            );
+
+            Row::new(cells).style(row_style)
         })
         .collect();
 
-    let widths = [
-        Constraint::Length(10),  // Time Ago
-        Constraint::Length(5),   // VLAN
-        Constraint::Length(5),   // TTL
-        Constraint::Length(15),  // Source IP
-        Constraint::Length(5),   // Port
-        Constraint::Length(10),  // Interface
-        Constraint::Length(5),   // Version
-        Constraint::Length(13),  // Message Type
-        Constraint::Length(6),   // Length
-        Constraint::Length(7),   // Domain
-        Constraint::Length(5),   // Sequence
-        Constraint::Length(6),   // Flags
-        Constraint::Length(11),  // Correction
-        Constraint::Length(11),  // Log Interval
+    let mut widths = vec![
+        Constraint::Length(match app.config.time_display {
+            TimeDisplayMode::Relative => 10,
+            TimeDisplayMode::Absolute => 12,
+        }), // Time
+        Constraint::Length(7),  // VLAN and priority
+        Constraint::Length(5),  // TTL
+        Constraint::Length(15), // Source IP
+        Constraint::Length(5),  // Port
+        Constraint::Length(10), // Interface
+        Constraint::Length(5),  // Version
+        Constraint::Length(13), // Message Type
+        Constraint::Length(6),  // Length
+        Constraint::Length(if app.config.domain_aliases.is_empty() {
+            7
+        } else {
+            16
+        }), // Domain
+        Constraint::Length(5),  // Sequence
+        Constraint::Length(6),  // Flags
+        Constraint::Length(11), // Correction
+        Constraint::Length(11), // Log Interval
         Constraint::Length(100), // Details
     ];
+    if app.show_packet_deltas {
+        widths.insert(1, Constraint::Length(11)); // Δt
+    }
+    widths.insert(0, Constraint::Length(1)); // Bookmark
 
     let table = Table::new(rows, widths)
         .header(headers)
@@ -1460,23 +5773,103 @@ fn render_packet_history(f: &mut Frame, area: Rect, app: &mut App) {
             theme,
         );
     }
+
+    if let Some(minimap_area) = minimap_area {
+        render_packet_minimap(f, minimap_area, app, &packets, scroll_offset..end);
+    }
+}
+
+/// An event message tagged with another 802.1p priority than the site's
+/// (--event-pcp)
+fn wrong_event_priority(packet: &ParsedPacket, expected_pcp: Option<u8>) -> bool {
+    packet.ptp.header().message_type.is_event()
+        && packet.raw.vlan_pcp.is_some()
+        && expected_pcp.is_some_and(|expected| packet.raw.vlan_pcp != Some(expected))
+}
+
+/// The whole packet history in one row: bars by packet density colored by
+/// message type, ★ for bookmarks, ! for wrong lengths or priorities, the
+/// packets on screen highlighted
+fn render_packet_minimap(
+    f: &mut Frame,
+    area: Rect,
+    app: &App,
+    packets: &[std::sync::Arc<ParsedPacket>],
+    on_screen: std::ops::Range<usize>,
+) {
+    let theme = &app.theme;
+    let expected_pcp = app.ptp_tracker.get_expected_event_pcp();
+    let minimap = Minimap::new(
+        packets,
+        area.width as usize,
+        |packet| app.bookmarks.is_bookmarked(packet),
+        |packet| wrong_event_priority(packet, expected_pcp) || !packet.length_issues().is_empty(),
+    );
+    let on_screen = minimap.columns_of(on_screen);
+
+    let spans: Vec<Span> = minimap
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            let (symbol, color) = if column.bookmarked {
+                ('★', theme.text_accent)
+            } else if column.anomaly {
+                ('!', theme.confidence_low)
+            } else {
+                (
+                    minimap.bar(column),
+                    column
+                        .message_type
+                        .map_or(theme.text_secondary, |message_type| {
+                            theme.get_message_type_color(&message_type)
+                        }),
+                )
+            };
+            let style = if on_screen.contains(&index) {
+                Style::default().fg(color).bg(theme.selected_row_background)
+            } else {
+                Style::default().fg(color)
+            };
+            Span::styled(symbol.to_string(), style)
+        })
+        .collect();
+
+    f.render_widget(
+        Paragraph::new(Line::from(spans)).style(Style::default().bg(theme.background)),
+        area,
+    );
+}
+
+/// Area of the packet modal: 40% width with minimum 82 chars, 60% height,
+/// shrunk to what the terminal has
+pub fn packet_modal_area(area: Rect) -> Rect {
+    let preferred_width = (area.width as f32 * 0.4) as u16;
+    let width = preferred_width.max(82).min(area.width);
+    let height = ((area.height as f32 * 0.6) as u16).max(10).min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// A raw field value in the selected format. Hex is zero-padded to the size
+/// of the field and shows its bytes in wire order (big-endian).
+fn format_raw(value: i64, size: usize, format: NumberFormat) -> String {
+    let bits = (value as u64) & (u64::MAX >> (64 - size.clamp(1, 8) * 8));
+    let hex = format!("0x{:0width$x}", bits, width = size * 2);
+    match format {
+        NumberFormat::Decimal => value.to_string(),
+        NumberFormat::Hex => hex,
+        NumberFormat::Both => format!("{} ({})", value, hex),
+    }
 }
 
 fn render_packet_modal(f: &mut Frame, area: Rect, app: &mut App) {
     if let Some(packet) = app.get_modal_packet().cloned() {
-        // Calculate modal size (40% width with minimum 82 chars, 60% height)
-        let preferred_width = (area.width as f32 * 0.4) as u16;
-        let modal_width = preferred_width.max(82);
-        let modal_height = (area.height as f32 * 0.6) as u16;
-        let x = (area.width - modal_width) / 2;
-        let y = (area.height - modal_height) / 2;
-
-        let modal_area = Rect {
-            x,
-            y,
-            width: modal_width,
-            height: modal_height,
-        };
+        let modal_area = packet_modal_area(area);
 
         // Create a dimmed overlay background (don't clear, just dim)
         let overlay = Block::default().style(Style::default().bg(Color::Rgb(20, 20, 20)));
@@ -1490,8 +5883,9 @@ fn render_packet_modal(f: &mut Frame, area: Rect, app: &mut App) {
 
         // Modal title
         let title = format!(
-            "Packet Details - Seq {} ('q' or click outside to close)",
-            packet.ptp.header().sequence_id
+            "Packet Details - Seq {} ('←/→' exchange, 'x' {}, 'q' or click outside to close)",
+            packet.ptp.header().sequence_id,
+            app.modal_number_format.label()
         );
 
         // Get theme reference before mutable operations
@@ -1520,7 +5914,7 @@ fn render_packet_details(
     app: &mut App,
 ) {
     let header = packet.ptp.header();
-    let time_ago_str = format_system_time_ago(packet.raw.timestamp, app.get_reference_timestamp());
+    let time_ago_str = format_system_time_ago(packet.raw.timestamp, app.packet_clock());
 
     let duration = packet.raw.timestamp.duration_since(UNIX_EPOCH).unwrap();
 
@@ -1530,6 +5924,7 @@ fn render_packet_details(
 
     // Define the width for label alignment (same as host details)
     const LABEL_WIDTH: usize = 30;
+    let number_format = app.modal_number_format;
 
     // Build all content lines (no truncation)
     let mut all_lines = vec![
@@ -1603,7 +5998,23 @@ fn render_packet_details(
         ),
         create_aligned_field(
             "Interface:".to_string(),
-            packet.raw.interface_name.clone(),
+            packet.raw.interface_label(),
+            LABEL_WIDTH,
+            theme,
+        ),
+        create_aligned_field(
+            "Encapsulation:".to_string(),
+            if packet.raw.encapsulation.is_empty() {
+                "-".to_string()
+            } else {
+                packet
+                    .raw
+                    .encapsulation
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" > ")
+            },
             LABEL_WIDTH,
             theme,
         ),
@@ -1616,6 +6027,15 @@ fn render_packet_details(
             LABEL_WIDTH,
             theme,
         ),
+        create_aligned_field(
+            "VLAN Priority:".to_string(),
+            packet
+                .raw
+                .vlan_pcp
+                .map_or("-".to_string(), |pcp| format!("{} (802.1p PCP)", pcp)),
+            LABEL_WIDTH,
+            theme,
+        ),
         Line::from(""),
         Line::from(vec![Span::styled(
             "PTP Header:",
@@ -1625,7 +6045,7 @@ fn render_packet_details(
         )]),
         create_aligned_field(
             "Version:".to_string(),
-            header.version.to_string(),
+            header.version_label(),
             LABEL_WIDTH,
             theme,
         ),
@@ -1641,19 +6061,60 @@ fn render_packet_details(
         ]),
         create_aligned_field(
             "Message Length:".to_string(),
-            format!("{} bytes", header.message_length),
+            format!(
+                "{} bytes",
+                format_raw(header.message_length as i64, 2, number_format)
+            ),
+            LABEL_WIDTH,
+            theme,
+        ),
+        create_aligned_field(
+            "Length Check:".to_string(),
+            {
+                let issues: Vec<String> = packet
+                    .truncation()
+                    .map(|truncation| truncation.to_string())
+                    .into_iter()
+                    .chain(packet.length_issues().iter().map(|issue| issue.to_string()))
+                    .map(|issue| format!("⚠ {}", issue))
+                    .collect();
+                if issues.is_empty() {
+                    "OK".to_string()
+                } else {
+                    issues.join(", ")
+                }
+            },
             LABEL_WIDTH,
             theme,
         ),
         create_aligned_field(
             "Domain Number:".to_string(),
-            header.domain_number.to_string(),
+            match number_format {
+                NumberFormat::Decimal => app.config.domain_label(header.domain_number),
+                format => format_raw(header.domain_number as i64, 1, format),
+            },
+            LABEL_WIDTH,
+            theme,
+        ),
+        create_aligned_field(
+            "SdoId:".to_string(),
+            if header.is_cmlds() {
+                // CMLDS serves all domains, its domainNumber carries no meaning
+                format!("0x{:03x} ({})", header.sdo_id, sdo_id_name(header.sdo_id))
+            } else {
+                format!(
+                    "0x{:03x} ({}, instance {})",
+                    header.sdo_id,
+                    sdo_id_name(header.sdo_id),
+                    format_ptp_instance(header.sdo_id, header.domain_number)
+                )
+            },
             LABEL_WIDTH,
             theme,
         ),
         create_aligned_field(
             "Sequence ID:".to_string(),
-            header.sequence_id.to_string(),
+            format_raw(header.sequence_id as i64, 2, number_format),
             LABEL_WIDTH,
             theme,
         ),
@@ -1665,16 +6126,29 @@ fn render_packet_details(
         ),
         create_aligned_field(
             "Correction Field:".to_string(),
+            // Scaled nanoseconds next to the raw value, ns × 2^16 on the wire
             format!(
-                "{} ({})",
-                header.correction_field, header.correction_field.value
+                "{}   raw {}",
+                header.correction_field,
+                format_raw(header.correction_field.value, 8, number_format)
             ),
             LABEL_WIDTH,
             theme,
         ),
         create_aligned_field(
             "Log Message Interval:".to_string(),
-            header.log_message_interval.to_string(),
+            format!(
+                "{}   raw {}",
+                match header.log_message_interval.exponent {
+                    0x7f => "unspecified".to_string(),
+                    exponent => format!("{:.3}s", 2f64.powi(exponent as i32)),
+                },
+                format_raw(
+                    header.log_message_interval.exponent as i64,
+                    1,
+                    number_format
+                )
+            ),
             LABEL_WIDTH,
             theme,
         ),
@@ -1688,7 +6162,7 @@ fn render_packet_details(
     ];
 
     // Add detailed message fields
-    let message_details = packet.ptp.details();
+    let message_details = packet.details();
     if !message_details.is_empty() {
         for (field_name, field_value) in message_details.iter() {
             all_lines.push(Line::from(vec![
@@ -1701,6 +6175,42 @@ fn render_packet_details(
         }
     }
 
+    // Unicast negotiation of Signaling messages
+    let unicast = unicast_tlvs(header, packet.raw.ptp_payload());
+    for (i, tlv) in unicast.iter().enumerate() {
+        all_lines.push(create_aligned_field(
+            if i == 0 {
+                "Unicast TLVs:".to_string()
+            } else {
+                String::new()
+            },
+            tlv.to_string(),
+            LABEL_WIDTH,
+            theme,
+        ));
+    }
+
+    // Organization extension TLVs, decoded where a decoder is registered
+    let org_tlvs = org_tlv::registry().decode(header, packet.raw.ptp_payload());
+    if !org_tlvs.is_empty() {
+        all_lines.extend(vec![
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "Organization TLVs:",
+                Style::default()
+                    .fg(theme.table_header)
+                    .add_modifier(Modifier::BOLD),
+            )]),
+        ]);
+        for tlv in &org_tlvs {
+            all_lines.push(Line::from(vec![Span::styled(
+                tlv.title(),
+                Style::default().fg(theme.text_accent),
+            )]));
+            all_lines.extend(org_tlv_lines(tlv, LABEL_WIDTH, theme));
+        }
+    }
+
     // Add flag details section
     all_lines.extend(vec![
         Line::from(""),
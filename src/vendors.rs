@@ -0,0 +1,131 @@
+//! Hosts per vendor
+//!
+//! The vendor of a host comes from the OUI of its clock identity. Counting the
+//! hosts of each vendor per state answers questions like "how many of the new
+//! brand-X boxes are actually announcing" without scrolling the host table.
+
+use std::collections::BTreeMap;
+
+use crate::ptp::{PtpHost, PtpHostState};
+
+/// Label of hosts whose OUI is not in the vendor table
+pub const UNKNOWN_VENDOR: &str = "Unknown";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VendorCount {
+    pub vendor: String,
+    pub transmitters: usize,
+    pub receivers: usize,
+    pub listening: usize,
+}
+
+impl VendorCount {
+    pub fn hosts(&self) -> usize {
+        self.transmitters + self.receivers + self.listening
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VendorSort {
+    #[default]
+    Hosts,
+    Vendor,
+    Transmitters,
+    Receivers,
+    Listening,
+}
+
+impl VendorSort {
+    pub fn next(&self) -> Self {
+        match self {
+            VendorSort::Hosts => VendorSort::Vendor,
+            VendorSort::Vendor => VendorSort::Transmitters,
+            VendorSort::Transmitters => VendorSort::Receivers,
+            VendorSort::Receivers => VendorSort::Listening,
+            VendorSort::Listening => VendorSort::Hosts,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            VendorSort::Hosts => "Hosts",
+            VendorSort::Vendor => "Vendor",
+            VendorSort::Transmitters => "Transmitters",
+            VendorSort::Receivers => "Receivers",
+            VendorSort::Listening => "Listening",
+        }
+    }
+}
+
+/// Vendor of a host as listed in the breakdown
+pub fn vendor_label(host: &PtpHost) -> &'static str {
+    host.get_vendor_name().unwrap_or(UNKNOWN_VENDOR)
+}
+
+/// Host counts per vendor, counts descending and names ascending, except
+/// when sorted by name
+pub fn vendor_breakdown<'a, I>(hosts: I, sort: VendorSort) -> Vec<VendorCount>
+where
+    I: IntoIterator<Item = &'a PtpHost>,
+{
+    let mut counts: BTreeMap<&str, VendorCount> = BTreeMap::new();
+    for host in hosts {
+        let vendor = vendor_label(host);
+        let count = counts.entry(vendor).or_insert_with(|| VendorCount {
+            vendor: vendor.to_string(),
+            ..Default::default()
+        });
+        match host.state {
+            PtpHostState::TimeTransmitter(_) => count.transmitters += 1,
+            PtpHostState::TimeReceiver(_) => count.receivers += 1,
+            PtpHostState::Listening => count.listening += 1,
+        }
+    }
+
+    let mut counts: Vec<VendorCount> = counts.into_values().collect();
+    let key = |count: &VendorCount| match sort {
+        VendorSort::Hosts | VendorSort::Vendor => count.hosts(),
+        VendorSort::Transmitters => count.transmitters,
+        VendorSort::Receivers => count.receivers,
+        VendorSort::Listening => count.listening,
+    };
+    if sort != VendorSort::Vendor {
+        // Stable, so equal counts stay in name order
+        counts.sort_by_key(|count| std::cmp::Reverse(key(count)));
+    }
+    counts
+}
+
+#[test]
+fn test_vendor_breakdown() {
+    use crate::types::ClockIdentity;
+
+    let host = |oui: [u8; 3], last: u8, transmitter: bool| {
+        let mut host = PtpHost::new(
+            ClockIdentity::try_from(&[oui[0], oui[1], oui[2], 0xff, 0xfe, 0, 0, last][..]).unwrap(),
+        );
+        if transmitter {
+            host.state = PtpHostState::TimeTransmitter(Default::default());
+        }
+        host
+    };
+    // Meinberg (ec:46:70) and an OUI nobody registered
+    let hosts = [
+        host([0xec, 0x46, 0x70], 1, true),
+        host([0xec, 0x46, 0x70], 2, false),
+        host([0x02, 0x00, 0x00], 3, false),
+        host([0x02, 0x00, 0x00], 4, false),
+        host([0x02, 0x00, 0x00], 5, true),
+    ];
+
+    let breakdown = vendor_breakdown(&hosts, VendorSort::Hosts);
+    assert_eq!(breakdown[0].vendor, UNKNOWN_VENDOR);
+    assert_eq!(breakdown[0].hosts(), 3);
+    assert_eq!(breakdown[0].transmitters, 1);
+    assert_eq!(breakdown[0].listening, 2);
+
+    let breakdown = vendor_breakdown(&hosts, VendorSort::Vendor);
+    assert_eq!(breakdown[0].vendor, vendor_label(&hosts[0]));
+    assert_ne!(breakdown[0].vendor, UNKNOWN_VENDOR);
+    assert_eq!(VendorSort::Listening.next(), VendorSort::Hosts);
+}
@@ -0,0 +1,311 @@
+//! BMCA what-if simulation
+//!
+//! A snapshot of the announcing transmitters in which priority1, clockClass
+//! and priority2 can be changed to see which transmitter would win the
+//! election, e.g. when planning a GM priority scheme. The snapshot is taken
+//! when the panel opens and never feeds back into the live election.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    bmca::{BmcaAttribute, BmcaCandidate, BmcaProfile},
+    ptp::PtpHostStateTimeTransmitter,
+    types::{ClockIdentity, PtpClockClass},
+};
+
+/// Attributes that can be changed, in column order
+pub const EDITABLE_ATTRIBUTES: [BmcaAttribute; 3] = [
+    BmcaAttribute::Priority1,
+    BmcaAttribute::ClockClass,
+    BmcaAttribute::Priority2,
+];
+
+/// clockClass of a transmitter that did not announce one yet (default, free running)
+const DEFAULT_CLOCK_CLASS: u8 = 248;
+
+/// Announced priority of a transmitter that did not announce one yet
+const DEFAULT_PRIORITY: u8 = 128;
+
+#[derive(Debug, Clone)]
+pub struct WhatIfCandidate {
+    pub instance: (u16, u8),
+    pub clock_identity: ClockIdentity,
+    /// Attributes as announced when the snapshot was taken
+    pub live: PtpHostStateTimeTransmitter,
+    /// Attributes with the hypothetical changes
+    pub state: PtpHostStateTimeTransmitter,
+    pub local_priority: u8,
+    /// Position in the live election of its instance, 0 is the PTT
+    pub live_rank: usize,
+}
+
+impl WhatIfCandidate {
+    fn candidate(&self) -> BmcaCandidate<'_> {
+        BmcaCandidate {
+            clock_identity: self.clock_identity,
+            state: &self.state,
+            local_priority: self.local_priority,
+        }
+    }
+
+    fn value(state: &PtpHostStateTimeTransmitter, attribute: BmcaAttribute) -> Option<u8> {
+        match attribute {
+            BmcaAttribute::Priority1 => state.priority1,
+            BmcaAttribute::ClockClass => state.clock_class.map(|class| class.class()),
+            BmcaAttribute::Priority2 => state.priority2,
+            _ => None,
+        }
+    }
+
+    /// Current hypothetical value of an editable attribute
+    pub fn get(&self, attribute: BmcaAttribute) -> Option<u8> {
+        Self::value(&self.state, attribute)
+    }
+
+    pub fn is_modified(&self, attribute: BmcaAttribute) -> bool {
+        Self::value(&self.state, attribute) != Self::value(&self.live, attribute)
+    }
+
+    fn adjust(&mut self, attribute: BmcaAttribute, delta: i16) {
+        let change = |value: Option<u8>, default: u8| {
+            Some((value.unwrap_or(default) as i16 + delta).clamp(0, u8::MAX as i16) as u8)
+        };
+        match attribute {
+            BmcaAttribute::Priority1 => {
+                self.state.priority1 = change(self.state.priority1, DEFAULT_PRIORITY)
+            }
+            BmcaAttribute::ClockClass => {
+                self.state.clock_class = change(
+                    self.state.clock_class.map(|class| class.class()),
+                    DEFAULT_CLOCK_CLASS,
+                )
+                .map(PtpClockClass::new)
+            }
+            BmcaAttribute::Priority2 => {
+                self.state.priority2 = change(self.state.priority2, DEFAULT_PRIORITY)
+            }
+            _ => {}
+        }
+    }
+}
+
+pub struct WhatIf {
+    pub profile: BmcaProfile,
+    /// Grouped by instance, in hypothetical election order
+    pub candidates: Vec<WhatIfCandidate>,
+    pub selected: usize,
+    /// Index into EDITABLE_ATTRIBUTES
+    pub field: usize,
+}
+
+impl WhatIf {
+    /// Snapshot of the live candidates, each instance sorted best first
+    pub fn new(
+        candidates: &BTreeMap<(u16, u8), Vec<BmcaCandidate<'_>>>,
+        profile: BmcaProfile,
+    ) -> Self {
+        let candidates = candidates
+            .iter()
+            .flat_map(|(instance, transmitters)| {
+                transmitters
+                    .iter()
+                    .enumerate()
+                    .map(|(live_rank, candidate)| WhatIfCandidate {
+                        instance: *instance,
+                        clock_identity: candidate.clock_identity,
+                        live: candidate.state.clone(),
+                        state: candidate.state.clone(),
+                        local_priority: candidate.local_priority,
+                        live_rank,
+                    })
+            })
+            .collect();
+        Self {
+            profile,
+            candidates,
+            selected: 0,
+            field: 0,
+        }
+    }
+
+    pub fn attribute(&self) -> BmcaAttribute {
+        EDITABLE_ATTRIBUTES[self.field]
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.candidates.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_field(&mut self, forward: bool) {
+        let count = EDITABLE_ATTRIBUTES.len();
+        self.field = if forward {
+            (self.field + 1) % count
+        } else {
+            (self.field + count - 1) % count
+        };
+    }
+
+    /// Change the selected value and re-run the election
+    pub fn adjust(&mut self, delta: i16) {
+        let attribute = self.attribute();
+        if let Some(candidate) = self.candidates.get_mut(self.selected) {
+            candidate.adjust(attribute, delta);
+            self.elect();
+        }
+    }
+
+    /// Back to the announced values of the selected transmitter, or of all
+    pub fn reset(&mut self, all: bool) {
+        for (index, candidate) in self.candidates.iter_mut().enumerate() {
+            if all || index == self.selected {
+                candidate.state = candidate.live.clone();
+            }
+        }
+        self.elect();
+    }
+
+    /// Sort each instance by the hypothetical attributes, keeping the
+    /// selected transmitter selected
+    fn elect(&mut self) {
+        let selected = self
+            .candidates
+            .get(self.selected)
+            .map(|candidate| (candidate.instance, candidate.clock_identity));
+        let profile = self.profile;
+        self.candidates.sort_by(|a, b| {
+            a.instance.cmp(&b.instance).then_with(|| {
+                profile
+                    .bmca_for(a.instance.0)
+                    .compare(&a.candidate(), &b.candidate())
+            })
+        });
+        if let Some(selected) = selected {
+            self.selected = self
+                .candidates
+                .iter()
+                .position(|candidate| (candidate.instance, candidate.clock_identity) == selected)
+                .unwrap_or(0);
+        }
+    }
+
+    /// Position of a candidate in the hypothetical election of its instance
+    pub fn rank(&self, index: usize) -> usize {
+        let instance = self.candidates[index].instance;
+        self.candidates[..index]
+            .iter()
+            .filter(|candidate| candidate.instance == instance)
+            .count()
+    }
+
+    /// Attribute on which a candidate loses against the one ranked above it
+    pub fn loses_on(&self, index: usize) -> Option<BmcaAttribute> {
+        let candidate = &self.candidates[index];
+        let previous = self.candidates[..index].last()?;
+        if previous.instance != candidate.instance {
+            return None;
+        }
+        self.profile
+            .bmca_for(candidate.instance.0)
+            .deciding_attribute(&previous.candidate(), &candidate.candidate())
+    }
+
+    /// Instances whose winner would change: (instance, live PTT, hypothetical PTT)
+    pub fn changed_winners(&self) -> Vec<((u16, u8), ClockIdentity, ClockIdentity)> {
+        let mut changes = Vec::new();
+        for (index, candidate) in self.candidates.iter().enumerate() {
+            if self.rank(index) != 0 || candidate.live_rank == 0 {
+                continue;
+            }
+            if let Some(live) = self
+                .candidates
+                .iter()
+                .find(|live| live.instance == candidate.instance && live.live_rank == 0)
+            {
+                changes.push((
+                    candidate.instance,
+                    live.clock_identity,
+                    candidate.clock_identity,
+                ));
+            }
+        }
+        changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+}
+
+#[test]
+fn test_what_if_election() {
+    use crate::bmca::DEFAULT_LOCAL_PRIORITY;
+
+    let primary = PtpHostStateTimeTransmitter {
+        priority1: Some(100),
+        clock_class: Some(PtpClockClass::new(6)),
+        priority2: Some(128),
+        ..Default::default()
+    };
+    let backup = PtpHostStateTimeTransmitter {
+        priority1: Some(110),
+        ..primary.clone()
+    };
+    let ids = [1u8, 2].map(|n| ClockIdentity {
+        clock_id: [0, 0, 0, 0, 0, 0, 0, n],
+    });
+    let mut live = BTreeMap::new();
+    live.insert(
+        (0, 0),
+        vec![
+            BmcaCandidate {
+                clock_identity: ids[0],
+                state: &primary,
+                local_priority: DEFAULT_LOCAL_PRIORITY,
+            },
+            BmcaCandidate {
+                clock_identity: ids[1],
+                state: &backup,
+                local_priority: DEFAULT_LOCAL_PRIORITY,
+            },
+        ],
+    );
+
+    let mut what_if = WhatIf::new(&live, BmcaProfile::Ieee1588);
+    assert!(what_if.changed_winners().is_empty());
+    assert_eq!(what_if.loses_on(1), Some(BmcaAttribute::Priority1));
+
+    // Lowering the backup's priority1 below the primary's makes it win
+    what_if.select_next();
+    what_if.adjust(-20);
+    assert_eq!(what_if.candidates[0].clock_identity, ids[1]);
+    assert_eq!(what_if.selected, 0);
+    assert!(what_if.candidates[0].is_modified(BmcaAttribute::Priority1));
+    assert_eq!(what_if.changed_winners(), [((0, 0), ids[0], ids[1])]);
+    // The live state is untouched
+    assert_eq!(backup.priority1, Some(110));
+
+    // A worse clockClass on the primary does not matter, priority1 comes first
+    what_if.reset(true);
+    what_if.select_previous();
+    what_if.select_field(true);
+    what_if.adjust(1);
+    assert_eq!(
+        what_if.candidates[0].get(BmcaAttribute::ClockClass),
+        Some(7)
+    );
+    assert!(what_if.changed_winners().is_empty());
+
+    what_if.reset(true);
+    assert!(
+        what_if
+            .candidates
+            .iter()
+            .all(|candidate| !candidate.is_modified(BmcaAttribute::ClockClass))
+    );
+}